@@ -0,0 +1,34 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use bit_encoding::*;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    decoder: SimpleIntDecodingProtocol,
+    num_padding_bits: u8,
+    bytes: Vec<u8>,
+}
+
+// Decoding arbitrary bytes must never panic: every read should either succeed
+// or return a DecodeError
+fuzz_target!(|input: Input| {
+    let mut source = U8SliceBitSource::new(&input.bytes, input.num_padding_bits);
+    let decoder = &input.decoder;
+    loop {
+        if decoder.read_u8(&mut source).is_err()
+            || decoder.read_i8(&mut source).is_err()
+            || decoder.read_u16(&mut source).is_err()
+            || decoder.read_i16(&mut source).is_err()
+            || decoder.read_u32(&mut source).is_err()
+            || decoder.read_i32(&mut source).is_err()
+            || decoder.read_u64(&mut source).is_err()
+            || decoder.read_i64(&mut source).is_err()
+            || decoder.read_u128(&mut source).is_err()
+            || decoder.read_i128(&mut source).is_err()
+        {
+            break;
+        }
+    }
+});
@@ -0,0 +1,227 @@
+use crate::*;
+
+/// A *ProtocolAdapter* that subtracts an offset from each value and divides the
+/// result by a scale, so that the inner protocol encodes
+/// *(value - offset) / scale*. The decoder computes
+/// *offset + scale * decoded*.
+///
+/// Writing a value fails when *value - offset* is not a multiple of the scale,
+/// or when the result doesn't fit in the integer type (for instance when an
+/// unsigned value is smaller than the offset).
+///
+/// This is usually used via *AffineEncoding*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AffineAdapter {
+    offset: i128,
+    scale: u64,
+}
+
+impl AffineAdapter {
+    /// Constructs a new *AffineAdapter* with the given *offset* and *scale*.
+    ///
+    /// Panics if *scale* is 0.
+    pub const fn new(offset: i128, scale: u64) -> Self {
+        if scale == 0 {
+            panic!("The scale must be positive");
+        }
+        Self { offset, scale }
+    }
+
+    /// Gets the offset that is subtracted from each value
+    pub fn get_offset(&self) -> i128 {
+        self.offset
+    }
+
+    /// Gets the scale by which each value (minus the offset) is divided
+    pub fn get_scale(&self) -> u64 {
+        self.scale
+    }
+
+    fn not_affine(&self, value: impl std::fmt::Display) -> WriteError {
+        format!(
+            "{} is not {} plus a multiple of {}",
+            value, self.offset, self.scale
+        )
+        .into()
+    }
+}
+
+impl ProtocolAdapter for AffineAdapter {
+    fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+        let scale = self.scale as u128;
+        if int_type.is_signed() {
+            let value = value as i128;
+            let difference = value
+                .checked_sub(self.offset)
+                .ok_or_else(|| self.not_affine(value))?;
+            if difference % scale as i128 != 0 {
+                return Err(self.not_affine(value));
+            }
+            Ok((difference / scale as i128) as u128)
+        } else {
+            let difference = match self.offset >= 0 {
+                true => value.checked_sub(self.offset as u128),
+                false => value.checked_add(self.offset.unsigned_abs()),
+            };
+            let difference = difference.ok_or_else(|| self.not_affine(value))?;
+            if difference % scale != 0 {
+                return Err(self.not_affine(value));
+            }
+            Ok(difference / scale)
+        }
+    }
+
+    fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+        let scale = self.scale as u128;
+        let result = if int_type.is_signed() {
+            (value as i128)
+                .checked_mul(scale as i128)
+                .and_then(|scaled| scaled.checked_add(self.offset))
+                .map(|result| result as u128)
+        } else {
+            value
+                .checked_mul(scale)
+                .and_then(|scaled| match self.offset >= 0 {
+                    true => scaled.checked_add(self.offset as u128),
+                    false => scaled.checked_sub(self.offset.unsigned_abs()),
+                })
+        };
+        result.ok_or(DecodeError::IntOverflow)
+    }
+}
+
+/// A protocol that normalizes each value with an *AffineAdapter* before
+/// encoding it with an inner protocol: it encodes *(value - offset) / scale*.
+/// When all values are multiples of some number (or close to some number), this
+/// makes them much smaller. The decoder must use an *AffineEncoding* with the
+/// same offset and scale.
+///
+/// Note that the offset and scale are applied to all integers, including the
+/// lengths of slices and strings, so it is usually best to use this only for
+/// the values that need it (for instance using a *ProtocolSet*).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // Sensor values are multiples of 25, starting at 4000
+/// let encoder = AffineEncoding::new(DigitIntEncodingProtocol::v1(), 4000, 25);
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_u32(&mut sink, 4075).unwrap();
+/// // 4075 is encoded as 3
+/// assert_eq!(DigitIntEncodingProtocol::v1().encoded_size_u32(3), sink.get_num_bools() as u32);
+///
+/// // 4080 is not a multiple of 25 above 4000
+/// assert!(encoder.write_u32(&mut sink, 4080).is_err());
+///
+/// let decoder = AffineEncoding::new(DigitIntDecodingProtocol::v1(), 4000, 25);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(4075, decoder.read_u32(&mut source).unwrap());
+/// ```
+pub type AffineEncoding<P> = AdaptedProtocol<AffineAdapter, P>;
+
+impl<P> AffineEncoding<P> {
+    /// Constructs a new *AffineEncoding* that encodes
+    /// *(value - offset) / scale* with *inner*.
+    ///
+    /// Panics if *scale* is 0.
+    pub fn new(inner: P, offset: i128, scale: u64) -> Self {
+        AffineAdapter::new(offset, scale).wrap(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_identity() {
+        let encoder = AffineEncoding::new(DigitIntEncodingProtocol::v1(), 0, 1);
+        let decoder = AffineEncoding::new(DigitIntDecodingProtocol::v1(), 0, 1);
+        test_encoding_pair(&encoder, &decoder);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (offset, scale) in &[(4000, 25), (-7, 3), (0, u64::MAX), (i128::MIN, 2)] {
+            let encoder = AffineEncoding::new(SimpleIntEncodingProtocol::new(), *offset, *scale);
+            let decoder = AffineEncoding::new(SimpleIntDecodingProtocol::new(), *offset, *scale);
+            let values: Vec<i128> = (-100..100)
+                .filter_map(|multiple| offset.checked_add(multiple * *scale as i128))
+                .collect();
+
+            let mut sink = BoolVecBitSink::new();
+            for value in &values {
+                encoder.write_i128(&mut sink, *value).unwrap();
+            }
+            // Not all values fit in a u64, but those that do should round trip
+            let mut unsigned_values = Vec::new();
+            for value in &values {
+                if *value >= 0 && *value <= u64::MAX as i128 {
+                    if let Ok(()) = encoder.write_u64(&mut sink, *value as u64) {
+                        unsigned_values.push(*value as u64);
+                    }
+                }
+            }
+
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            for value in &values {
+                assert_eq!(*value, decoder.read_i128(&mut source).unwrap());
+            }
+            for value in &unsigned_values {
+                assert_eq!(*value, decoder.read_u64(&mut source).unwrap());
+            }
+            assert!(source.read(&mut [false]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_sensor_values() {
+        let plain = DigitIntEncodingProtocol::v1();
+        let affine = AffineEncoding::new(plain, 4000, 25);
+        assert_eq!(plain.encoded_size_u16(0), affine.encoded_size_u16(4000));
+        assert_eq!(plain.encoded_size_u16(7), affine.encoded_size_u16(4175));
+
+        let mut plain_sink = BoolVecBitSink::new();
+        let mut affine_sink = BoolVecBitSink::new();
+        for index in 0..100 {
+            let value = 4000 + 25 * (index % 16);
+            plain.write_u16(&mut plain_sink, value).unwrap();
+            affine.write_u16(&mut affine_sink, value).unwrap();
+        }
+        assert!(2 * affine_sink.get_num_bools() < plain_sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let encoder = AffineEncoding::new(SimpleIntEncodingProtocol::new(), 10, 5);
+        let mut sink = BoolVecBitSink::new();
+        // Not a multiple
+        assert!(encoder.write_i32(&mut sink, 11).is_err());
+        // Below the offset, which doesn't fit in an unsigned integer
+        assert!(encoder.write_u32(&mut sink, 5).is_err());
+        // But it does fit in a signed integer
+        encoder.write_i32(&mut sink, 5).unwrap();
+        assert_eq!(32, sink.get_num_bools());
+
+        // The decoded value doesn't fit
+        let mut sink = BoolVecBitSink::new();
+        SimpleIntEncodingProtocol::new()
+            .write_u8(&mut sink, 100)
+            .unwrap();
+        let decoder = AffineEncoding::new(SimpleIntDecodingProtocol::new(), 10, 5);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert!(matches!(
+            decoder.read_u8(&mut source),
+            Err(DecodeError::IntOverflow)
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_scale() {
+        AffineAdapter::new(0, 0);
+    }
+}
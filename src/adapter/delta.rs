@@ -0,0 +1,209 @@
+use crate::*;
+
+use super::PreviousValues;
+
+/// A *ProtocolAdapter* that encodes the difference between each value and the
+/// previous value of the same width (so *u32* and *i32* share their previous
+/// value). The differences are zigzag encoded, so small steps in both
+/// directions get small values. The previous values start at 0.
+///
+/// This is usually used via *DeltaEncoding*.
+#[derive(Debug, Clone, Default)]
+pub struct DeltaAdapter {
+    previous: PreviousValues,
+}
+
+impl DeltaAdapter {
+    /// Constructs a new *DeltaAdapter* whose previous values are 0
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets all previous values back to 0
+    pub fn reset(&self) {
+        self.previous.reset();
+    }
+}
+
+impl ProtocolAdapter for DeltaAdapter {
+    fn get_inner_type(&self, int_type: IntType) -> IntType {
+        int_type.to_unsigned()
+    }
+
+    fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+        let previous = self.previous.get(int_type);
+        let shift = 128 - int_type.num_bits() as u32;
+        // Interpret the wrapping difference as a signed integer of the same width
+        let difference = ((value.wrapping_sub(previous) << shift) as i128) >> shift;
+        Ok(zigzag_encode(difference))
+    }
+
+    fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+        let previous = self.previous.get(int_type);
+        let difference = zigzag_decode(value) as u128;
+        Ok(int_type.cast(previous.wrapping_add(difference)))
+    }
+
+    fn update(&self, int_type: IntType, value: u128) {
+        self.previous.set(int_type, value);
+    }
+}
+
+/// The state consists of the previous value of each integer width
+impl SessionCodec for DeltaAdapter {
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        self.previous.write_state(sink)
+    }
+
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        self.previous.read_state(source)
+    }
+}
+
+/// A protocol that encodes the difference between each value and the previous
+/// value of the same width with an inner protocol, using a *DeltaAdapter*. This
+/// makes slowly increasing values (like timestamps and counters) much cheaper
+/// to encode.
+///
+/// Since the encoding of a value depends on the previous values, the values
+/// must be decoded in the same order as they were encoded, by a
+/// *DeltaEncoding* that wraps the corresponding decoding protocol. At record
+/// boundaries (or wherever decoding may start), both sides should call *reset*.
+/// Similarly, the *encoded_size* methods predict the size of the value if it
+/// would be the next value that is written.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DeltaEncoding::new(DigitIntEncodingProtocol::v1());
+/// let timestamps = [1_600_000_000u64, 1_600_000_005, 1_600_000_009, 1_600_000_020];
+/// let mut sink = BoolVecBitSink::new();
+/// for timestamp in &timestamps {
+///     encoder.write_u64(&mut sink, *timestamp).unwrap();
+/// }
+/// encoder.reset();
+/// encoder.write_u64(&mut sink, timestamps[0]).unwrap();
+///
+/// let decoder = DeltaEncoding::new(DigitIntDecodingProtocol::v1());
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// for timestamp in &timestamps {
+///     assert_eq!(*timestamp, decoder.read_u64(&mut source).unwrap());
+/// }
+/// decoder.reset();
+/// assert_eq!(timestamps[0], decoder.read_u64(&mut source).unwrap());
+/// ```
+pub type DeltaEncoding<P> = AdaptedProtocol<DeltaAdapter, P>;
+
+impl<P> DeltaEncoding<P> {
+    /// Constructs a new *DeltaEncoding* that encodes the differences with
+    /// *inner*
+    pub fn new(inner: P) -> Self {
+        DeltaAdapter::new().wrap(inner)
+    }
+
+    /// Sets all previous values back to 0, so that the next values can be
+    /// decoded without knowing the values before it. The decoder must call
+    /// *reset* at the same point.
+    pub fn reset(&self) {
+        self.get_adapter().reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        // test_encoding_pair can't be used because it also tests the encoded
+        // sizes after writing the values, which changes the previous values
+        let encoder = DeltaEncoding::new(DigitIntEncodingProtocol::v1());
+        let decoder = DeltaEncoding::new(DigitIntDecodingProtocol::v1());
+        test_skip(&encoder, &decoder);
+        test_slices(&encoder, &decoder);
+        test_bools(&encoder, &decoder);
+
+        let values: Vec<i64> = (0..1000)
+            .map(|index: i64| index.wrapping_mul(0x5DEE_CE66_D123_4567) >> (index % 64))
+            .chain(vec![i64::MIN, i64::MAX, 0, i64::MIN, -1])
+            .collect();
+        let mut sink = BoolVecBitSink::new();
+        for value in &values {
+            encoder.write_i64(&mut sink, *value).unwrap();
+            encoder.write_u16(&mut sink, *value as u16).unwrap();
+        }
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for value in &values {
+            assert_eq!(*value, decoder.read_i64(&mut source).unwrap());
+            assert_eq!(*value as u16, decoder.read_u16(&mut source).unwrap());
+        }
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_mixed_types() {
+        let encoder = DeltaEncoding::new(DigitIntEncodingProtocol::v1());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, u32::MAX).unwrap();
+        encoder.write_i32(&mut sink, -2).unwrap();
+        encoder.write_u8(&mut sink, 200).unwrap();
+        encoder.write_i128(&mut sink, i128::MIN).unwrap();
+        encoder.write_u128(&mut sink, u128::MAX).unwrap();
+        encoder.write_i8(&mut sink, 100).unwrap();
+
+        let decoder = DeltaEncoding::new(DigitIntDecodingProtocol::v1());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(u32::MAX, decoder.read_u32(&mut source).unwrap());
+        assert_eq!(-2, decoder.read_i32(&mut source).unwrap());
+        assert_eq!(200, decoder.read_u8(&mut source).unwrap());
+        assert_eq!(i128::MIN, decoder.read_i128(&mut source).unwrap());
+        assert_eq!(u128::MAX, decoder.read_u128(&mut source).unwrap());
+        assert_eq!(100, decoder.read_i8(&mut source).unwrap());
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let plain = DigitIntEncodingProtocol::v1();
+        let delta = DeltaEncoding::new(plain);
+        let mut plain_sink = BoolVecBitSink::new();
+        let mut delta_sink = BoolVecBitSink::new();
+        let mut timestamp = 1_600_000_000_000u64;
+        for index in 0..1000 {
+            timestamp += 10 + index % 7;
+            plain.write_u64(&mut plain_sink, timestamp).unwrap();
+            delta.write_u64(&mut delta_sink, timestamp).unwrap();
+        }
+        assert!(3 * delta_sink.get_num_bools() < plain_sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_reset() {
+        let encoder = DeltaEncoding::new(DigitIntEncodingProtocol::v1());
+        let mut first = BoolVecBitSink::new();
+        encoder.write_i64(&mut first, 1000).unwrap();
+        encoder.write_i64(&mut first, 1001).unwrap();
+        encoder.reset();
+        let mut second = BoolVecBitSink::new();
+        encoder.write_i64(&mut second, 1000).unwrap();
+        assert_eq!(
+            &first.get_bits()[..second.get_bits().len()],
+            second.get_bits()
+        );
+
+        // A decoder can start decoding after a reset
+        let decoder = DeltaEncoding::new(DigitIntDecodingProtocol::v1());
+        let mut source = BoolSliceBitSource::new(first.get_bits());
+        assert_eq!(1000, decoder.read_i64(&mut source).unwrap());
+        decoder.reset();
+        let mut source = BoolSliceBitSource::new(second.get_bits());
+        assert_eq!(1000, decoder.read_i64(&mut source).unwrap());
+    }
+}
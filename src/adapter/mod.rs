@@ -0,0 +1,404 @@
+use crate::*;
+
+use std::cell::Cell;
+
+mod affine;
+mod delta;
+mod xor;
+mod zigzag;
+
+pub use affine::*;
+pub use delta::*;
+pub use xor::*;
+pub use zigzag::*;
+
+/// A reversible transformation of integers that can be stacked on top of any
+/// protocol, similar to the layers of *tower*. Transforms like zigzag encoding
+/// or subtracting an offset can thus be written once as a *ProtocolAdapter*,
+/// rather than being baked into each protocol. Use *wrap* to put an adapter on
+/// top of a protocol, which gives an *AdaptedProtocol*. Since an
+/// *AdaptedProtocol* is a protocol itself, adapters can be stacked as often as
+/// needed.
+///
+/// The values are passed to the adapter as *u128*s, together with their
+/// *IntType*. Signed values are converted using *as*, so they are sign-extended
+/// (for instance, -1i8 is passed as *u128::MAX*). The values returned by the
+/// adapter must be converted in the same way (which *IntType::cast* can do),
+/// and they must fit in their integer type, or the *AdaptedProtocol* will
+/// return an error.
+///
+/// The encoder and decoder each need their own instance of the adapter: the
+/// encoder only calls *forward* and the decoder only calls *backward*. Both call
+/// *update* after every value, which allows adapters to keep state (using
+/// interior mutability, since protocols are used via shared references).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// /// Encodes all integers as their bitwise complement
+/// struct Complement;
+///
+/// impl ProtocolAdapter for Complement {
+///     fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+///         Ok(int_type.cast(!value))
+///     }
+///
+///     fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+///         Ok(int_type.cast(!value))
+///     }
+/// }
+///
+/// let encoder = Complement.wrap(SimpleIntEncodingProtocol::new());
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_u8(&mut sink, 0b0000_1111).unwrap();
+/// assert_eq!(&[false, false, false, false, true, true, true, true], sink.get_bits());
+///
+/// let decoder = Complement.wrap(SimpleIntDecodingProtocol::new());
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(0b0000_1111, decoder.read_u8(&mut source).unwrap());
+/// ```
+pub trait ProtocolAdapter {
+    /// Gets the integer type that the inner protocol should use to encode the
+    /// transformed values of type *int_type*. By default, this is *int_type*.
+    fn get_inner_type(&self, int_type: IntType) -> IntType {
+        int_type
+    }
+
+    /// Transforms *value* (of type *int_type*) into the value that the inner
+    /// protocol should encode. This must not change the state of the adapter,
+    /// since it is also used to compute encoded sizes.
+    fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError>;
+
+    /// Transforms *value*, which was decoded by the inner protocol, back into
+    /// the original value of type *int_type*. This must not change the state
+    /// of the adapter.
+    fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError>;
+
+    /// Is called after *value* (of type *int_type*) has been encoded or
+    /// decoded successfully. Stateful adapters can use this to remember the
+    /// value. The default implementation does nothing.
+    fn update(&self, _int_type: IntType, _value: u128) {}
+
+    /// Stacks this adapter on top of *inner*
+    fn wrap<P>(self, inner: P) -> AdaptedProtocol<Self, P>
+    where
+        Self: Sized,
+    {
+        AdaptedProtocol {
+            adapter: self,
+            inner,
+        }
+    }
+}
+
+/// The previous value of each integer width, for adapters that transform
+/// values based on the previous value. Integer types of the same width (like
+/// *u32* and *i32*) share their previous value. All previous values start at 0.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PreviousValues {
+    values: [Cell<u128>; 5],
+}
+
+impl PreviousValues {
+    fn get_index(int_type: IntType) -> usize {
+        int_type.num_bits().trailing_zeros() as usize - 3
+    }
+
+    pub(crate) fn get(&self, int_type: IntType) -> u128 {
+        self.values[Self::get_index(int_type)].get()
+    }
+
+    pub(crate) fn set(&self, int_type: IntType, value: u128) {
+        self.values[Self::get_index(int_type)].set(value);
+    }
+
+    pub(crate) fn reset(&self) {
+        for value in &self.values {
+            value.set(0);
+        }
+    }
+
+    /// Writes all previous values to *sink*, such that *read_state* can
+    /// restore them
+    pub(crate) fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        let protocol = DigitIntEncodingProtocol::v1();
+        for value in &self.values {
+            protocol.write_u128(sink, value.get())?;
+        }
+        Ok(())
+    }
+
+    /// Reads the previous values that were written by *write_state*. The
+    /// previous values are only changed when all of them were read successfully.
+    pub(crate) fn read_state(&self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        let protocol = DigitIntDecodingProtocol::v1();
+        let mut new_values = [0; 5];
+        for new_value in &mut new_values {
+            *new_value = protocol.read_u128(source)?;
+        }
+        for (value, new_value) in self.values.iter().zip(new_values.iter()) {
+            value.set(*new_value);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn write_int(
+    protocol: &impl IntEncodingProtocol,
+    sink: &mut impl BitSink,
+    int_type: IntType,
+    value: u128,
+) -> Result<(), WriteError> {
+    match int_type {
+        IntType::U8 => protocol.write_u8(sink, value as u8),
+        IntType::I8 => protocol.write_i8(sink, value as i8),
+        IntType::U16 => protocol.write_u16(sink, value as u16),
+        IntType::I16 => protocol.write_i16(sink, value as i16),
+        IntType::U32 => protocol.write_u32(sink, value as u32),
+        IntType::I32 => protocol.write_i32(sink, value as i32),
+        IntType::U64 => protocol.write_u64(sink, value as u64),
+        IntType::I64 => protocol.write_i64(sink, value as i64),
+        IntType::U128 => protocol.write_u128(sink, value),
+        IntType::I128 => protocol.write_i128(sink, value as i128),
+    }
+}
+
+pub(crate) fn encoded_size_int(
+    protocol: &impl IntEncodingProtocol,
+    int_type: IntType,
+    value: u128,
+) -> u32 {
+    match int_type {
+        IntType::U8 => protocol.encoded_size_u8(value as u8),
+        IntType::I8 => protocol.encoded_size_i8(value as i8),
+        IntType::U16 => protocol.encoded_size_u16(value as u16),
+        IntType::I16 => protocol.encoded_size_i16(value as i16),
+        IntType::U32 => protocol.encoded_size_u32(value as u32),
+        IntType::I32 => protocol.encoded_size_i32(value as i32),
+        IntType::U64 => protocol.encoded_size_u64(value as u64),
+        IntType::I64 => protocol.encoded_size_i64(value as i64),
+        IntType::U128 => protocol.encoded_size_u128(value),
+        IntType::I128 => protocol.encoded_size_i128(value as i128),
+    }
+}
+
+pub(crate) fn read_int(
+    protocol: &impl IntDecodingProtocol,
+    source: &mut impl BitSource,
+    int_type: IntType,
+) -> Result<u128, DecodeError> {
+    Ok(match int_type {
+        IntType::U8 => protocol.read_u8(source)? as u128,
+        IntType::I8 => protocol.read_i8(source)? as u128,
+        IntType::U16 => protocol.read_u16(source)? as u128,
+        IntType::I16 => protocol.read_i16(source)? as u128,
+        IntType::U32 => protocol.read_u32(source)? as u128,
+        IntType::I32 => protocol.read_i32(source)? as u128,
+        IntType::U64 => protocol.read_u64(source)? as u128,
+        IntType::I64 => protocol.read_i64(source)? as u128,
+        IntType::U128 => protocol.read_u128(source)?,
+        IntType::I128 => protocol.read_i128(source)? as u128,
+    })
+}
+
+/// A protocol that transforms all integers with a *ProtocolAdapter* before
+/// encoding them with an inner protocol (or after decoding them with an inner
+/// protocol). This is created by *ProtocolAdapter::wrap*.
+///
+/// It implements *IntEncodingProtocol* when the inner protocol does, and
+/// *IntDecodingProtocol* when the inner protocol does. Note that the lengths of
+/// slices and strings are integers as well, so they are transformed too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdaptedProtocol<A, P> {
+    adapter: A,
+    inner: P,
+}
+
+impl<A, P> AdaptedProtocol<A, P> {
+    /// Gets a reference to the adapter
+    pub fn get_adapter(&self) -> &A {
+        &self.adapter
+    }
+
+    /// Gets a reference to the inner protocol
+    pub fn get_inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+impl<A: ProtocolAdapter, P: IntEncodingProtocol> AdaptedProtocol<A, P> {
+    fn forward(&self, int_type: IntType, value: u128) -> Result<(IntType, u128), WriteError> {
+        let inner_type = self.adapter.get_inner_type(int_type);
+        let inner_value = self.adapter.forward(int_type, value)?;
+        if inner_type.cast(inner_value) != inner_value {
+            return Err(format!(
+                "The adapted value {} doesn't fit in {:?}",
+                inner_value, inner_type
+            )
+            .into());
+        }
+        Ok((inner_type, inner_value))
+    }
+
+    fn write_adapted(
+        &self,
+        sink: &mut impl BitSink,
+        int_type: IntType,
+        value: u128,
+    ) -> Result<(), WriteError> {
+        let (inner_type, inner_value) = self.forward(int_type, value)?;
+        write_int(&self.inner, sink, inner_type, inner_value)?;
+        self.adapter.update(int_type, value);
+        Ok(())
+    }
+
+    fn encoded_size_adapted(&self, int_type: IntType, value: u128) -> u32 {
+        // Like the default implementations, assume that valid values can be encoded
+        let (inner_type, inner_value) = self
+            .forward(int_type, value)
+            .expect("The adapter should accept the value");
+        encoded_size_int(&self.inner, inner_type, inner_value)
+    }
+}
+
+impl<A: ProtocolAdapter, P: IntDecodingProtocol> AdaptedProtocol<A, P> {
+    fn read_adapted(
+        &self,
+        source: &mut impl BitSource,
+        int_type: IntType,
+    ) -> Result<u128, DecodeError> {
+        let inner_type = self.adapter.get_inner_type(int_type);
+        let inner_value = read_int(&self.inner, source, inner_type)?;
+        let value = self.adapter.backward(int_type, inner_value)?;
+        if int_type.cast(value) != value {
+            return Err(DecodeError::IntOverflow);
+        }
+        self.adapter.update(int_type, value);
+        Ok(value)
+    }
+}
+
+macro_rules! adapted_write {
+    ($write:ident, $encoded_size:ident, $int:ty, $int_type:expr) => {
+        fn $write(&self, sink: &mut impl BitSink, value: $int) -> Result<(), WriteError> {
+            self.write_adapted(sink, $int_type, value as u128)
+        }
+
+        fn $encoded_size(&self, value: $int) -> u32 {
+            self.encoded_size_adapted($int_type, value as u128)
+        }
+    };
+}
+
+impl<A: ProtocolAdapter + SessionCodec, P> SessionCodec for AdaptedProtocol<A, P> {
+    fn reset_state(&mut self) {
+        self.adapter.reset_state();
+    }
+
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        self.adapter.write_state(sink)
+    }
+
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        self.adapter.read_state(source)
+    }
+}
+
+impl<A: ProtocolAdapter, P: IntEncodingProtocol> IntEncodingProtocol for AdaptedProtocol<A, P> {
+    adapted_write!(write_u8, encoded_size_u8, u8, IntType::U8);
+    adapted_write!(write_i8, encoded_size_i8, i8, IntType::I8);
+    adapted_write!(write_u16, encoded_size_u16, u16, IntType::U16);
+    adapted_write!(write_i16, encoded_size_i16, i16, IntType::I16);
+    adapted_write!(write_u32, encoded_size_u32, u32, IntType::U32);
+    adapted_write!(write_i32, encoded_size_i32, i32, IntType::I32);
+    adapted_write!(write_u64, encoded_size_u64, u64, IntType::U64);
+    adapted_write!(write_i64, encoded_size_i64, i64, IntType::I64);
+    adapted_write!(write_u128, encoded_size_u128, u128, IntType::U128);
+    adapted_write!(write_i128, encoded_size_i128, i128, IntType::I128);
+}
+
+macro_rules! adapted_read {
+    ($read:ident, $int:ty, $int_type:expr) => {
+        fn $read(&self, source: &mut impl BitSource) -> Result<$int, DecodeError> {
+            Ok(self.read_adapted(source, $int_type)? as $int)
+        }
+    };
+}
+
+impl<A: ProtocolAdapter, P: IntDecodingProtocol> IntDecodingProtocol for AdaptedProtocol<A, P> {
+    adapted_read!(read_u8, u8, IntType::U8);
+    adapted_read!(read_i8, i8, IntType::I8);
+    adapted_read!(read_u16, u16, IntType::U16);
+    adapted_read!(read_i16, i16, IntType::I16);
+    adapted_read!(read_u32, u32, IntType::U32);
+    adapted_read!(read_i32, i32, IntType::I32);
+    adapted_read!(read_u64, u64, IntType::U64);
+    adapted_read!(read_i64, i64, IntType::I64);
+    adapted_read!(read_u128, u128, IntType::U128);
+    adapted_read!(read_i128, i128, IntType::I128);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    struct Negate;
+
+    impl ProtocolAdapter for Negate {
+        fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+            Ok(int_type.cast(value.wrapping_neg()))
+        }
+
+        fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+            Ok(int_type.cast(value.wrapping_neg()))
+        }
+    }
+
+    #[test]
+    fn test_stacked() {
+        let encoder = Negate.wrap(Negate.wrap(DigitIntEncodingProtocol::v1()));
+        let decoder = Negate.wrap(Negate.wrap(DigitIntDecodingProtocol::v1()));
+        test_encoding_pair(&encoder, &decoder);
+        test_encoded_sizes(&encoder);
+        test_slices(&encoder, &decoder);
+
+        // Negating twice should give the original encoding
+        let protocol = DigitIntEncodingProtocol::v1();
+        for value in &[0, 1, -1, 12345, i32::MIN, i32::MAX] {
+            assert_eq!(
+                protocol.encoded_size_i32(*value),
+                encoder.encoded_size_i32(*value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_adapted_value_too_big() {
+        struct AddOne;
+
+        impl ProtocolAdapter for AddOne {
+            fn forward(&self, _int_type: IntType, value: u128) -> Result<u128, WriteError> {
+                Ok(value + 1)
+            }
+
+            fn backward(&self, _int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+                Ok(value + 1)
+            }
+        }
+
+        let encoder = AddOne.wrap(SimpleIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u8(&mut sink, 254).unwrap();
+        assert!(encoder.write_u8(&mut sink, 255).is_err());
+
+        let decoder = AddOne.wrap(SimpleIntDecodingProtocol::new());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert!(matches!(
+            decoder.read_u8(&mut source),
+            Err(DecodeError::IntOverflow)
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::*;
+
+use super::PreviousValues;
+
+/// A *ProtocolAdapter* that XORs each value with the previous value of the
+/// same width (so *u64* and *i64* share their previous value), and passes the
+/// result to the inner protocol as an unsigned integer. The previous values
+/// start at 0.
+///
+/// This is usually used via *XorEncoding*.
+#[derive(Debug, Clone, Default)]
+pub struct XorAdapter {
+    previous: PreviousValues,
+}
+
+impl XorAdapter {
+    /// Constructs a new *XorAdapter* whose previous values are 0
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets all previous values back to 0
+    pub fn reset(&self) {
+        self.previous.reset();
+    }
+}
+
+impl ProtocolAdapter for XorAdapter {
+    fn get_inner_type(&self, int_type: IntType) -> IntType {
+        int_type.to_unsigned()
+    }
+
+    fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+        let previous = self.previous.get(int_type);
+        Ok(int_type.to_unsigned().cast(value ^ previous))
+    }
+
+    fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+        let previous = self.previous.get(int_type);
+        Ok(int_type.cast(value ^ previous))
+    }
+
+    fn update(&self, int_type: IntType, value: u128) {
+        self.previous.set(int_type, value);
+    }
+}
+
+/// The state consists of the previous value of each integer width
+impl SessionCodec for XorAdapter {
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        self.previous.write_state(sink)
+    }
+
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        self.previous.read_state(source)
+    }
+}
+
+/// A protocol that XORs each value with the previous value of the same width
+/// before encoding it with an inner protocol, using an *XorAdapter*. When the
+/// values are bit patterns that change slowly (like register dumps or the bits
+/// of floating point numbers), most bits of the XOR will be 0. An inner
+/// protocol that gives short encodings to values with many leading zeros (like
+/// *DigitIntEncodingProtocol*) can exploit that.
+///
+/// Like *DeltaEncoding*, the values must be decoded in the same order as they
+/// were encoded, both sides should call *reset* at the same points, and the
+/// *encoded_size* methods predict the size of the value if it would be the
+/// next value that is written.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = XorEncoding::new(DigitIntEncodingProtocol::v1());
+/// let temperatures = [21.5f64, 21.5, 21.25, 21.75];
+/// let mut sink = BoolVecBitSink::new();
+/// for temperature in &temperatures {
+///     encoder.write_u64(&mut sink, temperature.to_bits()).unwrap();
+/// }
+///
+/// let decoder = XorEncoding::new(DigitIntDecodingProtocol::v1());
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// for temperature in &temperatures {
+///     let bits = decoder.read_u64(&mut source).unwrap();
+///     assert_eq!(*temperature, f64::from_bits(bits));
+/// }
+/// ```
+pub type XorEncoding<P> = AdaptedProtocol<XorAdapter, P>;
+
+impl<P> XorEncoding<P> {
+    /// Constructs a new *XorEncoding* that encodes the XORed values with
+    /// *inner*
+    pub fn new(inner: P) -> Self {
+        XorAdapter::new().wrap(inner)
+    }
+
+    /// Sets all previous values back to 0, so that the next values can be
+    /// decoded without knowing the values before it. The decoder must call
+    /// *reset* at the same point.
+    pub fn reset(&self) {
+        self.get_adapter().reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        let encoder = XorEncoding::new(DigitIntEncodingProtocol::v1());
+        let decoder = XorEncoding::new(DigitIntDecodingProtocol::v1());
+        test_skip(&encoder, &decoder);
+        test_slices(&encoder, &decoder);
+        test_bools(&encoder, &decoder);
+
+        let mut sink = BoolVecBitSink::new();
+        for value in -1000..1000 {
+            encoder.write_i32(&mut sink, value * 12345).unwrap();
+            encoder.write_i8(&mut sink, value as i8).unwrap();
+            encoder
+                .write_u128(
+                    &mut sink,
+                    (value as u128).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                )
+                .unwrap();
+        }
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for value in -1000..1000 {
+            assert_eq!(value * 12345, decoder.read_i32(&mut source).unwrap());
+            assert_eq!(value as i8, decoder.read_i8(&mut source).unwrap());
+            assert_eq!(
+                (value as u128).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                decoder.read_u128(&mut source).unwrap()
+            );
+        }
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_float_bits() {
+        let plain = DigitIntEncodingProtocol::v1();
+        let xor = XorEncoding::new(plain);
+        let mut plain_sink = BoolVecBitSink::new();
+        let mut xor_sink = BoolVecBitSink::new();
+        for index in 0..1000 {
+            // Slowly changing values with few significant bits
+            let value = 1000.0 + (index / 10) as f64 * 0.5;
+            plain.write_u64(&mut plain_sink, value.to_bits()).unwrap();
+            xor.write_u64(&mut xor_sink, value.to_bits()).unwrap();
+        }
+        assert!(2 * xor_sink.get_num_bools() < plain_sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_reset() {
+        let encoder = XorEncoding::new(SimpleIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u8(&mut sink, 0b1010).unwrap();
+        encoder.write_u8(&mut sink, 0b1000).unwrap();
+        encoder.reset();
+        encoder.write_u8(&mut sink, 0b1000).unwrap();
+        crate::assert_bits_eq!("01010000 01000000 00010000", sink);
+    }
+}
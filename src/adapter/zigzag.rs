@@ -0,0 +1,86 @@
+use crate::*;
+
+/// A *ProtocolAdapter* that maps signed integers to unsigned integers of the
+/// same size using zigzag encoding: 0, -1, 1, -2, 2, ... become 0, 1, 2, 3, 4,
+/// ... Unsigned integers are left unchanged.
+///
+/// This is useful for inner protocols that only give short encodings to small
+/// unsigned values, since small negative values would otherwise get mapped to
+/// huge values. The decoder must use a *ZigzagAdapter* as well.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = ZigzagAdapter.wrap(VlqIntEncodingProtocol::new());
+/// let decoder = ZigzagAdapter.wrap(VlqIntDecodingProtocol::new());
+///
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_i32(&mut sink, -3).unwrap();
+/// // -3 becomes 5, which fits in a single byte
+/// assert_eq!(8, sink.get_num_bools());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(-3, decoder.read_i32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZigzagAdapter;
+
+impl ProtocolAdapter for ZigzagAdapter {
+    fn get_inner_type(&self, int_type: IntType) -> IntType {
+        int_type.to_unsigned()
+    }
+
+    fn forward(&self, int_type: IntType, value: u128) -> Result<u128, WriteError> {
+        Ok(match int_type.is_signed() {
+            true => int_type.to_unsigned().cast(zigzag_encode(value as i128)),
+            false => value,
+        })
+    }
+
+    fn backward(&self, int_type: IntType, value: u128) -> Result<u128, DecodeError> {
+        Ok(match int_type.is_signed() {
+            true => zigzag_decode(value) as u128,
+            false => value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        let encoder = ZigzagAdapter.wrap(DigitIntEncodingProtocol::v1());
+        let decoder = ZigzagAdapter.wrap(DigitIntDecodingProtocol::v1());
+        test_encoding_pair(&encoder, &decoder);
+        test_encoded_sizes(&encoder);
+        test_slices(&encoder, &decoder);
+        test_skip(&encoder, &decoder);
+
+        let encoder = ZigzagAdapter.wrap(SimpleIntEncodingProtocol::new());
+        let decoder = ZigzagAdapter.wrap(SimpleIntDecodingProtocol::new());
+        test_encoding_pair(&encoder, &decoder);
+        test_i8_result(&encoder, &decoder, -1, "10000000");
+        test_i8_result(&encoder, &decoder, 1, "01000000");
+        test_i8_result(&encoder, &decoder, i8::MIN, "11111111");
+        test_i16_result(&encoder, &decoder, 3, "0110000000000000");
+    }
+
+    #[test]
+    fn test_same_as_zeta() {
+        // ZetaIntEncodingProtocol zigzags signed values itself
+        let zeta = ZetaIntEncodingProtocol::new(2);
+        let adapted = ZigzagAdapter.wrap(zeta);
+        for value in &[0, 1, -1, 1000, -1000, i64::MIN, i64::MAX] {
+            let mut expected = BoolVecBitSink::new();
+            zeta.write_i64(&mut expected, *value).unwrap();
+            let mut actual = BoolVecBitSink::new();
+            adapted.write_i64(&mut actual, *value).unwrap();
+            assert_eq!(expected.get_bits(), actual.get_bits());
+        }
+    }
+}
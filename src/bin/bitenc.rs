@@ -0,0 +1,540 @@
+//! *bitenc* is a small command line tool for inspecting and converting bit
+//! streams that were produced with this crate. It is only built when the *cli*
+//! feature is enabled:
+//!
+//! ```text
+//! cargo run --features cli --bin bitenc -- <command> ...
+//! ```
+//!
+//! Run it without arguments to see the supported commands.
+
+use bit_encoding::*;
+
+use std::error::Error;
+use std::io::{Read, Write};
+
+const USAGE: &str = "Usage:
+  bitenc convert <from-format> <to-format> [file]
+      Converts a bit stream from one representation to another
+  bitenc dump <format> <protocol> <int-type> [file]
+      Decodes a stream of integers and prints the bits and value of each one
+  bitenc check <format> <protocol> <int-type> [file]
+      Decodes a stream of integers and checks that re-encoding them gives
+      exactly the same bits
+
+Formats:
+  bits      text consisting of 0s and 1s (whitespace and _ are ignored)
+  hex       hexadecimal bytes (whitespace is ignored)
+  base64    standard base64 with padding
+  raw       binary bytes
+  The bits of each byte are ordered like bytes_to_bools: least significant
+  bit first. When a stream is read from bytes, the trailing zero bits of the
+  last byte are treated as padding.
+
+Protocols:
+  simple              SimpleIntEncodingProtocol
+  v1                  DigitIntEncodingProtocol::v1()
+  digit:<size>        DigitIntEncodingProtocol::new(size, false)
+  digit:<size>:short  DigitIntEncodingProtocol::new(size, true)
+
+Int types: u8, i8, u16, i16, u32, i32, u64, i64, u128, i128
+
+When no file is given (or the file is -), the input is read from stdin.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Bits,
+    Hex,
+    Base64,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Simple,
+    Digit {
+        digit_size: u8,
+        short_zero_and_one: bool,
+    },
+}
+
+/// The bits of an input stream, and whether they were read from whole bytes
+struct Stream {
+    bits: Vec<bool>,
+    from_bytes: bool,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn parse_format(name: &str) -> Result<Format, String> {
+    match name {
+        "bits" => Ok(Format::Bits),
+        "hex" => Ok(Format::Hex),
+        "base64" => Ok(Format::Base64),
+        "raw" => Ok(Format::Raw),
+        _ => Err(format!("Unknown format {}", name)),
+    }
+}
+
+fn parse_protocol(name: &str) -> Result<Protocol, String> {
+    if name == "simple" {
+        return Ok(Protocol::Simple);
+    }
+    if name == "v1" {
+        return Ok(Protocol::Digit {
+            digit_size: 3,
+            short_zero_and_one: true,
+        });
+    }
+    let parts: Vec<&str> = name.split(':').collect();
+    if parts.len() >= 2 && parts.len() <= 3 && parts[0] == "digit" {
+        let digit_size: u8 = parts[1]
+            .parse()
+            .map_err(|_| format!("Invalid digit size {}", parts[1]))?;
+        if !(2..=127).contains(&digit_size) {
+            return Err(format!(
+                "Digit size {} is not between 2 and 127",
+                digit_size
+            ));
+        }
+        let short_zero_and_one = match parts.get(2) {
+            None => false,
+            Some(&"short") => true,
+            Some(other) => return Err(format!("Unknown digit option {}", other)),
+        };
+        return Ok(Protocol::Digit {
+            digit_size,
+            short_zero_and_one,
+        });
+    }
+    Err(format!("Unknown protocol {}", name))
+}
+
+fn parse_int_type(name: &str) -> Result<IntType, String> {
+    IntType::ALL
+        .iter()
+        .copied()
+        .find(|int_type| int_type_name(*int_type) == name)
+        .ok_or_else(|| format!("Unknown int type {}", name))
+}
+
+fn int_type_name(int_type: IntType) -> &'static str {
+    match int_type {
+        IntType::U8 => "u8",
+        IntType::I8 => "i8",
+        IntType::U16 => "u16",
+        IntType::I16 => "i16",
+        IntType::U32 => "u32",
+        IntType::I32 => "i32",
+        IntType::U64 => "u64",
+        IntType::I64 => "i64",
+        IntType::U128 => "u128",
+        IntType::I128 => "i128",
+    }
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            c.to_digit(16)
+                .map(|digit| digit as u8)
+                .ok_or_else(|| format!("Invalid hex digit {:?}", c))
+        })
+        .collect::<Result<_, _>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return Err("The number of hex digits must be even".to_string());
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| pair[0] * 16 + pair[1])
+        .collect())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_base64(text: &str) -> Result<Vec<u8>, String> {
+    let text: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if !text.len().is_multiple_of(4) {
+        return Err("The length of base64 text must be a multiple of 4".to_string());
+    }
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    for (chunk_index, chunk) in text.chunks(4).enumerate() {
+        let is_last = chunk_index == text.len() / 4 - 1;
+        let num_padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if num_padding > 2 || (num_padding > 0 && !is_last) {
+            return Err("Invalid base64 padding".to_string());
+        }
+        let mut value = 0u32;
+        for c in &chunk[0..4 - num_padding] {
+            let index = BASE64_ALPHABET
+                .iter()
+                .position(|candidate| candidate == c)
+                .ok_or_else(|| format!("Invalid base64 character {:?}", *c as char))?;
+            value = (value << 6) | index as u32;
+        }
+        value <<= 6 * num_padding as u32;
+        let decoded = [(value >> 16) as u8, (value >> 8) as u8, value as u8];
+        bytes.extend_from_slice(&decoded[0..3 - num_padding]);
+    }
+    Ok(bytes)
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut value = 0u32;
+        for (index, byte) in chunk.iter().enumerate() {
+            value |= (*byte as u32) << (16 - 8 * index);
+        }
+        for index in 0..4 {
+            if index <= chunk.len() {
+                text.push(BASE64_ALPHABET[(value >> (18 - 6 * index)) as usize & 63] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+fn parse_stream(format: Format, input: &[u8]) -> Result<Stream, String> {
+    let text =
+        || std::str::from_utf8(input).map_err(|_| "The input is not valid UTF-8".to_string());
+    let bytes = match format {
+        Format::Bits => {
+            let bits = text()?
+                .chars()
+                .filter(|c| !c.is_whitespace() && *c != '_')
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(format!("Invalid bit {:?}", c)),
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(Stream {
+                bits,
+                from_bytes: false,
+            });
+        }
+        Format::Hex => parse_hex(text()?)?,
+        Format::Base64 => parse_base64(text()?)?,
+        Format::Raw => input.to_vec(),
+    };
+    Ok(Stream {
+        bits: bytes_to_bools(&bytes),
+        from_bytes: true,
+    })
+}
+
+fn format_stream(format: Format, bits: &[bool]) -> Vec<u8> {
+    match format {
+        Format::Bits => {
+            let mut text: String = bits
+                .iter()
+                .map(|bit| if *bit { '1' } else { '0' })
+                .collect();
+            text.push('\n');
+            text.into_bytes()
+        }
+        Format::Hex => format!("{}\n", to_hex(&bools_to_bytes(bits))).into_bytes(),
+        Format::Base64 => format!("{}\n", to_base64(&bools_to_bytes(bits))).into_bytes(),
+        Format::Raw => bools_to_bytes(bits),
+    }
+}
+
+/// Checks whether the remaining bits of the stream are padding bits
+fn is_padding(stream: &Stream, position: usize) -> bool {
+    let remaining = &stream.bits[position..];
+    remaining.is_empty() || (stream.from_bytes && remaining.len() < 8 && !remaining.contains(&true))
+}
+
+macro_rules! reencode {
+    ($encoder:expr, $decoder:expr, $source:expr, $sink:expr, $read:ident, $write:ident) => {{
+        let value = $decoder.$read($source)?;
+        $encoder.$write($sink, value)?;
+        value.to_string()
+    }};
+}
+
+/// Decodes the next integer of type *int_type*, encodes it again into *sink*,
+/// and returns its value as string
+fn read_and_reencode(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    source: &mut BoolSliceBitSource,
+    sink: &mut BoolVecBitSink,
+    int_type: IntType,
+) -> Result<String, Box<dyn Error>> {
+    Ok(match int_type {
+        IntType::U8 => reencode!(encoder, decoder, source, sink, read_u8, write_u8),
+        IntType::I8 => reencode!(encoder, decoder, source, sink, read_i8, write_i8),
+        IntType::U16 => reencode!(encoder, decoder, source, sink, read_u16, write_u16),
+        IntType::I16 => reencode!(encoder, decoder, source, sink, read_i16, write_i16),
+        IntType::U32 => reencode!(encoder, decoder, source, sink, read_u32, write_u32),
+        IntType::I32 => reencode!(encoder, decoder, source, sink, read_i32, write_i32),
+        IntType::U64 => reencode!(encoder, decoder, source, sink, read_u64, write_u64),
+        IntType::I64 => reencode!(encoder, decoder, source, sink, read_i64, write_i64),
+        IntType::U128 => reencode!(encoder, decoder, source, sink, read_u128, write_u128),
+        IntType::I128 => reencode!(encoder, decoder, source, sink, read_i128, write_i128),
+    })
+}
+
+/// A decoded integer of the stream
+struct Entry {
+    start: usize,
+    end: usize,
+    value: String,
+    canonical: bool,
+}
+
+/// The entries that *decode_stream* decoded, and the position and error of the
+/// invalid encoding that stopped it (if any)
+type DecodedStream = (Vec<Entry>, Option<(usize, Box<dyn Error>)>);
+
+/// Decodes all integers of the stream. When the stream contains an invalid
+/// encoding, the entries before it are returned together with the error.
+fn decode_stream(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    stream: &Stream,
+    int_type: IntType,
+) -> DecodedStream {
+    let mut entries = Vec::new();
+    let mut source = BoolSliceBitSource::new(&stream.bits);
+    loop {
+        let start = source.position() as usize;
+        if is_padding(stream, start) {
+            return (entries, None);
+        }
+        let mut sink = BoolVecBitSink::new();
+        match read_and_reencode(encoder, decoder, &mut source, &mut sink, int_type) {
+            Ok(value) => {
+                let end = source.position() as usize;
+                entries.push(Entry {
+                    start,
+                    end,
+                    value,
+                    canonical: sink.get_bits() == &stream.bits[start..end],
+                });
+            }
+            Err(error) => return (entries, Some((start, error))),
+        }
+    }
+}
+
+fn bits_to_string(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|bit| if *bit { '1' } else { '0' })
+        .collect()
+}
+
+fn run_decode(
+    check: bool,
+    stream: &Stream,
+    protocol: Protocol,
+    int_type: IntType,
+    output: &mut impl Write,
+) -> Result<bool, Box<dyn Error>> {
+    let (entries, error) = match protocol {
+        Protocol::Simple => decode_stream(
+            &SimpleIntEncodingProtocol::new(),
+            &SimpleIntDecodingProtocol::new(),
+            stream,
+            int_type,
+        ),
+        Protocol::Digit {
+            digit_size,
+            short_zero_and_one,
+        } => decode_stream(
+            &DigitIntEncodingProtocol::new(digit_size, short_zero_and_one),
+            &DigitIntDecodingProtocol::new(digit_size, short_zero_and_one),
+            stream,
+            int_type,
+        ),
+    };
+
+    let mut success = error.is_none();
+    for entry in &entries {
+        if !check {
+            writeln!(
+                output,
+                "{:>8}: {} = {}",
+                entry.start,
+                bits_to_string(&stream.bits[entry.start..entry.end]),
+                entry.value
+            )?;
+        }
+        if !entry.canonical {
+            success = false;
+            writeln!(
+                output,
+                "{:>8}: {} is not encoded canonically",
+                entry.start, entry.value
+            )?;
+        }
+    }
+    if let Some((position, error)) = error {
+        writeln!(output, "{:>8}: {}", position, error)?;
+    }
+    if check {
+        writeln!(
+            output,
+            "{} {} values, {}",
+            entries.len(),
+            int_type_name(int_type),
+            if success {
+                "round trip OK"
+            } else {
+                "round trip FAILED"
+            }
+        )?;
+    }
+    Ok(success)
+}
+
+fn read_input(path: Option<&String>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut input = Vec::new();
+    match path.map(|path| path.as_str()) {
+        None | Some("-") => {
+            std::io::stdin().read_to_end(&mut input)?;
+        }
+        Some(path) => input = std::fs::read(path)?,
+    }
+    Ok(input)
+}
+
+/// Runs the command given by *args* (without the program name), and returns
+/// whether it succeeded
+fn run(args: &[String], output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let command = args.first().map(|command| command.as_str());
+    match command {
+        Some("convert") if args.len() == 3 || args.len() == 4 => {
+            let from = parse_format(&args[1])?;
+            let to = parse_format(&args[2])?;
+            let stream = parse_stream(from, &read_input(args.get(3))?)?;
+            output.write_all(&format_stream(to, &stream.bits))?;
+            Ok(true)
+        }
+        Some("dump") | Some("check") if args.len() == 4 || args.len() == 5 => {
+            let format = parse_format(&args[1])?;
+            let protocol = parse_protocol(&args[2])?;
+            let int_type = parse_int_type(&args[3])?;
+            let stream = parse_stream(format, &read_input(args.get(4))?)?;
+            run_decode(
+                command == Some("check"),
+                &stream,
+                protocol,
+                int_type,
+                output,
+            )
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let stdout = std::io::stdout();
+    let mut output = stdout.lock();
+    match run(&args, &mut output) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for length in 0..20 {
+            let bytes: Vec<u8> = (0..length).map(|index| (index * 37 + 5) as u8).collect();
+            let text = to_base64(&bytes);
+            assert_eq!(bytes, parse_base64(&text).unwrap());
+        }
+        assert_eq!("aGVsbG8=", to_base64(b"hello"));
+        assert_eq!(b"hi".to_vec(), parse_base64("aGk=").unwrap());
+        assert!(parse_base64("aGk").is_err());
+        assert!(parse_base64("a=Gk").is_err());
+    }
+
+    #[test]
+    fn test_convert() {
+        let stream = parse_stream(Format::Bits, b"1011 0000_01\n").unwrap();
+        assert_eq!(10, stream.bits.len());
+        assert_eq!(b"0d02\n".to_vec(), format_stream(Format::Hex, &stream.bits));
+        let stream = parse_stream(Format::Hex, b"0d 02").unwrap();
+        assert_eq!(
+            b"DQI=\n".to_vec(),
+            format_stream(Format::Base64, &stream.bits)
+        );
+        assert_eq!(
+            b"1011000001000000\n".to_vec(),
+            format_stream(Format::Bits, &stream.bits)
+        );
+        assert!(parse_stream(Format::Hex, b"0d0").is_err());
+        assert!(parse_stream(Format::Bits, b"012").is_err());
+    }
+
+    #[test]
+    fn test_parse_protocol() {
+        assert_eq!(Protocol::Simple, parse_protocol("simple").unwrap());
+        assert_eq!(parse_protocol("digit:3:short"), parse_protocol("v1"));
+        assert_eq!(
+            Protocol::Digit {
+                digit_size: 5,
+                short_zero_and_one: false
+            },
+            parse_protocol("digit:5").unwrap()
+        );
+        assert!(parse_protocol("digit:1").is_err());
+        assert!(parse_protocol("digit:3:long").is_err());
+        assert_eq!(IntType::I64, parse_int_type("i64").unwrap());
+    }
+
+    #[test]
+    fn test_dump_and_check() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        for value in &[0, 5, 1000] {
+            encoder.write_i32(&mut sink, *value).unwrap();
+        }
+        let stream = Stream {
+            bits: bytes_to_bools(&bools_to_bytes(sink.get_bits())),
+            from_bytes: true,
+        };
+        let protocol = parse_protocol("v1").unwrap();
+
+        let mut output = Vec::new();
+        assert!(run_decode(false, &stream, protocol, IntType::I32, &mut output).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(3, output.lines().count());
+        assert!(output.lines().last().unwrap().ends_with("= 1000"));
+
+        let mut output = Vec::new();
+        assert!(run_decode(true, &stream, protocol, IntType::I32, &mut output).unwrap());
+        assert_eq!(
+            "3 i32 values, round trip OK\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        // The last value is truncated
+        let truncated = Stream {
+            bits: sink.get_bits()[0..sink.get_bits().len() - 1].to_vec(),
+            from_bytes: false,
+        };
+        let mut output = Vec::new();
+        assert!(!run_decode(true, &truncated, protocol, IntType::I32, &mut output).unwrap());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::*;
+
+/// Reverses the order of the bits within every byte of *bytes*. This crate
+/// packs bits starting at the least significant bit of each byte (see
+/// *U8VecBitSink*), whereas many external formats start at the most
+/// significant bit. Applying this function converts between these 2
+/// conventions (in both directions).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// sink.write(&[true, true, false, true]).unwrap();
+/// let (mut bytes, _) = sink.into_bytes();
+/// assert_eq!(vec![0b0000_1011], bytes);
+///
+/// // An MSB-first format would store the first bit in the highest bit
+/// reverse_bits_in_bytes(&mut bytes);
+/// assert_eq!(vec![0b1101_0000], bytes);
+/// ```
+pub fn reverse_bits_in_bytes(bytes: &mut [u8]) {
+    for byte in bytes {
+        *byte = byte.reverse_bits();
+    }
+}
+
+/// Reverses the order of the bits within every word of *words*. This converts
+/// words that were packed by a *WordVecBitSink* (least significant bit first)
+/// to words that start at their most significant bit, and back.
+pub fn reverse_bits_in_words<W: UnsignedWord>(words: &mut [W]) {
+    for word in words {
+        *word = word.reverse_bits();
+    }
+}
+
+/// Reverses the order of the bytes within every word of *words*, which
+/// converts between little endian and big endian words
+pub fn swap_bytes_in_words<W: UnsignedWord>(words: &mut [W]) {
+    for word in words {
+        *word = word.swap_bytes();
+    }
+}
+
+/// Reverses the order of the bytes within every group of *word_size* bytes of
+/// *bytes*. This converts a packed buffer of little endian words to big endian
+/// words (and back), without having to convert it to a slice of words first.
+///
+/// Panics if *word_size* is 0, or if the length of *bytes* is not a multiple of
+/// *word_size*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+/// swap_bytes_in_buffer(&mut bytes, 4);
+/// assert_eq!([4, 3, 2, 1, 8, 7, 6, 5], bytes);
+/// ```
+pub fn swap_bytes_in_buffer(bytes: &mut [u8], word_size: usize) {
+    assert!(word_size > 0, "The word size must be positive");
+    assert_eq!(
+        0,
+        bytes.len() % word_size,
+        "The length of the buffer must be a multiple of the word size"
+    );
+    for word in bytes.chunks_exact_mut(word_size) {
+        word.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_reverse_bits_in_bytes() {
+        let mut bytes = [0b0000_0001, 0b1100_1010, 0, 0xFF];
+        reverse_bits_in_bytes(&mut bytes);
+        assert_eq!([0b1000_0000, 0b0101_0011, 0, 0xFF], bytes);
+        reverse_bits_in_bytes(&mut bytes);
+        assert_eq!([0b0000_0001, 0b1100_1010, 0, 0xFF], bytes);
+    }
+
+    #[test]
+    fn test_words() {
+        let mut words = [0x0000_0001u32, 0x1234_5678];
+        reverse_bits_in_words(&mut words);
+        assert_eq!([0x8000_0000, 0x1E6A_2C48], words);
+        swap_bytes_in_words(&mut words);
+        assert_eq!([0x0000_0080, 0x482C_6A1E], words);
+
+        // Swapping the bytes of the words gives the same result as swapping
+        // the bytes of the little endian buffer
+        let mut words = [0x0102u16, 0x0304, 0x0506];
+        let mut bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        swap_bytes_in_words(&mut words);
+        swap_bytes_in_buffer(&mut bytes, 2);
+        let expected: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert_eq!(expected, bytes);
+    }
+
+    #[test]
+    fn test_msb_first_words() {
+        // Bits packed into u32 words starting at the most significant bit
+        // should become bits packed into bytes starting at the least
+        // significant bit
+        let bits: Vec<bool> = (0..64).map(|index| index % 5 == 0 || index == 9).collect();
+        let mut msb_words = [0u32; 2];
+        for (index, bit) in bits.iter().enumerate() {
+            if *bit {
+                msb_words[index / 32] |= 1 << (31 - index % 32);
+            }
+        }
+
+        reverse_bits_in_words(&mut msb_words);
+        let bytes: Vec<u8> = msb_words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        let mut dest = [false; 64];
+        source.read(&mut dest).unwrap();
+        assert_eq!(&bits[..], &dest[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swap_bytes_in_buffer_bad_length() {
+        swap_bytes_in_buffer(&mut [1, 2, 3], 2);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::*;
+
+/// The number of previous bits that *analyze_bits* uses as context for its
+/// context model
+const CONTEXT_BITS: u32 = 8;
+
+/// Statistics about the bits of an encoded corpus, computed by *analyze_bits*.
+/// This can be used to find out whether the encoded data still has regular
+/// structure (long runs, biased bit positions, repeating patterns) that an
+/// entropy coding stage (like *SymbolCoder* or *Lz77Codec*) could exploit.
+///
+/// The compressibility estimates are empirical entropies: they are the number
+/// of bits that an ideal entropy coder would need if it already knew the
+/// statistics of the corpus. Since real coders need to learn or transmit the
+/// statistics, the actual savings will be somewhat smaller, especially for
+/// small corpora.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitReport {
+    num_bits: u64,
+    num_ones: u64,
+
+    num_runs: u64,
+    longest_zero_run: u64,
+    longest_one_run: u64,
+    run_length_histogram: Vec<u64>,
+
+    position_bits: Vec<u64>,
+    position_ones: Vec<u64>,
+
+    context_bits: Vec<u64>,
+    context_ones: Vec<u64>,
+}
+
+/// Reads all bits from *source* (until it returns *ReadError::ReachedEnd*) and
+/// computes a *BitReport* of them. The bit position bias is computed modulo
+/// *period*, which should be the size of the records in the corpus (or 8 to
+/// find biased bits within bytes).
+///
+/// Returns an error if *source* returns any other *ReadError*.
+///
+/// Panics if *period* is 0.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // Every byte has its highest bit set, and the other bits are 'random'
+/// let bytes: Vec<u8> = (0..1000u32).map(|index| 128 | (index * 37 % 127) as u8).collect();
+/// let mut source = U8SliceBitSource::new(&bytes, 0);
+/// let report = analyze_bits(&mut source, 8).unwrap();
+///
+/// assert_eq!(8000, report.get_num_bits());
+/// assert_eq!(Some(1.0), report.get_position_bias(7));
+/// // The highest bit of each byte is wasted, so at least 1/8 can be saved
+/// assert!(report.get_compressibility() >= 0.125);
+/// ```
+pub fn analyze_bits(source: &mut impl BitSource, period: usize) -> Result<BitReport, ReadError> {
+    assert!(period > 0, "The period must be positive");
+    let mut report = BitReport {
+        num_bits: 0,
+        num_ones: 0,
+        num_runs: 0,
+        longest_zero_run: 0,
+        longest_one_run: 0,
+        run_length_histogram: Vec::new(),
+        position_bits: vec![0; period],
+        position_ones: vec![0; period],
+        context_bits: vec![0; 1 << CONTEXT_BITS],
+        context_ones: vec![0; 1 << CONTEXT_BITS],
+    };
+
+    let mut current_run = None;
+    let mut context = 0;
+    let mut buffer = [false; 1024];
+    loop {
+        let (num_read, reached_end) = match source.read(&mut buffer) {
+            Ok(()) => (buffer.len(), false),
+            Err(ReadError::ReachedEnd { read_bools }) => (read_bools, true),
+            Err(error) => return Err(error),
+        };
+
+        for bit in &buffer[..num_read] {
+            let position = (report.num_bits % period as u64) as usize;
+            report.position_bits[position] += 1;
+            report.context_bits[context] += 1;
+            if *bit {
+                report.num_ones += 1;
+                report.position_ones[position] += 1;
+                report.context_ones[context] += 1;
+            }
+            report.num_bits += 1;
+            context = ((context << 1) | *bit as usize) & ((1 << CONTEXT_BITS) - 1);
+
+            current_run = match current_run {
+                Some((value, length)) if value == *bit => Some((value, length + 1)),
+                Some((value, length)) => {
+                    report.add_run(value, length);
+                    Some((*bit, 1))
+                }
+                None => Some((*bit, 1)),
+            };
+        }
+
+        if reached_end {
+            break;
+        }
+    }
+    if let Some((value, length)) = current_run {
+        report.add_run(value, length);
+    }
+
+    Ok(report)
+}
+
+/// The entropy (in bits) of *total* bits of which *ones* are 1
+fn entropy(total: u64, ones: u64) -> f64 {
+    let mut result = 0.0;
+    for count in &[ones, total - ones] {
+        if *count > 0 {
+            let probability = *count as f64 / total as f64;
+            result -= *count as f64 * probability.log2();
+        }
+    }
+    result
+}
+
+impl BitReport {
+    fn add_run(&mut self, value: bool, length: u64) {
+        self.num_runs += 1;
+        let longest = match value {
+            true => &mut self.longest_one_run,
+            false => &mut self.longest_zero_run,
+        };
+        *longest = u64::max(*longest, length);
+
+        let bucket = (63 - length.leading_zeros()) as usize;
+        if self.run_length_histogram.len() <= bucket {
+            self.run_length_histogram.resize(bucket + 1, 0);
+        }
+        self.run_length_histogram[bucket] += 1;
+    }
+
+    /// Gets the total number of bits that were analyzed
+    pub fn get_num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Gets the number of bits that were 1 (true)
+    pub fn get_num_ones(&self) -> u64 {
+        self.num_ones
+    }
+
+    /// Gets the fraction of the bits that were 1 (true), or 0.0 if there were
+    /// no bits at all
+    pub fn get_ones_fraction(&self) -> f64 {
+        match self.num_bits {
+            0 => 0.0,
+            num_bits => self.num_ones as f64 / num_bits as f64,
+        }
+    }
+
+    /// Gets the number of runs: maximal sequences of consecutive equal bits
+    pub fn get_num_runs(&self) -> u64 {
+        self.num_runs
+    }
+
+    /// Gets the average length of the runs, or 0.0 if there were no bits. For
+    /// random bits, this is close to 2.
+    pub fn get_mean_run_length(&self) -> f64 {
+        match self.num_runs {
+            0 => 0.0,
+            num_runs => self.num_bits as f64 / num_runs as f64,
+        }
+    }
+
+    /// Gets the length of the longest run of bits that are equal to *value*
+    pub fn get_longest_run(&self, value: bool) -> u64 {
+        match value {
+            true => self.longest_one_run,
+            false => self.longest_zero_run,
+        }
+    }
+
+    /// Gets a histogram of the run lengths: the element at index *i* is the
+    /// number of runs whose length is at least *2^i* and smaller than
+    /// *2^(i + 1)*
+    pub fn get_run_length_histogram(&self) -> &[u64] {
+        &self.run_length_histogram
+    }
+
+    /// Gets the period that was passed to *analyze_bits*
+    pub fn get_period(&self) -> usize {
+        self.position_bits.len()
+    }
+
+    /// Gets the fraction of the bits at *position* (modulo the period) that
+    /// were 1. Returns *None* if there were no bits at that position.
+    ///
+    /// Panics if *position* is not smaller than the period.
+    pub fn get_position_bias(&self, position: usize) -> Option<f64> {
+        match self.position_bits[position] {
+            0 => None,
+            num_bits => Some(self.position_ones[position] as f64 / num_bits as f64),
+        }
+    }
+
+    /// Gets the estimated number of bits needed by an entropy coder that
+    /// encodes each bit independently
+    pub fn get_order0_bits(&self) -> f64 {
+        entropy(self.num_bits, self.num_ones)
+    }
+
+    /// Gets the estimated number of bits needed by an entropy coder that uses
+    /// the position of each bit (modulo the period) as context
+    pub fn get_position_model_bits(&self) -> f64 {
+        self.position_bits
+            .iter()
+            .zip(&self.position_ones)
+            .map(|(total, ones)| entropy(*total, *ones))
+            .sum()
+    }
+
+    /// Gets the estimated number of bits needed by an entropy coder that uses
+    /// the previous 8 bits as context. This model also captures runs and
+    /// short repeating patterns.
+    pub fn get_context_model_bits(&self) -> f64 {
+        self.context_bits
+            .iter()
+            .zip(&self.context_ones)
+            .map(|(total, ones)| entropy(*total, *ones))
+            .sum()
+    }
+
+    /// Gets the smallest of the estimated sizes (in bits) of the order-0,
+    /// position and context models
+    pub fn get_estimated_bits(&self) -> f64 {
+        self.get_order0_bits()
+            .min(self.get_position_model_bits())
+            .min(self.get_context_model_bits())
+    }
+
+    /// Gets the approximate fraction of the bits that an entropy coding stage
+    /// could save, based on *get_estimated_bits*. This is between 0.0 (the
+    /// bits look random, so entropy coding is pointless) and 1.0. When there
+    /// are no bits, this returns 0.0.
+    pub fn get_compressibility(&self) -> f64 {
+        match self.num_bits {
+            0 => 0.0,
+            num_bits => 1.0 - self.get_estimated_bits() / num_bits as f64,
+        }
+    }
+}
+
+impl std::fmt::Display for BitReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} bits, {:.2}% ones",
+            self.num_bits,
+            100.0 * self.get_ones_fraction()
+        )?;
+        writeln!(
+            f,
+            "{} runs, mean length {:.2}, longest 0-run {}, longest 1-run {}",
+            self.num_runs,
+            self.get_mean_run_length(),
+            self.longest_zero_run,
+            self.longest_one_run
+        )?;
+        write!(f, "position bias (period {}):", self.get_period())?;
+        for position in 0..self.get_period() {
+            match self.get_position_bias(position) {
+                Some(bias) => write!(f, " {:.2}", bias)?,
+                None => write!(f, " -")?,
+            }
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "estimated size: {:.0} bits ({:.2}% compressible)",
+            self.get_estimated_bits(),
+            100.0 * self.get_compressibility()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::faults::*;
+    use crate::*;
+
+    #[test]
+    fn test_runs() {
+        let bits = [true, true, false, true, true, true, true, false, false];
+        let report = analyze_bits(&mut BoolSliceBitSource::new(&bits), 3).unwrap();
+        assert_eq!(9, report.get_num_bits());
+        assert_eq!(6, report.get_num_ones());
+        assert_eq!(4, report.get_num_runs());
+        assert_eq!(2, report.get_longest_run(false));
+        assert_eq!(4, report.get_longest_run(true));
+        assert_eq!(2.25, report.get_mean_run_length());
+        // Runs of length 1, 2, 4 and 2
+        assert_eq!(&[1, 2, 1], report.get_run_length_histogram());
+
+        assert_eq!(3, report.get_period());
+        assert_eq!(Some(1.0), report.get_position_bias(0));
+        assert_eq!(Some(2.0 / 3.0), report.get_position_bias(1));
+        assert_eq!(Some(1.0 / 3.0), report.get_position_bias(2));
+    }
+
+    #[test]
+    fn test_empty() {
+        let report = analyze_bits(&mut BoolSliceBitSource::new(&[]), 8).unwrap();
+        assert_eq!(0, report.get_num_bits());
+        assert_eq!(0, report.get_num_runs());
+        assert_eq!(0.0, report.get_ones_fraction());
+        assert_eq!(0.0, report.get_compressibility());
+        assert_eq!(None, report.get_position_bias(5));
+        assert!(report.get_run_length_histogram().is_empty());
+    }
+
+    #[test]
+    fn test_compressibility() {
+        // Pseudo random bits are (nearly) incompressible
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let random: Vec<bool> = (0..100_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state & 1 == 1
+            })
+            .collect();
+        let report = analyze_bits(&mut BoolSliceBitSource::new(&random), 8).unwrap();
+        assert!(report.get_compressibility() < 0.01);
+
+        // A repeating pattern is captured by the context model
+        let pattern: Vec<bool> = (0..10_000).map(|index| index % 5 < 2).collect();
+        let report = analyze_bits(&mut BoolSliceBitSource::new(&pattern), 8).unwrap();
+        assert!(report.get_position_model_bits() > 0.9 * report.get_order0_bits());
+        assert!(report.get_context_model_bits() < 20.0);
+        assert!(report.get_compressibility() > 0.99);
+
+        // Constant bits are captured by every model
+        let report = analyze_bits(&mut BoolSliceBitSource::new(&[false; 100]), 1).unwrap();
+        assert_eq!(0.0, report.get_estimated_bits());
+        assert_eq!(1.0, report.get_compressibility());
+    }
+
+    #[test]
+    fn test_position_bias() {
+        // Small numbers encoded with a fixed 16-bit width
+        let encoder = SimpleIntEncodingProtocol::new();
+        let mut sink = BoolVecBitSink::new();
+        for index in 0..500u16 {
+            encoder.write_u16(&mut sink, index % 100).unwrap();
+        }
+        let report = analyze_bits(&mut BoolSliceBitSource::new(sink.get_bits()), 16).unwrap();
+        for position in 7..16 {
+            assert_eq!(Some(0.0), report.get_position_bias(position));
+        }
+        assert!(report.get_position_model_bits() < report.get_order0_bits());
+        assert!(report.get_compressibility() > 0.5);
+        assert!(report.to_string().contains("8000 bits"));
+    }
+
+    #[test]
+    fn test_truncated_source() {
+        // The analysis stops at the first ReachedEnd, and includes the bits
+        // that were read before it
+        let bits: Vec<bool> = (0..3000).map(|index| index % 3 == 0).collect();
+        let mut source = ShortReadBitSource::new(BoolSliceBitSource::new(&bits), 7);
+        let report = analyze_bits(&mut source, 3).unwrap();
+        assert_eq!(7, report.get_num_bits());
+        assert_eq!(3, report.get_num_ones());
+    }
+
+    #[test]
+    fn test_read_error() {
+        let mut source = FailAfterBitSource::new(BoolSliceBitSource::new(&[true; 100]), 50);
+        assert!(matches!(
+            analyze_bits(&mut source, 8),
+            Err(ReadError::Other(_))
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_period() {
+        let _ = analyze_bits(&mut BoolSliceBitSource::new(&[true]), 0);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::*;
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Whether *CanaryProtocol* writes and checks canaries, which is only the case
+/// in debug builds
+pub const CANARIES_ENABLED: bool = cfg!(debug_assertions);
+
+/// The number of bits of every canary
+const CANARY_BITS: usize = 8;
+
+/// Every canary is XORed with this pattern, so that runs of zeros (or ones)
+/// are unlikely to look like valid canaries
+const CANARY_PATTERN: u8 = 0b1010_0101;
+
+fn get_type_index(int_type: IntType) -> u8 {
+    IntType::ALL
+        .iter()
+        .position(|candidate| *candidate == int_type)
+        .unwrap() as u8
+}
+
+/// Wraps an *IntEncodingProtocol* and/or an *IntDecodingProtocol*, and writes a
+/// short canary after every integer in debug builds. The canary identifies the
+/// integer type that was written, as well as the (lowest 4 bits of the) index
+/// of the field. When decoding, every canary is checked, and a *CanaryMismatch*
+/// is returned (as *DecodeError::Other*) as soon as the decoder reads a field
+/// with a different type than the encoder wrote, or when it got out of sync in
+/// another way. This pinpoints the first field where the encoding and decoding
+/// code are asymmetric, instead of failing (or silently returning garbage)
+/// somewhere later in the stream.
+///
+/// In release builds, no canaries are written or checked, and this wrapper
+/// simply passes all calls to the wrapped protocol (see *CANARIES_ENABLED*).
+/// Both sides must thus be compiled in the same mode. Note that the
+/// *encoded_size* methods include the size of the canary.
+///
+/// Since the field index is counted by the wrapper, the encoder and the decoder
+/// each need their own *CanaryProtocol*, and both should call *reset* at the
+/// same points (if at all).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = CanaryProtocol::new(DigitIntEncodingProtocol::v1());
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_u32(&mut sink, 1234).unwrap();
+/// encoder.write_u32(&mut sink, 5678).unwrap();
+///
+/// // This decoder mistakenly reads the second field as u64
+/// let decoder = CanaryProtocol::new(DigitIntDecodingProtocol::v1());
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(1234, decoder.read_u32(&mut source).unwrap());
+/// let result = decoder.read_u64(&mut source);
+/// if CANARIES_ENABLED {
+///     match result {
+///         Err(DecodeError::Other(error)) => {
+///             let mismatch = error.downcast_ref::<CanaryMismatch>().unwrap();
+///             assert_eq!(1, mismatch.field_index);
+///             assert_eq!(IntType::U64, mismatch.read_type);
+///             assert_eq!(Some(IntType::U32), mismatch.written_type);
+///         }
+///         other => panic!("Expected a canary mismatch, but got {:?}", other),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CanaryProtocol<P> {
+    protocol: P,
+    num_fields: Cell<u64>,
+}
+
+impl<P> CanaryProtocol<P> {
+    /// Wraps *protocol*
+    pub fn new(protocol: P) -> Self {
+        Self {
+            protocol,
+            num_fields: Cell::new(0),
+        }
+    }
+
+    /// Gets a reference to the wrapped protocol
+    pub fn get_inner(&self) -> &P {
+        &self.protocol
+    }
+
+    /// Gets the number of fields that have been written or read
+    pub fn get_num_fields(&self) -> u64 {
+        self.num_fields.get()
+    }
+
+    /// Sets the field counter back to 0
+    pub fn reset(&self) {
+        self.num_fields.set(0);
+    }
+
+    fn next_field(&self) -> u64 {
+        let field_index = self.num_fields.get();
+        self.num_fields.set(field_index + 1);
+        field_index
+    }
+
+    fn get_canary(int_type: IntType, field_index: u64) -> u8 {
+        (get_type_index(int_type) | ((field_index as u8 & 0xF) << 4)) ^ CANARY_PATTERN
+    }
+
+    fn write_canary(&self, sink: &mut impl BitSink, int_type: IntType) -> Result<(), WriteError> {
+        let field_index = self.next_field();
+        if CANARIES_ENABLED {
+            let canary = Self::get_canary(int_type, field_index);
+            let mut bits = [false; CANARY_BITS];
+            for (index, bit) in bits.iter_mut().enumerate() {
+                *bit = canary & (1 << index) != 0;
+            }
+            sink.write(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn read_canary(
+        &self,
+        source: &mut impl BitSource,
+        int_type: IntType,
+    ) -> Result<(), DecodeError> {
+        let field_index = self.next_field();
+        if CANARIES_ENABLED {
+            let mut bits = [false; CANARY_BITS];
+            source.read(&mut bits)?;
+            let mut canary = 0;
+            for (index, bit) in bits.iter().enumerate() {
+                if *bit {
+                    canary |= 1 << index;
+                }
+            }
+
+            if canary != Self::get_canary(int_type, field_index) {
+                let tag = canary ^ CANARY_PATTERN;
+                return Err(DecodeError::Other(Box::new(CanaryMismatch {
+                    field_index,
+                    read_type: int_type,
+                    written_type: IntType::ALL.get((tag & 0xF) as usize).copied(),
+                    written_field_index: tag >> 4,
+                })));
+            }
+        }
+        Ok(())
+    }
+
+    fn canary_size(&self) -> u32 {
+        if CANARIES_ENABLED {
+            CANARY_BITS as u32
+        } else {
+            0
+        }
+    }
+}
+
+/// The error that *CanaryProtocol* returns (as *DecodeError::Other*) when the
+/// canary after a field doesn't match the field that was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanaryMismatch {
+    /// The index of the field after which the wrong canary was found, counted
+    /// by the decoder
+    pub field_index: u64,
+
+    /// The integer type that the decoder read
+    pub read_type: IntType,
+
+    /// The integer type that the canary claims was written, or *None* if the
+    /// canary is not valid at all (which means that the decoder is at a
+    /// different position in the stream than the encoder was)
+    pub written_type: Option<IntType>,
+
+    /// The lowest 4 bits of the field index that the canary claims
+    pub written_field_index: u8,
+}
+
+impl Display for CanaryMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self.written_type {
+            Some(written_type) => write!(
+                f,
+                "Field {} was read as {:?}, but the canary says that field {} (mod 16) was written as {:?}",
+                self.field_index, self.read_type, self.written_field_index, written_type
+            ),
+            None => write!(
+                f,
+                "Field {} was read as {:?}, but it isn't followed by a valid canary",
+                self.field_index, self.read_type
+            ),
+        }
+    }
+}
+
+impl Error for CanaryMismatch {}
+
+macro_rules! canary_write {
+    ($write:ident, $encoded_size:ident, $int:ty, $int_type:expr) => {
+        fn $write(&self, sink: &mut impl BitSink, value: $int) -> Result<(), WriteError> {
+            self.protocol.$write(sink, value)?;
+            self.write_canary(sink, $int_type)
+        }
+
+        fn $encoded_size(&self, value: $int) -> u32 {
+            self.protocol.$encoded_size(value) + self.canary_size()
+        }
+    };
+}
+
+impl<P: IntEncodingProtocol> IntEncodingProtocol for CanaryProtocol<P> {
+    canary_write!(write_u8, encoded_size_u8, u8, IntType::U8);
+    canary_write!(write_i8, encoded_size_i8, i8, IntType::I8);
+    canary_write!(write_u16, encoded_size_u16, u16, IntType::U16);
+    canary_write!(write_i16, encoded_size_i16, i16, IntType::I16);
+    canary_write!(write_u32, encoded_size_u32, u32, IntType::U32);
+    canary_write!(write_i32, encoded_size_i32, i32, IntType::I32);
+    canary_write!(write_u64, encoded_size_u64, u64, IntType::U64);
+    canary_write!(write_i64, encoded_size_i64, i64, IntType::I64);
+    canary_write!(write_u128, encoded_size_u128, u128, IntType::U128);
+    canary_write!(write_i128, encoded_size_i128, i128, IntType::I128);
+}
+
+macro_rules! canary_read {
+    ($read:ident, $int:ty, $int_type:expr) => {
+        fn $read(&self, source: &mut impl BitSource) -> Result<$int, DecodeError> {
+            let value = self.protocol.$read(source)?;
+            self.read_canary(source, $int_type)?;
+            Ok(value)
+        }
+    };
+}
+
+impl<P: IntDecodingProtocol> IntDecodingProtocol for CanaryProtocol<P> {
+    canary_read!(read_u8, u8, IntType::U8);
+    canary_read!(read_i8, i8, IntType::I8);
+    canary_read!(read_u16, u16, IntType::U16);
+    canary_read!(read_i16, i16, IntType::I16);
+    canary_read!(read_u32, u32, IntType::U32);
+    canary_read!(read_i32, i32, IntType::I32);
+    canary_read!(read_u64, u64, IntType::U64);
+    canary_read!(read_i64, i64, IntType::I64);
+    canary_read!(read_u128, u128, IntType::U128);
+    canary_read!(read_i128, i128, IntType::I128);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn get_mismatch(result: Result<impl std::fmt::Debug, DecodeError>) -> CanaryMismatch {
+        match result {
+            Err(DecodeError::Other(error)) => *error.downcast_ref::<CanaryMismatch>().unwrap(),
+            other => panic!("Expected a canary mismatch, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let encoder = CanaryProtocol::new(SimpleIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u8(&mut sink, 12).unwrap();
+        encoder.write_i64(&mut sink, -12345).unwrap();
+        encoder.write_string(&mut sink, "hello").unwrap();
+        encoder.write_u128_slice(&mut sink, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            8 + encoder.canary_size() as u64,
+            encoder.encoded_size_u8(12) as u64
+        );
+
+        let decoder = CanaryProtocol::new(SimpleIntDecodingProtocol::new());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert_eq!(12, decoder.read_u8(&mut source).unwrap());
+        assert_eq!(-12345, decoder.read_i64(&mut source).unwrap());
+        assert_eq!(
+            "hello",
+            decoder.read_string(&mut source, &mut context).unwrap()
+        );
+        assert_eq!(
+            vec![1, 2, 3],
+            decoder.read_u128_vec(&mut source, &mut context).unwrap()
+        );
+        assert!(source.read(&mut [false]).is_err());
+        assert_eq!(encoder.get_num_fields(), decoder.get_num_fields());
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        if !CANARIES_ENABLED {
+            return;
+        }
+        let encoder = CanaryProtocol::new(SimpleIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u16(&mut sink, 1).unwrap();
+        encoder.write_i16(&mut sink, 2).unwrap();
+
+        // The values have the same size, but the canaries reveal the difference
+        let decoder = CanaryProtocol::new(SimpleIntDecodingProtocol::new());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(1, decoder.read_u16(&mut source).unwrap());
+        let mismatch = get_mismatch(decoder.read_u16(&mut source));
+        assert_eq!(
+            CanaryMismatch {
+                field_index: 1,
+                read_type: IntType::U16,
+                written_type: Some(IntType::I16),
+                written_field_index: 1,
+            },
+            mismatch
+        );
+    }
+
+    #[test]
+    fn test_missing_field() {
+        if !CANARIES_ENABLED {
+            return;
+        }
+        let encoder = CanaryProtocol::new(SimpleIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u8(&mut sink, 1).unwrap();
+        encoder.write_u8(&mut sink, 2).unwrap();
+        encoder.write_u8(&mut sink, 3).unwrap();
+
+        // Simulate a decoder whose field counter is 1 ahead of the encoder
+        let decoder = CanaryProtocol::new(SimpleIntDecodingProtocol::new());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        decoder.read_u8(&mut source).unwrap();
+        decoder.next_field();
+        let mismatch = get_mismatch(decoder.read_u8(&mut source));
+        assert_eq!(2, mismatch.field_index);
+        assert_eq!(1, mismatch.written_field_index);
+        assert_eq!(Some(IntType::U8), mismatch.written_type);
+    }
+
+    #[test]
+    fn test_reset() {
+        let encoder = CanaryProtocol::new(VlqIntEncodingProtocol::new());
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, 100).unwrap();
+        encoder.reset();
+        assert_eq!(0, encoder.get_num_fields());
+        encoder.write_u32(&mut sink, 200).unwrap();
+
+        let decoder = CanaryProtocol::new(VlqIntDecodingProtocol::new());
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(100, decoder.read_u32(&mut source).unwrap());
+        decoder.reset();
+        assert_eq!(200, decoder.read_u32(&mut source).unwrap());
+    }
+}
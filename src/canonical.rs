@@ -0,0 +1,184 @@
+use crate::*;
+
+/// The error that *decode_canonical* wraps in *DecodeError::Other* when the
+/// input bits decode successfully, but are not the canonical encoding of the
+/// decoded value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonCanonicalEncoding {
+    /// The index of the first bit where the input differs from the canonical
+    /// encoding. When one of them is a prefix of the other, this is the length
+    /// of the shortest one.
+    pub position: u64,
+}
+
+impl std::fmt::Display for NonCanonicalEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "The encoding is not canonical: it differs from the canonical encoding at bit {}",
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for NonCanonicalEncoding {}
+
+/// Decodes a value from *bits* using *decode*, re-encodes it using *encode*,
+/// and checks that the result is exactly *bits*. This rejects every input that
+/// is not the canonical encoding of its value, for instance digit encodings
+/// with redundant leading zero digits, or inputs with unused trailing bits.
+/// This is needed when the encoded bits are hashed or signed, since 2 different
+/// inputs that decode to the same value would otherwise have different hashes.
+///
+/// Errors of *decode* and *encode* are returned as *DecodeError* (encode errors
+/// are wrapped in *DecodeError::Other*). When the bits are not canonical, a
+/// *DecodeError::Other* with a *NonCanonicalEncoding* is returned.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::new(2, false);
+/// let decoder = DigitIntDecodingProtocol::new(2, false);
+/// let decode = |source: &mut BoolSliceBitSource| decoder.read_u32(source);
+/// let encode = |value: &u32, sink: &mut BoolVecBitSink| encoder.write_u32(sink, *value);
+///
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_u32(&mut sink, 5).unwrap();
+/// let canonical = sink.get_bits().to_vec();
+/// assert_eq!(5, decode_canonical(&canonical, decode, encode).unwrap());
+///
+/// // 5 = 2 + 1 * 3: digits 2 and 1, followed by the terminator. Inserting a
+/// // redundant 0 digit before the terminator still decodes to 5.
+/// let redundant = parse_bits("01 10 00 11");
+/// assert_eq!(5, decoder.read_u32(&mut BoolSliceBitSource::new(&redundant)).unwrap());
+/// assert!(decode_canonical(&redundant, decode, encode).is_err());
+/// # fn parse_bits(text: &str) -> Vec<bool> {
+/// #     text.chars().filter(|c| *c != ' ').map(|c| c == '1').collect()
+/// # }
+/// ```
+pub fn decode_canonical<T>(
+    bits: &[bool],
+    decode: impl FnOnce(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+    encode: impl FnOnce(&T, &mut BoolVecBitSink) -> Result<(), WriteError>,
+) -> Result<T, DecodeError> {
+    let value = decode(&mut BoolSliceBitSource::new(bits))?;
+
+    let mut sink = BoolVecBitSink::new();
+    encode(&value, &mut sink).map_err(DecodeError::Other)?;
+    let canonical = sink.get_bits();
+
+    let position = bits
+        .iter()
+        .zip(canonical.iter())
+        .position(|(bit, canonical_bit)| bit != canonical_bit)
+        .unwrap_or_else(|| usize::min(bits.len(), canonical.len()));
+    if position < bits.len() || position < canonical.len() {
+        return Err(DecodeError::Other(Box::new(NonCanonicalEncoding {
+            position: position as u64,
+        })));
+    }
+    Ok(value)
+}
+
+/// The byte version of *decode_canonical*: *bytes* must contain the bits (in
+/// the order of *bytes_to_bools*) followed by *num_padding_bits* padding bits,
+/// which must all be 0. Non-zero padding bits are reported as a
+/// *NonCanonicalEncoding* at the position of the first padding bit.
+pub fn decode_canonical_bytes<T>(
+    bytes: &[u8],
+    num_padding_bits: u8,
+    decode: impl FnOnce(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+    encode: impl FnOnce(&T, &mut BoolVecBitSink) -> Result<(), WriteError>,
+) -> Result<T, DecodeError> {
+    let mut bits = bytes_to_bools(bytes);
+    let num_bits = bits.len().saturating_sub(num_padding_bits as usize);
+    if !U8SliceBitSource::new(bytes, num_padding_bits).has_zero_padding() {
+        return Err(DecodeError::Other(Box::new(NonCanonicalEncoding {
+            position: num_bits as u64,
+        })));
+    }
+    bits.truncate(num_bits);
+    decode_canonical(&bits, decode, encode)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn get_position(result: Result<u64, DecodeError>) -> u64 {
+        match result {
+            Err(DecodeError::Other(error)) => {
+                error
+                    .downcast_ref::<NonCanonicalEncoding>()
+                    .expect("Expected NonCanonicalEncoding")
+                    .position
+            }
+            other => panic!("Expected NonCanonicalEncoding, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_digit_encodings() {
+        let encoder = DigitIntEncodingProtocol::new(2, false);
+        let decoder = DigitIntDecodingProtocol::new(2, false);
+        let decode = |source: &mut BoolSliceBitSource| decoder.read_u64(source);
+        let encode = |value: &u64, sink: &mut BoolVecBitSink| encoder.write_u64(sink, *value);
+
+        for value in &[0, 1, 2, 5, 1000, u64::MAX] {
+            let mut sink = BoolVecBitSink::new();
+            encoder.write_u64(&mut sink, *value).unwrap();
+            let bits = sink.get_bits();
+            assert_eq!(*value, decode_canonical(bits, decode, encode).unwrap());
+
+            // Unused trailing bits are not canonical
+            let mut trailing = bits.to_vec();
+            trailing.push(false);
+            assert_eq!(
+                bits.len() as u64,
+                get_position(decode_canonical(&trailing, decode, encode))
+            );
+
+            // Insert a redundant 0 digit before the terminator
+            let mut redundant = bits.to_vec();
+            redundant.splice(bits.len() - 2..bits.len() - 2, vec![false, false]);
+            if decoder
+                .read_u64(&mut BoolSliceBitSource::new(&redundant))
+                .is_ok()
+            {
+                assert_eq!(
+                    bits.len() as u64 - 2,
+                    get_position(decode_canonical(&redundant, decode, encode))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        let encoder = SimpleIntEncodingProtocol::new();
+        let decoder = SimpleIntDecodingProtocol::new();
+        let decode = |source: &mut BoolSliceBitSource| decoder.read_u8(source);
+        let encode = |value: &u8, sink: &mut BoolVecBitSink| encoder.write_u8(sink, *value);
+
+        let bytes = [123, 0];
+        assert_eq!(
+            123,
+            decode_canonical_bytes(&bytes, 8, decode, encode).unwrap()
+        );
+        assert_eq!(
+            8,
+            get_position(
+                decode_canonical_bytes(&[123, 16], 8, decode, encode).map(|value| value as u64)
+            )
+        );
+        assert_eq!(
+            8,
+            get_position(
+                decode_canonical_bytes(&bytes, 0, decode, encode).map(|value| value as u64)
+            )
+        );
+        assert!(decode_canonical_bytes(&bytes, 9, decode, encode).is_err());
+    }
+}
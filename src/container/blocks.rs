@@ -0,0 +1,343 @@
+use crate::*;
+
+use std::convert::TryInto;
+
+/// The magic bytes at the start of every container created by *seal_blocks*
+pub const SEALED_BLOCKS_MAGIC: [u8; 4] = *b"BENB";
+
+/// The size of the header of *seal_blocks*: the magic bytes, the number of bits
+/// as *u64*, the block size as *u32*, and the checksum of the header as *u32*
+const HEADER_SIZE: usize = 20;
+
+const CHECKSUM_SIZE: usize = 4;
+
+/// Creates a byte representation of the bits in *sink* that is safe to store
+/// or transmit, like *seal*, but with a checksum after every *block_size*
+/// bytes rather than a single checksum at the end. When a long stream gets
+/// corrupted, this makes it possible to find out which blocks were damaged,
+/// and to decode the other blocks anyway. It consists of:
+///
+/// 1. the 4 *SEALED_BLOCKS_MAGIC* bytes
+/// 2. the number of bits in *sink*, as little endian *u64*
+/// 3. *block_size*, as little endian *u32*
+/// 4. the CRC-32 checksum of the header (everything before it), as little
+///    endian *u32*
+/// 5. for every block: the next *block_size* bytes of *sink* (or less for the
+///    last block), followed by their CRC-32 checksum as little endian *u32*
+///
+/// So a checksum is inserted every *8 x block_size* bits, which costs 32 bits
+/// per block. For instance, a *block_size* of 128 inserts a checksum every
+/// kilobit. The result can be opened with *open_blocks*.
+///
+/// Note that values can be split over 2 blocks, in which case they can't be
+/// decoded when either of these blocks is corrupted. Applications that want
+/// to decode every intact block should start every block with a new value
+/// (or record).
+///
+/// Panics if *block_size* is 0.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// let encoder = SimpleIntEncodingProtocol::new();
+/// for value in 0..100 {
+///     encoder.write_u32(&mut sink, value).unwrap();
+/// }
+/// // Every block contains exactly 10 values
+/// let mut sealed = seal_blocks(&sink, 40);
+///
+/// // Corrupt a byte of the 4th block
+/// sealed[20 + 3 * 44 + 5] ^= 1;
+///
+/// let blocks = open_blocks(&sealed).unwrap();
+/// assert_eq!(10, blocks.get_num_blocks());
+/// assert_eq!(vec![3], blocks.get_corrupted_blocks());
+/// assert!(blocks.get_block_source(3).is_err());
+///
+/// // The blocks after the corrupted block can still be decoded
+/// let mut source = blocks.get_block_source(4).unwrap();
+/// assert_eq!(40, SimpleIntDecodingProtocol::new().read_u32(&mut source).unwrap());
+/// ```
+pub fn seal_blocks(sink: &U8VecBitSink, block_size: u32) -> Vec<u8> {
+    assert!(block_size > 0, "The block size must be positive");
+    let payload = sink.get_bytes();
+    let num_blocks = payload.len().div_ceil(block_size as usize);
+
+    let mut sealed = Vec::with_capacity(HEADER_SIZE + payload.len() + CHECKSUM_SIZE * num_blocks);
+    sealed.extend_from_slice(&SEALED_BLOCKS_MAGIC);
+    sealed.extend_from_slice(&sink.get_num_bools().to_le_bytes());
+    sealed.extend_from_slice(&block_size.to_le_bytes());
+    let header_checksum = crc32(&sealed);
+    sealed.extend_from_slice(&header_checksum.to_le_bytes());
+
+    for block in payload.chunks(block_size as usize) {
+        sealed.extend_from_slice(block);
+        sealed.extend_from_slice(&crc32(block).to_le_bytes());
+    }
+    sealed
+}
+
+/// The blocks of a container that was created by *seal_blocks*, which can be
+/// obtained with *open_blocks*. The checksum of each block is only checked
+/// when that block is accessed, so the intact blocks can be used even when
+/// other blocks are corrupted.
+#[derive(Debug, Clone, Copy)]
+pub struct SealedBlocks<'a> {
+    blocks: &'a [u8],
+    num_bits: u64,
+    block_size: usize,
+}
+
+impl<'a> SealedBlocks<'a> {
+    /// Gets the total number of bits in all blocks
+    pub fn get_num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Gets the (maximum) number of bytes per block, excluding its checksum
+    pub fn get_block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Gets the number of blocks
+    pub fn get_num_blocks(&self) -> usize {
+        (self.num_bits.div_ceil(8) as usize).div_ceil(self.block_size)
+    }
+
+    /// Gets the index of the first bit of the block with the given *index*,
+    /// relative to the start of the first block
+    pub fn get_block_start_bit(&self, index: usize) -> u64 {
+        index as u64 * self.block_size as u64 * 8
+    }
+
+    /// Gets the bytes of the block with the given *index*, and checks its
+    /// checksum.
+    ///
+    /// Panics if *index* is not smaller than *get_num_blocks()*.
+    fn get_checked_block(&self, index: usize) -> Result<&'a [u8], ContainerError> {
+        assert!(
+            index < self.get_num_blocks(),
+            "Block index {} is out of bounds",
+            index
+        );
+        let start = index * (self.block_size + CHECKSUM_SIZE);
+        let payload_size = self.num_bits.div_ceil(8) as usize;
+        let length = self.block_size.min(payload_size - index * self.block_size);
+        let block = &self.blocks[start..start + length];
+        let checksum = &self.blocks[start + length..start + length + CHECKSUM_SIZE];
+
+        let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+        let actual = crc32(block);
+        if expected != actual {
+            return Err(ContainerError::ChecksumMismatch { expected, actual });
+        }
+        Ok(block)
+    }
+
+    /// Checks whether the checksum of the block with the given *index* matches
+    /// its content.
+    ///
+    /// Panics if *index* is not smaller than *get_num_blocks()*.
+    pub fn check_block(&self, index: usize) -> Result<(), ContainerError> {
+        self.get_checked_block(index).map(|_| ())
+    }
+
+    /// Gets the indices of all blocks whose checksum doesn't match their
+    /// content
+    pub fn get_corrupted_blocks(&self) -> Vec<usize> {
+        (0..self.get_num_blocks())
+            .filter(|index| self.check_block(*index).is_err())
+            .collect()
+    }
+
+    /// Checks the block with the given *index*, and returns a
+    /// *U8SliceBitSource* that reads exactly the bits of that block. Returns
+    /// *ContainerError::ChecksumMismatch* if the block is corrupted.
+    ///
+    /// Panics if *index* is not smaller than *get_num_blocks()*.
+    pub fn get_block_source(&self, index: usize) -> Result<U8SliceBitSource<'a>, ContainerError> {
+        let block = self.get_checked_block(index)?;
+        let num_block_bits =
+            (self.num_bits - self.get_block_start_bit(index)).min(block.len() as u64 * 8);
+        let num_padding_bits = (block.len() as u64 * 8 - num_block_bits) as u8;
+        Ok(U8SliceBitSource::new(block, num_padding_bits))
+    }
+
+    /// Checks all blocks, and copies their bits into a single *U8VecBitSink*.
+    /// Returns the error of the first corrupted block, if any.
+    pub fn to_sink(&self) -> Result<U8VecBitSink, ContainerError> {
+        let mut bytes = Vec::with_capacity(self.num_bits.div_ceil(8) as usize);
+        for index in 0..self.get_num_blocks() {
+            bytes.extend_from_slice(self.get_checked_block(index)?);
+        }
+        let num_used_bits = match bytes.len() {
+            0 => 0,
+            num_bytes => (self.num_bits - (num_bytes as u64 - 1) * 8) as u8,
+        };
+        Ok(U8VecBitSink::from_bytes(bytes, num_used_bits))
+    }
+}
+
+/// Opens a container that was created by *seal_blocks*, and checks its header.
+/// The checksums of the blocks are checked when they are accessed (see
+/// *SealedBlocks*).
+///
+/// Returns an error if the magic bytes are wrong, the checksum of the header
+/// doesn't match, *bytes* is truncated, or *bytes* contains more data after
+/// the container.
+pub fn open_blocks(bytes: &[u8]) -> Result<SealedBlocks<'_>, ContainerError> {
+    let truncated = |required_bytes| ContainerError::Truncated {
+        required_bytes,
+        available_bytes: bytes.len() as u64,
+    };
+
+    let magic_size = SEALED_BLOCKS_MAGIC.len();
+    if bytes.len() < magic_size {
+        return Err(truncated(magic_size as u64));
+    }
+    if bytes[0..magic_size] != SEALED_BLOCKS_MAGIC {
+        return Err(ContainerError::BadMagic);
+    }
+    if bytes.len() < HEADER_SIZE {
+        return Err(truncated(HEADER_SIZE as u64));
+    }
+
+    let checksum_start = HEADER_SIZE - CHECKSUM_SIZE;
+    let expected = u32::from_le_bytes(bytes[checksum_start..HEADER_SIZE].try_into().unwrap());
+    let actual = crc32(&bytes[0..checksum_start]);
+    if expected != actual {
+        return Err(ContainerError::ChecksumMismatch { expected, actual });
+    }
+
+    let num_bits = u64::from_le_bytes(bytes[4..12].try_into().unwrap());
+    let block_size = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as u64;
+    if block_size == 0 {
+        // seal_blocks never creates such a header, so this is not a container of this format
+        return Err(ContainerError::BadMagic);
+    }
+    let num_payload_bytes = num_bits.div_ceil(8);
+    let num_blocks = num_payload_bytes.div_ceil(block_size);
+    let total_size = HEADER_SIZE as u64 + num_payload_bytes + CHECKSUM_SIZE as u64 * num_blocks;
+    if total_size > bytes.len() as u64 {
+        return Err(truncated(total_size));
+    }
+    if total_size < bytes.len() as u64 {
+        return Err(ContainerError::TrailingBytes {
+            num_bytes: bytes.len() as u64 - total_size,
+        });
+    }
+
+    Ok(SealedBlocks {
+        blocks: &bytes[HEADER_SIZE..],
+        num_bits,
+        block_size: block_size as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn create_sink(num_bits: usize) -> U8VecBitSink {
+        let mut sink = U8VecBitSink::new();
+        for index in 0..num_bits {
+            sink.write(&[index % 3 == 0 || index % 7 == 2]).unwrap();
+        }
+        sink
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for num_bits in &[0, 1, 8, 9, 31, 32, 33, 100] {
+            let num_bits = *num_bits;
+            let sink = create_sink(num_bits);
+            let sealed = seal_blocks(&sink, 4);
+            let blocks = open_blocks(&sealed).unwrap();
+            assert_eq!(num_bits as u64, blocks.get_num_bits());
+            assert_eq!(4, blocks.get_block_size());
+            assert_eq!(num_bits.div_ceil(32), blocks.get_num_blocks());
+            assert!(blocks.get_corrupted_blocks().is_empty());
+
+            let copy = blocks.to_sink().unwrap();
+            assert_eq!(sink.get_num_bools(), copy.get_num_bools());
+            assert_eq!(sink.get_bytes(), copy.get_bytes());
+
+            let mut all_bits = Vec::new();
+            for index in 0..blocks.get_num_blocks() {
+                assert_eq!(all_bits.len() as u64, blocks.get_block_start_bit(index));
+                let mut source = blocks.get_block_source(index).unwrap();
+                let mut bit = [false];
+                while source.read(&mut bit).is_ok() {
+                    all_bits.push(bit[0]);
+                }
+            }
+            assert_eq!(sink.iter_bits().collect::<Vec<_>>(), all_bits);
+        }
+    }
+
+    #[test]
+    fn test_corrupted_blocks() {
+        let sink = create_sink(100);
+        let sealed = seal_blocks(&sink, 3);
+        assert_eq!(20 + 13 + 5 * 4, sealed.len());
+
+        // Corrupting any bit of a block (or its checksum) only affects that block
+        for byte_index in 20..sealed.len() {
+            let mut corrupted = sealed.clone();
+            corrupted[byte_index] ^= 0b100;
+            let blocks = open_blocks(&corrupted).unwrap();
+            let block_index = (byte_index - 20) / 7;
+            assert_eq!(vec![block_index], blocks.get_corrupted_blocks());
+            match blocks.to_sink() {
+                Err(ContainerError::ChecksumMismatch { .. }) => {}
+                other => panic!("Expected ChecksumMismatch, but got {:?}", other),
+            }
+            for index in 0..blocks.get_num_blocks() {
+                assert_eq!(index != block_index, blocks.get_block_source(index).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupted_header() {
+        let sealed = seal_blocks(&create_sink(50), 2);
+        let mut bad_magic = sealed.clone();
+        bad_magic[1] = b'X';
+        assert_eq!(
+            ContainerError::BadMagic,
+            open_blocks(&bad_magic).err().unwrap()
+        );
+
+        for byte_index in 4..20 {
+            let mut corrupted = sealed.clone();
+            corrupted[byte_index] ^= 1;
+            match open_blocks(&corrupted) {
+                Err(ContainerError::ChecksumMismatch { .. }) => {}
+                other => panic!("Expected ChecksumMismatch, but got {:?}", other.err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated_and_trailing() {
+        let sealed = seal_blocks(&create_sink(50), 2);
+        for length in 0..sealed.len() {
+            match open_blocks(&sealed[0..length]) {
+                Err(ContainerError::Truncated {
+                    available_bytes, ..
+                }) => assert_eq!(length as u64, available_bytes),
+                other => panic!("Expected Truncated, but got {:?}", other.err()),
+            }
+        }
+
+        let mut trailing = sealed.clone();
+        trailing.extend_from_slice(&[1, 2]);
+        assert_eq!(
+            ContainerError::TrailingBytes { num_bytes: 2 },
+            open_blocks(&trailing).err().unwrap()
+        );
+    }
+}
@@ -0,0 +1,53 @@
+/// The lookup table for *crc32*, which is computed at compile time
+const CRC32_TABLE: [u32; 256] = create_crc32_table();
+
+const fn create_crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 1 {
+                (value >> 1) ^ 0xEDB8_8320
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+}
+
+/// Computes the CRC-32 checksum (the IEEE variant used by zip and PNG) of
+/// *bytes*. This is used by the checksummed container formats of this crate,
+/// but it can also be used to check other data.
+///
+/// # Example
+/// ```
+/// use bit_encoding::crc32;
+///
+/// assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+/// ```
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF;
+    for byte in bytes {
+        crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0, crc32(&[]));
+        assert_eq!(0xE8B7_BE43, crc32(b"a"));
+        assert_eq!(0xC622_F71D, crc32(&[0; 5]));
+    }
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fmt::Display;
+
+/// Represents an error that occurred while trying to read a container (for
+/// instance using *read_container*). This normally indicates that the bytes
+/// were corrupted or truncated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerError {
+    /// The container needs more bytes than the number of bytes that are
+    /// available. This happens when the container was truncated.
+    Truncated {
+        required_bytes: u64,
+        available_bytes: u64,
+    },
+
+    /// The container doesn't start with the expected magic bytes, so it is
+    /// probably not a container of the expected format.
+    BadMagic,
+
+    /// The checksum stored in the container doesn't match the checksum of its
+    /// content, so the content was corrupted.
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    /// The padding bits in the last byte of the container are not all 0. This
+    /// is only checked by the strict variants of the read functions (like
+    /// *read_container_strict*), since it doesn't affect the decoded bits.
+    NonZeroPadding,
+
+    /// The container was read successfully, but it was followed by more bytes
+    /// than expected.
+    TrailingBytes { num_bytes: u64 },
+}
+
+impl Display for ContainerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ContainerError::Truncated {
+                required_bytes,
+                available_bytes,
+            } => write!(
+                f,
+                "The container needs {} bytes, but only {} bytes are available",
+                required_bytes, available_bytes
+            ),
+            ContainerError::BadMagic => {
+                write!(f, "The container starts with the wrong magic bytes")
+            }
+            ContainerError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "The container has checksum {:#010x}, but its content has checksum {:#010x}",
+                expected, actual
+            ),
+            ContainerError::NonZeroPadding => {
+                write!(f, "The padding bits of the container are not all 0")
+            }
+            ContainerError::TrailingBytes { num_bytes } => {
+                write!(
+                    f,
+                    "The container is followed by {} unexpected bytes",
+                    num_bytes
+                )
+            }
+        }
+    }
+}
+
+impl Error for ContainerError {}
@@ -0,0 +1,154 @@
+use crate::*;
+
+use std::convert::TryInto;
+
+/// The number of bytes in the header of *write_container*, which stores the
+/// number of bits as little endian *u64*
+pub const CONTAINER_HEADER_SIZE: usize = 8;
+
+/// Appends the bytes of *sink* to *dest*, prefixed by the exact number of bits
+/// that were written into *sink*. The result can be read back with
+/// *read_container*, without having to know the number of padding bits.
+///
+/// The header is always *CONTAINER_HEADER_SIZE* bytes long: the number of bits
+/// as little endian *u64*. The bytes of *sink* follow right after the header.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// sink.write(&[true, false, true]).unwrap();
+///
+/// let mut container = Vec::new();
+/// write_container(&sink, &mut container);
+///
+/// let (mut source, num_bytes) = read_container(&container).unwrap();
+/// assert_eq!(container.len(), num_bytes);
+/// let mut dest = [false; 3];
+/// source.read(&mut dest).unwrap();
+/// assert_eq!([true, false, true], dest);
+/// assert!(source.read(&mut [false]).is_err());
+/// ```
+pub fn write_container(sink: &U8VecBitSink, dest: &mut Vec<u8>) {
+    dest.reserve(CONTAINER_HEADER_SIZE + sink.get_bytes().len());
+    dest.extend_from_slice(&sink.get_num_bools().to_le_bytes());
+    dest.extend_from_slice(sink.get_bytes());
+}
+
+/// Reads a container that was written by *write_container* from the start of
+/// *bytes*. Returns a *U8SliceBitSource* that will read exactly the bits that
+/// were written into the sink, as well as the total size of the container (in
+/// bytes). Since *bytes* may contain more data after the container, this size
+/// can be used to read multiple containers that were written after each other.
+///
+/// Returns *ContainerError::Truncated* if *bytes* is too short to hold the
+/// header or the number of bits stated in the header.
+pub fn read_container(bytes: &[u8]) -> Result<(U8SliceBitSource<'_>, usize), ContainerError> {
+    let truncated = |required_bytes| ContainerError::Truncated {
+        required_bytes,
+        available_bytes: bytes.len() as u64,
+    };
+
+    if bytes.len() < CONTAINER_HEADER_SIZE {
+        return Err(truncated(CONTAINER_HEADER_SIZE as u64));
+    }
+    let num_bits = u64::from_le_bytes(bytes[0..CONTAINER_HEADER_SIZE].try_into().unwrap());
+    let num_payload_bytes = num_bits.div_ceil(8);
+
+    let total_size = CONTAINER_HEADER_SIZE as u64 + num_payload_bytes;
+    if total_size > bytes.len() as u64 {
+        return Err(truncated(total_size));
+    }
+
+    let total_size = total_size as usize;
+    let num_padding_bits = (num_payload_bytes * 8 - num_bits) as u8;
+    let payload = &bytes[CONTAINER_HEADER_SIZE..total_size];
+    Ok((U8SliceBitSource::new(payload, num_padding_bits), total_size))
+}
+
+/// Reads a container like *read_container*, but also returns
+/// *ContainerError::NonZeroPadding* if the padding bits in the last byte of the
+/// container are not all 0. Since every container written by *write_container*
+/// has 0 padding bits, this ensures that every sequence of bits has exactly 1
+/// valid container representation, which matters when containers are hashed
+/// or signed.
+pub fn read_container_strict(
+    bytes: &[u8],
+) -> Result<(U8SliceBitSource<'_>, usize), ContainerError> {
+    let (source, total_size) = read_container(bytes)?;
+    if !source.has_zero_padding() {
+        return Err(ContainerError::NonZeroPadding);
+    }
+    Ok((source, total_size))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut container = Vec::new();
+        for num_bits in 0..50 {
+            let bits: Vec<bool> = (0..num_bits).map(|index| index % 5 < 2).collect();
+            let mut sink = U8VecBitSink::new();
+            sink.write(&bits).unwrap();
+            write_container(&sink, &mut container);
+        }
+
+        let mut offset = 0;
+        for num_bits in 0..50 {
+            let (mut source, size) = read_container(&container[offset..]).unwrap();
+            offset += size;
+
+            let mut dest = vec![false; num_bits];
+            source.read(&mut dest).unwrap();
+            let expected: Vec<bool> = (0..num_bits).map(|index| index % 5 < 2).collect();
+            assert_eq!(expected, dest);
+            assert!(source.read(&mut [false]).is_err());
+        }
+        assert_eq!(container.len(), offset);
+    }
+
+    #[test]
+    fn test_strict_padding() {
+        let mut sink = U8VecBitSink::new();
+        sink.write(&[true; 13]).unwrap();
+        let mut container = Vec::new();
+        write_container(&sink, &mut container);
+        assert!(read_container_strict(&container).is_ok());
+
+        // The last 3 bits of the last byte are padding
+        container[9] |= 0b0100_0000;
+        assert!(read_container(&container).is_ok());
+        assert_eq!(
+            ContainerError::NonZeroPadding,
+            read_container_strict(&container).err().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut sink = U8VecBitSink::new();
+        sink.write(&[true; 20]).unwrap();
+        let mut container = Vec::new();
+        write_container(&sink, &mut container);
+
+        assert_eq!(
+            ContainerError::Truncated {
+                required_bytes: 11,
+                available_bytes: 10
+            },
+            read_container(&container[0..10]).err().unwrap()
+        );
+        assert_eq!(
+            ContainerError::Truncated {
+                required_bytes: 8,
+                available_bytes: 5
+            },
+            read_container(&container[0..5]).err().unwrap()
+        );
+    }
+}
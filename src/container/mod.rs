@@ -0,0 +1,17 @@
+//! Byte-level container formats that wrap the bytes of a *U8VecBitSink*, so
+//! that they can be stored or transmitted without having to communicate the
+//! number of padding bits (or other metadata) separately.
+
+mod blocks;
+mod crc;
+mod errors;
+mod length_prefixed;
+mod record_log;
+mod sealed;
+
+pub use blocks::*;
+pub use crc::*;
+pub use errors::*;
+pub use length_prefixed::*;
+pub use record_log::*;
+pub use sealed::*;
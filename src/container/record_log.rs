@@ -0,0 +1,202 @@
+use crate::*;
+
+use std::io::Write;
+
+/// Writes an append-only log of records to an *IoBitSink*. Every record is
+/// stored in the format of *seal* (magic bytes, bit length, payload and
+/// checksum), so every record is framed and checksummed on its own. The log can
+/// be read with *RecordLogReader*.
+///
+/// Every record is flushed after it has been appended, so a crash can only
+/// damage the record that was being appended at that moment. *RecordLogReader*
+/// will tolerate such a torn final record.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut log = RecordLogWriter::new(Vec::new());
+/// for value in 0..3 {
+///     let mut record = U8VecBitSink::new();
+///     DigitIntEncodingProtocol::v1().write_u8(&mut record, value).unwrap();
+///     log.append(&record).unwrap();
+/// }
+/// let mut bytes = log.into_inner();
+///
+/// // Simulate a crash while appending the 4th record
+/// bytes.extend_from_slice(&SEALED_MAGIC);
+///
+/// let mut reader = RecordLogReader::new(&bytes);
+/// for value in 0..3 {
+///     let mut record = reader.next().unwrap().unwrap();
+///     assert_eq!(value, DigitIntDecodingProtocol::v1().read_u8(&mut record).unwrap());
+/// }
+/// assert!(reader.next().is_none());
+/// assert!(reader.is_torn());
+/// ```
+pub struct RecordLogWriter<W: Write> {
+    sink: IoBitSink<W>,
+    num_records: u64,
+}
+
+impl<W: Write> RecordLogWriter<W> {
+    /// Constructs a new *RecordLogWriter* that appends records to *writer*. If
+    /// *writer* already contains records (for instance a file that was opened
+    /// in append mode), the new records will simply be added after them. If
+    /// the existing log has a torn final record, it should be truncated to
+    /// *RecordLogReader::get_valid_length* bytes first.
+    pub fn new(writer: W) -> Self {
+        Self {
+            sink: IoBitSink::new(writer),
+            num_records: 0,
+        }
+    }
+
+    /// Appends the bits of *record* to the log, and flushes the writer
+    pub fn append(&mut self, record: &U8VecBitSink) -> Result<(), WriteError> {
+        self.sink.write_bytes(&seal(record))?;
+        self.sink.flush()?;
+        self.num_records += 1;
+        Ok(())
+    }
+
+    /// Gets the number of records that were appended by this writer
+    pub fn get_num_records(&self) -> u64 {
+        self.num_records
+    }
+
+    /// Consumes this writer and returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.sink.into_inner()
+    }
+}
+
+/// An iterator over the records of a log that was written by
+/// *RecordLogWriter*. Every item is a *U8SliceBitSource* that reads exactly the
+/// bits of a record, or a *ContainerError* if a record is corrupted.
+///
+/// If the log ends with an incomplete record (because the process crashed while
+/// appending it), this iterator will stop before that record rather than
+/// returning an error, and *is_torn* will return true. After a corrupted record,
+/// this iterator will stop as well, since the boundaries of the next records
+/// can't be trusted anymore.
+pub struct RecordLogReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    torn: bool,
+    stopped: bool,
+}
+
+impl<'a> RecordLogReader<'a> {
+    /// Constructs a new *RecordLogReader* that reads the records in *bytes*
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            torn: false,
+            stopped: false,
+        }
+    }
+
+    /// Checks whether this reader found an incomplete record at the end of the
+    /// log
+    pub fn is_torn(&self) -> bool {
+        self.torn
+    }
+
+    /// Gets the number of bytes of all complete records that have been read so
+    /// far. After this reader has returned *None*, a torn log can be repaired
+    /// by truncating it to this length.
+    pub fn get_valid_length(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> Iterator for RecordLogReader<'a> {
+    type Item = Result<U8SliceBitSource<'a>, ContainerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped || self.position == self.bytes.len() {
+            return None;
+        }
+
+        match open_prefix(&self.bytes[self.position..]) {
+            Ok((source, size)) => {
+                self.position += size;
+                Some(Ok(source))
+            }
+            Err(ContainerError::Truncated { .. }) => {
+                self.torn = true;
+                self.stopped = true;
+                None
+            }
+            Err(error) => {
+                self.stopped = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn create_log(num_records: usize) -> Vec<u8> {
+        let mut log = RecordLogWriter::new(Vec::new());
+        for index in 0..num_records {
+            let mut record = U8VecBitSink::new();
+            record.write(&vec![true; index]).unwrap();
+            log.append(&record).unwrap();
+        }
+        assert_eq!(num_records as u64, log.get_num_records());
+        log.into_inner()
+    }
+
+    fn check_records(reader: &mut RecordLogReader, num_records: usize) {
+        for index in 0..num_records {
+            let mut record = reader.next().unwrap().unwrap();
+            let mut dest = vec![false; index];
+            record.read(&mut dest).unwrap();
+            assert_eq!(vec![true; index], dest);
+            assert!(record.read(&mut [false]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_complete_log() {
+        let bytes = create_log(20);
+        let mut reader = RecordLogReader::new(&bytes);
+        check_records(&mut reader, 20);
+        assert!(reader.next().is_none());
+        assert!(!reader.is_torn());
+        assert_eq!(bytes.len(), reader.get_valid_length());
+    }
+
+    #[test]
+    fn test_torn_log() {
+        let complete = create_log(5);
+        let valid_length = create_log(4).len();
+        for length in valid_length + 1..complete.len() {
+            let mut reader = RecordLogReader::new(&complete[0..length]);
+            check_records(&mut reader, 4);
+            assert!(reader.next().is_none());
+            assert!(reader.is_torn());
+            assert_eq!(valid_length, reader.get_valid_length());
+        }
+    }
+
+    #[test]
+    fn test_corrupted_record() {
+        let mut bytes = create_log(5);
+        let corrupted_index = create_log(2).len() + 5;
+        bytes[corrupted_index] ^= 1;
+
+        let mut reader = RecordLogReader::new(&bytes);
+        check_records(&mut reader, 2);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+        assert!(!reader.is_torn());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::*;
+
+use std::convert::TryInto;
+
+/// The magic bytes at the start of every container created by *seal*
+pub const SEALED_MAGIC: [u8; 4] = *b"BENC";
+
+const CHECKSUM_SIZE: usize = 4;
+
+/// Creates a self-contained byte representation of the bits in *sink* that is
+/// safe to store or transmit. It consists of:
+///
+/// 1. the 4 *SEALED_MAGIC* bytes
+/// 2. the length-prefixed bits of *sink* (in the format of *write_container*)
+/// 3. the CRC-32 checksum of everything before it, as little endian *u32*
+///
+/// The result can be opened with *open*, which will check the magic bytes and
+/// the checksum.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// DigitIntEncodingProtocol::v1().write_u32(&mut sink, 1234).unwrap();
+/// let sealed = seal(&sink);
+///
+/// let mut source = open(&sealed).unwrap();
+/// assert_eq!(1234, DigitIntDecodingProtocol::v1().read_u32(&mut source).unwrap());
+///
+/// let mut corrupted = sealed.clone();
+/// corrupted[12] ^= 1;
+/// assert!(open(&corrupted).is_err());
+/// ```
+pub fn seal(sink: &U8VecBitSink) -> Vec<u8> {
+    let mut sealed = Vec::with_capacity(
+        SEALED_MAGIC.len() + CONTAINER_HEADER_SIZE + sink.get_bytes().len() + CHECKSUM_SIZE,
+    );
+    sealed.extend_from_slice(&SEALED_MAGIC);
+    write_container(sink, &mut sealed);
+    let checksum = crc32(&sealed);
+    sealed.extend_from_slice(&checksum.to_le_bytes());
+    sealed
+}
+
+/// Opens a sealed container at the start of *bytes*, and returns a source for
+/// its bits, as well as the total size of the sealed container (in bytes)
+pub(crate) fn open_prefix(bytes: &[u8]) -> Result<(U8SliceBitSource<'_>, usize), ContainerError> {
+    let magic_size = SEALED_MAGIC.len();
+    if bytes.len() < magic_size {
+        return Err(ContainerError::Truncated {
+            required_bytes: magic_size as u64,
+            available_bytes: bytes.len() as u64,
+        });
+    }
+    if bytes[0..magic_size] != SEALED_MAGIC {
+        return Err(ContainerError::BadMagic);
+    }
+
+    let (source, container_size) =
+        read_container(&bytes[magic_size..]).map_err(|error| match error {
+            ContainerError::Truncated {
+                required_bytes,
+                available_bytes,
+            } => ContainerError::Truncated {
+                required_bytes: required_bytes + magic_size as u64,
+                available_bytes: available_bytes + magic_size as u64,
+            },
+            other => other,
+        })?;
+
+    let checksum_start = magic_size + container_size;
+    let total_size = checksum_start + CHECKSUM_SIZE;
+    if bytes.len() < total_size {
+        return Err(ContainerError::Truncated {
+            required_bytes: total_size as u64,
+            available_bytes: bytes.len() as u64,
+        });
+    }
+
+    let expected = u32::from_le_bytes(bytes[checksum_start..total_size].try_into().unwrap());
+    let actual = crc32(&bytes[0..checksum_start]);
+    if expected != actual {
+        return Err(ContainerError::ChecksumMismatch { expected, actual });
+    }
+
+    Ok((source, total_size))
+}
+
+/// Opens a container that was created by *seal*, and returns a
+/// *U8SliceBitSource* that will read exactly the bits that were sealed.
+///
+/// Returns an error if the magic bytes are wrong, *bytes* is truncated, the
+/// checksum doesn't match, or *bytes* contains more data after the container.
+pub fn open(bytes: &[u8]) -> Result<U8SliceBitSource<'_>, ContainerError> {
+    let (source, size) = open_prefix(bytes)?;
+    if size != bytes.len() {
+        return Err(ContainerError::TrailingBytes {
+            num_bytes: (bytes.len() - size) as u64,
+        });
+    }
+    Ok(source)
+}
+
+/// Opens a container like *open*, but also returns
+/// *ContainerError::NonZeroPadding* if the padding bits in the last byte of the
+/// sealed bits are not all 0. The checksum would normally catch modified
+/// padding bits, but not when someone deliberately recomputes it.
+pub fn open_strict(bytes: &[u8]) -> Result<U8SliceBitSource<'_>, ContainerError> {
+    let source = open(bytes)?;
+    if !source.has_zero_padding() {
+        return Err(ContainerError::NonZeroPadding);
+    }
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn create_sealed() -> Vec<u8> {
+        let mut sink = U8VecBitSink::new();
+        sink.write(&[true, false, true, true, false, true, false, false, true])
+            .unwrap();
+        seal(&sink)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sealed = create_sealed();
+        assert_eq!(4 + 8 + 2 + 4, sealed.len());
+
+        let mut source = open(&sealed).unwrap();
+        let mut dest = [false; 9];
+        source.read(&mut dest).unwrap();
+        assert_eq!(
+            [true, false, true, true, false, true, false, false, true],
+            dest
+        );
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        let sealed = seal(&U8VecBitSink::new());
+        let mut source = open(&sealed).unwrap();
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_corruption() {
+        let sealed = create_sealed();
+
+        // Flipping any bit should be detected
+        for index in 0..sealed.len() * 8 {
+            let mut corrupted = sealed.clone();
+            corrupted[index / 8] ^= 1 << (index % 8);
+            assert!(open(&corrupted).is_err());
+        }
+
+        let mut bad_magic = sealed.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(ContainerError::BadMagic, open(&bad_magic).err().unwrap());
+
+        let mut bad_checksum = sealed.clone();
+        let last_index = bad_checksum.len() - 1;
+        bad_checksum[last_index] ^= 0xFF;
+        match open(&bad_checksum) {
+            Err(ContainerError::ChecksumMismatch { .. }) => {}
+            other => panic!("Expected ChecksumMismatch, but got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_strict_padding() {
+        let sealed = create_sealed();
+        assert!(open_strict(&sealed).is_ok());
+
+        // Set a padding bit of the second payload byte and fix the checksum
+        let mut forged = sealed[0..sealed.len() - 4].to_vec();
+        forged[13] |= 0b1000_0000;
+        let checksum = crc32(&forged);
+        forged.extend_from_slice(&checksum.to_le_bytes());
+
+        assert!(open(&forged).is_ok());
+        assert_eq!(
+            ContainerError::NonZeroPadding,
+            open_strict(&forged).err().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_truncated_and_trailing() {
+        let sealed = create_sealed();
+        for length in 0..sealed.len() {
+            match open(&sealed[0..length]) {
+                Err(ContainerError::Truncated {
+                    available_bytes, ..
+                }) => assert_eq!(length as u64, available_bytes),
+                other => panic!("Expected Truncated, but got {:?}", other.err()),
+            }
+        }
+
+        let mut trailing = sealed.clone();
+        trailing.push(0);
+        assert_eq!(
+            ContainerError::TrailingBytes { num_bytes: 1 },
+            open(&trailing).err().unwrap()
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// Keeps track of the total number of bytes a decoding process is still allowed
+/// to allocate. Limiting the length of each decoded *Vec* or *String* is not
+/// enough to protect against malicious input: an attacker could simply send
+/// thousands of collections that are each just below the maximum length. A
+/// *DecodeBudget* should be shared by all collections decoded from the same
+/// input to prevent this.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut budget = DecodeBudget::new(1000);
+/// assert_eq!(100, budget.allocate_vec::<u32>(100).unwrap());
+/// assert_eq!(600, budget.get_remaining_bytes());
+///
+/// // There are only 600 bytes left, but 200 u32s need 800 bytes
+/// assert!(budget.allocate_vec::<u32>(200).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecodeBudget {
+    max_bytes: u64,
+    allocated_bytes: u64,
+}
+
+impl DecodeBudget {
+    /// Constructs a new *DecodeBudget* that allows at most *max_bytes* bytes to
+    /// be allocated in total.
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            allocated_bytes: 0,
+        }
+    }
+
+    /// Constructs a *DecodeBudget* without limit. This is only appropriate
+    /// when the input is trusted.
+    pub fn unlimited() -> Self {
+        Self::new(u64::MAX)
+    }
+
+    /// Gets the total number of bytes this budget allowed at construction
+    pub fn get_max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Gets the number of bytes that can still be allocated
+    pub fn get_remaining_bytes(&self) -> u64 {
+        self.max_bytes - self.allocated_bytes
+    }
+
+    /// Claims *num_bytes* bytes of this budget. If that would exceed the
+    /// budget, a *DecodeError::BudgetExceeded* will be returned and nothing will
+    /// be claimed.
+    pub fn allocate(&mut self, num_bytes: u64) -> Result<(), DecodeError> {
+        if num_bytes > self.get_remaining_bytes() {
+            return Err(DecodeError::BudgetExceeded(LengthExceeded::new(
+                self.max_bytes,
+                self.allocated_bytes.saturating_add(num_bytes),
+            )));
+        }
+        self.allocated_bytes += num_bytes;
+        Ok(())
+    }
+
+    /// Claims the memory needed for a *Vec\<T\>* of the given (decoded) length and
+    /// returns that length as *usize*, so it can be used to allocate the *Vec*.
+    pub fn allocate_vec<T>(&mut self, length: LengthType) -> Result<usize, DecodeError> {
+        let usize_length =
+            usize::try_from(length).map_err(|_| DecodeError::VecLengthOverflow { length })?;
+        self.allocate(length.saturating_mul(std::mem::size_of::<T>() as u64))?;
+        Ok(usize_length)
+    }
+
+    /// Claims the memory needed for a *String* of the given (decoded) length in
+    /// bytes and returns that length as *usize*.
+    pub fn allocate_string(&mut self, length: LengthType) -> Result<usize, DecodeError> {
+        let usize_length =
+            usize::try_from(length).map_err(|_| DecodeError::StringLengthOverflow { length })?;
+        self.allocate(length)?;
+        Ok(usize_length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_allocate() {
+        let mut budget = DecodeBudget::new(10);
+        budget.allocate(4).unwrap();
+        budget.allocate(6).unwrap();
+        assert_eq!(0, budget.get_remaining_bytes());
+        budget.allocate(0).unwrap();
+
+        match budget.allocate(1) {
+            Err(DecodeError::BudgetExceeded(exceeded)) => {
+                assert_eq!(10, exceeded.get_max_length());
+                assert_eq!(11, exceeded.get_read_length());
+            }
+            other => panic!("Expected BudgetExceeded, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_many_collections() {
+        // Each of these collections is small, but together they exceed the budget
+        let mut budget = DecodeBudget::new(1000);
+        for _counter in 0..11 {
+            budget.allocate_vec::<u64>(10).unwrap();
+            budget.allocate_string(10).unwrap();
+        }
+        assert!(budget.allocate_vec::<u64>(10).is_err());
+        assert_eq!(10, budget.get_remaining_bytes());
+    }
+
+    #[test]
+    fn test_huge_length() {
+        // The multiplication with the size of u128 must not overflow
+        let mut budget = DecodeBudget::new(1 << 40);
+        assert!(budget.allocate_vec::<u128>(u64::MAX / 2).is_err());
+        assert_eq!(1 << 40, budget.get_remaining_bytes());
+    }
+}
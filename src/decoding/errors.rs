@@ -30,6 +30,16 @@ pub enum DecodeError {
     /// returned.
     BigStringLength(LengthExceeded),
 
+    /// Limiting the length of individual `Vec`s and strings doesn't prevent
+    /// malicious input from making the decoder allocate many of them. To
+    /// prevent that, the decoder can use a `DecodeBudget` that limits the total
+    /// number of bytes that can be allocated while decoding.
+    ///
+    /// If that budget would be exceeded, this error will be returned. The
+    /// maximum length is the total budget (in bytes) and the read length is the
+    /// total number of bytes that would have been allocated.
+    BudgetExceeded(LengthExceeded),
+
     /// Some methods of `BitDecoder` return a `Vec` of elements, for instance
     /// a `Vec<i16>`. The length of the vector will have to be decoded first.
     /// However, the decoded length might be greater than the maximum value
@@ -42,6 +52,24 @@ pub enum DecodeError {
     /// returned.
     StringLengthOverflow { length: LengthType },
 
+    /// Decoding nested structures requires some bookkeeping for each level of
+    /// nesting. To prevent malicious input from nesting structures extremely
+    /// deep, a `DecodeContext` can limit the nesting depth. This error will be
+    /// returned when that limit would be exceeded.
+    TooDeep { max_depth: u32 },
+
+    /// This error indicates that the decoded integer doesn't fit in the integer
+    /// type that was requested (for instance a digit encoding of 300 while
+    /// reading a `u8`). Encoders never produce such encodings, so this
+    /// indicates a corrupted `BitSource` or mismatching protocols.
+    IntOverflow,
+
+    /// This error indicates that a decoded string is not valid UTF-8. The
+    /// *position* is the index of the first byte of the string that is not part
+    /// of a valid UTF-8 sequence. Use *read_string_lossy* of
+    /// *IntDecodingProtocol* to replace invalid sequences instead.
+    InvalidUtf8 { position: usize },
+
     /// This error indicates that an error occurred while reading the data
     /// needed to decode something.
     Reading(ReadError),
@@ -100,6 +128,13 @@ impl Display for DecodeError {
                 length.read_length, length.max_length
             ),
 
+            DecodeError::BudgetExceeded(length) => write!(
+                f,
+                "The decoder would need to allocate {} bytes in total, which is
+            more than its budget of {} bytes.",
+                length.read_length, length.max_length
+            ),
+
             DecodeError::VecLengthOverflow { length } => write!(
                 f,
                 "The decoder was asked to decode a Vec, but the length of the vector
@@ -118,6 +153,26 @@ impl Display for DecodeError {
                 usize::max_value()
             ),
 
+            DecodeError::TooDeep { max_depth } => write!(
+                f,
+                "The decoder encountered structures that are nested deeper than
+            the maximum depth of {}.",
+                max_depth
+            ),
+
+            DecodeError::IntOverflow => write!(
+                f,
+                "The decoded integer is too big for the integer type that was
+            requested."
+            ),
+
+            DecodeError::InvalidUtf8 { position } => write!(
+                f,
+                "The decoded string is not valid UTF-8: the byte at index {} is
+            not part of a valid UTF-8 sequence.",
+                position
+            ),
+
             DecodeError::Reading(read_error) => write!(
                 f,
                 "The following error occurred inside the BitSource the decoder was
@@ -0,0 +1,251 @@
+use crate::*;
+
+/// The limits a decoder should respect when it decodes (possibly malicious)
+/// input. It limits the length of every individual *Vec* and *String*, the total
+/// number of bytes that can be allocated, and how deeply nested structures can be.
+///
+/// Use *DecodeLimits::new* to get some reasonable default limits and use the
+/// *with_* methods to adjust them. To actually use the limits, create a
+/// *DecodeContext* with them and pass it to the decoding functions.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let limits = DecodeLimits::new()
+///     .with_max_vec_len(100)
+///     .with_max_string_len(20)
+///     .with_max_total_bytes(10_000)
+///     .with_max_depth(5);
+///
+/// let mut context = DecodeContext::new(limits);
+/// assert_eq!(50, context.check_vec_len::<u32>(50).unwrap());
+/// assert!(context.check_vec_len::<u32>(101).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    max_string_len: LengthType,
+    max_vec_len: LengthType,
+    max_total_bytes: u64,
+    max_depth: u32,
+}
+
+impl DecodeLimits {
+    /// Constructs the default limits: strings and vectors can have at most
+    /// 1 million elements, at most 64 MiB can be allocated in total, and
+    /// structures can be nested at most 64 levels deep.
+    pub const fn new() -> Self {
+        Self {
+            max_string_len: 1_000_000,
+            max_vec_len: 1_000_000,
+            max_total_bytes: 64 * 1024 * 1024,
+            max_depth: 64,
+        }
+    }
+
+    /// Constructs limits that don't limit anything. This should only be used
+    /// when the input is trusted.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_string_len: LengthType::MAX,
+            max_vec_len: LengthType::MAX,
+            max_total_bytes: u64::MAX,
+            max_depth: u32::MAX,
+        }
+    }
+
+    /// Returns a copy of these limits with the given maximum string length (in
+    /// bytes)
+    pub const fn with_max_string_len(mut self, max_string_len: LengthType) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Returns a copy of these limits with the given maximum *Vec* length (in
+    /// elements)
+    pub const fn with_max_vec_len(mut self, max_vec_len: LengthType) -> Self {
+        self.max_vec_len = max_vec_len;
+        self
+    }
+
+    /// Returns a copy of these limits with the given maximum total number of
+    /// bytes that can be allocated while decoding
+    pub const fn with_max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.max_total_bytes = max_total_bytes;
+        self
+    }
+
+    /// Returns a copy of these limits with the given maximum nesting depth
+    pub const fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Gets the maximum length of strings (in bytes)
+    pub const fn get_max_string_len(&self) -> LengthType {
+        self.max_string_len
+    }
+
+    /// Gets the maximum length of vectors (in elements)
+    pub const fn get_max_vec_len(&self) -> LengthType {
+        self.max_vec_len
+    }
+
+    /// Gets the maximum total number of bytes that can be allocated
+    pub const fn get_max_total_bytes(&self) -> u64 {
+        self.max_total_bytes
+    }
+
+    /// Gets the maximum nesting depth
+    pub const fn get_max_depth(&self) -> u32 {
+        self.max_depth
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The state that needs to be tracked while decoding a single input with some
+/// *DecodeLimits*: the remaining *DecodeBudget* and the current nesting depth.
+/// A new *DecodeContext* should be created for every input that is decoded.
+#[derive(Debug, Clone)]
+pub struct DecodeContext {
+    limits: DecodeLimits,
+    budget: DecodeBudget,
+    depth: u32,
+}
+
+impl DecodeContext {
+    /// Constructs a new *DecodeContext* that will enforce the given limits
+    pub fn new(limits: DecodeLimits) -> Self {
+        Self {
+            limits,
+            budget: DecodeBudget::new(limits.max_total_bytes),
+            depth: 0,
+        }
+    }
+
+    /// Gets the limits of this context
+    pub fn get_limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Gets the remaining budget of this context
+    pub fn get_budget(&self) -> &DecodeBudget {
+        &self.budget
+    }
+
+    /// Gets the current nesting depth
+    pub fn get_depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Checks that a *Vec\<T\>* with the given (decoded) length is allowed and
+    /// claims the memory for it from the budget. Returns the length as *usize*.
+    pub fn check_vec_len<T>(&mut self, length: LengthType) -> Result<usize, DecodeError> {
+        if length > self.limits.max_vec_len {
+            return Err(DecodeError::BigVecLength(LengthExceeded::new(
+                self.limits.max_vec_len,
+                length,
+            )));
+        }
+        self.budget.allocate_vec::<T>(length)
+    }
+
+    /// Checks that a *String* with the given (decoded) length in bytes is allowed
+    /// and claims the memory for it from the budget. Returns the length as *usize*.
+    pub fn check_string_len(&mut self, length: LengthType) -> Result<usize, DecodeError> {
+        if length > self.limits.max_string_len {
+            return Err(DecodeError::BigStringLength(LengthExceeded::new(
+                self.limits.max_string_len,
+                length,
+            )));
+        }
+        self.budget.allocate_string(length)
+    }
+
+    /// This should be called before decoding a nested structure. It will
+    /// return a *DecodeError::TooDeep* if the maximum depth would be exceeded.
+    /// Every successful call must be followed by a call to *exit* once the
+    /// nested structure has been decoded.
+    pub fn enter(&mut self) -> Result<(), DecodeError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(DecodeError::TooDeep {
+                max_depth: self.limits.max_depth,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// This should be called after a nested structure has been decoded. Calling
+    /// this without a matching call to *enter* is a programming error, which
+    /// panics in debug builds and leaves the depth at 0 in release builds.
+    pub fn exit(&mut self) {
+        debug_assert!(self.depth > 0, "exit was called without a matching enter");
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_lengths() {
+        let mut context = DecodeContext::new(
+            DecodeLimits::new()
+                .with_max_string_len(5)
+                .with_max_vec_len(3),
+        );
+        assert_eq!(5, context.check_string_len(5).unwrap());
+        assert!(matches!(
+            context.check_string_len(6),
+            Err(DecodeError::BigStringLength(_))
+        ));
+        assert_eq!(3, context.check_vec_len::<u8>(3).unwrap());
+        assert!(matches!(
+            context.check_vec_len::<u8>(4),
+            Err(DecodeError::BigVecLength(_))
+        ));
+    }
+
+    #[test]
+    fn test_total_bytes() {
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_total_bytes(10));
+        context.check_vec_len::<u32>(2).unwrap();
+        assert!(matches!(
+            context.check_vec_len::<u32>(1),
+            Err(DecodeError::BudgetExceeded(_))
+        ));
+        context.check_string_len(2).unwrap();
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_depth(2));
+        context.enter().unwrap();
+        context.enter().unwrap();
+        assert!(matches!(
+            context.enter(),
+            Err(DecodeError::TooDeep { max_depth: 2 })
+        ));
+        context.exit();
+        context.enter().unwrap();
+        assert_eq!(2, context.get_depth());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_exit_without_enter() {
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        context.enter().unwrap();
+        context.exit();
+        context.exit();
+    }
+}
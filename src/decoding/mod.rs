@@ -1,5 +1,13 @@
+mod budget;
 mod errors;
+mod limits;
 mod protocol;
+mod push;
+mod record_iter;
 
+pub use budget::*;
 pub use errors::*;
+pub use limits::*;
 pub use protocol::*;
+pub use push::*;
+pub use record_iter::*;
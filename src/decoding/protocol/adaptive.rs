@@ -0,0 +1,130 @@
+use crate::*;
+
+/// Decodes the values that were encoded by an *AdaptiveDigitEncoder*. It must
+/// be constructed with the same *block_size* and *short_zero_and_one* as the
+/// encoder, and it must read the values in the same order as they were written.
+#[derive(Debug, Clone)]
+pub struct AdaptiveDigitDecoder {
+    block_size: u32,
+    protocols: [DigitIntDecodingProtocol; 8],
+    current_index: usize,
+    num_values_in_block: u32,
+    is_first_block: bool,
+}
+
+impl AdaptiveDigitDecoder {
+    /// Constructs a new *AdaptiveDigitDecoder* for an encoder with the given
+    /// *block_size* and *short_zero_and_one*.
+    ///
+    /// Panics if *block_size* is 0.
+    pub fn new(block_size: u32, short_zero_and_one: bool) -> Self {
+        if block_size == 0 {
+            panic!("The block size must be positive");
+        }
+        let mut protocols = [DigitIntDecodingProtocol::v1(); 8];
+        for (protocol, digit_size) in protocols.iter_mut().zip(ADAPTIVE_DIGIT_SIZES.iter()) {
+            *protocol = DigitIntDecodingProtocol::new(*digit_size, short_zero_and_one);
+        }
+        Self {
+            block_size,
+            protocols,
+            current_index: ADAPTIVE_INITIAL_DIGIT_SIZE_INDEX,
+            num_values_in_block: 0,
+            is_first_block: true,
+        }
+    }
+
+    /// Gets the number of values per block
+    pub fn get_block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Gets the digit size of the current block
+    pub fn get_digit_size(&self) -> u8 {
+        ADAPTIVE_DIGIT_SIZES[self.current_index]
+    }
+
+    fn start_value(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        if self.num_values_in_block == 0 && !self.is_first_block {
+            let mut switch = [false];
+            source.read(&mut switch)?;
+            if switch[0] {
+                self.current_index = SimpleIntDecodingProtocol::new()
+                    .read_unsigned(source, ADAPTIVE_DIGIT_SIZE_BITS)?
+                    as usize;
+            }
+        }
+        self.num_values_in_block += 1;
+        if self.num_values_in_block == self.block_size {
+            self.num_values_in_block = 0;
+            self.is_first_block = false;
+        }
+        Ok(())
+    }
+
+    /// Reads the next value, which must have been written by *write_u64* of
+    /// *AdaptiveDigitEncoder*
+    pub fn read_u64(&mut self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        self.start_value(source)?;
+        self.protocols[self.current_index].read_u64(source)
+    }
+
+    /// Reads the next value, which must have been written by *write_i64* of
+    /// *AdaptiveDigitEncoder*
+    pub fn read_i64(&mut self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        self.start_value(source)?;
+        self.protocols[self.current_index].read_i64(source)
+    }
+}
+
+/// Reads a state that was written by *write_adaptive_digit_state*, and returns
+/// the index of the digit size, the number of values in the current block and
+/// whether a digit size signal is pending
+pub(crate) fn read_adaptive_digit_state(
+    source: &mut impl BitSource,
+    block_size: u32,
+) -> Result<(usize, u32, bool), DecodeError> {
+    let current_index =
+        SimpleIntDecodingProtocol::new().read_unsigned(source, ADAPTIVE_DIGIT_SIZE_BITS)? as usize;
+    let num_values_in_block = DigitIntDecodingProtocol::v1().read_u32(source)?;
+    let mut signal_pending = [false];
+    source.read(&mut signal_pending)?;
+    if num_values_in_block >= block_size || (signal_pending[0] && num_values_in_block != 0) {
+        return Err(DecodeError::Other(
+            format!(
+                "Invalid adaptive digit state: {} values in block of size {}",
+                num_values_in_block, block_size
+            )
+            .into(),
+        ));
+    }
+    Ok((current_index, num_values_in_block, signal_pending[0]))
+}
+
+/// The state consists of the digit size of the current block and the position
+/// in the current block
+impl SessionCodec for AdaptiveDigitDecoder {
+    fn reset_state(&mut self) {
+        self.current_index = ADAPTIVE_INITIAL_DIGIT_SIZE_INDEX;
+        self.num_values_in_block = 0;
+        self.is_first_block = true;
+    }
+
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        write_adaptive_digit_state(
+            sink,
+            self.current_index,
+            self.num_values_in_block,
+            self.num_values_in_block == 0 && !self.is_first_block,
+        )
+    }
+
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        let (current_index, num_values_in_block, signal_pending) =
+            read_adaptive_digit_state(source, self.block_size)?;
+        self.current_index = current_index;
+        self.num_values_in_block = num_values_in_block;
+        self.is_first_block = !signal_pending;
+        Ok(())
+    }
+}
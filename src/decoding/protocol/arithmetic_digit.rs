@@ -0,0 +1,144 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// Decodes the values that were encoded by an *ArithmeticDigitEncoder*. It
+/// must be constructed with the same *digit_size* as the encoder, it must read
+/// the values in the same order as they were written, and it must call
+/// *finish* at the same points as the encoder.
+#[derive(Debug, Clone)]
+pub struct ArithmeticDigitDecoder {
+    digit_size: u8,
+    model: ContinuationModel,
+    low: u64,
+    high: u64,
+    /// The state bits that have been read, or *None* at the start of a stream
+    value: Option<u64>,
+}
+
+impl ArithmeticDigitDecoder {
+    /// Constructs a new *ArithmeticDigitDecoder* for an encoder with the given
+    /// *digit_size*.
+    ///
+    /// Panics if *digit_size* is 0 or larger than 64.
+    pub fn new(digit_size: u8) -> Self {
+        if digit_size == 0 || digit_size > 64 {
+            panic!("Invalid digit size");
+        }
+        Self {
+            digit_size,
+            model: ContinuationModel::new(get_max_num_u64_digits(digit_size)),
+            low: 0,
+            high: ARITHMETIC_MAX,
+            value: None,
+        }
+    }
+
+    /// Gets the number of bits per digit
+    pub fn get_digit_size(&self) -> u8 {
+        self.digit_size
+    }
+
+    fn read_bit(source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        let mut bit = [false];
+        source.read(&mut bit)?;
+        Ok(bit[0] as u64)
+    }
+
+    fn start_stream(&mut self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        match self.value {
+            Some(value) => Ok(value),
+            None => {
+                let mut bits = [false; ARITHMETIC_PRECISION];
+                source.read(&mut bits)?;
+                let value = bits.iter().fold(0, |value, bit| (value << 1) | *bit as u64);
+                self.value = Some(value);
+                Ok(value)
+            }
+        }
+    }
+
+    /// Decodes a bit whose probability of being false is
+    /// *false_count / total_count*
+    fn decode_bit(
+        &mut self,
+        source: &mut impl BitSource,
+        false_count: u64,
+        total_count: u64,
+    ) -> Result<bool, DecodeError> {
+        let mut value = self.start_stream(source)?;
+        let range = self.high - self.low + 1;
+        let split = self.low + range * false_count / total_count - 1;
+        let bit = value > split;
+        if bit {
+            self.low = split + 1;
+        } else {
+            self.high = split;
+        }
+
+        loop {
+            if self.high < ARITHMETIC_HALF {
+                // Nothing to subtract
+            } else if self.low >= ARITHMETIC_HALF {
+                self.low -= ARITHMETIC_HALF;
+                self.high -= ARITHMETIC_HALF;
+                value -= ARITHMETIC_HALF;
+            } else if self.low >= ARITHMETIC_QUARTER && self.high < 3 * ARITHMETIC_QUARTER {
+                self.low -= ARITHMETIC_QUARTER;
+                self.high -= ARITHMETIC_QUARTER;
+                value -= ARITHMETIC_QUARTER;
+            } else {
+                break;
+            }
+            self.low *= 2;
+            self.high = 2 * self.high + 1;
+            value = 2 * value + Self::read_bit(source)?;
+        }
+        self.value = Some(value);
+        Ok(bit)
+    }
+
+    /// Reads the next value, which must have been written by *write_u64* of
+    /// *ArithmeticDigitEncoder*
+    pub fn read_u64(&mut self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        let digit_size = self.digit_size as usize;
+        let max_num_digits = get_max_num_u64_digits(self.digit_size);
+        let mut result: u128 = 0;
+        for digit_index in 0..max_num_digits {
+            for bit_index in 0..digit_size {
+                if self.decode_bit(source, 1, 2)? {
+                    result |= 1 << (digit_index * digit_size + bit_index);
+                }
+            }
+            if digit_index + 1 < max_num_digits {
+                let (stop_count, total_count) = self.model.get_frequencies(digit_index);
+                let has_more = self.decode_bit(source, stop_count, total_count)?;
+                self.model.update(digit_index, has_more);
+                if !has_more {
+                    break;
+                }
+            }
+        }
+        u64::try_from(result).map_err(|_| DecodeError::IntOverflow)
+    }
+
+    /// Reads the next value, which must have been written by *write_i64* of
+    /// *ArithmeticDigitEncoder*
+    pub fn read_i64(&mut self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        Ok(zigzag_decode(self.read_u64(source)? as u128) as i64)
+    }
+
+    /// Reads the remaining state of the arithmetic coder, which was written by
+    /// the *finish* method of the encoder. After this, *source* is positioned
+    /// right after the stream, and the decoder starts a new stream.
+    pub fn finish(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        self.start_stream(source)?;
+        self.low = 0;
+        self.high = ARITHMETIC_MAX;
+        self.value = None;
+        Ok(())
+    }
+}
+
+// This struct doesn't have its own unit tests, but relies on the unit tests of
+// *ArithmeticDigitEncoder* to improve code reuse in the unit tests.
@@ -0,0 +1,454 @@
+use crate::*;
+
+use std::collections::HashMap;
+
+/// The length of the longest code that a *CachedDigitIntDecodingProtocol* can
+/// look up. Longer codes only occur when the digit size is very large, and they
+/// are simply decoded by the wrapped protocol.
+const MAX_CACHED_CODE_LENGTH: usize = 128;
+
+/// The values of a range of consecutive values, indexed by their bit patterns
+#[derive(Debug, Clone)]
+struct DecodingTable {
+    /// For every distinct code length (in increasing order), maps the codes of
+    /// that length to their values. The first bit of a code is its least
+    /// significant bit.
+    codes: Vec<(usize, HashMap<u128, i128>)>,
+    num_values: usize,
+}
+
+impl DecodingTable {
+    fn new(
+        first_value: i128,
+        bound_value: i128,
+        write: impl Fn(&mut BoolVecBitSink, i128) -> Result<(), WriteError>,
+    ) -> Self {
+        let mut codes: Vec<(usize, HashMap<u128, i128>)> = Vec::new();
+        let mut sink = BoolVecBitSink::new();
+        for value in first_value..bound_value {
+            sink.clear();
+            write(&mut sink, value).expect("BoolVecBitSink shouldn't fail");
+            let length = sink.get_bits().len();
+            if length > MAX_CACHED_CODE_LENGTH {
+                continue;
+            }
+            let index = match codes.binary_search_by_key(&length, |(length, _)| *length) {
+                Ok(index) => index,
+                Err(index) => {
+                    codes.insert(index, (length, HashMap::new()));
+                    index
+                }
+            };
+            codes[index].1.insert(pack_code(sink.get_bits()), value);
+        }
+        Self {
+            codes,
+            num_values: (bound_value - first_value).max(0) as usize,
+        }
+    }
+
+    fn get_max_length(&self) -> usize {
+        self.codes.last().map_or(0, |(length, _)| *length)
+    }
+
+    /// Finds the value whose code is a prefix of the first *num_bits* bits of
+    /// *code*, and returns it together with the length of its code
+    fn get(&self, code: u128, num_bits: usize) -> Option<(i128, usize)> {
+        for (length, values) in &self.codes {
+            if *length > num_bits {
+                break;
+            }
+            let mask = u128::MAX >> (MAX_CACHED_CODE_LENGTH - length);
+            if let Some(value) = values.get(&(code & mask)) {
+                return Some((*value, *length));
+            }
+        }
+        None
+    }
+}
+
+fn pack_code(bits: &[bool]) -> u128 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |code, (index, bit)| code | ((*bit as u128) << index))
+}
+
+/// The decoding counterpart of *CachedDigitIntEncodingProtocol*: a
+/// *DigitIntDecodingProtocol* that pre-computes the encodings of small values,
+/// so that decoding such a value is just 1 *read* call followed by a table
+/// lookup, instead of reading and combining the digits one by one.
+///
+/// To do so, it reads as many bits as the longest cached code of the integer
+/// type, looks up the code that they start with, and seeks back to the end of
+/// that code. That is why its read methods need a *SeekableBitSource*, and why
+/// it doesn't implement *IntDecodingProtocol*. When the bits don't start with a
+/// cached code, the value is decoded by the wrapped protocol instead, so the
+/// results (including the errors) are exactly the same as those of the wrapped
+/// protocol.
+///
+/// The same values as in *CachedDigitIntEncodingProtocol* are cached: all *u8*
+/// and *i8* values, and the *cache_size* smallest unsigned values and signed
+/// values (closest to 0) of the other integer types. The encodings of all *u16*
+/// and *i16* values can be cached as well (using *with_full_16_bit_tables*).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = BoolVecBitSink::new();
+/// for value in &[0, 1, 10, 1023, 1024, 123456] {
+///     encoder.write_u32(&mut sink, *value).unwrap();
+/// }
+///
+/// let decoder = CachedDigitIntDecodingProtocol::new(DigitIntDecodingProtocol::v1(), 1024);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// for value in &[0, 1, 10, 1023, 1024, 123456] {
+///     assert_eq!(*value, decoder.read_u32(&mut source).unwrap());
+/// }
+/// assert!(decoder.read_u32(&mut source).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedDigitIntDecodingProtocol {
+    protocol: DigitIntDecodingProtocol,
+    cache_size: u32,
+    tables: Vec<DecodingTable>,
+}
+
+macro_rules! unsigned_table {
+    ($encoder:ident, $cache_size:ident, $t:ty, $write:ident) => {
+        DecodingTable::new(
+            0,
+            (<$t>::MAX as u128)
+                .saturating_add(1)
+                .min($cache_size as u128) as i128,
+            |sink, value| $encoder.$write(sink, value as $t),
+        )
+    };
+}
+
+macro_rules! full_table {
+    ($encoder:ident, $t:ty, $write:ident) => {
+        DecodingTable::new(<$t>::MIN as i128, <$t>::MAX as i128 + 1, |sink, value| {
+            $encoder.$write(sink, value as $t)
+        })
+    };
+}
+
+macro_rules! signed_table {
+    ($encoder:ident, $cache_size:ident, $t:ty, $write:ident) => {
+        DecodingTable::new(
+            i128::max(-($cache_size as i128 / 2), <$t>::MIN as i128),
+            (<$t>::MAX as i128)
+                .saturating_add(1)
+                .min($cache_size as i128 / 2),
+            |sink, value| $encoder.$write(sink, value as $t),
+        )
+    };
+}
+
+impl CachedDigitIntDecodingProtocol {
+    /// Constructs a new *CachedDigitIntDecodingProtocol* that decodes values
+    /// like *protocol*, and caches the encodings of the *cache_size* smallest
+    /// unsigned values and signed values (closest to 0).
+    ///
+    /// Panics if *cache_size* is larger than *MAX_DIGIT_CACHE_SIZE*.
+    pub fn new(protocol: DigitIntDecodingProtocol, cache_size: u32) -> Self {
+        if cache_size > MAX_DIGIT_CACHE_SIZE {
+            panic!("The cache size can be at most {}", MAX_DIGIT_CACHE_SIZE);
+        }
+        let encoder = protocol.to_encoder();
+        let tables = vec![
+            full_table!(encoder, u8, write_u8),
+            full_table!(encoder, i8, write_i8),
+            unsigned_table!(encoder, cache_size, u16, write_u16),
+            signed_table!(encoder, cache_size, i16, write_i16),
+            unsigned_table!(encoder, cache_size, u32, write_u32),
+            signed_table!(encoder, cache_size, i32, write_i32),
+            unsigned_table!(encoder, cache_size, u64, write_u64),
+            signed_table!(encoder, cache_size, i64, write_i64),
+            unsigned_table!(encoder, cache_size, u128, write_u128),
+            signed_table!(encoder, cache_size, i128, write_i128),
+        ];
+        Self {
+            protocol,
+            cache_size,
+            tables,
+        }
+    }
+
+    /// Caches the encodings of all *u16* and *i16* values (65536 of each),
+    /// rather than only the *cache_size* smallest ones
+    pub fn with_full_16_bit_tables(mut self) -> Self {
+        let encoder = self.protocol.to_encoder();
+        self.tables[2] = full_table!(encoder, u16, write_u16);
+        self.tables[3] = full_table!(encoder, i16, write_i16);
+        self
+    }
+
+    /// Checks whether the encodings of all *u16* and *i16* values are cached
+    pub fn has_full_16_bit_tables(&self) -> bool {
+        self.tables[2].num_values == 1 << 16
+    }
+
+    /// Gets the wrapped *DigitIntDecodingProtocol*
+    pub fn get_protocol(&self) -> DigitIntDecodingProtocol {
+        self.protocol
+    }
+
+    /// Gets the number of values whose encoding is cached (per integer type)
+    pub fn get_cache_size(&self) -> u32 {
+        self.cache_size
+    }
+
+    /// Looks up the value whose code the next bits of *source* start with, and
+    /// moves *source* to the end of that code. If the bits don't start with a
+    /// cached code, this returns *None* and leaves *source* where it was.
+    fn read_cached(
+        &self,
+        source: &mut impl SeekableBitSource,
+        table: usize,
+    ) -> Result<Option<i128>, DecodeError> {
+        let table = &self.tables[table];
+        let max_length = table.get_max_length();
+        if max_length == 0 {
+            return Ok(None);
+        }
+
+        let start_position = source.position();
+        let mut bits = [false; MAX_CACHED_CODE_LENGTH];
+        let num_bits = match source.read(&mut bits[..max_length]) {
+            Ok(()) => max_length,
+            // The value might still be cached if its code is shorter
+            Err(ReadError::ReachedEnd { read_bools }) => read_bools,
+            Err(error) => return Err(DecodeError::Reading(error)),
+        };
+
+        let cached = table.get(pack_code(&bits[..num_bits]), num_bits);
+        let code_length = cached.map_or(0, |(_, length)| length);
+        source.seek_bits(start_position + code_length as u64)?;
+        Ok(cached.map(|(value, _)| value))
+    }
+}
+
+macro_rules! read_cached {
+    ($self:ident, $source:ident, $table:expr, $t:ty, $read:ident) => {
+        match $self.read_cached($source, $table)? {
+            Some(value) => Ok(value as $t),
+            None => $self.protocol.$read($source),
+        }
+    };
+}
+
+impl CachedDigitIntDecodingProtocol {
+    /// Decodes a u8 value from the bits coming from *source*
+    pub fn read_u8(&self, source: &mut impl SeekableBitSource) -> Result<u8, DecodeError> {
+        read_cached!(self, source, 0, u8, read_u8)
+    }
+
+    /// Decodes an i8 value from the bits coming from *source*
+    pub fn read_i8(&self, source: &mut impl SeekableBitSource) -> Result<i8, DecodeError> {
+        read_cached!(self, source, 1, i8, read_i8)
+    }
+
+    /// Decodes a u16 value from the bits coming from *source*
+    pub fn read_u16(&self, source: &mut impl SeekableBitSource) -> Result<u16, DecodeError> {
+        read_cached!(self, source, 2, u16, read_u16)
+    }
+
+    /// Decodes an i16 value from the bits coming from *source*
+    pub fn read_i16(&self, source: &mut impl SeekableBitSource) -> Result<i16, DecodeError> {
+        read_cached!(self, source, 3, i16, read_i16)
+    }
+
+    /// Decodes a u32 value from the bits coming from *source*
+    pub fn read_u32(&self, source: &mut impl SeekableBitSource) -> Result<u32, DecodeError> {
+        read_cached!(self, source, 4, u32, read_u32)
+    }
+
+    /// Decodes an i32 value from the bits coming from *source*
+    pub fn read_i32(&self, source: &mut impl SeekableBitSource) -> Result<i32, DecodeError> {
+        read_cached!(self, source, 5, i32, read_i32)
+    }
+
+    /// Decodes a u64 value from the bits coming from *source*
+    pub fn read_u64(&self, source: &mut impl SeekableBitSource) -> Result<u64, DecodeError> {
+        read_cached!(self, source, 6, u64, read_u64)
+    }
+
+    /// Decodes an i64 value from the bits coming from *source*
+    pub fn read_i64(&self, source: &mut impl SeekableBitSource) -> Result<i64, DecodeError> {
+        read_cached!(self, source, 7, i64, read_i64)
+    }
+
+    /// Decodes a u128 value from the bits coming from *source*
+    pub fn read_u128(&self, source: &mut impl SeekableBitSource) -> Result<u128, DecodeError> {
+        read_cached!(self, source, 8, u128, read_u128)
+    }
+
+    /// Decodes an i128 value from the bits coming from *source*
+    pub fn read_i128(&self, source: &mut impl SeekableBitSource) -> Result<i128, DecodeError> {
+        read_cached!(self, source, 9, i128, read_i128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn test_round_trip(
+        protocol: DigitIntDecodingProtocol,
+        cached: &CachedDigitIntDecodingProtocol,
+    ) {
+        let encoder = protocol.to_encoder();
+        let mut sink = BoolVecBitSink::new();
+        let mut num_values = 0;
+        for value in -700i128..700 {
+            macro_rules! encode {
+                ($t:ty, $write:ident) => {
+                    if let Ok(value) = std::convert::TryInto::<$t>::try_into(value) {
+                        encoder.$write(&mut sink, value).unwrap();
+                    }
+                };
+            }
+            encode!(u8, write_u8);
+            encode!(i8, write_i8);
+            encode!(u16, write_u16);
+            encode!(i16, write_i16);
+            encode!(u32, write_u32);
+            encode!(i32, write_i32);
+            encode!(u64, write_u64);
+            encode!(i64, write_i64);
+            encode!(u128, write_u128);
+            encoder.write_i128(&mut sink, value).unwrap();
+            num_values += 1;
+        }
+        encoder.write_u128(&mut sink, u128::MAX).unwrap();
+        encoder.write_i128(&mut sink, i128::MIN).unwrap();
+
+        let mut expected = BoolSliceBitSource::new(sink.get_bits());
+        let mut actual = BoolSliceBitSource::new(sink.get_bits());
+        for value in -700i128..-700 + num_values {
+            macro_rules! check {
+                ($t:ty, $read:ident) => {
+                    if std::convert::TryInto::<$t>::try_into(value).is_ok() {
+                        assert_eq!(
+                            protocol.$read(&mut expected).unwrap(),
+                            cached.$read(&mut actual).unwrap()
+                        );
+                        assert_eq!(expected.position(), actual.position());
+                    }
+                };
+            }
+            check!(u8, read_u8);
+            check!(i8, read_i8);
+            check!(u16, read_u16);
+            check!(i16, read_i16);
+            check!(u32, read_u32);
+            check!(i32, read_i32);
+            check!(u64, read_u64);
+            check!(i64, read_i64);
+            check!(u128, read_u128);
+            check!(i128, read_i128);
+        }
+        assert_eq!(u128::MAX, cached.read_u128(&mut actual).unwrap());
+        assert_eq!(i128::MIN, cached.read_i128(&mut actual).unwrap());
+        assert_eq!(sink.get_num_bools(), actual.position());
+        assert!(cached.read_u8(&mut actual).is_err());
+    }
+
+    #[test]
+    fn test_same_decoding() {
+        for &(digit_size, short_zero_and_one) in
+            &[(2, false), (3, true), (8, false), (9, true), (100, true)]
+        {
+            for &signed_strategy in &[SignedStrategy::SignBit, SignedStrategy::Zigzag] {
+                let protocol = DigitIntDecodingProtocol::new(digit_size, short_zero_and_one)
+                    .with_signed_strategy(signed_strategy);
+                let cached = CachedDigitIntDecodingProtocol::new(protocol, 600);
+                assert_eq!(600, cached.get_cache_size());
+                test_round_trip(protocol, &cached);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_tables() {
+        let protocol = DigitIntDecodingProtocol::new(4, true);
+        let cached = CachedDigitIntDecodingProtocol::new(protocol, 10);
+        assert!(!cached.has_full_16_bit_tables());
+        let cached = cached.with_full_16_bit_tables();
+        assert!(cached.has_full_16_bit_tables());
+        test_round_trip(protocol, &cached);
+
+        let encoder = protocol.to_encoder();
+        let mut sink = BoolVecBitSink::new();
+        for value in 0..=u16::MAX {
+            encoder.write_u16(&mut sink, value).unwrap();
+            encoder.write_i16(&mut sink, value as i16).unwrap();
+        }
+        let mut expected = BoolSliceBitSource::new(sink.get_bits());
+        let mut actual = BoolSliceBitSource::new(sink.get_bits());
+        for _value in 0..=u16::MAX {
+            assert_eq!(
+                protocol.read_u16(&mut expected).unwrap(),
+                cached.read_u16(&mut actual).unwrap()
+            );
+            assert_eq!(
+                protocol.read_i16(&mut expected).unwrap(),
+                cached.read_i16(&mut actual).unwrap()
+            );
+        }
+        assert_eq!(expected.position(), actual.position());
+    }
+
+    #[test]
+    fn test_truncated_code() {
+        let protocol = DigitIntDecodingProtocol::v1();
+        let cached = CachedDigitIntDecodingProtocol::new(protocol, 100);
+        let mut sink = BoolVecBitSink::new();
+        protocol.to_encoder().write_u32(&mut sink, 50).unwrap();
+        let bits = &sink.get_bits()[..sink.get_bits().len() - 1];
+
+        // The code of 50 is incomplete, but shorter codes mustn't match it
+        let mut source = BoolSliceBitSource::new(bits);
+        assert!(matches!(
+            cached.read_u32(&mut source),
+            Err(DecodeError::Reading(ReadError::ReachedEnd { .. }))
+        ));
+
+        // The code of a small value can be shorter than the longest cached code
+        let mut sink = BoolVecBitSink::new();
+        protocol.to_encoder().write_u32(&mut sink, 1).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(1, cached.read_u32(&mut source).unwrap());
+        assert_eq!(sink.get_num_bools(), source.position());
+    }
+
+    #[test]
+    fn test_u8_slice_source() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = U8VecBitSink::new();
+        for value in 0..300 {
+            encoder.write_u16(&mut sink, value).unwrap();
+        }
+        let (bytes, num_used_bits) = sink.into_bytes();
+
+        let cached = CachedDigitIntDecodingProtocol::new(DigitIntDecodingProtocol::v1(), 256);
+        let mut source = U8SliceBitSource::new(&bytes, (8 - num_used_bits) % 8);
+        for value in 0..300 {
+            assert_eq!(value, cached.read_u16(&mut source).unwrap());
+        }
+        assert!(cached.read_u16(&mut source).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_large_cache() {
+        CachedDigitIntDecodingProtocol::new(
+            DigitIntDecodingProtocol::v1(),
+            MAX_DIGIT_CACHE_SIZE + 1,
+        );
+    }
+}
@@ -1,11 +1,15 @@
 use crate::*;
 
+use std::convert::TryFrom;
+
 /// An *IntDecodingProtocol* that decodes the digit-based encoding of
 /// *DigitIntEncodingProtocol*. See the documentation of *DigitIntEncodingProtocol*
 /// for more information.
+#[derive(Debug, Clone, Copy)]
 pub struct DigitIntDecodingProtocol {
     digit_size: u8,
     short_zero_and_one: bool,
+    signed_strategy: SignedStrategy,
 
     max_num_digits: [u8; 10],
 }
@@ -23,10 +27,31 @@ impl DigitIntDecodingProtocol {
         DigitIntDecodingProtocol {
             digit_size,
             short_zero_and_one,
+            signed_strategy: SignedStrategy::SignBit,
             max_num_digits: compute_relevant_num_digits(digit_size),
         }
     }
 
+    /// Returns a copy of this protocol that decodes signed integers using the
+    /// given *signed_strategy*, which must be the same strategy as the encoder
+    /// used. Note that *new* uses *SignedStrategy::SignBit*.
+    pub const fn with_signed_strategy(mut self, signed_strategy: SignedStrategy) -> Self {
+        self.signed_strategy = signed_strategy;
+        self
+    }
+
+    /// Gets the strategy that this protocol uses to decode signed integers
+    pub const fn get_signed_strategy(&self) -> SignedStrategy {
+        self.signed_strategy
+    }
+
+    /// Constructs the *DigitIntEncodingProtocol* whose encoding this protocol
+    /// decodes
+    pub(crate) const fn to_encoder(self) -> DigitIntEncodingProtocol {
+        DigitIntEncodingProtocol::new(self.digit_size, self.short_zero_and_one)
+            .with_signed_strategy(self.signed_strategy)
+    }
+
     /// Constructs a new instance of *DigitIntDecodingProtocol* that uses the first
     /// configuration (constructor parameters) that I found to perform well. The
     /// *v1* function of *DigitIntEncodingProtocol* returns a corresponding
@@ -43,8 +68,8 @@ impl DigitIntDecodingProtocol {
         let simple_decoder = SimpleIntDecodingProtocol::new();
         let num_digit_values = get_num_digit_values(self.digit_size);
 
-        let mut current_factor = 1;
-        let mut current_result = 0;
+        let mut current_factor = 1u128;
+        let mut current_result = 0u128;
         for current_digit in 1..=max_num_digits {
             let next_digit = simple_decoder.read_unsigned(source, self.digit_size as usize)?;
 
@@ -53,14 +78,35 @@ impl DigitIntDecodingProtocol {
                 break;
             }
 
-            current_result += current_factor * next_digit;
+            // Corrupted input could make the result exceed even a u128
+            current_result = current_factor
+                .checked_mul(next_digit)
+                .and_then(|digit_value| current_result.checked_add(digit_value))
+                .ok_or(DecodeError::IntOverflow)?;
             if current_digit < max_num_digits {
-                current_factor *= num_digit_values;
+                current_factor = current_factor
+                    .checked_mul(num_digit_values)
+                    .ok_or(DecodeError::IntOverflow)?;
             }
         }
         Ok(current_result)
     }
 
+    fn get_max_num_digits(&self, int_type: IntType) -> u8 {
+        match int_type {
+            IntType::I8 => self.max_num_digits[0],
+            IntType::U8 => self.max_num_digits[1],
+            IntType::I16 => self.max_num_digits[2],
+            IntType::U16 => self.max_num_digits[3],
+            IntType::I32 => self.max_num_digits[4],
+            IntType::U32 => self.max_num_digits[5],
+            IntType::I64 => self.max_num_digits[6],
+            IntType::U64 => self.max_num_digits[7],
+            IntType::I128 => self.max_num_digits[8],
+            IntType::U128 => self.max_num_digits[9],
+        }
+    }
+
     fn read_unsigned(
         &self,
         source: &mut impl BitSource,
@@ -82,7 +128,7 @@ impl DigitIntDecodingProtocol {
         let result = self.read_digit_part(source, max_num_digits)?;
         match self.short_zero_and_one {
             false => Ok(result),
-            true => Ok(result + 2),
+            true => result.checked_add(2).ok_or(DecodeError::IntOverflow),
         }
     }
 
@@ -90,7 +136,12 @@ impl DigitIntDecodingProtocol {
         &self,
         source: &mut impl BitSource,
         max_num_digits: u8,
+        max_num_unsigned_digits: u8,
     ) -> Result<i128, DecodeError> {
+        if self.signed_strategy == SignedStrategy::Zigzag {
+            let value = self.read_unsigned(source, max_num_unsigned_digits)?;
+            return Ok(zigzag_decode(value));
+        }
         if self.short_zero_and_one {
             let mut first_bit = [false];
             source.read(&mut first_bit)?;
@@ -107,54 +158,70 @@ impl DigitIntDecodingProtocol {
         let mut sign_bit = [false];
         source.read(&mut sign_bit)?;
 
-        let unsigned_result = self.read_digit_part(source, max_num_digits)? as i128;
+        let unsigned_result = i128::try_from(self.read_digit_part(source, max_num_digits)?)
+            .map_err(|_| DecodeError::IntOverflow)?;
         match [sign_bit[0], self.short_zero_and_one] {
             [false, false] => Ok(unsigned_result),
-            [false, true] => Ok(unsigned_result + 2),
+            [false, true] => unsigned_result
+                .checked_add(2)
+                .ok_or(DecodeError::IntOverflow),
             [true, _] => Ok(-unsigned_result - 1),
         }
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for DigitIntDecodingProtocol {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let digit_size = u.int_in_range(2..=127)?;
+        let short_zero_and_one = u.arbitrary()?;
+        let signed_strategy = match u.arbitrary()? {
+            false => SignedStrategy::SignBit,
+            true => SignedStrategy::Zigzag,
+        };
+        Ok(Self::new(digit_size, short_zero_and_one).with_signed_strategy(signed_strategy))
+    }
+}
+
 impl IntDecodingProtocol for DigitIntDecodingProtocol {
     fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
         self.read_unsigned(source, self.max_num_digits[1])
-            .map(|x| x as u8)
+            .and_then(|x| u8::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
-        self.read_signed(source, self.max_num_digits[0])
-            .map(|x| x as i8)
+        self.read_signed(source, self.max_num_digits[0], self.max_num_digits[1])
+            .and_then(|x| i8::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
         self.read_unsigned(source, self.max_num_digits[3])
-            .map(|x| x as u16)
+            .and_then(|x| u16::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
-        self.read_signed(source, self.max_num_digits[2])
-            .map(|x| x as i16)
+        self.read_signed(source, self.max_num_digits[2], self.max_num_digits[3])
+            .and_then(|x| i16::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
         self.read_unsigned(source, self.max_num_digits[5])
-            .map(|x| x as u32)
+            .and_then(|x| u32::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
-        self.read_signed(source, self.max_num_digits[4])
-            .map(|x| x as i32)
+        self.read_signed(source, self.max_num_digits[4], self.max_num_digits[5])
+            .and_then(|x| i32::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
         self.read_unsigned(source, self.max_num_digits[7])
-            .map(|x| x as u64)
+            .and_then(|x| u64::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
-        self.read_signed(source, self.max_num_digits[6])
-            .map(|x| x as i64)
+        self.read_signed(source, self.max_num_digits[6], self.max_num_digits[7])
+            .and_then(|x| i64::try_from(x).map_err(|_| DecodeError::IntOverflow))
     }
 
     fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
@@ -162,7 +229,37 @@ impl IntDecodingProtocol for DigitIntDecodingProtocol {
     }
 
     fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
-        self.read_signed(source, self.max_num_digits[8])
+        self.read_signed(source, self.max_num_digits[8], self.max_num_digits[9])
+    }
+
+    fn skip(&self, source: &mut impl BitSource, mut int_type: IntType) -> Result<(), DecodeError> {
+        // Zigzag encoded integers are encoded like unsigned integers
+        if self.signed_strategy == SignedStrategy::Zigzag {
+            int_type = int_type.to_unsigned();
+        }
+        if self.short_zero_and_one {
+            let mut first_bits = [false; 2];
+            source.read(&mut first_bits[0..1])?;
+            if first_bits[0] {
+                return source
+                    .read(&mut first_bits[1..2])
+                    .map_err(DecodeError::Reading);
+            }
+        }
+        if int_type.is_signed() {
+            source.read(&mut [false])?;
+        }
+
+        // Just read digits until the terminator or the maximum number of digits
+        let terminator = vec![true; self.digit_size as usize];
+        let mut digit = vec![false; self.digit_size as usize];
+        for _counter in 0..self.get_max_num_digits(int_type) {
+            source.read(&mut digit)?;
+            if digit == terminator {
+                break;
+            }
+        }
+        Ok(())
     }
 }
 
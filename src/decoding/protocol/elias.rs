@@ -0,0 +1,207 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// Reads the *num_bits* least significant bits of the number *n* of an Elias
+/// code (most significant bit first), and returns *n - 1*
+fn read_elias_remainder(source: &mut impl BitSource, num_bits: u32) -> Result<u128, DecodeError> {
+    let mut bits = vec![false; num_bits as usize];
+    source.read(&mut bits)?;
+    let mut remainder = 0u128;
+    for bit in &bits {
+        remainder = (remainder << 1) | *bit as u128;
+    }
+
+    // The only number with 129 bits that can be decoded is u128::MAX + 1
+    if num_bits == 128 {
+        return match remainder {
+            0 => Ok(u128::MAX),
+            _ => Err(DecodeError::IntOverflow),
+        };
+    }
+    Ok(((1 << num_bits) | remainder) - 1)
+}
+
+/// Reads an Elias gamma code and returns the number minus 1
+fn read_elias_gamma(source: &mut impl BitSource) -> Result<u128, DecodeError> {
+    let mut num_zeros = 0;
+    let mut bit = [false];
+    loop {
+        source.read(&mut bit)?;
+        if bit[0] {
+            break;
+        }
+        if num_zeros == 128 {
+            return Err(DecodeError::IntOverflow);
+        }
+        num_zeros += 1;
+    }
+    read_elias_remainder(source, num_zeros)
+}
+
+/// The *IntDecodingProtocol* that decodes the Elias gamma codes written by
+/// *EliasGammaIntEncodingProtocol*. See the documentation of
+/// *EliasGammaIntEncodingProtocol* for more information.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasGammaIntDecodingProtocol {}
+
+impl EliasGammaIntDecodingProtocol {
+    /// Constructs a new instance of *EliasGammaIntDecodingProtocol*. Note that
+    /// this is actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        EliasGammaIntDecodingProtocol {}
+    }
+
+    fn read_unsigned(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        read_elias_gamma(source)
+    }
+
+    fn read_signed(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        Ok(zigzag_decode(self.read_unsigned(source)?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EliasGammaIntDecodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntDecodingProtocol for EliasGammaIntDecodingProtocol {
+    fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        self.read_unsigned(source)
+    }
+
+    fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        self.read_signed(source)
+    }
+}
+
+/// The *IntDecodingProtocol* that decodes the Elias delta codes written by
+/// *EliasDeltaIntEncodingProtocol*. See the documentation of
+/// *EliasDeltaIntEncodingProtocol* for more information.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasDeltaIntDecodingProtocol {}
+
+impl EliasDeltaIntDecodingProtocol {
+    /// Constructs a new instance of *EliasDeltaIntDecodingProtocol*. Note that
+    /// this is actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        EliasDeltaIntDecodingProtocol {}
+    }
+
+    fn read_unsigned(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        // The gamma code contains the number of bits minus 1
+        let num_remaining_bits = read_elias_gamma(source)?;
+        if num_remaining_bits > 128 {
+            return Err(DecodeError::IntOverflow);
+        }
+        read_elias_remainder(source, num_remaining_bits as u32)
+    }
+
+    fn read_signed(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        Ok(zigzag_decode(self.read_unsigned(source)?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EliasDeltaIntDecodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntDecodingProtocol for EliasDeltaIntDecodingProtocol {
+    fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        self.read_unsigned(source)
+    }
+
+    fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        self.read_signed(source)
+    }
+}
+
+// These structs don't have their own unit tests, but rely on the unit tests of
+// *EliasGammaIntEncodingProtocol* and *EliasDeltaIntEncodingProtocol* to
+// improve code reuse in the unit tests.
@@ -1,10 +1,29 @@
 use crate::*;
 
+mod adaptive;
+mod arithmetic_digit;
+mod cached;
 mod digit;
+mod elias;
 mod simple;
+mod varint;
+mod vlq;
+mod zeta;
 
+pub use adaptive::*;
+pub use arithmetic_digit::*;
+pub use cached::*;
 pub use digit::*;
+pub use elias::*;
 pub use simple::*;
+pub use varint::*;
+pub use vlq::*;
+pub use zeta::*;
+
+/// The inverse of *zigzag_encode*
+pub(crate) fn zigzag_decode(value: u128) -> i128 {
+    (value >> 1) as i128 ^ -((value & 1) as i128)
+}
 
 /// A protocol for decoding primitive integers from a *BitSource*. Every
 /// implementation of this trait should have a corresponding *IntEncodingProtocol*
@@ -48,6 +67,15 @@ pub use simple::*;
 /// user may have given an invalid encoding, which is indicated by a
 /// *DecodeError*. If you are reading from user input, you should catch these
 /// kind of errors rather than unwrapping like in the example.
+///
+/// # Robustness
+/// The implementations of this trait in this crate will never panic or allocate
+/// an unreasonable amount of memory, regardless of the bits in the *BitSource*:
+/// invalid or malicious input will always result in a *DecodeError*. This is
+/// verified by the fuzz targets in the *fuzz* directory of this repository (run
+/// them with `cargo fuzz run digit_decoding`). When the *arbitrary* feature is
+/// enabled, the protocol configurations implement *arbitrary::Arbitrary* so that
+/// fuzz targets can pick random configurations as well.
 pub trait IntDecodingProtocol {
     /// Decodes a u8 value from the bits coming from *source*
     fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError>;
@@ -78,74 +106,288 @@ pub trait IntDecodingProtocol {
 
     /// Decodes an i128 value from the bits coming from *source*
     fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError>;
-}
 
-#[cfg(test)]
-pub(crate) mod testing {
+    /// Decodes a *Vec* that was encoded with *write_u8_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_u8_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let length = context.check_vec_len::<u8>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_u8(source)?);
+        }
+        Ok(values)
+    }
 
-    use crate::*;
+    /// Decodes a *Vec* that was encoded with *write_i8_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_i8_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i8>, DecodeError> {
+        let length = context.check_vec_len::<i8>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_i8(source)?);
+        }
+        Ok(values)
+    }
+
+    /// Decodes a *Vec* that was encoded with *write_u16_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_u16_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u16>, DecodeError> {
+        let length = context.check_vec_len::<u16>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_u16(source)?);
+        }
+        Ok(values)
+    }
 
-    fn test_result<R: Eq + std::fmt::Debug>(
-        mut action: impl FnMut(&mut BoolSliceBitSource) -> Result<R, DecodeError>,
-        value: R,
-        encoded: &str,
-    ) {
-        for character in encoded.chars() {
-            assert!(character == '0' || character == '1' || character == ' ');
+    /// Decodes a *Vec* that was encoded with *write_i16_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_i16_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i16>, DecodeError> {
+        let length = context.check_vec_len::<i16>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_i16(source)?);
         }
+        Ok(values)
+    }
 
-        let as_bools: Vec<bool> = encoded
-            .chars()
-            .filter(|c| *c != ' ')
-            .map(|c| if c == '0' { false } else { true })
-            .collect();
+    /// Decodes a *Vec* that was encoded with *write_u32_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_u32_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        let length = context.check_vec_len::<u32>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_u32(source)?);
+        }
+        Ok(values)
+    }
 
-        let mut source = BoolSliceBitSource::new(&as_bools);
+    /// Decodes a *Vec* that was encoded with *write_i32_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_i32_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i32>, DecodeError> {
+        let length = context.check_vec_len::<i32>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_i32(source)?);
+        }
+        Ok(values)
+    }
 
-        assert_eq!(value, action(&mut source).unwrap());
+    /// Decodes a *Vec* that was encoded with *write_u64_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_u64_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u64>, DecodeError> {
+        let length = context.check_vec_len::<u64>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_u64(source)?);
+        }
+        Ok(values)
     }
 
-    pub fn test_u8_result(decoder: &impl IntDecodingProtocol, value: u8, encoded: &str) {
-        test_result(|source| decoder.read_u8(source), value, encoded);
+    /// Decodes a *Vec* that was encoded with *write_i64_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_i64_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i64>, DecodeError> {
+        let length = context.check_vec_len::<i64>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_i64(source)?);
+        }
+        Ok(values)
     }
 
-    pub fn test_i8_result(decoder: &impl IntDecodingProtocol, value: i8, encoded: &str) {
-        test_result(|source| decoder.read_i8(source), value, encoded);
+    /// Decodes a *Vec* that was encoded with *write_u128_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_u128_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u128>, DecodeError> {
+        let length = context.check_vec_len::<u128>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_u128(source)?);
+        }
+        Ok(values)
     }
 
-    pub fn test_u16_result(decoder: &impl IntDecodingProtocol, value: u16, encoded: &str) {
-        test_result(|source| decoder.read_u16(source), value, encoded);
+    /// Decodes a *Vec* that was encoded with *write_i128_slice*. Its length is
+    /// checked against the limits of *context* before it is allocated.
+    fn read_i128_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i128>, DecodeError> {
+        let length = context.check_vec_len::<i128>(self.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        for _counter in 0..length {
+            values.push(self.read_i128(source)?);
+        }
+        Ok(values)
     }
 
-    pub fn test_i16_result(decoder: &impl IntDecodingProtocol, value: i16, encoded: &str) {
-        test_result(|source| decoder.read_i16(source), value, encoded);
+    /// Decodes a string that was encoded with *write_string*. Its length is
+    /// checked against the limits of *context* before it is allocated. Returns
+    /// *DecodeError::InvalidUtf8* if the decoded bytes are not valid UTF-8.
+    fn read_string(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<String, DecodeError> {
+        let bytes = read_string_bytes(self, source, context)?;
+        String::from_utf8(bytes).map_err(|error| DecodeError::InvalidUtf8 {
+            position: error.utf8_error().valid_up_to(),
+        })
     }
 
-    pub fn test_u32_result(decoder: &impl IntDecodingProtocol, value: u32, encoded: &str) {
-        test_result(|source| decoder.read_u32(source), value, encoded);
+    /// Decodes a string that was encoded with *write_string*, like
+    /// *read_string*, but replaces invalid UTF-8 sequences with the replacement
+    /// character U+FFFD instead of returning an error.
+    fn read_string_lossy(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<String, DecodeError> {
+        let bytes = read_string_bytes(self, source, context)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
     }
 
-    pub fn test_i32_result(decoder: &impl IntDecodingProtocol, value: i32, encoded: &str) {
-        test_result(|source| decoder.read_i32(source), value, encoded);
+    /// Decodes a *Vec* of bools that was encoded with *write_bools*. Its length
+    /// is checked against the limits of *context* before it is allocated.
+    fn read_bools(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<bool>, DecodeError> {
+        let length = context.check_vec_len::<bool>(self.read_u64(source)?)?;
+        let mut is_run_length_encoded = [false];
+        source.read(&mut is_run_length_encoded)?;
+
+        if is_run_length_encoded[0] {
+            let mut current_value = [false];
+            source.read(&mut current_value)?;
+            let mut bools = Vec::with_capacity(length);
+            while bools.len() < length {
+                let remaining = (length - bools.len()) as u64;
+                let run_length = self.read_u64(source)?;
+                if run_length >= remaining {
+                    return Err(DecodeError::Other(
+                        format!(
+                            "Run length {} exceeds the remaining {} bools",
+                            run_length as u128 + 1,
+                            remaining
+                        )
+                        .into(),
+                    ));
+                }
+                bools.resize(bools.len() + run_length as usize + 1, current_value[0]);
+                current_value[0] = !current_value[0];
+            }
+            Ok(bools)
+        } else {
+            let mut bools = vec![false; length];
+            source.read(&mut bools)?;
+            Ok(bools)
+        }
     }
 
-    // I should use these functions someday
-    #[allow(dead_code)]
-    pub fn test_u64_result(decoder: &impl IntDecodingProtocol, value: u64, encoded: &str) {
-        test_result(|source| decoder.read_u64(source), value, encoded);
+    /// Reads the bits of an encoded integer of type *int_type* from *source*
+    /// without computing its value. This can be used to skip fields that are not
+    /// needed. The default implementation simply decodes the value and discards
+    /// it, but implementations can override it with something cheaper.
+    fn skip(&self, source: &mut impl BitSource, int_type: IntType) -> Result<(), DecodeError> {
+        match int_type {
+            IntType::U8 => self.read_u8(source).map(|_| ()),
+            IntType::I8 => self.read_i8(source).map(|_| ()),
+            IntType::U16 => self.read_u16(source).map(|_| ()),
+            IntType::I16 => self.read_i16(source).map(|_| ()),
+            IntType::U32 => self.read_u32(source).map(|_| ()),
+            IntType::I32 => self.read_i32(source).map(|_| ()),
+            IntType::U64 => self.read_u64(source).map(|_| ()),
+            IntType::I64 => self.read_i64(source).map(|_| ()),
+            IntType::U128 => self.read_u128(source).map(|_| ()),
+            IntType::I128 => self.read_i128(source).map(|_| ()),
+        }
     }
-    #[allow(dead_code)]
-    pub fn test_i64_result(decoder: &impl IntDecodingProtocol, value: i64, encoded: &str) {
-        test_result(|source| decoder.read_i64(source), value, encoded);
+
+    /// Skips an encoded integer for each of the *int_types*, in order. This is
+    /// convenient to skip all fields in front of the field you are interested in.
+    fn skip_all(&self, source: &mut impl BitSource, int_types: &[IntType]) -> Result<(), DecodeError> {
+        for int_type in int_types {
+            self.skip(source, *int_type)?;
+        }
+        Ok(())
     }
 
-    #[allow(dead_code)]
-    pub fn test_u128_result(decoder: &impl IntDecodingProtocol, value: u128, encoded: &str) {
-        test_result(|source| decoder.read_u128(source), value, encoded);
+    /// Calls *read* with this protocol and *source*, and returns its result
+    /// together with the number of bits that it read from *source*:
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let encoder = DigitIntEncodingProtocol::v1();
+    /// let decoder = DigitIntDecodingProtocol::v1();
+    /// let mut sink = BoolVecBitSink::new();
+    /// encoder.write_u32(&mut sink, 12345).unwrap();
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let (value, num_bits) = decoder
+    ///     .read_counted(&mut source, |decoder, source| decoder.read_u32(source))
+    ///     .unwrap();
+    /// assert_eq!(12345, value);
+    /// assert_eq!(sink.get_num_bools(), num_bits);
+    /// ```
+    fn read_counted<S: BitSource, T>(
+        &self,
+        source: &mut S,
+        read: impl FnOnce(&Self, &mut CountingBitSource<S>) -> Result<T, DecodeError>,
+    ) -> Result<(T, u64), DecodeError> {
+        let mut counting_source = CountingBitSource::new(source);
+        let value = read(self, &mut counting_source)?;
+        Ok((value, counting_source.get_num_bits()))
     }
+}
 
-    #[allow(dead_code)]
-    pub fn test_i128_result(decoder: &impl IntDecodingProtocol, value: i128, encoded: &str) {
-        test_result(|source| decoder.read_i128(source), value, encoded);
+fn read_string_bytes<P: IntDecodingProtocol + ?Sized>(
+    protocol: &P,
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+) -> Result<Vec<u8>, DecodeError> {
+    let length = context.check_string_len(protocol.read_u64(source)?)?;
+    let mut bytes = Vec::with_capacity(length);
+    for _counter in 0..length {
+        bytes.push(protocol.read_u8(source)?);
     }
+    Ok(bytes)
 }
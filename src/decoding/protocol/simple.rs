@@ -9,6 +9,7 @@ use crate::*;
 /// integers) are much more common than the other integers.
 ///
 /// The corresponding encoding protocol is *SimpleIntEncodingProtocol*.
+#[derive(Debug, Clone, Copy)]
 pub struct SimpleIntDecodingProtocol {}
 
 impl SimpleIntDecodingProtocol {
@@ -50,6 +51,45 @@ impl SimpleIntDecodingProtocol {
             Ok(unsigned as i128)
         }
     }
+
+    /// Reads a length (checked against *context*) followed by that many values
+    /// of *num_bits* bits each. The bits of many values are read at once.
+    fn read_bulk<T>(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+        num_bits: usize,
+        convert: impl Fn(u128) -> T,
+    ) -> Result<Vec<T>, DecodeError> {
+        let length = self.read_unsigned(source, 64)? as LengthType;
+        let length = context.check_vec_len::<T>(length)?;
+        let mut values = Vec::with_capacity(length);
+
+        let values_per_read = BULK_BUFFER_SIZE / num_bits;
+        let mut buffer = vec![false; values_per_read * num_bits];
+        while values.len() < length {
+            let num_values = values_per_read.min(length - values.len());
+            let bits = &mut buffer[0..num_values * num_bits];
+            source.read(bits)?;
+            for value_bits in bits.chunks(num_bits) {
+                let mut value = 0;
+                for (index, bit) in value_bits.iter().enumerate() {
+                    if *bit {
+                        value |= 1 << index;
+                    }
+                }
+                values.push(convert(value));
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SimpleIntDecodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
 }
 
 impl IntDecodingProtocol for SimpleIntDecodingProtocol {
@@ -92,6 +132,92 @@ impl IntDecodingProtocol for SimpleIntDecodingProtocol {
     fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
         self.read_signed(source, 128).map(|x| x as i128)
     }
+
+    fn read_u8_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u8>, DecodeError> {
+        self.read_bulk(source, context, 8, |value| value as u8)
+    }
+
+    fn read_i8_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i8>, DecodeError> {
+        self.read_bulk(source, context, 8, |value| value as i8)
+    }
+
+    fn read_u16_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u16>, DecodeError> {
+        self.read_bulk(source, context, 16, |value| value as u16)
+    }
+
+    fn read_i16_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i16>, DecodeError> {
+        self.read_bulk(source, context, 16, |value| value as i16)
+    }
+
+    fn read_u32_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        self.read_bulk(source, context, 32, |value| value as u32)
+    }
+
+    fn read_i32_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i32>, DecodeError> {
+        self.read_bulk(source, context, 32, |value| value as i32)
+    }
+
+    fn read_u64_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u64>, DecodeError> {
+        self.read_bulk(source, context, 64, |value| value as u64)
+    }
+
+    fn read_i64_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i64>, DecodeError> {
+        self.read_bulk(source, context, 64, |value| value as i64)
+    }
+
+    fn read_u128_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u128>, DecodeError> {
+        self.read_bulk(source, context, 128, |value| value)
+    }
+
+    fn read_i128_vec(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i128>, DecodeError> {
+        self.read_bulk(source, context, 128, |value| value as i128)
+    }
+
+    fn skip(&self, source: &mut impl BitSource, int_type: IntType) -> Result<(), DecodeError> {
+        let mut bits = [false; 128];
+        source.read(&mut bits[0..int_type.num_bits() as usize])?;
+        Ok(())
+    }
 }
 
 // This implementation doesn't have its own unit tests, but is instead tested
@@ -0,0 +1,104 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// The *IntDecodingProtocol* that decodes the LEB128 varints written by
+/// *VarIntEncodingProtocol*. See the documentation of *VarIntEncodingProtocol*
+/// for more information.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VarIntDecodingProtocol {}
+
+impl VarIntDecodingProtocol {
+    /// Constructs a new instance of *VarIntDecodingProtocol*. Note that this is
+    /// actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        VarIntDecodingProtocol {}
+    }
+
+    fn read_unsigned(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        let mut value: u128 = 0;
+        let mut byte = [false; 8];
+        for byte_index in 0..MAX_NUM_VARINT_BYTES {
+            source.read(&mut byte)?;
+            let mut group: u128 = 0;
+            for (bit_index, bit) in byte[0..7].iter().enumerate() {
+                if *bit {
+                    group |= 1 << bit_index;
+                }
+            }
+            let shift = 7 * byte_index as u32;
+            if group.leading_zeros() < shift {
+                return Err(DecodeError::IntOverflow);
+            }
+            value |= group << shift;
+            if !byte[7] {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::IntOverflow)
+    }
+
+    fn read_signed(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        Ok(zigzag_decode(self.read_unsigned(source)?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VarIntDecodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntDecodingProtocol for VarIntDecodingProtocol {
+    fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        self.read_unsigned(source)
+    }
+
+    fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        self.read_signed(source)
+    }
+}
+
+// This struct doesn't have its own unit tests, but relies on the unit tests of
+// *VarIntEncodingProtocol* to improve code reuse in the unit tests.
@@ -0,0 +1,126 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// The *IntDecodingProtocol* that decodes the zeta codes written by
+/// *ZetaIntEncodingProtocol*. See the documentation of
+/// *ZetaIntEncodingProtocol* for more information.
+#[derive(Debug, Clone, Copy)]
+pub struct ZetaIntDecodingProtocol {
+    k: u8,
+}
+
+impl ZetaIntDecodingProtocol {
+    /// Constructs a new instance of *ZetaIntDecodingProtocol* with shrinking
+    /// factor *k*, which must be the same as the shrinking factor of the
+    /// encoder.
+    ///
+    /// Panics if *k* is 0 or larger than 64.
+    pub const fn new(k: u8) -> Self {
+        if k < 1 || k > 64 {
+            panic!("Invalid shrinking factor");
+        }
+        ZetaIntDecodingProtocol { k }
+    }
+
+    /// Gets the shrinking factor *k* of this protocol
+    pub fn get_k(&self) -> u8 {
+        self.k
+    }
+
+    fn read_unsigned(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        let max_interval = get_zeta_max_interval(self.k);
+        let mut interval = 0;
+        let mut bit = [false];
+        loop {
+            source.read(&mut bit)?;
+            if bit[0] {
+                break;
+            }
+            if interval == max_interval {
+                return Err(DecodeError::IntOverflow);
+            }
+            interval += 1;
+        }
+
+        let interval_size = get_zeta_interval_size(self.k, interval);
+        let (num_bits, num_short_values) = get_minimal_binary_parameters(interval_size);
+        let mut offset = 0;
+        if num_bits > 0 {
+            let mut bits = vec![false; num_bits as usize - 1];
+            source.read(&mut bits)?;
+            for bit in &bits {
+                offset = (offset << 1) | *bit as u128;
+            }
+            if offset >= num_short_values {
+                source.read(&mut bit)?;
+                offset = ((offset << 1) | bit[0] as u128) - num_short_values;
+            }
+        }
+        Ok(get_zeta_interval_start(self.k, interval) + offset)
+    }
+
+    fn read_signed(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        Ok(zigzag_decode(self.read_unsigned(source)?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ZetaIntDecodingProtocol {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.int_in_range(1..=64)?))
+    }
+}
+
+impl IntDecodingProtocol for ZetaIntDecodingProtocol {
+    fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i8::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i16::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i32::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        self.read_unsigned(source)
+            .and_then(|x| u64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        self.read_signed(source)
+            .and_then(|x| i64::try_from(x).map_err(|_| DecodeError::IntOverflow))
+    }
+
+    fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        self.read_unsigned(source)
+    }
+
+    fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        self.read_signed(source)
+    }
+}
+
+// This struct doesn't have its own unit tests, but relies on the unit tests of
+// *ZetaIntEncodingProtocol* to improve code reuse in the unit tests.
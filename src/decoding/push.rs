@@ -0,0 +1,256 @@
+use crate::*;
+
+/// A decoder for data that arrives in pieces, for instance from a non-blocking
+/// socket. Instead of reading from a *BitSource* that blocks until enough data
+/// is available, the data is pushed into this decoder (using *push_bytes* or
+/// *push_bits*) whenever it arrives, after which *poll* can be used to try to
+/// decode the next value.
+///
+/// When the buffered data ends in the middle of a value, *poll* returns
+/// *Ok(None)* and keeps the buffered data, so that it can try again after more
+/// data has been pushed. The value is decoded from the start every time, so the
+/// decode function doesn't need to keep any state, but it should not have side
+/// effects that must only happen once.
+///
+/// Decoding from the start does have a cost: *poll* won't try again before more
+/// bits are buffered than the last attempt read, but a value that arrives in *n*
+/// pieces can still be decoded up to *n* times. When the size of the next value
+/// is known in advance (for instance from a length prefix that was polled
+/// separately), *wait_for_bits* can be used to decode it only once.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = U8VecBitSink::new();
+/// for value in &[5, 123456, 7] {
+///     encoder.write_u32(&mut sink, *value).unwrap();
+/// }
+/// let bytes = sink.get_bytes();
+///
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let mut push_decoder = PushDecoder::new();
+/// let mut values = Vec::new();
+/// for byte in bytes {
+///     // Pretend that the bytes arrive 1 by 1
+///     push_decoder.push_bytes(&[*byte]);
+///     while let Some(value) = push_decoder.poll(|source| decoder.read_u32(source)).unwrap() {
+///         values.push(value);
+///     }
+/// }
+/// assert_eq!(vec![5, 123456, 7], values);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PushDecoder {
+    bits: Vec<bool>,
+    position: usize,
+    min_bits: usize,
+}
+
+impl PushDecoder {
+    /// Constructs a new *PushDecoder* without any buffered data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn compact(&mut self) {
+        // Only move the remaining bits when that is cheap compared to the
+        // number of bits that were consumed
+        if self.position > 0 && self.position >= self.bits.len() / 2 {
+            self.bits.drain(0..self.position);
+            self.position = 0;
+        }
+    }
+
+    /// Appends the bits of *bytes* to the buffered data, in the order of
+    /// *bytes_to_bools* (so in the order in which a *U8VecBitSink* wrote them).
+    /// Note that this includes the padding bits of the last byte that the
+    /// sender wrote, so this is only suitable for continuous streams of bits.
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.compact();
+        self.bits.reserve(bytes.len() * 8);
+        for byte in bytes {
+            for bit_index in 0..8 {
+                self.bits.push(byte & (1 << bit_index) != 0);
+            }
+        }
+    }
+
+    /// Appends *bits* to the buffered data
+    pub fn push_bits(&mut self, bits: &[bool]) {
+        self.compact();
+        self.bits.extend_from_slice(bits);
+    }
+
+    /// Gets the number of bits that have been pushed, but not consumed by a
+    /// successful *poll* yet
+    pub fn get_num_buffered_bits(&self) -> usize {
+        self.bits.len() - self.position
+    }
+
+    /// Makes *poll* return *Ok(None)* without calling its decode function until
+    /// at least *num_bits* bits are buffered. This is reset after the next value
+    /// has been decoded.
+    pub fn wait_for_bits(&mut self, num_bits: usize) {
+        self.min_bits = usize::max(self.min_bits, num_bits);
+    }
+
+    /// Tries to decode the next value from the buffered data, using *decode*.
+    ///
+    /// - When *decode* succeeds, the bits that it read are consumed, and the
+    ///   value is returned.
+    /// - When *decode* fails because it reached the end of the buffered data,
+    ///   nothing is consumed, and *Ok(None)* is returned. The next call to *poll*
+    ///   will only try again once more bits are buffered than *decode* read.
+    /// - When less bits are buffered than *wait_for_bits* or the last failed
+    ///   attempt requires, *decode* is not called, and *Ok(None)* is returned.
+    /// - When *decode* fails because of any other error, nothing is consumed,
+    ///   and the error is returned.
+    pub fn poll<T>(
+        &mut self,
+        decode: impl FnOnce(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        if self.get_num_buffered_bits() < self.min_bits {
+            return Ok(None);
+        }
+        let mut source = BoolSliceBitSource::new(&self.bits[self.position..]);
+        match decode(&mut source) {
+            Ok(value) => {
+                self.position += source.position() as usize;
+                self.min_bits = 0;
+                Ok(Some(value))
+            }
+            Err(DecodeError::Reading(ReadError::ReachedEnd { .. })) => {
+                self.min_bits = source.position() as usize + 1;
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_split_values() {
+        let encoder = DigitIntEncodingProtocol::new(2, false);
+        let decoder = DigitIntDecodingProtocol::new(2, false);
+        let values: Vec<u64> = (0..200).map(|index| index * index * index).collect();
+
+        let mut sink = BoolVecBitSink::new();
+        for value in &values {
+            encoder.write_u64(&mut sink, *value).unwrap();
+        }
+        let bits = sink.get_bits();
+
+        for &chunk_size in &[1, 3, 8, 100, bits.len()] {
+            let mut push_decoder = PushDecoder::new();
+            let mut decoded = Vec::new();
+            for chunk in bits.chunks(chunk_size) {
+                push_decoder.push_bits(chunk);
+                while let Some(value) = push_decoder
+                    .poll(|source| decoder.read_u64(source))
+                    .unwrap()
+                {
+                    decoded.push(value);
+                }
+            }
+            assert_eq!(values, decoded);
+            assert_eq!(0, push_decoder.get_num_buffered_bits());
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let encoder = SimpleIntEncodingProtocol::new();
+        let decoder = SimpleIntDecodingProtocol::new();
+        let mut sink = U8VecBitSink::new();
+        for index in 0..10u8 {
+            encoder.write_u8_slice(&mut sink, &[index; 5]).unwrap();
+        }
+        let bytes = sink.get_bytes();
+
+        let mut push_decoder = PushDecoder::new();
+        let mut records = Vec::new();
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        for chunk in bytes.chunks(7) {
+            push_decoder.push_bytes(chunk);
+            while let Some(record) = push_decoder
+                .poll(|source| decoder.read_u8_vec(source, &mut context))
+                .unwrap()
+            {
+                records.push(record);
+            }
+        }
+        let expected: Vec<Vec<u8>> = (0..10).map(|index| vec![index; 5]).collect();
+        assert_eq!(expected, records);
+    }
+
+    #[test]
+    fn test_retries() {
+        let encoder = SimpleIntEncodingProtocol::new();
+        let decoder = SimpleIntDecodingProtocol::new();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, 1000).unwrap();
+        encoder.write_u8_slice(&mut sink, &[7; 100]).unwrap();
+        let bits = sink.get_bits();
+
+        // Polling again without new bits shouldn't decode again
+        let mut push_decoder = PushDecoder::new();
+        let mut num_attempts = 0;
+        push_decoder.push_bits(&bits[0..20]);
+        for _ in 0..5 {
+            let result = push_decoder.poll(|source| {
+                num_attempts += 1;
+                decoder.read_u32(source)
+            });
+            assert!(result.unwrap().is_none());
+        }
+        assert_eq!(1, num_attempts);
+        push_decoder.push_bits(&bits[20..32]);
+        assert_eq!(
+            Some(1000),
+            push_decoder
+                .poll(|source| decoder.read_u32(source))
+                .unwrap()
+        );
+
+        // The length prefix tells how many bits are needed for the bytes
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut num_attempts = 0;
+        push_decoder.wait_for_bits(64 + 8 * 100);
+        for chunk in bits[32..].chunks(8) {
+            push_decoder.push_bits(chunk);
+            let result = push_decoder.poll(|source| {
+                num_attempts += 1;
+                decoder.read_u8_vec(source, &mut context)
+            });
+            if let Some(bytes) = result.unwrap() {
+                assert_eq!(vec![7; 100], bytes);
+            }
+        }
+        assert_eq!(1, num_attempts);
+        assert_eq!(0, push_decoder.get_num_buffered_bits());
+    }
+
+    #[test]
+    fn test_error() {
+        // Digit 1 (encoded as 10) repeated: 1 + 3 + 9 + 27 + 81 + 243 > 255
+        let bits: Vec<bool> = (0..24).map(|index| index % 2 == 0).collect();
+        let decoder = DigitIntDecodingProtocol::new(2, false);
+        let mut push_decoder = PushDecoder::new();
+        push_decoder.push_bits(&bits[0..3]);
+        assert!(push_decoder
+            .poll(|source| decoder.read_u8(source))
+            .unwrap()
+            .is_none());
+
+        push_decoder.push_bits(&bits[3..]);
+        assert!(push_decoder.poll(|source| decoder.read_u8(source)).is_err());
+        assert_eq!(24, push_decoder.get_num_buffered_bits());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::*;
+
+use std::marker::PhantomData;
+
+/// An *Iterator* that keeps decoding records of the same type from a
+/// *BitSource* until the source runs out of bits. Every record is decoded by
+/// the *read* function that was given to the constructor.
+///
+/// The iterator distinguishes the clean end of the stream from truncation:
+/// - when the source reaches its end before the first bit of a record, the
+///   stream ended cleanly, and the iterator returns *None*
+/// - when the source reaches its end in the middle of a record, the record was
+///   truncated, and the iterator returns the *DecodeError::Reading* error
+///
+/// After the first error, the iterator will only return *None*.
+///
+/// Note that sources that are backed by bytes without knowing the number of
+/// padding bits (like *IoBitSource*) will encounter the padding bits at the
+/// end, which would be reported as truncation (or decoded as garbage).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = BoolVecBitSink::new();
+/// for value in 0..10 {
+///     encoder.write_u32(&mut sink, value * 100).unwrap();
+/// }
+///
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut expected = 0;
+/// for value in RecordIter::new(source, |source| decoder.read_u32(source)) {
+///     assert_eq!(expected, value.unwrap());
+///     expected += 100;
+/// }
+/// assert_eq!(1000, expected);
+///
+/// // When the last value is truncated, the iterator returns an error
+/// let truncated = &sink.get_bits()[0..sink.get_bits().len() - 1];
+/// let values: Vec<_> = RecordIter::new(
+///     BoolSliceBitSource::new(truncated), |source| decoder.read_u32(source)
+/// ).collect();
+/// assert_eq!(10, values.len());
+/// assert!(values[9].is_err());
+/// ```
+pub struct RecordIter<T, S, F>
+where
+    S: BitSource,
+    F: FnMut(&mut CountingBitSource<S>) -> Result<T, DecodeError>,
+{
+    source: S,
+    read: F,
+    num_records: u64,
+    finished: bool,
+    value_type: PhantomData<T>,
+}
+
+impl<T, S, F> RecordIter<T, S, F>
+where
+    S: BitSource,
+    F: FnMut(&mut CountingBitSource<S>) -> Result<T, DecodeError>,
+{
+    /// Constructs a new *RecordIter* that uses *read* to decode each record
+    /// from *source*
+    pub fn new(source: S, read: F) -> Self {
+        Self {
+            source,
+            read,
+            num_records: 0,
+            finished: false,
+            value_type: PhantomData,
+        }
+    }
+
+    /// Gets the number of records that have been decoded successfully
+    pub fn get_num_records(&self) -> u64 {
+        self.num_records
+    }
+
+    /// Gets the source back, for instance to read data after the records
+    pub fn into_source(self) -> S {
+        self.source
+    }
+}
+
+impl<T, S, F> Iterator for RecordIter<T, S, F>
+where
+    S: BitSource,
+    F: FnMut(&mut CountingBitSource<S>) -> Result<T, DecodeError>,
+{
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let mut counting_source = CountingBitSource::new(&mut self.source);
+        let result = (self.read)(&mut counting_source);
+        match result {
+            Ok(record) => {
+                self.num_records += 1;
+                Some(Ok(record))
+            }
+            Err(DecodeError::Reading(ReadError::ReachedEnd { .. }))
+                if counting_source.get_num_bits() == 0 =>
+            {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_clean_end() {
+        let bits: Vec<bool> = (0..40).map(|index| index % 3 == 0).collect();
+        let decoder = SimpleIntDecodingProtocol::new();
+        let mut iter = RecordIter::new(BoolSliceBitSource::new(&bits), |source| {
+            decoder.read_u8(source)
+        });
+        for _counter in 0..5 {
+            assert!(iter.next().unwrap().is_ok());
+        }
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+        assert_eq!(5, iter.get_num_records());
+
+        let empty = RecordIter::new(BoolSliceBitSource::new(&[]), |source| {
+            decoder.read_u8(source)
+        });
+        assert_eq!(0, empty.count());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bits = [true; 20];
+        let decoder = SimpleIntDecodingProtocol::new();
+        let mut iter = RecordIter::new(BoolSliceBitSource::new(&bits), |source| {
+            decoder.read_u8(source)
+        });
+        assert_eq!(255, iter.next().unwrap().unwrap());
+        assert_eq!(255, iter.next().unwrap().unwrap());
+        match iter.next() {
+            Some(Err(DecodeError::Reading(ReadError::ReachedEnd { read_bools }))) => {
+                assert_eq!(4, read_bools)
+            }
+            other => panic!("Expected ReachedEnd, but got {:?}", other),
+        }
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_into_source() {
+        let bits = [false, true, true];
+        let mut iter = RecordIter::new(BoolSliceBitSource::new(&bits), |source| {
+            let mut bit = [false];
+            source.read(&mut bit)?;
+            if bit[0] {
+                Ok(bit[0])
+            } else {
+                Err(DecodeError::Other("Stop".into()))
+            }
+        });
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+        assert_eq!(0, iter.get_num_records());
+
+        let mut source = iter.into_source();
+        let mut rest = [false; 2];
+        source.read(&mut rest).unwrap();
+        assert_eq!([true, true], rest);
+    }
+}
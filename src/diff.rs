@@ -0,0 +1,252 @@
+use crate::*;
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+const DIFF_CHUNK_SIZE: usize = 4096;
+
+/// The first difference between 2 bit streams, as found by *diff_bits*. It
+/// contains a window of bits from both streams around the first difference,
+/// which can be printed (in binary and hexadecimal) with its *Display*
+/// implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitDiff {
+    /// The offset of the first bit that differs. If one of the streams is a
+    /// prefix of the other stream, this is the length of the shortest stream.
+    pub offset: u64,
+    /// The offset of the first bit in the window. This is always a multiple of
+    /// 8, so that the window starts at a byte boundary.
+    pub window_start: u64,
+    /// The bits of the left stream in the window
+    pub left: Vec<bool>,
+    /// The bits of the right stream in the window. Its length can be different
+    /// from the length of *left* when one of the streams ended in the window.
+    pub right: Vec<bool>,
+}
+
+/// Reads until *dest* is full or *source* ended, and returns the number of bits
+/// that were read
+fn read_chunk(source: &mut impl BitSource, dest: &mut [bool]) -> Result<usize, ReadError> {
+    match source.read(dest) {
+        Ok(()) => Ok(dest.len()),
+        Err(ReadError::ReachedEnd { read_bools }) => Ok(read_bools),
+        Err(error) => Err(error),
+    }
+}
+
+/// Reads bits from *source* and appends them to *dest* until *dest* has
+/// *length* bits or *source* ended
+fn read_until(
+    source: &mut impl BitSource,
+    dest: &mut Vec<bool>,
+    length: usize,
+) -> Result<(), ReadError> {
+    let mut buffer = vec![false; length.saturating_sub(dest.len())];
+    let num_bits = read_chunk(source, &mut buffer)?;
+    dest.extend_from_slice(&buffer[0..num_bits]);
+    Ok(())
+}
+
+/// Compares the bits of *left* and *right* until it finds the first difference,
+/// and returns it (or *None* if both sources contain exactly the same bits). The
+/// returned *BitDiff* contains about *window_size* bits before and after the
+/// difference. This is useful to find out where the output of 2 versions (or
+/// implementations) of an encoder starts to differ.
+///
+/// Both sources will be read until the first difference (plus the window), or
+/// until they both end. Only errors other than *ReadError::ReachedEnd* will be
+/// returned.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let left = [0b0000_1111, 0b1010_1010];
+/// let right = [0b0000_1111, 0b1010_1011];
+/// let diff = diff_bits(
+///     &mut U8SliceBitSource::new(&left, 0),
+///     &mut U8SliceBitSource::new(&right, 0),
+///     8
+/// ).unwrap().unwrap();
+///
+/// assert_eq!(8, diff.offset);
+/// assert_eq!(0, diff.window_start);
+/// println!("{}", diff);
+/// ```
+pub fn diff_bits(
+    left: &mut impl BitSource,
+    right: &mut impl BitSource,
+    window_size: usize,
+) -> Result<Option<BitDiff>, ReadError> {
+    let mut history = VecDeque::with_capacity(window_size + 8);
+    let mut left_buffer = vec![false; DIFF_CHUNK_SIZE];
+    let mut right_buffer = vec![false; DIFF_CHUNK_SIZE];
+    let mut offset = 0;
+
+    loop {
+        let left_length = read_chunk(left, &mut left_buffer)?;
+        let right_length = read_chunk(right, &mut right_buffer)?;
+        let common_length = left_length.min(right_length);
+
+        let first_difference = (0..common_length)
+            .find(|index| left_buffer[*index] != right_buffer[*index])
+            .or(if left_length != right_length {
+                Some(common_length)
+            } else {
+                None
+            });
+
+        if let Some(index) = first_difference {
+            let diff_offset = offset + index as u64;
+
+            // The window should start at a byte boundary
+            let num_before = (window_size as u64).min(diff_offset);
+            let window_start = (diff_offset - num_before) / 8 * 8;
+            let num_before = (diff_offset - window_start) as usize;
+
+            let mut before: Vec<bool> = history.iter().copied().collect();
+            before.extend_from_slice(&left_buffer[0..index]);
+            let before = &before[before.len() - num_before..];
+
+            let mut left_window = before.to_vec();
+            left_window.extend_from_slice(&left_buffer[index..left_length]);
+            let mut right_window = before.to_vec();
+            right_window.extend_from_slice(&right_buffer[index..right_length]);
+
+            let window_end = num_before + window_size;
+            if left_window.len() < window_end && left_length == DIFF_CHUNK_SIZE {
+                read_until(left, &mut left_window, window_end)?;
+            }
+            if right_window.len() < window_end && right_length == DIFF_CHUNK_SIZE {
+                read_until(right, &mut right_window, window_end)?;
+            }
+            left_window.truncate(window_end);
+            right_window.truncate(window_end);
+
+            return Ok(Some(BitDiff {
+                offset: diff_offset,
+                window_start,
+                left: left_window,
+                right: right_window,
+            }));
+        }
+
+        if left_length == 0 {
+            return Ok(None);
+        }
+
+        history.extend(&left_buffer[0..left_length]);
+        while history.len() > window_size + 8 {
+            history.pop_front();
+        }
+        offset += left_length as u64;
+    }
+}
+
+fn format_binary(bits: &[bool]) -> String {
+    let mut result = String::with_capacity(bits.len() + bits.len() / 8);
+    for (index, bit) in bits.iter().enumerate() {
+        if index > 0 && index % 8 == 0 {
+            result.push(' ');
+        }
+        result.push(if *bit { '1' } else { '0' });
+    }
+    result
+}
+
+fn format_hex(bits: &[bool]) -> String {
+    let hex: Vec<String> = bools_to_bytes(bits)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    hex.join(" ")
+}
+
+impl Display for BitDiff {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let relative_offset = (self.offset - self.window_start) as usize;
+        writeln!(
+            f,
+            "First difference at bit {} (byte {}, bit {} within that byte)",
+            self.offset,
+            self.offset / 8,
+            self.offset % 8
+        )?;
+        writeln!(
+            f,
+            "Showing bits {}.. (starting at byte {}), the first bit of each byte is on the left",
+            self.window_start,
+            self.window_start / 8
+        )?;
+        writeln!(f, "left:  {}", format_binary(&self.left))?;
+        writeln!(f, "right: {}", format_binary(&self.right))?;
+        writeln!(
+            f,
+            "       {}^",
+            " ".repeat(relative_offset + relative_offset / 8)
+        )?;
+        writeln!(f, "left hex:  {}", format_hex(&self.left))?;
+        write!(f, "right hex: {}", format_hex(&self.right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_equal() {
+        let bits = vec![true; 10_000];
+        let result = diff_bits(
+            &mut BoolSliceBitSource::new(&bits),
+            &mut BoolSliceBitSource::new(&bits),
+            16,
+        )
+        .unwrap();
+        assert_eq!(None, result);
+    }
+
+    #[test]
+    fn test_difference() {
+        let left = vec![false; 10_000];
+        let mut right = left.clone();
+        right[5000] = true;
+
+        let diff = diff_bits(
+            &mut BoolSliceBitSource::new(&left),
+            &mut BoolSliceBitSource::new(&right),
+            16,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(5000, diff.offset);
+        assert_eq!(4984, diff.window_start);
+        assert_eq!(&left[4984..5016], &diff.left[..]);
+        assert_eq!(&right[4984..5016], &diff.right[..]);
+
+        let text = diff.to_string();
+        assert!(text.contains("First difference at bit 5000 (byte 625, bit 0"));
+        assert!(text.contains("right: 00000000 00000000 10000000 00000000"));
+        assert!(text.contains(&format!("\n{}^\n", " ".repeat(7 + 16 + 2))));
+        assert!(text.contains("right hex: 00 00 01 00"));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let left = vec![true; 4100];
+        let right = vec![true; 4096];
+
+        let diff = diff_bits(
+            &mut BoolSliceBitSource::new(&left),
+            &mut BoolSliceBitSource::new(&right),
+            3,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(4096, diff.offset);
+        assert_eq!(4088, diff.window_start);
+        assert_eq!(11, diff.left.len());
+        assert_eq!(8, diff.right.len());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::*;
+
+/// The error that a *DiscriminantCodec* returns (wrapped in a
+/// *DecodeError::Other*) when it reads a discriminant that is reserved for a
+/// future variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownVariant {
+    /// The discriminant that was read
+    pub discriminant: u32,
+    /// The number of variants that the decoder knows
+    pub num_variants: u32,
+}
+
+impl std::fmt::Display for UnknownVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Unknown variant {}: only {} variants are known",
+            self.discriminant, self.num_variants
+        )
+    }
+}
+
+impl std::error::Error for UnknownVariant {}
+
+/// Encodes enum discriminants (the index of the variant) using the minimal
+/// number of bits: *ceil(log2(num_variants + num_reserved))*. The
+/// *num_reserved* discriminants after the known variants are reserved for
+/// variants that might be added in the future. As long as the total number of
+/// variants stays within the reserved headroom, adding a variant doesn't change
+/// the number of bits, so old data can still be read by new decoders. When an
+/// old decoder encounters a new variant, it returns an *UnknownVariant* error.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // 5 variants with room for 3 more fit in 3 bits
+/// let codec = DiscriminantCodec::new(5, 3);
+/// assert_eq!(3, codec.get_num_bits());
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, 4).unwrap();
+///
+/// // A newer version of the protocol added a variant
+/// let new_codec = DiscriminantCodec::new(6, 2);
+/// assert_eq!(3, new_codec.get_num_bits());
+/// new_codec.write(&mut sink, 5).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(4, codec.read(&mut source).unwrap());
+/// assert!(codec.read(&mut source).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscriminantCodec {
+    num_variants: u32,
+    num_reserved: u32,
+    num_bits: u8,
+}
+
+impl DiscriminantCodec {
+    /// Constructs a new *DiscriminantCodec* for an enum with *num_variants*
+    /// variants, which reserves room for *num_reserved* more variants.
+    ///
+    /// Panics if *num_variants* is 0.
+    pub const fn new(num_variants: u32, num_reserved: u32) -> Self {
+        if num_variants == 0 {
+            panic!("An enum must have at least 1 variant");
+        }
+        let total = num_variants as u64 + num_reserved as u64;
+        let num_bits = (64 - (total - 1).leading_zeros()) as u8;
+        Self {
+            num_variants,
+            num_reserved,
+            num_bits,
+        }
+    }
+
+    /// Gets the number of known variants
+    pub fn get_num_variants(&self) -> u32 {
+        self.num_variants
+    }
+
+    /// Gets the number of discriminants that are reserved for future variants
+    pub fn get_num_reserved(&self) -> u32 {
+        self.num_reserved
+    }
+
+    /// Gets the number of bits that are used to encode each discriminant. This
+    /// is 0 when there is only 1 variant and nothing is reserved.
+    pub fn get_num_bits(&self) -> u8 {
+        self.num_bits
+    }
+
+    /// Writes *discriminant* to *sink*. Returns an error if *discriminant* is
+    /// not smaller than the number of variants.
+    pub fn write(&self, sink: &mut impl BitSink, discriminant: u32) -> Result<(), WriteError> {
+        if discriminant >= self.num_variants {
+            return Err(format!(
+                "Discriminant {} is too large for {} variants",
+                discriminant, self.num_variants
+            )
+            .into());
+        }
+        SimpleIntEncodingProtocol::new().write_unsigned(
+            sink,
+            self.num_bits as usize,
+            discriminant as u128,
+        )
+    }
+
+    /// Reads a discriminant that was written by *write* from *source*. Returns
+    /// an *UnknownVariant* error (wrapped in a *DecodeError::Other*) when the
+    /// discriminant is not smaller than the number of known variants.
+    pub fn read(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        let discriminant =
+            SimpleIntDecodingProtocol::new().read_unsigned(source, self.num_bits as usize)?;
+        if discriminant >= self.num_variants as u128 {
+            return Err(DecodeError::Other(Box::new(UnknownVariant {
+                discriminant: discriminant as u32,
+                num_variants: self.num_variants,
+            })));
+        }
+        Ok(discriminant as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_num_bits() {
+        assert_eq!(0, DiscriminantCodec::new(1, 0).get_num_bits());
+        assert_eq!(1, DiscriminantCodec::new(1, 1).get_num_bits());
+        assert_eq!(1, DiscriminantCodec::new(2, 0).get_num_bits());
+        assert_eq!(2, DiscriminantCodec::new(3, 0).get_num_bits());
+        assert_eq!(2, DiscriminantCodec::new(2, 2).get_num_bits());
+        assert_eq!(3, DiscriminantCodec::new(4, 1).get_num_bits());
+        assert_eq!(8, DiscriminantCodec::new(200, 56).get_num_bits());
+        assert_eq!(9, DiscriminantCodec::new(200, 57).get_num_bits());
+        assert_eq!(32, DiscriminantCodec::new(u32::MAX, 1).get_num_bits());
+        assert_eq!(33, DiscriminantCodec::new(u32::MAX, 2).get_num_bits());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(num_variants, num_reserved) in &[(1, 0), (3, 0), (3, 5), (17, 100)] {
+            let codec = DiscriminantCodec::new(num_variants, num_reserved);
+            let mut sink = BoolVecBitSink::new();
+            for discriminant in 0..num_variants {
+                codec.write(&mut sink, discriminant).unwrap();
+            }
+            assert!(codec.write(&mut sink, num_variants).is_err());
+            assert_eq!(
+                num_variants as u64 * codec.get_num_bits() as u64,
+                sink.get_num_bools()
+            );
+
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            for discriminant in 0..num_variants {
+                assert_eq!(discriminant, codec.read(&mut source).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_variant() {
+        let mut sink = BoolVecBitSink::new();
+        DiscriminantCodec::new(7, 1).write(&mut sink, 6).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match DiscriminantCodec::new(4, 4).read(&mut source) {
+            Err(DecodeError::Other(error)) => assert_eq!(
+                &UnknownVariant {
+                    discriminant: 6,
+                    num_variants: 4
+                },
+                error.downcast_ref::<UnknownVariant>().unwrap()
+            ),
+            _ => panic!("Expected UnknownVariant"),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Adapters for the *Read* and *Write* traits of the *embedded-io* crates, which
+//! are implemented by the serial ports, sockets, and flash drivers of many
+//! microcontroller HALs. This module is only available when the *embedded-io*
+//! feature is enabled, and the async adapters additionally require the
+//! *embedded-io-async* feature.
+//!
+//! * *EmbeddedIo* wraps a blocking *embedded_io::Read* and/or
+//!   *embedded_io::Write* and implements the corresponding *std::io* trait, so
+//!   that it can be used by *IoBitSource* and *IoBitSink*.
+//! * *AsyncEmbeddedBitSink* is a *BitSink* that buffers the encoded bytes until
+//!   they are written to an *embedded_io_async::Write* using *flush*.
+//! * *AsyncEmbeddedBitSource* is a *BitSource* that reads from the bytes that
+//!   were read from an *embedded_io_async::Read* using *fill*.
+//!
+//! Since *BitSink* and *BitSource* are blocking traits, the async adapters can't
+//! await the underlying IO while encoding or decoding. Instead, the IO happens
+//! in the async *flush* and *fill* methods, in between encoding or decoding.
+//!
+//! # Example
+//! ```
+//! use bit_encoding::*;
+//!
+//! // &mut [u8] implements embedded_io::Write, just like a UART would
+//! let mut buffer = [0u8; 4];
+//! let mut sink = IoBitSink::new(EmbeddedIo::new(&mut buffer[..]));
+//! DigitIntEncodingProtocol::v1().write_u16(&mut sink, 1234).unwrap();
+//! sink.finish().unwrap();
+//! drop(sink);
+//!
+//! let mut source = IoBitSource::new(EmbeddedIo::new(&buffer[..]));
+//! assert_eq!(1234, DigitIntDecodingProtocol::v1().read_u16(&mut source).unwrap());
+//! ```
+
+#[cfg(feature = "embedded-io-async")]
+use crate::*;
+
+/// Converts an *embedded-io* error to a *std::io::Error* with the same kind
+fn to_io_error(error: impl embedded_io::Error) -> std::io::Error {
+    std::io::Error::new(error.kind().into(), format!("{:?}", error))
+}
+
+/// Wraps a blocking *embedded_io::Read* and/or *embedded_io::Write*, and
+/// implements *std::io::Read* and/or *std::io::Write* by forwarding to it.
+/// This makes it possible to use *IoBitSource* and *IoBitSink* with the
+/// peripherals of embedded HALs.
+///
+/// Errors of the wrapped reader or writer are converted to *std::io::Error*s
+/// with the same *ErrorKind*.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedIo<T> {
+    inner: T,
+}
+
+impl<T> EmbeddedIo<T> {
+    /// Wraps *inner*
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Gets a reference to the wrapped reader or writer
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the wrapped reader or writer
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes this adapter and returns the wrapped reader or writer
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: embedded_io::Read> std::io::Read for EmbeddedIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf).map_err(to_io_error)
+    }
+}
+
+impl<T: embedded_io::Write> std::io::Write for EmbeddedIo<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf).map_err(to_io_error)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush().map_err(to_io_error)
+    }
+}
+
+/// A *BitSink* that packs the bools written to it into bytes (in the same way as
+/// *U8VecBitSink*) and buffers these bytes until *flush* writes them to an
+/// *embedded_io_async::Write*. Like *IoBitSink*, the last (partial) byte is only
+/// added to the buffer when *finish* is called.
+///
+/// This sink is only available when the *embedded-io-async* feature is enabled.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// async fn send(writer: impl embedded_io_async::Write) -> Result<(), WriteError> {
+///     let mut sink = AsyncEmbeddedBitSink::new(writer);
+///     let encoder = DigitIntEncodingProtocol::v1();
+///     for value in 0..100 {
+///         encoder.write_u32(&mut sink, value)?;
+///         // Send the complete bytes whenever enough have been buffered
+///         if sink.get_num_buffered_bytes() >= 32 {
+///             sink.flush().await?;
+///         }
+///     }
+///     sink.finish()?;
+///     sink.flush().await
+/// }
+/// ```
+#[cfg(feature = "embedded-io-async")]
+pub struct AsyncEmbeddedBitSink<W: embedded_io_async::Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    current_byte: u8,
+    bit_index: u8,
+    num_bits: u64,
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W: embedded_io_async::Write> AsyncEmbeddedBitSink<W> {
+    /// Constructs a new *AsyncEmbeddedBitSink* that writes its bytes to *writer*
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+            current_byte: 0,
+            bit_index: 0,
+            num_bits: 0,
+        }
+    }
+
+    /// Gets the number of complete bytes that have not been written to the
+    /// writer yet
+    pub fn get_num_buffered_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Writes all buffered bytes to the writer, and flushes the writer. Note
+    /// that a partial last byte will not be written until *finish* is called.
+    pub async fn flush(&mut self) -> Result<(), WriteError> {
+        let result = self.writer.write_all(&self.buffer).await;
+        self.buffer.clear();
+        result.map_err(to_io_error)?;
+        self.writer.flush().await.map_err(to_io_error)?;
+        Ok(())
+    }
+
+    /// Consumes this sink and returns its writer. Buffered bytes will be lost
+    /// if *flush* wasn't called.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W: embedded_io_async::Write> BitSink for AsyncEmbeddedBitSink<W> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        for bit in bits {
+            if *bit {
+                self.current_byte |= 1 << self.bit_index;
+            }
+            self.bit_index += 1;
+            if self.bit_index == 8 {
+                self.buffer.push(self.current_byte);
+                self.current_byte = 0;
+                self.bit_index = 0;
+            }
+        }
+        self.num_bits += bits.len() as u64;
+        Ok(())
+    }
+
+    /// Adds the partial last byte (if any) to the buffer. Note that this
+    /// doesn't write anything to the writer: *flush* must still be awaited.
+    fn finish(&mut self) -> Result<(), WriteError> {
+        if self.bit_index != 0 {
+            self.buffer.push(self.current_byte);
+            self.current_byte = 0;
+            self.bit_index = 0;
+        }
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bits
+    }
+}
+
+/// A *BitSource* that reads the bits of the bytes that were read from an
+/// *embedded_io_async::Read* by *fill*, in the same order as *U8SliceBitSource*.
+/// When the bytes that were filled run out, *read* returns
+/// *ReadError::ReachedEnd*, even when the reader has more data. Like
+/// *IoBitSource*, this source can't know how many padding bits are in the last
+/// byte.
+///
+/// This source is only available when the *embedded-io-async* feature is
+/// enabled.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// async fn receive(reader: impl embedded_io_async::Read) -> Result<u32, DecodeError> {
+///     let mut source = AsyncEmbeddedBitSource::new(reader);
+///     // The encoded value is known to fit in 8 bytes
+///     source.fill(8).await?;
+///     DigitIntDecodingProtocol::v1().read_u32(&mut source)
+/// }
+/// ```
+#[cfg(feature = "embedded-io-async")]
+pub struct AsyncEmbeddedBitSource<R: embedded_io_async::Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    bit_position: usize,
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read> AsyncEmbeddedBitSource<R> {
+    /// Constructs a new *AsyncEmbeddedBitSource* that reads its bytes from
+    /// *reader*
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+            bit_position: 0,
+        }
+    }
+
+    /// Gets the number of bits that were filled, but not read yet
+    pub fn get_num_buffered_bits(&self) -> usize {
+        self.buffer.len() * 8 - self.bit_position
+    }
+
+    /// Reads bytes from the reader until at least *num_bytes* bytes are
+    /// buffered (including the partially read byte), or until the reader
+    /// reaches its end. Returns the number of buffered bytes, which is only
+    /// smaller than *num_bytes* when the reader reached its end.
+    pub async fn fill(&mut self, num_bytes: usize) -> Result<usize, ReadError> {
+        let consumed_bytes = self.bit_position / 8;
+        self.buffer.drain(0..consumed_bytes);
+        self.bit_position -= consumed_bytes * 8;
+
+        while self.buffer.len() < num_bytes {
+            let old_length = self.buffer.len();
+            self.buffer.resize(num_bytes, 0);
+            let result = self.reader.read(&mut self.buffer[old_length..]).await;
+            let length = match result {
+                Ok(length) => length,
+                Err(error) => {
+                    self.buffer.truncate(old_length);
+                    return Err(ReadError::Other(Box::new(to_io_error(error))));
+                }
+            };
+            self.buffer.truncate(old_length + length);
+            if length == 0 {
+                break;
+            }
+        }
+        Ok(self.buffer.len())
+    }
+
+    /// Consumes this source and returns its reader. Bytes that were filled,
+    /// but not (completely) read from this source, will be lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read> BitSource for AsyncEmbeddedBitSource<R> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        for (index, dest_bit) in dest.iter_mut().enumerate() {
+            if self.bit_position == self.buffer.len() * 8 {
+                return Err(ReadError::ReachedEnd { read_bools: index });
+            }
+            let byte = self.buffer[self.bit_position / 8];
+            *dest_bit = byte & (1 << (self.bit_position % 8)) != 0;
+            self.bit_position += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_blocking() {
+        let mut buffer = [0u8; 100];
+        let mut sink = IoBitSink::new(EmbeddedIo::new(&mut buffer[..]));
+        let encoder = DigitIntEncodingProtocol::v1();
+        for value in 0..50 {
+            encoder.write_u32(&mut sink, value * value).unwrap();
+        }
+        sink.finish().unwrap();
+        let num_bits = sink.get_num_bools();
+        drop(sink);
+
+        let mut source = IoBitSource::new(EmbeddedIo::new(&buffer[..]));
+        let decoder = DigitIntDecodingProtocol::v1();
+        for value in 0..50 {
+            assert_eq!(value * value, decoder.read_u32(&mut source).unwrap());
+        }
+
+        // The slice writer reports an error when it is full
+        let mut small = [0u8; 2];
+        let mut sink = IoBitSink::new(EmbeddedIo::new(&mut small[..]));
+        assert!(sink.write(&vec![true; num_bits as usize]).is_err());
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+        // The slice readers and vec writers are always ready, so polling once
+        // is enough
+        let mut future = Box::pin(future);
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            std::task::Poll::Ready(result) => result,
+            std::task::Poll::Pending => panic!("Future is not ready"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-io-async")]
+    fn test_async_sink() {
+        let mut sink = AsyncEmbeddedBitSink::new(Vec::new());
+        let mut vec_sink = U8VecBitSink::new();
+        for counter in 0..100 {
+            let bits: Vec<bool> = (0..counter % 13).map(|index| index % 3 == 0).collect();
+            sink.write(&bits).unwrap();
+            vec_sink.write(&bits).unwrap();
+            if counter % 10 == 0 {
+                block_on(sink.flush()).unwrap();
+                assert_eq!(0, sink.get_num_buffered_bytes());
+            }
+        }
+        assert_eq!(vec_sink.get_num_bools(), sink.get_num_bools());
+        sink.finish().unwrap();
+        block_on(sink.flush()).unwrap();
+        assert_eq!(vec_sink.get_bytes(), &sink.into_inner());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-io-async")]
+    fn test_async_source() {
+        let bytes: Vec<u8> = (0..100).map(|index| (index * 7) as u8).collect();
+        let expected = bytes_to_bools(&bytes);
+        let mut source = AsyncEmbeddedBitSource::new(&bytes[..]);
+
+        let mut actual = vec![false; expected.len()];
+        for chunk in actual.chunks_mut(12) {
+            match source.read(chunk) {
+                Err(ReadError::ReachedEnd { read_bools }) => {
+                    block_on(source.fill(2)).unwrap();
+                    source.read(&mut chunk[read_bools..]).unwrap();
+                }
+                result => result.unwrap(),
+            }
+        }
+        assert_eq!(expected, actual);
+        assert_eq!(0, source.get_num_buffered_bits());
+
+        assert_eq!(0, block_on(source.fill(5)).unwrap());
+        match source.read(&mut [false]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(0, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::*;
+
+/// The digit sizes that the adaptive digit protocol can switch between
+pub(crate) const ADAPTIVE_DIGIT_SIZES: [u8; 8] = [2, 3, 4, 5, 6, 7, 8, 10];
+
+/// The number of bits that are needed to encode an index into
+/// *ADAPTIVE_DIGIT_SIZES*
+pub(crate) const ADAPTIVE_DIGIT_SIZE_BITS: usize = 3;
+
+/// The digit size that the adaptive digit protocol uses for the first block
+pub(crate) const ADAPTIVE_INITIAL_DIGIT_SIZE_INDEX: usize = 1;
+
+/// A variant of *DigitIntEncodingProtocol* that monitors the sizes of the values
+/// that it encodes, and switches to a different *digit_size* when that would
+/// have encoded the recent values more compactly. This works well for data with
+/// phases of small values and phases of large values, which no single digit
+/// size handles well.
+///
+/// The values are split into blocks of *block_size* values. At the end of each
+/// block, the encoder computes how many bits each of the supported digit sizes
+/// (2, 3, 4, 5, 6, 7, 8 and 10) would have needed to encode that block, and
+/// picks the best one for the next block. The choice is signalled in-band at
+/// the start of every block (except the first one): 1 bit tells whether the
+/// digit size changes, and if so, it is followed by 3 bits that identify the
+/// new digit size. The first block always uses digit size 3.
+///
+/// Unlike the other protocols, this encoder has state, so it needs a mutable
+/// reference and it doesn't implement *IntEncodingProtocol*. Its values must be
+/// decoded (in the same order) by an *AdaptiveDigitDecoder* with the same
+/// *block_size* and *short_zero_and_one*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut encoder = AdaptiveDigitEncoder::new(8, false);
+/// let mut sink = BoolVecBitSink::new();
+/// for value in 0..100 {
+///     encoder.write_u64(&mut sink, value % 3).unwrap();
+/// }
+/// // Digit size 2 is the best choice for such small values
+/// assert_eq!(2, encoder.get_digit_size());
+///
+/// let mut decoder = AdaptiveDigitDecoder::new(8, false);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// for value in 0..100 {
+///     assert_eq!(value % 3, decoder.read_u64(&mut source).unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveDigitEncoder {
+    block_size: u32,
+    protocols: [DigitIntEncodingProtocol; 8],
+    current_index: usize,
+    next_index: Option<usize>,
+    block_costs: [u64; 8],
+    num_values_in_block: u32,
+}
+
+impl AdaptiveDigitEncoder {
+    /// Constructs a new *AdaptiveDigitEncoder* that reconsiders its digit size
+    /// after every *block_size* values. See *DigitIntEncodingProtocol::new* for
+    /// the meaning of *short_zero_and_one*.
+    ///
+    /// Panics if *block_size* is 0.
+    pub fn new(block_size: u32, short_zero_and_one: bool) -> Self {
+        if block_size == 0 {
+            panic!("The block size must be positive");
+        }
+        let mut protocols = [DigitIntEncodingProtocol::v1(); 8];
+        for (protocol, digit_size) in protocols.iter_mut().zip(ADAPTIVE_DIGIT_SIZES.iter()) {
+            *protocol = DigitIntEncodingProtocol::new(*digit_size, short_zero_and_one);
+        }
+        Self {
+            block_size,
+            protocols,
+            current_index: ADAPTIVE_INITIAL_DIGIT_SIZE_INDEX,
+            next_index: None,
+            block_costs: [0; 8],
+            num_values_in_block: 0,
+        }
+    }
+
+    /// Gets the number of values per block
+    pub fn get_block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Gets the digit size that is used for the current block
+    pub fn get_digit_size(&self) -> u8 {
+        ADAPTIVE_DIGIT_SIZES[self.next_index.unwrap_or(self.current_index)]
+    }
+
+    fn start_value(&mut self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        if let Some(next_index) = self.next_index {
+            if next_index == self.current_index {
+                sink.write(&[false])?;
+            } else {
+                sink.write(&[true])?;
+                SimpleIntEncodingProtocol::new().write_unsigned(
+                    sink,
+                    ADAPTIVE_DIGIT_SIZE_BITS,
+                    next_index as u128,
+                )?;
+            }
+            self.current_index = next_index;
+            self.next_index = None;
+        }
+        Ok(())
+    }
+
+    fn finish_value(&mut self, cost: impl Fn(&DigitIntEncodingProtocol) -> u32) {
+        for (block_cost, protocol) in self.block_costs.iter_mut().zip(self.protocols.iter()) {
+            *block_cost += cost(protocol) as u64;
+        }
+        self.num_values_in_block += 1;
+
+        if self.num_values_in_block == self.block_size {
+            let mut best_index = self.current_index;
+            for (index, block_cost) in self.block_costs.iter().enumerate() {
+                // Switching costs extra bits, so only switch for a real gain
+                let switch_cost = ADAPTIVE_DIGIT_SIZE_BITS as u64;
+                if *block_cost + switch_cost < self.block_costs[best_index] {
+                    best_index = index;
+                }
+            }
+            self.next_index = Some(best_index);
+            self.block_costs = [0; 8];
+            self.num_values_in_block = 0;
+        }
+    }
+
+    /// Encodes *value* using the digit size of the current block, and writes it
+    /// to *sink* (preceded by the digit size signal when a new block starts)
+    pub fn write_u64(&mut self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.start_value(sink)?;
+        self.protocols[self.current_index].write_u64(sink, value)?;
+        self.finish_value(|protocol| protocol.encoded_size_u64(value));
+        Ok(())
+    }
+
+    /// Encodes *value* using the digit size of the current block, and writes it
+    /// to *sink* (preceded by the digit size signal when a new block starts)
+    pub fn write_i64(&mut self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.start_value(sink)?;
+        self.protocols[self.current_index].write_i64(sink, value)?;
+        self.finish_value(|protocol| protocol.encoded_size_i64(value));
+        Ok(())
+    }
+}
+
+/// Writes the state of an adaptive digit encoder or decoder: the index of the
+/// digit size of the current block, the number of values that have been
+/// encoded in the current block, and whether the next value will be preceded
+/// by a digit size signal
+pub(crate) fn write_adaptive_digit_state(
+    sink: &mut impl BitSink,
+    current_index: usize,
+    num_values_in_block: u32,
+    signal_pending: bool,
+) -> Result<(), WriteError> {
+    SimpleIntEncodingProtocol::new().write_unsigned(
+        sink,
+        ADAPTIVE_DIGIT_SIZE_BITS,
+        current_index as u128,
+    )?;
+    DigitIntEncodingProtocol::v1().write_u32(sink, num_values_in_block)?;
+    sink.write(&[signal_pending])
+}
+
+/// The state consists of the digit size of the current block and the position
+/// in the current block. Note that the block costs are not part of the state,
+/// so an encoder that reads a state in the middle of a block will choose the
+/// next digit size based on only the remaining values of the block.
+impl SessionCodec for AdaptiveDigitEncoder {
+    fn reset_state(&mut self) {
+        self.current_index = ADAPTIVE_INITIAL_DIGIT_SIZE_INDEX;
+        self.next_index = None;
+        self.block_costs = [0; 8];
+        self.num_values_in_block = 0;
+    }
+
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        write_adaptive_digit_state(
+            sink,
+            self.current_index,
+            self.num_values_in_block,
+            self.next_index.is_some(),
+        )
+    }
+
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        let (current_index, num_values_in_block, signal_pending) =
+            read_adaptive_digit_state(source, self.block_size)?;
+        self.current_index = current_index;
+        self.next_index = if signal_pending {
+            Some(current_index)
+        } else {
+            None
+        };
+        self.block_costs = [0; 8];
+        self.num_values_in_block = num_values_in_block;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn encode_phases(block_size: u32, short_zero_and_one: bool) -> (Vec<i64>, BoolVecBitSink) {
+        let mut values = Vec::new();
+        for phase in 0..6 {
+            for index in 0..100i64 {
+                values.push(match phase % 3 {
+                    0 => index % 3,
+                    1 => -index * 100_000,
+                    _ => index * 1_000_000_000,
+                });
+            }
+        }
+
+        let mut encoder = AdaptiveDigitEncoder::new(block_size, short_zero_and_one);
+        let mut sink = BoolVecBitSink::new();
+        for value in &values {
+            encoder.write_i64(&mut sink, *value).unwrap();
+        }
+        (values, sink)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &block_size in &[1, 2, 7, 16, 1000] {
+            for &short_zero_and_one in &[false, true] {
+                let (values, sink) = encode_phases(block_size, short_zero_and_one);
+                let mut decoder = AdaptiveDigitDecoder::new(block_size, short_zero_and_one);
+                let mut source = BoolSliceBitSource::new(sink.get_bits());
+                for value in values {
+                    assert_eq!(value, decoder.read_i64(&mut source).unwrap());
+                }
+                assert!(source.read(&mut [false]).is_err());
+            }
+        }
+
+        let mut encoder = AdaptiveDigitEncoder::new(3, true);
+        let mut sink = BoolVecBitSink::new();
+        for value in 0..20 {
+            encoder.write_u64(&mut sink, value << (value * 3)).unwrap();
+        }
+        let mut decoder = AdaptiveDigitDecoder::new(3, true);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for value in 0..20 {
+            assert_eq!(value << (value * 3), decoder.read_u64(&mut source).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_beats_static_digit_sizes() {
+        let (values, sink) = encode_phases(16, false);
+        for digit_size in 2..=10 {
+            let protocol = DigitIntEncodingProtocol::new(digit_size, false);
+            let static_size: u64 = values
+                .iter()
+                .map(|value| protocol.encoded_size_i64(*value) as u64)
+                .sum();
+            assert!(
+                sink.get_num_bools() < static_size,
+                "Digit size {} needs only {} bits, but the adaptive protocol needs {}",
+                digit_size,
+                static_size,
+                sink.get_num_bools()
+            );
+        }
+    }
+
+    #[test]
+    fn test_switching() {
+        let mut encoder = AdaptiveDigitEncoder::new(4, false);
+        let mut sink = BoolVecBitSink::new();
+        assert_eq!(3, encoder.get_digit_size());
+
+        // Digit size 2 needs 4 bits for 1, whereas digit size 3 needs 6 bits
+        for _ in 0..4 {
+            encoder.write_u64(&mut sink, 1).unwrap();
+        }
+        assert_eq!(2, encoder.get_digit_size());
+
+        // Digit size 2 needs 10 bits for 30, whereas digit size 3 needs 9 bits
+        for _ in 0..4 {
+            encoder.write_u64(&mut sink, 30).unwrap();
+        }
+        assert_eq!(3, encoder.get_digit_size());
+
+        // Saving 1 bit per block is not worth the cost of switching
+        for _ in 0..2 {
+            encoder.write_u64(&mut sink, 30).unwrap();
+            encoder.write_u64(&mut sink, 30).unwrap();
+            encoder.write_u64(&mut sink, 30).unwrap();
+            encoder.write_u64(&mut sink, 1).unwrap();
+        }
+        assert_eq!(3, encoder.get_digit_size());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::*;
+
+/// The number of bits of the state of the binary arithmetic coder
+pub(crate) const ARITHMETIC_PRECISION: usize = 32;
+pub(crate) const ARITHMETIC_HALF: u64 = 1 << (ARITHMETIC_PRECISION - 1);
+pub(crate) const ARITHMETIC_QUARTER: u64 = 1 << (ARITHMETIC_PRECISION - 2);
+pub(crate) const ARITHMETIC_MAX: u64 = (1 << ARITHMETIC_PRECISION) - 1;
+
+/// The maximum total count of a context of *ContinuationModel*. When this is
+/// exceeded, the counts are halved, so that the model keeps adapting.
+const MAX_TOTAL_COUNT: u32 = 1024;
+
+/// The adaptive probability model that the arithmetic digit encoder and
+/// decoder use to code whether another digit follows. Every digit index has
+/// its own context, since the probability that a value has more than 1 digit
+/// is usually very different from the probability that it has more than 5.
+#[derive(Debug, Clone)]
+pub(crate) struct ContinuationModel {
+    /// For each digit index: the number of times the value stopped after that
+    /// digit, and the number of times another digit followed (both start at 1)
+    counts: Vec<[u32; 2]>,
+}
+
+impl ContinuationModel {
+    pub(crate) fn new(num_contexts: usize) -> Self {
+        Self {
+            counts: vec![[1, 1]; num_contexts],
+        }
+    }
+
+    /// Gets the count of *false* (stop) and the total count of *context*
+    pub(crate) fn get_frequencies(&self, context: usize) -> (u64, u64) {
+        let [stop, more] = self.counts[context];
+        (stop as u64, (stop + more) as u64)
+    }
+
+    pub(crate) fn update(&mut self, context: usize, has_more: bool) {
+        let counts = &mut self.counts[context];
+        counts[has_more as usize] += 2;
+        if counts[0] + counts[1] > MAX_TOTAL_COUNT {
+            counts[0] = u32::max(1, counts[0] / 2);
+            counts[1] = u32::max(1, counts[1] / 2);
+        }
+    }
+}
+
+/// Gets the maximum number of digits of a *u64* with the given *digit_size*
+pub(crate) fn get_max_num_u64_digits(digit_size: u8) -> usize {
+    64usize.div_ceil(digit_size as usize)
+}
+
+/// A variant of *DigitIntEncodingProtocol* that doesn't spend a terminator
+/// digit to mark the end of each value. Instead, every digit uses all
+/// 2^*digit_size* values, and the decision "is there another digit?" is coded
+/// after each digit using an adaptive binary arithmetic coder. For digit sizes
+/// of 4 and larger, this removes most of the cost of the terminator: a
+/// terminator digit costs *digit_size* bits per value, whereas a well-predicted
+/// decision costs only a fraction of a bit per digit.
+///
+/// The digit bits go through the same arithmetic coder (with probability 1/2,
+/// so they still cost 1 bit each), which keeps everything in a single bit
+/// stream. The arithmetic coder only writes bits once they are certain, so the
+/// encoder must be finished with *finish* after the last value, which writes
+/// the remaining 32 bits of its state. After that, the stream can be followed
+/// by other data, and the encoder can be used to start a new stream.
+///
+/// Signed values are mapped to unsigned values using zigzag encoding (0, -1,
+/// 1, -2, 2, ...). Like *AdaptiveDigitEncoder*, this encoder has state, so it
+/// needs a mutable reference and it doesn't implement *IntEncodingProtocol*.
+/// Its values must be decoded (in the same order) by an
+/// *ArithmeticDigitDecoder* with the same *digit_size*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut encoder = ArithmeticDigitEncoder::new(4);
+/// let mut sink = BoolVecBitSink::new();
+/// for value in 0..1000 {
+///     encoder.write_u64(&mut sink, value % 10).unwrap();
+/// }
+/// encoder.finish(&mut sink).unwrap();
+/// // DigitIntEncodingProtocol::new(4, false) would need 8 bits for most values
+/// assert!(sink.get_num_bools() < 5000);
+///
+/// let mut decoder = ArithmeticDigitDecoder::new(4);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// for value in 0..1000 {
+///     assert_eq!(value % 10, decoder.read_u64(&mut source).unwrap());
+/// }
+/// decoder.finish(&mut source).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArithmeticDigitEncoder {
+    digit_size: u8,
+    model: ContinuationModel,
+    low: u64,
+    high: u64,
+    num_pending_bits: u64,
+    output: Vec<bool>,
+}
+
+impl ArithmeticDigitEncoder {
+    /// Constructs a new *ArithmeticDigitEncoder* that uses *digit_size* bits
+    /// per digit.
+    ///
+    /// Panics if *digit_size* is 0 or larger than 64.
+    pub fn new(digit_size: u8) -> Self {
+        if digit_size == 0 || digit_size > 64 {
+            panic!("Invalid digit size");
+        }
+        Self {
+            digit_size,
+            model: ContinuationModel::new(get_max_num_u64_digits(digit_size)),
+            low: 0,
+            high: ARITHMETIC_MAX,
+            num_pending_bits: 0,
+            output: Vec::new(),
+        }
+    }
+
+    /// Gets the number of bits per digit
+    pub fn get_digit_size(&self) -> u8 {
+        self.digit_size
+    }
+
+    fn output_bit(&mut self, bit: bool) {
+        self.output.push(bit);
+        for _ in 0..self.num_pending_bits {
+            self.output.push(!bit);
+        }
+        self.num_pending_bits = 0;
+    }
+
+    /// Codes *bit*, given that the probability that it's false is
+    /// *false_count / total_count*
+    fn encode_bit(&mut self, bit: bool, false_count: u64, total_count: u64) {
+        let range = self.high - self.low + 1;
+        let split = self.low + range * false_count / total_count - 1;
+        if bit {
+            self.low = split + 1;
+        } else {
+            self.high = split;
+        }
+
+        loop {
+            if self.high < ARITHMETIC_HALF {
+                self.output_bit(false);
+            } else if self.low >= ARITHMETIC_HALF {
+                self.output_bit(true);
+                self.low -= ARITHMETIC_HALF;
+                self.high -= ARITHMETIC_HALF;
+            } else if self.low >= ARITHMETIC_QUARTER && self.high < 3 * ARITHMETIC_QUARTER {
+                self.num_pending_bits += 1;
+                self.low -= ARITHMETIC_QUARTER;
+                self.high -= ARITHMETIC_QUARTER;
+            } else {
+                break;
+            }
+            self.low *= 2;
+            self.high = 2 * self.high + 1;
+        }
+    }
+
+    fn flush_output(&mut self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        let result = sink.write(&self.output);
+        self.output.clear();
+        result
+    }
+
+    /// Encodes *value*, and writes the bits that are certain to *sink*
+    pub fn write_u64(&mut self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        let digit_size = self.digit_size as u32;
+        let num_used_bits = 64 - value.leading_zeros();
+        let num_digits = usize::max(1, num_used_bits.div_ceil(digit_size) as usize);
+        let max_num_digits = get_max_num_u64_digits(self.digit_size);
+        for digit_index in 0..num_digits {
+            for bit_index in 0..digit_size {
+                let bit_index = digit_index as u32 * digit_size + bit_index;
+                let bit = bit_index < 64 && value & 1 << bit_index != 0;
+                self.encode_bit(bit, 1, 2);
+            }
+            if digit_index + 1 < max_num_digits {
+                let has_more = digit_index + 1 < num_digits;
+                let (stop_count, total_count) = self.model.get_frequencies(digit_index);
+                self.encode_bit(has_more, stop_count, total_count);
+                self.model.update(digit_index, has_more);
+            }
+        }
+        self.flush_output(sink)
+    }
+
+    /// Encodes *value* using zigzag encoding, and writes the bits that are
+    /// certain to *sink*
+    pub fn write_i64(&mut self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_u64(sink, zigzag_encode(value as i128) as u64)
+    }
+
+    /// Writes the remaining state of the arithmetic coder to *sink*. This must
+    /// be called after the last value, and the decoder must call its *finish*
+    /// at the same point. After this, the encoder starts a new stream, but the
+    /// adaptive probabilities are kept.
+    pub fn finish(&mut self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        self.num_pending_bits += 1;
+        self.output_bit(self.low >= ARITHMETIC_QUARTER);
+        // The decoder always reads the full state, so pad it
+        self.output
+            .extend_from_slice(&[false; ARITHMETIC_PRECISION - 2]);
+        self.low = 0;
+        self.high = ARITHMETIC_MAX;
+        self.flush_output(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(digit_size: u8, values: &[i64]) -> u64 {
+        let mut encoder = ArithmeticDigitEncoder::new(digit_size);
+        let mut sink = BoolVecBitSink::new();
+        for value in values {
+            encoder.write_i64(&mut sink, *value).unwrap();
+        }
+        encoder.finish(&mut sink).unwrap();
+        // The stream can be followed by other data
+        sink.write(&[true, false, true]).unwrap();
+
+        let mut decoder = ArithmeticDigitDecoder::new(digit_size);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for value in values {
+            assert_eq!(*value, decoder.read_i64(&mut source).unwrap());
+        }
+        decoder.finish(&mut source).unwrap();
+        let mut rest = [false; 3];
+        source.read(&mut rest).unwrap();
+        assert_eq!([true, false, true], rest);
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools() - 3
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mixed: Vec<i64> = (0..500)
+            .map(|index: i64| match index % 4 {
+                0 => index,
+                1 => -index * 1_000_003,
+                2 => i64::MAX - index,
+                _ => i64::MIN + index,
+            })
+            .collect();
+        for digit_size in &[1, 2, 3, 4, 5, 7, 8, 13, 32, 63, 64] {
+            round_trip(*digit_size, &[]);
+            round_trip(*digit_size, &[0]);
+            round_trip(*digit_size, &mixed);
+        }
+    }
+
+    #[test]
+    fn test_beats_terminator_digits() {
+        let values: Vec<i64> = (0..2000)
+            .map(|index: i64| (index * 7919) % 300 - 150)
+            .collect();
+        for digit_size in 4..=8 {
+            let protocol = DigitIntEncodingProtocol::new(digit_size, false);
+            let terminator_size: u64 = values
+                .iter()
+                .map(|value| protocol.encoded_size_i64(*value) as u64)
+                .sum();
+            let arithmetic_size = round_trip(digit_size, &values);
+            assert!(
+                arithmetic_size < terminator_size,
+                "Digit size {}: {} vs {}",
+                digit_size,
+                arithmetic_size,
+                terminator_size
+            );
+        }
+    }
+
+    #[test]
+    fn test_multiple_streams() {
+        let mut encoder = ArithmeticDigitEncoder::new(4);
+        let mut sink = BoolVecBitSink::new();
+        for stream in 0..3 {
+            for value in 0..100 {
+                encoder.write_u64(&mut sink, value * stream).unwrap();
+            }
+            encoder.finish(&mut sink).unwrap();
+        }
+
+        let mut decoder = ArithmeticDigitDecoder::new(4);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for stream in 0..3 {
+            for value in 0..100 {
+                assert_eq!(value * stream, decoder.read_u64(&mut source).unwrap());
+            }
+            decoder.finish(&mut source).unwrap();
+        }
+        assert!(source.read(&mut [false]).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+use crate::*;
+
+/// The bit patterns of a range of consecutive values, stored after each other
+#[derive(Debug, Clone)]
+struct EncodingTable {
+    first_value: i128,
+    bits: Vec<bool>,
+    ends: Vec<usize>,
+}
+
+impl EncodingTable {
+    fn new(
+        first_value: i128,
+        bound_value: i128,
+        write: impl Fn(&mut BoolVecBitSink, i128) -> Result<(), WriteError>,
+    ) -> Self {
+        let mut sink = BoolVecBitSink::new();
+        let mut ends = Vec::with_capacity((bound_value - first_value) as usize);
+        for value in first_value..bound_value {
+            write(&mut sink, value).expect("BoolVecBitSink shouldn't fail");
+            ends.push(sink.get_num_bools() as usize);
+        }
+        Self {
+            first_value,
+            bits: sink.get_bits().to_vec(),
+            ends,
+        }
+    }
+
+    fn get(&self, value: i128) -> Option<&[bool]> {
+        let index = value.checked_sub(self.first_value)?;
+        if !(0..self.ends.len() as i128).contains(&index) {
+            return None;
+        }
+        let index = index as usize;
+        let start = if index == 0 { 0 } else { self.ends[index - 1] };
+        Some(&self.bits[start..self.ends[index]])
+    }
+}
+
+/// The maximum *cache_size* of a *CachedDigitIntEncodingProtocol*
+pub const MAX_DIGIT_CACHE_SIZE: u32 = 1 << 16;
+
+/// A *DigitIntEncodingProtocol* that pre-computes the encodings of small values,
+/// so that writing such a value is just 1 *write* call with a cached slice of
+/// bits, instead of computing the digits of the value every time. This is
+/// useful when most values are small and encoding is a bottleneck.
+///
+/// The encodings of all *u8* and *i8* values are cached. For the other integer
+/// types, the unsigned values *0* to *cache_size - 1* and the signed values
+/// *-cache_size / 2* to *cache_size / 2 - 1* are cached. The encodings of all
+/// *u16* and *i16* values can be cached as well (using *with_full_16_bit_tables*),
+/// which is useful for workloads that encode mostly bytes and shorts. All other
+/// values are simply encoded by the wrapped protocol. The encoded bits
+/// are exactly the same as those of the wrapped protocol, so they can be decoded
+/// by the corresponding *DigitIntDecodingProtocol*.
+///
+/// The decoding counterpart is *CachedDigitIntDecodingProtocol*, which caches
+/// the same values, but needs a *SeekableBitSource* to look at the next bits
+/// before knowing how many bits the value has.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let digit_protocol = DigitIntEncodingProtocol::v1();
+/// let cached_protocol = CachedDigitIntEncodingProtocol::new(digit_protocol, 1024);
+///
+/// let mut cached_sink = BoolVecBitSink::new();
+/// let mut digit_sink = BoolVecBitSink::new();
+/// for value in &[0, 1, 10, 1023, 1024, 123456] {
+///     cached_protocol.write_u32(&mut cached_sink, *value).unwrap();
+///     digit_protocol.write_u32(&mut digit_sink, *value).unwrap();
+/// }
+/// assert_eq!(digit_sink.get_bits(), cached_sink.get_bits());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedDigitIntEncodingProtocol {
+    protocol: DigitIntEncodingProtocol,
+    cache_size: u32,
+    tables: Vec<EncodingTable>,
+}
+
+macro_rules! unsigned_table {
+    ($protocol:ident, $cache_size:ident, $t:ty, $write:ident) => {
+        EncodingTable::new(
+            0,
+            (<$t>::MAX as u128)
+                .saturating_add(1)
+                .min($cache_size as u128) as i128,
+            |sink, value| $protocol.$write(sink, value as $t),
+        )
+    };
+}
+
+macro_rules! full_table {
+    ($protocol:ident, $t:ty, $write:ident) => {
+        EncodingTable::new(<$t>::MIN as i128, <$t>::MAX as i128 + 1, |sink, value| {
+            $protocol.$write(sink, value as $t)
+        })
+    };
+}
+
+macro_rules! signed_table {
+    ($protocol:ident, $cache_size:ident, $t:ty, $write:ident) => {
+        EncodingTable::new(
+            i128::max(-($cache_size as i128 / 2), <$t>::MIN as i128),
+            (<$t>::MAX as i128)
+                .saturating_add(1)
+                .min($cache_size as i128 / 2),
+            |sink, value| $protocol.$write(sink, value as $t),
+        )
+    };
+}
+
+impl CachedDigitIntEncodingProtocol {
+    /// Constructs a new *CachedDigitIntEncodingProtocol* that encodes values
+    /// like *protocol*, and caches the encodings of the *cache_size* smallest
+    /// unsigned values and signed values (closest to 0).
+    ///
+    /// Panics if *cache_size* is larger than *MAX_DIGIT_CACHE_SIZE*.
+    pub fn new(protocol: DigitIntEncodingProtocol, cache_size: u32) -> Self {
+        if cache_size > MAX_DIGIT_CACHE_SIZE {
+            panic!("The cache size can be at most {}", MAX_DIGIT_CACHE_SIZE);
+        }
+        let tables = vec![
+            full_table!(protocol, u8, write_u8),
+            full_table!(protocol, i8, write_i8),
+            unsigned_table!(protocol, cache_size, u16, write_u16),
+            signed_table!(protocol, cache_size, i16, write_i16),
+            unsigned_table!(protocol, cache_size, u32, write_u32),
+            signed_table!(protocol, cache_size, i32, write_i32),
+            unsigned_table!(protocol, cache_size, u64, write_u64),
+            signed_table!(protocol, cache_size, i64, write_i64),
+            unsigned_table!(protocol, cache_size, u128, write_u128),
+            signed_table!(protocol, cache_size, i128, write_i128),
+        ];
+        Self {
+            protocol,
+            cache_size,
+            tables,
+        }
+    }
+
+    /// Caches the encodings of all *u16* and *i16* values (65536 of each),
+    /// rather than only the *cache_size* smallest ones
+    pub fn with_full_16_bit_tables(mut self) -> Self {
+        let protocol = self.protocol;
+        self.tables[2] = full_table!(protocol, u16, write_u16);
+        self.tables[3] = full_table!(protocol, i16, write_i16);
+        self
+    }
+
+    /// Checks whether the encodings of all *u16* and *i16* values are cached
+    pub fn has_full_16_bit_tables(&self) -> bool {
+        self.tables[2].ends.len() == 1 << 16
+    }
+
+    /// Gets the wrapped *DigitIntEncodingProtocol*
+    pub fn get_protocol(&self) -> DigitIntEncodingProtocol {
+        self.protocol
+    }
+
+    /// Gets the number of values whose encoding is cached (per integer type)
+    pub fn get_cache_size(&self) -> u32 {
+        self.cache_size
+    }
+}
+
+macro_rules! write_cached {
+    ($self:ident, $sink:ident, $value:ident, $table:expr, $write:ident) => {
+        match $self.tables[$table].get($value as i128) {
+            Some(bits) => $sink.write(bits),
+            None => $self.protocol.$write($sink, $value),
+        }
+    };
+}
+
+impl IntEncodingProtocol for CachedDigitIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 0, write_u8)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 1, write_i8)
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 2, write_u16)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 3, write_i16)
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 4, write_u32)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 5, write_i32)
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 6, write_u64)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 7, write_i64)
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        // Large u128 values would wrap around to negative i128 values
+        if value > i128::MAX as u128 {
+            return self.protocol.write_u128(sink, value);
+        }
+        write_cached!(self, sink, value, 8, write_u128)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        write_cached!(self, sink, value, 9, write_i128)
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        self.protocol.encoded_size_u8(value)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        self.protocol.encoded_size_i8(value)
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        self.protocol.encoded_size_u16(value)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        self.protocol.encoded_size_i16(value)
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        self.protocol.encoded_size_u32(value)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        self.protocol.encoded_size_i32(value)
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        self.protocol.encoded_size_u64(value)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        self.protocol.encoded_size_i64(value)
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        self.protocol.encoded_size_u128(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        self.protocol.encoded_size_i128(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_same_encoding() {
+        for &(digit_size, short_zero_and_one) in &[(2, false), (3, true), (8, false), (9, true)] {
+            let protocol = DigitIntEncodingProtocol::new(digit_size, short_zero_and_one);
+            let cached = CachedDigitIntEncodingProtocol::new(protocol, 600);
+            assert_eq!(600, cached.get_cache_size());
+
+            let mut expected = BoolVecBitSink::new();
+            let mut actual = BoolVecBitSink::new();
+            for value in -700i128..700 {
+                macro_rules! check {
+                    ($t:ty, $write:ident) => {
+                        if let Ok(value) = std::convert::TryInto::<$t>::try_into(value) {
+                            protocol.$write(&mut expected, value).unwrap();
+                            cached.$write(&mut actual, value).unwrap();
+                        }
+                    };
+                }
+                check!(u8, write_u8);
+                check!(i8, write_i8);
+                check!(u16, write_u16);
+                check!(i16, write_i16);
+                check!(u32, write_u32);
+                check!(i32, write_i32);
+                check!(u64, write_u64);
+                check!(i64, write_i64);
+                check!(u128, write_u128);
+                protocol.write_i128(&mut expected, value).unwrap();
+                cached.write_i128(&mut actual, value).unwrap();
+            }
+            for value in &[u128::MAX, i128::MAX as u128 + 1, i128::MAX as u128] {
+                protocol.write_u128(&mut expected, *value).unwrap();
+                cached.write_u128(&mut actual, *value).unwrap();
+            }
+            protocol.write_i128(&mut expected, i128::MIN).unwrap();
+            cached.write_i128(&mut actual, i128::MIN).unwrap();
+            assert_eq!(expected.get_bits(), actual.get_bits());
+        }
+    }
+
+    #[test]
+    fn test_full_tables() {
+        let protocol = DigitIntEncodingProtocol::new(4, true);
+        let cached = CachedDigitIntEncodingProtocol::new(protocol, 10);
+        assert!(!cached.has_full_16_bit_tables());
+        let cached = cached.with_full_16_bit_tables();
+        assert!(cached.has_full_16_bit_tables());
+
+        let mut expected = BoolVecBitSink::new();
+        let mut actual = BoolVecBitSink::new();
+        for value in 0..=u16::MAX {
+            protocol.write_u16(&mut expected, value).unwrap();
+            cached.write_u16(&mut actual, value).unwrap();
+            protocol.write_i16(&mut expected, value as i16).unwrap();
+            cached.write_i16(&mut actual, value as i16).unwrap();
+        }
+        for value in 0..=u8::MAX {
+            protocol.write_u8(&mut expected, value).unwrap();
+            cached.write_u8(&mut actual, value).unwrap();
+            protocol.write_i8(&mut expected, value as i8).unwrap();
+            cached.write_i8(&mut actual, value as i8).unwrap();
+        }
+        assert_eq!(expected.get_bits(), actual.get_bits());
+    }
+
+    #[test]
+    fn test_empty_cache() {
+        let protocol = DigitIntEncodingProtocol::v1();
+        let cached = CachedDigitIntEncodingProtocol::new(protocol, 0);
+        let mut sink = BoolVecBitSink::new();
+        cached.write_u32(&mut sink, 0).unwrap();
+        cached.write_i16(&mut sink, -1).unwrap();
+        assert_eq!(
+            protocol.encoded_size_u32(0) + protocol.encoded_size_i16(-1),
+            sink.get_num_bools() as u32
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_large_cache() {
+        CachedDigitIntEncodingProtocol::new(
+            DigitIntEncodingProtocol::v1(),
+            MAX_DIGIT_CACHE_SIZE + 1,
+        );
+    }
+}
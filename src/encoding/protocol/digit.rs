@@ -32,6 +32,21 @@ pub(crate) const fn compute_relevant_num_digits(digit_size: u8) -> [u8; 10] {
     result
 }
 
+/// How *DigitIntEncodingProtocol* and *DigitIntDecodingProtocol* encode signed
+/// integers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignedStrategy {
+    /// Write a sign bit, followed by the digits of the absolute value (minus
+    /// 1 for negative values). This is the default strategy.
+    SignBit,
+    /// Map the value to an unsigned integer using zigzag encoding (0, -1, 1,
+    /// -2, 2, ...), and encode that like an unsigned integer of the same size.
+    /// This saves the sign bit, so negative values are encoded as compactly as
+    /// positive values. When *short_zero_and_one* is used, 0 and -1 get the
+    /// short encodings.
+    Zigzag,
+}
+
 /// An *IntEncodingProtocol* based on writing integers digit by digit that uses a
 /// special digit value as terminator digit. This encoding protocol is suitable for
 /// encoding values that are often small.
@@ -94,9 +109,36 @@ pub(crate) const fn compute_relevant_num_digits(digit_size: u8) -> [u8; 10] {
 /// greater than 1 is increased by 1 bit. Because this is quite a waste if 0 and 1
 /// aren't used frequently, this behavior is configurable: it will only be used if
 /// *short_zero_and_one* is true.
+///
+/// By default, signed integers are encoded with a sign bit, which is wasteful
+/// when small negative values are as common as small positive values. Use
+/// *with_signed_strategy* to encode them with zigzag encoding instead (see
+/// *SignedStrategy*). The decoder must use the same strategy.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let sign_bit = DigitIntEncodingProtocol::new(3, false);
+/// let zigzag = sign_bit.with_signed_strategy(SignedStrategy::Zigzag);
+/// assert_eq!(SignedStrategy::SignBit, sign_bit.get_signed_strategy());
+///
+/// // -3 becomes 5 after zigzag encoding, which fits in 1 digit
+/// assert_eq!(7, sign_bit.encoded_size_i32(-3));
+/// assert_eq!(6, zigzag.encoded_size_i32(-3));
+///
+/// let mut sink = BoolVecBitSink::new();
+/// zigzag.write_i32(&mut sink, -3).unwrap();
+/// let decoder = DigitIntDecodingProtocol::new(3, false)
+///     .with_signed_strategy(SignedStrategy::Zigzag);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(-3, decoder.read_i32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy)]
 pub struct DigitIntEncodingProtocol {
     digit_size: u8,
     short_zero_and_one: bool,
+    signed_strategy: SignedStrategy,
 
     max_num_digits: [u8; 10],
 }
@@ -117,10 +159,23 @@ impl DigitIntEncodingProtocol {
         DigitIntEncodingProtocol {
             digit_size,
             short_zero_and_one,
+            signed_strategy: SignedStrategy::SignBit,
             max_num_digits: compute_relevant_num_digits(digit_size),
         }
     }
 
+    /// Returns a copy of this protocol that encodes signed integers using the
+    /// given *signed_strategy*. Note that *new* uses *SignedStrategy::SignBit*.
+    pub const fn with_signed_strategy(mut self, signed_strategy: SignedStrategy) -> Self {
+        self.signed_strategy = signed_strategy;
+        self
+    }
+
+    /// Gets the strategy that this protocol uses to encode signed integers
+    pub const fn get_signed_strategy(&self) -> SignedStrategy {
+        self.signed_strategy
+    }
+
     /// Constructs a new instance of *DigitIntEncodingProtocol* that uses the first
     /// configuration (constructor parameters) that I found to perform well. The
     /// *v1* function of *DigitIntDecodingProtocol* returns a corresponding
@@ -184,7 +239,11 @@ impl DigitIntEncodingProtocol {
         sink: &mut impl BitSink,
         mut value: i128,
         max_num_digits: u8,
+        max_num_unsigned_digits: u8,
     ) -> Result<(), WriteError> {
+        if self.signed_strategy == SignedStrategy::Zigzag {
+            return self.write_unsigned(sink, zigzag_encode(value), max_num_unsigned_digits);
+        }
         if self.short_zero_and_one {
             if value == 0 {
                 return sink.write(&[true, false]);
@@ -208,6 +267,68 @@ impl DigitIntEncodingProtocol {
 
         self.write_digit_part(sink, value as u128, max_num_digits)
     }
+
+    fn digit_part_size(&self, mut value: u128, max_num_digits: u8) -> u32 {
+        let num_digit_values = self.get_num_digit_values();
+        let mut num_digits = 0;
+        while value > 0 {
+            value /= num_digit_values;
+            num_digits += 1;
+        }
+
+        if num_digits < max_num_digits {
+            num_digits += 1;
+        }
+        num_digits as u32 * self.digit_size as u32
+    }
+
+    fn unsigned_size(&self, mut value: u128, max_num_digits: u8) -> u32 {
+        let mut size = 0;
+        if self.short_zero_and_one {
+            if value <= 1 {
+                return 2;
+            }
+            size += 1;
+            value -= 2;
+        }
+
+        size + self.digit_part_size(value, max_num_digits)
+    }
+
+    fn signed_size(&self, mut value: i128, max_num_digits: u8, max_num_unsigned_digits: u8) -> u32 {
+        if self.signed_strategy == SignedStrategy::Zigzag {
+            return self.unsigned_size(zigzag_encode(value), max_num_unsigned_digits);
+        }
+        let mut size = 1;
+        if self.short_zero_and_one {
+            if value == 0 || value == 1 {
+                return 2;
+            }
+            size += 1;
+            if value >= 0 {
+                value -= 2;
+            }
+        }
+
+        if value < 0 {
+            value += 1;
+            value = -value;
+        }
+        size + self.digit_part_size(value as u128, max_num_digits)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for DigitIntEncodingProtocol {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let digit_size = u.int_in_range(2..=127)?;
+        let short_zero_and_one = u.arbitrary()?;
+        let signed_strategy = match u.arbitrary()? {
+            false => SignedStrategy::SignBit,
+            true => SignedStrategy::Zigzag,
+        };
+        Ok(Self::new(digit_size, short_zero_and_one).with_signed_strategy(signed_strategy))
+    }
 }
 
 impl IntEncodingProtocol for DigitIntEncodingProtocol {
@@ -216,7 +337,12 @@ impl IntEncodingProtocol for DigitIntEncodingProtocol {
     }
 
     fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
-        self.write_signed(sink, value as i128, self.max_num_digits[0])
+        self.write_signed(
+            sink,
+            value as i128,
+            self.max_num_digits[0],
+            self.max_num_digits[1],
+        )
     }
 
     fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
@@ -224,7 +350,12 @@ impl IntEncodingProtocol for DigitIntEncodingProtocol {
     }
 
     fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
-        self.write_signed(sink, value as i128, self.max_num_digits[2])
+        self.write_signed(
+            sink,
+            value as i128,
+            self.max_num_digits[2],
+            self.max_num_digits[3],
+        )
     }
 
     fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
@@ -232,7 +363,12 @@ impl IntEncodingProtocol for DigitIntEncodingProtocol {
     }
 
     fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
-        self.write_signed(sink, value as i128, self.max_num_digits[4])
+        self.write_signed(
+            sink,
+            value as i128,
+            self.max_num_digits[4],
+            self.max_num_digits[5],
+        )
     }
 
     fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
@@ -240,7 +376,12 @@ impl IntEncodingProtocol for DigitIntEncodingProtocol {
     }
 
     fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
-        self.write_signed(sink, value as i128, self.max_num_digits[6])
+        self.write_signed(
+            sink,
+            value as i128,
+            self.max_num_digits[6],
+            self.max_num_digits[7],
+        )
     }
 
     fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
@@ -248,7 +389,63 @@ impl IntEncodingProtocol for DigitIntEncodingProtocol {
     }
 
     fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
-        self.write_signed(sink, value, self.max_num_digits[8])
+        self.write_signed(sink, value, self.max_num_digits[8], self.max_num_digits[9])
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        self.unsigned_size(value as u128, self.max_num_digits[1])
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        self.signed_size(
+            value as i128,
+            self.max_num_digits[0],
+            self.max_num_digits[1],
+        )
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        self.unsigned_size(value as u128, self.max_num_digits[3])
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        self.signed_size(
+            value as i128,
+            self.max_num_digits[2],
+            self.max_num_digits[3],
+        )
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        self.unsigned_size(value as u128, self.max_num_digits[5])
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        self.signed_size(
+            value as i128,
+            self.max_num_digits[4],
+            self.max_num_digits[5],
+        )
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        self.unsigned_size(value as u128, self.max_num_digits[7])
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        self.signed_size(
+            value as i128,
+            self.max_num_digits[6],
+            self.max_num_digits[7],
+        )
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        self.unsigned_size(value, self.max_num_digits[9])
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        self.signed_size(value, self.max_num_digits[8], self.max_num_digits[9])
     }
 }
 
@@ -257,7 +454,7 @@ mod tests {
 
     use crate::*;
 
-    use crate::encoding::protocol::testing::*;
+    use crate::testkit::*;
 
     const ENCODER: DigitIntEncodingProtocol = DigitIntEncodingProtocol::new(4, true);
     const DECODER: DigitIntDecodingProtocol = DigitIntDecodingProtocol::new(4, true);
@@ -437,4 +634,68 @@ mod tests {
         test_u8_result(&special_encoder, &special_decoder, 0, "1 0");
         test_u8_result(&special_encoder, &special_decoder, 1, "1 1");
     }
+
+    #[test]
+    fn test_encoded_size() {
+        // 537 is encoded as "0 0011 1010 0100 1111" (see the struct documentation)
+        assert_eq!(17, ENCODER.encoded_size_u16(537));
+        assert_eq!(2, ENCODER.encoded_size_i64(1));
+        assert_eq!(6, ENCODER.encoded_size_i8(-1));
+        test_encoded_sizes(&DigitIntEncodingProtocol::new(9, false));
+    }
+
+    #[test]
+    fn test_overflow() {
+        const F: bool = false;
+        const T: bool = true;
+
+        // 14 + 14 * 15 + 14 * 15 * 15 + 2 is way too big for a u8
+        let bools = [F, F, T, T, T, F, T, T, T, F, T, T, T];
+        let mut source = BoolSliceBitSource::new(&bools);
+        match DECODER.read_u8(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // 81 digits with value 2 is even too big for a u128
+        let bools = [F, T].repeat(81);
+        let mut source = BoolSliceBitSource::new(&bools);
+        match DigitIntDecodingProtocol::new(2, false).read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_zigzag() {
+        let encoder = ENCODER.with_signed_strategy(SignedStrategy::Zigzag);
+        let decoder = DECODER.with_signed_strategy(SignedStrategy::Zigzag);
+        assert_eq!(SignedStrategy::Zigzag, encoder.get_signed_strategy());
+        assert_eq!(SignedStrategy::Zigzag, decoder.get_signed_strategy());
+
+        // 0 and -1 get the short encodings
+        test_i8_result(&encoder, &decoder, 0, "1 0");
+        test_i8_result(&encoder, &decoder, -1, "1 1");
+        test_i8_result(&encoder, &decoder, 1, "0 1111");
+        test_i8_result(&encoder, &decoder, -2, "0 1000 1111");
+        test_i16_result(&encoder, &decoder, -2, "0 1000 1111");
+
+        // The extreme values are encoded like the large u8 values
+        test_i8_result(&encoder, &decoder, 127, "0 0011 1000 1000");
+        test_i8_result(&encoder, &decoder, -128, "0 1011 1000 1000");
+
+        // Unsigned integers are not affected
+        test_u8_result(&encoder, &decoder, 2, "0 1111");
+
+        for (digit_size, short_zero_and_one) in &[(4, true), (2, false), (3, true), (7, false)] {
+            let encoder = DigitIntEncodingProtocol::new(*digit_size, *short_zero_and_one)
+                .with_signed_strategy(SignedStrategy::Zigzag);
+            let decoder = DigitIntDecodingProtocol::new(*digit_size, *short_zero_and_one)
+                .with_signed_strategy(SignedStrategy::Zigzag);
+            test_encoding_pair(&encoder, &decoder);
+            test_skip(&encoder, &decoder);
+            test_slices(&encoder, &decoder);
+            test_encoded_sizes(&encoder);
+        }
+    }
 }
@@ -0,0 +1,418 @@
+use crate::*;
+
+/// Gets the number of bits of *value + 1*, which is 129 for *u128::MAX*
+fn get_elias_length(value: u128) -> u32 {
+    match value.checked_add(1) {
+        Some(number) => 128 - number.leading_zeros(),
+        None => 129,
+    }
+}
+
+/// Appends the Elias gamma code of *value + 1* to *bits*
+fn push_elias_gamma(bits: &mut Vec<bool>, value: u128) {
+    let length = get_elias_length(value);
+    bits.resize(bits.len() + length as usize - 1, false);
+    bits.push(true);
+    push_elias_remainder(bits, value, length);
+}
+
+/// Appends the *length - 1* least significant bits of *value + 1* to *bits*
+/// (most significant bit first), where *length* is the number of bits of
+/// *value + 1*. The most significant bit is always 1, so it is not written.
+fn push_elias_remainder(bits: &mut Vec<bool>, value: u128, length: u32) {
+    let number = value.wrapping_add(1);
+    for index in (0..length - 1).rev() {
+        bits.push(number & 1 << index != 0);
+    }
+}
+
+/// An *IntEncodingProtocol* that writes the Elias gamma code of the value plus
+/// 1 (since the Elias codes can't encode 0). The corresponding decoding
+/// protocol is *EliasGammaIntDecodingProtocol*.
+///
+/// The gamma code of a positive integer *n* with *L* bits is *L - 1* times 0,
+/// followed by the *L* bits of *n* (most significant bit first). This needs
+/// *2L - 1* bits, which is optimal when the probability of *n* is about
+/// 1 / (2 n^2). It is the same code as the zeta code with *k = 1* (see
+/// *ZetaIntEncodingProtocol*), except for *u128::MAX*, which needs 257 bits in
+/// the gamma code.
+///
+/// Signed integers are first mapped to unsigned integers using zigzag encoding
+/// (0, -1, 1, -2, 2, ...), so values close to 0 get the shortest encodings.
+///
+/// The encoding doesn't depend on the integer type: a value that was written as
+/// *u8* can also be read as *u64*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = EliasGammaIntEncodingProtocol::new();
+/// let mut sink = BoolVecBitSink::new();
+/// // 4 + 1 = 5 is 101 in binary
+/// encoder.write_u32(&mut sink, 4).unwrap();
+/// assert_eq!(&[false, false, true, false, true], sink.get_bits());
+///
+/// let decoder = EliasGammaIntDecodingProtocol::new();
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(4, decoder.read_u32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasGammaIntEncodingProtocol {}
+
+impl EliasGammaIntEncodingProtocol {
+    /// Constructs a new instance of *EliasGammaIntEncodingProtocol*. Note that
+    /// this is actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        EliasGammaIntEncodingProtocol {}
+    }
+
+    fn write_unsigned(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        let mut bits = Vec::new();
+        push_elias_gamma(&mut bits, value);
+        sink.write(&bits)
+    }
+
+    fn unsigned_size(value: u128) -> u32 {
+        2 * get_elias_length(value) - 1
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EliasGammaIntEncodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntEncodingProtocol for EliasGammaIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value))
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        Self::unsigned_size(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        Self::unsigned_size(zigzag_encode(value))
+    }
+}
+
+/// An *IntEncodingProtocol* that writes the Elias delta code of the value plus
+/// 1 (since the Elias codes can't encode 0). The corresponding decoding
+/// protocol is *EliasDeltaIntDecodingProtocol*.
+///
+/// The delta code of a positive integer *n* with *L* bits is the Elias gamma
+/// code of *L* (see *EliasGammaIntEncodingProtocol*), followed by the *L - 1*
+/// least significant bits of *n* (most significant bit first). This is longer
+/// than the gamma code for some small values, but much shorter for large
+/// values: it needs about *log2(n) + 2 log2(log2(n))* bits.
+///
+/// Signed integers are first mapped to unsigned integers using zigzag encoding
+/// (0, -1, 1, -2, 2, ...), so values close to 0 get the shortest encodings.
+///
+/// The encoding doesn't depend on the integer type: a value that was written as
+/// *u8* can also be read as *u64*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = EliasDeltaIntEncodingProtocol::new();
+/// let mut sink = BoolVecBitSink::new();
+/// // 4 + 1 = 5 is 101 in binary: the gamma code of its length 3, and then 01
+/// encoder.write_u32(&mut sink, 4).unwrap();
+/// assert_eq!(&[false, true, true, false, true], sink.get_bits());
+///
+/// let decoder = EliasDeltaIntDecodingProtocol::new();
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(4, decoder.read_u32(&mut source).unwrap());
+///
+/// // The delta code is much shorter than the gamma code for large values
+/// assert_eq!(43, encoder.encoded_size_u64(1 << 32));
+/// assert_eq!(65, EliasGammaIntEncodingProtocol::new().encoded_size_u64(1 << 32));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EliasDeltaIntEncodingProtocol {}
+
+impl EliasDeltaIntEncodingProtocol {
+    /// Constructs a new instance of *EliasDeltaIntEncodingProtocol*. Note that
+    /// this is actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        EliasDeltaIntEncodingProtocol {}
+    }
+
+    fn write_unsigned(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        let length = get_elias_length(value);
+        let mut bits = Vec::new();
+        push_elias_gamma(&mut bits, length as u128 - 1);
+        push_elias_remainder(&mut bits, value, length);
+        sink.write(&bits)
+    }
+
+    fn unsigned_size(value: u128) -> u32 {
+        let length = get_elias_length(value);
+        EliasGammaIntEncodingProtocol::unsigned_size(length as u128 - 1) + length - 1
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EliasDeltaIntEncodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntEncodingProtocol for EliasDeltaIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value))
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        Self::unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        Self::unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        Self::unsigned_size(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        Self::unsigned_size(zigzag_encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use crate::testkit::*;
+
+    const GAMMA_ENCODER: EliasGammaIntEncodingProtocol = EliasGammaIntEncodingProtocol::new();
+    const GAMMA_DECODER: EliasGammaIntDecodingProtocol = EliasGammaIntDecodingProtocol::new();
+    const DELTA_ENCODER: EliasDeltaIntEncodingProtocol = EliasDeltaIntEncodingProtocol::new();
+    const DELTA_DECODER: EliasDeltaIntDecodingProtocol = EliasDeltaIntDecodingProtocol::new();
+
+    #[test]
+    fn test_symmetry() {
+        test_encoding_pair(&GAMMA_ENCODER, &GAMMA_DECODER);
+        test_skip(&GAMMA_ENCODER, &GAMMA_DECODER);
+        test_slices(&GAMMA_ENCODER, &GAMMA_DECODER);
+        test_encoded_sizes(&GAMMA_ENCODER);
+
+        test_encoding_pair(&DELTA_ENCODER, &DELTA_DECODER);
+        test_skip(&DELTA_ENCODER, &DELTA_DECODER);
+        test_slices(&DELTA_ENCODER, &DELTA_DECODER);
+        test_encoded_sizes(&DELTA_ENCODER);
+    }
+
+    #[test]
+    fn test_gamma_results() {
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 0, "1");
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 1, "0 1 0");
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 2, "0 1 1");
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 3, "00 1 00");
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 6, "00 1 11");
+        test_u8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 255, "00000000 1 00000000");
+        test_i8_result(&GAMMA_ENCODER, &GAMMA_DECODER, -1, "0 1 0");
+        test_i8_result(&GAMMA_ENCODER, &GAMMA_DECODER, 1, "0 1 1");
+
+        // Compare with the zeta code with k = 1
+        let zeta = ZetaIntEncodingProtocol::new(1);
+        for value in 0..1000u32 {
+            assert_eq!(
+                zeta.encoded_size_u32(value),
+                GAMMA_ENCODER.encoded_size_u32(value)
+            );
+        }
+        assert_eq!(257, GAMMA_ENCODER.encoded_size_u128(u128::MAX));
+    }
+
+    #[test]
+    fn test_delta_results() {
+        test_u8_result(&DELTA_ENCODER, &DELTA_DECODER, 0, "1");
+        test_u8_result(&DELTA_ENCODER, &DELTA_DECODER, 1, "010 0");
+        test_u8_result(&DELTA_ENCODER, &DELTA_DECODER, 2, "010 1");
+        test_u8_result(&DELTA_ENCODER, &DELTA_DECODER, 3, "011 00");
+        test_u8_result(&DELTA_ENCODER, &DELTA_DECODER, 15, "00101 0000");
+        test_i8_result(&DELTA_ENCODER, &DELTA_DECODER, -1, "010 0");
+
+        // The length of u128::MAX + 1 is 129, whose gamma code needs 15 bits
+        assert_eq!(143, DELTA_ENCODER.encoded_size_u128(u128::MAX));
+        let mut sink = BoolVecBitSink::new();
+        DELTA_ENCODER.write_u128(&mut sink, u128::MAX).unwrap();
+        DELTA_ENCODER.write_i128(&mut sink, i128::MIN).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(u128::MAX, DELTA_DECODER.read_u128(&mut source).unwrap());
+        assert_eq!(i128::MIN, DELTA_DECODER.read_i128(&mut source).unwrap());
+    }
+
+    fn expect_overflow<T: std::fmt::Debug>(result: Result<T, DecodeError>) {
+        match result {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut sink = BoolVecBitSink::new();
+        GAMMA_ENCODER.write_u16(&mut sink, 300).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        expect_overflow(GAMMA_DECODER.read_u8(&mut source));
+
+        // No value has more than 129 bits
+        let bools = vec![false; 200];
+        let mut source = BoolSliceBitSource::new(&bools);
+        expect_overflow(GAMMA_DECODER.read_u128(&mut source));
+
+        // The only value with 129 bits is u128::MAX + 1
+        let mut bools = vec![false; 257];
+        bools[128] = true;
+        let mut source = BoolSliceBitSource::new(&bools);
+        assert_eq!(u128::MAX, GAMMA_DECODER.read_u128(&mut source).unwrap());
+        bools[256] = true;
+        let mut source = BoolSliceBitSource::new(&bools);
+        expect_overflow(GAMMA_DECODER.read_u128(&mut source));
+
+        // A length of 130 bits in the delta code
+        let mut sink = BoolVecBitSink::new();
+        GAMMA_ENCODER.write_u8(&mut sink, 129).unwrap();
+        sink.write(&[false; 129]).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        expect_overflow(DELTA_DECODER.read_u128(&mut source));
+    }
+}
@@ -1,10 +1,30 @@
 use crate::*;
 
+mod adaptive;
+mod arithmetic_digit;
+mod cached;
 mod digit;
+mod elias;
 mod simple;
+mod varint;
+mod vlq;
+mod zeta;
 
+pub use adaptive::*;
+pub use arithmetic_digit::*;
+pub use cached::*;
 pub use digit::*;
+pub use elias::*;
 pub use simple::*;
+pub use varint::*;
+pub use vlq::*;
+pub use zeta::*;
+
+/// Maps signed integers to unsigned integers such that values close to 0 are
+/// mapped to small values: 0, -1, 1, -2, 2, ... become 0, 1, 2, 3, 4, ...
+pub(crate) fn zigzag_encode(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
 
 /// A protocol for encoding primitive integers into a *BitSink*. Every
 /// implementation of this trait should have a corresponding *IntDecodingProtocol*
@@ -44,6 +64,15 @@ pub use simple::*;
 /// because implementations of *BitSource* and *BitSink* can be backed by IO
 /// operations, which could fail. Furthermore, the *DecodingProtocol* has to
 /// be careful because it might deal with user input.
+///
+/// The *slice* methods encode a length-prefixed slice of integers. Their
+/// default implementations encode the elements one by one, but implementations
+/// can override them with something faster.
+///
+/// The *encoded_size* methods predict exactly how many bits the corresponding
+/// write method would write, without needing a sink. Their default
+/// implementations simply encode the value into a sink that only counts the
+/// bits, but the protocols in this crate compute the size arithmetically.
 pub trait IntEncodingProtocol {
     /// Encodes the given u8 value and writes it to *sink*
     fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError>;
@@ -74,373 +103,263 @@ pub trait IntEncodingProtocol {
 
     /// Encodes the given i128 value and writes it to *sink*
     fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError>;
-}
 
-#[cfg(test)]
-pub(crate) mod testing {
-
-    use crate::*;
-
-    use rand::distributions::Standard;
-    use rand::prelude::*;
-
-    pub fn test_encoding_pair(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-    ) {
-        test_u8(encoder, decoder);
-        test_i8(encoder, decoder);
-        test_u16(encoder, decoder);
-        test_i16(encoder, decoder);
-        test_u32(encoder, decoder);
-        test_i32(encoder, decoder);
-        test_u64(encoder, decoder);
-        test_i64(encoder, decoder);
-        test_u128(encoder, decoder);
-        test_i128(encoder, decoder);
-
-        // Combining them...
-        let mut sink = BoolVecBitSink::new();
-        write_combined(&mut sink, encoder).unwrap();
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        read_combined(&mut source, decoder).unwrap();
-    }
-
-    fn write_combined(
-        sink: &mut impl BitSink,
-        encoder: &impl IntEncodingProtocol,
-    ) -> Result<(), WriteError> {
-        encoder.write_i128(sink, -123456)?;
-        sink.write(&[true, false])?;
-        encoder.write_i64(sink, 987654321)?;
-        encoder.write_i32(sink, -13579)?;
-        encoder.write_i16(sink, 24680)?;
-        encoder.write_i8(sink, 123)?;
-        sink.write(&[false])?;
-        encoder.write_u8(sink, 200)?;
-        encoder.write_u16(sink, 23456)?;
-        encoder.write_u32(sink, 123456789)?;
-        sink.write(&[true])?;
-        encoder.write_u64(sink, 0)?;
-        encoder.write_u128(sink, 345678901234567)?;
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_u8*. It can be decoded with *read_u8_vec*.
+    fn write_u8_slice(&self, sink: &mut impl BitSink, values: &[u8]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_u8(sink, *value)?;
+        }
         Ok(())
     }
 
-    fn read_combined(
-        source: &mut impl BitSource,
-        decoder: &impl IntDecodingProtocol,
-    ) -> Result<(), DecodeError> {
-        assert_eq!(-123456, decoder.read_i128(source)?);
-        let mut dest = [false; 2];
-        source.read(&mut dest).unwrap();
-        assert_eq!(&[true, false], &dest);
-        assert_eq!(987654321, decoder.read_i64(source)?);
-        assert_eq!(-13579, decoder.read_i32(source)?);
-        assert_eq!(24680, decoder.read_i16(source)?);
-        assert_eq!(123, decoder.read_i8(source)?);
-        let mut dest = [true];
-        source.read(&mut dest).unwrap();
-        assert!(!dest[0]);
-        assert_eq!(200, decoder.read_u8(source)?);
-        assert_eq!(23456, decoder.read_u16(source)?);
-        assert_eq!(123456789, decoder.read_u32(source)?);
-        source.read(&mut dest).unwrap();
-        assert!(dest[0]);
-        assert_eq!(0, decoder.read_u64(source)?);
-        assert_eq!(345678901234567, decoder.read_u128(source)?);
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_i8*. It can be decoded with *read_i8_vec*.
+    fn write_i8_slice(&self, sink: &mut impl BitSink, values: &[i8]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_i8(sink, *value)?;
+        }
         Ok(())
     }
 
-    fn test_u8(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        let mut sink = BoolVecBitSink::with_capacity(256 * 8);
-        for value in 0..=255 {
-            encoder.write_u8(&mut sink, value).unwrap();
-        }
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        for value in 0..=255 {
-            assert_eq!(value, decoder.read_u8(&mut source).unwrap());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_u16*. It can be decoded with *read_u16_vec*.
+    fn write_u16_slice(&self, sink: &mut impl BitSink, values: &[u16]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_u16(sink, *value)?;
         }
+        Ok(())
     }
 
-    fn test_i8(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        let mut sink = BoolVecBitSink::with_capacity(256 * 8);
-        for value in -128..=127 {
-            encoder.write_i8(&mut sink, value).unwrap();
-        }
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        for value in -128..=127 {
-            assert_eq!(value, decoder.read_i8(&mut source).unwrap());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_i16*. It can be decoded with *read_i16_vec*.
+    fn write_i16_slice(&self, sink: &mut impl BitSink, values: &[i16]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_i16(sink, *value)?;
         }
+        Ok(())
     }
 
-    fn test_u16(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        let mut sink = BoolVecBitSink::with_capacity(65536 * 16);
-        for value in 0..=65535 {
-            encoder.write_u16(&mut sink, value).unwrap();
-        }
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        for value in 0..=65535 {
-            assert_eq!(value, decoder.read_u16(&mut source).unwrap());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_u32*. It can be decoded with *read_u32_vec*.
+    fn write_u32_slice(&self, sink: &mut impl BitSink, values: &[u32]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_u32(sink, *value)?;
         }
+        Ok(())
     }
 
-    fn test_i16(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        let mut sink = BoolVecBitSink::with_capacity(656536 * 16);
-        for value in -32768..=32767 {
-            encoder.write_i16(&mut sink, value).unwrap();
-        }
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        for value in -32768..=32767 {
-            assert_eq!(value, decoder.read_i16(&mut source).unwrap());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_i32*. It can be decoded with *read_i32_vec*.
+    fn write_i32_slice(&self, sink: &mut impl BitSink, values: &[i32]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_i32(sink, *value)?;
         }
+        Ok(())
     }
 
-    const RANDOM_AMOUNT: usize = 1_000;
-
-    fn test_random_symmetry<T: Copy + Eq + std::fmt::Debug>(
-        write_method: impl Fn(&mut BoolVecBitSink, T) -> Result<(), WriteError>,
-        read_method: impl Fn(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
-    ) where
-        Standard: Distribution<T>,
-    {
-        let mut rng = rand::thread_rng();
-        let mut values = Vec::with_capacity(RANDOM_AMOUNT);
-        for _counter in 0..RANDOM_AMOUNT {
-            values.push(rng.gen());
-        }
-
-        let mut sink = BoolVecBitSink::new();
-        for value in &values {
-            write_method(&mut sink, *value).unwrap();
-        }
-
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
-        for value in &values {
-            assert_eq!(*value, read_method(&mut source).unwrap());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_u64*. It can be decoded with *read_u64_vec*.
+    fn write_u64_slice(&self, sink: &mut impl BitSink, values: &[u64]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_u64(sink, *value)?;
         }
+        Ok(())
     }
 
-    fn test_given_symmetry<T: Copy + Eq + std::fmt::Debug>(
-        values: &[T],
-        write_method: impl Fn(&mut BoolVecBitSink, T) -> Result<(), WriteError>,
-        read_method: impl Fn(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
-    ) {
-        let mut sink = BoolVecBitSink::new();
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_i64*. It can be decoded with *read_i64_vec*.
+    fn write_i64_slice(&self, sink: &mut impl BitSink, values: &[i64]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
         for value in values {
-            write_method(&mut sink, *value).unwrap();
+            self.write_i64(sink, *value)?;
         }
+        Ok(())
+    }
 
-        let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_u128*. It can be decoded with *read_u128_vec*.
+    fn write_u128_slice(&self, sink: &mut impl BitSink, values: &[u128]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
         for value in values {
-            assert_eq!(*value, read_method(&mut source).unwrap());
+            self.write_u128(sink, *value)?;
         }
+        Ok(())
     }
 
-    fn test_u32(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, u32::max_value()],
-            |sink, value| encoder.write_u32(sink, value),
-            |source| decoder.read_u32(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_u32(sink, value),
-            |source| decoder.read_u32(source),
-        );
-    }
-
-    fn test_i32(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, -1, i32::max_value(), i32::min_value()],
-            |sink, value| encoder.write_i32(sink, value),
-            |source| decoder.read_i32(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_i32(sink, value),
-            |source| decoder.read_i32(source),
-        );
-    }
-
-    fn test_u64(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, u64::max_value()],
-            |sink, value| encoder.write_u64(sink, value),
-            |source| decoder.read_u64(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_u64(sink, value),
-            |source| decoder.read_u64(source),
-        );
+    /// Writes the length of *values* (using *write_u64*) and then encodes all
+    /// *values* with *write_i128*. It can be decoded with *read_i128_vec*.
+    fn write_i128_slice(&self, sink: &mut impl BitSink, values: &[i128]) -> Result<(), WriteError> {
+        self.write_u64(sink, values.len() as u64)?;
+        for value in values {
+            self.write_i128(sink, *value)?;
+        }
+        Ok(())
     }
 
-    fn test_i64(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, -1, i64::max_value(), i64::min_value()],
-            |sink, value| encoder.write_i64(sink, value),
-            |source| decoder.read_i64(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_i64(sink, value),
-            |source| decoder.read_i64(source),
-        );
+    /// Writes the length of *string* in bytes (using *write_u64*) and then
+    /// encodes its UTF-8 bytes with *write_u8*. It can be decoded with
+    /// *read_string* (or *read_string_lossy*).
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let encoder = DigitIntEncodingProtocol::v1();
+    /// let decoder = DigitIntDecodingProtocol::v1();
+    /// let mut sink = BoolVecBitSink::new();
+    /// encoder.write_string(&mut sink, "héllo").unwrap();
+    /// // 0xFF is never valid in UTF-8
+    /// encoder.write_u8_slice(&mut sink, &[b'h', 0xFF, b'i']).unwrap();
+    /// encoder.write_u8_slice(&mut sink, &[b'h', 0xFF, b'i']).unwrap();
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let mut context = DecodeContext::new(DecodeLimits::new());
+    /// assert_eq!("héllo", decoder.read_string(&mut source, &mut context).unwrap());
+    /// assert!(matches!(
+    ///     decoder.read_string(&mut source, &mut context),
+    ///     Err(DecodeError::InvalidUtf8 { position: 1 })
+    /// ));
+    /// assert_eq!("h\u{FFFD}i", decoder.read_string_lossy(&mut source, &mut context).unwrap());
+    /// ```
+    fn write_string(&self, sink: &mut impl BitSink, string: &str) -> Result<(), WriteError> {
+        self.write_u8_slice(sink, string.as_bytes())
     }
 
-    fn test_u128(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, u128::max_value()],
-            |sink, value| encoder.write_u128(sink, value),
-            |source| decoder.read_u128(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_u128(sink, value),
-            |source| decoder.read_u128(source),
-        );
+    /// Writes the length of *bools* (using *write_u64*) followed by the bools
+    /// themselves. When *bools* consists of long runs of equal values, it will
+    /// instead write the lengths of these runs (using *write_u64*) if that is
+    /// shorter. 1 extra bit is used to indicate which of the 2 representations
+    /// was chosen. It can be decoded with *read_bools*.
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let encoder = DigitIntEncodingProtocol::v1();
+    /// let decoder = DigitIntDecodingProtocol::v1();
+    /// let mut bools = vec![false; 1000];
+    /// bools[500] = true;
+    ///
+    /// let mut sink = BoolVecBitSink::new();
+    /// encoder.write_bools(&mut sink, &bools).unwrap();
+    /// assert!(sink.get_num_bools() < 100);
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let mut context = DecodeContext::new(DecodeLimits::new());
+    /// assert_eq!(bools, decoder.read_bools(&mut source, &mut context).unwrap());
+    /// ```
+    fn write_bools(&self, sink: &mut impl BitSink, bools: &[bool]) -> Result<(), WriteError> {
+        self.write_u64(sink, bools.len() as u64)?;
+
+        let mut run_lengths = Vec::new();
+        let mut run_start = 0;
+        for index in 1..=bools.len() {
+            if index == bools.len() || bools[index] != bools[run_start] {
+                run_lengths.push((index - run_start) as u64);
+                run_start = index;
+            }
+        }
+        let run_length_size: u64 = 1 + run_lengths
+            .iter()
+            .map(|run_length| self.encoded_size_u64(run_length - 1) as u64)
+            .sum::<u64>();
+
+        if run_length_size < bools.len() as u64 {
+            sink.write(&[true, bools[0]])?;
+            for run_length in run_lengths {
+                self.write_u64(sink, run_length - 1)?;
+            }
+            Ok(())
+        } else {
+            sink.write(&[false])?;
+            sink.write(bools)
+        }
     }
 
-    fn test_i128(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
-        test_given_symmetry(
-            &[0, 1, -1, i128::max_value(), i128::min_value()],
-            |sink, value| encoder.write_i128(sink, value),
-            |source| decoder.read_i128(source),
-        );
-
-        test_random_symmetry(
-            |sink, value| encoder.write_i128(sink, value),
-            |source| decoder.read_i128(source),
-        );
+    /// Computes the number of bits that *write_u8* would write for *value*
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        count_bits(|sink| self.write_u8(sink, value))
     }
 
-    fn test_result(
-        mut action: impl FnMut(&mut BoolVecBitSink) -> Result<(), WriteError>,
-        encoded: &str,
-    ) {
-        for character in encoded.chars() {
-            assert!(character == '0' || character == '1' || character == ' ');
-        }
-
-        let as_bools: Vec<bool> = encoded
-            .chars()
-            .filter(|c| *c != ' ')
-            .map(|c| if c == '0' { false } else { true })
-            .collect();
-        let mut sink = BoolVecBitSink::with_capacity(as_bools.len());
-
-        action(&mut sink).unwrap();
-
-        assert_eq!(as_bools, sink.get_bits());
+    /// Computes the number of bits that *write_i8* would write for *value*
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        count_bits(|sink| self.write_i8(sink, value))
     }
 
-    pub fn test_u8_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: u8,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_u8(sink, value), encoded);
-        decoding::testing::test_u8_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_u16* would write for *value*
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        count_bits(|sink| self.write_u16(sink, value))
     }
 
-    pub fn test_i8_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: i8,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_i8(sink, value), encoded);
-        decoding::testing::test_i8_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_i16* would write for *value*
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        count_bits(|sink| self.write_i16(sink, value))
     }
 
-    pub fn test_u16_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: u16,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_u16(sink, value), encoded);
-        decoding::testing::test_u16_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_u32* would write for *value*
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        count_bits(|sink| self.write_u32(sink, value))
     }
 
-    pub fn test_i16_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: i16,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_i16(sink, value), encoded);
-        decoding::testing::test_i16_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_i32* would write for *value*
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        count_bits(|sink| self.write_i32(sink, value))
     }
 
-    pub fn test_u32_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: u32,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_u32(sink, value), encoded);
-        decoding::testing::test_u32_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_u64* would write for *value*
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        count_bits(|sink| self.write_u64(sink, value))
     }
 
-    pub fn test_i32_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: i32,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_i32(sink, value), encoded);
-        decoding::testing::test_i32_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_i64* would write for *value*
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        count_bits(|sink| self.write_i64(sink, value))
     }
 
-    // I should use these functions someday
-    #[allow(dead_code)]
-    pub fn test_u64_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: u64,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_u64(sink, value), encoded);
-        decoding::testing::test_u64_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_u128* would write for *value*
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        count_bits(|sink| self.write_u128(sink, value))
     }
 
-    #[allow(dead_code)]
-    pub fn test_i64_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: i64,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_i64(sink, value), encoded);
-        decoding::testing::test_i64_result(decoder, value, encoded);
+    /// Computes the number of bits that *write_i128* would write for *value*
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        count_bits(|sink| self.write_i128(sink, value))
     }
 
-    #[allow(dead_code)]
-    pub fn test_u128_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: u128,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_u128(sink, value), encoded);
-        decoding::testing::test_u128_result(decoder, value, encoded);
+    /// Calls *write* with this protocol and *sink*, and returns the number of
+    /// bits that it wrote to *sink*. This is convenient to keep track of the
+    /// size of individual fields:
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let encoder = DigitIntEncodingProtocol::v1();
+    /// let mut sink = BoolVecBitSink::new();
+    /// let num_bits = encoder
+    ///     .write_counted(&mut sink, |encoder, sink| encoder.write_u32(sink, 12345))
+    ///     .unwrap();
+    /// assert_eq!(sink.get_num_bools(), num_bits);
+    /// ```
+    fn write_counted<S: BitSink>(
+        &self,
+        sink: &mut S,
+        write: impl FnOnce(&Self, &mut S) -> Result<(), WriteError>,
+    ) -> Result<u64, WriteError> {
+        let start_bits = sink.get_num_bools();
+        write(self, sink)?;
+        Ok(sink.get_num_bools() - start_bits)
     }
+}
 
-    #[allow(dead_code)]
-    pub fn test_i128_result(
-        encoder: &impl IntEncodingProtocol,
-        decoder: &impl IntDecodingProtocol,
-        value: i128,
-        encoded: &str,
-    ) {
-        test_result(|sink| encoder.write_i128(sink, value), encoded);
-        decoding::testing::test_i128_result(decoder, value, encoded);
-    }
+fn count_bits(write: impl FnOnce(&mut CountingBitSink) -> Result<(), WriteError>) -> u32 {
+    let mut sink = CountingBitSink::new();
+    // The sink never returns errors, and encoders shouldn't fail for valid values
+    write(&mut sink).expect("Encoding into a CountingBitSink shouldn't fail");
+    sink.get_num_bools() as u32
 }
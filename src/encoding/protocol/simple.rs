@@ -1,9 +1,13 @@
 use crate::*;
 
+/// The number of bits that the slice methods collect before they write them
+pub(crate) const BULK_BUFFER_SIZE: usize = 4096;
+
 /// The simple implementation of *DecodingProtocol*. This implementation will
 /// not try to encode any data type compactly, but rather use a simple encoding
 /// based on their binary representation. The corresponding decoding protocol is
 /// *SimpleDecodingProtocol*.
+#[derive(Debug, Clone, Copy)]
 pub struct SimpleIntEncodingProtocol {}
 
 impl SimpleIntEncodingProtocol {
@@ -37,6 +41,36 @@ impl SimpleIntEncodingProtocol {
         }
         self.write_unsigned(sink, num_bits, value as u128)
     }
+
+    /// Writes the length of *values* and then the *num_bits* least significant
+    /// bits of each value. Unlike the per-element methods, this collects the
+    /// bits of many values before passing them to *sink*.
+    fn write_bulk(
+        &self,
+        sink: &mut impl BitSink,
+        num_bits: usize,
+        values: impl ExactSizeIterator<Item = u128>,
+    ) -> Result<(), WriteError> {
+        self.write_unsigned(sink, 64, values.len() as u128)?;
+        let mut buffer = Vec::with_capacity(BULK_BUFFER_SIZE + 128);
+        for value in values {
+            for index in 0..num_bits {
+                buffer.push(value & 1 << index != 0);
+            }
+            if buffer.len() >= BULK_BUFFER_SIZE {
+                sink.write(&buffer)?;
+                buffer.clear();
+            }
+        }
+        sink.write(&buffer)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SimpleIntEncodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
 }
 
 impl IntEncodingProtocol for SimpleIntEncodingProtocol {
@@ -79,6 +113,86 @@ impl IntEncodingProtocol for SimpleIntEncodingProtocol {
     fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
         self.write_signed(sink, 128, value)
     }
+
+    fn write_u8_slice(&self, sink: &mut impl BitSink, values: &[u8]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 8, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_i8_slice(&self, sink: &mut impl BitSink, values: &[i8]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 8, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_u16_slice(&self, sink: &mut impl BitSink, values: &[u16]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 16, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_i16_slice(&self, sink: &mut impl BitSink, values: &[i16]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 16, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_u32_slice(&self, sink: &mut impl BitSink, values: &[u32]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 32, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_i32_slice(&self, sink: &mut impl BitSink, values: &[i32]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 32, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_u64_slice(&self, sink: &mut impl BitSink, values: &[u64]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 64, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_i64_slice(&self, sink: &mut impl BitSink, values: &[i64]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 64, values.iter().map(|value| *value as u128))
+    }
+
+    fn write_u128_slice(&self, sink: &mut impl BitSink, values: &[u128]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 128, values.iter().copied())
+    }
+
+    fn write_i128_slice(&self, sink: &mut impl BitSink, values: &[i128]) -> Result<(), WriteError> {
+        self.write_bulk(sink, 128, values.iter().map(|value| *value as u128))
+    }
+
+    fn encoded_size_u8(&self, _value: u8) -> u32 {
+        8
+    }
+
+    fn encoded_size_i8(&self, _value: i8) -> u32 {
+        8
+    }
+
+    fn encoded_size_u16(&self, _value: u16) -> u32 {
+        16
+    }
+
+    fn encoded_size_i16(&self, _value: i16) -> u32 {
+        16
+    }
+
+    fn encoded_size_u32(&self, _value: u32) -> u32 {
+        32
+    }
+
+    fn encoded_size_i32(&self, _value: i32) -> u32 {
+        32
+    }
+
+    fn encoded_size_u64(&self, _value: u64) -> u32 {
+        64
+    }
+
+    fn encoded_size_i64(&self, _value: i64) -> u32 {
+        64
+    }
+
+    fn encoded_size_u128(&self, _value: u128) -> u32 {
+        128
+    }
+
+    fn encoded_size_i128(&self, _value: i128) -> u32 {
+        128
+    }
 }
 
 #[cfg(test)]
@@ -86,7 +200,7 @@ mod tests {
 
     use crate::*;
 
-    use crate::encoding::protocol::testing::*;
+    use crate::testkit::*;
 
     const ENCODER: SimpleIntEncodingProtocol = SimpleIntEncodingProtocol::new();
     const DECODER: SimpleIntDecodingProtocol = SimpleIntDecodingProtocol::new();
@@ -96,6 +210,26 @@ mod tests {
         test_encoding_pair(&ENCODER, &DECODER);
     }
 
+    #[test]
+    fn test_bulk_slices() {
+        // The bulk slice methods should write exactly the same bits as the
+        // default implementations, which write the values one by one
+        let values: Vec<i32> = (-1000..1000).map(|value| value * 12345).collect();
+        let mut bulk_sink = BoolVecBitSink::new();
+        ENCODER.write_i32_slice(&mut bulk_sink, &values).unwrap();
+
+        let mut sink = BoolVecBitSink::new();
+        ENCODER.write_u64(&mut sink, values.len() as u64).unwrap();
+        for value in &values {
+            ENCODER.write_i32(&mut sink, *value).unwrap();
+        }
+        assert_eq!(sink.get_bits(), bulk_sink.get_bits());
+
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_vec_len(1999));
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert!(DECODER.read_i32_vec(&mut source, &mut context).is_err());
+    }
+
     #[test]
     fn test_u8() {
         test_u8_result(&ENCODER, &DECODER, 0, "0000 0000");
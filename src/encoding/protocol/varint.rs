@@ -0,0 +1,244 @@
+use crate::*;
+
+/// The maximum number of bytes of a LEB128 varint that fits in a *u128*
+pub(crate) const MAX_NUM_VARINT_BYTES: usize = 19;
+
+/// An *IntEncodingProtocol* that writes LEB128 varints, which are also used by
+/// Protocol Buffers, WebAssembly and DWARF: the value is split into groups of 7
+/// bits, and each group is written as 1 byte, starting with the least
+/// significant group. The most significant bit of each byte is 1 when more
+/// bytes follow, and 0 in the last byte. The corresponding decoding protocol is
+/// *VarIntDecodingProtocol*.
+///
+/// Every byte is written like *SimpleIntEncodingProtocol* writes a *u8* (least
+/// significant bit first), so a value that is written at a byte boundary of a
+/// *U8VecBitSink* results in exactly the bytes of a protobuf varint. See
+/// *VlqIntEncodingProtocol* for the MIDI convention, which starts with the most
+/// significant group instead.
+///
+/// Signed integers are first mapped to unsigned integers using zigzag encoding
+/// (0, -1, 1, -2, 2, ...), which is the same encoding that protobuf uses for
+/// its *sint32* and *sint64* types. (Protobuf *int32* and *int64* values are
+/// not zigzag encoded, and can be read as *u64* instead.)
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// VarIntEncodingProtocol::new().write_u32(&mut sink, 300).unwrap();
+/// assert_eq!(&vec![0xAC, 0x02], sink.get_bytes());
+///
+/// let mut source = U8SliceBitSource::new(sink.get_bytes(), 0);
+/// assert_eq!(300, VarIntDecodingProtocol::new().read_u32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VarIntEncodingProtocol {}
+
+impl VarIntEncodingProtocol {
+    /// Constructs a new instance of *VarIntEncodingProtocol*. Note that this is
+    /// actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        VarIntEncodingProtocol {}
+    }
+
+    fn num_bytes(value: u128) -> u32 {
+        let num_used_bits = 128 - value.leading_zeros();
+        u32::max(1, num_used_bits.div_ceil(7))
+    }
+
+    fn write_unsigned(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        let num_bytes = Self::num_bytes(value);
+        let mut bits = Vec::with_capacity(8 * num_bytes as usize);
+        for byte_index in 0..num_bytes {
+            let group = (value >> (7 * byte_index)) & 0x7F;
+            for bit_index in 0..7 {
+                bits.push(group & 1 << bit_index != 0);
+            }
+            bits.push(byte_index + 1 < num_bytes);
+        }
+        sink.write(&bits)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VarIntEncodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntEncodingProtocol for VarIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value))
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        8 * Self::num_bytes(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use crate::testkit::*;
+
+    const ENCODER: VarIntEncodingProtocol = VarIntEncodingProtocol::new();
+    const DECODER: VarIntDecodingProtocol = VarIntDecodingProtocol::new();
+
+    #[test]
+    fn test_symmetry() {
+        test_encoding_pair(&ENCODER, &DECODER);
+        test_skip(&ENCODER, &DECODER);
+        test_slices(&ENCODER, &DECODER);
+        test_encoded_sizes(&ENCODER);
+    }
+
+    #[test]
+    fn test_protobuf_examples() {
+        let examples: [(u64, &[u8]); 7] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (150, &[0x96, 0x01]),
+            (300, &[0xAC, 0x02]),
+            (
+                u64::MAX,
+                &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+            ),
+        ];
+        for (value, bytes) in &examples {
+            let mut sink = U8VecBitSink::new();
+            ENCODER.write_u64(&mut sink, *value).unwrap();
+            assert_eq!(bytes, &&sink.get_bytes()[..]);
+
+            let mut source = U8SliceBitSource::new(bytes, 0);
+            assert_eq!(*value, DECODER.read_u64(&mut source).unwrap());
+        }
+
+        // The sint32 examples from the protobuf documentation
+        let signed_examples: [(i32, u8); 4] = [(0, 0), (-1, 1), (1, 2), (-2, 3)];
+        for (value, byte) in &signed_examples {
+            let mut sink = U8VecBitSink::new();
+            ENCODER.write_i32(&mut sink, *value).unwrap();
+            assert_eq!(&vec![*byte], sink.get_bytes());
+        }
+    }
+
+    #[test]
+    fn test_signed() {
+        test_i8_result(&ENCODER, &DECODER, 0, "0000000 0");
+        test_i8_result(&ENCODER, &DECODER, -1, "1000000 0");
+        test_i8_result(&ENCODER, &DECODER, 1, "0100000 0");
+        test_i16_result(&ENCODER, &DECODER, -65, "1000000 1 1000000 0");
+        test_i16_result(&ENCODER, &DECODER, 64, "0000000 1 1000000 0");
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut sink = BoolVecBitSink::new();
+        ENCODER.write_u16(&mut sink, 300).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match DECODER.read_u8(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // The last of 19 bytes can only contain 2 bits of a u128
+        let mut bytes = [0xFF; 19];
+        bytes[18] = 0x03;
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        assert_eq!(u128::MAX, DECODER.read_u128(&mut source).unwrap());
+        bytes[18] = 0x04;
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        match DECODER.read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // Trailing zero groups don't increase the value, but there is still a
+        // limit on the number of bytes
+        let mut bytes = [0x80; 20];
+        bytes[19] = 0x00;
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        match DECODER.read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::*;
+
+/// The maximum number of bytes of a variable-length quantity that fits in a
+/// *u128*
+pub(crate) const MAX_NUM_VLQ_BYTES: usize = 19;
+
+/// An *IntEncodingProtocol* that writes variable-length quantities, using the
+/// convention of MIDI files and ID3 tags: the value is split into groups of 7
+/// bits, and each group is written as 1 byte, starting with the most
+/// significant group. The most significant bit of each byte is 1 when more
+/// bytes follow, and 0 in the last byte. The corresponding decoding protocol is
+/// *VlqIntDecodingProtocol*.
+///
+/// Every byte is written like *SimpleIntEncodingProtocol* writes a *u8* (least
+/// significant bit first), so a value that is written at a byte boundary of a
+/// *U8VecBitSink* results in exactly the bytes that MIDI would use. Note that
+/// this is a different byte order than the LEB128 format, which starts with the
+/// least significant group (see *VarIntEncodingProtocol*).
+///
+/// Signed integers are first mapped to unsigned integers using zigzag encoding
+/// (0, -1, 1, -2, 2, ...), so values close to 0 get the shortest encodings.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// VlqIntEncodingProtocol::new().write_u32(&mut sink, 0x2000).unwrap();
+/// assert_eq!(&vec![0xC0, 0x00], sink.get_bytes());
+///
+/// let mut source = U8SliceBitSource::new(sink.get_bytes(), 0);
+/// assert_eq!(0x2000, VlqIntDecodingProtocol::new().read_u32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VlqIntEncodingProtocol {}
+
+impl VlqIntEncodingProtocol {
+    /// Constructs a new instance of *VlqIntEncodingProtocol*. Note that this is
+    /// actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        VlqIntEncodingProtocol {}
+    }
+
+    fn num_bytes(value: u128) -> u32 {
+        let num_used_bits = 128 - value.leading_zeros();
+        u32::max(1, num_used_bits.div_ceil(7))
+    }
+
+    fn write_unsigned(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        let num_bytes = Self::num_bytes(value);
+        let mut bits = Vec::with_capacity(8 * num_bytes as usize);
+        for byte_index in (0..num_bytes).rev() {
+            let group = (value >> (7 * byte_index)) & 0x7F;
+            for bit_index in 0..7 {
+                bits.push(group & 1 << bit_index != 0);
+            }
+            bits.push(byte_index > 0);
+        }
+        sink.write(&bits)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VlqIntEncodingProtocol {
+    fn arbitrary(_u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+impl IntEncodingProtocol for VlqIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value))
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        8 * Self::num_bytes(value as u128)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        8 * Self::num_bytes(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        8 * Self::num_bytes(zigzag_encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use crate::testkit::*;
+
+    const ENCODER: VlqIntEncodingProtocol = VlqIntEncodingProtocol::new();
+    const DECODER: VlqIntDecodingProtocol = VlqIntDecodingProtocol::new();
+
+    #[test]
+    fn test_symmetry() {
+        test_encoding_pair(&ENCODER, &DECODER);
+        test_skip(&ENCODER, &DECODER);
+        test_slices(&ENCODER, &DECODER);
+        test_encoded_sizes(&ENCODER);
+    }
+
+    #[test]
+    fn test_midi_examples() {
+        // The examples from the Standard MIDI File specification
+        let examples: [(u32, &[u8]); 12] = [
+            (0x00, &[0x00]),
+            (0x40, &[0x40]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x81, 0x00]),
+            (0x2000, &[0xC0, 0x00]),
+            (0x3FFF, &[0xFF, 0x7F]),
+            (0x4000, &[0x81, 0x80, 0x00]),
+            (0x10_0000, &[0xC0, 0x80, 0x00]),
+            (0x1F_FFFF, &[0xFF, 0xFF, 0x7F]),
+            (0x20_0000, &[0x81, 0x80, 0x80, 0x00]),
+            (0x800_0000, &[0xC0, 0x80, 0x80, 0x00]),
+            (0xFFF_FFFF, &[0xFF, 0xFF, 0xFF, 0x7F]),
+        ];
+        for (value, bytes) in &examples {
+            let mut sink = U8VecBitSink::new();
+            ENCODER.write_u32(&mut sink, *value).unwrap();
+            assert_eq!(bytes, &&sink.get_bytes()[..]);
+
+            let mut source = U8SliceBitSource::new(bytes, 0);
+            assert_eq!(*value, DECODER.read_u32(&mut source).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_signed() {
+        test_i8_result(&ENCODER, &DECODER, 0, "0000000 0");
+        test_i8_result(&ENCODER, &DECODER, -1, "1000000 0");
+        test_i8_result(&ENCODER, &DECODER, 1, "0100000 0");
+        test_i16_result(&ENCODER, &DECODER, -65, "1000000 1 1000000 0");
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut sink = BoolVecBitSink::new();
+        ENCODER.write_u16(&mut sink, 300).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match DECODER.read_u8(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // 19 bytes with 7 ones each are too big for a u128
+        let bytes = [0xFF; 19];
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        match DECODER.read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // Leading zero groups don't increase the value, but there is still a
+        // limit on the number of bytes
+        let bytes = [0x80; 20];
+        let mut source = U8SliceBitSource::new(&bytes, 0);
+        match DECODER.read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::*;
+
+/// Gets the first (unsigned) value of interval *h* of the zeta code with
+/// shrinking factor *k*, which is 2^(h * k) - 1
+pub(crate) fn get_zeta_interval_start(k: u8, h: u32) -> u128 {
+    let exponent = h * k as u32;
+    match exponent >= 128 {
+        true => u128::MAX,
+        false => (1 << exponent) - 1,
+    }
+}
+
+/// Gets the number of values in interval *h* of the zeta code with shrinking
+/// factor *k*. The last interval is cut off at *u128::MAX*.
+pub(crate) fn get_zeta_interval_size(k: u8, h: u32) -> u128 {
+    let start = get_zeta_interval_start(k, h);
+    match (h + 1) * k as u32 > 128 {
+        true => u128::MAX - start + 1,
+        false => get_zeta_interval_start(k, h + 1) - start,
+    }
+}
+
+/// Gets the largest interval index of the zeta code with shrinking factor *k*
+pub(crate) fn get_zeta_max_interval(k: u8) -> u32 {
+    128 / k as u32
+}
+
+/// Gets the number of bits *s* of the minimal binary code for *interval_size*
+/// values, as well as the number of values that are written with only *s - 1*
+/// bits
+pub(crate) fn get_minimal_binary_parameters(interval_size: u128) -> (u32, u128) {
+    let num_bits = 128 - (interval_size - 1).leading_zeros();
+    let power = match num_bits {
+        128 => 0,
+        _ => 1 << num_bits,
+    };
+    (num_bits, u128::wrapping_sub(power, interval_size))
+}
+
+/// An *IntEncodingProtocol* that uses the zeta codes of Boldi and Vigna, which
+/// are designed for values that follow a power-law distribution (like the gaps
+/// between the neighbours of a node in a web graph). The corresponding decoding
+/// protocol is *ZetaIntDecodingProtocol*.
+///
+/// The zeta code with shrinking factor *k* divides the unsigned integers into
+/// intervals: interval *h* contains the values from 2^(h * k) - 1 (inclusive)
+/// up to 2^((h + 1) * k) - 1 (exclusive). A value is encoded by writing *h* in
+/// unary (*h* times 0, followed by a 1), followed by the offset of the value in
+/// its interval, using a minimal binary code (most significant bit first). The
+/// zeta code with *k = 1* is the same as the Elias gamma code (of the value
+/// plus 1, see *EliasGammaIntEncodingProtocol*).
+///
+/// Signed integers are first mapped to unsigned integers using zigzag encoding
+/// (0, -1, 1, -2, 2, ...), so values close to 0 get the shortest encodings.
+///
+/// The encoding doesn't depend on the integer type: a value that was written as
+/// *u8* can also be read as *u64*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = ZetaIntEncodingProtocol::new(3);
+/// let mut sink = BoolVecBitSink::new();
+/// for gap in &[1u32, 2, 1, 5, 40, 3, 1200] {
+///     encoder.write_u32(&mut sink, *gap).unwrap();
+/// }
+///
+/// let decoder = ZetaIntDecodingProtocol::new(3);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(1, decoder.read_u32(&mut source).unwrap());
+/// assert_eq!(2, decoder.read_u32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ZetaIntEncodingProtocol {
+    k: u8,
+}
+
+impl ZetaIntEncodingProtocol {
+    /// Constructs a new instance of *ZetaIntEncodingProtocol* with shrinking
+    /// factor *k*. Larger values of *k* give shorter encodings for large values,
+    /// but longer encodings for small values. Boldi and Vigna found *k = 3* to
+    /// work well for web graphs.
+    ///
+    /// Panics if *k* is 0 or larger than 64.
+    pub const fn new(k: u8) -> Self {
+        if k < 1 || k > 64 {
+            panic!("Invalid shrinking factor");
+        }
+        ZetaIntEncodingProtocol { k }
+    }
+
+    /// Gets the shrinking factor *k* of this protocol
+    pub fn get_k(&self) -> u8 {
+        self.k
+    }
+
+    fn find_interval(&self, value: u128) -> u32 {
+        let max_interval = get_zeta_max_interval(self.k);
+        let mut interval = 0;
+        while interval < max_interval
+            && value - get_zeta_interval_start(self.k, interval)
+                >= get_zeta_interval_size(self.k, interval)
+        {
+            interval += 1;
+        }
+        interval
+    }
+
+    fn write_unsigned(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        let interval = self.find_interval(value);
+        let mut bits = vec![false; interval as usize];
+        bits.push(true);
+
+        let offset = value - get_zeta_interval_start(self.k, interval);
+        let interval_size = get_zeta_interval_size(self.k, interval);
+        let (num_bits, num_short_values) = get_minimal_binary_parameters(interval_size);
+        let (num_bits, binary) = match offset < num_short_values {
+            true => (num_bits - 1, offset),
+            false => (num_bits, offset + num_short_values),
+        };
+        for index in (0..num_bits).rev() {
+            bits.push(binary & 1 << index != 0);
+        }
+        sink.write(&bits)
+    }
+
+    fn unsigned_size(&self, value: u128) -> u32 {
+        let interval = self.find_interval(value);
+        let offset = value - get_zeta_interval_start(self.k, interval);
+        let interval_size = get_zeta_interval_size(self.k, interval);
+        let (num_bits, num_short_values) = get_minimal_binary_parameters(interval_size);
+        match offset < num_short_values {
+            true => interval + num_bits,
+            false => interval + 1 + num_bits,
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ZetaIntEncodingProtocol {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.int_in_range(1..=64)?))
+    }
+}
+
+impl IntEncodingProtocol for ZetaIntEncodingProtocol {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value as u128)
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value as i128))
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, value)
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        self.write_unsigned(sink, zigzag_encode(value))
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        self.unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        self.unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        self.unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        self.unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        self.unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        self.unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        self.unsigned_size(value as u128)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        self.unsigned_size(zigzag_encode(value as i128))
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        self.unsigned_size(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        self.unsigned_size(zigzag_encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use crate::testkit::*;
+
+    const ENCODER: ZetaIntEncodingProtocol = ZetaIntEncodingProtocol::new(3);
+    const DECODER: ZetaIntDecodingProtocol = ZetaIntDecodingProtocol::new(3);
+
+    #[test]
+    fn test_symmetry() {
+        for k in &[1, 2, 3, 5, 7, 13, 64] {
+            let encoder = ZetaIntEncodingProtocol::new(*k);
+            let decoder = ZetaIntDecodingProtocol::new(*k);
+            test_encoding_pair(&encoder, &decoder);
+            test_skip(&encoder, &decoder);
+            test_slices(&encoder, &decoder);
+            test_encoded_sizes(&encoder);
+        }
+    }
+
+    #[test]
+    fn test_results() {
+        // Interval 0 contains 0 to 6, so 7 values: 0 is written with 2 bits,
+        // and the other values with 3 bits
+        test_u8_result(&ENCODER, &DECODER, 0, "1 00");
+        test_u8_result(&ENCODER, &DECODER, 1, "1 010");
+        test_u8_result(&ENCODER, &DECODER, 6, "1 111");
+        // Interval 1 contains 7 to 62, so 56 values: 8 values are written with
+        // 5 bits, and the other values with 6 bits
+        test_u8_result(&ENCODER, &DECODER, 7, "01 00000");
+        test_u8_result(&ENCODER, &DECODER, 14, "01 00111");
+        test_u8_result(&ENCODER, &DECODER, 15, "01 010000");
+        test_u8_result(&ENCODER, &DECODER, 62, "01 111111");
+        test_i8_result(&ENCODER, &DECODER, -1, "1 010");
+        test_i8_result(&ENCODER, &DECODER, 1, "1 011");
+    }
+
+    #[test]
+    fn test_gamma() {
+        let encoder = ZetaIntEncodingProtocol::new(1);
+        for value in 0..1000u32 {
+            let expected = 2 * (31 - (value + 1).leading_zeros()) + 1;
+            assert_eq!(expected, encoder.encoded_size_u32(value));
+        }
+        // The last interval only contains u128::MAX
+        assert_eq!(129, encoder.encoded_size_u128(u128::MAX));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut sink = BoolVecBitSink::new();
+        ENCODER.write_u16(&mut sink, 300).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match DECODER.read_u8(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+
+        // Interval 43 doesn't exist
+        let bools = vec![false; 50];
+        let mut source = BoolSliceBitSource::new(&bools);
+        match DECODER.read_u128(&mut source) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_k() {
+        ZetaIntEncodingProtocol::new(65);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::*;
+
+/// A set of boolean flags that can be encoded with a *FlagSetCodec*. Every
+/// declared flag has an index smaller than *NUM_FLAGS*, and the set is
+/// represented by a mask in which bit *i* is set if and only if flag *i* is set.
+///
+/// This trait can be implemented manually, or by declaring the flag set with
+/// the *flag_set!* macro. When the *bitflags* feature is enabled, types created
+/// with the *bitflags* crate can be encoded by wrapping them in *BitFlags*.
+pub trait FlagSet: Sized {
+    /// The number of declared flags, which must be at most 64
+    const NUM_FLAGS: usize;
+
+    /// Gets the mask of this flag set. Only the lowest *NUM_FLAGS* bits may be
+    /// set.
+    fn get_flag_mask(&self) -> u64;
+
+    /// Constructs the flag set from a mask in which only the lowest *NUM_FLAGS*
+    /// bits can be set
+    fn from_flag_mask(mask: u64) -> Self;
+
+    /// Checks whether this flag set contains flags that were not declared, and
+    /// can therefore not be represented by *get_flag_mask*. The default
+    /// implementation returns false.
+    fn has_unknown_flags(&self) -> bool {
+        false
+    }
+}
+
+/// The error that a strict *FlagSetCodec* returns (wrapped in a
+/// *DecodeError::Other*) when it reads a flag that was not declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownFlags {
+    /// The mask of the encoded flags whose index is at least *NUM_FLAGS*
+    pub mask: u64,
+}
+
+impl std::fmt::Display for UnknownFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "Encountered unknown flags with mask {:#b}", self.mask)
+    }
+}
+
+impl std::error::Error for UnknownFlags {}
+
+/// Encodes flag sets (see *FlagSet*) as a fixed number of bits: one bit per
+/// flag. The codec created by *exact* uses exactly *NUM_FLAGS* bits, which is
+/// the most compact option. When new flags might be declared in the future, a
+/// codec with some reserved bits can be created with *new*, so that older
+/// decoders can still read the flag sets of newer encoders.
+///
+/// A *strict* codec returns an *UnknownFlags* error when it reads a set bit
+/// that doesn't correspond to a declared flag, whereas a lenient codec simply
+/// ignores such bits.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// flag_set! {
+///     pub struct Permissions {
+///         read,
+///         write,
+///         execute,
+///     }
+/// }
+///
+/// let codec = FlagSetCodec::exact::<Permissions>();
+/// let permissions = Permissions { read: true, write: false, execute: true };
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, &permissions).unwrap();
+/// assert_eq!(&[true, false, true], sink.get_bits());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(permissions, codec.read(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagSetCodec {
+    num_bits: u8,
+    strict: bool,
+}
+
+impl FlagSetCodec {
+    /// Constructs a new *FlagSetCodec* that uses *num_bits* bits for each flag
+    /// set. See the documentation of *FlagSetCodec* for the meaning of *strict*.
+    ///
+    /// Panics if *num_bits* is larger than 64.
+    pub const fn new(num_bits: u8, strict: bool) -> Self {
+        if num_bits > 64 {
+            panic!("The number of bits must be at most 64");
+        }
+        Self { num_bits, strict }
+    }
+
+    /// Constructs a strict *FlagSetCodec* that uses exactly 1 bit per declared
+    /// flag of *F*.
+    ///
+    /// Panics if *F* declares more than 64 flags.
+    pub fn exact<F: FlagSet>() -> Self {
+        if F::NUM_FLAGS > 64 {
+            panic!("A flag set can have at most 64 flags");
+        }
+        Self::new(F::NUM_FLAGS as u8, true)
+    }
+
+    /// Gets the number of bits that are used to encode each flag set
+    pub fn get_num_bits(&self) -> u8 {
+        self.num_bits
+    }
+
+    /// Checks whether this codec rejects unknown flags
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Writes *flags* to *sink*. Returns an error if *flags* contains unknown
+    /// flags, or when *F* declares more flags than this codec has bits.
+    pub fn write<F: FlagSet>(&self, sink: &mut impl BitSink, flags: &F) -> Result<(), WriteError> {
+        if F::NUM_FLAGS > self.num_bits as usize {
+            return Err(format!(
+                "Can't encode {} flags with only {} bits",
+                F::NUM_FLAGS,
+                self.num_bits
+            )
+            .into());
+        }
+        let mask = flags.get_flag_mask();
+        if flags.has_unknown_flags() || (mask as u128) >> F::NUM_FLAGS != 0 {
+            return Err("Can't encode unknown flags".into());
+        }
+        SimpleIntEncodingProtocol::new().write_unsigned(sink, self.num_bits as usize, mask as u128)
+    }
+
+    /// Reads a flag set that was written by *write* (using a codec with the
+    /// same number of bits) from *source*
+    pub fn read<F: FlagSet>(&self, source: &mut impl BitSource) -> Result<F, DecodeError> {
+        let mask =
+            SimpleIntDecodingProtocol::new().read_unsigned(source, self.num_bits as usize)? as u64;
+        let unknown_mask = ((mask as u128) >> F::NUM_FLAGS << F::NUM_FLAGS) as u64;
+        if unknown_mask != 0 && self.strict {
+            return Err(DecodeError::Other(Box::new(UnknownFlags {
+                mask: unknown_mask,
+            })));
+        }
+        Ok(F::from_flag_mask(mask & !unknown_mask))
+    }
+}
+
+/// Declares a struct with a public *bool* field for each flag, and implements
+/// *FlagSet* for it. The index of each flag is its position in the declaration.
+/// The struct derives *Debug*, *Clone*, *Copy*, *PartialEq*, *Eq*, *Hash* and
+/// *Default* (which has no flags set).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// flag_set! {
+///     /// The styles of a piece of text
+///     pub struct TextStyle {
+///         bold,
+///         italic,
+///         underlined,
+///     }
+/// }
+///
+/// assert_eq!(3, TextStyle::NUM_FLAGS);
+/// let style = TextStyle { italic: true, ..TextStyle::default() };
+/// assert_eq!(0b10, style.get_flag_mask());
+/// assert_eq!(style, TextStyle::from_flag_mask(0b10));
+/// ```
+#[macro_export]
+macro_rules! flag_set {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field:ident),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        $vis struct $name {
+            $($(#[$field_meta])* pub $field: bool),+
+        }
+
+        impl $crate::FlagSet for $name {
+            const NUM_FLAGS: usize = [$(stringify!($field)),+].len();
+
+            fn get_flag_mask(&self) -> u64 {
+                [$(self.$field),+]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, value)| **value)
+                    .fold(0, |mask, (index, _)| mask | (1 << index))
+            }
+
+            fn from_flag_mask(mask: u64) -> Self {
+                let mut flags = (0..Self::NUM_FLAGS).map(|index| mask & (1 << index) != 0);
+                Self {
+                    $($field: flags.next().unwrap()),+
+                }
+            }
+        }
+    };
+}
+
+/// Wraps a flag type created with the *bitflags* crate, so that it can be
+/// encoded with a *FlagSetCodec*. Flag *i* of the wrapped type is the *i*-th
+/// flag of *F::FLAGS*, and all bits that are not covered by any of these flags
+/// are considered unknown.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// bitflags::bitflags! {
+///     #[derive(Debug, PartialEq, Eq)]
+///     struct Sides: u32 {
+///         const TOP = 1;
+///         const BOTTOM = 1 << 5;
+///         const LEFT = 1 << 10;
+///     }
+/// }
+///
+/// let codec = FlagSetCodec::exact::<BitFlags<Sides>>();
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, &BitFlags(Sides::TOP | Sides::LEFT)).unwrap();
+/// assert_eq!(&[true, false, true], sink.get_bits());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let decoded: BitFlags<Sides> = codec.read(&mut source).unwrap();
+/// assert_eq!(Sides::TOP | Sides::LEFT, decoded.0);
+/// ```
+#[cfg(feature = "bitflags")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitFlags<F>(pub F);
+
+#[cfg(feature = "bitflags")]
+impl<F: bitflags::Flags> FlagSet for BitFlags<F> {
+    const NUM_FLAGS: usize = F::FLAGS.len();
+
+    fn get_flag_mask(&self) -> u64 {
+        F::FLAGS
+            .iter()
+            .enumerate()
+            .filter(|(_, flag)| self.0.contains(F::from_bits_retain(flag.value().bits())))
+            .fold(0, |mask, (index, _)| mask | (1 << index))
+    }
+
+    fn from_flag_mask(mask: u64) -> Self {
+        let mut flags = F::empty();
+        for (index, flag) in F::FLAGS.iter().enumerate() {
+            if mask & (1 << index) != 0 {
+                flags.insert(F::from_bits_retain(flag.value().bits()));
+            }
+        }
+        BitFlags(flags)
+    }
+
+    fn has_unknown_flags(&self) -> bool {
+        F::from_bits(self.0.bits()).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    flag_set! {
+        struct Flags5 {
+            a,
+            b,
+            c,
+            d,
+            e,
+        }
+    }
+
+    #[test]
+    fn test_exact() {
+        let codec = FlagSetCodec::exact::<Flags5>();
+        assert_eq!(5, codec.get_num_bits());
+        assert!(codec.is_strict());
+
+        let mut sink = BoolVecBitSink::new();
+        for mask in 0..32 {
+            codec
+                .write(&mut sink, &Flags5::from_flag_mask(mask))
+                .unwrap();
+        }
+        assert_eq!(32 * 5, sink.get_num_bools());
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for mask in 0..32 {
+            let flags: Flags5 = codec.read(&mut source).unwrap();
+            assert_eq!(mask, flags.get_flag_mask());
+        }
+    }
+
+    #[test]
+    fn test_unknown_flags() {
+        let mut sink = BoolVecBitSink::new();
+        SimpleIntEncodingProtocol::new()
+            .write_unsigned(&mut sink, 8, 0b1010_0101)
+            .unwrap();
+
+        let strict = FlagSetCodec::new(8, true);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match strict.read::<Flags5>(&mut source) {
+            Err(DecodeError::Other(error)) => assert_eq!(
+                &UnknownFlags { mask: 0b1010_0000 },
+                error.downcast_ref::<UnknownFlags>().unwrap()
+            ),
+            _ => panic!("Expected UnknownFlags"),
+        }
+
+        let lenient = FlagSetCodec::new(8, false);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let flags: Flags5 = lenient.read(&mut source).unwrap();
+        assert_eq!(
+            Flags5 {
+                a: true,
+                b: false,
+                c: true,
+                d: false,
+                e: false
+            },
+            flags
+        );
+
+        // Without unknown flags, the strict codec should be fine
+        let mut sink = BoolVecBitSink::new();
+        strict.write(&mut sink, &flags).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(flags, strict.read(&mut source).unwrap());
+    }
+
+    #[test]
+    fn test_too_few_bits() {
+        let mut sink = BoolVecBitSink::new();
+        assert!(FlagSetCodec::new(4, true)
+            .write(&mut sink, &Flags5::default())
+            .is_err());
+        assert_eq!(0, sink.get_num_bools());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_many_bits() {
+        FlagSetCodec::new(65, false);
+    }
+
+    #[cfg(feature = "bitflags")]
+    #[test]
+    fn test_bitflags() {
+        bitflags::bitflags! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            struct Mixed: u8 {
+                const A = 0b0001;
+                const B = 0b0100;
+                const BOTH = Self::A.bits() | Self::B.bits();
+                const C = 0b1000_0000;
+            }
+        }
+
+        let codec = FlagSetCodec::exact::<BitFlags<Mixed>>();
+        assert_eq!(4, codec.get_num_bits());
+
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&mut sink, &BitFlags(Mixed::A | Mixed::C))
+            .unwrap();
+        codec.write(&mut sink, &BitFlags(Mixed::BOTH)).unwrap();
+        assert!(codec
+            .write(&mut sink, &BitFlags(Mixed::from_bits_retain(0b10)))
+            .is_err());
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let first: BitFlags<Mixed> = codec.read(&mut source).unwrap();
+        let second: BitFlags<Mixed> = codec.read(&mut source).unwrap();
+        assert_eq!(Mixed::A | Mixed::C, first.0);
+        assert_eq!(Mixed::BOTH, second.0);
+    }
+}
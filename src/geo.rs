@@ -0,0 +1,307 @@
+use crate::*;
+
+/// The number of meters per degree of latitude (or longitude at the equator),
+/// using the equatorial radius of the WGS 84 ellipsoid
+const METERS_PER_DEGREE: f64 = 6_378_137.0 * std::f64::consts::PI / 180.0;
+
+/// Encodes latitude/longitude pairs (in degrees) by quantizing them to a fixed
+/// number of bits each. The latitude range [-90, 90] is divided into
+/// *2^latitude_bits - 1* equal steps (so both poles can be represented exactly),
+/// and the longitude range [-180, 180) is divided into *2^longitude_bits* equal
+/// steps (longitude 180 is the same as -180, so it wraps around). Every value is
+/// rounded to the nearest step, so the maximum error is half a step, which can
+/// be computed with *get_max_latitude_error* and *get_max_longitude_error*.
+///
+/// | bits per coordinate | max error (meters) |
+/// |---------------------|--------------------|
+/// | 16                  | ~340               |
+/// | 20                  | ~21                |
+/// | 24                  | ~1.3               |
+/// | 26                  | ~0.34              |
+/// | 32                  | ~0.005             |
+///
+/// The errors in meters are upper bounds at the equator: the longitude error
+/// becomes smaller closer to the poles.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let codec = GeoCodec::METER;
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, 52.0116, 4.3571).unwrap();
+/// assert_eq!(52, sink.get_num_bools());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let (latitude, longitude) = codec.read(&mut source).unwrap();
+/// assert!((latitude - 52.0116).abs() <= codec.get_max_latitude_error());
+/// assert!((longitude - 4.3571).abs() <= codec.get_max_longitude_error());
+/// assert!(codec.get_max_error_meters() < 1.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeoCodec {
+    latitude_bits: u8,
+    longitude_bits: u8,
+    policy: RangePolicy,
+}
+
+impl GeoCodec {
+    /// Uses 26 bits per coordinate (52 bits in total), which gives a maximum
+    /// error of less than 1 meter
+    pub const METER: GeoCodec = GeoCodec::new(26, 26);
+
+    /// Constructs a new *GeoCodec* that uses *latitude_bits* bits for the
+    /// latitude and *longitude_bits* bits for the longitude.
+    ///
+    /// Panics if any of them is 0 or larger than 32.
+    pub const fn new(latitude_bits: u8, longitude_bits: u8) -> Self {
+        if latitude_bits == 0 || latitude_bits > 32 {
+            panic!("The number of latitude bits must be between 1 and 32");
+        }
+        if longitude_bits == 0 || longitude_bits > 32 {
+            panic!("The number of longitude bits must be between 1 and 32");
+        }
+        Self {
+            latitude_bits,
+            longitude_bits,
+            policy: RangePolicy::Error,
+        }
+    }
+
+    /// Sets the *RangePolicy* that decides what *write* does with latitudes
+    /// outside [-90, 90] and longitudes outside [-180, 180]. The default policy
+    /// is *RangePolicy::Error*. *RangePolicy::Wrap* is mostly useful for
+    /// longitudes: it wraps them around the globe, so 190 becomes -170.
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let codec = GeoCodec::METER.with_policy(RangePolicy::Saturate);
+    /// let mut sink = BoolVecBitSink::new();
+    /// codec.write(&mut sink, 90.0001, 12.0).unwrap();
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let (latitude, _longitude) = codec.read(&mut source).unwrap();
+    /// assert_eq!(90.0, latitude);
+    /// ```
+    pub const fn with_policy(mut self, policy: RangePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Gets the *RangePolicy* of this codec
+    pub fn get_policy(&self) -> RangePolicy {
+        self.policy
+    }
+
+    /// Gets the number of bits that are used to encode each latitude
+    pub fn get_latitude_bits(&self) -> u8 {
+        self.latitude_bits
+    }
+
+    /// Gets the number of bits that are used to encode each longitude
+    pub fn get_longitude_bits(&self) -> u8 {
+        self.longitude_bits
+    }
+
+    fn max_latitude_step(&self) -> f64 {
+        ((1u64 << self.latitude_bits) - 1) as f64
+    }
+
+    fn num_longitude_steps(&self) -> f64 {
+        (1u64 << self.longitude_bits) as f64
+    }
+
+    /// Gets the maximum difference (in degrees) between a latitude and its
+    /// decoded value
+    pub fn get_max_latitude_error(&self) -> f64 {
+        90.0 / self.max_latitude_step()
+    }
+
+    /// Gets the maximum difference (in degrees) between a longitude and its
+    /// decoded value (where -180 and 180 are considered equal)
+    pub fn get_max_longitude_error(&self) -> f64 {
+        180.0 / self.num_longitude_steps()
+    }
+
+    /// Gets the maximum distance (in meters) between a position and its decoded
+    /// position. This is computed at the equator, where the longitude error is
+    /// largest.
+    pub fn get_max_error_meters(&self) -> f64 {
+        let latitude_error = self.get_max_latitude_error();
+        let longitude_error = self.get_max_longitude_error();
+        (latitude_error * latitude_error + longitude_error * longitude_error).sqrt()
+            * METERS_PER_DEGREE
+    }
+
+    /// Quantizes *latitude* and *longitude* (in degrees) and writes them to
+    /// *sink*. When *latitude* is not in the range [-90, 90] or *longitude* is
+    /// not in the range [-180, 180], the *RangePolicy* of this codec decides
+    /// what happens. Returns an error if any of them is NaN, or when the policy
+    /// is *Error* and any of them is out of range.
+    pub fn write(
+        &self,
+        sink: &mut impl BitSink,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<(), WriteError> {
+        let latitude = match self.policy.apply_f64(latitude, -90.0, 90.0) {
+            Some(latitude) => latitude,
+            None => return Err(format!("Invalid latitude {}", latitude).into()),
+        };
+        let longitude = match self.policy.apply_f64(longitude, -180.0, 180.0) {
+            Some(longitude) => longitude,
+            None => return Err(format!("Invalid longitude {}", longitude).into()),
+        };
+
+        let quantized_latitude = ((latitude + 90.0) / 180.0 * self.max_latitude_step()).round();
+        let quantized_longitude = ((longitude + 180.0) / 360.0 * self.num_longitude_steps()).round()
+            as u64
+            % (1u64 << self.longitude_bits);
+
+        let writer = SimpleIntEncodingProtocol::new();
+        writer.write_unsigned(
+            sink,
+            self.latitude_bits as usize,
+            quantized_latitude as u128,
+        )?;
+        writer.write_unsigned(
+            sink,
+            self.longitude_bits as usize,
+            quantized_longitude as u128,
+        )
+    }
+
+    /// Reads a latitude/longitude pair (in degrees) that was written by *write*
+    pub fn read(&self, source: &mut impl BitSource) -> Result<(f64, f64), DecodeError> {
+        let reader = SimpleIntDecodingProtocol::new();
+        let quantized_latitude = reader.read_unsigned(source, self.latitude_bits as usize)?;
+        let quantized_longitude = reader.read_unsigned(source, self.longitude_bits as usize)?;
+
+        let latitude = quantized_latitude as f64 / self.max_latitude_step() * 180.0 - 90.0;
+        let longitude = quantized_longitude as f64 / self.num_longitude_steps() * 360.0 - 180.0;
+        Ok((latitude, longitude))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn longitude_difference(a: f64, b: f64) -> f64 {
+        let difference = (a - b).abs();
+        difference.min(360.0 - difference)
+    }
+
+    fn check_round_trip(codec: GeoCodec, latitude: f64, longitude: f64) {
+        let mut sink = BoolVecBitSink::new();
+        codec.write(&mut sink, latitude, longitude).unwrap();
+        assert_eq!(
+            (codec.get_latitude_bits() + codec.get_longitude_bits()) as u64,
+            sink.get_num_bools()
+        );
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let (decoded_latitude, decoded_longitude) = codec.read(&mut source).unwrap();
+        assert!((-90.0..=90.0).contains(&decoded_latitude));
+        assert!((-180.0..180.0).contains(&decoded_longitude));
+
+        // Allow a tiny bit of extra error for floating point rounding
+        let tolerance = 1e-9;
+        assert!(
+            (decoded_latitude - latitude).abs() <= codec.get_max_latitude_error() + tolerance,
+            "Latitude {} became {}",
+            latitude,
+            decoded_latitude
+        );
+        assert!(
+            longitude_difference(decoded_longitude, longitude)
+                <= codec.get_max_longitude_error() + tolerance,
+            "Longitude {} became {}",
+            longitude,
+            decoded_longitude
+        );
+    }
+
+    #[test]
+    fn test_all_hemispheres() {
+        for codec in &[
+            GeoCodec::METER,
+            GeoCodec::new(32, 32),
+            GeoCodec::new(16, 17),
+            GeoCodec::new(1, 1),
+        ] {
+            let mut latitude = -90.0;
+            while latitude <= 90.0 {
+                let mut longitude = -180.0;
+                while longitude <= 180.0 {
+                    check_round_trip(*codec, latitude, longitude);
+                    longitude += 3.7;
+                }
+                check_round_trip(*codec, latitude, 180.0);
+                latitude += 1.3;
+            }
+            check_round_trip(*codec, 90.0, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_exact_values() {
+        let mut sink = BoolVecBitSink::new();
+        let codec = GeoCodec::METER;
+        codec.write(&mut sink, -90.0, -180.0).unwrap();
+        codec.write(&mut sink, 90.0, 180.0).unwrap();
+        codec.write(&mut sink, 0.0, 0.0).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!((-90.0, -180.0), codec.read(&mut source).unwrap());
+        assert_eq!((90.0, -180.0), codec.read(&mut source).unwrap());
+        assert_eq!(0.0, codec.read(&mut source).unwrap().1);
+    }
+
+    #[test]
+    fn test_max_error() {
+        assert!(GeoCodec::METER.get_max_error_meters() < 1.0);
+        assert!(GeoCodec::new(24, 24).get_max_error_meters() < 1.5);
+        assert!(GeoCodec::new(16, 16).get_max_error_meters() > 300.0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut sink = BoolVecBitSink::new();
+        let codec = GeoCodec::METER;
+        assert!(codec.write(&mut sink, 90.1, 0.0).is_err());
+        assert!(codec.write(&mut sink, 0.0, -180.1).is_err());
+        assert!(codec.write(&mut sink, f64::NAN, 0.0).is_err());
+        assert!(codec.write(&mut sink, 0.0, f64::NAN).is_err());
+        assert_eq!(0, sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(RangePolicy::Error, GeoCodec::METER.get_policy());
+        let saturate = GeoCodec::METER.with_policy(RangePolicy::Saturate);
+        let wrap = GeoCodec::METER.with_policy(RangePolicy::Wrap);
+        for codec in &[saturate, wrap] {
+            let mut sink = BoolVecBitSink::new();
+            assert!(codec.write(&mut sink, f64::NAN, 0.0).is_err());
+            assert!(codec.write(&mut sink, 0.0, f64::NAN).is_err());
+            assert_eq!(0, sink.get_num_bools());
+        }
+
+        let mut sink = BoolVecBitSink::new();
+        saturate.write(&mut sink, -95.0, 200.0).unwrap();
+        wrap.write(&mut sink, 10.0, 200.0).unwrap();
+        wrap.write(&mut sink, 10.0, -900.0).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let tolerance = GeoCodec::METER.get_max_longitude_error();
+        let (latitude, longitude) = saturate.read(&mut source).unwrap();
+        assert_eq!(-90.0, latitude);
+        assert!(longitude_difference(180.0, longitude) <= tolerance);
+        let (_latitude, longitude) = wrap.read(&mut source).unwrap();
+        assert!(longitude_difference(-160.0, longitude) <= tolerance);
+        let (_latitude, longitude) = wrap.read(&mut source).unwrap();
+        assert!(longitude_difference(180.0, longitude) <= tolerance);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::*;
+
+/// Encodes *u32*s in groups of 4 using the Group Varint format: every group
+/// starts with 1 tag byte, followed by the 4 values, which take 1 to 4 bytes
+/// each (little endian). The tag byte contains the number of bytes of each
+/// value minus 1, using 2 bits per value (the first value is stored in the 2
+/// least significant bits).
+///
+/// Unlike the varint formats that store a continuation bit in every byte, the
+/// decoder only needs to look at the tag byte to know where all 4 values are,
+/// which makes decoding much faster on modern CPUs. This makes it a good choice
+/// for throughput-sensitive users that don't need bit-level compactness.
+///
+/// The fastest way to use this codec is with *encode* and *decode*, which work
+/// directly on bytes. The *write_group* and *read_group* methods can be used to
+/// embed groups in a *BitSink* or *BitSource*. Every byte is written least
+/// significant bit first (just like *U8VecBitSink* stores bits), so groups
+/// that are written at a byte boundary of a *U8VecBitSink* result in the same
+/// bytes as *encode*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let codec = GroupVarintCodec::new();
+/// let values = [1, 300, 70000, 5, 16_000_000, 0];
+/// let bytes = codec.encode(&values);
+/// // 2 tag bytes + 1 + 2 + 3 + 1 + 3 + 1 bytes (the last group is padded with 2 zeros)
+/// assert_eq!(2 + 11 + 2, bytes.len());
+/// assert_eq!(&values[..], &codec.decode(&bytes, values.len()).unwrap()[..]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroupVarintCodec {}
+
+fn truncated(bytes: &[u8]) -> DecodeError {
+    DecodeError::Reading(ReadError::ReachedEnd {
+        read_bools: 8 * bytes.len(),
+    })
+}
+
+impl GroupVarintCodec {
+    /// Constructs a new instance of *GroupVarintCodec*. Note that this is
+    /// actually a no-op since this struct doesn't have any fields.
+    pub const fn new() -> Self {
+        GroupVarintCodec {}
+    }
+
+    fn num_bytes(value: u32) -> usize {
+        usize::max(1, (32 - value.leading_zeros() as usize).div_ceil(8))
+    }
+
+    /// Appends the tag byte and the bytes of the 4 values of *group* to *dest*
+    pub fn encode_group(&self, group: [u32; 4], dest: &mut Vec<u8>) {
+        let mut tag = 0;
+        for (index, value) in group.iter().enumerate() {
+            tag |= ((Self::num_bytes(*value) - 1) as u8) << (2 * index);
+        }
+        dest.push(tag);
+        for value in &group {
+            dest.extend_from_slice(&value.to_le_bytes()[0..Self::num_bytes(*value)]);
+        }
+    }
+
+    /// Decodes the group at the start of *bytes*, and returns its values and
+    /// the number of bytes that it occupied
+    pub fn decode_group(&self, bytes: &[u8]) -> Result<([u32; 4], usize), DecodeError> {
+        let tag = *bytes.first().ok_or_else(|| truncated(bytes))?;
+        let mut group = [0; 4];
+        let mut offset = 1;
+        for (index, value) in group.iter_mut().enumerate() {
+            let num_bytes = 1 + (tag >> (2 * index) & 3) as usize;
+            let value_bytes = bytes
+                .get(offset..offset + num_bytes)
+                .ok_or_else(|| truncated(bytes))?;
+            let mut le_bytes = [0; 4];
+            le_bytes[0..num_bytes].copy_from_slice(value_bytes);
+            *value = u32::from_le_bytes(le_bytes);
+            offset += num_bytes;
+        }
+        Ok((group, offset))
+    }
+
+    /// Encodes all *values* into bytes. If the number of values is not a
+    /// multiple of 4, the last group is padded with zeros. Note that the
+    /// number of values is not stored, so it must be passed to *decode*.
+    pub fn encode(&self, values: &[u32]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(values.len() * 5 / 4 + 5);
+        for chunk in values.chunks(4) {
+            let mut group = [0; 4];
+            group[0..chunk.len()].copy_from_slice(chunk);
+            self.encode_group(group, &mut bytes);
+        }
+        bytes
+    }
+
+    /// Decodes *num_values* values that were encoded by *encode*
+    pub fn decode(&self, bytes: &[u8], num_values: usize) -> Result<Vec<u32>, DecodeError> {
+        let mut values = Vec::with_capacity(usize::min(num_values, bytes.len()));
+        let mut offset = 0;
+        while values.len() < num_values {
+            let (group, num_bytes) = self.decode_group(&bytes[offset..])?;
+            let num_used = usize::min(4, num_values - values.len());
+            values.extend_from_slice(&group[0..num_used]);
+            offset += num_bytes;
+        }
+        Ok(values)
+    }
+
+    /// Writes the tag byte and the 4 values of *group* to *sink*
+    pub fn write_group(&self, sink: &mut impl BitSink, group: [u32; 4]) -> Result<(), WriteError> {
+        let mut bytes = Vec::with_capacity(17);
+        self.encode_group(group, &mut bytes);
+        sink.write(&bytes_to_bools(&bytes))
+    }
+
+    /// Reads a group that was written by *write_group*
+    pub fn read_group(&self, source: &mut impl BitSource) -> Result<[u32; 4], DecodeError> {
+        let mut tag = [false; 8];
+        source.read(&mut tag)?;
+        let tag = bools_to_bytes(&tag)[0];
+
+        let mut group = [0; 4];
+        for (index, value) in group.iter_mut().enumerate() {
+            let num_bytes = 1 + (tag >> (2 * index) & 3) as usize;
+            let mut bits = [false; 32];
+            source.read(&mut bits[0..8 * num_bytes])?;
+            let mut le_bytes = [0; 4];
+            le_bytes.copy_from_slice(&bools_to_bytes(&bits));
+            *value = u32::from_le_bytes(le_bytes);
+        }
+        Ok(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_group_format() {
+        let codec = GroupVarintCodec::new();
+        let mut bytes = Vec::new();
+        codec.encode_group([3, 0x1234, 0x12_3456, 0xFFFF_FFFF], &mut bytes);
+        let expected = [
+            0b11_10_01_00,
+            3,
+            0x34,
+            0x12,
+            0x56,
+            0x34,
+            0x12,
+            0xFF,
+            0xFF,
+            0xFF,
+            0xFF,
+        ];
+        assert_eq!(&expected[..], &bytes[..]);
+        assert_eq!(
+            ([3, 0x1234, 0x12_3456, 0xFFFF_FFFF], expected.len()),
+            codec.decode_group(&bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let codec = GroupVarintCodec::new();
+        let values: Vec<u32> = (0..1001).map(|index| index * index * 4001).collect();
+        for num_values in &[0, 1, 3, 4, 5, 1001] {
+            let bytes = codec.encode(&values[0..*num_values]);
+            let decoded = codec.decode(&bytes, *num_values).unwrap();
+            assert_eq!(&values[0..*num_values], &decoded[..]);
+        }
+
+        let bytes = codec.encode(&values);
+        for length in &[0, 1, bytes.len() - 1] {
+            match codec.decode(&bytes[0..*length], values.len()) {
+                Err(DecodeError::Reading(ReadError::ReachedEnd { .. })) => {}
+                other => panic!("Expected ReachedEnd, but got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sink_and_source() {
+        let codec = GroupVarintCodec::new();
+        let groups = [[0, 1, 2, 3], [1000, 0, 100_000, u32::MAX]];
+
+        let mut sink = U8VecBitSink::new();
+        for group in &groups {
+            codec.write_group(&mut sink, *group).unwrap();
+        }
+        assert_eq!(
+            &codec.encode(&[0, 1, 2, 3, 1000, 0, 100_000, u32::MAX]),
+            sink.get_bytes()
+        );
+
+        // Groups don't need to be byte-aligned in a BitSink
+        let mut sink = BoolVecBitSink::new();
+        sink.write(&[true]).unwrap();
+        for group in &groups {
+            codec.write_group(&mut sink, *group).unwrap();
+        }
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        source.read(&mut [false]).unwrap();
+        for group in &groups {
+            assert_eq!(*group, codec.read_group(&mut source).unwrap());
+        }
+        assert!(codec.read_group(&mut source).is_err());
+    }
+}
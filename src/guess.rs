@@ -0,0 +1,414 @@
+use crate::*;
+
+/// A decoding protocol that can be tried by *guess_protocol*. Unlike
+/// *IntDecodingProtocol*, this trait can be used as trait object, so that
+/// protocols of different types can be passed to *guess_protocol* together.
+///
+/// This trait is implemented for every *IntDecodingProtocol*, so it normally
+/// doesn't need to be implemented manually.
+pub trait GuessCandidate {
+    /// Reads a single integer of type *int_type* from *source*. Signed values
+    /// should be sign-extended to *u128* (like the *as* operator does).
+    fn read_value(
+        &self,
+        source: &mut BoolSliceBitSource,
+        int_type: IntType,
+    ) -> Result<u128, DecodeError>;
+}
+
+impl<D: IntDecodingProtocol> GuessCandidate for D {
+    fn read_value(
+        &self,
+        source: &mut BoolSliceBitSource,
+        int_type: IntType,
+    ) -> Result<u128, DecodeError> {
+        read_int(self, source, int_type)
+    }
+}
+
+/// Describes what *guess_protocol* should expect from the encoded data: a
+/// sequence of integers of some *IntType*, optionally within a known range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuessOptions {
+    int_type: IntType,
+    range: Option<(i128, i128)>,
+    max_sample_bits: u64,
+    max_padding_bits: u8,
+}
+
+impl GuessOptions {
+    /// Constructs new *GuessOptions* for a sequence of integers of type
+    /// *int_type*, without an expected range. By default, at most 1 million
+    /// bits are sampled, and at most 7 trailing 0 bits are allowed (which is
+    /// the padding of byte-oriented sinks like *U8VecBitSink*).
+    pub fn new(int_type: IntType) -> Self {
+        Self {
+            int_type,
+            range: None,
+            max_sample_bits: 1_000_000,
+            max_padding_bits: 7,
+        }
+    }
+
+    /// Expects all decoded values to be between *min* and *max* (inclusive).
+    /// This is the most important heuristic: values of wrong protocols are
+    /// usually way too large.
+    ///
+    /// Panics if *min* is larger than *max*.
+    pub fn with_range(mut self, min: i128, max: i128) -> Self {
+        assert!(min <= max, "Invalid range [{}, {}]", min, max);
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Reads at most *max_sample_bits* bits from the source. When the source
+    /// has more bits than that, the last value of the sample may be
+    /// incomplete, which is not counted against the candidates.
+    pub fn with_max_sample_bits(mut self, max_sample_bits: u64) -> Self {
+        self.max_sample_bits = max_sample_bits;
+        self
+    }
+
+    /// Allows at most *max_padding_bits* trailing 0 bits after the last value
+    pub fn with_max_padding_bits(mut self, max_padding_bits: u8) -> Self {
+        self.max_padding_bits = max_padding_bits;
+        self
+    }
+
+    /// Gets the type of the integers that are expected
+    pub fn get_int_type(&self) -> IntType {
+        self.int_type
+    }
+
+    /// Gets the expected range of the values, if any
+    pub fn get_range(&self) -> Option<(i128, i128)> {
+        self.range
+    }
+
+    /// Gets the maximum number of bits that will be sampled
+    pub fn get_max_sample_bits(&self) -> u64 {
+        self.max_sample_bits
+    }
+
+    /// Gets the maximum number of trailing 0 bits after the last value
+    pub fn get_max_padding_bits(&self) -> u8 {
+        self.max_padding_bits
+    }
+
+    fn is_in_range(&self, value: u128) -> bool {
+        let (min, max) = match self.range {
+            Some(range) => range,
+            None => return true,
+        };
+        if self.int_type.is_signed() {
+            let value = value as i128;
+            value >= min && value <= max
+        } else {
+            // Unsigned values above i128::MAX are larger than max
+            value <= i128::MAX as u128 && value as i128 >= min && value as i128 <= max
+        }
+    }
+}
+
+/// The result of trying one candidate protocol in *guess_protocol*
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolGuess<'a> {
+    name: &'a str,
+    num_values: u64,
+    num_in_range: u64,
+    fully_consumed: bool,
+    error: Option<String>,
+}
+
+impl<'a> ProtocolGuess<'a> {
+    /// Gets the name of the candidate, as it was passed to *guess_protocol*
+    pub fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Gets the number of values that were decoded successfully
+    pub fn get_num_values(&self) -> u64 {
+        self.num_values
+    }
+
+    /// Gets the number of decoded values that were in the expected range
+    pub fn get_num_in_range(&self) -> u64 {
+        self.num_in_range
+    }
+
+    /// Checks whether the candidate decoded the sample until its end (except
+    /// for the allowed padding bits), without errors
+    pub fn is_fully_consumed(&self) -> bool {
+        self.fully_consumed
+    }
+
+    /// Gets the error that stopped the candidate from decoding the rest of the
+    /// sample, if any
+    pub fn get_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Gets the score of this candidate, between 0.0 and 1.0. This is the
+    /// fraction of the decoded values that was in the expected range, which
+    /// is halved when the sample was not fully consumed. Candidates that
+    /// couldn't decode any value get 0.0.
+    pub fn get_score(&self) -> f64 {
+        if self.num_values == 0 {
+            return 0.0;
+        }
+        let score = self.num_in_range as f64 / self.num_values as f64;
+        match self.fully_consumed {
+            true => score,
+            false => score / 2.0,
+        }
+    }
+}
+
+/// Reads a sample from *source* and tries to decode it with each of the
+/// *candidates* (pairs of a name and a protocol), as a sequence of integers
+/// described by *options*. Returns the results of all candidates, ranked from
+/// most to least likely (by *ProtocolGuess::get_score*, where candidates that
+/// come first win ties).
+///
+/// This is a best-effort heuristic that is meant for inspecting undocumented
+/// data: the candidate that decodes the sample completely with plausible
+/// values is ranked first, but several protocols may be able to decode the
+/// same bits. An expected range (see *GuessOptions::with_range*) makes the
+/// guess much more reliable.
+///
+/// Returns an error if reading the sample fails (but running out of bits is
+/// not an error).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // Some data file with unknown encoding
+/// let mut sink = U8VecBitSink::new();
+/// let encoder = DigitIntEncodingProtocol::new(4, false);
+/// for value in 0..200u32 {
+///     encoder.write_u32(&mut sink, value * 3).unwrap();
+/// }
+/// let (bytes, _) = sink.into_bytes();
+///
+/// let simple = SimpleIntDecodingProtocol::new();
+/// let digit3 = DigitIntDecodingProtocol::new(3, false);
+/// let digit4 = DigitIntDecodingProtocol::new(4, false);
+/// let candidates: [(&str, &dyn GuessCandidate); 3] = [
+///     ("simple", &simple), ("digit:3", &digit3), ("digit:4", &digit4)
+/// ];
+/// let options = GuessOptions::new(IntType::U32).with_range(0, 1000);
+/// let guesses = guess_protocol(&mut U8SliceBitSource::new(&bytes, 0), &candidates, &options).unwrap();
+///
+/// assert_eq!("digit:4", guesses[0].get_name());
+/// assert_eq!(200, guesses[0].get_num_values());
+/// assert_eq!(1.0, guesses[0].get_score());
+/// ```
+pub fn guess_protocol<'a>(
+    source: &mut impl BitSource,
+    candidates: &[(&'a str, &dyn GuessCandidate)],
+    options: &GuessOptions,
+) -> Result<Vec<ProtocolGuess<'a>>, ReadError> {
+    let mut sample = Vec::new();
+    let mut buffer = [false; 1024];
+    let mut truncated = true;
+    while (sample.len() as u64) < options.max_sample_bits {
+        let remaining = options.max_sample_bits - sample.len() as u64;
+        let dest = &mut buffer[..u64::min(remaining, 1024) as usize];
+        match source.read(dest) {
+            Ok(()) => sample.extend_from_slice(dest),
+            Err(ReadError::ReachedEnd { read_bools }) => {
+                sample.extend_from_slice(&dest[..read_bools]);
+                truncated = false;
+                break;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    let mut guesses: Vec<ProtocolGuess> = candidates
+        .iter()
+        .map(|(name, candidate)| try_candidate(name, *candidate, &sample, truncated, options))
+        .collect();
+    // sort_by is stable, so earlier candidates win ties
+    guesses.sort_by(|a, b| b.get_score().partial_cmp(&a.get_score()).unwrap());
+    Ok(guesses)
+}
+
+fn try_candidate<'a>(
+    name: &'a str,
+    candidate: &dyn GuessCandidate,
+    sample: &[bool],
+    truncated: bool,
+    options: &GuessOptions,
+) -> ProtocolGuess<'a> {
+    let mut guess = ProtocolGuess {
+        name,
+        num_values: 0,
+        num_in_range: 0,
+        fully_consumed: false,
+        error: None,
+    };
+
+    let mut source = BoolSliceBitSource::new(sample);
+    loop {
+        let remaining = &sample[source.position() as usize..];
+        if remaining.len() <= options.max_padding_bits as usize && !remaining.contains(&true) {
+            guess.fully_consumed = true;
+            break;
+        }
+        match candidate.read_value(&mut source, options.int_type) {
+            Ok(value) => {
+                guess.num_values += 1;
+                if options.is_in_range(value) {
+                    guess.num_in_range += 1;
+                }
+            }
+            Err(DecodeError::Reading(ReadError::ReachedEnd { .. })) if truncated => {
+                // The last value of the sample was cut off
+                guess.fully_consumed = true;
+                break;
+            }
+            Err(error) => {
+                guess.error = Some(error.to_string());
+                break;
+            }
+        }
+    }
+    guess
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn encode_values(encoder: &impl IntEncodingProtocol, values: &[i64]) -> Vec<bool> {
+        let mut sink = BoolVecBitSink::new();
+        for value in values {
+            encoder.write_i64(&mut sink, *value).unwrap();
+        }
+        sink.get_bits().to_vec()
+    }
+
+    #[test]
+    fn test_guess() {
+        let values: Vec<i64> = (-500..500).map(|value| value * 7).collect();
+        let simple = SimpleIntDecodingProtocol::new();
+        let v1 = DigitIntDecodingProtocol::v1();
+        let digit5 = DigitIntDecodingProtocol::new(5, false);
+        let zeta = ZetaIntDecodingProtocol::new(3);
+        let candidates: [(&str, &dyn GuessCandidate); 4] = [
+            ("simple", &simple),
+            ("v1", &v1),
+            ("digit:5", &digit5),
+            ("zeta", &zeta),
+        ];
+        let options = GuessOptions::new(IntType::I64)
+            .with_range(-3500, 3500)
+            .with_max_padding_bits(0);
+
+        let bits = encode_values(&DigitIntEncodingProtocol::v1(), &values);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert_eq!(4, guesses.len());
+        assert_eq!("v1", guesses[0].get_name());
+        assert_eq!(1000, guesses[0].get_num_values());
+        assert_eq!(1000, guesses[0].get_num_in_range());
+        assert!(guesses[0].is_fully_consumed());
+        assert_eq!(None, guesses[0].get_error());
+        for guess in &guesses[1..] {
+            assert!(guess.get_score() < 1.0);
+        }
+
+        let bits = encode_values(&ZetaIntEncodingProtocol::new(3), &values);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert_eq!("zeta", guesses[0].get_name());
+        assert_eq!(1.0, guesses[0].get_score());
+
+        let bits = encode_values(&SimpleIntEncodingProtocol::new(), &values);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert_eq!("simple", guesses[0].get_name());
+        assert_eq!(1.0, guesses[0].get_score());
+    }
+
+    #[test]
+    fn test_sample_limit() {
+        let values: Vec<i64> = (0..1000).collect();
+        let bits = encode_values(&SimpleIntEncodingProtocol::new(), &values);
+        let simple = SimpleIntDecodingProtocol::new();
+        let candidates: [(&str, &dyn GuessCandidate); 1] = [("simple", &simple)];
+
+        // The sample ends in the middle of the 11th value
+        let options = GuessOptions::new(IntType::I64).with_max_sample_bits(700);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert_eq!(10, guesses[0].get_num_values());
+        assert!(guesses[0].is_fully_consumed());
+
+        // But without a sample limit, a cut off value is suspicious
+        let options = GuessOptions::new(IntType::I64).with_max_padding_bits(0);
+        let guesses = guess_protocol(
+            &mut BoolSliceBitSource::new(&bits[..700]),
+            &candidates,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(10, guesses[0].get_num_values());
+        assert!(!guesses[0].is_fully_consumed());
+        assert!(guesses[0].get_error().is_some());
+        assert_eq!(0.5, guesses[0].get_score());
+    }
+
+    #[test]
+    fn test_padding() {
+        let simple = SimpleIntDecodingProtocol::new();
+        let candidates: [(&str, &dyn GuessCandidate); 1] = [("simple", &simple)];
+        let mut bits = vec![true; 16];
+        bits.extend_from_slice(&[false; 5]);
+
+        let options = GuessOptions::new(IntType::U8);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert_eq!(2, guesses[0].get_num_values());
+        assert!(guesses[0].is_fully_consumed());
+
+        let options = GuessOptions::new(IntType::U8).with_max_padding_bits(4);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert!(!guesses[0].is_fully_consumed());
+
+        // Padding bits must be 0
+        bits.push(true);
+        let options = GuessOptions::new(IntType::U8);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&bits), &candidates, &options).unwrap();
+        assert!(!guesses[0].is_fully_consumed());
+    }
+
+    #[test]
+    fn test_unsigned_range() {
+        let options = GuessOptions::new(IntType::U128).with_range(-5, 5);
+        assert!(options.is_in_range(5));
+        assert!(!options.is_in_range(6));
+        assert!(!options.is_in_range(u128::MAX));
+
+        let options = GuessOptions::new(IntType::I8).with_range(-5, 5);
+        assert!(options.is_in_range(-5i8 as u128));
+        assert!(!options.is_in_range(-6i8 as u128));
+    }
+
+    #[test]
+    fn test_no_values() {
+        let simple = SimpleIntDecodingProtocol::new();
+        let candidates: [(&str, &dyn GuessCandidate); 1] = [("simple", &simple)];
+        let options = GuessOptions::new(IntType::U8);
+        let guesses =
+            guess_protocol(&mut BoolSliceBitSource::new(&[]), &candidates, &options).unwrap();
+        assert_eq!(0, guesses[0].get_num_values());
+        assert!(guesses[0].is_fully_consumed());
+        assert_eq!(0.0, guesses[0].get_score());
+    }
+}
@@ -0,0 +1,118 @@
+/// Describes one of the primitive integer types that can be encoded by an
+/// *IntEncodingProtocol*. This is useful for code that needs to know which type
+/// of integer is stored somewhere without having a value of that type, for
+/// instance to skip an encoded field with *IntDecodingProtocol::skip*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+}
+
+impl IntType {
+    /// All integer types, from small to big, unsigned before signed
+    pub const ALL: [IntType; 10] = [
+        IntType::U8,
+        IntType::I8,
+        IntType::U16,
+        IntType::I16,
+        IntType::U32,
+        IntType::I32,
+        IntType::U64,
+        IntType::I64,
+        IntType::U128,
+        IntType::I128,
+    ];
+
+    /// Gets the number of bits of this integer type (in memory)
+    pub const fn num_bits(self) -> u8 {
+        match self {
+            IntType::U8 | IntType::I8 => 8,
+            IntType::U16 | IntType::I16 => 16,
+            IntType::U32 | IntType::I32 => 32,
+            IntType::U64 | IntType::I64 => 64,
+            IntType::U128 | IntType::I128 => 128,
+        }
+    }
+
+    /// Checks whether this is a signed integer type
+    pub const fn is_signed(self) -> bool {
+        match self {
+            IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64 | IntType::I128 => true,
+            IntType::U8 | IntType::U16 | IntType::U32 | IntType::U64 | IntType::U128 => false,
+        }
+    }
+
+    /// Converts *value* to this integer type and back to *u128* using *as*,
+    /// which truncates it to the size of this type, and sign-extends it if this
+    /// type is signed
+    pub const fn cast(self, value: u128) -> u128 {
+        match self {
+            IntType::U8 => value as u8 as u128,
+            IntType::I8 => value as i8 as u128,
+            IntType::U16 => value as u16 as u128,
+            IntType::I16 => value as i16 as u128,
+            IntType::U32 => value as u32 as u128,
+            IntType::I32 => value as i32 as u128,
+            IntType::U64 => value as u64 as u128,
+            IntType::I64 => value as i64 as u128,
+            IntType::U128 | IntType::I128 => value,
+        }
+    }
+
+    /// Gets the unsigned integer type with the same number of bits as this type
+    pub const fn to_unsigned(self) -> IntType {
+        match self {
+            IntType::U8 | IntType::I8 => IntType::U8,
+            IntType::U16 | IntType::I16 => IntType::U16,
+            IntType::U32 | IntType::I32 => IntType::U32,
+            IntType::U64 | IntType::I64 => IntType::U64,
+            IntType::U128 | IntType::I128 => IntType::U128,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_num_bits() {
+        assert_eq!(8, IntType::I8.num_bits());
+        assert_eq!(32, IntType::U32.num_bits());
+        assert_eq!(128, IntType::I128.num_bits());
+    }
+
+    #[test]
+    fn test_is_signed() {
+        let num_signed = IntType::ALL.iter().filter(|t| t.is_signed()).count();
+        assert_eq!(5, num_signed);
+        assert!(IntType::I16.is_signed());
+        assert!(!IntType::U64.is_signed());
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(u128::MAX, IntType::I8.cast(255));
+        assert_eq!(255, IntType::U8.cast(u128::MAX));
+        assert_eq!(1, IntType::U16.cast(65537));
+        assert_eq!(u128::MAX, IntType::I128.cast(u128::MAX));
+    }
+
+    #[test]
+    fn test_to_unsigned() {
+        for int_type in &IntType::ALL {
+            let unsigned = int_type.to_unsigned();
+            assert!(!unsigned.is_signed());
+            assert_eq!(int_type.num_bits(), unsigned.num_bits());
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use crate::*;
+
+use std::collections::VecDeque;
+
+/// Multiplexes several logical bit streams into 1 physical *BitSink*, so that
+/// for instance every column of a table can be encoded into its own stream,
+/// while the result is still stored in 1 file. Use a *BitStreamDeinterleaver*
+/// to read the logical streams back.
+///
+/// The bits of each logical stream are buffered until *block_size* bits are
+/// available, after which they are written to the physical sink as 1 block.
+/// Every block starts with a small header: a 1 bit, the stream id (using
+/// *ceil(log2(num_streams))* bits), and the block size minus 1 (using
+/// *ceil(log2(block_size))* bits). When the interleaver is finished, the
+/// remaining (partial) blocks are written, followed by a single 0 bit that
+/// marks the end of all streams.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut interleaver = BitStreamInterleaver::new(BoolVecBitSink::new(), 2, 64);
+/// for value in 0..100u32 {
+///     encoder.write_u32(&mut interleaver.get_stream(0), value).unwrap();
+///     encoder.write_u32(&mut interleaver.get_stream(1), value * value).unwrap();
+/// }
+/// interleaver.finish().unwrap();
+/// let sink = interleaver.into_inner();
+///
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut deinterleaver = BitStreamDeinterleaver::new(source, 2, 64);
+/// // The streams can be read in any order
+/// for value in 0..100u32 {
+///     assert_eq!(value * value, decoder.read_u32(&mut deinterleaver.get_stream(1)).unwrap());
+/// }
+/// for value in 0..100u32 {
+///     assert_eq!(value, decoder.read_u32(&mut deinterleaver.get_stream(0)).unwrap());
+/// }
+/// ```
+pub struct BitStreamInterleaver<S: BitSink> {
+    sink: S,
+    buffers: Vec<Vec<bool>>,
+    num_written_bits: Vec<u64>,
+    block_size: usize,
+    id_codec: DiscriminantCodec,
+    size_codec: DiscriminantCodec,
+}
+
+impl<S: BitSink> BitStreamInterleaver<S> {
+    /// Constructs a new *BitStreamInterleaver* that writes *num_streams*
+    /// logical streams to *sink*, in blocks of at most *block_size* bits.
+    ///
+    /// Panics if *num_streams* or *block_size* is 0.
+    pub fn new(sink: S, num_streams: u32, block_size: u32) -> Self {
+        if num_streams == 0 || block_size == 0 {
+            panic!("num_streams and block_size must be positive");
+        }
+        Self {
+            sink,
+            buffers: vec![Vec::new(); num_streams as usize],
+            num_written_bits: vec![0; num_streams as usize],
+            block_size: block_size as usize,
+            id_codec: DiscriminantCodec::new(num_streams, 0),
+            size_codec: DiscriminantCodec::new(block_size, 0),
+        }
+    }
+
+    /// Gets a *BitSink* that writes to the logical stream with the given id.
+    ///
+    /// Panics if *stream_id* is not smaller than the number of streams.
+    pub fn get_stream(&mut self, stream_id: u32) -> InterleavedStreamSink<'_, S> {
+        if stream_id as usize >= self.buffers.len() {
+            panic!(
+                "Stream id {} is too large for {} streams",
+                stream_id,
+                self.buffers.len()
+            );
+        }
+        InterleavedStreamSink {
+            interleaver: self,
+            stream_id,
+        }
+    }
+
+    fn write_block(&mut self, stream_id: u32, num_bits: usize) -> Result<(), WriteError> {
+        self.sink.write(&[true])?;
+        self.id_codec.write(&mut self.sink, stream_id)?;
+        self.size_codec.write(&mut self.sink, num_bits as u32 - 1)?;
+        let buffer = &mut self.buffers[stream_id as usize];
+        let result = self.sink.write(&buffer[0..num_bits]);
+        buffer.drain(0..num_bits);
+        result
+    }
+
+    /// Writes the remaining bits of all streams, marks the end of the streams,
+    /// and finishes the physical sink. No more bits should be written after
+    /// this method has been called.
+    pub fn finish(&mut self) -> Result<(), WriteError> {
+        for stream_id in 0..self.buffers.len() {
+            let num_bits = self.buffers[stream_id].len();
+            if num_bits > 0 {
+                self.write_block(stream_id as u32, num_bits)?;
+            }
+        }
+        self.sink.write(&[false])?;
+        self.sink.finish()
+    }
+
+    /// Consumes this interleaver and returns the physical sink. Buffered bits
+    /// will be lost if *finish* wasn't called.
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+/// The *BitSink* that *BitStreamInterleaver::get_stream* returns. Its *finish*
+/// method does nothing: the interleaver itself must be finished instead.
+pub struct InterleavedStreamSink<'a, S: BitSink> {
+    interleaver: &'a mut BitStreamInterleaver<S>,
+    stream_id: u32,
+}
+
+impl<'a, S: BitSink> BitSink for InterleavedStreamSink<'a, S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let block_size = self.interleaver.block_size;
+        self.interleaver.buffers[self.stream_id as usize].extend_from_slice(bits);
+        self.interleaver.num_written_bits[self.stream_id as usize] += bits.len() as u64;
+        while self.interleaver.buffers[self.stream_id as usize].len() >= block_size {
+            self.interleaver.write_block(self.stream_id, block_size)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    /// Gets the total number of bits that have been written to this stream
+    fn get_num_bools(&self) -> u64 {
+        self.interleaver.num_written_bits[self.stream_id as usize]
+    }
+}
+
+/// Reads the logical bit streams that were written by a *BitStreamInterleaver*
+/// with the same *num_streams* and *block_size*. See the documentation of
+/// *BitStreamInterleaver* for an example.
+///
+/// Since the blocks of all streams are mixed in the physical source, reading
+/// from 1 logical stream may require the blocks of other streams to be read
+/// first. These blocks are buffered until their own stream is read. When the
+/// physical source ends before the end marker, or contains an invalid stream
+/// id, reading returns a *ReadError::Other*.
+pub struct BitStreamDeinterleaver<S: BitSource> {
+    source: S,
+    buffers: Vec<VecDeque<bool>>,
+    id_codec: DiscriminantCodec,
+    size_codec: DiscriminantCodec,
+    reached_end: bool,
+}
+
+impl<S: BitSource> BitStreamDeinterleaver<S> {
+    /// Constructs a new *BitStreamDeinterleaver* that reads *num_streams*
+    /// logical streams from *source*.
+    ///
+    /// Panics if *num_streams* or *block_size* is 0.
+    pub fn new(source: S, num_streams: u32, block_size: u32) -> Self {
+        if num_streams == 0 || block_size == 0 {
+            panic!("num_streams and block_size must be positive");
+        }
+        Self {
+            source,
+            buffers: vec![VecDeque::new(); num_streams as usize],
+            id_codec: DiscriminantCodec::new(num_streams, 0),
+            size_codec: DiscriminantCodec::new(block_size, 0),
+            reached_end: false,
+        }
+    }
+
+    /// Gets a *BitSource* that reads from the logical stream with the given id.
+    ///
+    /// Panics if *stream_id* is not smaller than the number of streams.
+    pub fn get_stream(&mut self, stream_id: u32) -> InterleavedStreamSource<'_, S> {
+        if stream_id as usize >= self.buffers.len() {
+            panic!(
+                "Stream id {} is too large for {} streams",
+                stream_id,
+                self.buffers.len()
+            );
+        }
+        InterleavedStreamSource {
+            deinterleaver: self,
+            stream_id,
+        }
+    }
+
+    /// Gets the number of bits of the given stream that have been read from
+    /// the physical source, but not from the logical stream
+    pub fn get_num_buffered_bits(&self, stream_id: u32) -> usize {
+        self.buffers[stream_id as usize].len()
+    }
+
+    /// Consumes this deinterleaver and returns the physical source
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn read_block(&mut self) -> Result<(), DecodeError> {
+        let mut has_block = [false];
+        self.source.read(&mut has_block)?;
+        if !has_block[0] {
+            self.reached_end = true;
+            return Ok(());
+        }
+        let stream_id = self.id_codec.read(&mut self.source)?;
+        let num_bits = self.size_codec.read(&mut self.source)? as usize + 1;
+        let mut bits = vec![false; num_bits];
+        self.source.read(&mut bits)?;
+        self.buffers[stream_id as usize].extend(bits);
+        Ok(())
+    }
+}
+
+/// The *BitSource* that *BitStreamDeinterleaver::get_stream* returns
+pub struct InterleavedStreamSource<'a, S: BitSource> {
+    deinterleaver: &'a mut BitStreamDeinterleaver<S>,
+    stream_id: u32,
+}
+
+impl<'a, S: BitSource> BitSource for InterleavedStreamSource<'a, S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let deinterleaver = &mut *self.deinterleaver;
+        let stream_index = self.stream_id as usize;
+        while deinterleaver.buffers[stream_index].len() < dest.len() && !deinterleaver.reached_end {
+            if let Err(error) = deinterleaver.read_block() {
+                let message = format!("Failed to read the next interleaved block: {}", error);
+                return Err(ReadError::Other(message.into()));
+            }
+        }
+
+        let buffer = &mut deinterleaver.buffers[stream_index];
+        let read_bools = usize::min(buffer.len(), dest.len());
+        for (dest_bit, bit) in dest.iter_mut().zip(buffer.drain(0..read_bools)) {
+            *dest_bit = bit;
+        }
+        if read_bools < dest.len() {
+            return Err(ReadError::ReachedEnd { read_bools });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_streams() {
+        let streams: Vec<Vec<bool>> = (0..5)
+            .map(|stream| {
+                (0..stream * 37)
+                    .map(|index| index % (stream + 2) == 0)
+                    .collect()
+            })
+            .collect();
+        for &block_size in &[1, 7, 64, 1000] {
+            let mut interleaver = BitStreamInterleaver::new(U8VecBitSink::new(), 5, block_size);
+            for index in 0..200 {
+                for (stream_id, stream) in streams.iter().enumerate() {
+                    if index < stream.len() {
+                        interleaver
+                            .get_stream(stream_id as u32)
+                            .write(&stream[index..index + 1])
+                            .unwrap();
+                    }
+                }
+            }
+            interleaver.finish().unwrap();
+            let bytes = interleaver.into_inner().get_bytes().to_vec();
+
+            let source = U8SliceBitSource::new(&bytes, 0);
+            let mut deinterleaver = BitStreamDeinterleaver::new(source, 5, block_size);
+            for stream_id in (0..5).rev() {
+                let expected = &streams[stream_id];
+                let mut actual = vec![false; expected.len()];
+                deinterleaver
+                    .get_stream(stream_id as u32)
+                    .read(&mut actual)
+                    .unwrap();
+                assert_eq!(expected, &actual);
+                match deinterleaver
+                    .get_stream(stream_id as u32)
+                    .read(&mut [false])
+                {
+                    Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(0, read_bools),
+                    _ => panic!("Expected ReachedEnd"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut interleaver = BitStreamInterleaver::new(BoolVecBitSink::new(), 3, 16);
+        interleaver.get_stream(2).write(&[true; 40]).unwrap();
+        assert_eq!(40, interleaver.get_stream(2).get_num_bools());
+        assert_eq!(0, interleaver.get_stream(1).get_num_bools());
+        interleaver.finish().unwrap();
+        let sink = interleaver.into_inner();
+        let bits = &sink.get_bits()[0..sink.get_num_bools() as usize - 5];
+
+        let mut deinterleaver = BitStreamDeinterleaver::new(BoolSliceBitSource::new(bits), 3, 16);
+        let mut dest = [false; 32];
+        deinterleaver.get_stream(2).read(&mut dest).unwrap();
+        assert_eq!([true; 32], dest);
+        match deinterleaver.get_stream(2).read(&mut [false]) {
+            Err(ReadError::Other(_)) => {}
+            _ => panic!("Expected ReadError::Other"),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_stream_id() {
+        let mut interleaver = BitStreamInterleaver::new(BoolVecBitSink::new(), 3, 16);
+        interleaver.get_stream(3);
+    }
+}
@@ -0,0 +1,792 @@
+use crate::*;
+
+use std::collections::{HashMap, HashSet};
+
+/// The key of an entry in a *KeyValueRecord*. Keys are either small integer ids
+/// (which are the most compact), or names. Names are interned by the
+/// *KeyValueEncoder*: the first time a name is written, its bytes are encoded,
+/// but every later occurrence (in the same or a later record) only encodes the
+/// index of that name.
+///
+/// Keys are ordered by their id or name, and all ids come before all names.
+/// This is the order in which a deterministic *KeyValueEncoder* writes them.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RecordKey {
+    Id(u32),
+    Name(String),
+}
+
+/// A tagged value of an entry in a *KeyValueRecord*. The tag is encoded in front
+/// of the value, so decoders can decode (and skip) values without knowing the
+/// schema of the record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordValue {
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Bytes(Vec<u8>),
+    String(String),
+    Record(KeyValueRecord),
+}
+
+/// A record of key-value pairs that can be encoded with a *KeyValueEncoder* and
+/// decoded with a *KeyValueDecoder*. The entries are kept in insertion order,
+/// and every key occurs at most once.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyValueRecord {
+    entries: Vec<(RecordKey, RecordValue)>,
+}
+
+impl KeyValueRecord {
+    /// Constructs a new empty *KeyValueRecord*
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of *key* to *value*. If *key* already had a value, it
+    /// will be replaced, and the old value will be returned.
+    pub fn insert(&mut self, key: RecordKey, value: RecordValue) -> Option<RecordValue> {
+        for entry in &mut self.entries {
+            if entry.0 == key {
+                return Some(std::mem::replace(&mut entry.1, value));
+            }
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Gets the value of *key*, if this record has one
+    pub fn get(&self, key: &RecordKey) -> Option<&RecordValue> {
+        self.entries
+            .iter()
+            .find(|entry| &entry.0 == key)
+            .map(|entry| &entry.1)
+    }
+
+    /// Gets all entries of this record, in insertion order
+    pub fn get_entries(&self) -> &[(RecordKey, RecordValue)] {
+        &self.entries
+    }
+
+    /// Gets the number of entries in this record
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether this record doesn't have any entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+const KEY_ID: [bool; 2] = [false, false];
+const KEY_NEW_NAME: [bool; 2] = [true, false];
+const KEY_NAME_INDEX: [bool; 2] = [false, true];
+
+const VALUE_TAG_BITS: usize = 3;
+const TAG_BOOL: u8 = 0;
+const TAG_UNSIGNED: u8 = 1;
+const TAG_SIGNED: u8 = 2;
+const TAG_BYTES: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_RECORD: u8 = 5;
+
+/// Encodes *KeyValueRecord*s using an *IntEncodingProtocol* for all integers
+/// (including lengths, ids and name indices). Alternatively, *from_protocols*
+/// can be used to choose a different protocol for the lengths, the ids and name
+/// indices, and the payload (the values and the bytes of strings and names).
+///
+/// Since the encoder remembers the names it has written, all records of a
+/// stream should be encoded with the same encoder, and decoded (in the same
+/// order) with the same decoder.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut record = KeyValueRecord::new();
+/// record.insert(RecordKey::Id(1), RecordValue::Unsigned(1234));
+/// record.insert(RecordKey::Name("name".to_string()), RecordValue::String("knokko".to_string()));
+///
+/// let mut sink = BoolVecBitSink::new();
+/// let protocol = DigitIntEncodingProtocol::v1();
+/// KeyValueEncoder::new(&protocol).write_record(&mut sink, &record).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoder_protocol = DigitIntDecodingProtocol::v1();
+/// let mut decoder = KeyValueDecoder::new(&decoder_protocol);
+/// assert_eq!(record, decoder.read_record(&mut source, &mut context).unwrap());
+/// ```
+///
+/// # Deterministic mode
+/// By default, the bits of a record depend on the insertion order of its
+/// entries, and on the names that the encoder wrote before (since those are
+/// interned). When identical records must always produce identical bits (for
+/// instance to hash or sign them), use *deterministic*.
+pub struct KeyValueEncoder<
+    'a,
+    P: IntEncodingProtocol,
+    L: IntEncodingProtocol = P,
+    I: IntEncodingProtocol = P,
+> {
+    lengths: &'a L,
+    ids: &'a I,
+    payload: &'a P,
+    names: HashMap<String, u32>,
+    deterministic: bool,
+}
+
+impl<'a, P: IntEncodingProtocol> KeyValueEncoder<'a, P> {
+    /// Constructs a new *KeyValueEncoder* that uses *protocol* to encode all
+    /// integers
+    pub fn new(protocol: &'a P) -> Self {
+        Self {
+            lengths: protocol,
+            ids: protocol,
+            payload: protocol,
+            names: HashMap::new(),
+            deterministic: false,
+        }
+    }
+}
+
+impl<'a, P: IntEncodingProtocol, L: IntEncodingProtocol, I: IntEncodingProtocol>
+    KeyValueEncoder<'a, P, L, I>
+{
+    /// Constructs a new *KeyValueEncoder* that uses the protocols of
+    /// *protocols* for the lengths, ids and payload. The timestamp protocol
+    /// is not used, since records don't have timestamps. When all protocols
+    /// are the same, the encoded records are identical to those of *new*.
+    pub fn from_protocols<T>(protocols: &'a ProtocolSet<L, I, T, P>) -> Self {
+        Self {
+            lengths: protocols.get_lengths(),
+            ids: protocols.get_ids(),
+            payload: protocols.get_payload(),
+            names: HashMap::new(),
+            deterministic: false,
+        }
+    }
+
+    /// Puts this encoder in deterministic mode, in which the bits of a record
+    /// only depend on its entries (and on the protocol):
+    /// - the entries are written in the order of their keys rather than in
+    ///   insertion order (also in nested records)
+    /// - names are never interned, so the encoder doesn't keep any state between
+    ///   records
+    ///
+    /// The records can still be decoded by a normal *KeyValueDecoder*. Note that
+    /// the protocol must be deterministic as well, so an *AdaptiveDigitEncoder*
+    /// can't be used for this. The float codecs of this crate (like *GeoCodec*)
+    /// are deterministic because they quantize all values and reject NaN.
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let mut first = KeyValueRecord::new();
+    /// first.insert(RecordKey::Name("b".to_string()), RecordValue::Bool(true));
+    /// first.insert(RecordKey::Id(5), RecordValue::Unsigned(12));
+    /// let mut second = KeyValueRecord::new();
+    /// second.insert(RecordKey::Id(5), RecordValue::Unsigned(12));
+    /// second.insert(RecordKey::Name("b".to_string()), RecordValue::Bool(true));
+    ///
+    /// let protocol = DigitIntEncodingProtocol::v1();
+    /// let mut encoder = KeyValueEncoder::new(&protocol).deterministic();
+    /// let mut first_sink = BoolVecBitSink::new();
+    /// encoder.write_record(&mut first_sink, &first).unwrap();
+    /// let mut second_sink = BoolVecBitSink::new();
+    /// encoder.write_record(&mut second_sink, &second).unwrap();
+    /// assert_eq!(first_sink.get_bits(), second_sink.get_bits());
+    /// ```
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Checks whether this encoder is in deterministic mode
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    fn write_key(&mut self, sink: &mut impl BitSink, key: &RecordKey) -> Result<(), WriteError> {
+        match key {
+            RecordKey::Id(id) => {
+                sink.write(&KEY_ID)?;
+                self.ids.write_u32(sink, *id)
+            }
+            RecordKey::Name(name) => match self.names.get(name) {
+                Some(index) if !self.deterministic => {
+                    sink.write(&KEY_NAME_INDEX)?;
+                    self.ids.write_u32(sink, *index)
+                }
+                _ => {
+                    sink.write(&KEY_NEW_NAME)?;
+                    self.write_bytes(sink, name.as_bytes())?;
+                    if !self.deterministic {
+                        let index = self.names.len() as u32;
+                        self.names.insert(name.clone(), index);
+                    }
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn write_bytes(&self, sink: &mut impl BitSink, bytes: &[u8]) -> Result<(), WriteError> {
+        self.lengths.write_u64(sink, bytes.len() as u64)?;
+        for byte in bytes {
+            self.payload.write_u8(sink, *byte)?;
+        }
+        Ok(())
+    }
+
+    fn write_value(
+        &mut self,
+        sink: &mut impl BitSink,
+        value: &RecordValue,
+    ) -> Result<(), WriteError> {
+        let tag = match value {
+            RecordValue::Bool(_) => TAG_BOOL,
+            RecordValue::Unsigned(_) => TAG_UNSIGNED,
+            RecordValue::Signed(_) => TAG_SIGNED,
+            RecordValue::Bytes(_) => TAG_BYTES,
+            RecordValue::String(_) => TAG_STRING,
+            RecordValue::Record(_) => TAG_RECORD,
+        };
+        let mut tag_bits = [false; VALUE_TAG_BITS];
+        for (index, bit) in tag_bits.iter_mut().enumerate() {
+            *bit = tag & (1 << index) != 0;
+        }
+        sink.write(&tag_bits)?;
+
+        match value {
+            RecordValue::Bool(value) => sink.write(&[*value]),
+            RecordValue::Unsigned(value) => self.payload.write_u64(sink, *value),
+            RecordValue::Signed(value) => self.payload.write_i64(sink, *value),
+            RecordValue::Bytes(bytes) => self.write_bytes(sink, bytes),
+            RecordValue::String(string) => self.write_bytes(sink, string.as_bytes()),
+            RecordValue::Record(record) => self.write_record(sink, record),
+        }
+    }
+
+    /// Encodes *record* and writes it to *sink*
+    pub fn write_record(
+        &mut self,
+        sink: &mut impl BitSink,
+        record: &KeyValueRecord,
+    ) -> Result<(), WriteError> {
+        self.lengths.write_u64(sink, record.entries.len() as u64)?;
+        let mut entries: Vec<&(RecordKey, RecordValue)> = record.entries.iter().collect();
+        if self.deterministic {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        for (key, value) in entries {
+            self.write_key(sink, key)?;
+            self.write_value(sink, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes *KeyValueRecord*s that were encoded by a *KeyValueEncoder*. See the
+/// documentation of *KeyValueEncoder* for an example. If the encoder was
+/// constructed with *from_protocols*, the decoder must be constructed with
+/// *from_protocols* as well, using the corresponding decoding protocols.
+///
+/// When the known keys are set (using *with_known_keys*), entries with other
+/// keys will be skipped rather than added to the decoded records. This allows
+/// old decoders to decode records of newer encoders that added more keys.
+pub struct KeyValueDecoder<
+    'a,
+    P: IntDecodingProtocol,
+    L: IntDecodingProtocol = P,
+    I: IntDecodingProtocol = P,
+> {
+    lengths: &'a L,
+    ids: &'a I,
+    payload: &'a P,
+    names: Vec<String>,
+    known_keys: Option<HashSet<RecordKey>>,
+}
+
+impl<'a, P: IntDecodingProtocol> KeyValueDecoder<'a, P> {
+    /// Constructs a new *KeyValueDecoder* that uses *protocol* to decode all
+    /// integers. It will keep all entries it decodes.
+    pub fn new(protocol: &'a P) -> Self {
+        Self {
+            lengths: protocol,
+            ids: protocol,
+            payload: protocol,
+            names: Vec::new(),
+            known_keys: None,
+        }
+    }
+}
+
+impl<'a, P: IntDecodingProtocol, L: IntDecodingProtocol, I: IntDecodingProtocol>
+    KeyValueDecoder<'a, P, L, I>
+{
+    /// Constructs a new *KeyValueDecoder* that uses the protocols of
+    /// *protocols* for the lengths, ids and payload. It will keep all entries
+    /// it decodes.
+    pub fn from_protocols<T>(protocols: &'a ProtocolSet<L, I, T, P>) -> Self {
+        Self {
+            lengths: protocols.get_lengths(),
+            ids: protocols.get_ids(),
+            payload: protocols.get_payload(),
+            names: Vec::new(),
+            known_keys: None,
+        }
+    }
+
+    /// Lets this decoder skip all entries whose key is not in *known_keys*.
+    /// This applies to nested records as well.
+    pub fn with_known_keys(mut self, known_keys: impl IntoIterator<Item = RecordKey>) -> Self {
+        self.known_keys = Some(known_keys.into_iter().collect());
+        self
+    }
+
+    fn read_key(
+        &mut self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<RecordKey, DecodeError> {
+        let mut kind = [false; 2];
+        source.read(&mut kind)?;
+        if kind == KEY_ID {
+            Ok(RecordKey::Id(self.ids.read_u32(source)?))
+        } else if kind == KEY_NEW_NAME {
+            let name = self.read_string(source, context)?;
+            self.names.push(name.clone());
+            Ok(RecordKey::Name(name))
+        } else if kind == KEY_NAME_INDEX {
+            let index = self.ids.read_u32(source)?;
+            match self.names.get(index as usize) {
+                Some(name) => Ok(RecordKey::Name(name.clone())),
+                None => Err(DecodeError::Other(
+                    format!("Unknown name index {}", index).into(),
+                )),
+            }
+        } else {
+            Err(DecodeError::Other("Invalid key kind".into()))
+        }
+    }
+
+    fn read_bytes(
+        &self,
+        source: &mut impl BitSource,
+        length: usize,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::with_capacity(length);
+        for _counter in 0..length {
+            bytes.push(self.payload.read_u8(source)?);
+        }
+        Ok(bytes)
+    }
+
+    fn read_string(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<String, DecodeError> {
+        let length = context.check_string_len(self.lengths.read_u64(source)?)?;
+        let bytes = self.read_bytes(source, length)?;
+        String::from_utf8(bytes).map_err(|error| DecodeError::InvalidUtf8 {
+            position: error.utf8_error().valid_up_to(),
+        })
+    }
+
+    fn read_value(
+        &mut self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<RecordValue, DecodeError> {
+        let mut tag_bits = [false; VALUE_TAG_BITS];
+        source.read(&mut tag_bits)?;
+        let mut tag = 0;
+        for (index, bit) in tag_bits.iter().enumerate() {
+            if *bit {
+                tag |= 1 << index;
+            }
+        }
+
+        match tag {
+            TAG_BOOL => {
+                let mut value = [false];
+                source.read(&mut value)?;
+                Ok(RecordValue::Bool(value[0]))
+            }
+            TAG_UNSIGNED => Ok(RecordValue::Unsigned(self.payload.read_u64(source)?)),
+            TAG_SIGNED => Ok(RecordValue::Signed(self.payload.read_i64(source)?)),
+            TAG_BYTES => {
+                let length = context.check_vec_len::<u8>(self.lengths.read_u64(source)?)?;
+                Ok(RecordValue::Bytes(self.read_bytes(source, length)?))
+            }
+            TAG_STRING => Ok(RecordValue::String(self.read_string(source, context)?)),
+            TAG_RECORD => {
+                context.enter()?;
+                let result = self.read_record(source, context);
+                context.exit();
+                Ok(RecordValue::Record(result?))
+            }
+            other => Err(DecodeError::Other(
+                format!("Unknown value tag {}", other).into(),
+            )),
+        }
+    }
+
+    /// Reads and decodes the next record from *source*. The number of entries
+    /// is checked against the limits of *context*, and a record that contains
+    /// the same key more than once is rejected with a *DecodeError::Other*.
+    pub fn read_record(
+        &mut self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<KeyValueRecord, DecodeError> {
+        let num_entries =
+            context.check_vec_len::<(RecordKey, RecordValue)>(self.lengths.read_u64(source)?)?;
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut decoded_keys = HashSet::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let key = self.read_key(source, context)?;
+            let value = self.read_value(source, context)?;
+            if !decoded_keys.insert(key.clone()) {
+                return Err(DecodeError::Other(
+                    format!("Duplicate key {:?}", key).into(),
+                ));
+            }
+            let is_known = match &self.known_keys {
+                Some(known_keys) => known_keys.contains(&key),
+                None => true,
+            };
+            if is_known {
+                entries.push((key, value));
+            }
+        }
+        Ok(KeyValueRecord { entries })
+    }
+
+    /// Turns this decoder into an *Iterator* that decodes records from *source*
+    /// until it runs out of bits. See *RecordIter* for more information.
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let protocol = DigitIntEncodingProtocol::v1();
+    /// let mut encoder = KeyValueEncoder::new(&protocol);
+    /// let mut sink = BoolVecBitSink::new();
+    /// for id in 0..5 {
+    ///     let mut record = KeyValueRecord::new();
+    ///     record.insert(RecordKey::Id(id), RecordValue::Bool(true));
+    ///     encoder.write_record(&mut sink, &record).unwrap();
+    /// }
+    ///
+    /// let decoder_protocol = DigitIntDecodingProtocol::v1();
+    /// let decoder = KeyValueDecoder::new(&decoder_protocol);
+    /// let source = BoolSliceBitSource::new(sink.get_bits());
+    /// let context = DecodeContext::new(DecodeLimits::new());
+    /// let mut num_records = 0;
+    /// for record in decoder.iter_records(source, context) {
+    ///     assert_eq!(1, record.unwrap().len());
+    ///     num_records += 1;
+    /// }
+    /// assert_eq!(5, num_records);
+    /// ```
+    pub fn iter_records<S: BitSource>(
+        mut self,
+        source: S,
+        mut context: DecodeContext,
+    ) -> RecordIter<
+        KeyValueRecord,
+        S,
+        impl FnMut(&mut CountingBitSource<S>) -> Result<KeyValueRecord, DecodeError> + 'a,
+    > {
+        RecordIter::new(source, move |source| self.read_record(source, &mut context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn name(name: &str) -> RecordKey {
+        RecordKey::Name(name.to_string())
+    }
+
+    fn create_record() -> KeyValueRecord {
+        let mut nested = KeyValueRecord::new();
+        nested.insert(name("x"), RecordValue::Signed(-12));
+        nested.insert(RecordKey::Id(3), RecordValue::Bool(true));
+
+        let mut record = KeyValueRecord::new();
+        record.insert(RecordKey::Id(0), RecordValue::Unsigned(u64::MAX));
+        record.insert(name("x"), RecordValue::Bytes(vec![1, 2, 3]));
+        record.insert(name("text"), RecordValue::String("hello".to_string()));
+        record.insert(name("nested"), RecordValue::Record(nested));
+        record
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let record = create_record();
+
+        let mut sink = BoolVecBitSink::new();
+        let mut kv_encoder = KeyValueEncoder::new(&encoder);
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+        let first_length = sink.get_num_bools();
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+
+        // The second record should be smaller because the names are interned
+        assert!(sink.get_num_bools() - first_length < first_length);
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut kv_decoder = KeyValueDecoder::new(&decoder);
+        assert_eq!(
+            record,
+            kv_decoder.read_record(&mut source, &mut context).unwrap()
+        );
+        assert_eq!(
+            record,
+            kv_decoder.read_record(&mut source, &mut context).unwrap()
+        );
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_protocol_set() {
+        let record = create_record();
+        let digit = DigitIntEncodingProtocol::v1();
+        let mut expected = BoolVecBitSink::new();
+        KeyValueEncoder::new(&digit)
+            .write_record(&mut expected, &record)
+            .unwrap();
+        let uniform = ProtocolSet::uniform(digit);
+        let mut actual = BoolVecBitSink::new();
+        KeyValueEncoder::from_protocols(&uniform)
+            .write_record(&mut actual, &record)
+            .unwrap();
+        assert_eq!(expected.get_bits(), actual.get_bits());
+
+        let encoders = ProtocolSet::uniform(DigitIntEncodingProtocol::v1())
+            .with_ids(SimpleIntEncodingProtocol::new())
+            .with_payload(ZetaIntEncodingProtocol::new(3));
+        let decoders = ProtocolSet::uniform(DigitIntDecodingProtocol::v1())
+            .with_ids(SimpleIntDecodingProtocol::new())
+            .with_payload(ZetaIntDecodingProtocol::new(3));
+        let mut sink = BoolVecBitSink::new();
+        let mut kv_encoder = KeyValueEncoder::from_protocols(&encoders);
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut kv_decoder = KeyValueDecoder::from_protocols(&decoders);
+        for _ in 0..2 {
+            assert_eq!(
+                record,
+                kv_decoder.read_record(&mut source, &mut context).unwrap()
+            );
+        }
+        assert!(source.read(&mut [false]).is_err());
+
+        // Decoding with the wrong id protocol should not give the same record
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let wrong = ProtocolSet::uniform(DigitIntDecodingProtocol::v1())
+            .with_payload(ZetaIntDecodingProtocol::new(3));
+        let decoded =
+            KeyValueDecoder::from_protocols(&wrong).read_record(&mut source, &mut context);
+        assert_ne!(Some(&record), decoded.as_ref().ok());
+    }
+
+    #[test]
+    fn test_skip_unknown_keys() {
+        let encoder = SimpleIntEncodingProtocol::new();
+        let decoder = SimpleIntDecodingProtocol::new();
+        let mut record = create_record();
+
+        let mut sink = BoolVecBitSink::new();
+        let mut kv_encoder = KeyValueEncoder::new(&encoder);
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+        kv_encoder.write_record(&mut sink, &record).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut kv_decoder =
+            KeyValueDecoder::new(&decoder).with_known_keys(vec![RecordKey::Id(0), name("text")]);
+
+        let mut expected = KeyValueRecord::new();
+        expected.insert(
+            RecordKey::Id(0),
+            record.get(&RecordKey::Id(0)).unwrap().clone(),
+        );
+        expected.insert(name("text"), record.get(&name("text")).unwrap().clone());
+        assert_eq!(
+            expected,
+            kv_decoder.read_record(&mut source, &mut context).unwrap()
+        );
+        assert_eq!(
+            expected,
+            kv_decoder.read_record(&mut source, &mut context).unwrap()
+        );
+
+        assert_eq!(
+            Some(RecordValue::Unsigned(u64::MAX)),
+            record.insert(RecordKey::Id(0), RecordValue::Bool(false))
+        );
+        assert_eq!(4, record.len());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let record = create_record();
+        let mut reversed = KeyValueRecord::new();
+        for (key, value) in record.get_entries().iter().rev() {
+            reversed.insert(key.clone(), value.clone());
+        }
+
+        let mut kv_encoder = KeyValueEncoder::new(&encoder).deterministic();
+        assert!(kv_encoder.is_deterministic());
+        let mut sinks = [
+            BoolVecBitSink::new(),
+            BoolVecBitSink::new(),
+            BoolVecBitSink::new(),
+        ];
+        kv_encoder.write_record(&mut sinks[0], &record).unwrap();
+        kv_encoder.write_record(&mut sinks[1], &reversed).unwrap();
+        KeyValueEncoder::new(&encoder)
+            .deterministic()
+            .write_record(&mut sinks[2], &record)
+            .unwrap();
+        assert_eq!(sinks[0].get_bits(), sinks[1].get_bits());
+        assert_eq!(sinks[0].get_bits(), sinks[2].get_bits());
+
+        // The normal decoder can still decode it, but the entries are sorted
+        let mut source = BoolSliceBitSource::new(sinks[1].get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = KeyValueDecoder::new(&decoder)
+            .read_record(&mut source, &mut context)
+            .unwrap();
+        let keys: Vec<&RecordKey> = decoded.get_entries().iter().map(|entry| &entry.0).collect();
+        assert_eq!(
+            vec![
+                &RecordKey::Id(0),
+                &name("nested"),
+                &name("text"),
+                &name("x")
+            ],
+            keys
+        );
+        assert_eq!(record.get(&name("text")), decoded.get(&name("text")));
+
+        // Nested records are sorted as well
+        match decoded.get(&name("nested")) {
+            Some(RecordValue::Record(nested)) => assert_eq!(
+                &[
+                    (RecordKey::Id(3), RecordValue::Bool(true)),
+                    (name("x"), RecordValue::Signed(-12))
+                ],
+                nested.get_entries()
+            ),
+            other => panic!("Expected a nested record, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_large_record() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let mut record = KeyValueRecord::new();
+        for id in 0..100_000 {
+            record
+                .entries
+                .push((RecordKey::Id(id), RecordValue::Unsigned(id as u64)));
+        }
+
+        let mut sink = BoolVecBitSink::new();
+        KeyValueEncoder::new(&encoder)
+            .write_record(&mut sink, &record)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert_eq!(
+            record,
+            KeyValueDecoder::new(&decoder)
+                .read_record(&mut source, &mut context)
+                .unwrap()
+        );
+
+        // The number of entries is limited like the length of a Vec
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_vec_len(1000));
+        assert!(matches!(
+            KeyValueDecoder::new(&decoder).read_record(&mut source, &mut context),
+            Err(DecodeError::BigVecLength(_))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+
+        // KeyValueRecord::insert prevents duplicate keys, so bypass it
+        let record = KeyValueRecord {
+            entries: vec![
+                (name("x"), RecordValue::Bool(true)),
+                (RecordKey::Id(1), RecordValue::Bool(true)),
+                (name("x"), RecordValue::Bool(false)),
+            ],
+        };
+        let mut sink = BoolVecBitSink::new();
+        KeyValueEncoder::new(&encoder)
+            .write_record(&mut sink, &record)
+            .unwrap();
+
+        // Duplicate keys are rejected, even when they are unknown
+        for known_keys in &[None, Some(vec![RecordKey::Id(1)])] {
+            let mut kv_decoder = KeyValueDecoder::new(&decoder);
+            if let Some(known_keys) = known_keys {
+                kv_decoder = kv_decoder.with_known_keys(known_keys.clone());
+            }
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            let mut context = DecodeContext::new(DecodeLimits::new());
+            assert!(matches!(
+                kv_decoder.read_record(&mut source, &mut context),
+                Err(DecodeError::Other(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+
+        let mut record = KeyValueRecord::new();
+        for _ in 0..5 {
+            let mut parent = KeyValueRecord::new();
+            parent.insert(RecordKey::Id(0), RecordValue::Record(record));
+            record = parent;
+        }
+
+        let mut sink = BoolVecBitSink::new();
+        KeyValueEncoder::new(&encoder)
+            .write_record(&mut sink, &record)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_depth(3));
+        match KeyValueDecoder::new(&decoder).read_record(&mut source, &mut context) {
+            Err(DecodeError::TooDeep { max_depth }) => assert_eq!(3, max_depth),
+            other => panic!("Expected TooDeep, but got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::*;
+
+/// Writes a stream of records with a stateful codec (see *SessionCodec*), where
+/// every *keyframe_interval*-th record is a *keyframe*. Before each keyframe,
+/// the codec is reset, so a keyframe can be decoded without knowing any of the
+/// previous records. All other records are *deltas*, which are encoded relative
+/// to the records before them (and are thus smaller). This allows receivers to
+/// join a stream that is already in progress: they can start decoding at any
+/// keyframe. The records must be decoded by a *KeyframeDecoder*.
+///
+/// Every record starts with 1 bit that tells whether it is a keyframe, which is
+/// written by *start_record*. The records should be framed separately (for
+/// instance, one record per datagram), so that a receiver can skip the delta
+/// records that it can't decode yet.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut encoder = KeyframeEncoder::new(DeltaEncoding::new(DigitIntEncodingProtocol::v1()), 3);
+/// let mut records = Vec::new();
+/// for value in 100..110 {
+///     let mut sink = BoolVecBitSink::new();
+///     encoder.start_record(&mut sink).unwrap();
+///     encoder.get_codec().write_u32(&mut sink, value).unwrap();
+///     records.push(sink);
+/// }
+///
+/// // This receiver joins at record 4, so it has to wait for the keyframe at record 6
+/// let mut decoder = KeyframeDecoder::new(DeltaEncoding::new(DigitIntDecodingProtocol::v1()));
+/// let mut values = Vec::new();
+/// for record in &records[4..] {
+///     let mut source = BoolSliceBitSource::new(record.get_bits());
+///     if decoder.start_record(&mut source).unwrap() {
+///         values.push(decoder.get_codec().read_u32(&mut source).unwrap());
+///     }
+/// }
+/// assert_eq!(vec![106, 107, 108, 109], values);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyframeEncoder<C> {
+    session: Session<C>,
+    keyframe_interval: u64,
+    num_records: u64,
+    num_keyframes: u64,
+    num_records_since_keyframe: u64,
+    force_keyframe: bool,
+}
+
+impl<C: SessionCodec> KeyframeEncoder<C> {
+    /// Constructs a new *KeyframeEncoder* that encodes the records with *codec*,
+    /// and emits a keyframe every *keyframe_interval* records (starting with the
+    /// first record).
+    ///
+    /// Panics if *keyframe_interval* is 0.
+    pub fn new(codec: C, keyframe_interval: u64) -> Self {
+        assert!(
+            keyframe_interval > 0,
+            "The keyframe interval must be positive"
+        );
+        Self {
+            session: Session::new(codec),
+            keyframe_interval,
+            num_records: 0,
+            num_keyframes: 0,
+            num_records_since_keyframe: 0,
+            force_keyframe: false,
+        }
+    }
+
+    /// Gets a reference to the codec, which should be used to encode the
+    /// content of the records
+    pub fn get_codec(&self) -> &C {
+        self.session.get_codec()
+    }
+
+    /// Gets a mutable reference to the codec
+    pub fn get_codec_mut(&mut self) -> &mut C {
+        self.session.get_codec_mut()
+    }
+
+    /// Gets the session that wraps the codec
+    pub fn get_session(&self) -> &Session<C> {
+        &self.session
+    }
+
+    /// Gets the number of records after which a keyframe is emitted
+    pub fn get_keyframe_interval(&self) -> u64 {
+        self.keyframe_interval
+    }
+
+    /// Gets the number of records that have been started
+    pub fn get_num_records(&self) -> u64 {
+        self.num_records
+    }
+
+    /// Gets the number of keyframes that have been started
+    pub fn get_num_keyframes(&self) -> u64 {
+        self.num_keyframes
+    }
+
+    /// Makes sure that the next record will be a keyframe, for instance because
+    /// a new receiver joined the stream. The keyframe after that will come
+    /// *keyframe_interval* records later.
+    pub fn request_keyframe(&mut self) {
+        self.force_keyframe = true;
+    }
+
+    /// Checks whether the next record will be a keyframe
+    pub fn is_next_keyframe(&self) -> bool {
+        self.force_keyframe
+            || self.num_keyframes == 0
+            || self.num_records_since_keyframe == self.keyframe_interval
+    }
+
+    /// Starts the next record by writing its keyframe bit to *sink*, and
+    /// resetting the codec if it is a keyframe. The content of the record
+    /// should be written with the codec afterwards. Returns true if the record
+    /// is a keyframe.
+    pub fn start_record(&mut self, sink: &mut impl BitSink) -> Result<bool, WriteError> {
+        let is_keyframe = self.is_next_keyframe();
+        sink.write(&[is_keyframe])?;
+        if is_keyframe {
+            self.session.reset();
+            self.num_keyframes += 1;
+            self.num_records_since_keyframe = 0;
+            self.force_keyframe = false;
+        }
+        self.num_records += 1;
+        self.num_records_since_keyframe += 1;
+        Ok(is_keyframe)
+    }
+}
+
+/// Reads the records that were written by a *KeyframeEncoder*. It can start at
+/// any record of the stream: records are skipped until the first keyframe
+/// arrives. When a record gets lost, the application should call *lose_sync*,
+/// so that the decoder waits for the next keyframe.
+#[derive(Debug, Clone)]
+pub struct KeyframeDecoder<C> {
+    session: Session<C>,
+    is_synced: bool,
+    num_skipped_records: u64,
+}
+
+impl<C: SessionCodec> KeyframeDecoder<C> {
+    /// Constructs a new *KeyframeDecoder* that decodes the records with
+    /// *codec*, which must correspond to the codec of the encoder
+    pub fn new(codec: C) -> Self {
+        Self {
+            session: Session::new(codec),
+            is_synced: false,
+            num_skipped_records: 0,
+        }
+    }
+
+    /// Gets a reference to the codec, which should be used to decode the
+    /// content of the records
+    pub fn get_codec(&self) -> &C {
+        self.session.get_codec()
+    }
+
+    /// Gets a mutable reference to the codec
+    pub fn get_codec_mut(&mut self) -> &mut C {
+        self.session.get_codec_mut()
+    }
+
+    /// Gets the session that wraps the codec
+    pub fn get_session(&self) -> &Session<C> {
+        &self.session
+    }
+
+    /// Checks whether the decoder has received a keyframe since it was
+    /// constructed (or since the last call to *lose_sync*)
+    pub fn is_synced(&self) -> bool {
+        self.is_synced
+    }
+
+    /// Gets the number of records that were skipped because the decoder wasn't
+    /// synced
+    pub fn get_num_skipped_records(&self) -> u64 {
+        self.num_skipped_records
+    }
+
+    /// Tells the decoder that one or more records were lost, so it must skip
+    /// all records until the next keyframe
+    pub fn lose_sync(&mut self) {
+        self.is_synced = false;
+    }
+
+    /// Starts the next record by reading its keyframe bit from *source*, and
+    /// resetting the codec if it is a keyframe. Returns true if the content of
+    /// the record can be decoded with the codec, or false if the record is a
+    /// delta while the decoder is not synced. In the latter case, the rest of
+    /// the record must be skipped.
+    pub fn start_record(&mut self, source: &mut impl BitSource) -> Result<bool, DecodeError> {
+        let mut is_keyframe = [false];
+        source.read(&mut is_keyframe)?;
+        if is_keyframe[0] {
+            self.session.reset();
+            self.is_synced = true;
+        } else if !self.is_synced {
+            self.num_skipped_records += 1;
+        }
+        Ok(self.is_synced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn encode_records(keyframe_interval: u64, values: &[u64]) -> Vec<BoolVecBitSink> {
+        let mut encoder = KeyframeEncoder::new(
+            DeltaEncoding::new(DigitIntEncodingProtocol::v1()),
+            keyframe_interval,
+        );
+        let mut records = Vec::new();
+        for (index, value) in values.iter().enumerate() {
+            if index == 7 {
+                encoder.request_keyframe();
+            }
+            let mut sink = BoolVecBitSink::new();
+            encoder.start_record(&mut sink).unwrap();
+            encoder.get_codec().write_u64(&mut sink, *value).unwrap();
+            records.push(sink);
+        }
+        // Keyframes at 0, 4, 7 and 11
+        assert_eq!(4, encoder.get_num_keyframes());
+        records
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let values: Vec<u64> = (0..14).map(|index| 1_000_000 + index * 10).collect();
+        let records = encode_records(4, &values);
+        let keyframes: Vec<usize> = (0..records.len())
+            .filter(|index| records[*index].get_bits()[0])
+            .collect();
+        assert_eq!(vec![0, 4, 7, 11], keyframes);
+
+        // Deltas are much smaller than keyframes
+        assert!(records[1].get_num_bools() * 2 < records[0].get_num_bools());
+
+        for start in 0..records.len() {
+            let mut decoder =
+                KeyframeDecoder::new(DeltaEncoding::new(DigitIntDecodingProtocol::v1()));
+            for (index, record) in records.iter().enumerate().skip(start) {
+                let mut source = BoolSliceBitSource::new(record.get_bits());
+                if decoder.start_record(&mut source).unwrap() {
+                    assert_eq!(
+                        values[index],
+                        decoder.get_codec().read_u64(&mut source).unwrap()
+                    );
+                } else {
+                    // Only records before the first keyframe are skipped
+                    assert!(!keyframes
+                        .iter()
+                        .any(|keyframe| (start..=index).contains(keyframe)));
+                }
+            }
+            let first_keyframe = keyframes.iter().find(|keyframe| **keyframe >= start);
+            let expected_skipped = first_keyframe.unwrap_or(&records.len()) - start;
+            assert_eq!(expected_skipped as u64, decoder.get_num_skipped_records());
+        }
+    }
+
+    #[test]
+    fn test_lose_sync() {
+        let values: Vec<u64> = (0..14).map(|index| index * index).collect();
+        let records = encode_records(4, &values);
+
+        let mut decoder = KeyframeDecoder::new(DeltaEncoding::new(DigitIntDecodingProtocol::v1()));
+        let mut decoded = Vec::new();
+        for (index, record) in records.iter().enumerate() {
+            // Record 2 gets lost
+            if index == 2 {
+                decoder.lose_sync();
+                continue;
+            }
+            let mut source = BoolSliceBitSource::new(record.get_bits());
+            if decoder.start_record(&mut source).unwrap() {
+                decoded.push(decoder.get_codec().read_u64(&mut source).unwrap());
+            }
+        }
+        let mut expected = vec![0, 1];
+        expected.extend_from_slice(&values[4..]);
+        assert_eq!(expected, decoded);
+        assert_eq!(1, decoder.get_num_skipped_records());
+        assert!(decoder.is_synced());
+    }
+}
@@ -0,0 +1,340 @@
+use crate::*;
+
+/// The error that *BitLayout::write* returns when a value doesn't fit in the
+/// bits of its field (and the *RangePolicy* of the layout is *Error*)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldOutOfRange {
+    /// The name of the field
+    pub field: String,
+    /// The value that was given for the field
+    pub value: i128,
+    /// The smallest value that fits in the field
+    pub min: i128,
+    /// The largest value that fits in the field
+    pub max: i128,
+}
+
+impl std::fmt::Display for FieldOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Value {} of field {} is not in the range [{}, {}]",
+            self.value, self.field, self.min, self.max
+        )
+    }
+}
+
+impl std::error::Error for FieldOutOfRange {}
+
+/// A field of a *BitLayout*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitField {
+    name: String,
+    num_bits: u8,
+    signed: bool,
+}
+
+impl BitField {
+    /// Gets the name of this field
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the number of bits of this field
+    pub fn get_num_bits(&self) -> u8 {
+        self.num_bits
+    }
+
+    /// Checks whether this field stores signed values (in two's complement)
+    pub fn is_signed(&self) -> bool {
+        self.signed
+    }
+
+    /// Gets the smallest value that fits in this field
+    pub fn get_min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.num_bits - 1))
+        } else {
+            0
+        }
+    }
+
+    /// Gets the largest value that fits in this field
+    pub fn get_max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.num_bits - 1)) - 1
+        } else {
+            (1 << self.num_bits) - 1
+        }
+    }
+}
+
+/// Describes a fixed sequence of fields with explicit bit widths, like the
+/// fields of a hardware register or the header of a binary protocol. Unlike the
+/// *IntEncodingProtocol*s, every field always takes the same number of bits, so
+/// the total size of the layout is known in advance.
+///
+/// The fields are written in the order in which they were added, and the bits
+/// of each value are written from least significant to most significant (like
+/// *SimpleIntEncodingProtocol*). Signed fields use two's complement.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let header = BitLayout::new()
+///     .unsigned("opcode", 3)
+///     .unsigned("length", 11)
+///     .flag("last");
+/// assert_eq!(15, header.get_num_bits());
+///
+/// let mut sink = BoolVecBitSink::new();
+/// header.write(&mut sink, &[5, 1200, 1]).unwrap();
+/// assert_eq!(15, sink.get_num_bools());
+///
+/// // 2048 doesn't fit in 11 bits
+/// assert!(header.write(&mut BoolVecBitSink::new(), &[5, 2048, 1]).is_err());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let values = header.read(&mut source).unwrap();
+/// assert_eq!(1200, values[header.get_index("length").unwrap()]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitLayout {
+    fields: Vec<BitField>,
+    policy: RangePolicy,
+}
+
+impl BitLayout {
+    /// Constructs a new *BitLayout* without any fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_field(mut self, name: &str, num_bits: u8, signed: bool) -> Self {
+        if num_bits == 0 || num_bits > 64 {
+            panic!(
+                "Field {} has {} bits, but must have 1 to 64",
+                name, num_bits
+            );
+        }
+        if self.get_index(name).is_some() {
+            panic!("Duplicate field {}", name);
+        }
+        self.fields.push(BitField {
+            name: name.to_string(),
+            num_bits,
+            signed,
+        });
+        self
+    }
+
+    /// Adds a field with the given *name* that stores values from 0 to
+    /// 2^*num_bits* - 1.
+    ///
+    /// Panics if *num_bits* is not between 1 and 64, or if this layout already
+    /// has a field with the same name.
+    pub fn unsigned(self, name: &str, num_bits: u8) -> Self {
+        self.add_field(name, num_bits, false)
+    }
+
+    /// Adds a field with the given *name* that stores values from
+    /// -2^(*num_bits* - 1) to 2^(*num_bits* - 1) - 1.
+    ///
+    /// Panics if *num_bits* is not between 1 and 64, or if this layout already
+    /// has a field with the same name.
+    pub fn signed(self, name: &str, num_bits: u8) -> Self {
+        self.add_field(name, num_bits, true)
+    }
+
+    /// Adds a 1-bit unsigned field with the given *name*, whose value is 0 or 1.
+    ///
+    /// Panics if this layout already has a field with the same name.
+    pub fn flag(self, name: &str) -> Self {
+        self.add_field(name, 1, false)
+    }
+
+    /// Sets the *RangePolicy* that decides what *write* does with values that
+    /// don't fit in their field. The default policy is *RangePolicy::Error*.
+    ///
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let layout = BitLayout::new()
+    ///     .unsigned("brightness", 4)
+    ///     .with_policy(RangePolicy::Saturate);
+    /// let mut sink = BoolVecBitSink::new();
+    /// layout.write(&mut sink, &[20]).unwrap();
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// assert_eq!(vec![15], layout.read(&mut source).unwrap());
+    /// ```
+    pub fn with_policy(mut self, policy: RangePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Gets the *RangePolicy* of this layout
+    pub fn get_policy(&self) -> RangePolicy {
+        self.policy
+    }
+
+    /// Gets the fields of this layout, in order
+    pub fn get_fields(&self) -> &[BitField] {
+        &self.fields
+    }
+
+    /// Gets the index of the field with the given name, or *None* if this layout
+    /// doesn't have such a field
+    pub fn get_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    /// Gets the total number of bits of all fields
+    pub fn get_num_bits(&self) -> u32 {
+        self.fields.iter().map(|field| field.num_bits as u32).sum()
+    }
+
+    /// Writes *values* to *sink*, where *values[i]* is the value of the i-th
+    /// field. Values that don't fit in their field are handled according to the
+    /// *RangePolicy* of this layout. Returns an error if the number of values is
+    /// wrong, or if a value doesn't fit in its field and the policy is *Error*
+    /// (in which case the error is a *FieldOutOfRange*). Nothing is written
+    /// when an error is returned.
+    pub fn write(&self, sink: &mut impl BitSink, values: &[i128]) -> Result<(), WriteError> {
+        if values.len() != self.fields.len() {
+            return Err(format!(
+                "Expected {} values, but got {}",
+                self.fields.len(),
+                values.len()
+            )
+            .into());
+        }
+        let mut field_values = Vec::with_capacity(values.len());
+        for (field, value) in self.fields.iter().zip(values) {
+            match self.policy.apply(*value, field.get_min(), field.get_max()) {
+                Some(field_value) => field_values.push(field_value),
+                None => {
+                    return Err(Box::new(FieldOutOfRange {
+                        field: field.name.clone(),
+                        value: *value,
+                        min: field.get_min(),
+                        max: field.get_max(),
+                    }))
+                }
+            }
+        }
+
+        let writer = SimpleIntEncodingProtocol::new();
+        for (field, value) in self.fields.iter().zip(&field_values) {
+            let mask = (1u128 << field.num_bits) - 1;
+            writer.write_unsigned(sink, field.num_bits as usize, *value as u128 & mask)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the values of all fields from *source*, in order. Every value is
+    /// in the range of its field.
+    pub fn read(&self, source: &mut impl BitSource) -> Result<Vec<i128>, DecodeError> {
+        let reader = SimpleIntDecodingProtocol::new();
+        let mut values = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let raw = reader.read_unsigned(source, field.num_bits as usize)? as i128;
+            values.push(if field.signed && raw > field.get_max() {
+                raw - (1 << field.num_bits)
+            } else {
+                raw
+            });
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_ranges() {
+        let layout = BitLayout::new()
+            .signed("small", 1)
+            .signed("big", 64)
+            .unsigned("huge", 64)
+            .flag("flag");
+        assert_eq!(130, layout.get_num_bits());
+        let fields = layout.get_fields();
+        assert_eq!((-1, 0), (fields[0].get_min(), fields[0].get_max()));
+        assert_eq!(
+            (i64::MIN as i128, i64::MAX as i128),
+            (fields[1].get_min(), fields[1].get_max())
+        );
+        assert_eq!(
+            (0, u64::MAX as i128),
+            (fields[2].get_min(), fields[2].get_max())
+        );
+        assert_eq!((0, 1), (fields[3].get_min(), fields[3].get_max()));
+        assert!(fields[1].is_signed());
+        assert_eq!("huge", fields[2].get_name());
+
+        for values in &[
+            [-1, i64::MIN as i128, u64::MAX as i128, 1],
+            [0, i64::MAX as i128, 0, 0],
+            [-1, -12345, 12345, 1],
+        ] {
+            let mut sink = BoolVecBitSink::new();
+            layout.write(&mut sink, values).unwrap();
+            assert_eq!(130, sink.get_num_bools());
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            assert_eq!(values.to_vec(), layout.read(&mut source).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let layout = BitLayout::new().unsigned("a", 4).signed("b", 4);
+        let mut sink = BoolVecBitSink::new();
+        let error = layout.write(&mut sink, &[3, 8]).unwrap_err();
+        assert_eq!(
+            &FieldOutOfRange {
+                field: "b".to_string(),
+                value: 8,
+                min: -8,
+                max: 7
+            },
+            error.downcast_ref::<FieldOutOfRange>().unwrap()
+        );
+        assert!(layout.write(&mut sink, &[-1, 0]).is_err());
+        assert!(layout.write(&mut sink, &[16, 0]).is_err());
+        assert!(layout.write(&mut sink, &[1]).is_err());
+        assert_eq!(0, sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_policies() {
+        let layout = BitLayout::new().unsigned("a", 4).signed("b", 4);
+        assert_eq!(RangePolicy::Error, layout.get_policy());
+        for (policy, expected) in &[
+            (RangePolicy::Saturate, [15, -8]),
+            (RangePolicy::Wrap, [1, 7]),
+        ] {
+            let layout = layout.clone().with_policy(*policy);
+            let mut sink = BoolVecBitSink::new();
+            layout.write(&mut sink, &[17, -9]).unwrap();
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            assert_eq!(expected.to_vec(), layout.read(&mut source).unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_name() {
+        BitLayout::new().flag("a").unsigned("a", 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_many_bits() {
+        BitLayout::new().unsigned("a", 65);
+    }
+}
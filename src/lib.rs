@@ -3,17 +3,100 @@
 //! compact sequences to disk or send them over the network, attempting to use as
 //! little disk space or bandwidth as possible.
 
-#![feature(const_if_match, const_fn, const_panic, const_loop)]
-
+mod adapter;
+mod bit_order;
+mod bit_report;
+mod canary;
+mod canonical;
+mod container;
 mod decoding;
+mod diff;
+mod discriminant;
+#[cfg(feature = "embedded-io")]
+mod embedded;
 mod encoding;
+mod flags;
+mod geo;
+mod guess;
+mod group_varint;
+mod int_type;
+mod interleave;
+mod keyframe;
+mod key_value;
+mod layout;
+mod lz77;
+mod message;
+mod orientation;
+mod pfor;
+mod prefix_code;
+mod protocol_set;
+mod range_policy;
+mod rank_select;
+mod roaring;
+mod schema;
+mod sequence;
+mod session;
 mod sink;
+mod snapshot;
 mod source;
-
+mod symbol_coder;
+mod transform;
+mod tunstall;
+mod two_pass;
+mod untagged;
+mod wavelet;
+mod zero_run;
+#[cfg(feature = "tracing")]
+mod traced;
+
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+
+pub use adapter::*;
+pub use bit_order::*;
+pub use bit_report::*;
+pub use canary::*;
+pub use canonical::*;
+pub use container::*;
 pub use decoding::*;
+pub use diff::*;
+pub use discriminant::*;
+#[cfg(feature = "embedded-io")]
+pub use embedded::*;
 pub use encoding::*;
+pub use flags::*;
+pub use geo::*;
+pub use guess::*;
+pub use group_varint::*;
+pub use int_type::*;
+pub use interleave::*;
+pub use keyframe::*;
+pub use key_value::*;
+pub use layout::*;
+pub use lz77::*;
+pub use message::*;
+pub use orientation::*;
+pub use pfor::*;
+pub use prefix_code::*;
+pub use protocol_set::*;
+pub use range_policy::*;
+pub use rank_select::*;
+pub use roaring::*;
+pub use schema::*;
+pub use sequence::*;
+pub use session::*;
 pub use sink::*;
+pub use snapshot::*;
 pub use source::*;
+pub use symbol_coder::*;
+pub use transform::*;
+pub use tunstall::*;
+pub use two_pass::*;
+pub use untagged::*;
+pub use wavelet::*;
+pub use zero_run::*;
+#[cfg(feature = "tracing")]
+pub use traced::*;
 
 /// The type to be used for encoding lengths of collections and strings. Note that
 /// this type only indicates the size in memory and usually *not* the number of
@@ -0,0 +1,353 @@
+use crate::*;
+
+use std::collections::HashMap;
+
+/// The minimum length of a back-reference
+pub const LZ77_MIN_MATCH_LENGTH: usize = 3;
+
+/// The maximum number of earlier positions that the encoder considers when it
+/// searches for the longest match
+const MAX_CHAIN_LENGTH: usize = 64;
+
+/// Keeps track of the earlier positions of every sequence of 3 bytes, so that
+/// the encoder can quickly find candidates for back-references
+struct MatchFinder {
+    /// Maps every sequence of 3 bytes to the last position where it occurred
+    head: HashMap<[u8; 3], usize>,
+    /// previous[i] is the position before i where the same 3 bytes occurred
+    previous: Vec<Option<usize>>,
+}
+
+impl MatchFinder {
+    fn new(num_bytes: usize) -> Self {
+        Self {
+            head: HashMap::new(),
+            previous: vec![None; num_bytes],
+        }
+    }
+
+    fn key(bytes: &[u8], position: usize) -> Option<[u8; 3]> {
+        if position + LZ77_MIN_MATCH_LENGTH <= bytes.len() {
+            Some([bytes[position], bytes[position + 1], bytes[position + 2]])
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, bytes: &[u8], position: usize) {
+        if let Some(key) = Self::key(bytes, position) {
+            self.previous[position] = self.head.insert(key, position);
+        }
+    }
+
+    /// Finds the longest match for the bytes at *position*, and returns its
+    /// distance and length
+    fn find(
+        &self,
+        bytes: &[u8],
+        position: usize,
+        window_size: usize,
+        max_length: usize,
+    ) -> Option<(usize, usize)> {
+        let key = Self::key(bytes, position)?;
+        // Since the first 3 bytes of every candidate are equal, every match is
+        // at least LZ77_MIN_MATCH_LENGTH bytes long
+        let max_length = max_length.min(bytes.len() - position);
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.head.get(&key).copied();
+        for _ in 0..MAX_CHAIN_LENGTH {
+            let start = match candidate {
+                Some(start) if position - start <= window_size => start,
+                _ => break,
+            };
+            let length = (0..max_length)
+                .take_while(|offset| bytes[start + offset] == bytes[position + offset])
+                .count();
+            if !matches!(best, Some((_, best_length)) if best_length >= length) {
+                best = Some((position - start, length));
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.previous[start];
+        }
+        best
+    }
+}
+
+/// An LZ77-like encoder for byte payloads, which replaces repeated parts of the
+/// payload with back-references to an earlier occurrence. It writes the length
+/// of the payload, followed by a sequence of tokens. Every token starts with 1
+/// bit that tells whether it is a literal or a back-reference:
+///
+/// - a literal is followed by the byte itself (8 bits)
+/// - a back-reference is followed by its *distance* minus 1 (how many bytes
+///   back the earlier occurrence starts) and its length minus
+///   *LZ77_MIN_MATCH_LENGTH*, both encoded with the given int protocol
+///
+/// The earlier occurrence must start within the last *window_size* bytes, and
+/// back-references are only used when they take fewer bits than the literals
+/// they replace. Back-references can overlap with the bytes they produce, so a
+/// long run of the same byte costs only 1 literal and 1 back-reference.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let text = b"to be or not to be, that is the question: to be or not to be";
+/// let codec = Lz77Codec::new(1024);
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&DigitIntEncodingProtocol::v1(), &mut sink, text).unwrap();
+/// assert!(sink.get_num_bools() < 8 * text.len() as u64 * 4 / 5);
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = codec.read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(text.to_vec(), decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lz77Codec {
+    window_size: usize,
+    max_match_length: usize,
+}
+
+impl Lz77Codec {
+    /// Constructs a new *Lz77Codec* whose back-references can point at most
+    /// *window_size* bytes back. The maximum length of a back-reference is 258
+    /// by default, and can be changed with *with_max_match_length*.
+    ///
+    /// Panics if *window_size* is 0.
+    pub fn new(window_size: usize) -> Self {
+        if window_size == 0 {
+            panic!("The window size must be positive");
+        }
+        Self {
+            window_size,
+            max_match_length: 258,
+        }
+    }
+
+    /// Returns a copy of this codec whose back-references are at most
+    /// *max_match_length* bytes long. This only affects the encoder: the decoder
+    /// accepts back-references of any length.
+    ///
+    /// Panics if *max_match_length* is smaller than *LZ77_MIN_MATCH_LENGTH*.
+    pub fn with_max_match_length(self, max_match_length: usize) -> Self {
+        if max_match_length < LZ77_MIN_MATCH_LENGTH {
+            panic!(
+                "The maximum match length must be at least {}",
+                LZ77_MIN_MATCH_LENGTH
+            );
+        }
+        Self {
+            max_match_length,
+            ..self
+        }
+    }
+
+    /// Gets the maximum distance of back-references
+    pub fn get_window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Gets the maximum length of the back-references that the encoder emits
+    pub fn get_max_match_length(&self) -> usize {
+        self.max_match_length
+    }
+
+    /// Encodes *bytes* and writes the result to *sink*. The distances and
+    /// lengths of the back-references (and the length of *bytes*) are encoded
+    /// with *protocol*.
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        bytes: &[u8],
+    ) -> Result<(), WriteError> {
+        protocol.write_u64(sink, bytes.len() as u64)?;
+
+        let literal_writer = SimpleIntEncodingProtocol::new();
+        let mut finder = MatchFinder::new(bytes.len());
+        let mut position = 0;
+        while position < bytes.len() {
+            let mut token_length = 1;
+            let found = finder.find(bytes, position, self.window_size, self.max_match_length);
+            if let Some((distance, length)) = found {
+                let encoded_distance = distance as u64 - 1;
+                let encoded_length = (length - LZ77_MIN_MATCH_LENGTH) as u64;
+                let match_bits = 1
+                    + protocol.encoded_size_u64(encoded_distance) as usize
+                    + protocol.encoded_size_u64(encoded_length) as usize;
+                if match_bits < 9 * length {
+                    sink.write(&[true])?;
+                    protocol.write_u64(sink, encoded_distance)?;
+                    protocol.write_u64(sink, encoded_length)?;
+                    token_length = length;
+                }
+            }
+            if token_length == 1 {
+                sink.write(&[false])?;
+                literal_writer.write_u8(sink, bytes[position])?;
+            }
+            for inserted in position..position + token_length {
+                finder.insert(bytes, inserted);
+            }
+            position += token_length;
+        }
+        Ok(())
+    }
+
+    /// Decodes bytes that were encoded by *write*. The length is checked
+    /// against the limits of *context* before anything is allocated. Returns
+    /// *DecodeError::Other* when a back-reference points before the start of the
+    /// payload, or further back than the window size, or past its end.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let length = context.check_vec_len::<u8>(protocol.read_u64(source)?)?;
+        let mut bytes = Vec::with_capacity(length);
+
+        let literal_reader = SimpleIntDecodingProtocol::new();
+        let mut is_match = [false];
+        while bytes.len() < length {
+            source.read(&mut is_match)?;
+            if !is_match[0] {
+                bytes.push(literal_reader.read_u8(source)?);
+                continue;
+            }
+
+            let distance = protocol.read_u64(source)?.saturating_add(1);
+            let match_length = protocol
+                .read_u64(source)?
+                .saturating_add(LZ77_MIN_MATCH_LENGTH as u64);
+            if distance > bytes.len() as u64 || distance > self.window_size as u64 {
+                return Err(DecodeError::Other(
+                    format!(
+                        "Invalid back-reference distance {} at position {}",
+                        distance,
+                        bytes.len()
+                    )
+                    .into(),
+                ));
+            }
+            if match_length > (length - bytes.len()) as u64 {
+                return Err(DecodeError::Other(
+                    format!(
+                        "Back-reference of length {} goes past the end of the payload",
+                        match_length
+                    )
+                    .into(),
+                ));
+            }
+
+            let start = bytes.len() - distance as usize;
+            for index in start..start + match_length as usize {
+                bytes.push(bytes[index]);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(codec: Lz77Codec, bytes: &[u8]) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, bytes)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = codec
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .unwrap();
+        assert_eq!(bytes, &decoded[..]);
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let codec = Lz77Codec::new(100);
+        round_trip(codec, b"");
+        round_trip(codec, b"a");
+        round_trip(codec, b"abcabc");
+        round_trip(codec, b"abcabcabcabcabcabcabcabcxyzabc");
+
+        let mut pseudo_random = Vec::new();
+        let mut state = 12345u32;
+        for _ in 0..5000 {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            pseudo_random.push((state >> 16) as u8 % 6);
+        }
+        round_trip(codec, &pseudo_random);
+        round_trip(codec.with_max_match_length(3), &pseudo_random);
+        round_trip(Lz77Codec::new(1), &pseudo_random);
+    }
+
+    #[test]
+    fn test_runs() {
+        let zeros = vec![0; 10_000];
+        let bits = round_trip(Lz77Codec::new(1), &zeros);
+        assert!(bits < 2000, "Got {} bits", bits);
+
+        // Without back-references, every byte costs 9 bits
+        let no_repetition: Vec<u8> = (0..=255).collect();
+        let bits = round_trip(Lz77Codec::new(1000), &no_repetition);
+        let mut length_sink = BoolVecBitSink::new();
+        DigitIntEncodingProtocol::v1()
+            .write_u64(&mut length_sink, 256)
+            .unwrap();
+        assert_eq!(length_sink.get_num_bools() + 9 * 256, bits);
+    }
+
+    #[test]
+    fn test_window_size() {
+        let mut bytes = b"this part repeats".to_vec();
+        bytes.extend_from_slice(&[0; 50]);
+        bytes.extend_from_slice(b"this part repeats");
+
+        let small_window = round_trip(Lz77Codec::new(20), &bytes);
+        let large_window = round_trip(Lz77Codec::new(100), &bytes);
+        assert!(large_window < small_window);
+    }
+
+    #[test]
+    fn test_invalid_back_references() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let literal_writer = SimpleIntEncodingProtocol::new();
+
+        let invalid_payload = |distance: u64, length: u64| {
+            let mut sink = BoolVecBitSink::new();
+            encoder.write_u64(&mut sink, 10).unwrap();
+            sink.write(&[false]).unwrap();
+            literal_writer.write_u8(&mut sink, 5).unwrap();
+            sink.write(&[true]).unwrap();
+            encoder.write_u64(&mut sink, distance - 1).unwrap();
+            encoder
+                .write_u64(&mut sink, length - LZ77_MIN_MATCH_LENGTH as u64)
+                .unwrap();
+            sink
+        };
+
+        let decode = |sink: &BoolVecBitSink, window_size: usize| {
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            let mut context = DecodeContext::new(DecodeLimits::new());
+            Lz77Codec::new(window_size).read(&decoder, &mut source, &mut context)
+        };
+
+        assert_eq!(vec![5; 10], decode(&invalid_payload(1, 9), 10).unwrap());
+        assert!(decode(&invalid_payload(2, 9), 10).is_err());
+        assert!(decode(&invalid_payload(1, 10), 10).is_err());
+        assert!(decode(&invalid_payload(u64::MAX, 9), 10).is_err());
+    }
+}
@@ -0,0 +1,848 @@
+use crate::*;
+
+use std::fmt::Display;
+
+/// The integer protocol that a field of a *Message* uses to encode its integer
+/// (or the length of its string or byte array)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldEncoding {
+    /// *SimpleIntEncodingProtocol*, which always uses the full width of the
+    /// integer type
+    Simple,
+    /// *DigitIntEncodingProtocol* with the given *digit_size* (and without the
+    /// short encodings for 0 and 1)
+    Digit(u8),
+    /// *VlqIntEncodingProtocol*
+    Vlq,
+    /// *ZetaIntEncodingProtocol* with the given shrinking factor *k*
+    Zeta(u8),
+}
+
+impl FieldEncoding {
+    /// Checks whether the parameter of this encoding is valid, and returns a
+    /// description of the problem if it isn't
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            FieldEncoding::Digit(digit_size) if *digit_size < 2 || *digit_size > 127 => {
+                Err(format!(
+                    "Invalid digit size {}: it must be between 2 and 127",
+                    digit_size
+                ))
+            }
+            FieldEncoding::Zeta(k) if *k < 1 || *k > 64 => Err(format!(
+                "Invalid shrinking factor {}: it must be between 1 and 64",
+                k
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for FieldEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            FieldEncoding::Simple => write!(f, "simple"),
+            FieldEncoding::Digit(digit_size) => write!(f, "digit {}", digit_size),
+            FieldEncoding::Vlq => write!(f, "vlq"),
+            FieldEncoding::Zeta(k) => write!(f, "zeta {}", k),
+        }
+    }
+}
+
+/// Runs *$body* with *$protocol* bound to the encoding protocol of *$encoding*
+macro_rules! with_encoder {
+    ($encoding:expr, $protocol:ident => $body:expr) => {
+        match $encoding {
+            FieldEncoding::Simple => {
+                let $protocol = SimpleIntEncodingProtocol::new();
+                $body
+            }
+            FieldEncoding::Digit(digit_size) => {
+                let $protocol = DigitIntEncodingProtocol::new(digit_size, false);
+                $body
+            }
+            FieldEncoding::Vlq => {
+                let $protocol = VlqIntEncodingProtocol::new();
+                $body
+            }
+            FieldEncoding::Zeta(k) => {
+                let $protocol = ZetaIntEncodingProtocol::new(k);
+                $body
+            }
+        }
+    };
+}
+
+/// Runs *$body* with *$protocol* bound to the decoding protocol of *$encoding*
+macro_rules! with_decoder {
+    ($encoding:expr, $protocol:ident => $body:expr) => {
+        match $encoding {
+            FieldEncoding::Simple => {
+                let $protocol = SimpleIntDecodingProtocol::new();
+                $body
+            }
+            FieldEncoding::Digit(digit_size) => {
+                let $protocol = DigitIntDecodingProtocol::new(digit_size, false);
+                $body
+            }
+            FieldEncoding::Vlq => {
+                let $protocol = VlqIntDecodingProtocol::new();
+                $body
+            }
+            FieldEncoding::Zeta(k) => {
+                let $protocol = ZetaIntDecodingProtocol::new(k);
+                $body
+            }
+        }
+    };
+}
+
+/// The type of a field of a *Message*
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    /// A single bit
+    Bool,
+    /// An integer of the given type, which is encoded with the given encoding
+    Int(IntType, FieldEncoding),
+    /// A UTF-8 string, whose length is encoded with the given encoding
+    String(FieldEncoding),
+    /// A byte array, whose length and bytes are encoded with the given encoding
+    Bytes(FieldEncoding),
+}
+
+impl FieldType {
+    /// Gets the encoding of this field type, or *None* if it is *Bool*
+    pub fn get_encoding(&self) -> Option<FieldEncoding> {
+        match self {
+            FieldType::Bool => None,
+            FieldType::Int(_, encoding) => Some(*encoding),
+            FieldType::String(encoding) => Some(*encoding),
+            FieldType::Bytes(encoding) => Some(*encoding),
+        }
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            FieldType::Bool => write!(f, "bool (1 bit)"),
+            FieldType::Int(int_type, encoding) => write!(
+                f,
+                "{} ({})",
+                format!("{:?}", int_type).to_lowercase(),
+                encoding
+            ),
+            FieldType::String(encoding) => write!(f, "string (length: {})", encoding),
+            FieldType::Bytes(encoding) => write!(f, "bytes ({})", encoding),
+        }
+    }
+}
+
+/// A value of a field of a *Message*
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Bool(bool),
+    /// The value of an unsigned integer field
+    Unsigned(u128),
+    /// The value of a signed integer field
+    Signed(i128),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+/// A field of a *Message*
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageField {
+    name: String,
+    field_type: FieldType,
+    since: u32,
+    default: Option<Value>,
+}
+
+impl MessageField {
+    /// Gets the name of this field
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the type of this field
+    pub fn get_type(&self) -> FieldType {
+        self.field_type
+    }
+
+    /// Gets the version of the message in which this field was added, which
+    /// is 0 unless it was changed with *Message::since*
+    pub fn get_since(&self) -> u32 {
+        self.since
+    }
+
+    /// Gets the value that *Message::read_versioned* uses for this field when
+    /// it decodes data of a version older than *get_since*, or *None* if this
+    /// field is part of version 0
+    pub fn get_default(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    /// Checks whether *value* has the right type for this field, and whether it
+    /// fits in the integer type (if any)
+    pub(crate) fn check_value(&self, value: &Value) -> Result<(), String> {
+        let fits = match (self.field_type, value) {
+            (FieldType::Bool, Value::Bool(_)) => true,
+            (FieldType::Int(int_type, _), Value::Unsigned(value)) if !int_type.is_signed() => {
+                int_type.cast(*value) == *value
+            }
+            (FieldType::Int(int_type, _), Value::Signed(value)) if int_type.is_signed() => {
+                int_type.cast(*value as u128) == *value as u128
+            }
+            (FieldType::String(_), Value::String(_)) => true,
+            (FieldType::Bytes(_), Value::Bytes(_)) => true,
+            (field_type, value) => {
+                return Err(format!(
+                    "Field {} has type {}, which doesn't match value {:?}",
+                    self.name, field_type, value
+                ))
+            }
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!(
+                "Value {:?} of field {} doesn't fit in its type {}",
+                value, self.name, self.field_type
+            ))
+        }
+    }
+}
+
+/// Describes the layout of a message at runtime: a sequence of named fields,
+/// each with its own type and integer encoding. This is an alternative to
+/// writing the encoding and decoding code by hand, which is useful for
+/// messages that are only known at runtime (for instance because they are
+/// configured or loaded from a file).
+///
+/// The fields are encoded in the order in which they were added, without any
+/// tags or padding in between. The layout of a message can be described with
+/// its *Display* implementation.
+///
+/// When the layout needs to evolve, new fields can be marked with *since*, and
+/// the values can be encoded with *write_versioned* and *read_versioned*
+/// instead. That format stores the version of the writer and the length of the
+/// fields, so that new decoders can read old data (by using the defaults of
+/// the newer fields), and old decoders can read new data (by skipping the
+/// fields they don't know).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let message = Message::new()
+///     .int("id", IntType::U32, FieldEncoding::Digit(3))
+///     .string("name", FieldEncoding::Vlq)
+///     .bool("online");
+/// assert_eq!(
+///     "id: u32 (digit 3)\nname: string (length: vlq)\nonline: bool (1 bit)\n",
+///     message.to_string()
+/// );
+///
+/// let values = vec![
+///     Value::Unsigned(1234),
+///     Value::String("knokko".to_string()),
+///     Value::Bool(true),
+/// ];
+/// let mut sink = BoolVecBitSink::new();
+/// message.write(&mut sink, &values).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// assert_eq!(values, message.read(&mut source, &mut context).unwrap());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Message {
+    fields: Vec<MessageField>,
+}
+
+impl Message {
+    /// Constructs a new *Message* without any fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field with the given *name* and *field_type*.
+    ///
+    /// Panics if this message already has a field with the same name, or if
+    /// the encoding of *field_type* is invalid (see *FieldEncoding::validate*).
+    pub fn field(mut self, name: &str, field_type: FieldType) -> Self {
+        if self.get_index(name).is_some() {
+            panic!("Duplicate field {}", name);
+        }
+        if let Some(encoding) = field_type.get_encoding() {
+            if let Err(problem) = encoding.validate() {
+                panic!("Field {}: {}", name, problem);
+            }
+        }
+        self.fields.push(MessageField {
+            name: name.to_string(),
+            field_type,
+            since: 0,
+            default: None,
+        });
+        self
+    }
+
+    /// Marks the field that was added last as a field that was added in
+    /// *version* of this message. When *read_versioned* decodes data that was
+    /// written by an older version, it will use *default* as the value of this
+    /// field. Fields can only be appended in newer versions, so all fields
+    /// after this field must be marked with the same or a newer version.
+    ///
+    /// Panics if this message doesn't have any fields, if *version* is 0, if
+    /// *version* is older than the version of the previous field, or if
+    /// *default* doesn't match the type of the field.
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let message = Message::new()
+    ///     .int("id", IntType::U32, FieldEncoding::Vlq)
+    ///     .bool("online")
+    ///     .since(2, Value::Bool(false));
+    /// assert_eq!(2, message.get_version());
+    /// ```
+    pub fn since(mut self, version: u32, default: Value) -> Self {
+        assert!(version > 0, "Version 0 can't have defaults");
+        let num_fields = self.fields.len();
+        if num_fields > 1 && self.fields[num_fields - 2].since > version {
+            panic!(
+                "Field {} can't be added in version {}, since the field before it was added in \
+                 version {}",
+                self.fields[num_fields - 1].name,
+                version,
+                self.fields[num_fields - 2].since
+            );
+        }
+        let field = self
+            .fields
+            .last_mut()
+            .expect("There is no field to mark with a version");
+        if let Err(problem) = field.check_value(&default) {
+            panic!("Invalid default: {}", problem);
+        }
+        field.since = version;
+        field.default = Some(default);
+        self
+    }
+
+    /// Adds a *FieldType::Bool* field with the given *name*. See *field*.
+    pub fn bool(self, name: &str) -> Self {
+        self.field(name, FieldType::Bool)
+    }
+
+    /// Adds a *FieldType::Int* field with the given *name*. See *field*.
+    pub fn int(self, name: &str, int_type: IntType, encoding: FieldEncoding) -> Self {
+        self.field(name, FieldType::Int(int_type, encoding))
+    }
+
+    /// Adds a *FieldType::String* field with the given *name*. See *field*.
+    pub fn string(self, name: &str, encoding: FieldEncoding) -> Self {
+        self.field(name, FieldType::String(encoding))
+    }
+
+    /// Adds a *FieldType::Bytes* field with the given *name*. See *field*.
+    pub fn bytes(self, name: &str, encoding: FieldEncoding) -> Self {
+        self.field(name, FieldType::Bytes(encoding))
+    }
+
+    /// Gets all fields of this message, in the order in which they are encoded
+    pub fn get_fields(&self) -> &[MessageField] {
+        &self.fields
+    }
+
+    /// Gets the index of the field with the given *name*, which is also the
+    /// index of its value in the values of *write* and *read*
+    pub fn get_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    /// Gets the version of this message, which is the newest version of its
+    /// fields (see *since*), or 0 if none of its fields has a version
+    pub fn get_version(&self) -> u32 {
+        self.fields
+            .iter()
+            .map(|field| field.since)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks that no field of an older version comes after a field of a
+    /// newer version
+    fn check_version_order(&self) -> Result<(), String> {
+        for pair in self.fields.windows(2) {
+            if pair[0].since > pair[1].since {
+                return Err(format!(
+                    "Field {} (version {}) comes after field {} (version {})",
+                    pair[1].name, pair[1].since, pair[0].name, pair[0].since
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_field(
+        sink: &mut impl BitSink,
+        field: &MessageField,
+        value: &Value,
+    ) -> Result<(), WriteError> {
+        field.check_value(value)?;
+        match (field.field_type, value) {
+            (FieldType::Bool, Value::Bool(value)) => sink.write(&[*value]),
+            (FieldType::Int(int_type, encoding), Value::Unsigned(value)) => {
+                with_encoder!(encoding, protocol => write_int(&protocol, sink, int_type, *value))
+            }
+            (FieldType::Int(int_type, encoding), Value::Signed(value)) => {
+                let value = *value as u128;
+                with_encoder!(encoding, protocol => write_int(&protocol, sink, int_type, value))
+            }
+            (FieldType::String(encoding), Value::String(value)) => {
+                with_encoder!(encoding, protocol => protocol.write_string(sink, value))
+            }
+            (FieldType::Bytes(encoding), Value::Bytes(value)) => {
+                with_encoder!(encoding, protocol => protocol.write_u8_slice(sink, value))
+            }
+            _ => unreachable!("check_value should have rejected this value"),
+        }
+    }
+
+    /// Encodes *values* (one value per field, in the same order as the fields)
+    /// and writes them to *sink*.
+    ///
+    /// Returns an error if the number of values is not equal to the number of
+    /// fields, if a value doesn't match the type of its field (signed integer
+    /// types need *Value::Signed* and unsigned integer types need
+    /// *Value::Unsigned*), or if an integer doesn't fit in its type.
+    pub fn write(&self, sink: &mut impl BitSink, values: &[Value]) -> Result<(), WriteError> {
+        if values.len() != self.fields.len() {
+            return Err(format!(
+                "Expected {} values, but got {}",
+                self.fields.len(),
+                values.len()
+            )
+            .into());
+        }
+        for (field, value) in self.fields.iter().zip(values.iter()) {
+            Self::write_field(sink, field, value)?;
+        }
+        Ok(())
+    }
+
+    fn read_field(
+        source: &mut impl BitSource,
+        field_type: FieldType,
+        context: &mut DecodeContext,
+    ) -> Result<Value, DecodeError> {
+        Ok(match field_type {
+            FieldType::Bool => {
+                let mut value = [false];
+                source.read(&mut value)?;
+                Value::Bool(value[0])
+            }
+            FieldType::Int(int_type, encoding) => {
+                let value =
+                    with_decoder!(encoding, protocol => read_int(&protocol, source, int_type))?;
+                if int_type.is_signed() {
+                    Value::Signed(value as i128)
+                } else {
+                    Value::Unsigned(value)
+                }
+            }
+            FieldType::String(encoding) => Value::String(
+                with_decoder!(encoding, protocol => protocol.read_string(source, context))?,
+            ),
+            FieldType::Bytes(encoding) => Value::Bytes(
+                with_decoder!(encoding, protocol => protocol.read_u8_vec(source, context))?,
+            ),
+        })
+    }
+
+    /// Reads the values of all fields from *source*, which must have been
+    /// written by *write* of the same message. The memory for the strings and
+    /// byte arrays is claimed from *context*.
+    pub fn read(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<Value>, DecodeError> {
+        let mut values = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            values.push(Self::read_field(source, field.field_type, context)?);
+        }
+        Ok(values)
+    }
+
+    /// Encodes *values* like *write*, but prefixes them with the version of
+    /// this message (see *get_version*) and the number of bits of the encoded
+    /// values (both encoded with *DigitIntEncodingProtocol::v1*). The result
+    /// can be decoded by *read_versioned* of older and newer versions of this
+    /// message.
+    ///
+    /// Returns the same errors as *write*, and also returns an error if a field
+    /// of an older version comes after a field of a newer version.
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let old_message = Message::new().int("id", IntType::U32, FieldEncoding::Vlq);
+    /// let new_message = old_message
+    ///     .clone()
+    ///     .string("name", FieldEncoding::Vlq)
+    ///     .since(1, Value::String("unknown".to_string()));
+    ///
+    /// // The new message can read data of the old message...
+    /// let mut sink = BoolVecBitSink::new();
+    /// old_message.write_versioned(&mut sink, &[Value::Unsigned(12)]).unwrap();
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let mut context = DecodeContext::new(DecodeLimits::new());
+    /// assert_eq!(
+    ///     vec![Value::Unsigned(12), Value::String("unknown".to_string())],
+    ///     new_message.read_versioned(&mut source, &mut context).unwrap()
+    /// );
+    ///
+    /// // ... and the old message can read data of the new message
+    /// let mut sink = BoolVecBitSink::new();
+    /// let values = [Value::Unsigned(34), Value::String("knokko".to_string())];
+    /// new_message.write_versioned(&mut sink, &values).unwrap();
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// assert_eq!(
+    ///     vec![Value::Unsigned(34)],
+    ///     old_message.read_versioned(&mut source, &mut context).unwrap()
+    /// );
+    /// ```
+    pub fn write_versioned(
+        &self,
+        sink: &mut impl BitSink,
+        values: &[Value],
+    ) -> Result<(), WriteError> {
+        self.check_version_order()?;
+        let mut section = BoolVecBitSink::new();
+        self.write(&mut section, values)?;
+
+        let protocol = DigitIntEncodingProtocol::v1();
+        protocol.write_u32(sink, self.get_version())?;
+        protocol.write_u64(sink, section.get_bits().len() as u64)?;
+        sink.write(section.get_bits())
+    }
+
+    /// Reads values that were written by *write_versioned* of any version of
+    /// this message. When the data was written by an older version, the
+    /// fields that were added later get their default value. When the data
+    /// was written by a newer version, the fields that this message doesn't
+    /// know are skipped. The memory for the strings and byte arrays is claimed
+    /// from *context*.
+    pub fn read_versioned(
+        &self,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<Value>, DecodeError> {
+        self.check_version_order()
+            .map_err(|problem| DecodeError::Other(problem.into()))?;
+        let protocol = DigitIntDecodingProtocol::v1();
+        let data_version = protocol.read_u32(source)?;
+        let num_bits = protocol.read_u64(source)?;
+
+        let mut section = SubSource::new(source, num_bits);
+        let mut values = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            if field.since <= data_version {
+                values.push(Self::read_field(&mut section, field.field_type, context)?);
+            } else {
+                values.push(field.default.clone().unwrap());
+            }
+        }
+
+        let num_remaining_bits = section.get_num_remaining_bits();
+        if num_remaining_bits > 0 && data_version <= self.get_version() {
+            return Err(DecodeError::Other(
+                format!(
+                    "{} bits are left after the fields of version {}",
+                    num_remaining_bits, data_version
+                )
+                .into(),
+            ));
+        }
+        section.skip_remaining()?;
+        Ok(values)
+    }
+}
+
+/// Describes the layout of the message: 1 line per field, with its name, type
+/// and (if any) its version and default value
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for field in &self.fields {
+            write!(f, "{}: {}", field.name, field.field_type)?;
+            if let Some(default) = &field.default {
+                write!(f, " since version {} (default {:?})", field.since, default)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn create_message() -> Message {
+        Message::new()
+            .bool("alive")
+            .int("x", IntType::I32, FieldEncoding::Zeta(2))
+            .int("y", IntType::I32, FieldEncoding::Simple)
+            .int("id", IntType::U64, FieldEncoding::Vlq)
+            .int("health", IntType::U8, FieldEncoding::Digit(4))
+            .string("name", FieldEncoding::Digit(3))
+            .bytes("inventory", FieldEncoding::Zeta(1))
+    }
+
+    fn create_values() -> Vec<Value> {
+        vec![
+            Value::Bool(true),
+            Value::Signed(-12345),
+            Value::Signed(i32::MAX as i128),
+            Value::Unsigned(u64::MAX as u128),
+            Value::Unsigned(100),
+            Value::String("hello world".to_string()),
+            Value::Bytes(vec![0, 1, 2, 255]),
+        ]
+    }
+
+    #[test]
+    fn test_same_as_protocols() {
+        let message = create_message();
+        let mut sink = BoolVecBitSink::new();
+        message.write(&mut sink, &create_values()).unwrap();
+
+        let mut expected = BoolVecBitSink::new();
+        expected.write(&[true]).unwrap();
+        let zeta = ZetaIntEncodingProtocol::new(2);
+        zeta.write_i32(&mut expected, -12345).unwrap();
+        SimpleIntEncodingProtocol::new()
+            .write_i32(&mut expected, i32::MAX)
+            .unwrap();
+        VlqIntEncodingProtocol::new()
+            .write_u64(&mut expected, u64::MAX)
+            .unwrap();
+        DigitIntEncodingProtocol::new(4, false)
+            .write_u8(&mut expected, 100)
+            .unwrap();
+        DigitIntEncodingProtocol::new(3, false)
+            .write_string(&mut expected, "hello world")
+            .unwrap();
+        ZetaIntEncodingProtocol::new(1)
+            .write_u8_slice(&mut expected, &[0, 1, 2, 255])
+            .unwrap();
+        assert_eq!(expected.get_bits(), sink.get_bits());
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert_eq!(
+            create_values(),
+            message.read(&mut source, &mut context).unwrap()
+        );
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let message = create_message();
+        let mut sink = BoolVecBitSink::new();
+        assert!(message.write(&mut sink, &create_values()[1..]).is_err());
+
+        let mut wrong_type = create_values();
+        wrong_type[3] = Value::Signed(5);
+        assert!(message.write(&mut sink, &wrong_type).is_err());
+
+        let mut too_large = create_values();
+        too_large[4] = Value::Unsigned(256);
+        assert!(message.write(&mut sink, &too_large).is_err());
+
+        let mut too_small = create_values();
+        too_small[1] = Value::Signed(i32::MIN as i128 - 1);
+        assert!(message.write(&mut sink, &too_small).is_err());
+    }
+
+    #[test]
+    fn test_description() {
+        let message = create_message();
+        assert_eq!(Some(5), message.get_index("name"));
+        assert_eq!(None, message.get_index("nope"));
+        assert_eq!(
+            "alive: bool (1 bit)\n\
+             x: i32 (zeta 2)\n\
+             y: i32 (simple)\n\
+             id: u64 (vlq)\n\
+             health: u8 (digit 4)\n\
+             name: string (length: digit 3)\n\
+             inventory: bytes (zeta 1)\n",
+            message.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_field() {
+        Message::new().bool("a").string("a", FieldEncoding::Vlq);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_encoding() {
+        Message::new().int("a", IntType::U8, FieldEncoding::Digit(1));
+    }
+
+    fn create_versions() -> (Message, Message, Message) {
+        let v0 = Message::new()
+            .int("id", IntType::U32, FieldEncoding::Vlq)
+            .bool("alive");
+        let v1 = v0
+            .clone()
+            .int("x", IntType::I16, FieldEncoding::Zeta(2))
+            .since(1, Value::Signed(-1))
+            .string("name", FieldEncoding::Digit(3))
+            .since(1, Value::String("nobody".to_string()));
+        let v3 = v1
+            .clone()
+            .bytes("inventory", FieldEncoding::Vlq)
+            .since(3, Value::Bytes(vec![]));
+        (v0, v1, v3)
+    }
+
+    #[test]
+    fn test_versioned() {
+        let (v0, v1, v3) = create_versions();
+        assert_eq!(
+            (0, 1, 3),
+            (v0.get_version(), v1.get_version(), v3.get_version())
+        );
+        let values = [
+            Value::Unsigned(12),
+            Value::Bool(true),
+            Value::Signed(300),
+            Value::String("knokko".to_string()),
+            Value::Bytes(vec![1, 2, 3]),
+        ];
+        let defaults = [
+            Value::Unsigned(12),
+            Value::Bool(true),
+            Value::Signed(-1),
+            Value::String("nobody".to_string()),
+            Value::Bytes(vec![]),
+        ];
+
+        let messages = [&v0, &v1, &v3];
+        for (writer_index, writer) in messages.iter().enumerate() {
+            for (reader_index, reader) in messages.iter().enumerate() {
+                let num_fields = writer.get_fields().len();
+                let mut sink = BoolVecBitSink::new();
+                writer
+                    .write_versioned(&mut sink, &values[0..num_fields])
+                    .unwrap();
+                sink.write(&[true, false, true]).unwrap();
+
+                let mut source = BoolSliceBitSource::new(sink.get_bits());
+                let mut context = DecodeContext::new(DecodeLimits::new());
+                let read_values = reader.read_versioned(&mut source, &mut context).unwrap();
+                for (index, value) in read_values.iter().enumerate() {
+                    if index < num_fields {
+                        assert_eq!(&values[index], value, "{} {}", writer_index, reader_index);
+                    } else {
+                        assert_eq!(&defaults[index], value, "{} {}", writer_index, reader_index);
+                    }
+                }
+                assert_eq!(reader.get_fields().len(), read_values.len());
+
+                // The reader must continue right after the versioned values
+                let mut rest = [false; 3];
+                source.read(&mut rest).unwrap();
+                assert_eq!([true, false, true], rest);
+            }
+        }
+    }
+
+    #[test]
+    fn test_versioned_corrupt_length() {
+        let (_, v1, _) = create_versions();
+        let values = [
+            Value::Unsigned(12),
+            Value::Bool(true),
+            Value::Signed(300),
+            Value::String("knokko".to_string()),
+        ];
+        let mut sink = BoolVecBitSink::new();
+        v1.write_versioned(&mut sink, &values).unwrap();
+        let bits = sink.get_bits();
+
+        // Take the version and the length from the real data, but claim that
+        // the section is 1 bit longer
+        let mut source = BoolSliceBitSource::new(bits);
+        let decoder = DigitIntDecodingProtocol::v1();
+        let version = decoder.read_u32(&mut source).unwrap();
+        let num_bits = decoder.read_u64(&mut source).unwrap();
+        let section = &bits[bits.len() - num_bits as usize..];
+
+        let mut corrupt = BoolVecBitSink::new();
+        let encoder = DigitIntEncodingProtocol::v1();
+        encoder.write_u32(&mut corrupt, version).unwrap();
+        encoder.write_u64(&mut corrupt, num_bits + 1).unwrap();
+        corrupt.write(section).unwrap();
+        corrupt.write(&[false]).unwrap();
+
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut source = BoolSliceBitSource::new(corrupt.get_bits());
+        assert!(v1.read_versioned(&mut source, &mut context).is_err());
+
+        // When the section is too short, the last field can't be read
+        let mut truncated = BoolVecBitSink::new();
+        encoder.write_u32(&mut truncated, version).unwrap();
+        encoder.write_u64(&mut truncated, num_bits - 1).unwrap();
+        truncated.write(section).unwrap();
+        let mut source = BoolSliceBitSource::new(truncated.get_bits());
+        assert!(v1.read_versioned(&mut source, &mut context).is_err());
+    }
+
+    #[test]
+    fn test_versioned_order() {
+        let message = Message::new()
+            .bool("a")
+            .since(2, Value::Bool(false))
+            .bool("b");
+        let mut sink = BoolVecBitSink::new();
+        let values = [Value::Bool(true), Value::Bool(false)];
+        assert!(message.write_versioned(&mut sink, &values).is_err());
+        assert!(message.write(&mut sink, &values).is_ok());
+        assert_eq!(
+            "a: bool (1 bit) since version 2 (default Bool(false))\nb: bool (1 bit)\n",
+            message.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_since_older_than_previous() {
+        Message::new()
+            .bool("a")
+            .since(2, Value::Bool(false))
+            .bool("b")
+            .since(1, Value::Bool(false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_since_wrong_default() {
+        Message::new()
+            .int("a", IntType::U8, FieldEncoding::Vlq)
+            .since(1, Value::Unsigned(256));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::*;
+
+/// Maps *value* from the range [-1, 1] to an unsigned integer with *num_bits*
+/// bits, rounding to the nearest step
+fn quantize_unit(value: f32, num_bits: u8) -> u128 {
+    let max_step = ((1u64 << num_bits) - 1) as f32;
+    let clamped = value.clamp(-1.0, 1.0);
+    ((clamped + 1.0) * 0.5 * max_step).round() as u128
+}
+
+/// The inverse of *quantize_unit*
+fn dequantize_unit(quantized: u128, num_bits: u8) -> f32 {
+    let max_step = ((1u64 << num_bits) - 1) as f32;
+    quantized as f32 / max_step * 2.0 - 1.0
+}
+
+/// Compresses unit quaternions (for instance the rotations of game entities)
+/// using the "smallest three" technique. Since the components of a unit
+/// quaternion *q* satisfy *x² + y² + z² + w² = 1*, only 3 of them need to be
+/// stored: the 4th can be computed from the other 3 (up to its sign, but *q*
+/// and *-q* represent the same rotation). This codec drops the component with
+/// the largest absolute value, which guarantees that the other 3 components are
+/// in the range [-1/sqrt(2), 1/sqrt(2)].
+///
+/// Every quaternion is encoded with 2 bits for the index of the dropped
+/// component, followed by *bits_per_component* bits for each of the other 3
+/// components. The quaternions are given as *[x, y, z, w]* arrays.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let codec = QuaternionCodec::new(9);
+/// let rotation = [0.0, 0.38268343, 0.0, 0.9238795];
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, rotation).unwrap();
+/// assert_eq!(29, sink.get_num_bools());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let decoded = codec.read(&mut source).unwrap();
+/// for index in 0..4 {
+///     assert!((rotation[index] - decoded[index]).abs() < 0.003);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuaternionCodec {
+    bits_per_component: u8,
+}
+
+impl QuaternionCodec {
+    /// Constructs a new *QuaternionCodec* that uses *bits_per_component* bits
+    /// for each of the 3 stored components (so every quaternion will take
+    /// *2 + 3 * bits_per_component* bits).
+    ///
+    /// Panics if *bits_per_component* is smaller than 2 or larger than 32.
+    pub const fn new(bits_per_component: u8) -> Self {
+        if bits_per_component < 2 || bits_per_component > 32 {
+            panic!("The number of bits per component must be between 2 and 32");
+        }
+        Self { bits_per_component }
+    }
+
+    /// Gets the number of bits that are used to store each of the 3 components
+    pub fn get_bits_per_component(&self) -> u8 {
+        self.bits_per_component
+    }
+
+    /// Gets the maximum difference between a stored component and its decoded
+    /// value (the dropped component can have a slightly larger error)
+    pub fn get_max_component_error(&self) -> f32 {
+        std::f32::consts::FRAC_1_SQRT_2 / ((1u64 << self.bits_per_component) - 1) as f32
+    }
+
+    /// Quantizes *quaternion* (given as *[x, y, z, w]*) and writes it to *sink*.
+    /// The quaternion will be normalized first. Returns an error if it has length
+    /// 0 or any of its components is not finite.
+    pub fn write(&self, sink: &mut impl BitSink, quaternion: [f32; 4]) -> Result<(), WriteError> {
+        if quaternion.iter().any(|component| !component.is_finite()) {
+            return Err(format!("Invalid quaternion {:?}", quaternion).into());
+        }
+        let length = quaternion
+            .iter()
+            .map(|component| component * component)
+            .sum::<f32>()
+            .sqrt();
+        if length == 0.0 {
+            return Err("Can't encode a quaternion with length 0".into());
+        }
+
+        let mut largest_index = 0;
+        for index in 1..4 {
+            if quaternion[index].abs() > quaternion[largest_index].abs() {
+                largest_index = index;
+            }
+        }
+
+        // Since q and -q are the same rotation, make the dropped component positive
+        let sign = if quaternion[largest_index] < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let writer = SimpleIntEncodingProtocol::new();
+        writer.write_unsigned(sink, 2, largest_index as u128)?;
+        for (index, component) in quaternion.iter().enumerate() {
+            if index != largest_index {
+                let normalized = sign * component / length * std::f32::consts::SQRT_2;
+                writer.write_unsigned(
+                    sink,
+                    self.bits_per_component as usize,
+                    quantize_unit(normalized, self.bits_per_component),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a quaternion that was written by *write*, as *[x, y, z, w]*. The
+    /// result is normalized, and might be the negation of the original
+    /// quaternion (which represents the same rotation).
+    pub fn read(&self, source: &mut impl BitSource) -> Result<[f32; 4], DecodeError> {
+        let reader = SimpleIntDecodingProtocol::new();
+        let largest_index = reader.read_unsigned(source, 2)? as usize;
+
+        let mut quaternion = [0.0; 4];
+        let mut sum_of_squares = 0.0;
+        for (index, component) in quaternion.iter_mut().enumerate() {
+            if index != largest_index {
+                let quantized = reader.read_unsigned(source, self.bits_per_component as usize)?;
+                *component = dequantize_unit(quantized, self.bits_per_component)
+                    * std::f32::consts::FRAC_1_SQRT_2;
+                sum_of_squares += *component * *component;
+            }
+        }
+        quaternion[largest_index] = (1.0f32 - sum_of_squares).max(0.0).sqrt();
+
+        let length = quaternion
+            .iter()
+            .map(|component| component * component)
+            .sum::<f32>()
+            .sqrt();
+        for component in &mut quaternion {
+            *component /= length;
+        }
+        Ok(quaternion)
+    }
+}
+
+/// Compresses unit vectors (for instance directions or normals) using the
+/// octahedral mapping: the unit sphere is projected onto an octahedron, which
+/// is then unfolded onto a square. Both coordinates of that square are stored
+/// with *bits_per_coordinate* bits. This distributes the precision much more
+/// evenly over the sphere than storing angles.
+///
+/// The vectors are given as *[x, y, z]* arrays.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let codec = UnitVectorCodec::new(12);
+/// let direction = [0.6, 0.0, -0.8];
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&mut sink, direction).unwrap();
+/// assert_eq!(24, sink.get_num_bools());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let decoded = codec.read(&mut source).unwrap();
+/// for index in 0..3 {
+///     assert!((direction[index] - decoded[index]).abs() < 0.002);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitVectorCodec {
+    bits_per_coordinate: u8,
+}
+
+impl UnitVectorCodec {
+    /// Constructs a new *UnitVectorCodec* that uses *bits_per_coordinate* bits
+    /// for both octahedral coordinates (so every vector will take
+    /// *2 * bits_per_coordinate* bits).
+    ///
+    /// Panics if *bits_per_coordinate* is smaller than 2 or larger than 32.
+    pub const fn new(bits_per_coordinate: u8) -> Self {
+        if bits_per_coordinate < 2 || bits_per_coordinate > 32 {
+            panic!("The number of bits per coordinate must be between 2 and 32");
+        }
+        Self {
+            bits_per_coordinate,
+        }
+    }
+
+    /// Gets the number of bits that are used to store each of the 2 octahedral
+    /// coordinates
+    pub fn get_bits_per_coordinate(&self) -> u8 {
+        self.bits_per_coordinate
+    }
+
+    /// Quantizes *vector* (given as *[x, y, z]*) and writes it to *sink*. The
+    /// vector will be normalized first. Returns an error if it has length 0 or
+    /// any of its components is not finite.
+    pub fn write(&self, sink: &mut impl BitSink, vector: [f32; 3]) -> Result<(), WriteError> {
+        if vector.iter().any(|component| !component.is_finite()) {
+            return Err(format!("Invalid vector {:?}", vector).into());
+        }
+        let l1_norm = vector[0].abs() + vector[1].abs() + vector[2].abs();
+        if l1_norm == 0.0 {
+            return Err("Can't encode a vector with length 0".into());
+        }
+
+        // Project onto the octahedron |x| + |y| + |z| = 1
+        let mut u = vector[0] / l1_norm;
+        let mut v = vector[1] / l1_norm;
+        if vector[2] < 0.0 {
+            // Fold the lower half over the diagonals
+            let old_u = u;
+            u = (1.0 - v.abs()) * sign_not_zero(old_u);
+            v = (1.0 - old_u.abs()) * sign_not_zero(v);
+        }
+
+        let writer = SimpleIntEncodingProtocol::new();
+        let num_bits = self.bits_per_coordinate;
+        writer.write_unsigned(sink, num_bits as usize, quantize_unit(u, num_bits))?;
+        writer.write_unsigned(sink, num_bits as usize, quantize_unit(v, num_bits))
+    }
+
+    /// Reads a unit vector that was written by *write*, as *[x, y, z]*
+    pub fn read(&self, source: &mut impl BitSource) -> Result<[f32; 3], DecodeError> {
+        let reader = SimpleIntDecodingProtocol::new();
+        let num_bits = self.bits_per_coordinate;
+        let u = dequantize_unit(reader.read_unsigned(source, num_bits as usize)?, num_bits);
+        let v = dequantize_unit(reader.read_unsigned(source, num_bits as usize)?, num_bits);
+
+        let z = 1.0 - u.abs() - v.abs();
+        let (x, y) = if z < 0.0 {
+            (
+                (1.0 - v.abs()) * sign_not_zero(u),
+                (1.0 - u.abs()) * sign_not_zero(v),
+            )
+        } else {
+            (u, v)
+        };
+
+        let length = (x * x + y * y + z * z).sqrt();
+        Ok([x / length, y / length, z / length])
+    }
+}
+
+fn sign_not_zero(value: f32) -> f32 {
+    if value < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn normalize4(quaternion: [f32; 4]) -> [f32; 4] {
+        let length = quaternion.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let mut result = quaternion;
+        for component in &mut result {
+            *component /= length;
+        }
+        result
+    }
+
+    fn check_quaternion(codec: QuaternionCodec, quaternion: [f32; 4]) {
+        let expected = normalize4(quaternion);
+        let mut sink = BoolVecBitSink::new();
+        codec.write(&mut sink, quaternion).unwrap();
+        assert_eq!(
+            2 + 3 * codec.get_bits_per_component() as u64,
+            sink.get_num_bools()
+        );
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let decoded = codec.read(&mut source).unwrap();
+
+        // q and -q are the same rotation
+        let dot: f32 = (0..4).map(|index| expected[index] * decoded[index]).sum();
+        let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+        let max_error = codec.get_max_component_error() * 4.0;
+        for index in 0..4 {
+            assert!(
+                (expected[index] - sign * decoded[index]).abs() <= max_error,
+                "{:?} became {:?}",
+                quaternion,
+                decoded
+            );
+        }
+    }
+
+    #[test]
+    fn test_quaternions() {
+        for codec in &[
+            QuaternionCodec::new(9),
+            QuaternionCodec::new(12),
+            QuaternionCodec::new(20),
+        ] {
+            check_quaternion(*codec, [0.0, 0.0, 0.0, 1.0]);
+            check_quaternion(*codec, [0.0, 0.0, 0.0, -1.0]);
+            check_quaternion(*codec, [1.0, 0.0, 0.0, 0.0]);
+            check_quaternion(*codec, [0.5, -0.5, 0.5, -0.5]);
+            check_quaternion(*codec, [0.1, 0.2, -0.3, 0.9]);
+            for seed in 0..200 {
+                let value = |offset: u32| ((seed * 37 + offset * 11) % 41) as f32 / 20.0 - 1.0;
+                let quaternion = [value(0), value(1), value(2), value(3)];
+                if quaternion.iter().any(|component| *component != 0.0) {
+                    check_quaternion(*codec, quaternion);
+                }
+            }
+        }
+    }
+
+    fn check_vector(codec: UnitVectorCodec, vector: [f32; 3], max_error: f32) {
+        let length = vector.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let mut sink = BoolVecBitSink::new();
+        codec.write(&mut sink, vector).unwrap();
+        assert_eq!(
+            2 * codec.get_bits_per_coordinate() as u64,
+            sink.get_num_bools()
+        );
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let decoded = codec.read(&mut source).unwrap();
+        for index in 0..3 {
+            assert!(
+                (vector[index] / length - decoded[index]).abs() <= max_error,
+                "{:?} became {:?}",
+                vector,
+                decoded
+            );
+        }
+    }
+
+    #[test]
+    fn test_unit_vectors() {
+        for (bits, max_error) in &[(8, 0.02), (12, 0.002), (16, 0.0002)] {
+            let codec = UnitVectorCodec::new(*bits);
+            check_vector(codec, [1.0, 0.0, 0.0], *max_error);
+            check_vector(codec, [0.0, -1.0, 0.0], *max_error);
+            check_vector(codec, [0.0, 0.0, 1.0], *max_error);
+            check_vector(codec, [0.0, 0.0, -1.0], *max_error);
+            check_vector(codec, [-1.0, -1.0, -1.0], *max_error);
+            for seed in 0..200 {
+                let value = |offset: u32| ((seed * 37 + offset * 13) % 41) as f32 / 20.0 - 1.0;
+                let vector = [value(0), value(1), value(2)];
+                if vector.iter().any(|component| *component != 0.0) {
+                    check_vector(codec, vector, *max_error);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut sink = BoolVecBitSink::new();
+        assert!(QuaternionCodec::new(10).write(&mut sink, [0.0; 4]).is_err());
+        assert!(QuaternionCodec::new(10)
+            .write(&mut sink, [f32::NAN, 0.0, 0.0, 1.0])
+            .is_err());
+        assert!(UnitVectorCodec::new(10).write(&mut sink, [0.0; 3]).is_err());
+        assert!(UnitVectorCodec::new(10)
+            .write(&mut sink, [f32::INFINITY, 0.0, 0.0])
+            .is_err());
+        assert_eq!(0, sink.get_num_bools());
+    }
+}
@@ -0,0 +1,390 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+fn num_position_bits(block_length: usize) -> usize {
+    64 - (block_length as u64 - 1).leading_zeros() as usize
+}
+
+fn low_bits_mask(bit_width: usize) -> u64 {
+    match bit_width {
+        64 => u64::MAX,
+        _ => (1 << bit_width) - 1,
+    }
+}
+
+/// A block codec for slices of *u64*s (or *u32*s) using Patched Frame Of
+/// Reference (PFOR), which is the standard choice for the posting lists of
+/// search engines.
+///
+/// The values are split into blocks of *block_size* values. For each block,
+/// the smallest value (the *reference*) is written with the int protocol, and
+/// every value of the block is written as its difference with the reference,
+/// using a fixed number of bits (the *bit width*) per value. The bit width is
+/// chosen per block such that the encoding of the block is as small as
+/// possible. The few values that don't fit in the bit width are *exceptions*:
+/// their least significant bits are stored in the block like all other values,
+/// and their position and remaining bits are stored separately after the
+/// block (the remaining bits are written with the int protocol). This way, a
+/// single large value doesn't increase the size of all other values in its
+/// block.
+///
+/// When *delta* is true (PFOR-delta), every value is first replaced by its
+/// difference with the previous value (using wrapping arithmetic), which works
+/// best for sorted values, like document IDs.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let document_ids: Vec<u64> = (0..1000).map(|index| 1_000_000 + 3 * index + index / 2).collect();
+///
+/// let codec = PforCodec::new(128, true);
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&DigitIntEncodingProtocol::v1(), &mut sink, &document_ids).unwrap();
+/// // The differences are 3 or 4, so most values only need 1 bit
+/// assert!(sink.get_num_bools() < 1200);
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = codec.read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(document_ids, decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PforCodec {
+    block_size: usize,
+    delta: bool,
+}
+
+impl PforCodec {
+    /// Constructs a new *PforCodec* that splits the values into blocks of
+    /// *block_size* values, and stores the differences between consecutive
+    /// values if *delta* is true. The decoder must use the same parameters as
+    /// the encoder.
+    ///
+    /// Panics if *block_size* is 0.
+    pub const fn new(block_size: usize, delta: bool) -> Self {
+        if block_size == 0 {
+            panic!("The block size must be positive");
+        }
+        Self { block_size, delta }
+    }
+
+    /// Gets the (maximum) number of values per block
+    pub fn get_block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Checks whether this codec stores the differences between consecutive
+    /// values (PFOR-delta) rather than the values themselves
+    pub fn is_delta(&self) -> bool {
+        self.delta
+    }
+
+    fn block_size_in_bits(
+        protocol: &impl IntEncodingProtocol,
+        offsets: &[u64],
+        bit_width: usize,
+    ) -> u64 {
+        let mut num_exceptions = 0;
+        let mut exception_size = 0;
+        for offset in offsets {
+            if bit_width < 64 && offset >> bit_width != 0 {
+                num_exceptions += 1;
+                exception_size += num_position_bits(offsets.len()) as u64
+                    + protocol.encoded_size_u64((offset >> bit_width) - 1) as u64;
+            }
+        }
+        7 + protocol.encoded_size_u64(num_exceptions) as u64
+            + (bit_width * offsets.len()) as u64
+            + exception_size
+    }
+
+    fn write_block(
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        block: &[u64],
+    ) -> Result<(), WriteError> {
+        let reference = *block.iter().min().unwrap();
+        protocol.write_u64(sink, reference)?;
+        let offsets: Vec<u64> = block.iter().map(|value| value - reference).collect();
+
+        let bit_width = (0..=64)
+            .min_by_key(|bit_width| Self::block_size_in_bits(protocol, &offsets, *bit_width))
+            .unwrap();
+        let simple = SimpleIntEncodingProtocol::new();
+        simple.write_unsigned(sink, 7, bit_width as u128)?;
+
+        let mut bits = Vec::with_capacity(bit_width * offsets.len());
+        for offset in &offsets {
+            for index in 0..bit_width {
+                bits.push(offset & 1 << index != 0);
+            }
+        }
+        sink.write(&bits)?;
+
+        let mask = low_bits_mask(bit_width);
+        let num_exceptions = offsets
+            .iter()
+            .filter(|offset| **offset & !mask != 0)
+            .count();
+        protocol.write_u64(sink, num_exceptions as u64)?;
+        for (position, offset) in offsets.iter().enumerate() {
+            if offset & !mask != 0 {
+                simple.write_unsigned(sink, num_position_bits(block.len()), position as u128)?;
+                // The high bits of an exception are never 0, so store them minus 1
+                protocol.write_u64(sink, (offset >> bit_width) - 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes *values* with *protocol*, and writes them to *sink*
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[u64],
+    ) -> Result<(), WriteError> {
+        protocol.write_u64(sink, values.len() as u64)?;
+        let mut previous = 0;
+        for block in values.chunks(self.block_size) {
+            if self.delta {
+                let deltas: Vec<u64> = block
+                    .iter()
+                    .map(|value| {
+                        let delta = value.wrapping_sub(previous);
+                        previous = *value;
+                        delta
+                    })
+                    .collect();
+                Self::write_block(protocol, sink, &deltas)?;
+            } else {
+                Self::write_block(protocol, sink, block)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes *values* with *protocol*, and writes them to *sink*. The result
+    /// is the same as calling *write* with the values converted to *u64*s.
+    pub fn write_u32s(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[u32],
+    ) -> Result<(), WriteError> {
+        let values: Vec<u64> = values.iter().map(|value| *value as u64).collect();
+        self.write(protocol, sink, &values)
+    }
+
+    fn read_block(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        block_length: usize,
+        dest: &mut Vec<u64>,
+    ) -> Result<(), DecodeError> {
+        let reference = protocol.read_u64(source)?;
+        let simple = SimpleIntDecodingProtocol::new();
+        let bit_width = simple.read_unsigned(source, 7)? as usize;
+        if bit_width > 64 {
+            return Err(DecodeError::Other(
+                format!("Invalid bit width {}", bit_width).into(),
+            ));
+        }
+
+        let mut bits = vec![false; bit_width * block_length];
+        source.read(&mut bits)?;
+        let mut offsets: Vec<u64> = bits
+            .chunks(usize::max(1, bit_width))
+            .take(block_length)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |offset, (index, bit)| offset | (*bit as u64) << index)
+            })
+            .collect();
+        offsets.resize(block_length, 0);
+
+        let num_exceptions = protocol.read_u64(source)?;
+        if num_exceptions > block_length as u64 {
+            return Err(DecodeError::Other(
+                format!(
+                    "{} exceptions don't fit in a block of {} values",
+                    num_exceptions, block_length
+                )
+                .into(),
+            ));
+        }
+        for _ in 0..num_exceptions {
+            let position = simple.read_unsigned(source, num_position_bits(block_length))? as usize;
+            if position >= block_length {
+                return Err(DecodeError::Other(
+                    format!(
+                        "Exception position {} is outside the block of {} values",
+                        position, block_length
+                    )
+                    .into(),
+                ));
+            }
+            let high_bits = protocol
+                .read_u64(source)?
+                .checked_add(1)
+                .ok_or(DecodeError::IntOverflow)?;
+            if bit_width == 64 || high_bits.leading_zeros() < bit_width as u32 {
+                return Err(DecodeError::IntOverflow);
+            }
+            offsets[position] |= high_bits << bit_width;
+        }
+
+        for offset in offsets {
+            dest.push(
+                reference
+                    .checked_add(offset)
+                    .ok_or(DecodeError::IntOverflow)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Decodes values that were encoded by *write* with the same parameters.
+    /// The number of values is checked against the limits of *context* before
+    /// the *Vec* is allocated.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u64>, DecodeError> {
+        let length = context.check_vec_len::<u64>(protocol.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        while values.len() < length {
+            let block_length = usize::min(self.block_size, length - values.len());
+            Self::read_block(protocol, source, block_length, &mut values)?;
+        }
+        if self.delta {
+            let mut previous = 0u64;
+            for value in &mut values {
+                *value = previous.wrapping_add(*value);
+                previous = *value;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Decodes values that were encoded by *write_u32s* with the same
+    /// parameters. Returns *DecodeError::IntOverflow* if any of the values
+    /// doesn't fit in a *u32*.
+    pub fn read_u32s(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        self.read(protocol, source, context)?
+            .into_iter()
+            .map(|value| u32::try_from(value).map_err(|_| DecodeError::IntOverflow))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(codec: PforCodec, values: &[u64]) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, values)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = codec
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .unwrap();
+        assert_eq!(values, &decoded[..]);
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut with_outliers: Vec<u64> = (0..300).map(|index| 100 + index % 7).collect();
+        with_outliers[17] = 1_000_000;
+        with_outliers[200] = u64::MAX;
+        let sorted: Vec<u64> = (0..500).map(|index| index * index).collect();
+        let unsorted: Vec<u64> = (0..500).map(|index| (index * 7919) % 1013).collect();
+        for values in &[
+            vec![],
+            vec![0],
+            vec![u64::MAX; 5],
+            vec![0, u64::MAX, 0, u64::MAX],
+            with_outliers,
+            sorted,
+            unsorted,
+        ] {
+            for block_size in &[1, 3, 128] {
+                round_trip(PforCodec::new(*block_size, false), values);
+                round_trip(PforCodec::new(*block_size, true), values);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let mut values = vec![5; 128];
+        values[50] = 5 + 1_000_000_000;
+        let size = round_trip(PforCodec::new(128, false), &values);
+        // The outlier should be an exception, so the other values should only
+        // need 0 bits each
+        assert!(size < 100, "size is {}", size);
+    }
+
+    #[test]
+    fn test_u32s() {
+        let codec = PforCodec::new(16, true);
+        let values: Vec<u32> = (0..100).map(|index| index * 1000 + index % 3).collect();
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write_u32s(&DigitIntEncodingProtocol::v1(), &mut sink, &values)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = codec.read_u32s(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+        assert_eq!(values, decoded.unwrap());
+
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, &[1, 1 << 40])
+            .unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        match codec.read_u32s(&DigitIntDecodingProtocol::v1(), &mut source, &mut context) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_bit_width() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u64(&mut sink, 1).unwrap();
+        encoder.write_u64(&mut sink, 0).unwrap();
+        // The bit width is a 7-bit field, but at most 64 is valid
+        SimpleIntEncodingProtocol::new()
+            .write_unsigned(&mut sink, 7, 65)
+            .unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let result = PforCodec::new(8, false).read(
+            &DigitIntDecodingProtocol::v1(),
+            &mut source,
+            &mut context,
+        );
+        assert!(matches!(result, Err(DecodeError::Other(_))));
+    }
+}
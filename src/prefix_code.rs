@@ -0,0 +1,324 @@
+use crate::*;
+
+use std::error::Error;
+use std::fmt::Display;
+
+/// Represents a reason why a symbol→code mapping can't be used as *PrefixCode*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixCodeError {
+    /// The code of this symbol is empty
+    EmptyCode { symbol: u16 },
+
+    /// This symbol occurs more than once in the mapping
+    DuplicateSymbol { symbol: u16 },
+
+    /// The code of *prefix_symbol* is a prefix of the code of *symbol* (or they
+    /// are equal), so the decoder can't distinguish them
+    NotPrefixFree { prefix_symbol: u16, symbol: u16 },
+}
+
+impl Display for PrefixCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            PrefixCodeError::EmptyCode { symbol } => {
+                write!(f, "The code of symbol {} is empty", symbol)
+            }
+            PrefixCodeError::DuplicateSymbol { symbol } => {
+                write!(f, "Symbol {} has more than 1 code", symbol)
+            }
+            PrefixCodeError::NotPrefixFree {
+                prefix_symbol,
+                symbol,
+            } => write!(
+                f,
+                "The code of symbol {} is a prefix of the code of symbol {}",
+                prefix_symbol, symbol
+            ),
+        }
+    }
+}
+
+impl Error for PrefixCodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecodeNode {
+    Internal([Option<usize>; 2]),
+    Leaf(u16),
+}
+
+/// A prefix code with a user-supplied mapping from symbols (*u8*s or *u16*s) to
+/// codes. This can be used to exactly match the static code tables of an
+/// existing format. The mapping is validated when the code is constructed: no
+/// code may be a prefix of another code.
+///
+/// The code doesn't need to be complete: some bit sequences may not correspond
+/// to any symbol, in which case the decoder will return an error.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let code = PrefixCode::from_bit_strings(&[(b'e', "0"), (b't', "10"), (b'x', "110")]).unwrap();
+///
+/// let mut sink = BoolVecBitSink::new();
+/// for symbol in b"text" {
+///     code.write_u8(&mut sink, *symbol).unwrap();
+/// }
+/// assert_eq!(&[true, false, false, true, true, false, true, false], sink.get_bits());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// assert_eq!(b't', code.read_u8(&mut source).unwrap());
+/// assert_eq!(b'e', code.read_u8(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixCode {
+    codes: Vec<Option<Vec<bool>>>,
+    nodes: Vec<DecodeNode>,
+}
+
+impl PrefixCode {
+    /// Constructs a *PrefixCode* from the given (symbol, code) pairs, or returns
+    /// an error if the codes are not prefix-free.
+    pub fn new(
+        mapping: impl IntoIterator<Item = (u16, Vec<bool>)>,
+    ) -> Result<Self, PrefixCodeError> {
+        let mut code = Self {
+            codes: Vec::new(),
+            nodes: vec![DecodeNode::Internal([None, None])],
+        };
+        for (symbol, bits) in mapping {
+            code.insert(symbol, bits)?;
+        }
+        Ok(code)
+    }
+
+    /// Constructs a *PrefixCode* from (symbol, code) pairs where every code is
+    /// a string of '0's and '1's (spaces are ignored), or returns an error if
+    /// the codes are not prefix-free.
+    ///
+    /// Panics if any code contains other characters.
+    pub fn from_bit_strings<S: Into<u16> + Copy>(
+        mapping: &[(S, &str)],
+    ) -> Result<Self, PrefixCodeError> {
+        Self::new(mapping.iter().map(|(symbol, code)| {
+            let bits = code
+                .chars()
+                .filter(|character| *character != ' ')
+                .map(|character| match character {
+                    '0' => false,
+                    '1' => true,
+                    _ => panic!("Unexpected character {} in code {}", character, code),
+                })
+                .collect();
+            ((*symbol).into(), bits)
+        }))
+    }
+
+    fn insert(&mut self, symbol: u16, bits: Vec<bool>) -> Result<(), PrefixCodeError> {
+        if bits.is_empty() {
+            return Err(PrefixCodeError::EmptyCode { symbol });
+        }
+        if self.get_code(symbol).is_some() {
+            return Err(PrefixCodeError::DuplicateSymbol { symbol });
+        }
+
+        let mut node = 0;
+        for bit in &bits {
+            let children = match self.nodes[node] {
+                DecodeNode::Internal(children) => children,
+                DecodeNode::Leaf(prefix_symbol) => {
+                    return Err(PrefixCodeError::NotPrefixFree {
+                        prefix_symbol,
+                        symbol,
+                    })
+                }
+            };
+            node = match children[*bit as usize] {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(DecodeNode::Internal([None, None]));
+                    if let DecodeNode::Internal(children) = &mut self.nodes[node] {
+                        children[*bit as usize] = Some(child);
+                    }
+                    child
+                }
+            };
+        }
+
+        match self.nodes[node] {
+            DecodeNode::Internal([None, None]) => {}
+            DecodeNode::Leaf(prefix_symbol) => {
+                return Err(PrefixCodeError::NotPrefixFree {
+                    prefix_symbol,
+                    symbol,
+                })
+            }
+            DecodeNode::Internal(_) => {
+                // Find any symbol whose code starts with the new code
+                let mut descendant = node;
+                while let DecodeNode::Internal(children) = self.nodes[descendant] {
+                    descendant = children[0].or(children[1]).unwrap();
+                }
+                if let DecodeNode::Leaf(longer_symbol) = self.nodes[descendant] {
+                    return Err(PrefixCodeError::NotPrefixFree {
+                        prefix_symbol: symbol,
+                        symbol: longer_symbol,
+                    });
+                }
+            }
+        }
+
+        self.nodes[node] = DecodeNode::Leaf(symbol);
+        if self.codes.len() <= symbol as usize {
+            self.codes.resize(symbol as usize + 1, None);
+        }
+        self.codes[symbol as usize] = Some(bits);
+        Ok(())
+    }
+
+    /// Gets the code of *symbol*, or *None* if *symbol* doesn't have a code
+    pub fn get_code(&self, symbol: u16) -> Option<&[bool]> {
+        match self.codes.get(symbol as usize) {
+            Some(Some(code)) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Writes the code of *symbol* to *sink*, or returns an error if *symbol*
+    /// doesn't have a code
+    pub fn write_u16(&self, sink: &mut impl BitSink, symbol: u16) -> Result<(), WriteError> {
+        match self.get_code(symbol) {
+            Some(code) => sink.write(code),
+            None => Err(format!("Symbol {} doesn't have a code", symbol).into()),
+        }
+    }
+
+    /// Writes the code of *symbol* to *sink*, or returns an error if *symbol*
+    /// doesn't have a code
+    pub fn write_u8(&self, sink: &mut impl BitSink, symbol: u8) -> Result<(), WriteError> {
+        self.write_u16(sink, symbol as u16)
+    }
+
+    /// Reads a code from *source* and returns its symbol. Returns
+    /// *DecodeError::Other* if the bits don't match any code.
+    pub fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        let mut node = 0;
+        let mut bit = [false];
+        loop {
+            match self.nodes[node] {
+                DecodeNode::Leaf(symbol) => return Ok(symbol),
+                DecodeNode::Internal(children) => {
+                    source.read(&mut bit)?;
+                    node = match children[bit[0] as usize] {
+                        Some(child) => child,
+                        None => {
+                            return Err(DecodeError::Other("The bits don't match any code".into()))
+                        }
+                    };
+                }
+            }
+        }
+    }
+
+    /// Reads a code from *source* and returns its symbol. Returns
+    /// *DecodeError::Other* if the bits don't match any code, or if the symbol
+    /// doesn't fit in a *u8*.
+    pub fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        let symbol = self.read_u16(source)?;
+        if symbol > u8::MAX as u16 {
+            return Err(DecodeError::Other(
+                format!("Symbol {} doesn't fit in a u8", symbol).into(),
+            ));
+        }
+        Ok(symbol as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::parse_bit_string;
+    use crate::*;
+
+    #[test]
+    fn test_round_trip() {
+        // The fixed literal/length code of DEFLATE (for symbols 0 to 279)
+        let mut mapping = Vec::new();
+        for symbol in 0..144u16 {
+            let code = 0b0011_0000 + symbol;
+            mapping.push((symbol, (0..8).rev().map(|i| code & (1 << i) != 0).collect()));
+        }
+        for symbol in 144..256u16 {
+            let code = 0b1_1001_0000 + symbol - 144;
+            mapping.push((symbol, (0..9).rev().map(|i| code & (1 << i) != 0).collect()));
+        }
+        for symbol in 256..280u16 {
+            let code = symbol - 256;
+            mapping.push((symbol, (0..7).rev().map(|i| code & (1 << i) != 0).collect()));
+        }
+        let code = PrefixCode::new(mapping).unwrap();
+        assert_eq!(Some(&parse_bit_string("0011 0000")[..]), code.get_code(0));
+        assert_eq!(None, code.get_code(280));
+
+        let mut sink = BoolVecBitSink::new();
+        for symbol in 0..280 {
+            code.write_u16(&mut sink, symbol).unwrap();
+        }
+        assert!(code.write_u16(&mut sink, 280).is_err());
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for symbol in 0..280 {
+            assert_eq!(symbol, code.read_u16(&mut source).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(
+            Err(PrefixCodeError::EmptyCode { symbol: 3 }),
+            PrefixCode::from_bit_strings(&[(1u8, "0"), (3u8, "")])
+        );
+        assert_eq!(
+            Err(PrefixCodeError::DuplicateSymbol { symbol: 1 }),
+            PrefixCode::from_bit_strings(&[(1u8, "0"), (1u8, "1")])
+        );
+        assert_eq!(
+            Err(PrefixCodeError::NotPrefixFree {
+                prefix_symbol: 1,
+                symbol: 2
+            }),
+            PrefixCode::from_bit_strings(&[(1u8, "01"), (2u8, "011")])
+        );
+        assert_eq!(
+            Err(PrefixCodeError::NotPrefixFree {
+                prefix_symbol: 2,
+                symbol: 1
+            }),
+            PrefixCode::from_bit_strings(&[(1u8, "011"), (2u8, "01")])
+        );
+        assert_eq!(
+            Err(PrefixCodeError::NotPrefixFree {
+                prefix_symbol: 1,
+                symbol: 2
+            }),
+            PrefixCode::from_bit_strings(&[(1u8, "01"), (2u8, "01")])
+        );
+    }
+
+    #[test]
+    fn test_incomplete_code() {
+        let code = PrefixCode::from_bit_strings(&[(5u16, "0"), (300u16, "10")]).unwrap();
+
+        let mut sink = BoolVecBitSink::new();
+        code.write_u16(&mut sink, 300).unwrap();
+        code.write_u8(&mut sink, 5).unwrap();
+        assert!(code.write_u8(&mut sink, 6).is_err());
+        sink.write(&[true, true]).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert!(code.read_u8(&mut source).is_err());
+        assert_eq!(5, code.read_u8(&mut source).unwrap());
+        assert!(code.read_u16(&mut source).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+/// A set of protocols that assigns a protocol to each role that an integer can
+/// have in a message:
+/// - *lengths*: the lengths of collections, strings and byte arrays, which are
+///   usually small, so they benefit from a protocol like
+///   *DigitIntEncodingProtocol*
+/// - *ids*: identifiers like keys and entity ids
+/// - *timestamps*: points in time, which are often large but close together
+/// - *payload*: the actual values, including the bytes of strings and byte
+///   arrays. When these are hashes or otherwise random, a protocol like
+///   *SimpleIntEncodingProtocol* is the most compact.
+///
+/// Choosing a single protocol for an entire message forces compromises, so the
+/// higher-level encoders of this crate (like *KeyValueEncoder* and
+/// *write_snapshot_delta_with_protocols*) can also take a *ProtocolSet*. The
+/// same type is used for both encoding protocols and decoding protocols, and
+/// the decoder must of course use the corresponding decoding protocol for every
+/// role.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let protocols = ProtocolSet::uniform(DigitIntEncodingProtocol::v1())
+///     .with_payload(SimpleIntEncodingProtocol::new());
+/// let mut record = KeyValueRecord::new();
+/// record.insert(RecordKey::Id(3), RecordValue::Bytes(vec![0xA7, 0x3F, 0xC2, 0x19]));
+///
+/// let mut sink = BoolVecBitSink::new();
+/// KeyValueEncoder::from_protocols(&protocols).write_record(&mut sink, &record).unwrap();
+///
+/// let decoder_protocols = ProtocolSet::uniform(DigitIntDecodingProtocol::v1())
+///     .with_payload(SimpleIntDecodingProtocol::new());
+/// let mut decoder = KeyValueDecoder::from_protocols(&decoder_protocols);
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// assert_eq!(record, decoder.read_record(&mut source, &mut context).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtocolSet<L, I, T, P> {
+    lengths: L,
+    ids: I,
+    timestamps: T,
+    payload: P,
+}
+
+impl<X: Clone> ProtocolSet<X, X, X, X> {
+    /// Constructs a new *ProtocolSet* that uses *protocol* for all roles
+    pub fn uniform(protocol: X) -> Self {
+        Self {
+            lengths: protocol.clone(),
+            ids: protocol.clone(),
+            timestamps: protocol.clone(),
+            payload: protocol,
+        }
+    }
+}
+
+impl<L, I, T, P> ProtocolSet<L, I, T, P> {
+    /// Constructs a new *ProtocolSet* with the given protocol for each role
+    pub fn new(lengths: L, ids: I, timestamps: T, payload: P) -> Self {
+        Self {
+            lengths,
+            ids,
+            timestamps,
+            payload,
+        }
+    }
+
+    /// Replaces the protocol for lengths with *lengths*
+    pub fn with_lengths<N>(self, lengths: N) -> ProtocolSet<N, I, T, P> {
+        ProtocolSet::new(lengths, self.ids, self.timestamps, self.payload)
+    }
+
+    /// Replaces the protocol for ids with *ids*
+    pub fn with_ids<N>(self, ids: N) -> ProtocolSet<L, N, T, P> {
+        ProtocolSet::new(self.lengths, ids, self.timestamps, self.payload)
+    }
+
+    /// Replaces the protocol for timestamps with *timestamps*
+    pub fn with_timestamps<N>(self, timestamps: N) -> ProtocolSet<L, I, N, P> {
+        ProtocolSet::new(self.lengths, self.ids, timestamps, self.payload)
+    }
+
+    /// Replaces the protocol for payload values with *payload*
+    pub fn with_payload<N>(self, payload: N) -> ProtocolSet<L, I, T, N> {
+        ProtocolSet::new(self.lengths, self.ids, self.timestamps, payload)
+    }
+
+    /// Gets the protocol for lengths
+    pub fn get_lengths(&self) -> &L {
+        &self.lengths
+    }
+
+    /// Gets the protocol for ids
+    pub fn get_ids(&self) -> &I {
+        &self.ids
+    }
+
+    /// Gets the protocol for timestamps
+    pub fn get_timestamps(&self) -> &T {
+        &self.timestamps
+    }
+
+    /// Gets the protocol for payload values
+    pub fn get_payload(&self) -> &P {
+        &self.payload
+    }
+}
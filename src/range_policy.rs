@@ -0,0 +1,137 @@
+/// Decides what an encoder does with a value that is outside the range that it
+/// can encode, like a *BitLayout* field or the latitude of a *GeoCodec*. Using
+/// an explicit policy avoids having to clamp (or wrap) every value by hand
+/// before encoding it.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// assert_eq!(None, RangePolicy::Error.apply(20, 0, 15));
+/// assert_eq!(Some(15), RangePolicy::Saturate.apply(20, 0, 15));
+/// assert_eq!(Some(4), RangePolicy::Wrap.apply(20, 0, 15));
+/// // Values in the range are never changed
+/// assert_eq!(Some(7), RangePolicy::Wrap.apply(7, 0, 15));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RangePolicy {
+    /// Refuse to encode the value (the write method will return an error).
+    /// This is the default policy.
+    #[default]
+    Error,
+    /// Replace the value by the nearest bound of the range
+    Saturate,
+    /// Wrap the value around, as if the range repeats itself (like the
+    /// overflow of unsigned integers)
+    Wrap,
+}
+
+impl RangePolicy {
+    /// Applies this policy to *value* for the range [*min*, *max*]. Returns
+    /// *None* if the value is out of range and this policy is *Error*.
+    ///
+    /// Panics if *min* is larger than *max*.
+    pub fn apply(self, value: i128, min: i128, max: i128) -> Option<i128> {
+        assert!(min <= max, "Invalid range [{}, {}]", min, max);
+        if value >= min && value <= max {
+            return Some(value);
+        }
+        match self {
+            RangePolicy::Error => None,
+            RangePolicy::Saturate => Some(value.max(min).min(max)),
+            RangePolicy::Wrap => {
+                // The range can't contain all i128 values, or value would be in it
+                let size = max.wrapping_sub(min) as u128 + 1;
+                Some(if value < min {
+                    let distance = min.wrapping_sub(value) as u128;
+                    max.wrapping_sub(((distance - 1) % size) as i128)
+                } else {
+                    let distance = value.wrapping_sub(min) as u128;
+                    min.wrapping_add((distance % size) as i128)
+                })
+            }
+        }
+    }
+
+    /// Applies this policy to *value* for the range [*min*, *max*], where *Wrap*
+    /// uses a period of *max - min*. Returns *None* if the value is NaN, or if
+    /// it is out of range and this policy is *Error*.
+    ///
+    /// Panics if *min* is not smaller than *max*.
+    pub fn apply_f64(self, value: f64, min: f64, max: f64) -> Option<f64> {
+        assert!(min < max, "Invalid range [{}, {}]", min, max);
+        if value.is_nan() {
+            return None;
+        }
+        if value >= min && value <= max {
+            return Some(value);
+        }
+        match self {
+            RangePolicy::Error => None,
+            RangePolicy::Saturate => Some(value.max(min).min(max)),
+            RangePolicy::Wrap if value.is_infinite() => None,
+            RangePolicy::Wrap => Some(min + (value - min).rem_euclid(max - min)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_apply() {
+        for policy in &[RangePolicy::Error, RangePolicy::Saturate, RangePolicy::Wrap] {
+            for value in -3..=3 {
+                assert_eq!(Some(value), policy.apply(value, -3, 3));
+            }
+        }
+        assert_eq!(None, RangePolicy::Error.apply(-4, -3, 3));
+        assert_eq!(Some(-3), RangePolicy::Saturate.apply(-4, -3, 3));
+        assert_eq!(Some(3), RangePolicy::Wrap.apply(-4, -3, 3));
+        assert_eq!(Some(-3), RangePolicy::Wrap.apply(4, -3, 3));
+        assert_eq!(Some(-3), RangePolicy::Wrap.apply(-10, -3, 3));
+        assert_eq!(Some(3), RangePolicy::Wrap.apply(10, -3, 3));
+        assert_eq!(Some(5), RangePolicy::Wrap.apply(5, 5, 5));
+        assert_eq!(Some(5), RangePolicy::Wrap.apply(-100, 5, 5));
+
+        // Extreme values
+        assert_eq!(Some(255), RangePolicy::Wrap.apply(i128::MAX, 0, 255));
+        assert_eq!(Some(0), RangePolicy::Wrap.apply(i128::MIN, 0, 255));
+        assert_eq!(
+            Some(i128::MIN),
+            RangePolicy::Wrap.apply(i128::MAX, i128::MIN, i128::MAX - 1)
+        );
+        assert_eq!(
+            Some(u64::MAX as i128),
+            RangePolicy::Saturate.apply(i128::MAX, 0, u64::MAX as i128)
+        );
+    }
+
+    #[test]
+    fn test_apply_f64() {
+        assert_eq!(None, RangePolicy::Error.apply_f64(190.0, -180.0, 180.0));
+        assert_eq!(
+            Some(180.0),
+            RangePolicy::Saturate.apply_f64(190.0, -180.0, 180.0)
+        );
+        assert_eq!(
+            Some(-170.0),
+            RangePolicy::Wrap.apply_f64(190.0, -180.0, 180.0)
+        );
+        assert_eq!(
+            Some(170.0),
+            RangePolicy::Wrap.apply_f64(-550.0, -180.0, 180.0)
+        );
+        assert_eq!(
+            Some(-90.0),
+            RangePolicy::Saturate.apply_f64(f64::NEG_INFINITY, -90.0, 90.0)
+        );
+        for policy in &[RangePolicy::Error, RangePolicy::Saturate, RangePolicy::Wrap] {
+            assert_eq!(None, policy.apply_f64(f64::NAN, 0.0, 1.0));
+            assert_eq!(Some(0.5), policy.apply_f64(0.5, 0.0, 1.0));
+        }
+        assert_eq!(None, RangePolicy::Wrap.apply_f64(f64::INFINITY, 0.0, 1.0));
+    }
+}
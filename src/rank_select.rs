@@ -0,0 +1,395 @@
+use crate::*;
+
+/// The number of 64-bit words per superblock
+const SUPERBLOCK_WORDS: usize = 8;
+
+/// The number of 1 bits between two select samples
+const SELECT_SAMPLE_RATE: u64 = 512;
+
+/// An immutable bit vector that supports *rank* (counting the 1 bits before a
+/// position) queries in constant time, and *select* (finding the position of
+/// the n-th 1 bit) queries in nearly constant time. This is the building block
+/// of many succinct data structures (like Elias-Fano codes and wavelet trees).
+///
+/// The bits are stored in 64-bit words. On top of that, this stores the number
+/// of 1 bits before every block of 512 bits (12.5% extra memory), and the
+/// superblock of every 512th 1 bit (at most 12.5% extra memory). A rank query
+/// counts the 1 bits of at most 8 words, and a select query looks up the
+/// sampled superblocks before and after the 1 bit, and then binary searches the
+/// superblocks between them. There are only many such superblocks when the 1
+/// bits are very sparse, so select queries take logarithmic time in the worst
+/// case.
+///
+/// Only the bits themselves are encoded by *write*; the rank and select
+/// samples are rebuilt by *read*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let bits: Vec<bool> = (0..1000).map(|index| index % 3 == 0).collect();
+/// let vector = RankSelectBitVec::from_bools(&bits);
+/// assert_eq!(334, vector.count_ones());
+/// // The 1 bits before position 10 are at positions 0, 3, 6, and 9
+/// assert_eq!(4, vector.rank1(10));
+/// assert_eq!(Some(9), vector.select1(3));
+/// assert_eq!(None, vector.select1(334));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankSelectBitVec {
+    words: Vec<u64>,
+    num_bits: u64,
+    superblock_ranks: Vec<u64>,
+    select_samples: Vec<u32>,
+}
+
+impl RankSelectBitVec {
+    fn from_words(words: Vec<u64>, num_bits: u64) -> Self {
+        let mut superblock_ranks = Vec::with_capacity(words.len() / SUPERBLOCK_WORDS + 2);
+        let mut select_samples = Vec::new();
+        let mut rank = 0;
+        for (index, superblock) in words.chunks(SUPERBLOCK_WORDS).enumerate() {
+            superblock_ranks.push(rank);
+            let num_ones: u64 = superblock.iter().map(|word| word.count_ones() as u64).sum();
+            // Record this superblock for every sampled 1 bit that it contains
+            while (select_samples.len() as u64) * SELECT_SAMPLE_RATE < rank + num_ones {
+                select_samples.push(index as u32);
+            }
+            rank += num_ones;
+        }
+        superblock_ranks.push(rank);
+        Self {
+            words,
+            num_bits,
+            superblock_ranks,
+            select_samples,
+        }
+    }
+
+    /// Constructs a new *RankSelectBitVec* that contains the given bits
+    pub fn from_bools(bits: &[bool]) -> Self {
+        let words = bits
+            .chunks(64)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |word, (index, bit)| word | (*bit as u64) << index)
+            })
+            .collect();
+        Self::from_words(words, bits.len() as u64)
+    }
+
+    /// Constructs a new *RankSelectBitVec* that contains the first *num_bits*
+    /// bits of *bytes*, in the same order as *U8VecBitSink* (starting at the
+    /// least significant bit of each byte).
+    ///
+    /// Panics if *bytes* contains less than *num_bits* bits.
+    pub fn from_bytes(bytes: &[u8], num_bits: u64) -> Self {
+        assert!(num_bits <= 8 * bytes.len() as u64);
+        let num_bytes = num_bits.div_ceil(8) as usize;
+        let mut words: Vec<u64> = bytes[0..num_bytes]
+            .chunks(8)
+            .map(|chunk| {
+                let mut word_bytes = [0; 8];
+                word_bytes[0..chunk.len()].copy_from_slice(chunk);
+                u64::from_le_bytes(word_bytes)
+            })
+            .collect();
+        if !num_bits.is_multiple_of(64) {
+            let last_index = words.len() - 1;
+            words[last_index] &= (1 << (num_bits % 64)) - 1;
+        }
+        Self::from_words(words, num_bits)
+    }
+
+    /// Gets the number of bits in this vector
+    pub fn len(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Checks whether this vector contains 0 bits
+    pub fn is_empty(&self) -> bool {
+        self.num_bits == 0
+    }
+
+    /// Gets the number of 1 bits in this vector
+    pub fn count_ones(&self) -> u64 {
+        *self.superblock_ranks.last().unwrap()
+    }
+
+    /// Gets the bit at *position*.
+    ///
+    /// Panics if *position* is not smaller than the length of this vector.
+    pub fn get(&self, position: u64) -> bool {
+        assert!(
+            position < self.num_bits,
+            "Position {} is out of bounds",
+            position
+        );
+        self.words[position as usize / 64] & 1 << (position % 64) != 0
+    }
+
+    /// Counts the number of 1 bits before *position* (exclusive).
+    ///
+    /// Panics if *position* is larger than the length of this vector.
+    pub fn rank1(&self, position: u64) -> u64 {
+        assert!(
+            position <= self.num_bits,
+            "Position {} is out of bounds",
+            position
+        );
+        let word_index = position as usize / 64;
+        let superblock_index = word_index / SUPERBLOCK_WORDS;
+        let mut rank = self.superblock_ranks[superblock_index];
+        for word in &self.words[superblock_index * SUPERBLOCK_WORDS..word_index] {
+            rank += word.count_ones() as u64;
+        }
+        if !position.is_multiple_of(64) {
+            rank += (self.words[word_index] & ((1 << (position % 64)) - 1)).count_ones() as u64;
+        }
+        rank
+    }
+
+    /// Counts the number of 0 bits before *position* (exclusive).
+    ///
+    /// Panics if *position* is larger than the length of this vector.
+    pub fn rank0(&self, position: u64) -> u64 {
+        position - self.rank1(position)
+    }
+
+    /// Finds the position of the 1 bit with index *rank* (starting at 0), or
+    /// returns *None* if this vector doesn't contain more than *rank* 1 bits.
+    /// For every *rank* smaller than *count_ones()*, it holds that
+    /// *rank1(select1(rank)) == rank*.
+    pub fn select1(&self, rank: u64) -> Option<u64> {
+        if rank >= self.count_ones() {
+            return None;
+        }
+        // The 1 bit is between the sampled superblocks before and after it
+        let sample_index = (rank / SELECT_SAMPLE_RATE) as usize;
+        let first_superblock = self.select_samples[sample_index] as usize;
+        let last_superblock = match self.select_samples.get(sample_index + 1) {
+            Some(next_sample) => *next_sample as usize,
+            None => self.superblock_ranks.len() - 2,
+        };
+        let superblock_index = first_superblock
+            + self.superblock_ranks[first_superblock + 1..=last_superblock]
+                .partition_point(|superblock_rank| *superblock_rank <= rank);
+
+        let mut remaining = rank - self.superblock_ranks[superblock_index];
+        let mut word_index = superblock_index * SUPERBLOCK_WORDS;
+        loop {
+            let num_ones = self.words[word_index].count_ones() as u64;
+            if remaining < num_ones {
+                break;
+            }
+            remaining -= num_ones;
+            word_index += 1;
+        }
+
+        let mut word = self.words[word_index];
+        for _ in 0..remaining {
+            // Clear the lowest 1 bit
+            word &= word - 1;
+        }
+        Some(64 * word_index as u64 + word.trailing_zeros() as u64)
+    }
+
+    /// Finds the position of the 0 bit with index *rank* (starting at 0), or
+    /// returns *None* if this vector doesn't contain more than *rank* 0 bits.
+    /// Unlike *select1*, this doesn't have samples to narrow down the
+    /// superblocks, so it binary searches all of them.
+    pub fn select0(&self, rank: u64) -> Option<u64> {
+        if rank >= self.num_bits - self.count_ones() {
+            return None;
+        }
+        let superblock_bits = 64 * SUPERBLOCK_WORDS as u64;
+        let num_zeros_before = |superblock_index: usize| {
+            superblock_bits * superblock_index as u64 - self.superblock_ranks[superblock_index]
+        };
+        // Find the last superblock whose number of preceding 0 bits is at most rank
+        let mut superblock_index = 0;
+        let mut bound = self.superblock_ranks.len() - 1;
+        while bound - superblock_index > 1 {
+            let middle = (superblock_index + bound) / 2;
+            if num_zeros_before(middle) <= rank {
+                superblock_index = middle;
+            } else {
+                bound = middle;
+            }
+        }
+
+        let mut remaining = rank - num_zeros_before(superblock_index);
+        let mut word_index = superblock_index * SUPERBLOCK_WORDS;
+        loop {
+            let num_zeros = self.words[word_index].count_zeros() as u64;
+            if remaining < num_zeros {
+                break;
+            }
+            remaining -= num_zeros;
+            word_index += 1;
+        }
+
+        let mut word = !self.words[word_index];
+        for _ in 0..remaining {
+            word &= word - 1;
+        }
+        Some(64 * word_index as u64 + word.trailing_zeros() as u64)
+    }
+
+    /// Writes all bits of this vector to *sink*, without the length
+    pub(crate) fn write_bits(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        let mut bits = Vec::with_capacity(64 * usize::min(self.words.len(), 64));
+        for (word_index, word) in self.words.iter().enumerate() {
+            let num_bits = u64::min(64, self.num_bits - 64 * word_index as u64);
+            for index in 0..num_bits {
+                bits.push(word & 1 << index != 0);
+            }
+            if bits.len() >= 4096 {
+                sink.write(&bits)?;
+                bits.clear();
+            }
+        }
+        sink.write(&bits)
+    }
+
+    /// Reads a vector of *num_bits* bits that was written by *write_bits*
+    pub(crate) fn read_bits(
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+        num_bits: u64,
+    ) -> Result<Self, DecodeError> {
+        let num_words = context.check_vec_len::<u64>(num_bits.div_ceil(64))?;
+        let mut words = Vec::with_capacity(num_words);
+        let mut bits = [false; 64];
+        for word_index in 0..num_words {
+            let num_word_bits = u64::min(64, num_bits - 64 * word_index as u64) as usize;
+            source.read(&mut bits[0..num_word_bits])?;
+            words.push(
+                bits[0..num_word_bits]
+                    .iter()
+                    .enumerate()
+                    .fold(0, |word, (index, bit)| word | (*bit as u64) << index),
+            );
+        }
+        Ok(Self::from_words(words, num_bits))
+    }
+
+    /// Writes the length of this vector with *protocol*, followed by all bits
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError> {
+        protocol.write_u64(sink, self.num_bits)?;
+        self.write_bits(sink)
+    }
+
+    /// Reads a vector that was written by *write*, and rebuilds its rank and
+    /// select samples. The length is checked against the limits of *context*.
+    pub fn read(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError> {
+        let num_bits = protocol.read_u64(source)?;
+        Self::read_bits(source, context, num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn check_queries(bits: &[bool]) {
+        let vector = RankSelectBitVec::from_bools(bits);
+        assert_eq!(bits.len() as u64, vector.len());
+        let mut rank = 0;
+        for (position, bit) in bits.iter().enumerate() {
+            assert_eq!(*bit, vector.get(position as u64));
+            assert_eq!(rank, vector.rank1(position as u64));
+            let rank0 = position as u64 - rank;
+            assert_eq!(rank0, vector.rank0(position as u64));
+            if *bit {
+                assert_eq!(Some(position as u64), vector.select1(rank));
+                rank += 1;
+            } else {
+                assert_eq!(Some(position as u64), vector.select0(rank0));
+            }
+        }
+        assert_eq!(rank, vector.rank1(bits.len() as u64));
+        assert_eq!(rank, vector.count_ones());
+        assert_eq!(None, vector.select1(rank));
+        assert_eq!(None, vector.select0(bits.len() as u64 - rank));
+    }
+
+    #[test]
+    fn test_queries() {
+        check_queries(&[]);
+        check_queries(&[true]);
+        check_queries(&[false; 1000]);
+        check_queries(&[true; 3000]);
+        let sparse: Vec<bool> = (0..20_000).map(|index| index % 997 == 5).collect();
+        check_queries(&sparse);
+        let mixed: Vec<bool> = (0..20_000u64)
+            .map(|index| index.wrapping_mul(2_654_435_761) % 7 < 3 || (5000..9000).contains(&index))
+            .collect();
+        check_queries(&mixed);
+    }
+
+    #[test]
+    fn test_sparse_select() {
+        // The 1 bits at both ends are separated by thousands of superblocks
+        let num_bits = 4_000_000;
+        let mut bits = vec![false; num_bits];
+        let positions: Vec<u64> = (0..600)
+            .chain(vec![1_234_567, 2_345_678])
+            .chain(num_bits as u64 - 600..num_bits as u64)
+            .collect();
+        for position in &positions {
+            bits[*position as usize] = true;
+        }
+
+        let vector = RankSelectBitVec::from_bools(&bits);
+        assert_eq!(positions.len() as u64, vector.count_ones());
+        for (rank, position) in positions.iter().enumerate() {
+            assert_eq!(Some(*position), vector.select1(rank as u64));
+            assert_eq!(rank as u64, vector.rank1(*position));
+        }
+        assert_eq!(None, vector.select1(positions.len() as u64));
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let bytes: Vec<u8> = (0..100).map(|index| (index * 37) as u8).collect();
+        let bits = bytes_to_bools(&bytes);
+        for num_bits in &[0, 1, 63, 64, 65, 700, 800] {
+            assert_eq!(
+                RankSelectBitVec::from_bools(&bits[0..*num_bits]),
+                RankSelectBitVec::from_bytes(&bytes, *num_bits as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_encoding() {
+        let bits: Vec<bool> = (0..10_000).map(|index| index % 13 < 4).collect();
+        let vector = RankSelectBitVec::from_bools(&bits);
+        let mut sink = BoolVecBitSink::new();
+        vector
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink)
+            .unwrap();
+        assert_eq!(
+            &bits[..],
+            &sink.get_bits()[sink.get_bits().len() - bits.len()..]
+        );
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded =
+            RankSelectBitVec::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+        assert_eq!(vector, decoded.unwrap());
+    }
+}
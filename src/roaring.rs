@@ -0,0 +1,564 @@
+use crate::*;
+
+use std::iter::FromIterator;
+
+/// The maximum number of values in an array container. Larger containers use a
+/// bitmap, which takes 65536 bits, or 4096 values of 16 bits.
+const MAX_ARRAY_SIZE: usize = 4096;
+
+const NUM_BITMAP_WORDS: usize = 65536 / 64;
+
+#[derive(Debug, Clone)]
+enum Container {
+    /// The sorted values of the chunk
+    Array(Vec<u16>),
+    /// 1 bit per value of the chunk, and the number of 1 bits
+    Bitmap(Box<[u64; NUM_BITMAP_WORDS]>, u32),
+    /// The sorted runs of consecutive values of the chunk: the first value of
+    /// each run, and the length of the run minus 1
+    Runs(Vec<(u16, u16)>),
+}
+
+impl Container {
+    fn len(&self) -> u32 {
+        match self {
+            Container::Array(values) => values.len() as u32,
+            Container::Bitmap(_, cardinality) => *cardinality,
+            Container::Runs(runs) => runs.iter().map(|(_, length)| *length as u32 + 1).sum(),
+        }
+    }
+
+    fn contains(&self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&value).is_ok(),
+            Container::Bitmap(words, _) => words[value as usize / 64] & 1 << (value % 64) != 0,
+            Container::Runs(runs) => {
+                let index = runs.partition_point(|(start, _)| *start <= value);
+                index > 0 && value - runs[index - 1].0 <= runs[index - 1].1
+            }
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u16> + '_> {
+        match self {
+            Container::Array(values) => Box::new(values.iter().copied()),
+            Container::Bitmap(words, _) => Box::new(
+                (0..=u16::MAX)
+                    .filter(move |value| words[*value as usize / 64] & 1 << (value % 64) != 0),
+            ),
+            Container::Runs(runs) => Box::new(
+                runs.iter()
+                    .flat_map(|(start, length)| *start..=start + length),
+            ),
+        }
+    }
+
+    fn to_bitmap(&self) -> Container {
+        let mut words = Box::new([0; NUM_BITMAP_WORDS]);
+        for value in self.iter() {
+            words[value as usize / 64] |= 1 << (value % 64);
+        }
+        Container::Bitmap(words, self.len())
+    }
+
+    fn to_array_or_bitmap(&self) -> Container {
+        match self.len() as usize > MAX_ARRAY_SIZE {
+            true => self.to_bitmap(),
+            false => Container::Array(self.iter().collect()),
+        }
+    }
+
+    fn to_runs(&self) -> Container {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for value in self.iter() {
+            match runs.last_mut() {
+                Some((start, length)) if *start as u32 + *length as u32 + 1 == value as u32 => {
+                    *length += 1
+                }
+                _ => runs.push((value, 0)),
+            }
+        }
+        Container::Runs(runs)
+    }
+
+    fn num_runs(&self) -> usize {
+        match self {
+            Container::Runs(runs) => runs.len(),
+            _ => {
+                let mut num_runs = 0;
+                let mut next = None;
+                for value in self.iter() {
+                    if next != Some(value as u32) {
+                        num_runs += 1;
+                    }
+                    next = Some(value as u32 + 1);
+                }
+                num_runs
+            }
+        }
+    }
+
+    /// Inserts *value*, and returns true if it wasn't present yet
+    fn insert(&mut self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => match values.binary_search(&value) {
+                Ok(_) => false,
+                Err(index) => {
+                    values.insert(index, value);
+                    if values.len() > MAX_ARRAY_SIZE {
+                        *self = self.to_bitmap();
+                    }
+                    true
+                }
+            },
+            Container::Bitmap(words, cardinality) => {
+                let mask = 1 << (value % 64);
+                let word = &mut words[value as usize / 64];
+                let is_new = *word & mask == 0;
+                *word |= mask;
+                *cardinality += is_new as u32;
+                is_new
+            }
+            Container::Runs(_) => {
+                if self.contains(value) {
+                    return false;
+                }
+                *self = self.to_array_or_bitmap();
+                self.insert(value)
+            }
+        }
+    }
+
+    /// Removes *value*, and returns true if it was present
+    fn remove(&mut self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => match values.binary_search(&value) {
+                Ok(index) => {
+                    values.remove(index);
+                    true
+                }
+                Err(_) => false,
+            },
+            Container::Bitmap(words, cardinality) => {
+                let mask = 1 << (value % 64);
+                let word = &mut words[value as usize / 64];
+                let was_present = *word & mask != 0;
+                *word &= !mask;
+                *cardinality -= was_present as u32;
+                if *cardinality as usize <= MAX_ARRAY_SIZE {
+                    *self = Container::Array(self.iter().collect());
+                }
+                was_present
+            }
+            Container::Runs(_) => {
+                if !self.contains(value) {
+                    return false;
+                }
+                *self = self.to_array_or_bitmap();
+                self.remove(value)
+            }
+        }
+    }
+
+    fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError> {
+        match self {
+            Container::Array(values) => {
+                sink.write(&[false, false])?;
+                protocol.write_u16(sink, (values.len() - 1) as u16)?;
+                let mut next = 0;
+                for value in values {
+                    protocol.write_u16(sink, value - next)?;
+                    next = value.wrapping_add(1);
+                }
+                Ok(())
+            }
+            Container::Bitmap(words, _) => {
+                sink.write(&[true, false])?;
+                let mut bits = Vec::with_capacity(65536);
+                for word in words.iter() {
+                    for index in 0..64 {
+                        bits.push(word & 1 << index != 0);
+                    }
+                }
+                sink.write(&bits)
+            }
+            Container::Runs(runs) => {
+                sink.write(&[false, true])?;
+                protocol.write_u16(sink, (runs.len() - 1) as u16)?;
+                let mut next = 0;
+                for (start, length) in runs {
+                    protocol.write_u16(sink, start - next)?;
+                    protocol.write_u16(sink, *length)?;
+                    next = (start + length).wrapping_add(1);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn read(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Container, DecodeError> {
+        let invalid = |message: &str| DecodeError::Other(message.to_string().into());
+        let mut kind = [false; 2];
+        source.read(&mut kind)?;
+        match kind {
+            [false, false] => {
+                let length = context.check_vec_len::<u16>(protocol.read_u16(source)? as u64 + 1)?;
+                let mut values = Vec::with_capacity(length);
+                let mut next = 0u32;
+                for _ in 0..length {
+                    let value = next + protocol.read_u16(source)? as u32;
+                    if value > u16::MAX as u32 {
+                        return Err(invalid("Array container value is too large"));
+                    }
+                    values.push(value as u16);
+                    next = value + 1;
+                }
+                Ok(Container::Array(values))
+            }
+            [true, false] => {
+                let mut bits = vec![false; 65536];
+                source.read(&mut bits)?;
+                let mut words = Box::new([0; NUM_BITMAP_WORDS]);
+                for (word, chunk) in words.iter_mut().zip(bits.chunks(64)) {
+                    for (index, bit) in chunk.iter().enumerate() {
+                        *word |= (*bit as u64) << index;
+                    }
+                }
+                let cardinality = words.iter().map(|word| word.count_ones()).sum();
+                if cardinality == 0 {
+                    return Err(invalid("Empty bitmap container"));
+                }
+                Ok(Container::Bitmap(words, cardinality))
+            }
+            [false, true] => {
+                let num_runs =
+                    context.check_vec_len::<(u16, u16)>(protocol.read_u16(source)? as u64 + 1)?;
+                let mut runs = Vec::with_capacity(num_runs);
+                let mut next = 0u32;
+                for _ in 0..num_runs {
+                    let start = next + protocol.read_u16(source)? as u32;
+                    let length = protocol.read_u16(source)?;
+                    if start + length as u32 > u16::MAX as u32 {
+                        return Err(invalid("Run container run is too large"));
+                    }
+                    runs.push((start as u16, length));
+                    next = start + length as u32 + 1;
+                }
+                Ok(Container::Runs(runs))
+            }
+            [true, true] => Err(invalid("Unknown container type")),
+        }
+    }
+}
+
+/// A compressed set of *u32*s in the style of Roaring bitmaps. The values are
+/// partitioned into chunks of 2^16 values (based on their 16 most significant
+/// bits), and each non-empty chunk stores its values in the container type
+/// that suits it best:
+/// - An *array* container stores the sorted values of the chunk, and is used
+///   for chunks with at most 4096 values.
+/// - A *bitmap* container stores 1 bit for every possible value in the chunk,
+///   and is used for chunks with more than 4096 values.
+/// - A *run* container stores runs of consecutive values. Chunks are only
+///   converted to run containers by *optimize*, since inserting values in the
+///   middle of runs is expensive.
+///
+/// Unlike the codecs in this crate, a *RoaringSet* can be queried and modified
+/// while it is compressed. It can be written to a *BitSink* with *write*, and
+/// read back with *read*, which preserves the container types.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut set: RoaringSet = (1000..200_000).collect();
+/// set.insert(5_000_000);
+/// set.remove(1234);
+/// assert!(set.contains(1000));
+/// assert!(!set.contains(1234));
+/// assert_eq!(199_000, set.len());
+///
+/// // The consecutive values are stored in a couple of runs
+/// set.optimize();
+/// let mut sink = BoolVecBitSink::new();
+/// set.write(&DigitIntEncodingProtocol::v1(), &mut sink).unwrap();
+/// assert!(sink.get_num_bools() < 1000);
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = RoaringSet::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context).unwrap();
+/// assert!(decoded.iter().eq(set.iter()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RoaringSet {
+    chunks: Vec<(u16, Container)>,
+}
+
+impl RoaringSet {
+    /// Constructs a new empty *RoaringSet*
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn split(value: u32) -> (u16, u16) {
+        ((value >> 16) as u16, value as u16)
+    }
+
+    fn find_chunk(&self, key: u16) -> Result<usize, usize> {
+        self.chunks
+            .binary_search_by_key(&key, |(chunk_key, _)| *chunk_key)
+    }
+
+    /// Checks whether *value* is in this set
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, low) = Self::split(value);
+        match self.find_chunk(key) {
+            Ok(index) => self.chunks[index].1.contains(low),
+            Err(_) => false,
+        }
+    }
+
+    /// Adds *value* to this set, and returns true if it wasn't in this set yet
+    pub fn insert(&mut self, value: u32) -> bool {
+        let (key, low) = Self::split(value);
+        match self.find_chunk(key) {
+            Ok(index) => self.chunks[index].1.insert(low),
+            Err(index) => {
+                self.chunks
+                    .insert(index, (key, Container::Array(vec![low])));
+                true
+            }
+        }
+    }
+
+    /// Removes *value* from this set, and returns true if it was in this set
+    pub fn remove(&mut self, value: u32) -> bool {
+        let (key, low) = Self::split(value);
+        match self.find_chunk(key) {
+            Ok(index) => {
+                let was_present = self.chunks[index].1.remove(low);
+                if self.chunks[index].1.len() == 0 {
+                    self.chunks.remove(index);
+                }
+                was_present
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Gets the number of values in this set
+    pub fn len(&self) -> u64 {
+        self.chunks
+            .iter()
+            .map(|(_, container)| container.len() as u64)
+            .sum()
+    }
+
+    /// Checks whether this set is empty
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Gets an iterator over all values of this set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.chunks.iter().flat_map(|(key, container)| {
+            container
+                .iter()
+                .map(move |low| (*key as u32) << 16 | low as u32)
+        })
+    }
+
+    /// Converts every container to the container type that needs the least
+    /// memory, which will be a run container for chunks that consist of only a
+    /// few runs of consecutive values. Call this after adding many values, and
+    /// before encoding this set.
+    pub fn optimize(&mut self) {
+        for (_, container) in &mut self.chunks {
+            let run_size = 32 * container.num_runs();
+            let array_size = 16 * container.len() as usize;
+            if run_size < usize::min(array_size, 65536) {
+                *container = container.to_runs();
+            } else {
+                *container = container.to_array_or_bitmap();
+            }
+        }
+    }
+
+    /// Encodes this set with *protocol*, and writes it to *sink*
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError> {
+        protocol.write_u32(sink, self.chunks.len() as u32)?;
+        let mut next_key = 0;
+        for (key, container) in &self.chunks {
+            protocol.write_u16(sink, key - next_key)?;
+            next_key = key.wrapping_add(1);
+            container.write(protocol, sink)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a set that was written by *write*. Returns a
+    /// *DecodeError::Other* if the encoded set is invalid.
+    pub fn read(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError> {
+        let num_chunks = protocol.read_u32(source)?;
+        if num_chunks > 65536 {
+            return Err(DecodeError::Other(
+                format!("{} chunks is too many", num_chunks).into(),
+            ));
+        }
+        let num_chunks = context.check_vec_len::<(u16, Container)>(num_chunks as u64)?;
+        let mut chunks = Vec::with_capacity(num_chunks);
+        let mut next_key = 0u32;
+        for _ in 0..num_chunks {
+            let key = next_key + protocol.read_u16(source)? as u32;
+            if key > u16::MAX as u32 {
+                return Err(DecodeError::Other("Chunk key is too large".into()));
+            }
+            chunks.push((key as u16, Container::read(protocol, source, context)?));
+            next_key = key + 1;
+        }
+        Ok(Self { chunks })
+    }
+}
+
+impl FromIterator<u32> for RoaringSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Container;
+    use crate::*;
+
+    use std::collections::BTreeSet;
+
+    fn round_trip(set: &RoaringSet) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        set.write(&DigitIntEncodingProtocol::v1(), &mut sink)
+            .unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded =
+            RoaringSet::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context).unwrap();
+        assert!(decoded.iter().eq(set.iter()));
+        assert_eq!(set.len(), decoded.len());
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_against_btree_set() {
+        let mut set = RoaringSet::new();
+        let mut expected = BTreeSet::new();
+        assert!(set.is_empty());
+        for index in 0..20_000u32 {
+            // Dense values in chunk 0, sparse values in the other chunks
+            let value = match index % 3 {
+                0 => index.wrapping_mul(2_654_435_761),
+                _ => index * 3 % 60_000,
+            };
+            assert_eq!(expected.insert(value), set.insert(value));
+            if index % 5 == 0 {
+                let removed = index * 7 % 60_000;
+                assert_eq!(expected.remove(&removed), set.remove(removed));
+            }
+        }
+        assert_eq!(expected.len() as u64, set.len());
+        assert!(set.iter().eq(expected.iter().copied()));
+        for value in 0..70_000 {
+            assert_eq!(expected.contains(&value), set.contains(value));
+        }
+        round_trip(&set);
+
+        set.optimize();
+        assert!(set.iter().eq(expected.iter().copied()));
+        round_trip(&set);
+
+        for value in expected.iter() {
+            assert!(set.remove(*value));
+        }
+        assert!(set.is_empty());
+        assert_eq!(
+            DigitIntEncodingProtocol::v1().encoded_size_u32(0) as u64,
+            round_trip(&set)
+        );
+    }
+
+    #[test]
+    fn test_containers() {
+        // A bitmap container that turns back into an array container
+        let mut set: RoaringSet = (0..5000).map(|value| value * 2).collect();
+        assert!(matches!(set.chunks[0].1, Container::Bitmap(_, 5000)));
+        for value in 0..1000 {
+            set.remove(value * 2);
+        }
+        assert!(matches!(&set.chunks[0].1, Container::Array(values) if values.len() == 4000));
+
+        // Run containers
+        let mut set: RoaringSet = (100..60_000).chain(u32::MAX - 10..=u32::MAX).collect();
+        let size_before = round_trip(&set);
+        set.optimize();
+        assert!(matches!(&set.chunks[0].1, Container::Runs(runs) if runs.len() == 1));
+        assert!(matches!(&set.chunks[1].1, Container::Runs(runs) if runs == &[(65525, 10)]));
+        assert!(round_trip(&set) * 100 < size_before);
+        assert!(set.contains(100));
+        assert!(set.contains(59_999));
+        assert!(!set.contains(60_000));
+        assert!(set.contains(u32::MAX));
+
+        // Modifying a run container converts it back
+        assert!(set.remove(500));
+        assert!(!set.remove(500));
+        assert!(set.insert(70));
+        assert!(!set.insert(70));
+        assert_eq!(59_900 + 11, set.len());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, 1).unwrap();
+        encoder.write_u16(&mut sink, 0).unwrap();
+        // Unknown container type
+        sink.write(&[true, true]).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let result = RoaringSet::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+        assert!(matches!(result, Err(DecodeError::Other(_))));
+
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, 1).unwrap();
+        encoder.write_u16(&mut sink, 0).unwrap();
+        // An array container with values 65535 and 65536
+        sink.write(&[false, false]).unwrap();
+        encoder.write_u16(&mut sink, 1).unwrap();
+        encoder.write_u16(&mut sink, 65535).unwrap();
+        encoder.write_u16(&mut sink, 0).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let result = RoaringSet::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+        assert!(matches!(result, Err(DecodeError::Other(_))));
+    }
+}
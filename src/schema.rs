@@ -0,0 +1,780 @@
+use crate::*;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Write;
+
+/// The version of the schema format that *Message::to_schema_json* produces
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The value of the *format* property of every schema
+const SCHEMA_FORMAT: &str = "bit-encoding-message";
+
+/// The maximum number of nested JSON objects and arrays. Valid schemas are
+/// only nested 4 levels deep, so this just prevents malicious schemas from
+/// overflowing the stack of the recursive parser.
+const MAX_JSON_DEPTH: usize = 16;
+
+fn write_json_string(dest: &mut String, value: &str) {
+    dest.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => dest.push_str("\\\""),
+            '\\' => dest.push_str("\\\\"),
+            '\n' => dest.push_str("\\n"),
+            '\r' => dest.push_str("\\r"),
+            '\t' => dest.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                write!(dest, "\\u{:04x}", character as u32).unwrap();
+            }
+            character => dest.push(character),
+        }
+    }
+    dest.push('"');
+}
+
+fn write_json_encoding(dest: &mut String, encoding: FieldEncoding) {
+    match encoding {
+        FieldEncoding::Simple => dest.push_str("{\"protocol\": \"simple\"}"),
+        FieldEncoding::Digit(digit_size) => write!(
+            dest,
+            "{{\"protocol\": \"digit\", \"digit_size\": {}}}",
+            digit_size
+        )
+        .unwrap(),
+        FieldEncoding::Vlq => dest.push_str("{\"protocol\": \"vlq\"}"),
+        FieldEncoding::Zeta(k) => write!(dest, "{{\"protocol\": \"zeta\", \"k\": {}}}", k).unwrap(),
+    }
+}
+
+fn write_json_value(dest: &mut String, value: &Value) {
+    match value {
+        Value::Bool(value) => write!(dest, "{}", value).unwrap(),
+        // Integers are written as strings because JSON parsers in other
+        // languages often can't represent large 64-bit or 128-bit integers
+        Value::Unsigned(value) => write!(dest, "\"{}\"", value).unwrap(),
+        Value::Signed(value) => write!(dest, "\"{}\"", value).unwrap(),
+        Value::String(value) => write_json_string(dest, value),
+        Value::Bytes(value) => {
+            dest.push('[');
+            for (index, byte) in value.iter().enumerate() {
+                if index > 0 {
+                    dest.push_str(", ");
+                }
+                write!(dest, "{}", byte).unwrap();
+            }
+            dest.push(']');
+        }
+    }
+}
+
+fn get_type_name(field_type: FieldType) -> String {
+    match field_type {
+        FieldType::Bool => "bool".to_string(),
+        FieldType::Int(int_type, _) => format!("{:?}", int_type).to_lowercase(),
+        FieldType::String(_) => "string".to_string(),
+        FieldType::Bytes(_) => "bytes".to_string(),
+    }
+}
+
+impl Message {
+    /// Exports the layout of this message to a machine-readable JSON schema,
+    /// so that decoders for the same format can be generated in other
+    /// languages (or loaded at runtime with *Message::from_schema_json*).
+    ///
+    /// The schema is an object with the following properties:
+    /// - *format*: always *"bit-encoding-message"*
+    /// - *version*: the version of the schema format (*SCHEMA_VERSION*)
+    /// - *bit_order*: always *"lsb-first"*, which means that the bits are
+    ///   packed into bytes starting at the least significant bit (like
+    ///   *U8VecBitSink*)
+    /// - *fields*: an array with an object for every field, in the order in
+    ///   which they are encoded. Every field has a *name* and a *type*, which
+    ///   is *"bool"*, *"string"*, *"bytes"*, or an integer type like *"u32"* or
+    ///   *"i64"*. All fields except bools also have an *encoding*: an object
+    ///   with a *protocol* (*"simple"*, *"digit"*, *"vlq"* or *"zeta"*) and
+    ///   the parameter of that protocol (*digit_size* or *k*), if any. Fields
+    ///   that were marked with *since* also have a *since* (the version) and a
+    ///   *default*, which is a string for integers (like *"-12"*) and an array
+    ///   of numbers for bytes.
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let message = Message::new()
+    ///     .int("id", IntType::U32, FieldEncoding::Digit(3))
+    ///     .bool("online");
+    /// assert_eq!(
+    ///     r#"{
+    ///   "format": "bit-encoding-message",
+    ///   "version": 1,
+    ///   "bit_order": "lsb-first",
+    ///   "fields": [
+    ///     {"name": "id", "type": "u32", "encoding": {"protocol": "digit", "digit_size": 3}},
+    ///     {"name": "online", "type": "bool"}
+    ///   ]
+    /// }
+    /// "#,
+    ///     message.to_schema_json()
+    /// );
+    /// ```
+    pub fn to_schema_json(&self) -> String {
+        let mut json = String::new();
+        json.push_str("{\n");
+        writeln!(json, "  \"format\": \"{}\",", SCHEMA_FORMAT).unwrap();
+        writeln!(json, "  \"version\": {},", SCHEMA_VERSION).unwrap();
+        json.push_str("  \"bit_order\": \"lsb-first\",\n");
+        json.push_str("  \"fields\": [");
+        for (index, field) in self.get_fields().iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str("\n    {\"name\": ");
+            write_json_string(&mut json, field.get_name());
+            write!(json, ", \"type\": \"{}\"", get_type_name(field.get_type())).unwrap();
+            if let Some(encoding) = field.get_type().get_encoding() {
+                json.push_str(", \"encoding\": ");
+                write_json_encoding(&mut json, encoding);
+            }
+            if let Some(default) = field.get_default() {
+                write!(json, ", \"since\": {}, \"default\": ", field.get_since()).unwrap();
+                write_json_value(&mut json, default);
+            }
+            json.push('}');
+        }
+        if !self.get_fields().is_empty() {
+            json.push_str("\n  ");
+        }
+        json.push_str("]\n}\n");
+        json
+    }
+}
+
+/// The error that *Message::from_schema_json* returns when it can't load a
+/// schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The schema is not valid JSON. The *position* is the byte index in the
+    /// schema where the problem was found.
+    Syntax { position: usize, message: String },
+
+    /// The schema has a *version* that is not supported by this crate
+    UnsupportedVersion(u64),
+
+    /// The schema is valid JSON, but it doesn't describe a valid message
+    Invalid(String),
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            SchemaError::Syntax { position, message } => {
+                write!(f, "Invalid JSON at byte {}: {}", position, message)
+            }
+            SchemaError::UnsupportedVersion(version) => write!(
+                f,
+                "Schema version {} is not supported (only version {} is)",
+                version, SCHEMA_VERSION
+            ),
+            SchemaError::Invalid(message) => write!(f, "Invalid schema: {}", message),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+/// The subset of JSON values that schemas can contain. Numbers are only
+/// supported when they are non-negative integers.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(HashMap<String, Json>),
+}
+
+struct JsonParser<'a> {
+    json: &'a str,
+    position: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, SchemaError> {
+        Err(SchemaError::Syntax {
+            position: self.position,
+            message: message.to_string(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.json[self.position..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let next = self.peek()?;
+        self.position += next.len_utf8();
+        Some(next)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SchemaError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return self.error(&format!("Expected '{}'", expected));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, SchemaError> {
+        if !self.json[self.position..].starts_with(keyword) {
+            return self.error("Unexpected character");
+        }
+        self.position += keyword.len();
+        Ok(value)
+    }
+
+    fn parse_hex_digits(&mut self) -> Result<u32, SchemaError> {
+        let digits = self.json.get(self.position..self.position + 4);
+        match digits.and_then(|digits| u32::from_str_radix(digits, 16).ok()) {
+            Some(code) => {
+                self.position += 4;
+                Ok(code)
+            }
+            None => self.error("Expected 4 hexadecimal digits"),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, SchemaError> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.next_char() {
+                None => return self.error("Unterminated string"),
+                Some('"') => return Ok(result),
+                Some('\\') => {
+                    let escaped = match self.next_char() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.parse_hex_digits()?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // A surrogate pair
+                                if !self.json[self.position..].starts_with("\\u") {
+                                    return self.error("Expected a low surrogate");
+                                }
+                                self.position += 2;
+                                let low = self.parse_hex_digits()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return self.error("Invalid low surrogate");
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            match char::from_u32(code) {
+                                Some(character) => character,
+                                None => return self.error("Invalid unicode escape"),
+                            }
+                        }
+                        _ => return self.error("Invalid escape sequence"),
+                    };
+                    result.push(escaped);
+                }
+                Some(character) if (character as u32) < 0x20 => {
+                    return self.error("Control character in string")
+                }
+                Some(character) => result.push(character),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, SchemaError> {
+        let start = self.position;
+        while let Some('0'..='9') = self.peek() {
+            self.position += 1;
+        }
+        if let Some('.') | Some('e') | Some('E') = self.peek() {
+            return self.error("Only integers are supported");
+        }
+        match self.json[start..self.position].parse() {
+            Ok(number) => Ok(Json::Number(number)),
+            Err(_) => self.error("Invalid or too large integer"),
+        }
+    }
+
+    /// Parses the next value, which is nested in *depth* objects and arrays
+    fn parse_value(&mut self, depth: usize) -> Result<Json, SchemaError> {
+        self.skip_whitespace();
+        if let Some('{') | Some('[') = self.peek() {
+            if depth >= MAX_JSON_DEPTH {
+                return self.error("Too deeply nested");
+            }
+        }
+        match self.peek() {
+            Some('{') => {
+                self.position += 1;
+                let mut object = HashMap::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.position += 1;
+                    return Ok(Json::Object(object));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key_position = self.position;
+                    let key = self.parse_string()?;
+                    self.expect(':')?;
+                    let value = self.parse_value(depth + 1)?;
+                    if object.insert(key, value).is_some() {
+                        self.position = key_position;
+                        return self.error("Duplicate key");
+                    }
+                    self.skip_whitespace();
+                    match self.next_char() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Json::Object(object)),
+                        _ => return self.error("Expected ',' or '}'"),
+                    }
+                }
+            }
+            Some('[') => {
+                self.position += 1;
+                let mut array = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.position += 1;
+                    return Ok(Json::Array(array));
+                }
+                loop {
+                    array.push(self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.next_char() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Json::Array(array)),
+                        _ => return self.error("Expected ',' or ']'"),
+                    }
+                }
+            }
+            Some('"') => Ok(Json::String(self.parse_string()?)),
+            Some('0'..='9') => self.parse_number(),
+            Some('-') => self.error("Only non-negative integers are supported"),
+            Some('t') => self.parse_keyword("true", Json::Bool(true)),
+            Some('f') => self.parse_keyword("false", Json::Bool(false)),
+            Some('n') => self.parse_keyword("null", Json::Null),
+            Some(_) => self.error("Unexpected character"),
+            None => self.error("Unexpected end of JSON"),
+        }
+    }
+
+    fn parse(json: &'a str) -> Result<Json, SchemaError> {
+        let mut parser = Self { json, position: 0 };
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.position != json.len() {
+            return parser.error("Unexpected data after the JSON value");
+        }
+        Ok(value)
+    }
+}
+
+fn invalid<T>(message: String) -> Result<T, SchemaError> {
+    Err(SchemaError::Invalid(message))
+}
+
+fn get_property<'a>(
+    object: &'a HashMap<String, Json>,
+    key: &str,
+    context: &str,
+) -> Result<&'a Json, SchemaError> {
+    match object.get(key) {
+        Some(value) => Ok(value),
+        None => invalid(format!("{} doesn't have a '{}'", context, key)),
+    }
+}
+
+fn get_string<'a>(
+    object: &'a HashMap<String, Json>,
+    key: &str,
+    context: &str,
+) -> Result<&'a str, SchemaError> {
+    match get_property(object, key, context)? {
+        Json::String(value) => Ok(value),
+        _ => invalid(format!("The '{}' of {} must be a string", key, context)),
+    }
+}
+
+fn get_u8(object: &HashMap<String, Json>, key: &str, context: &str) -> Result<u8, SchemaError> {
+    match get_property(object, key, context)? {
+        Json::Number(value) if *value <= u8::MAX as u64 => Ok(*value as u8),
+        _ => invalid(format!(
+            "The '{}' of {} must be an integer between 0 and 255",
+            key, context
+        )),
+    }
+}
+
+fn parse_encoding(
+    field: &HashMap<String, Json>,
+    context: &str,
+) -> Result<FieldEncoding, SchemaError> {
+    let encoding = match get_property(field, "encoding", context)? {
+        Json::Object(encoding) => encoding,
+        _ => return invalid(format!("The 'encoding' of {} must be an object", context)),
+    };
+    let context = format!("the encoding of {}", context);
+    let encoding = match get_string(encoding, "protocol", &context)? {
+        "simple" => FieldEncoding::Simple,
+        "digit" => FieldEncoding::Digit(get_u8(encoding, "digit_size", &context)?),
+        "vlq" => FieldEncoding::Vlq,
+        "zeta" => FieldEncoding::Zeta(get_u8(encoding, "k", &context)?),
+        protocol => return invalid(format!("Unknown protocol '{}' in {}", protocol, context)),
+    };
+    if let Err(problem) = encoding.validate() {
+        return invalid(format!("{} in {}", problem, context));
+    }
+    Ok(encoding)
+}
+
+fn parse_field_type(
+    field: &HashMap<String, Json>,
+    context: &str,
+) -> Result<FieldType, SchemaError> {
+    let type_name = get_string(field, "type", context)?;
+    if type_name == "bool" {
+        return Ok(FieldType::Bool);
+    }
+    if type_name == "string" {
+        return Ok(FieldType::String(parse_encoding(field, context)?));
+    }
+    if type_name == "bytes" {
+        return Ok(FieldType::Bytes(parse_encoding(field, context)?));
+    }
+    for int_type in &IntType::ALL {
+        if type_name == format!("{:?}", int_type).to_lowercase() {
+            return Ok(FieldType::Int(*int_type, parse_encoding(field, context)?));
+        }
+    }
+    invalid(format!("Unknown type '{}' of {}", type_name, context))
+}
+
+fn parse_default(
+    field: &HashMap<String, Json>,
+    field_type: FieldType,
+    context: &str,
+) -> Result<Value, SchemaError> {
+    let default = get_property(field, "default", context)?;
+    let value = match (field_type, default) {
+        (FieldType::Bool, Json::Bool(value)) => Some(Value::Bool(*value)),
+        (FieldType::Int(int_type, _), Json::String(value)) if int_type.is_signed() => {
+            value.parse().ok().map(Value::Signed)
+        }
+        (FieldType::Int(_, _), Json::String(value)) => value.parse().ok().map(Value::Unsigned),
+        (FieldType::String(_), Json::String(value)) => Some(Value::String(value.clone())),
+        (FieldType::Bytes(_), Json::Array(values)) => {
+            let mut bytes = Vec::with_capacity(values.len());
+            for value in values {
+                match value {
+                    Json::Number(byte) if *byte <= u8::MAX as u64 => bytes.push(*byte as u8),
+                    _ => return invalid(format!("Invalid byte in the default of {}", context)),
+                }
+            }
+            Some(Value::Bytes(bytes))
+        }
+        _ => None,
+    };
+    match value {
+        Some(value) => Ok(value),
+        None => invalid(format!("The default of {} doesn't match its type", context)),
+    }
+}
+
+impl Message {
+    /// Loads a message from a JSON schema that was created by *to_schema_json*
+    /// (or by other tools that follow the same format), so that messages can
+    /// be decoded (into *Value*s) by generic tools that don't know the layout
+    /// at compile time. Unknown properties are ignored.
+    ///
+    /// Returns *SchemaError::Syntax* if *json* is not valid JSON,
+    /// *SchemaError::UnsupportedVersion* if the version of the schema is not
+    /// supported, and *SchemaError::Invalid* if the schema doesn't describe a
+    /// valid message.
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let message = Message::from_schema_json(r#"{
+    ///     "format": "bit-encoding-message",
+    ///     "version": 1,
+    ///     "bit_order": "lsb-first",
+    ///     "fields": [
+    ///         {"name": "id", "type": "u32", "encoding": {"protocol": "vlq"}},
+    ///         {"name": "online", "type": "bool"}
+    ///     ]
+    /// }"#).unwrap();
+    ///
+    /// let mut sink = BoolVecBitSink::new();
+    /// VlqIntEncodingProtocol::new().write_u32(&mut sink, 1234).unwrap();
+    /// sink.write(&[true]).unwrap();
+    ///
+    /// let mut source = BoolSliceBitSource::new(sink.get_bits());
+    /// let mut context = DecodeContext::new(DecodeLimits::new());
+    /// assert_eq!(
+    ///     vec![Value::Unsigned(1234), Value::Bool(true)],
+    ///     message.read(&mut source, &mut context).unwrap()
+    /// );
+    /// ```
+    pub fn from_schema_json(json: &str) -> Result<Message, SchemaError> {
+        let schema = match JsonParser::parse(json)? {
+            Json::Object(schema) => schema,
+            _ => return invalid("The schema must be a JSON object".to_string()),
+        };
+        let context = "the schema";
+        let format = get_string(&schema, "format", context)?;
+        if format != SCHEMA_FORMAT {
+            return invalid(format!("Unknown format '{}'", format));
+        }
+        match get_property(&schema, "version", context)? {
+            Json::Number(version) if *version == SCHEMA_VERSION as u64 => {}
+            Json::Number(version) => return Err(SchemaError::UnsupportedVersion(*version)),
+            _ => return invalid("The version must be an integer".to_string()),
+        }
+        let bit_order = get_string(&schema, "bit_order", context)?;
+        if bit_order != "lsb-first" {
+            return invalid(format!("Unsupported bit order '{}'", bit_order));
+        }
+
+        let fields = match get_property(&schema, "fields", context)? {
+            Json::Array(fields) => fields,
+            _ => return invalid("The fields must be an array".to_string()),
+        };
+        let mut message = Message::new();
+        for (index, field) in fields.iter().enumerate() {
+            let context = format!("field {}", index);
+            let field = match field {
+                Json::Object(field) => field,
+                _ => return invalid(format!("{} must be an object", context)),
+            };
+            let name = get_string(field, "name", &context)?;
+            let context = format!("field {} ({})", index, name);
+            let field_type = parse_field_type(field, &context)?;
+            if message.get_index(name).is_some() {
+                return invalid(format!("Duplicate field '{}'", name));
+            }
+            message = message.field(name, field_type);
+
+            let since = match field.get("since") {
+                None => continue,
+                Some(Json::Number(since)) if *since > 0 && *since <= u32::MAX as u64 => {
+                    *since as u32
+                }
+                Some(_) => return invalid(format!("Invalid 'since' of {}", context)),
+            };
+            if since < message.get_version() {
+                return invalid(format!(
+                    "{} has version {}, but a field before it has version {}",
+                    context,
+                    since,
+                    message.get_version()
+                ));
+            }
+            let default = parse_default(field, field_type, &context)?;
+            let last_field = message.get_fields().last().unwrap();
+            if let Err(problem) = last_field.check_value(&default) {
+                return invalid(problem);
+            }
+            message = message.since(since, default);
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_all_field_types() {
+        let message = Message::new()
+            .bool("alive")
+            .int("x", IntType::I128, FieldEncoding::Zeta(2))
+            .int("id", IntType::U16, FieldEncoding::Simple)
+            .string("name", FieldEncoding::Vlq)
+            .bytes("data", FieldEncoding::Digit(8));
+        assert_eq!(
+            "{
+  \"format\": \"bit-encoding-message\",
+  \"version\": 1,
+  \"bit_order\": \"lsb-first\",
+  \"fields\": [
+    {\"name\": \"alive\", \"type\": \"bool\"},
+    {\"name\": \"x\", \"type\": \"i128\", \"encoding\": {\"protocol\": \"zeta\", \"k\": 2}},
+    {\"name\": \"id\", \"type\": \"u16\", \"encoding\": {\"protocol\": \"simple\"}},
+    {\"name\": \"name\", \"type\": \"string\", \"encoding\": {\"protocol\": \"vlq\"}},
+    {\"name\": \"data\", \"type\": \"bytes\", \"encoding\": {\"protocol\": \"digit\", \"digit_size\": 8}}
+  ]
+}
+",
+            message.to_schema_json()
+        );
+    }
+
+    #[test]
+    fn test_empty_and_escaped() {
+        assert!(Message::new()
+            .to_schema_json()
+            .ends_with("\"fields\": []\n}\n"));
+
+        let message = Message::new().bool("quote\" backslash\\ newline\n bell\u{7}");
+        assert!(message
+            .to_schema_json()
+            .contains(r#"{"name": "quote\" backslash\\ newline\n bell\u0007", "type": "bool"}"#));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let message = Message::new()
+            .bool("alive")
+            .int("x", IntType::I128, FieldEncoding::Zeta(2))
+            .int("id", IntType::U16, FieldEncoding::Simple)
+            .string("name \"quoted\" \u{1F600}", FieldEncoding::Vlq)
+            .bytes("data", FieldEncoding::Digit(8));
+        let json = message.to_schema_json();
+        assert_eq!(message, Message::from_schema_json(&json).unwrap());
+
+        let message = message
+            .bool("flag")
+            .since(1, Value::Bool(true))
+            .int("big", IntType::U128, FieldEncoding::Vlq)
+            .since(1, Value::Unsigned(u128::MAX))
+            .int("small", IntType::I64, FieldEncoding::Simple)
+            .since(3, Value::Signed(i64::MIN as i128))
+            .string("text", FieldEncoding::Vlq)
+            .since(3, Value::String("\"default\"".to_string()))
+            .bytes("more_data", FieldEncoding::Vlq)
+            .since(4, Value::Bytes(vec![0, 12, 255]));
+        let json = message.to_schema_json();
+        assert!(json.contains(
+            "{\"name\": \"small\", \"type\": \"i64\", \"encoding\": {\"protocol\": \"simple\"}, \
+             \"since\": 3, \"default\": \"-9223372036854775808\"}"
+        ));
+        assert!(json.contains("\"since\": 4, \"default\": [0, 12, 255]}"));
+        assert_eq!(message, Message::from_schema_json(&json).unwrap());
+        assert_eq!(
+            Message::new(),
+            Message::from_schema_json(&Message::new().to_schema_json()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_syntax() {
+        let message = Message::from_schema_json(
+            r#"{"fields":[{"type":"bool","name":"\u00e9\ud83d\ude00\n","extra":[null,true,{}]}],
+            "bit_order":"lsb-first","version":1,"format":"bit-encoding-message","comment":false}"#,
+        )
+        .unwrap();
+        assert_eq!("\u{e9}\u{1F600}\n", message.get_fields()[0].get_name());
+
+        let invalid_json = [
+            "",
+            "{",
+            "{\"a\": 1,}",
+            "[1 2]",
+            "{\"a\": 1.5}",
+            "{\"a\": -1}",
+            "{\"a\": 99999999999999999999}",
+            "{\"a\": \"\\x\"}",
+            "{\"a\": \"\\ud83d\"}",
+            "{\"a\": tru}",
+            "{\"a\": 1, \"a\": 2}",
+            "{} {}",
+        ];
+        for json in &invalid_json {
+            match Message::from_schema_json(json) {
+                Err(SchemaError::Syntax { .. }) => {}
+                other => panic!("Expected a syntax error for {}, but got {:?}", json, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // This would overflow the stack if the depth weren't limited
+        let json = "[".repeat(200_000);
+        match Message::from_schema_json(&json) {
+            Err(SchemaError::Syntax { position, .. }) => assert_eq!(16, position),
+            other => panic!("Expected a syntax error, but got {:?}", other),
+        }
+
+        // Unknown properties may be nested a bit, as long as it stays below the limit
+        let nested = format!("{}{}", "[".repeat(10), "]".repeat(10));
+        let valid = Message::new().bool("a").to_schema_json();
+        let json = valid.replacen('{', &format!("{{\"extra\": {}, ", nested), 1);
+        assert_eq!(
+            Message::new().bool("a"),
+            Message::from_schema_json(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        let valid = Message::new()
+            .int("a", IntType::U8, FieldEncoding::Digit(3))
+            .to_schema_json();
+        let replace = |from: &str, to: &str| {
+            assert!(valid.contains(from));
+            Message::from_schema_json(&valid.replace(from, to))
+        };
+
+        assert_eq!(
+            Err(SchemaError::UnsupportedVersion(2)),
+            replace("\"version\": 1", "\"version\": 2")
+        );
+        let invalid_schemas = [
+            replace("bit-encoding-message", "other"),
+            replace("lsb-first", "msb-first"),
+            replace("\"u8\"", "\"u7\""),
+            replace("\"digit\"", "\"unknown\""),
+            replace("\"digit_size\": 3", "\"digit_size\": 1"),
+            replace("\"digit_size\": 3", "\"digit_size\": 300"),
+            replace("\"digit_size\": 3", "\"size\": 3"),
+            replace(
+                ", \"encoding\": {\"protocol\": \"digit\", \"digit_size\": 3}",
+                "",
+            ),
+            replace("\"name\": \"a\"", "\"name\": 5"),
+            replace("}\n  ]", "}, {\"name\": \"a\", \"type\": \"bool\"}\n  ]"),
+            Message::from_schema_json("[]"),
+            replace("3}}", "3}, \"since\": 1}"),
+            replace("3}}", "3}, \"since\": 0, \"default\": \"1\"}"),
+            replace("3}}", "3}, \"since\": 1, \"default\": 1}"),
+            replace("3}}", "3}, \"since\": 1, \"default\": \"256\"}"),
+            replace(
+                "3}}",
+                "3}, \"since\": 2, \"default\": \"1\"}, \
+                 {\"name\": \"b\", \"type\": \"bool\", \"since\": 1, \"default\": true}",
+            ),
+        ];
+        for result in &invalid_schemas {
+            match result {
+                Err(SchemaError::Invalid(_)) => {}
+                other => panic!("Expected an invalid schema, but got {:?}", other),
+            }
+        }
+    }
+}
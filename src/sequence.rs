@@ -0,0 +1,391 @@
+use crate::*;
+
+/// Encodes a set of *u64*s, given as strictly increasing slice, and writes it to
+/// *sink*. Rather than encoding the values themselves, it encodes the number of
+/// values, the first value, and then the gap between every value and the
+/// previous value (minus 1, since the gaps are at least 1). All these integers
+/// are encoded with *protocol*.
+///
+/// When the values are close to each other, the gaps are much smaller than the
+/// values, so this works very well with protocols that encode small integers
+/// with few bits (like *DigitIntEncodingProtocol*).
+///
+/// Returns an error if *values* is not strictly increasing. The set can be
+/// decoded with *read_sorted_set*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let ids = [1000, 1001, 1005, 1010];
+/// let mut sink = BoolVecBitSink::new();
+/// write_sorted_set(&DigitIntEncodingProtocol::v1(), &mut sink, &ids).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = read_sorted_set(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(ids.to_vec(), decoded.unwrap());
+/// ```
+pub fn write_sorted_set(
+    protocol: &impl IntEncodingProtocol,
+    sink: &mut impl BitSink,
+    values: &[u64],
+) -> Result<(), WriteError> {
+    for (index, pair) in values.windows(2).enumerate() {
+        if pair[0] >= pair[1] {
+            return Err(format!(
+                "The values must be strictly increasing, but value {} is {} and value {} is {}",
+                index,
+                pair[0],
+                index + 1,
+                pair[1]
+            )
+            .into());
+        }
+    }
+
+    protocol.write_u64(sink, values.len() as u64)?;
+    if let Some(first) = values.first() {
+        protocol.write_u64(sink, *first)?;
+    }
+    for pair in values.windows(2) {
+        protocol.write_u64(sink, pair[1] - pair[0] - 1)?;
+    }
+    Ok(())
+}
+
+/// Decodes a set of *u64*s that was encoded by *write_sorted_set*, and returns
+/// its values in increasing order. The number of values is checked against the
+/// limits of *context* before the *Vec* is allocated.
+///
+/// Returns *DecodeError::IntOverflow* if a gap would make a value larger than
+/// *u64::MAX*, which can only happen when the input is corrupted.
+pub fn read_sorted_set(
+    protocol: &impl IntDecodingProtocol,
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+) -> Result<Vec<u64>, DecodeError> {
+    let length = context.check_vec_len::<u64>(protocol.read_u64(source)?)?;
+    let mut values = Vec::with_capacity(length);
+    if length > 0 {
+        let mut value = protocol.read_u64(source)?;
+        values.push(value);
+        for _ in 1..length {
+            let gap = protocol.read_u64(source)?;
+            value = value
+                .checked_add(gap)
+                .and_then(|value| value.checked_add(1))
+                .ok_or(DecodeError::IntOverflow)?;
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+/// The kind of monotonicity that a *MonotonicSequenceCodec* requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotonicity {
+    /// Every value must be greater than or equal to the previous value
+    NonDecreasing,
+    /// Every value must be greater than the previous value
+    StrictlyIncreasing,
+}
+
+impl Monotonicity {
+    fn allows(&self, previous: i64, value: i64) -> bool {
+        match self {
+            Monotonicity::NonDecreasing => value >= previous,
+            Monotonicity::StrictlyIncreasing => value > previous,
+        }
+    }
+}
+
+/// The error that *MonotonicSequenceCodec* returns when a sequence is not
+/// monotonic. When decoding, it will be wrapped in a *DecodeError::Other*.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonotonicityViolation {
+    /// The index of the first value that is not monotonic
+    pub index: usize,
+    /// The value before that value
+    pub previous: i64,
+    /// The value that is not monotonic
+    pub value: i64,
+}
+
+impl std::fmt::Display for MonotonicityViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Value {} is {}, which violates the monotonicity because the previous value is {}",
+            self.index, self.value, self.previous
+        )
+    }
+}
+
+impl std::error::Error for MonotonicityViolation {}
+
+/// Encodes and decodes sequences of values that should be monotonic, like
+/// timestamps or file offsets. It encodes the number of values, the first value,
+/// and then the difference between every value and the previous value. All these
+/// integers are encoded with the given protocol.
+///
+/// Unlike *write_sorted_set*, the differences are encoded as *signed* integers,
+/// which costs (at most) 1 extra bit per value. This allows *read* to detect
+/// differences that violate the monotonicity, which normally means that the
+/// input is corrupted. Such violations are reported as soon as they are read,
+/// rather than propagating nonsense values to the rest of the application.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let codec = MonotonicSequenceCodec::new(Monotonicity::NonDecreasing);
+/// let timestamps = [1_600_000_000, 1_600_000_000, 1_600_000_015];
+///
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&DigitIntEncodingProtocol::v1(), &mut sink, &timestamps).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = codec.read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(timestamps.to_vec(), decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonotonicSequenceCodec {
+    monotonicity: Monotonicity,
+}
+
+impl MonotonicSequenceCodec {
+    /// Constructs a new *MonotonicSequenceCodec* that requires *monotonicity*
+    pub const fn new(monotonicity: Monotonicity) -> Self {
+        Self { monotonicity }
+    }
+
+    /// Gets the monotonicity that this codec requires
+    pub fn get_monotonicity(&self) -> Monotonicity {
+        self.monotonicity
+    }
+
+    /// Encodes *values* and writes them to *sink*. Returns a (boxed)
+    /// *MonotonicityViolation* if *values* is not monotonic, or an error if the
+    /// difference between 2 consecutive values doesn't fit in an *i64*.
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[i64],
+    ) -> Result<(), WriteError> {
+        for (index, pair) in values.windows(2).enumerate() {
+            if !self.monotonicity.allows(pair[0], pair[1]) {
+                return Err(Box::new(MonotonicityViolation {
+                    index: index + 1,
+                    previous: pair[0],
+                    value: pair[1],
+                }));
+            }
+            if pair[1].checked_sub(pair[0]).is_none() {
+                return Err(format!(
+                    "The difference between {} and {} is too large",
+                    pair[0], pair[1]
+                )
+                .into());
+            }
+        }
+
+        protocol.write_u64(sink, values.len() as u64)?;
+        if let Some(first) = values.first() {
+            protocol.write_i64(sink, *first)?;
+        }
+        for pair in values.windows(2) {
+            protocol.write_i64(sink, pair[1] - pair[0])?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a sequence that was encoded by *write*. Returns a
+    /// *DecodeError::Other* with a *MonotonicityViolation* as soon as it reads a
+    /// value that violates the monotonicity, or *DecodeError::IntOverflow* if a
+    /// value would be out of the range of *i64*.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<i64>, DecodeError> {
+        let length = context.check_vec_len::<i64>(protocol.read_u64(source)?)?;
+        let mut values = Vec::with_capacity(length);
+        if length > 0 {
+            let mut previous = protocol.read_i64(source)?;
+            values.push(previous);
+            for index in 1..length {
+                let difference = protocol.read_i64(source)?;
+                let value = previous
+                    .checked_add(difference)
+                    .ok_or(DecodeError::IntOverflow)?;
+                if !self.monotonicity.allows(previous, value) {
+                    return Err(DecodeError::Other(Box::new(MonotonicityViolation {
+                        index,
+                        previous,
+                        value,
+                    })));
+                }
+                values.push(value);
+                previous = value;
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(values: &[u64]) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        write_sorted_set(&DigitIntEncodingProtocol::v1(), &mut sink, values).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded =
+            read_sorted_set(&DigitIntDecodingProtocol::v1(), &mut source, &mut context).unwrap();
+        assert_eq!(values, &decoded[..]);
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&[]);
+        round_trip(&[0]);
+        round_trip(&[u64::MAX]);
+        round_trip(&[0, 1, 2, 3]);
+        round_trip(&[0, u64::MAX]);
+        round_trip(&[5, 100, 1_000_000, u64::MAX - 1, u64::MAX]);
+    }
+
+    #[test]
+    fn test_gaps_are_smaller() {
+        let values: Vec<u64> = (0..100).map(|index| 1_000_000 + index * 3).collect();
+        let gap_size = round_trip(&values);
+
+        let mut sink = BoolVecBitSink::new();
+        DigitIntEncodingProtocol::v1()
+            .write_u64_slice(&mut sink, &values)
+            .unwrap();
+        assert!(gap_size * 2 < sink.get_num_bools());
+    }
+
+    #[test]
+    fn test_not_increasing() {
+        let protocol = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        assert!(write_sorted_set(&protocol, &mut sink, &[1, 2, 2]).is_err());
+        assert!(write_sorted_set(&protocol, &mut sink, &[3, 2]).is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u64(&mut sink, 2).unwrap();
+        encoder.write_u64(&mut sink, u64::MAX).unwrap();
+        encoder.write_u64(&mut sink, 0).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        match read_sorted_set(&DigitIntDecodingProtocol::v1(), &mut source, &mut context) {
+            Err(DecodeError::IntOverflow) => {}
+            other => panic!("Expected IntOverflow, but got {:?}", other),
+        }
+    }
+
+    fn round_trip_sequence(monotonicity: Monotonicity, values: &[i64]) {
+        let codec = MonotonicSequenceCodec::new(monotonicity);
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, values)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = codec
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .unwrap();
+        assert_eq!(values, &decoded[..]);
+    }
+
+    #[test]
+    fn test_monotonic_round_trip() {
+        round_trip_sequence(Monotonicity::NonDecreasing, &[]);
+        round_trip_sequence(Monotonicity::NonDecreasing, &[-5, -5, 0, 0, 100]);
+        round_trip_sequence(Monotonicity::StrictlyIncreasing, &[-5, -4, 0, 100]);
+        round_trip_sequence(Monotonicity::StrictlyIncreasing, &[i64::MIN, -1]);
+        round_trip_sequence(Monotonicity::NonDecreasing, &[0, i64::MAX]);
+    }
+
+    #[test]
+    fn test_monotonic_write_violations() {
+        let protocol = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+
+        let strict = MonotonicSequenceCodec::new(Monotonicity::StrictlyIncreasing);
+        let error = strict.write(&protocol, &mut sink, &[1, 2, 2]).unwrap_err();
+        assert_eq!(
+            &MonotonicityViolation {
+                index: 2,
+                previous: 2,
+                value: 2
+            },
+            error.downcast_ref::<MonotonicityViolation>().unwrap()
+        );
+
+        let non_decreasing = MonotonicSequenceCodec::new(Monotonicity::NonDecreasing);
+        assert!(non_decreasing.write(&protocol, &mut sink, &[3, 2]).is_err());
+        assert!(non_decreasing
+            .write(&protocol, &mut sink, &[i64::MIN, i64::MAX])
+            .is_err());
+    }
+
+    #[test]
+    fn test_monotonic_read_violation() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u64(&mut sink, 3).unwrap();
+        encoder.write_i64(&mut sink, 10).unwrap();
+        encoder.write_i64(&mut sink, 0).unwrap();
+        encoder.write_i64(&mut sink, -1).unwrap();
+
+        let decoder = DigitIntDecodingProtocol::v1();
+        let non_decreasing = MonotonicSequenceCodec::new(Monotonicity::NonDecreasing);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        match non_decreasing.read(&decoder, &mut source, &mut context) {
+            Err(DecodeError::Other(error)) => assert_eq!(
+                &MonotonicityViolation {
+                    index: 2,
+                    previous: 10,
+                    value: 9
+                },
+                error.downcast_ref::<MonotonicityViolation>().unwrap()
+            ),
+            other => panic!("Expected a MonotonicityViolation, but got {:?}", other),
+        }
+
+        let strict = MonotonicSequenceCodec::new(Monotonicity::StrictlyIncreasing);
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let error = strict
+            .read(&decoder, &mut source, &mut context)
+            .unwrap_err();
+        assert_eq!(
+            1,
+            match error {
+                DecodeError::Other(error) => {
+                    error.downcast_ref::<MonotonicityViolation>().unwrap().index
+                }
+                _ => panic!("Expected a MonotonicityViolation"),
+            }
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use crate::*;
+
+/// A codec whose encoding of a value depends on the values that were encoded
+/// before it (like *DeltaEncoding*, *XorEncoding* and *AdaptiveDigitEncoder*).
+/// Such codecs only work when the encoder and the decoder have the same state,
+/// so they need a way to get back in sync when that is no longer the case (for
+/// instance after a lost packet). This is usually used via *Session*.
+///
+/// The state of an encoder is written in the same format as the state of the
+/// corresponding decoder, so the encoder can write its state into the stream,
+/// and the decoder can read it to continue from the same point.
+pub trait SessionCodec {
+    /// Puts this codec back in its initial state (the state it had right after
+    /// it was constructed)
+    fn reset_state(&mut self);
+
+    /// Writes the current state of this codec to *sink*, such that
+    /// *read_state* of the corresponding encoder or decoder can restore it
+    fn write_state(&self, sink: &mut impl BitSink) -> Result<(), WriteError>;
+
+    /// Reads a state that was written by *write_state* of this codec or of the
+    /// corresponding encoder or decoder, and continues from that state. When
+    /// this returns an error, the state of this codec is unchanged.
+    fn read_state(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError>;
+}
+
+/// A snapshot of the state of the codec of a *Session*, which was created by
+/// *Session::checkpoint*
+#[derive(Debug, Clone)]
+pub struct SessionCheckpoint<C> {
+    codec: C,
+    num_syncs: u64,
+}
+
+impl<C> SessionCheckpoint<C> {
+    /// Gets the codec as it was when this checkpoint was created
+    pub fn get_codec(&self) -> &C {
+        &self.codec
+    }
+}
+
+/// Wraps a stateful codec (see *SessionCodec*) for a long-lived connection,
+/// and provides explicit points where both sides can get back in sync:
+/// - *reset* puts the codec back in its initial state. When both sides call it
+///   at the same point (for instance at the start of every packet), every
+///   packet can be decoded on its own.
+/// - *write_sync* writes the state of the encoder into the stream, and
+///   *read_sync* makes the decoder continue from that state. This allows the
+///   receiver to resynchronize after packet loss, without losing the benefits
+///   of the state.
+/// - *checkpoint* and *restore* allow either side to go back to an earlier
+///   state, for instance to retransmit values after a packet was lost.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut encoder = Session::new(DeltaEncoding::new(DigitIntEncodingProtocol::v1()));
+/// let mut decoder = Session::new(DeltaEncoding::new(DigitIntDecodingProtocol::v1()));
+///
+/// // The first packet gets lost
+/// let mut lost_packet = BoolVecBitSink::new();
+/// encoder.get_codec().write_u32(&mut lost_packet, 1000).unwrap();
+///
+/// // The second packet starts with a sync point
+/// let mut packet = BoolVecBitSink::new();
+/// encoder.write_sync(&mut packet).unwrap();
+/// encoder.get_codec().write_u32(&mut packet, 1005).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(packet.get_bits());
+/// decoder.read_sync(&mut source).unwrap();
+/// assert_eq!(1005, decoder.get_codec().read_u32(&mut source).unwrap());
+/// assert_eq!(1, decoder.get_num_syncs());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session<C> {
+    codec: C,
+    num_syncs: u64,
+}
+
+impl<C: SessionCodec> Session<C> {
+    /// Constructs a new *Session* that wraps *codec*
+    pub fn new(codec: C) -> Self {
+        Self {
+            codec,
+            num_syncs: 0,
+        }
+    }
+
+    /// Gets a reference to the codec, which should be used to encode or decode
+    /// the values
+    pub fn get_codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Gets a mutable reference to the codec, which is needed for codecs like
+    /// *AdaptiveDigitEncoder*
+    pub fn get_codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Consumes this session and returns its codec
+    pub fn into_codec(self) -> C {
+        self.codec
+    }
+
+    /// Gets the number of sync points that were written by *write_sync* or read
+    /// by *read_sync*
+    pub fn get_num_syncs(&self) -> u64 {
+        self.num_syncs
+    }
+
+    /// Puts the codec back in its initial state. The other side must reset at
+    /// the same point in the stream.
+    pub fn reset(&mut self) {
+        self.codec.reset_state();
+    }
+
+    /// Writes a sync point to *sink*: the current state of the codec. The other
+    /// side must call *read_sync* at the same point in the stream.
+    pub fn write_sync(&mut self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        self.codec.write_state(sink)?;
+        self.num_syncs += 1;
+        Ok(())
+    }
+
+    /// Reads a sync point that was written by *write_sync* of the other side,
+    /// and continues from the state of the other side. When this returns an
+    /// error, the state of the codec is unchanged.
+    pub fn read_sync(&mut self, source: &mut impl BitSource) -> Result<(), DecodeError> {
+        self.codec.read_state(source)?;
+        self.num_syncs += 1;
+        Ok(())
+    }
+}
+
+impl<C: SessionCodec + Clone> Session<C> {
+    /// Creates a snapshot of the current state, which can be restored later by
+    /// calling *restore*
+    pub fn checkpoint(&self) -> SessionCheckpoint<C> {
+        SessionCheckpoint {
+            codec: self.codec.clone(),
+            num_syncs: self.num_syncs,
+        }
+    }
+
+    /// Goes back to the state of *checkpoint*
+    pub fn restore(&mut self, checkpoint: &SessionCheckpoint<C>) {
+        self.codec = checkpoint.codec.clone();
+        self.num_syncs = checkpoint.num_syncs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::faults::*;
+    use crate::*;
+
+    #[test]
+    fn test_reset() {
+        let mut encoder = Session::new(XorEncoding::new(DigitIntEncodingProtocol::v1()));
+        let mut decoder = Session::new(XorEncoding::new(DigitIntDecodingProtocol::v1()));
+
+        let mut sink = BoolVecBitSink::new();
+        encoder.get_codec().write_u16(&mut sink, 1234).unwrap();
+        encoder.reset();
+        encoder.get_codec().write_u16(&mut sink, 1234).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(1234, decoder.get_codec().read_u16(&mut source).unwrap());
+        decoder.reset();
+        assert_eq!(1234, decoder.get_codec().read_u16(&mut source).unwrap());
+        assert_eq!(0, decoder.get_num_syncs());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut encoder = Session::new(DeltaEncoding::new(SimpleIntEncodingProtocol::new()));
+        encoder
+            .get_codec()
+            .write_i32(&mut VoidBitSink::new(), 50)
+            .unwrap();
+        encoder.write_sync(&mut VoidBitSink::new()).unwrap();
+        let checkpoint = encoder.checkpoint();
+
+        encoder
+            .get_codec()
+            .write_i32(&mut VoidBitSink::new(), 80)
+            .unwrap();
+        encoder.write_sync(&mut VoidBitSink::new()).unwrap();
+        assert_eq!(2, encoder.get_num_syncs());
+
+        encoder.restore(&checkpoint);
+        assert_eq!(1, encoder.get_num_syncs());
+        let mut sink = BoolVecBitSink::new();
+        encoder.get_codec().write_i32(&mut sink, 51).unwrap();
+
+        // The difference is 1, which is 2 after zigzag encoding
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(
+            2,
+            SimpleIntDecodingProtocol::new()
+                .read_u32(&mut source)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_failed_sync_keeps_state() {
+        let mut encoder = Session::new(DeltaEncoding::new(DigitIntEncodingProtocol::v1()));
+        let mut decoder = Session::new(DeltaEncoding::new(DigitIntDecodingProtocol::v1()));
+        let mut sink = BoolVecBitSink::new();
+        encoder.get_codec().write_u64(&mut sink, 12345).unwrap();
+        encoder.write_sync(&mut sink).unwrap();
+        encoder.get_codec().write_u64(&mut sink, 12350).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert_eq!(12345, decoder.get_codec().read_u64(&mut source).unwrap());
+        let position = source.position();
+
+        let mut truncated = BoolSliceBitSource::new(sink.get_bits());
+        truncated.seek_bits(position).unwrap();
+        let mut failing = FailAfterBitSource::new(truncated, 20);
+        assert!(decoder.read_sync(&mut failing).is_err());
+        assert_eq!(0, decoder.get_num_syncs());
+
+        source.seek_bits(position).unwrap();
+        decoder.read_sync(&mut source).unwrap();
+        assert_eq!(12350, decoder.get_codec().read_u64(&mut source).unwrap());
+    }
+
+    #[test]
+    fn test_adaptive_resync() {
+        let values: Vec<u64> = (0..100).map(|index| index * index * 1000).collect();
+        let mut encoder = Session::new(AdaptiveDigitEncoder::new(4, false));
+        let mut sink = BoolVecBitSink::new();
+        let mut sync_positions = Vec::new();
+        for (index, value) in values.iter().enumerate() {
+            if index % 7 == 0 {
+                sync_positions.push((index, sink.get_num_bools()));
+                encoder.write_sync(&mut sink).unwrap();
+            }
+            encoder
+                .get_codec_mut()
+                .write_u64(&mut sink, *value)
+                .unwrap();
+        }
+
+        // The decoder can join at every sync point
+        for (start_index, start_position) in sync_positions {
+            let mut decoder = Session::new(AdaptiveDigitDecoder::new(4, false));
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            source.seek_bits(start_position).unwrap();
+            for (index, value) in values.iter().enumerate().skip(start_index) {
+                if index % 7 == 0 {
+                    decoder.read_sync(&mut source).unwrap();
+                }
+                assert_eq!(
+                    *value,
+                    decoder.get_codec_mut().read_u64(&mut source).unwrap()
+                );
+            }
+        }
+    }
+}
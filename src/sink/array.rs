@@ -0,0 +1,253 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// A *BitSink* that stores its bits in a fixed-size array of *N* bytes, without
+/// allocating any memory. This is useful on embedded targets and in hot loops
+/// where the maximum size of the encoded data is known in advance.
+///
+/// The bits are packed in the same order as *U8VecBitSink* (starting at the
+/// least significant bit of the first byte). When a write would exceed the
+/// capacity of *N \* 8* bits, none of its bits are written and a *SinkFull*
+/// error is returned (which is the only case where this sink allocates, since
+/// *WriteError* is boxed). Note that encoders can call *write* multiple times
+/// for a single value, so the first part of that value may have been written.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = ArrayBitSink::<2>::new();
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// encoder.write_u8(&mut sink, 12).unwrap();
+/// assert_eq!(10, sink.get_num_bools());
+/// assert_eq!(2, sink.get_bytes().len());
+///
+/// // The array is too small for this value
+/// let error = encoder.write_u32(&mut sink, 1234567).unwrap_err();
+/// assert_eq!(16, error.downcast_ref::<SinkFull>().unwrap().capacity);
+///
+/// let mut source = U8SliceBitSource::new(sink.get_bytes(), 0);
+/// assert_eq!(12, DigitIntDecodingProtocol::v1().read_u8(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArrayBitSink<const N: usize> {
+    bytes: [u8; N],
+    num_bits: usize,
+}
+
+impl<const N: usize> ArrayBitSink<N> {
+    /// Constructs a new empty *ArrayBitSink*
+    pub fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            num_bits: 0,
+        }
+    }
+
+    /// Gets the maximum number of bits that this sink can hold, which is *N \* 8*
+    pub fn get_capacity(&self) -> u64 {
+        N as u64 * 8
+    }
+
+    /// Gets the bytes that contain the bits that have been written so far. The
+    /// unused bits of the last byte are 0.
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes[0..self.num_bits.div_ceil(8)]
+    }
+
+    /// Gets an iterator over the bits that have been written so far
+    pub fn iter_bits(&self) -> WordBitIter<u8, &[u8]> {
+        WordBitIter::new(&self.bytes, self.num_bits as u64)
+    }
+
+    /// Consumes this sink and returns its entire array, together with the
+    /// number of bits that were written into it
+    pub fn into_array(self) -> ([u8; N], usize) {
+        (self.bytes, self.num_bits)
+    }
+
+    /// Removes all bits from this sink, so that it can be reused
+    pub fn clear(&mut self) {
+        self.bytes = [0; N];
+        self.num_bits = 0;
+    }
+}
+
+impl<const N: usize> IntoIterator for ArrayBitSink<N> {
+    type Item = bool;
+    type IntoIter = WordBitIter<u8, [u8; N]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        WordBitIter::new(self.bytes, self.num_bits as u64)
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a ArrayBitSink<N> {
+    type Item = bool;
+    type IntoIter = WordBitIter<u8, &'a [u8]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_bits()
+    }
+}
+
+/// Borrows the bytes of the sink, with the right number of padding bits
+impl<'a, const N: usize> From<&'a ArrayBitSink<N>> for U8SliceBitSource<'a> {
+    fn from(sink: &'a ArrayBitSink<N>) -> Self {
+        let num_padding_bits = (8 - sink.num_bits % 8) % 8;
+        U8SliceBitSource::new(sink.get_bytes(), num_padding_bits as u8)
+    }
+}
+
+/// Copies the bits of the *U8VecBitSink* into an array. This fails with a
+/// *SinkFull* error when the array is too small.
+impl<const N: usize> TryFrom<&U8VecBitSink> for ArrayBitSink<N> {
+    type Error = SinkFull;
+
+    fn try_from(sink: &U8VecBitSink) -> Result<Self, SinkFull> {
+        let num_bits = sink.get_num_bools();
+        let capacity = N as u64 * 8;
+        if num_bits > capacity {
+            return Err(SinkFull {
+                capacity,
+                required: num_bits,
+            });
+        }
+        let mut result = Self::new();
+        result.bytes[..sink.get_bytes().len()].copy_from_slice(sink.get_bytes());
+        result.num_bits = num_bits as usize;
+        Ok(result)
+    }
+}
+
+impl<const N: usize> Default for ArrayBitSink<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> BitSink for ArrayBitSink<N> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let required = self.num_bits as u64 + bits.len() as u64;
+        if required > self.get_capacity() {
+            return Err(Box::new(SinkFull {
+                capacity: self.get_capacity(),
+                required,
+            }));
+        }
+        for bit in bits {
+            if *bit {
+                self.bytes[self.num_bits / 8] |= 1 << (self.num_bits % 8);
+            }
+            self.num_bits += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bits as u64
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.get_capacity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_same_as_u8_vec() {
+        let bits: Vec<bool> = (0..77)
+            .map(|index| index % 3 == 1 || index % 7 == 0)
+            .collect();
+        let mut array_sink = ArrayBitSink::<10>::new();
+        let mut vec_sink = U8VecBitSink::new();
+        for chunk in bits.chunks(6) {
+            array_sink.write(chunk).unwrap();
+            vec_sink.write(chunk).unwrap();
+        }
+        assert_eq!(77, array_sink.get_num_bools());
+        assert_eq!(&vec_sink.get_bytes()[..], array_sink.get_bytes());
+
+        let (array, num_bits) = array_sink.into_array();
+        assert_eq!(77, num_bits);
+        assert_eq!(&vec_sink.get_bytes()[..], &array[..]);
+    }
+
+    #[test]
+    fn test_iter_bits() {
+        let mut sink = ArrayBitSink::<2>::new();
+        sink.write(&[true, false, true]).unwrap();
+        sink.write(&[false; 7]).unwrap();
+        sink.write(&[true]).unwrap();
+        let bits = sink.iter_bits().collect::<Vec<_>>();
+        assert_eq!(11, bits.len());
+        assert_eq!(&bytes_to_bools(sink.get_bytes())[..11], &bits[..]);
+        assert_eq!(bits, sink.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let mut vec_sink = U8VecBitSink::new();
+        vec_sink
+            .write(&[true, true, false, true, false, true, true, true, true, true])
+            .unwrap();
+        let mut array_sink = ArrayBitSink::<2>::try_from(&vec_sink).unwrap();
+        assert_eq!(10, array_sink.get_num_bools());
+        assert_eq!(&vec_sink.get_bytes()[..], array_sink.get_bytes());
+        array_sink.write(&[true; 6]).unwrap();
+        assert!(array_sink.write(&[true]).is_err());
+
+        let error = ArrayBitSink::<1>::try_from(&vec_sink).unwrap_err();
+        assert_eq!(
+            SinkFull {
+                capacity: 8,
+                required: 10
+            },
+            error
+        );
+
+        let array_sink = ArrayBitSink::<2>::try_from(&vec_sink).unwrap();
+        let mut source = U8SliceBitSource::from(&array_sink);
+        let mut dest = [false; 10];
+        source.read(&mut dest).unwrap();
+        assert_eq!(&vec_sink.get_bools()[..], &dest);
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_full() {
+        let mut sink = ArrayBitSink::<1>::new();
+        sink.write(&[true; 5]).unwrap();
+        let error = sink.write(&[true; 4]).unwrap_err();
+        assert_eq!(
+            &SinkFull {
+                capacity: 8,
+                required: 9
+            },
+            error.downcast_ref::<SinkFull>().unwrap()
+        );
+        assert_eq!(5, sink.get_num_bools());
+        sink.write(&[false; 3]).unwrap();
+        assert!(sink.write(&[]).is_ok());
+        assert!(sink.write(&[false]).is_err());
+        assert_eq!(&[0b0001_1111], sink.get_bytes());
+
+        sink.clear();
+        assert_eq!(0, sink.get_num_bools());
+        assert!(sink.get_bytes().is_empty());
+
+        let mut empty = ArrayBitSink::<0>::default();
+        assert!(empty.write(&[]).is_ok());
+        assert!(empty.write(&[true]).is_err());
+    }
+}
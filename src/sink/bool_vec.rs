@@ -37,6 +37,12 @@ impl BoolVecBitSink {
         &self.vec
     }
 
+    /// Gets an iterator over the bools that were written to this
+    /// BoolVecBitSink (in the right order).
+    pub fn iter_bits(&self) -> std::iter::Copied<std::slice::Iter<'_, bool>> {
+        self.vec.iter().copied()
+    }
+
     /// Encodes the bools that were written to this BoolVecBitSink as bytes,
     /// using the bools_to_bytes function of this crate.
     ///
@@ -71,6 +77,28 @@ impl BoolVecBitSink {
         }
         result
     }
+
+    /// Removes all bools from this sink, but keeps the capacity of its *Vec*.
+    /// This allows a single sink to be reused for many messages without
+    /// allocating new memory each time.
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+
+    /// Removes all bools after the first *num_bits* bools from this sink, but
+    /// keeps the capacity of its *Vec*. This does nothing if this sink doesn't
+    /// contain more than *num_bits* bools.
+    pub fn truncate_bits(&mut self, num_bits: u64) {
+        if num_bits < self.vec.len() as u64 {
+            self.vec.truncate(num_bits as usize);
+        }
+    }
+
+    /// Reserves capacity for at least *num_bits* more bools, so that they can
+    /// be written into this sink without reallocating.
+    pub fn reserve_bits(&mut self, num_bits: u64) {
+        self.vec.reserve(num_bits as usize);
+    }
 }
 
 impl BitSink for BoolVecBitSink {
@@ -92,6 +120,99 @@ impl BitSink for BoolVecBitSink {
     fn get_num_bools(&self) -> u64 {
         self.vec.len() as u64
     }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.vec.capacity() as u64)
+    }
+}
+
+impl IntoIterator for BoolVecBitSink {
+    type Item = bool;
+    type IntoIter = std::vec::IntoIter<bool>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a BoolVecBitSink {
+    type Item = bool;
+    type IntoIter = std::iter::Copied<std::slice::Iter<'a, bool>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_bits()
+    }
+}
+
+/// Constructs a *BoolVecBitSink* that continues writing after *bits*
+impl From<Vec<bool>> for BoolVecBitSink {
+    fn from(bits: Vec<bool>) -> Self {
+        Self { vec: bits }
+    }
+}
+
+/// Takes the *Vec* of the sink, without copying it
+impl From<BoolVecBitSink> for Vec<bool> {
+    fn from(sink: BoolVecBitSink) -> Self {
+        sink.vec
+    }
+}
+
+/// Unpacks the bits of the bytes into bools. The resulting sink can be used to
+/// continue writing.
+impl From<U8VecBitSink> for BoolVecBitSink {
+    fn from(sink: U8VecBitSink) -> Self {
+        Self {
+            vec: sink.get_bools(),
+        }
+    }
+}
+
+/// Borrows the bools of the sink
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = BoolVecBitSink::new();
+/// DigitIntEncodingProtocol::v1().write_u32(&mut sink, 1234).unwrap();
+///
+/// let mut source = BoolSliceBitSource::from(&sink);
+/// assert_eq!(1234, DigitIntDecodingProtocol::v1().read_u32(&mut source).unwrap());
+/// ```
+impl<'a> From<&'a BoolVecBitSink> for BoolSliceBitSource<'a> {
+    fn from(sink: &'a BoolVecBitSink) -> Self {
+        BoolSliceBitSource::new(sink.get_bits())
+    }
+}
+
+/// Writes the bits to the end of the *Vec*, so that quick scripts can encode
+/// into a plain *Vec<bool>* without constructing a *BoolVecBitSink*:
+///
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut bits = Vec::new();
+/// DigitIntEncodingProtocol::v1().write_u32(&mut bits, 1234).unwrap();
+/// assert_eq!(bits.len() as u64, bits.get_num_bools());
+/// ```
+impl BitSink for Vec<bool> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        self.extend_from_slice(bits);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.capacity() as u64)
+    }
 }
 
 #[cfg(test)]
@@ -108,6 +229,21 @@ mod tests {
         assert_eq!(&[false, true, false, true, false, true], sink.get_bits());
     }
 
+    #[test]
+    fn test_bool_vec_bit_sink_reuse() {
+        let mut sink = BoolVecBitSink::new();
+        sink.reserve_bits(100);
+        let capacity = sink.vec.capacity();
+        sink.write(&[true, false, true, false]).unwrap();
+        sink.truncate_bits(10);
+        assert_eq!(4, sink.get_num_bools());
+        sink.truncate_bits(2);
+        assert_eq!(&[true, false], sink.get_bits());
+        sink.clear();
+        assert_eq!(0, sink.get_num_bools());
+        assert_eq!(capacity, sink.vec.capacity());
+    }
+
     #[test]
     fn test_bool_vec_bit_sink_low_capacity() {
         let mut sink = BoolVecBitSink::with_capacity(2);
@@ -140,4 +276,78 @@ mod tests {
             .unwrap();
         assert_eq!(&[204, 1], &sink.to_bytes()[..]);
     }
+
+    #[test]
+    fn test_vec_bit_sink() {
+        let mut vec = vec![true];
+        vec.write(&[false, true]).unwrap();
+        vec.finish().unwrap();
+        assert_eq!(vec![true, false, true], vec);
+        assert_eq!(3, vec.get_num_bools());
+    }
+
+    #[test]
+    fn test_iter_bits() {
+        let mut sink = BoolVecBitSink::new();
+        sink.write(&[true, false, false, true]).unwrap();
+        let mut copy = BoolVecBitSink::new();
+        for bit in &sink {
+            copy.write(&[bit]).unwrap();
+        }
+        assert_eq!(sink.get_bits(), copy.get_bits());
+        assert_eq!(2, sink.iter_bits().filter(|bit| *bit).count());
+        assert_eq!(
+            vec![true, false, false, true],
+            sink.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        let bits = vec![true, false, true, true, false, false, false, true, true];
+        let sink = BoolVecBitSink::from(bits.clone());
+        assert_eq!(&bits[..], sink.get_bits());
+        let mut source = BoolSliceBitSource::from(&sink);
+        let mut dest = [false; 9];
+        source.read(&mut dest).unwrap();
+        assert_eq!(&bits[..], &dest);
+
+        let mut bytes = U8VecBitSink::new();
+        bytes.write(&bits).unwrap();
+        let mut sink = BoolVecBitSink::from(bytes);
+        assert_eq!(&bits[..], sink.get_bits());
+        sink.write(&[true]).unwrap();
+        assert_eq!(10, Vec::from(sink).len());
+    }
+
+    fn check_accessors(sink: &mut impl BitSink) {
+        assert!(sink.is_empty());
+        assert_eq!(0, sink.len_bits());
+        sink.write(&[true, false, true]).unwrap();
+        assert!(!sink.is_empty());
+        assert_eq!(3, sink.len_bits());
+        assert!(sink.capacity_bits().unwrap() >= 3);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut sink = BoolVecBitSink::with_capacity(100);
+        assert_eq!(Some(100), sink.capacity_bits());
+        check_accessors(&mut sink);
+        check_accessors(&mut Vec::new());
+        check_accessors(&mut U8VecBitSink::new());
+        check_accessors(&mut U32VecBitSink::new());
+        check_accessors(&mut ArrayBitSink::<1>::new());
+        check_accessors(&mut ProgressBitSink::new(
+            BoolVecBitSink::new(),
+            10,
+            None,
+            |_| {},
+        ));
+
+        let mut counter = CountingBitSink::new();
+        assert_eq!(None, counter.capacity_bits());
+        counter.write(&[true]).unwrap();
+        assert!(!counter.is_empty());
+    }
 }
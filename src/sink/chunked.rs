@@ -0,0 +1,189 @@
+use crate::*;
+
+use std::path::Path;
+
+/// A *BitSink* that packs the bools written to it into bytes (in the same way as
+/// *U8VecBitSink*) and splits these bytes into chunks of a fixed size. Every time
+/// a chunk is full, it will be passed to a callback, and this sink will continue
+/// with the next chunk. This is useful when the output needs to be uploaded to
+/// a storage service that has a size limit per object.
+///
+/// The chunks are simply consecutive parts of the byte sequence: concatenating
+/// all chunks gives exactly the same bytes as a *U8VecBitSink* would have, so
+/// bits can be split over 2 chunks. All chunks will have exactly *chunk_size*
+/// bytes, except the last chunk, which is passed to the callback when *finish*
+/// is called.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut chunks = Vec::new();
+/// let mut sink = ChunkedBitSink::new(2, |chunk_index, chunk: Vec<u8>| {
+///     chunks.push((chunk_index, chunk));
+///     Ok(())
+/// });
+/// sink.write(&[true; 20]).unwrap();
+/// sink.finish().unwrap();
+/// drop(sink);
+///
+/// assert_eq!(vec![(0, vec![255, 255]), (1, vec![15])], chunks);
+/// ```
+pub struct ChunkedBitSink<F: FnMut(usize, Vec<u8>) -> Result<(), WriteError>> {
+    chunk_size: usize,
+    on_chunk: F,
+
+    current_chunk: Vec<u8>,
+    bit_index: u8,
+    num_chunks: usize,
+}
+
+impl<F: FnMut(usize, Vec<u8>) -> Result<(), WriteError>> ChunkedBitSink<F> {
+    /// Constructs a new *ChunkedBitSink* that calls *on_chunk* with the index
+    /// and the content of each chunk of *chunk_size* bytes.
+    ///
+    /// Panics if *chunk_size* is 0.
+    pub fn new(chunk_size: usize, on_chunk: F) -> Self {
+        assert!(chunk_size > 0, "The chunk size must be positive");
+        Self {
+            chunk_size,
+            on_chunk,
+            current_chunk: Vec::with_capacity(chunk_size),
+            bit_index: 0,
+            num_chunks: 0,
+        }
+    }
+
+    /// Gets the number of chunks that have been passed to the callback so far
+    pub fn get_num_chunks(&self) -> usize {
+        self.num_chunks
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), WriteError> {
+        let chunk = std::mem::replace(&mut self.current_chunk, Vec::with_capacity(self.chunk_size));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            chunk_index = self.num_chunks,
+            chunk_size = chunk.len(),
+            "Flushing chunk"
+        );
+        (self.on_chunk)(self.num_chunks, chunk)?;
+        self.num_chunks += 1;
+        Ok(())
+    }
+}
+
+impl ChunkedBitSink<fn(usize, Vec<u8>) -> Result<(), WriteError>> {
+    /// Constructs a *ChunkedBitSink* that writes every chunk to its own file in
+    /// *directory*. The files will be named *prefix* followed by the index of
+    /// the chunk (padded with zeros to 6 digits), for instance "data.000012".
+    pub fn to_files(
+        chunk_size: usize,
+        directory: impl AsRef<Path>,
+        prefix: &str,
+    ) -> ChunkedBitSink<impl FnMut(usize, Vec<u8>) -> Result<(), WriteError>> {
+        let directory = directory.as_ref().to_path_buf();
+        let prefix = prefix.to_string();
+        ChunkedBitSink::new(chunk_size, move |chunk_index, chunk| {
+            let path = directory.join(format!("{}{:06}", prefix, chunk_index));
+            std::fs::write(path, chunk)?;
+            Ok(())
+        })
+    }
+}
+
+impl<F: FnMut(usize, Vec<u8>) -> Result<(), WriteError>> BitSink for ChunkedBitSink<F> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        for bit in bits {
+            if self.bit_index == 0 {
+                if self.current_chunk.len() == self.chunk_size {
+                    self.flush_chunk()?;
+                }
+                self.current_chunk.push(0);
+            }
+            if *bit {
+                *self.current_chunk.last_mut().unwrap() |= 1 << self.bit_index;
+            }
+            self.bit_index = (self.bit_index + 1) % 8;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        if !self.current_chunk.is_empty() {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        let num_full_bytes = (self.num_chunks * self.chunk_size + self.current_chunk.len()) as u64;
+        if self.bit_index == 0 {
+            num_full_bytes * 8
+        } else {
+            (num_full_bytes - 1) * 8 + self.bit_index as u64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_same_as_u8_vec() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut bytes = Vec::new();
+        let mut chunk_sizes = Vec::new();
+        let mut chunked_sink = ChunkedBitSink::new(3, |chunk_index, chunk: Vec<u8>| {
+            assert_eq!(chunk_sizes.len(), chunk_index);
+            chunk_sizes.push(chunk.len());
+            bytes.extend_from_slice(&chunk);
+            Ok(())
+        });
+        let mut vec_sink = U8VecBitSink::new();
+
+        for value in 0..1000 {
+            encoder.write_u32(&mut chunked_sink, value).unwrap();
+            encoder.write_u32(&mut vec_sink, value).unwrap();
+            assert_eq!(vec_sink.get_num_bools(), chunked_sink.get_num_bools());
+        }
+        chunked_sink.finish().unwrap();
+        drop(chunked_sink);
+
+        assert_eq!(vec_sink.get_bytes(), &bytes);
+        let (last, full) = chunk_sizes.split_last().unwrap();
+        assert!(full.iter().all(|size| *size == 3));
+        assert!(*last > 0 && *last <= 3);
+    }
+
+    #[test]
+    fn test_callback_error() {
+        let mut sink = ChunkedBitSink::new(1, |_, _| Err("Upload failed".into()));
+        sink.write(&[true; 8]).unwrap();
+        assert!(sink.write(&[true]).is_err());
+    }
+
+    #[test]
+    fn test_files() {
+        let directory =
+            std::env::temp_dir().join(format!("bit-encoding-chunks-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let mut sink = ChunkedBitSink::to_files(2, &directory, "part.");
+        sink.write(&[true; 17]).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(2, sink.get_num_chunks());
+
+        assert_eq!(
+            vec![255, 255],
+            std::fs::read(directory.join("part.000000")).unwrap()
+        );
+        assert_eq!(
+            vec![1],
+            std::fs::read(directory.join("part.000001")).unwrap()
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
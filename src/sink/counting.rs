@@ -0,0 +1,45 @@
+use crate::*;
+
+/// A *BitSink* that discards all bits that are written to it, but counts them.
+/// This is useful to measure how many bits an encoding would take, without
+/// storing it. Unlike *VoidBitSink*, its *get_num_bools* returns the number of
+/// bits that were written.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = CountingBitSink::new();
+/// encoder.write_u32(&mut sink, 1234).unwrap();
+/// encoder.write_u32(&mut sink, 5678).unwrap();
+///
+/// let expected = encoder.encoded_size_u32(1234) + encoder.encoded_size_u32(5678);
+/// assert_eq!(expected as u64, sink.get_num_bools());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CountingBitSink {
+    num_bools: u64,
+}
+
+impl CountingBitSink {
+    /// Constructs a new *CountingBitSink* that hasn't counted any bits yet
+    pub fn new() -> Self {
+        Self { num_bools: 0 }
+    }
+}
+
+impl BitSink for CountingBitSink {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        self.num_bools += bits.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bools
+    }
+}
@@ -0,0 +1,311 @@
+use crate::*;
+
+/// The number of bits of the header of each datagram, which stores the number of
+/// padding bits in the last byte
+const HEADER_BITS: u64 = 3;
+
+/// A *BitSink* that collects the bits of a single datagram (like a UDP packet),
+/// and passes the finished datagram to a callback when *finish_packet* is
+/// called. After that, the sink starts with the next (empty) datagram, and
+/// reuses the memory of the previous one.
+///
+/// The datagrams are packed in the same way as *U8VecBitSink*, except that
+/// every datagram starts with a header of 3 bits that stores the number of
+/// padding bits at the end of its last byte. This allows the receiver to find
+/// out exactly how many bits were written, using only the size of the datagram
+/// (see *read_datagram*).
+///
+/// When a write would make the datagram larger than *max_packet_size* bytes
+/// (including the header), none of its bits are written and a *SinkFull* error
+/// is returned. The application can then call *finish_packet* and write the
+/// value again into the next datagram. Note that encoders can call *write*
+/// multiple times for a single value, so the first part of that value may have
+/// been written, which can be undone with *truncate_packet_bits*.
+///
+/// Calling *finish* will finish the current datagram, unless nothing was
+/// written into it.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut packets = Vec::new();
+/// let mut sink = DatagramBitSink::new(1200, |packet: &[u8]| {
+///     packets.push(packet.to_vec());
+///     Ok(())
+/// });
+/// encoder.write_u32(&mut sink, 1234).unwrap();
+/// sink.finish_packet().unwrap();
+/// encoder.write_u32(&mut sink, 5678).unwrap();
+/// encoder.write_u32(&mut sink, 9).unwrap();
+/// sink.finish().unwrap();
+/// drop(sink);
+///
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let mut source = read_datagram(&packets[0]).unwrap();
+/// assert_eq!(1234, decoder.read_u32(&mut source).unwrap());
+/// assert!(source.read(&mut [false]).is_err());
+///
+/// let mut source = read_datagram(&packets[1]).unwrap();
+/// assert_eq!(5678, decoder.read_u32(&mut source).unwrap());
+/// assert_eq!(9, decoder.read_u32(&mut source).unwrap());
+/// assert!(source.read(&mut [false]).is_err());
+/// ```
+pub struct DatagramBitSink<F: FnMut(&[u8]) -> Result<(), WriteError>> {
+    max_packet_size: usize,
+    on_packet: F,
+
+    packet: Vec<u8>,
+    packet_num_bits: u64,
+    num_packets: u64,
+    num_written_bits: u64,
+}
+
+impl<F: FnMut(&[u8]) -> Result<(), WriteError>> DatagramBitSink<F> {
+    /// Constructs a new *DatagramBitSink* that calls *on_packet* with every
+    /// finished datagram, and allows at most *max_packet_size* bytes per
+    /// datagram (including the header).
+    ///
+    /// Panics if *max_packet_size* is 0.
+    pub fn new(max_packet_size: usize, on_packet: F) -> Self {
+        assert!(
+            max_packet_size > 0,
+            "The maximum packet size must be positive"
+        );
+        Self {
+            max_packet_size,
+            on_packet,
+            packet: Vec::with_capacity(max_packet_size),
+            packet_num_bits: 0,
+            num_packets: 0,
+            num_written_bits: 0,
+        }
+    }
+
+    /// Gets the maximum size of a datagram, in bytes
+    pub fn get_max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+
+    /// Gets the number of datagrams that have been passed to the callback
+    pub fn get_num_packets(&self) -> u64 {
+        self.num_packets
+    }
+
+    /// Gets the number of bits that have been written into the current
+    /// datagram (excluding its header)
+    pub fn get_packet_num_bits(&self) -> u64 {
+        self.packet_num_bits
+    }
+
+    /// Gets the number of bits that can still be written into the current
+    /// datagram
+    pub fn get_packet_remaining_bits(&self) -> u64 {
+        self.max_packet_size as u64 * 8 - HEADER_BITS - self.packet_num_bits
+    }
+
+    /// Removes all bits after the first *num_bits* bits from the current
+    /// datagram. This does nothing if the current datagram doesn't contain more
+    /// than *num_bits* bits.
+    pub fn truncate_packet_bits(&mut self, num_bits: u64) {
+        if num_bits >= self.packet_num_bits {
+            return;
+        }
+        self.num_written_bits -= self.packet_num_bits - num_bits;
+        self.packet_num_bits = num_bits;
+
+        // The bits after the new end must be 0 because write() adds bits with OR
+        let total_bits = HEADER_BITS + num_bits;
+        self.packet.truncate(total_bits.div_ceil(8) as usize);
+        let bit_index = total_bits % 8;
+        if bit_index != 0 {
+            let last_index = self.packet.len() - 1;
+            self.packet[last_index] &= (1 << bit_index) - 1;
+        }
+    }
+
+    /// Passes the current datagram to the callback, and starts a new empty
+    /// datagram. This also works when no bits have been written into the
+    /// current datagram (the callback will get a datagram of 1 byte).
+    ///
+    /// If the callback returns an error, this method returns that error, and
+    /// the current datagram is discarded anyway.
+    pub fn finish_packet(&mut self) -> Result<(), WriteError> {
+        if self.packet.is_empty() {
+            self.packet.push(0);
+        }
+        let total_bits = HEADER_BITS + self.packet_num_bits;
+        let num_padding_bits = (8 - total_bits % 8) % 8;
+        self.packet[0] |= num_padding_bits as u8;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            packet_index = self.num_packets,
+            packet_size = self.packet.len(),
+            "Finishing datagram"
+        );
+        let result = (self.on_packet)(&self.packet);
+        self.num_packets += 1;
+        self.packet.clear();
+        self.packet_num_bits = 0;
+        result
+    }
+}
+
+impl<F: FnMut(&[u8]) -> Result<(), WriteError>> BitSink for DatagramBitSink<F> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let capacity = self.max_packet_size as u64 * 8;
+        let required = HEADER_BITS + self.packet_num_bits + bits.len() as u64;
+        if required > capacity {
+            return Err(Box::new(SinkFull { capacity, required }));
+        }
+        for bit in bits {
+            let bit_index = HEADER_BITS + self.packet_num_bits;
+            if bit_index / 8 == self.packet.len() as u64 {
+                self.packet.push(0);
+            }
+            if *bit {
+                self.packet[(bit_index / 8) as usize] |= 1 << (bit_index % 8);
+            }
+            self.packet_num_bits += 1;
+        }
+        self.num_written_bits += bits.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        if self.packet_num_bits > 0 {
+            self.finish_packet()?;
+        }
+        Ok(())
+    }
+
+    /// Gets the total number of bits that have been written into all datagrams
+    /// (excluding their headers)
+    fn get_num_bools(&self) -> u64 {
+        self.num_written_bits
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.max_packet_size as u64 * 8 - HEADER_BITS)
+    }
+}
+
+/// Reads the header of a *datagram* that was created by a *DatagramBitSink*,
+/// and returns a *U8SliceBitSource* that reads exactly the bits that were
+/// written into that datagram.
+///
+/// Returns an error if the datagram is empty or if its header is invalid.
+pub fn read_datagram(datagram: &[u8]) -> Result<U8SliceBitSource<'_>, DecodeError> {
+    let header = match datagram.first() {
+        Some(first_byte) => first_byte & 0b111,
+        None => {
+            return Err(DecodeError::Reading(ReadError::ReachedEnd {
+                read_bools: 0,
+            }))
+        }
+    };
+    if HEADER_BITS + header as u64 > datagram.len() as u64 * 8 {
+        return Err(DecodeError::Other(
+            format!("Invalid datagram header: {} padding bits", header).into(),
+        ));
+    }
+    let mut source = U8SliceBitSource::new(datagram, header);
+    source.seek_bits(HEADER_BITS)?;
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_packets() {
+        let mut packets = Vec::new();
+        let mut sink = DatagramBitSink::new(3, |packet: &[u8]| {
+            packets.push(packet.to_vec());
+            Ok(())
+        });
+        assert_eq!(Some(21), sink.capacity_bits());
+        sink.finish_packet().unwrap();
+        sink.write(&[true; 5]).unwrap();
+        sink.finish_packet().unwrap();
+        sink.write(&[true, false, true]).unwrap();
+        sink.write(&[false; 18]).unwrap();
+        assert_eq!(0, sink.get_packet_remaining_bits());
+        let error = sink.write(&[true]).unwrap_err();
+        assert_eq!(
+            &SinkFull {
+                capacity: 24,
+                required: 25
+            },
+            error.downcast_ref::<SinkFull>().unwrap()
+        );
+        sink.finish().unwrap();
+        // There is nothing left to finish
+        sink.finish().unwrap();
+        assert_eq!(3, sink.get_num_packets());
+        assert_eq!(26, sink.get_num_bools());
+        drop(sink);
+
+        // Header 101 (5 padding bits)
+        assert_eq!(vec![0b101], packets[0]);
+        // Header 000 followed by 5 ones
+        assert_eq!(vec![0b1111_1000], packets[1]);
+        // Header 000 followed by 101 and 18 zeros
+        assert_eq!(vec![0b0010_1000, 0, 0], packets[2]);
+
+        let expected: [&[bool]; 3] = [&[], &[true; 5], &[true, false, true]];
+        for (packet, expected) in packets.iter().zip(expected.iter()) {
+            let mut source = read_datagram(packet).unwrap();
+            let mut dest = vec![false; expected.len()];
+            source.read(&mut dest).unwrap();
+            assert_eq!(*expected, &dest[..]);
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut packets = Vec::new();
+        let mut sink = DatagramBitSink::new(10, |packet: &[u8]| {
+            packets.push(packet.to_vec());
+            Ok(())
+        });
+        sink.write(&[true; 30]).unwrap();
+        sink.truncate_packet_bits(40);
+        assert_eq!(30, sink.get_packet_num_bits());
+        sink.truncate_packet_bits(6);
+        assert_eq!(6, sink.get_packet_num_bits());
+        assert_eq!(6, sink.get_num_bools());
+        sink.write(&[false, true]).unwrap();
+        sink.finish_packet().unwrap();
+        drop(sink);
+
+        let mut expected = U8VecBitSink::new();
+        // Header 101 (5 padding bits)
+        expected.write(&[true, false, true]).unwrap();
+        expected.write(&[true; 6]).unwrap();
+        expected.write(&[false, true]).unwrap();
+        assert_eq!(expected.get_bytes(), &packets[0]);
+    }
+
+    #[test]
+    fn test_callback_error() {
+        let mut sink = DatagramBitSink::new(10, |_packet: &[u8]| Err("Network is down".into()));
+        sink.write(&[true; 10]).unwrap();
+        assert!(sink.finish_packet().is_err());
+        assert_eq!(0, sink.get_packet_num_bits());
+        assert_eq!(1, sink.get_num_packets());
+    }
+
+    #[test]
+    fn test_invalid_datagrams() {
+        assert!(read_datagram(&[]).is_err());
+        // 6 padding bits + 3 header bits don't fit in 1 byte
+        assert!(read_datagram(&[0b110]).is_err());
+        let mut source = read_datagram(&[0b101]).unwrap();
+        assert!(source.read(&mut [false]).is_err());
+    }
+}
@@ -3,3 +3,26 @@
 /// Currently, this is simply a *Box* containing an *Error* because
 /// only implementation-specific errors can occur.
 pub type WriteError = Box<dyn std::error::Error>;
+
+/// The error that fixed-capacity sinks (like *ArrayBitSink*) return when a write
+/// would exceed their capacity. Since *WriteError* is a boxed error, callers
+/// can detect this error using *downcast_ref::\<SinkFull\>()*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkFull {
+    /// The maximum number of bits that the sink can hold
+    pub capacity: u64,
+    /// The number of bits that the sink would need to hold after the write
+    pub required: u64,
+}
+
+impl std::fmt::Display for SinkFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "The sink can hold only {} bits, but {} bits would be needed",
+            self.capacity, self.required
+        )
+    }
+}
+
+impl std::error::Error for SinkFull {}
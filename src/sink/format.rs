@@ -0,0 +1,199 @@
+use crate::*;
+
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter};
+
+/// Determines how *DisplayBits* groups the bits that it prints. The default
+/// format groups the bits per nibble (4 bits) and puts 8 nibbles on each line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFormat {
+    /// The number of bits in each group. A space is put between every 2 groups.
+    pub group_size: usize,
+    /// The number of groups on each line. Every line starts with the offset
+    /// of its first bit.
+    pub groups_per_line: usize,
+}
+
+impl BitFormat {
+    /// Groups the bits per nibble, with 8 nibbles (32 bits) per line
+    pub const NIBBLES: BitFormat = BitFormat {
+        group_size: 4,
+        groups_per_line: 8,
+    };
+
+    /// Groups the bits per byte, with 8 bytes (64 bits) per line
+    pub const BYTES: BitFormat = BitFormat {
+        group_size: 8,
+        groups_per_line: 8,
+    };
+}
+
+impl Default for BitFormat {
+    fn default() -> Self {
+        Self::NIBBLES
+    }
+}
+
+/// A human-friendly *Display* of a sequence of bits, which is returned by the
+/// *display_bits* methods of the sinks of this crate. It prints the bits in
+/// groups (according to its *BitFormat*), and starts every line with the
+/// offset of its first bit, for instance:
+///
+/// ```text
+/// 0: 0101 0011 1
+/// ```
+pub struct DisplayBits<'a> {
+    bits: Cow<'a, [bool]>,
+    format: BitFormat,
+}
+
+impl<'a> DisplayBits<'a> {
+    /// Constructs a new *DisplayBits* that will display *bits* using *format*
+    pub fn new(bits: impl Into<Cow<'a, [bool]>>, format: BitFormat) -> Self {
+        Self {
+            bits: bits.into(),
+            format,
+        }
+    }
+}
+
+impl<'a> Display for DisplayBits<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let group_size = self.format.group_size.max(1);
+        let line_size = group_size * self.format.groups_per_line.max(1);
+        let last_offset = self.bits.len().saturating_sub(1) / line_size * line_size;
+        let offset_width = last_offset.to_string().len();
+
+        for (line_index, line) in self.bits.chunks(line_size).enumerate() {
+            if line_index > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{:>width$}:",
+                line_index * line_size,
+                width = offset_width
+            )?;
+            for group in line.chunks(group_size) {
+                write!(f, " ")?;
+                for bit in group {
+                    write!(f, "{}", if *bit { '1' } else { '0' })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Debug for DisplayBits<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        Display::fmt(self, f)
+    }
+}
+
+impl BoolVecBitSink {
+    /// Creates a *DisplayBits* that shows the bits of this sink using *format*.
+    /// The *Display* implementation of this sink uses *BitFormat::NIBBLES*.
+    pub fn display_bits(&self, format: BitFormat) -> DisplayBits<'_> {
+        DisplayBits::new(self.get_bits(), format)
+    }
+}
+
+impl<W: UnsignedWord> WordVecBitSink<W> {
+    /// Creates a *DisplayBits* that shows the bits of this sink using *format*.
+    /// The *Display* implementation of this sink uses *BitFormat::NIBBLES*.
+    pub fn display_bits(&self, format: BitFormat) -> DisplayBits<'_> {
+        DisplayBits::new(self.get_bools(), format)
+    }
+}
+
+macro_rules! impl_sink_format {
+    ($sink:ty, $name:expr, $($generics:tt)*) => {
+        impl$($generics)* Display for $sink {
+            fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+                Display::fmt(&self.display_bits(BitFormat::NIBBLES), f)
+            }
+        }
+
+        impl$($generics)* Debug for $sink {
+            fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+                writeln!(f, "{} ({} bits) {{", $name, self.get_num_bools())?;
+                for line in self.display_bits(BitFormat::BYTES).to_string().lines() {
+                    writeln!(f, "    {}", line)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    };
+}
+
+impl_sink_format!(BoolVecBitSink, "BoolVecBitSink",);
+impl_sink_format!(
+    WordVecBitSink<W>,
+    format!("WordVecBitSink<{}>", std::any::type_name::<W>()),
+    <W: UnsignedWord>
+);
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_display_bits() {
+        let bits = [true, false, true, true, false, false, true, false, true];
+        assert_eq!(
+            "0: 1011 0010 1",
+            DisplayBits::new(&bits[..], BitFormat::NIBBLES).to_string()
+        );
+        assert_eq!(
+            "0: 10 11\n4: 00 10\n8: 1",
+            DisplayBits::new(
+                &bits[..],
+                BitFormat {
+                    group_size: 2,
+                    groups_per_line: 2
+                }
+            )
+            .to_string()
+        );
+        assert_eq!("", DisplayBits::new(&[][..], BitFormat::BYTES).to_string());
+    }
+
+    #[test]
+    fn test_offset_alignment() {
+        let text = DisplayBits::new(vec![false; 40], BitFormat::BYTES).to_string();
+        assert_eq!("0: 00000000 00000000 00000000 00000000 00000000", text);
+
+        let text = DisplayBits::new(vec![true; 70], BitFormat::BYTES).to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with(" 0: 11111111 "));
+        assert_eq!("64: 111111", lines[1]);
+    }
+
+    #[test]
+    fn test_sinks() {
+        let bits = [false, true, false, true, false, false, true, true, true];
+
+        let mut bool_sink = BoolVecBitSink::new();
+        let mut u8_sink = U8VecBitSink::new();
+        let mut u32_sink = U32VecBitSink::new();
+        bool_sink.write(&bits).unwrap();
+        u8_sink.write(&bits).unwrap();
+        u32_sink.write(&bits).unwrap();
+
+        assert_eq!("0: 0101 0011 1", bool_sink.to_string());
+        assert_eq!("0: 0101 0011 1", u8_sink.to_string());
+        assert_eq!("0: 0101 0011 1", u32_sink.to_string());
+
+        assert_eq!(
+            "WordVecBitSink<u8> (9 bits) {\n    0: 01010011 1\n}",
+            format!("{:?}", u8_sink)
+        );
+        assert_eq!(
+            "0: 01010011 1",
+            u32_sink.display_bits(BitFormat::BYTES).to_string()
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::*;
+
+/// A *BitSink* that wraps another *BitSink* and remembers the bit offsets at
+/// which records start. The offsets are stored in a *BitIndex*, which can be
+/// stored alongside the data to find the start of a record without decoding all
+/// records in front of it.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = IndexedBitSink::new(U8VecBitSink::new());
+/// for record in 0..100u32 {
+///     sink.mark_record();
+///     encoder.write_u32(&mut sink, record * 1000).unwrap();
+/// }
+///
+/// let (inner, index) = sink.into_parts();
+/// let num_padding_bits = (8 - inner.get_num_bools() % 8) % 8;
+/// let reader = IndexedBitReader::new(inner.get_bytes(), num_padding_bits as u8, &index);
+///
+/// let mut source = reader.record_source(42).unwrap();
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// assert_eq!(42_000, decoder.read_u32(&mut source).unwrap());
+/// ```
+pub struct IndexedBitSink<S: BitSink> {
+    sink: S,
+    num_bools: u64,
+    index: BitIndex,
+}
+
+impl<S: BitSink> IndexedBitSink<S> {
+    /// Constructs a new *IndexedBitSink* that will write all its data to *sink*.
+    /// The offsets of the records will be relative to the moment this
+    /// *IndexedBitSink* was created.
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            num_bools: 0,
+            index: BitIndex::new(),
+        }
+    }
+
+    /// Marks the current position as the start of the next record
+    pub fn mark_record(&mut self) {
+        self.index.offsets.push(self.num_bools);
+    }
+
+    /// Gets the index containing the start offsets of all marked records
+    pub fn get_index(&self) -> &BitIndex {
+        &self.index
+    }
+
+    /// Gets a reference to the wrapped *BitSink*
+    pub fn get_inner(&self) -> &S {
+        &self.sink
+    }
+
+    /// Splits this *IndexedBitSink* into the wrapped *BitSink* and the index
+    pub fn into_parts(self) -> (S, BitIndex) {
+        (self.sink, self.index)
+    }
+}
+
+impl<S: BitSink> BitSink for IndexedBitSink<S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        self.sink.write(bits)?;
+        self.num_bools += bits.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.sink.finish()
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bools
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+/// A table of the bit offsets at which records start, as generated by an
+/// *IndexedBitSink*. The offsets are always sorted in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitIndex {
+    offsets: Vec<u64>,
+}
+
+impl BitIndex {
+    /// Constructs an empty *BitIndex*
+    pub fn new() -> Self {
+        Self {
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Gets the number of records in this index
+    pub fn num_records(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Gets the bit offset at which the record with the given index starts, or
+    /// *None* if there is no such record
+    pub fn get_offset(&self, record: usize) -> Option<u64> {
+        self.offsets.get(record).copied()
+    }
+
+    /// Gets all record offsets in ascending order
+    pub fn get_offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+
+    /// Moves *source* to the start of the record with the given index. Returns
+    /// *ReadError::ReachedEnd* if this index doesn't have the record or if its
+    /// offset is beyond the end of *source*.
+    pub fn seek_to_record(
+        &self,
+        source: &mut impl SeekableBitSource,
+        record: usize,
+    ) -> Result<(), ReadError> {
+        match self.get_offset(record) {
+            Some(offset) => source.seek_bits(offset),
+            None => Err(ReadError::ReachedEnd { read_bools: 0 }),
+        }
+    }
+
+    /// Writes this index to *sink*. The number of records and the distances
+    /// between consecutive offsets will be encoded with *encoder*. Since
+    /// these distances are usually small, the *DigitIntEncodingProtocol* is a
+    /// good choice.
+    pub fn write_to(
+        &self,
+        sink: &mut impl BitSink,
+        encoder: &impl IntEncodingProtocol,
+    ) -> Result<(), WriteError> {
+        encoder.write_u64(sink, self.offsets.len() as u64)?;
+        let mut previous = 0;
+        for offset in &self.offsets {
+            encoder.write_u64(sink, offset - previous)?;
+            previous = *offset;
+        }
+        Ok(())
+    }
+
+    /// Reads an index that was written by *write_to* from *source*. The
+    /// number of records is checked against the limits of *context*.
+    pub fn read_from(
+        source: &mut impl BitSource,
+        decoder: &impl IntDecodingProtocol,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError> {
+        let num_records = context.check_vec_len::<u64>(decoder.read_u64(source)?)?;
+        let mut offsets = Vec::with_capacity(num_records);
+        let mut previous: u64 = 0;
+        for _counter in 0..num_records {
+            previous = previous
+                .checked_add(decoder.read_u64(source)?)
+                .ok_or(DecodeError::IntOverflow)?;
+            offsets.push(previous);
+        }
+        Ok(Self { offsets })
+    }
+}
+
+impl Default for BitIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_offsets() {
+        let mut sink = IndexedBitSink::new(BoolVecBitSink::new());
+        sink.mark_record();
+        sink.write(&[true, false, true]).unwrap();
+        sink.mark_record();
+        sink.mark_record();
+        sink.write(&[false; 10]).unwrap();
+        sink.mark_record();
+
+        assert_eq!(&[0, 3, 3, 13], sink.get_index().get_offsets());
+        assert_eq!(13, sink.get_num_bools());
+        assert_eq!(13, sink.get_inner().get_bits().len());
+    }
+
+    #[test]
+    fn test_index_symmetry() {
+        let mut sink = IndexedBitSink::new(VoidBitSink::new());
+        for counter in 0..1000 {
+            sink.mark_record();
+            sink.write(&vec![true; counter % 17]).unwrap();
+        }
+        let index = sink.get_index();
+
+        let mut index_sink = BoolVecBitSink::new();
+        index
+            .write_to(&mut index_sink, &DigitIntEncodingProtocol::v1())
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(index_sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded =
+            BitIndex::read_from(&mut source, &DigitIntDecodingProtocol::v1(), &mut context)
+                .unwrap();
+        assert_eq!(index, &decoded);
+    }
+
+    #[test]
+    fn test_index_limits() {
+        let mut index = BitIndex::new();
+        index.offsets = vec![1, 2, 3];
+        let mut sink = BoolVecBitSink::new();
+        index
+            .write_to(&mut sink, &SimpleIntEncodingProtocol::new())
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_vec_len(2));
+        assert!(BitIndex::read_from(
+            &mut source,
+            &SimpleIntDecodingProtocol::new(),
+            &mut context
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::*;
+
+use std::io::{BufWriter, Stdout, Write};
+
+/// A *BitSink* that packs the bools written to it into bytes (in the same way as
+/// *U8VecBitSink*) and writes these bytes to a *std::io::Write* (for instance a
+/// file or a *TcpStream*). Every byte is written as soon as it is complete, and
+/// the last (partial) byte is written when *finish* is called.
+///
+/// This sink doesn't buffer more than 1 byte, so wrapping the writer in a
+/// *BufWriter* is recommended when writing many small pieces of data.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = IoBitSink::new(Vec::new());
+/// sink.write(&[true; 10]).unwrap();
+/// sink.finish().unwrap();
+///
+/// assert_eq!(vec![255, 3], sink.into_inner());
+/// ```
+pub struct IoBitSink<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    current_byte: u8,
+    bit_index: u8,
+    num_bits: u64,
+}
+
+impl<W: Write> IoBitSink<W> {
+    /// Constructs a new *IoBitSink* that writes its bytes to *writer*
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+            current_byte: 0,
+            bit_index: 0,
+            num_bits: 0,
+        }
+    }
+
+    /// Gets a reference to the writer of this sink
+    pub fn get_writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the writer of this sink. Writing to it
+    /// directly while this sink is in the middle of a byte will corrupt the
+    /// output.
+    pub fn get_writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consumes this sink and returns its writer. The partial last byte (if
+    /// any) will be lost if *finish* wasn't called.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Checks whether the number of bits written so far is a multiple of 8
+    pub fn is_byte_aligned(&self) -> bool {
+        self.bit_index == 0
+    }
+
+    /// Writes whole bytes to this sink. If this sink is byte aligned, the
+    /// bytes will be passed to the writer directly. Otherwise, they will be
+    /// written bit by bit (from the least significant bit to the most
+    /// significant bit of each byte).
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), WriteError> {
+        if self.is_byte_aligned() {
+            self.writer.write_all(bytes)?;
+            self.num_bits += bytes.len() as u64 * 8;
+            Ok(())
+        } else {
+            self.write(&bytes_to_bools(bytes))
+        }
+    }
+
+    /// Flushes the underlying writer. Note that a partial last byte will not
+    /// be written until *finish* is called.
+    pub fn flush(&mut self) -> Result<(), WriteError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> IoBitSink<BufWriter<W>> {
+    /// Constructs a new *IoBitSink* that writes its bytes to a *BufWriter* that
+    /// wraps *writer*, which is useful for pipes and sockets. Note that the
+    /// buffered bytes are only guaranteed to be written after *finish* (or
+    /// *flush*) has been called.
+    pub fn buffered(writer: W) -> Self {
+        Self::new(BufWriter::new(writer))
+    }
+}
+
+impl IoBitSink<BufWriter<Stdout>> {
+    /// Constructs a new buffered *IoBitSink* that writes its bytes to the
+    /// standard output, for instance to pipe the encoded data to another
+    /// process. Don't forget to call *finish*, since that writes the last
+    /// partial byte and flushes the buffer.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use bit_encoding::*;
+    ///
+    /// let mut sink = IoBitSink::to_stdout();
+    /// DigitIntEncodingProtocol::v1().write_u32(&mut sink, 1234).unwrap();
+    /// sink.finish().unwrap();
+    /// ```
+    pub fn to_stdout() -> Self {
+        Self::buffered(std::io::stdout())
+    }
+}
+
+impl<W: Write> BitSink for IoBitSink<W> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        for bit in bits {
+            if *bit {
+                self.current_byte |= 1 << self.bit_index;
+            }
+            self.bit_index += 1;
+            if self.bit_index == 8 {
+                self.buffer.push(self.current_byte);
+                self.current_byte = 0;
+                self.bit_index = 0;
+            }
+        }
+        self.num_bits += bits.len() as u64;
+
+        if !self.buffer.is_empty() {
+            let result = self.writer.write_all(&self.buffer);
+            self.buffer.clear();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        if self.bit_index != 0 {
+            self.writer.write_all(&[self.current_byte])?;
+            self.current_byte = 0;
+            self.bit_index = 0;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_same_as_u8_vec() {
+        let mut io_sink = IoBitSink::new(Vec::new());
+        let mut vec_sink = U8VecBitSink::new();
+        for counter in 0..100 {
+            let bits: Vec<bool> = (0..counter % 13).map(|index| index % 3 == 0).collect();
+            io_sink.write(&bits).unwrap();
+            vec_sink.write(&bits).unwrap();
+        }
+        assert_eq!(vec_sink.get_num_bools(), io_sink.get_num_bools());
+        io_sink.finish().unwrap();
+        assert_eq!(vec_sink.get_bytes(), &io_sink.into_inner());
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        let mut sink = IoBitSink::new(Vec::new());
+        sink.write_bytes(&[1, 2]).unwrap();
+        assert!(sink.is_byte_aligned());
+        sink.write(&[true]).unwrap();
+        assert!(!sink.is_byte_aligned());
+        sink.write_bytes(&[255]).unwrap();
+        assert_eq!(25, sink.get_num_bools());
+        sink.finish().unwrap();
+        assert_eq!(vec![1, 2, 255, 1], sink.into_inner());
+    }
+
+    #[test]
+    fn test_buffered() {
+        let mut sink = IoBitSink::buffered(Vec::new());
+        sink.write(&[true; 12]).unwrap();
+        assert!(sink.get_writer().get_ref().is_empty());
+        sink.finish().unwrap();
+        assert_eq!(&vec![255, 15], sink.get_writer().get_ref());
+    }
+}
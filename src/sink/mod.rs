@@ -1,18 +1,42 @@
 // Extra modules
 mod errors;
+mod format;
 
 // Implementations
+mod array;
 mod bool_vec;
+mod chunked;
+mod counting;
+mod datagram;
+mod indexed;
+mod io;
+mod progress;
+mod ring_buffer;
+mod slice;
+mod throttled;
 mod u32_vec;
 mod u8_vec;
 mod void;
+mod word_vec;
 
 // Export all as part of this module
+pub use array::*;
 pub use bool_vec::*;
+pub use chunked::*;
+pub use counting::*;
+pub use datagram::*;
 pub use errors::*;
+pub use format::*;
+pub use indexed::*;
+pub use io::*;
+pub use progress::*;
+pub use ring_buffer::*;
+pub use slice::*;
+pub use throttled::*;
 pub use u32_vec::*;
 pub use u8_vec::*;
 pub use void::*;
+pub use word_vec::*;
 
 /// A type to which bools can be written.
 ///
@@ -43,4 +67,23 @@ pub trait BitSink {
     /// Gets the total number of bools that have been written into this sink so
     /// far.
     fn get_num_bools(&self) -> u64;
+
+    /// Gets the total number of bits that have been written into this sink so
+    /// far. This is the same as *get_num_bools*.
+    fn len_bits(&self) -> u64 {
+        self.get_num_bools()
+    }
+
+    /// Checks whether no bits have been written into this sink yet
+    fn is_empty(&self) -> bool {
+        self.len_bits() == 0
+    }
+
+    /// Gets the number of bits that this sink can hold without allocating more
+    /// memory (for sinks that store their bits in memory) or before it becomes
+    /// full (for sinks with a fixed size). Returns *None* for sinks that don't
+    /// store their bits (like *IoBitSink*), or when it is unknown.
+    fn capacity_bits(&self) -> Option<u64> {
+        None
+    }
 }
@@ -0,0 +1,161 @@
+use crate::*;
+
+/// The progress of a long encoding or decoding operation, as reported by
+/// *ProgressBitSink* and *ProgressBitSource*
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of bits that have been written or read so far
+    pub processed_bits: u64,
+    /// The total number of bits that will be written or read, if it is known
+    pub total_bits: Option<u64>,
+}
+
+impl Progress {
+    /// Gets the fraction of the total number of bits that has been processed
+    /// (between 0.0 and 1.0), or *None* if the total is unknown
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bits.map(|total_bits| {
+            if total_bits == 0 {
+                1.0
+            } else {
+                (self.processed_bits as f64 / total_bits as f64).min(1.0)
+            }
+        })
+    }
+}
+
+/// A *BitSink* that wraps another *BitSink* and calls an observer whenever
+/// another *report_interval* bits have been written to it, and once more when
+/// it is finished. This can be used to show a progress bar while encoding
+/// large files.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut reports = Vec::new();
+/// let mut sink = ProgressBitSink::new(VoidBitSink::new(), 100, Some(250), |progress| {
+///     reports.push(progress.processed_bits)
+/// });
+/// for _counter in 0..25 {
+///     sink.write(&[true; 10]).unwrap();
+/// }
+/// sink.finish().unwrap();
+/// drop(sink);
+///
+/// assert_eq!(vec![100, 200, 250], reports);
+/// ```
+pub struct ProgressBitSink<S: BitSink, F: FnMut(Progress)> {
+    sink: S,
+    report_interval: u64,
+    total_bits: Option<u64>,
+    observer: F,
+
+    processed_bits: u64,
+    next_report: u64,
+}
+
+impl<S: BitSink, F: FnMut(Progress)> ProgressBitSink<S, F> {
+    /// Constructs a new *ProgressBitSink* that writes to *sink* and calls
+    /// *observer* every *report_interval* bits. The *total_bits* (if known)
+    /// will be passed to the observer as well.
+    ///
+    /// Panics if *report_interval* is 0.
+    pub fn new(sink: S, report_interval: u64, total_bits: Option<u64>, observer: F) -> Self {
+        assert!(report_interval > 0, "The report interval must be positive");
+        Self {
+            sink,
+            report_interval,
+            total_bits,
+            observer,
+            processed_bits: 0,
+            next_report: report_interval,
+        }
+    }
+
+    /// Gets a reference to the wrapped *BitSink*
+    pub fn get_inner(&self) -> &S {
+        &self.sink
+    }
+
+    /// Consumes this *ProgressBitSink* and returns the wrapped *BitSink*
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+
+    fn report(&mut self) {
+        (self.observer)(Progress {
+            processed_bits: self.processed_bits,
+            total_bits: self.total_bits,
+        });
+    }
+}
+
+impl<S: BitSink, F: FnMut(Progress)> BitSink for ProgressBitSink<S, F> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        self.sink.write(bits)?;
+        self.processed_bits += bits.len() as u64;
+        if self.processed_bits >= self.next_report {
+            self.report();
+            self.next_report =
+                (self.processed_bits / self.report_interval + 1) * self.report_interval;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.sink.finish()?;
+        self.report();
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.sink.get_num_bools()
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_reports() {
+        let mut reports = Vec::new();
+        let mut sink = ProgressBitSink::new(BoolVecBitSink::new(), 8, None, |progress| {
+            reports.push(progress)
+        });
+        sink.write(&[true; 5]).unwrap();
+        sink.write(&[true; 5]).unwrap();
+        sink.write(&[true; 30]).unwrap();
+        sink.write(&[true; 1]).unwrap();
+        assert_eq!(41, sink.get_num_bools());
+        sink.finish().unwrap();
+        drop(sink);
+
+        let processed: Vec<_> = reports.iter().map(|p| p.processed_bits).collect();
+        assert_eq!(vec![10, 40, 41], processed);
+        assert!(reports.iter().all(|p| p.total_bits.is_none()));
+    }
+
+    #[test]
+    fn test_fraction() {
+        let progress = Progress {
+            processed_bits: 25,
+            total_bits: Some(100),
+        };
+        assert_eq!(Some(0.25), progress.fraction());
+        assert_eq!(
+            None,
+            Progress {
+                processed_bits: 25,
+                total_bits: None
+            }
+            .fraction()
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::*;
+
+use std::collections::VecDeque;
+
+/// A first-in-first-out buffer of bits that is both a *BitSink* and a
+/// *BitSource*: the bits that are written to it can be read back in the same
+/// order, and writing and reading can be interleaved. The bits are packed into
+/// a *VecDeque* of bytes, and bytes are released as soon as all their bits have
+/// been read, so the memory usage only depends on the number of buffered bits.
+///
+/// This is useful for incremental transcoding pipelines, where one stage
+/// encodes into the buffer while the next stage decodes from it. When the
+/// buffer is empty, *read* returns *ReadError::ReachedEnd*, just like reading
+/// past the end of any other source.
+///
+/// When a capacity is given (using *with_capacity*), a write that would make
+/// the number of buffered bits exceed it writes nothing and returns a
+/// *SinkFull* error, which bounds the memory usage of a pipeline whose consumer
+/// is slower than its producer.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let mut buffer = BitRingBuffer::new();
+/// for value in 0..1000 {
+///     encoder.write_u32(&mut buffer, value).unwrap();
+///     assert_eq!(value, decoder.read_u32(&mut buffer).unwrap());
+/// }
+/// assert_eq!(0, buffer.get_num_buffered_bits());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BitRingBuffer {
+    bytes: VecDeque<u8>,
+    read_offset: usize,
+    num_buffered_bits: u64,
+    num_written_bits: u64,
+    capacity: Option<u64>,
+}
+
+impl BitRingBuffer {
+    /// Constructs a new empty *BitRingBuffer* without a capacity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new empty *BitRingBuffer* that can buffer at most
+    /// *capacity* bits at the same time
+    pub fn with_capacity(capacity: u64) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// Gets the maximum number of bits that this buffer can hold at the same
+    /// time, or *None* if it doesn't have a capacity
+    pub fn get_capacity(&self) -> Option<u64> {
+        self.capacity
+    }
+
+    /// Gets the number of bits that have been written, but not read yet
+    pub fn get_num_buffered_bits(&self) -> u64 {
+        self.num_buffered_bits
+    }
+
+    /// Checks whether all bits that were written have been read. Note that
+    /// *BitSink::is_empty* checks whether any bits were written at all.
+    pub fn is_empty(&self) -> bool {
+        self.num_buffered_bits == 0
+    }
+
+    /// Gets an iterator over the bits that have been written, but not read yet
+    /// (without reading them)
+    pub fn iter_bits(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.num_buffered_bits as usize).map(move |index| {
+            let bit_index = self.read_offset + index;
+            self.bytes[bit_index / 8] & (1 << (bit_index % 8)) != 0
+        })
+    }
+
+    /// Discards all buffered bits
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.read_offset = 0;
+        self.num_buffered_bits = 0;
+    }
+}
+
+impl BitSink for BitRingBuffer {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        if let Some(capacity) = self.capacity {
+            let required = self.num_buffered_bits + bits.len() as u64;
+            if required > capacity {
+                return Err(Box::new(SinkFull { capacity, required }));
+            }
+        }
+        for bit in bits {
+            let bit_index = self.read_offset + self.num_buffered_bits as usize;
+            if bit_index / 8 == self.bytes.len() {
+                self.bytes.push_back(0);
+            }
+            if *bit {
+                self.bytes[bit_index / 8] |= 1 << (bit_index % 8);
+            }
+            self.num_buffered_bits += 1;
+        }
+        self.num_written_bits += bits.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    /// Gets the total number of bits that have been written to this buffer,
+    /// including the bits that have already been read
+    fn get_num_bools(&self) -> u64 {
+        self.num_written_bits
+    }
+
+    /// Gets the maximum number of bits that this buffer can hold at the same
+    /// time (see *get_capacity*)
+    fn capacity_bits(&self) -> Option<u64> {
+        self.capacity
+    }
+}
+
+impl BitSource for BitRingBuffer {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        for (index, dest_bit) in dest.iter_mut().enumerate() {
+            if self.num_buffered_bits == 0 {
+                return Err(ReadError::ReachedEnd { read_bools: index });
+            }
+            *dest_bit = self.bytes[0] & (1 << self.read_offset) != 0;
+            self.read_offset += 1;
+            self.num_buffered_bits -= 1;
+            if self.read_offset == 8 {
+                self.bytes.pop_front();
+                self.read_offset = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_interleaved() {
+        let bits: Vec<bool> = (0..1000)
+            .map(|index| index % 3 == 0 || index % 7 == 1)
+            .collect();
+        let mut buffer = BitRingBuffer::new();
+        let mut read_bits = Vec::new();
+        for (index, chunk) in bits.chunks(13).enumerate() {
+            buffer.write(chunk).unwrap();
+            let mut dest = vec![false; 5 + index % 10];
+            buffer.read(&mut dest).unwrap();
+            read_bits.extend_from_slice(&dest);
+            assert!(buffer.bytes.len() as u64 <= buffer.get_num_buffered_bits() / 8 + 2);
+        }
+        let mut rest = vec![false; buffer.get_num_buffered_bits() as usize];
+        buffer.read(&mut rest).unwrap();
+        read_bits.extend_from_slice(&rest);
+
+        assert_eq!(bits, read_bits);
+        assert!(buffer.is_empty());
+        assert_eq!(1000, buffer.get_num_bools());
+        match buffer.read(&mut [false; 3]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(0, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+
+    #[test]
+    fn test_iter_bits() {
+        let mut buffer = BitRingBuffer::new();
+        buffer.write(&[true, true, false, true]).unwrap();
+        buffer.read(&mut [false; 1]).unwrap();
+        buffer.write(&[false; 10]).unwrap();
+        buffer.write(&[true]).unwrap();
+        let mut expected = vec![true, false, true];
+        expected.extend_from_slice(&[false; 10]);
+        expected.push(true);
+        assert_eq!(expected, buffer.iter_bits().collect::<Vec<_>>());
+        // Iterating doesn't read the bits
+        assert_eq!(14, buffer.get_num_buffered_bits());
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut buffer = BitRingBuffer::with_capacity(10);
+        assert_eq!(Some(10), buffer.capacity_bits());
+        buffer.write(&[true, true]).unwrap();
+        buffer.read(&mut [false; 2]).unwrap();
+        // Nothing is buffered, but bits have been written
+        assert!(buffer.is_empty());
+        assert!(!BitSink::is_empty(&buffer));
+        assert_eq!(2, buffer.len_bits());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut buffer = BitRingBuffer::with_capacity(10);
+        assert_eq!(Some(10), buffer.get_capacity());
+        buffer.write(&[true; 8]).unwrap();
+        let error = buffer.write(&[false; 3]).unwrap_err();
+        assert_eq!(
+            &SinkFull {
+                capacity: 10,
+                required: 11
+            },
+            error.downcast_ref::<SinkFull>().unwrap()
+        );
+        assert_eq!(8, buffer.get_num_buffered_bits());
+
+        let mut dest = [false; 4];
+        buffer.read(&mut dest).unwrap();
+        buffer.write(&[false; 6]).unwrap();
+        assert_eq!(10, buffer.get_num_buffered_bits());
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        buffer.write(&[true; 10]).unwrap();
+    }
+}
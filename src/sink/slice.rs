@@ -0,0 +1,184 @@
+use crate::*;
+
+/// A *BitSink* that writes its bits into a mutable byte slice that is provided
+/// by the caller, without allocating any memory. This is useful to encode
+/// directly into pre-allocated buffers, like DMA buffers or pooled packet
+/// buffers.
+///
+/// The bits are packed in the same order as *U8VecBitSink* (starting at the
+/// least significant bit of the first byte). The previous content of the slice
+/// doesn't matter: each byte is cleared when the first bit is written into it,
+/// and the bytes after the last written bit are left unchanged. When a write
+/// would exceed the capacity of the slice, none of its bits are written and a
+/// *SinkFull* error is returned. Note that encoders can call *write* multiple
+/// times for a single value, so the first part of that value may have been
+/// written.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut buffer = [0xFF; 2];
+/// let mut sink = SliceBitSink::new(&mut buffer);
+/// DigitIntEncodingProtocol::v1().write_u8(&mut sink, 12).unwrap();
+///
+/// // The slice only has room for 6 more bits
+/// let error = sink.write(&[true; 7]).unwrap_err();
+/// assert_eq!(16, error.downcast_ref::<SinkFull>().unwrap().capacity);
+///
+/// let num_bits = sink.into_num_bits();
+/// assert_eq!(10, num_bits);
+///
+/// let num_padding_bits = (16 - num_bits) as u8;
+/// let mut source = U8SliceBitSource::new(&buffer, num_padding_bits);
+/// assert_eq!(12, DigitIntDecodingProtocol::v1().read_u8(&mut source).unwrap());
+/// ```
+#[derive(Debug)]
+pub struct SliceBitSink<'a> {
+    bytes: &'a mut [u8],
+    num_bits: usize,
+}
+
+impl<'a> SliceBitSink<'a> {
+    /// Constructs a new empty *SliceBitSink* that writes into *bytes*
+    pub fn new(bytes: &'a mut [u8]) -> Self {
+        Self { bytes, num_bits: 0 }
+    }
+
+    /// Gets the maximum number of bits that this sink can hold, which is 8
+    /// times the length of the slice
+    pub fn get_capacity(&self) -> u64 {
+        self.bytes.len() as u64 * 8
+    }
+
+    /// Gets the bytes that contain the bits that have been written so far. The
+    /// unused bits of the last byte are 0.
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes[0..self.num_bits.div_ceil(8)]
+    }
+
+    /// Gets an iterator over the bits that have been written so far
+    pub fn iter_bits(&self) -> WordBitIter<u8, &[u8]> {
+        WordBitIter::new(self.bytes, self.num_bits as u64)
+    }
+
+    /// Consumes this sink and returns the number of bits that were written
+    /// into the slice, so that the slice can be used again
+    pub fn into_num_bits(self) -> u64 {
+        self.num_bits as u64
+    }
+
+    /// Removes all bits from this sink, so that it can be reused
+    pub fn clear(&mut self) {
+        self.num_bits = 0;
+    }
+}
+
+impl<'a> BitSink for SliceBitSink<'a> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let required = self.num_bits as u64 + bits.len() as u64;
+        if required > self.get_capacity() {
+            return Err(Box::new(SinkFull {
+                capacity: self.get_capacity(),
+                required,
+            }));
+        }
+        for bit in bits {
+            let byte = &mut self.bytes[self.num_bits / 8];
+            let bit_index = self.num_bits % 8;
+            if bit_index == 0 {
+                *byte = 0;
+            }
+            if *bit {
+                *byte |= 1 << bit_index;
+            }
+            self.num_bits += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.num_bits as u64
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.get_capacity())
+    }
+}
+
+/// Borrows the written bytes of the sink, with the right number of padding
+/// bits
+impl<'a, 'b> From<&'a SliceBitSink<'b>> for U8SliceBitSource<'a> {
+    fn from(sink: &'a SliceBitSink<'b>) -> Self {
+        let num_padding_bits = (8 - sink.num_bits % 8) % 8;
+        U8SliceBitSource::new(sink.get_bytes(), num_padding_bits as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_same_as_u8_vec() {
+        let bits: Vec<bool> = (0..77)
+            .map(|index| index % 3 == 1 || index % 7 == 0)
+            .collect();
+        let mut buffer = [0b1010_1010; 12];
+        let mut slice_sink = SliceBitSink::new(&mut buffer);
+        let mut vec_sink = U8VecBitSink::new();
+        for chunk in bits.chunks(6) {
+            slice_sink.write(chunk).unwrap();
+            vec_sink.write(chunk).unwrap();
+        }
+        assert_eq!(77, slice_sink.get_num_bools());
+        assert_eq!(&vec_sink.get_bytes()[..], slice_sink.get_bytes());
+        assert_eq!(bits, slice_sink.iter_bits().collect::<Vec<_>>());
+
+        let mut source = U8SliceBitSource::from(&slice_sink);
+        let mut dest = [false; 77];
+        source.read(&mut dest).unwrap();
+        assert_eq!(&bits[..], &dest[..]);
+        assert!(source.read(&mut [false]).is_err());
+
+        assert_eq!(77, slice_sink.into_num_bits());
+        assert_eq!(&vec_sink.get_bytes()[..], &buffer[..10]);
+        // The bytes after the written bits are untouched
+        assert_eq!(&[0b1010_1010; 2], &buffer[10..]);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut buffer = [0; 1];
+        let mut sink = SliceBitSink::new(&mut buffer);
+        sink.write(&[true; 5]).unwrap();
+        let error = sink.write(&[true; 4]).unwrap_err();
+        assert_eq!(
+            &SinkFull {
+                capacity: 8,
+                required: 9
+            },
+            error.downcast_ref::<SinkFull>().unwrap()
+        );
+        assert_eq!(5, sink.get_num_bools());
+        sink.write(&[false; 3]).unwrap();
+        assert!(sink.write(&[]).is_ok());
+        assert!(sink.write(&[false]).is_err());
+        assert_eq!(&[0b0001_1111], sink.get_bytes());
+
+        sink.clear();
+        assert_eq!(0, sink.get_num_bools());
+        sink.write(&[false, true]).unwrap();
+        assert_eq!(&[0b10], sink.get_bytes());
+
+        let mut empty = SliceBitSink::new(&mut []);
+        assert_eq!(Some(0), empty.capacity_bits());
+        assert!(empty.write(&[]).is_ok());
+        assert!(empty.write(&[true]).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::*;
+
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
+
+/// Determines what a *ThrottledBitSink* should do when a write would exceed
+/// its rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleMode {
+    /// Block the current thread until enough bits can be written
+    Sleep,
+    /// Return a *std::io::Error* with kind *ErrorKind::WouldBlock* (wrapped in
+    /// a *WriteError*) without writing anything. The caller can try the same
+    /// write again later.
+    WouldBlock,
+}
+
+/// A *BitSink* that wraps another *BitSink* and limits the number of bits per
+/// second that are written to it. This is useful when the wrapped sink
+/// transmits the bits directly over a slow (radio or serial) link.
+///
+/// The limit is enforced with a token bucket: the bucket can hold at most
+/// *burst_bits* bits and is refilled with *bits_per_second* bits every second.
+/// Every written bit takes 1 bit from the bucket.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let inner = BoolVecBitSink::new();
+/// let mut sink = ThrottledBitSink::new(inner, 1000, 100, ThrottleMode::WouldBlock);
+/// sink.write(&[true; 100]).unwrap();
+///
+/// // The bucket is empty, so the next write is rejected
+/// let error = sink.write(&[true; 50]).unwrap_err();
+/// let io_error = error.downcast_ref::<std::io::Error>().unwrap();
+/// assert_eq!(std::io::ErrorKind::WouldBlock, io_error.kind());
+/// ```
+pub struct ThrottledBitSink<S: BitSink> {
+    sink: S,
+    bits_per_second: u64,
+    burst_bits: u64,
+    mode: ThrottleMode,
+
+    available_bits: f64,
+    last_refill: Instant,
+}
+
+impl<S: BitSink> ThrottledBitSink<S> {
+    /// Constructs a new *ThrottledBitSink* that writes at most *bits_per_second*
+    /// bits per second to *sink*, and at most *burst_bits* bits at once. The
+    /// bucket starts full, so the first *burst_bits* bits can be written
+    /// immediately.
+    ///
+    /// Panics if *bits_per_second* or *burst_bits* is 0.
+    pub fn new(sink: S, bits_per_second: u64, burst_bits: u64, mode: ThrottleMode) -> Self {
+        assert!(bits_per_second > 0, "bits_per_second must be positive");
+        assert!(burst_bits > 0, "burst_bits must be positive");
+        Self {
+            sink,
+            bits_per_second,
+            burst_bits,
+            mode,
+            available_bits: burst_bits as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Gets a reference to the wrapped *BitSink*
+    pub fn get_inner(&self) -> &S {
+        &self.sink
+    }
+
+    /// Consumes this *ThrottledBitSink* and returns the wrapped *BitSink*
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.available_bits = (self.available_bits + elapsed * self.bits_per_second as f64)
+            .min(self.burst_bits as f64);
+        self.last_refill = now;
+    }
+
+    fn wait_for(&mut self, num_bits: u64) {
+        self.refill();
+        let missing_bits = num_bits as f64 - self.available_bits;
+        if missing_bits > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(
+                missing_bits / self.bits_per_second as f64,
+            ));
+            self.refill();
+        }
+    }
+}
+
+impl<S: BitSink> BitSink for ThrottledBitSink<S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        match self.mode {
+            ThrottleMode::Sleep => {
+                for part in bits.chunks(self.burst_bits as usize) {
+                    self.wait_for(part.len() as u64);
+                    self.sink.write(part)?;
+                    self.available_bits = (self.available_bits - part.len() as f64).max(0.0);
+                }
+                Ok(())
+            }
+            ThrottleMode::WouldBlock => {
+                if bits.len() as u64 > self.burst_bits {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Can't write {} bits at once when burst_bits is {}",
+                            bits.len(),
+                            self.burst_bits
+                        ),
+                    )));
+                }
+                self.refill();
+                if self.available_bits < bits.len() as f64 {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::WouldBlock,
+                        "The rate limit would be exceeded",
+                    )));
+                }
+                self.sink.write(bits)?;
+                self.available_bits -= bits.len() as f64;
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.sink.finish()
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.sink.get_num_bools()
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_sleep() {
+        let mut sink =
+            ThrottledBitSink::new(BoolVecBitSink::new(), 10_000, 500, ThrottleMode::Sleep);
+        let start_time = Instant::now();
+        sink.write(&[true; 2500]).unwrap();
+
+        // The first 500 bits are free, the other 2000 bits take 0.2 seconds
+        assert!(start_time.elapsed() >= Duration::from_millis(190));
+        assert_eq!(2500, sink.get_num_bools());
+        assert_eq!(&[true; 2500][..], sink.into_inner().get_bits());
+    }
+
+    #[test]
+    fn test_would_block() {
+        let mut sink =
+            ThrottledBitSink::new(BoolVecBitSink::new(), 5, 10, ThrottleMode::WouldBlock);
+        sink.write(&[true; 6]).unwrap();
+        sink.write(&[false; 4]).unwrap();
+
+        let error = sink.write(&[true; 5]).unwrap_err();
+        assert_eq!(
+            std::io::ErrorKind::WouldBlock,
+            error.downcast_ref::<std::io::Error>().unwrap().kind()
+        );
+        assert_eq!(10, sink.get_inner().get_num_bools());
+
+        let error = sink.write(&[true; 11]).unwrap_err();
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            error.downcast_ref::<std::io::Error>().unwrap().kind()
+        );
+    }
+}
@@ -15,114 +15,65 @@ use crate::*;
 /// This implementation was an attempt to create a *BitSink* that is just as
 /// efficient with memory as *U8VecBitSink*, but has better performance.
 /// Unfortunately, the performance appeared to be only a little bit better.
-pub struct U32VecBitSink {
-    ints: Vec<u32>,
-    bit_index: u8,
-}
+///
+/// This is an alias for *WordVecBitSink\<u32\>*, so all methods of
+/// *WordVecBitSink* are available as well.
+pub type U32VecBitSink = WordVecBitSink<u32>;
 
 impl U32VecBitSink {
-    /// Constructs a new and empty instance of *U32VecBitSink*
-    pub fn new() -> Self {
-        Self {
-            ints: Vec::new(),
-            bit_index: 0,
-        }
-    }
-
-    /// Constructs a new and empty instance of *U32VecBitSink* with an initial
-    /// capacity of *initial_capacity* *u32*s.
-    pub fn with_capacity(initial_capacity: usize) -> Self {
-        Self {
-            ints: Vec::with_capacity(initial_capacity),
-            bit_index: 0,
-        }
-    }
-
     /// Gets a reference to the *u32* *Vec* storing the data written into this
     /// sink.
     pub fn get_ints(&self) -> &Vec<u32> {
-        &self.ints
+        self.get_words()
     }
 
-    /// Creates a *Vec* of bools that shows exactly which bools were written into
-    /// this sink in which order: The first bool of the *Vec* will be the first
-    /// bool that was written into this sink.
+    /// Converts the bits of this sink to bytes, and returns them together with
+    /// the number of padding bits at the end of the last byte. Note that this is
+    /// *not* the number of used bits in the last byte that
+    /// *U8VecBitSink::into_bytes* returns. The bytes will contain the bits in
+    /// the same order as a *U8VecBitSink* would (every *u32* is converted to
+    /// little endian bytes), so they can be read with
+    /// *U8SliceBitSource::new(&bytes, num_padding_bits)*.
     ///
     /// # Example
     /// ```
     /// use bit_encoding::*;
     ///
     /// let mut sink = U32VecBitSink::new();
-    /// sink.write(&[true, false, true]);
+    /// sink.write(&[true, false, true]).unwrap();
+    /// let (bytes, num_padding_bits) = sink.to_bytes_with_padding();
+    /// assert_eq!(vec![0b101], bytes);
+    /// assert_eq!(5, num_padding_bits);
     ///
-    /// assert_eq!(vec![true, false, true], sink.get_bools());
+    /// let mut source = U8SliceBitSource::new(&bytes, num_padding_bits);
+    /// let mut dest = [false; 3];
+    /// source.read(&mut dest).unwrap();
+    /// assert_eq!([true, false, true], dest);
+    /// assert!(source.read(&mut [false]).is_err());
     /// ```
-    pub fn get_bools(&self) -> Vec<bool> {
-        let mut bools = Vec::with_capacity(self.get_num_bools() as usize);
-        let first_bound = match self.bit_index == 0 {
-            true => self.ints.len(),
-            false => self.ints.len() - 1,
-        };
-        for index in 0..first_bound {
-            let current_int = self.ints[index];
-            for bit_index in 0..32 {
-                bools.push(current_int & (1 << bit_index) != 0);
-            }
-        }
-
-        if self.ints.len() > 0 {
-            let last_int = self.ints[self.ints.len() - 1];
-            for bit_index in 0..self.bit_index {
-                bools.push(last_int & (1 << bit_index) != 0);
-            }
-        }
-        bools
+    pub fn to_bytes_with_padding(&self) -> (Vec<u8>, u8) {
+        Self::pack_bytes(self.get_ints().iter().copied(), self.get_num_bools())
     }
-}
-
-impl BitSink for U32VecBitSink {
-    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
-        // This may not be exact, but should be very accurate
-        self.ints.reserve(bits.len() / 32);
 
-        // If we ended with a partial byte previously, we should continue with it
-        let mut current_int = match self.bit_index == 0 {
-            true => 0,
-            false => self.ints.pop().unwrap(),
-        };
-
-        // Add all bits...
-        for bit in bits {
-            if *bit {
-                current_int |= 1 << self.bit_index;
-            }
-            self.bit_index += 1;
-            if self.bit_index == 32 {
-                self.ints.push(current_int);
-                self.bit_index = 0;
-                current_int = 0;
-            }
-        }
-
-        // If we end with a partial int, we should remember it
-        if self.bit_index != 0 {
-            self.ints.push(current_int);
-        }
-
-        Ok(())
-    }
-
-    fn finish(&mut self) -> Result<(), WriteError> {
-        self.ints.shrink_to_fit();
-        Ok(())
+    /// Like *to_bytes_with_padding*, but consumes this sink. The words are
+    /// moved out of the sink instead of being borrowed, and their buffer is
+    /// freed once the bytes have been created.
+    pub fn into_bytes_with_padding(self) -> (Vec<u8>, u8) {
+        let num_bits = self.get_num_bools();
+        let (words, _) = self.into_words();
+        Self::pack_bytes(words, num_bits)
     }
 
-    fn get_num_bools(&self) -> u64 {
-        if self.bit_index == 0 {
-            self.ints.len() as u64 * 32
-        } else {
-            (self.ints.len() as u64 - 1) * 32 + self.bit_index as u64
-        }
+    fn pack_bytes(words: impl IntoIterator<Item = u32>, num_bits: u64) -> (Vec<u8>, u8) {
+        let num_bytes = num_bits.div_ceil(8) as usize;
+        let mut bytes = Vec::with_capacity(num_bytes);
+        bytes.extend(
+            words
+                .into_iter()
+                .flat_map(|int| int.to_le_bytes())
+                .take(num_bytes),
+        );
+        (bytes, (num_bytes as u64 * 8 - num_bits) as u8)
     }
 }
 
@@ -150,4 +101,21 @@ mod tests {
             assert_eq!(counter, decoder.read_u8(&mut source).unwrap());
         }
     }
+
+    #[test]
+    fn test_to_bytes() {
+        for num_bits in 0..100 {
+            let bits: Vec<bool> = (0..num_bits).map(|index| index % 3 != 1).collect();
+            let mut u32_sink = U32VecBitSink::new();
+            let mut u8_sink = U8VecBitSink::new();
+            u32_sink.write(&bits).unwrap();
+            u8_sink.write(&bits).unwrap();
+
+            let (borrowed_bytes, _) = u32_sink.to_bytes_with_padding();
+            let (bytes, num_padding_bits) = u32_sink.into_bytes_with_padding();
+            assert_eq!(borrowed_bytes, bytes);
+            assert_eq!(u8_sink.get_bytes(), &bytes);
+            assert_eq!(bytes.len() * 8 - num_bits, num_padding_bits as usize);
+        }
+    }
 }
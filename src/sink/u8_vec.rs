@@ -11,103 +11,95 @@ use crate::*;
 /// is approximately twice as slow as that of *BoolVecBitSink* (but note that the
 /// time to write bools into the *BitSink* is rarely the performance bottleneck
 /// of the encoding process).
-pub struct U8VecBitSink {
-    bytes: Vec<u8>,
-    bit_index: u8,
-}
+///
+/// This is an alias for *WordVecBitSink\<u8\>*, so all methods of
+/// *WordVecBitSink* are available as well.
+pub type U8VecBitSink = WordVecBitSink<u8>;
 
 impl U8VecBitSink {
-    /// Constructs a new and empty instance of *U8VecBitSink*
-    pub fn new() -> Self {
-        Self {
-            bytes: Vec::new(),
-            bit_index: 0,
-        }
-    }
-
-    /// Constructs a new and empty instance of *U8VecBitSink* with an initial
-    /// capacity of *initial_capacity* *u8*s.
-    pub fn with_capacity(initial_capacity: usize) -> Self {
-        Self {
-            bytes: Vec::with_capacity(initial_capacity),
-            bit_index: 0,
-        }
-    }
-
-    /// Gets a reference to the *Vec\<u8\>* of this sink
-    pub fn get_bytes(&self) -> &Vec<u8> {
-        &self.bytes
-    }
-
-    /// Creates a *Vec* of bools that shows exactly which bools were written into
-    /// this sink in which order: The first bool of the *Vec* will be the first
-    /// bool that was written into this sink.
+    /// Constructs a *U8VecBitSink* that continues writing after the bits in
+    /// *bytes*, of which only the first *num_used_bits* bits of the last byte
+    /// are in use. This can be used to append more bits to bytes that were
+    /// obtained from *into_bytes* earlier.
+    ///
+    /// # Panics
+    /// This function panics when *num_used_bits* is larger than 8, or when
+    /// *num_used_bits* is 0 while *bytes* is not empty (or the other way around).
     ///
     /// # Example
     /// ```
     /// use bit_encoding::*;
     ///
     /// let mut sink = U8VecBitSink::new();
-    /// sink.write(&[true, false, true]);
+    /// sink.write(&[true, false, true]).unwrap();
+    /// let (bytes, num_used_bits) = sink.into_bytes();
+    /// assert_eq!(3, num_used_bits);
     ///
-    /// assert_eq!(vec![true, false, true], sink.get_bools());
+    /// let mut sink = U8VecBitSink::from_bytes(bytes, num_used_bits);
+    /// sink.write(&[true]).unwrap();
+    /// assert_eq!(vec![true, false, true, true], sink.get_bools());
     /// ```
-    pub fn get_bools(&self) -> Vec<bool> {
-        let mut as_bools = bytes_to_bools(self.get_bytes());
-        if self.bit_index > 0 {
-            for _counter in 0..8 - self.bit_index {
-                as_bools.pop();
-            }
-            as_bools.shrink_to_fit();
-        }
+    pub fn from_bytes(bytes: Vec<u8>, num_used_bits: u8) -> Self {
+        Self::from_words(bytes, num_used_bits)
+    }
+
+    /// Gets a reference to the *Vec\<u8\>* of this sink
+    pub fn get_bytes(&self) -> &Vec<u8> {
+        self.get_words()
+    }
 
-        as_bools
+    /// Consumes this sink and returns its bytes (without cloning them), together
+    /// with the number of bits that are used in the last byte (between 1 and 8,
+    /// or 0 when no bits were written). The unused bits of the last byte are 0.
+    pub fn into_bytes(self) -> (Vec<u8>, u8) {
+        self.into_words()
     }
 }
 
-impl BitSink for U8VecBitSink {
-    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
-        // This may not be exact, but should be very accurate
-        self.bytes.reserve(bits.len() / 8);
+/// Borrows the bytes of the sink, with the right number of padding bits
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = U8VecBitSink::new();
+/// DigitIntEncodingProtocol::v1().write_u32(&mut sink, 1234).unwrap();
+///
+/// let mut source = U8SliceBitSource::from(&sink);
+/// assert_eq!(1234, DigitIntDecodingProtocol::v1().read_u32(&mut source).unwrap());
+/// assert!(source.read(&mut [false]).is_err());
+/// ```
+impl<'a> From<&'a U8VecBitSink> for U8SliceBitSource<'a> {
+    fn from(sink: &'a U8VecBitSink) -> Self {
+        let num_padding_bits = (8 - sink.get_num_bools() % 8) % 8;
+        U8SliceBitSource::new(sink.get_bytes(), num_padding_bits as u8)
+    }
+}
 
-        // If we ended with a partial byte previously, we should continue with it
-        let mut current_byte = match self.bit_index == 0 {
+/// Repacks the bits of the *u32*s into bytes (see *U32VecBitSink::to_bytes_with_padding*).
+/// The resulting sink can be used to continue writing.
+impl From<U32VecBitSink> for U8VecBitSink {
+    fn from(sink: U32VecBitSink) -> Self {
+        let (bytes, num_padding_bits) = sink.into_bytes_with_padding();
+        let num_used_bits = match bytes.is_empty() {
             true => 0,
-            false => self.bytes.pop().unwrap(),
+            false => 8 - num_padding_bits,
         };
-
-        // Add all bits...
-        for bit in bits {
-            if *bit {
-                current_byte |= 1 << self.bit_index;
-            }
-            self.bit_index += 1;
-            if self.bit_index == 8 {
-                self.bytes.push(current_byte);
-                self.bit_index = 0;
-                current_byte = 0;
-            }
-        }
-
-        // If we end with a partial byte, we should remember it
-        if self.bit_index != 0 {
-            self.bytes.push(current_byte);
-        }
-
-        Ok(())
-    }
-
-    fn finish(&mut self) -> Result<(), WriteError> {
-        self.bytes.shrink_to_fit();
-        Ok(())
+        U8VecBitSink::from_bytes(bytes, num_used_bits)
     }
+}
 
-    fn get_num_bools(&self) -> u64 {
-        if self.bit_index == 0 {
-            self.bytes.len() as u64 * 8
-        } else {
-            (self.bytes.len() as u64 - 1) * 8 + self.bit_index as u64
-        }
+/// Packs the bools into bytes. The resulting sink can be used to continue
+/// writing.
+impl From<BoolVecBitSink> for U8VecBitSink {
+    fn from(sink: BoolVecBitSink) -> Self {
+        let bits = sink.get_bits();
+        let num_used_bits = match bits.len() % 8 {
+            0 if bits.is_empty() => 0,
+            0 => 8,
+            remainder => remainder as u8,
+        };
+        U8VecBitSink::from_bytes(bools_to_bytes(bits), num_used_bits)
     }
 }
 
@@ -126,7 +118,7 @@ mod tests {
             encoder.write_u8(&mut sink, counter).unwrap();
         }
 
-        let as_bools = bytes_to_bools(&sink.bytes);
+        let as_bools = bytes_to_bools(sink.get_bytes());
         let mut source = BoolSliceBitSource::new(&as_bools);
         let decoder = DigitIntDecodingProtocol::v1();
 
@@ -135,4 +127,38 @@ mod tests {
             assert_eq!(counter, decoder.read_u8(&mut source).unwrap());
         }
     }
+
+    #[test]
+    fn test_conversions() {
+        for num_bits in 0..70 {
+            let bits: Vec<bool> = (0..num_bits).map(|index| index % 5 < 2).collect();
+            let mut expected = U8VecBitSink::new();
+            expected.write(&bits).unwrap();
+
+            let mut u32_sink = U32VecBitSink::new();
+            u32_sink.write(&bits).unwrap();
+            let mut from_u32 = U8VecBitSink::from(u32_sink);
+            assert_eq!(expected.get_bytes(), from_u32.get_bytes());
+            assert_eq!(num_bits as u64, from_u32.get_num_bools());
+
+            let mut bool_sink = BoolVecBitSink::new();
+            bool_sink.write(&bits).unwrap();
+            let mut from_bools = U8VecBitSink::from(bool_sink);
+            assert_eq!(expected.get_bytes(), from_bools.get_bytes());
+            assert_eq!(num_bits as u64, from_bools.get_num_bools());
+
+            // Both sinks can continue writing
+            from_u32.write(&[true, false, true]).unwrap();
+            from_bools.write(&[true, false, true]).unwrap();
+            expected.write(&[true, false, true]).unwrap();
+            assert_eq!(expected.get_bools(), from_u32.get_bools());
+            assert_eq!(expected.get_bools(), from_bools.get_bools());
+
+            let mut source = U8SliceBitSource::from(&expected);
+            let mut dest = vec![false; num_bits + 3];
+            source.read(&mut dest).unwrap();
+            assert_eq!(expected.get_bools(), dest);
+            assert!(source.read(&mut [false]).is_err());
+        }
+    }
 }
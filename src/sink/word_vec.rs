@@ -0,0 +1,478 @@
+use crate::*;
+
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+/// An unsigned primitive integer type that can be used as the word type of a
+/// *WordVecBitSink*. This trait is implemented for *u8*, *u16*, *u32*, *u64*
+/// and *usize*.
+pub trait UnsignedWord: Copy + Eq + std::fmt::Debug {
+    /// The number of bits in this word type
+    const BITS: u8;
+
+    /// The word value that has all bits set to 0
+    const ZERO: Self;
+
+    /// Returns a copy of this word where the bit at *index* is set to 1. The bit
+    /// at index 0 is the least significant bit.
+    fn with_bit(self, index: u8) -> Self;
+
+    /// Checks whether the bit at *index* of this word is 1. The bit at index 0
+    /// is the least significant bit.
+    fn get_bit(self, index: u8) -> bool;
+
+    /// Returns a copy of this word where only the *amount* least significant
+    /// bits are kept, and all other bits are set to 0. *amount* must be smaller
+    /// than *BITS*.
+    fn low_bits(self, amount: u8) -> Self;
+
+    /// Returns a copy of this word where the order of the bits is reversed, so
+    /// the least significant bit becomes the most significant bit
+    fn reverse_bits(self) -> Self;
+
+    /// Returns a copy of this word where the order of the bytes is reversed
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_unsigned_word {
+    ($word:ty) => {
+        impl UnsignedWord for $word {
+            const BITS: u8 = (std::mem::size_of::<$word>() * 8) as u8;
+
+            const ZERO: Self = 0;
+
+            fn with_bit(self, index: u8) -> Self {
+                self | (1 << index)
+            }
+
+            fn get_bit(self, index: u8) -> bool {
+                self & (1 << index) != 0
+            }
+
+            fn low_bits(self, amount: u8) -> Self {
+                self & ((1 << amount) - 1)
+            }
+
+            fn reverse_bits(self) -> Self {
+                <$word>::reverse_bits(self)
+            }
+
+            fn swap_bytes(self) -> Self {
+                <$word>::swap_bytes(self)
+            }
+        }
+    };
+}
+
+impl_unsigned_word!(u8);
+impl_unsigned_word!(u16);
+impl_unsigned_word!(u32);
+impl_unsigned_word!(u64);
+impl_unsigned_word!(usize);
+
+/// An implementation of *BitSink* that uses a *Vec* of unsigned integers (of
+/// type *W*) to store the bools written into it. Every word will represent
+/// *W::BITS* bools (except for the last one if the number of bools is not a
+/// multiple of *W::BITS*). The first bool is stored in the least significant
+/// bit of the first word.
+///
+/// *U8VecBitSink* and *U32VecBitSink* are simply aliases for this type with
+/// *u8* and *u32* words. Users that need a different word size (for instance
+/// to pass the words to an API that expects *u64*s) can choose it here.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = WordVecBitSink::<u16>::new();
+/// sink.write(&[true, false, true]).unwrap();
+///
+/// assert_eq!(&vec![0b101], sink.get_words());
+/// assert_eq!(vec![true, false, true], sink.get_bools());
+/// ```
+pub struct WordVecBitSink<W: UnsignedWord> {
+    words: Vec<W>,
+    bit_index: u8,
+}
+
+impl<W: UnsignedWord> WordVecBitSink<W> {
+    /// Constructs a new and empty instance of *WordVecBitSink*
+    pub fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            bit_index: 0,
+        }
+    }
+
+    /// Constructs a new and empty instance of *WordVecBitSink* with an initial
+    /// capacity of *initial_capacity* words.
+    pub fn with_capacity(initial_capacity: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(initial_capacity),
+            bit_index: 0,
+        }
+    }
+
+    /// Constructs a *WordVecBitSink* that continues writing after the bits in
+    /// *words*, of which only the *num_used_bits* least significant bits of the
+    /// last word are in use (the other bits of the last word will be cleared).
+    /// This is the inverse of *into_words*.
+    ///
+    /// # Panics
+    /// This function panics when *num_used_bits* is larger than *W::BITS*, or
+    /// when *num_used_bits* is 0 while *words* is not empty (or the other way
+    /// around).
+    pub fn from_words(mut words: Vec<W>, num_used_bits: u8) -> Self {
+        assert!(num_used_bits <= W::BITS, "Too many used bits");
+        assert_eq!(
+            words.is_empty(),
+            num_used_bits == 0,
+            "num_used_bits must be 0 if and only if words is empty"
+        );
+
+        let bit_index = num_used_bits % W::BITS;
+        if bit_index != 0 {
+            let last_index = words.len() - 1;
+            words[last_index] = words[last_index].low_bits(bit_index);
+        }
+        Self { words, bit_index }
+    }
+
+    /// Gets a reference to the *Vec* storing the words written into this sink
+    pub fn get_words(&self) -> &Vec<W> {
+        &self.words
+    }
+
+    /// Consumes this sink and returns its words, together with the number of
+    /// bits that are used in the last word (between 1 and *W::BITS*, or 0 when
+    /// no bits were written). The unused bits of the last word are always 0.
+    pub fn into_words(self) -> (Vec<W>, u8) {
+        let num_used_bits = match (self.words.is_empty(), self.bit_index) {
+            (true, _) => 0,
+            (false, 0) => W::BITS,
+            (false, bit_index) => bit_index,
+        };
+        (self.words, num_used_bits)
+    }
+
+    /// Creates a *Vec* of bools that shows exactly which bools were written into
+    /// this sink in which order: The first bool of the *Vec* will be the first
+    /// bool that was written into this sink.
+    pub fn get_bools(&self) -> Vec<bool> {
+        let mut bools = Vec::with_capacity(self.get_num_bools() as usize);
+        let num_full_words = match self.bit_index == 0 {
+            true => self.words.len(),
+            false => self.words.len() - 1,
+        };
+        for word in &self.words[0..num_full_words] {
+            for bit_index in 0..W::BITS {
+                bools.push(word.get_bit(bit_index));
+            }
+        }
+
+        if self.bit_index != 0 {
+            let last_word = self.words[self.words.len() - 1];
+            for bit_index in 0..self.bit_index {
+                bools.push(last_word.get_bit(bit_index));
+            }
+        }
+        bools
+    }
+
+    /// Gets an iterator over the bools that were written into this sink (in
+    /// the same order as *get_bools*), without collecting them into a *Vec*
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let mut sink = U32VecBitSink::new();
+    /// sink.write(&[true, false, true]).unwrap();
+    /// assert_eq!(2, sink.iter_bits().filter(|bit| *bit).count());
+    /// ```
+    pub fn iter_bits(&self) -> WordBitIter<W, &[W]> {
+        WordBitIter::new(&self.words, self.get_num_bools())
+    }
+
+    /// Removes all bits from this sink, but keeps the capacity of its *Vec*.
+    /// This allows a single sink to be reused for many messages without
+    /// allocating new memory each time.
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.bit_index = 0;
+    }
+
+    /// Removes all bits after the first *num_bits* bits from this sink, but
+    /// keeps the capacity of its *Vec*. This does nothing if this sink doesn't
+    /// contain more than *num_bits* bits.
+    ///
+    /// # Example
+    /// ```
+    /// use bit_encoding::*;
+    ///
+    /// let mut sink = U8VecBitSink::new();
+    /// sink.write(&[true; 12]).unwrap();
+    /// sink.truncate_bits(3);
+    /// sink.write(&[false, true]).unwrap();
+    ///
+    /// assert_eq!(vec![true, true, true, false, true], sink.get_bools());
+    /// ```
+    pub fn truncate_bits(&mut self, num_bits: u64) {
+        if num_bits >= self.get_num_bools() {
+            return;
+        }
+
+        let word_size = W::BITS as u64;
+        let num_words = num_bits.div_ceil(word_size);
+        self.words.truncate(num_words as usize);
+        self.bit_index = (num_bits % word_size) as u8;
+
+        // The bits after the new end must be 0 because write() adds bits with OR
+        if self.bit_index != 0 {
+            let last_index = self.words.len() - 1;
+            self.words[last_index] = self.words[last_index].low_bits(self.bit_index);
+        }
+    }
+
+    /// Reserves capacity for at least *num_bits* more bits, so that they can
+    /// be written into this sink without reallocating.
+    pub fn reserve_bits(&mut self, num_bits: u64) {
+        let word_size = W::BITS as u64;
+        self.words.reserve(num_bits.div_ceil(word_size) as usize);
+    }
+}
+
+impl<W: UnsignedWord> IntoIterator for WordVecBitSink<W> {
+    type Item = bool;
+    type IntoIter = WordBitIter<W, Vec<W>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let num_bits = self.get_num_bools();
+        WordBitIter::new(self.words, num_bits)
+    }
+}
+
+impl<'a, W: UnsignedWord> IntoIterator for &'a WordVecBitSink<W> {
+    type Item = bool;
+    type IntoIter = WordBitIter<W, &'a [W]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_bits()
+    }
+}
+
+/// An iterator over the bits that are stored in a sequence of words of type
+/// *W*, starting with the least significant bit of the first word. This is
+/// returned by *iter_bits* of *WordVecBitSink* and *ArrayBitSink*, and by their
+/// *IntoIterator* implementations (in which case *V* owns the words).
+#[derive(Debug, Clone)]
+pub struct WordBitIter<W: UnsignedWord, V: Borrow<[W]>> {
+    words: V,
+    position: u64,
+    num_bits: u64,
+    phantom: PhantomData<W>,
+}
+
+impl<W: UnsignedWord, V: Borrow<[W]>> WordBitIter<W, V> {
+    pub(crate) fn new(words: V, num_bits: u64) -> Self {
+        Self {
+            words,
+            position: 0,
+            num_bits,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<W: UnsignedWord, V: Borrow<[W]>> Iterator for WordBitIter<W, V> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.position == self.num_bits {
+            return None;
+        }
+        let word_size = W::BITS as u64;
+        let word = self.words.borrow()[(self.position / word_size) as usize];
+        let bit = word.get_bit((self.position % word_size) as u8);
+        self.position += 1;
+        Some(bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.num_bits - self.position) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<W: UnsignedWord, V: Borrow<[W]>> ExactSizeIterator for WordBitIter<W, V> {}
+
+impl<W: UnsignedWord> Default for WordVecBitSink<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: UnsignedWord> BitSink for WordVecBitSink<W> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        // This may not be exact, but should be very accurate
+        self.words.reserve(bits.len() / W::BITS as usize);
+
+        // If we ended with a partial word previously, we should continue with it
+        let mut current_word = match self.bit_index == 0 {
+            true => W::ZERO,
+            false => self.words.pop().unwrap(),
+        };
+
+        // Add all bits...
+        for bit in bits {
+            if *bit {
+                current_word = current_word.with_bit(self.bit_index);
+            }
+            self.bit_index += 1;
+            if self.bit_index == W::BITS {
+                self.words.push(current_word);
+                self.bit_index = 0;
+                current_word = W::ZERO;
+            }
+        }
+
+        // If we end with a partial word, we should remember it
+        if self.bit_index != 0 {
+            self.words.push(current_word);
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.words.shrink_to_fit();
+        Ok(())
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        if self.bit_index == 0 {
+            self.words.len() as u64 * W::BITS as u64
+        } else {
+            (self.words.len() as u64 - 1) * W::BITS as u64 + self.bit_index as u64
+        }
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        Some(self.words.capacity() as u64 * W::BITS as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn test_word_size<W: UnsignedWord>() {
+        let mut bits = Vec::new();
+        for index in 0..300 {
+            bits.push(index % 3 == 0 || index % 7 == 0);
+        }
+
+        let mut sink = WordVecBitSink::<W>::new();
+        let mut expected_length = 0;
+        for chunk in bits.chunks(13) {
+            sink.write(chunk).unwrap();
+            expected_length += chunk.len();
+            assert_eq!(expected_length as u64, sink.get_num_bools());
+            assert_eq!(&bits[0..expected_length], &sink.get_bools()[..]);
+        }
+        sink.finish().unwrap();
+
+        let num_words = bits.len().div_ceil(W::BITS as usize);
+        assert_eq!(num_words, sink.get_words().len());
+
+        assert_eq!(bits.len(), sink.iter_bits().len());
+        assert_eq!(bits, sink.iter_bits().collect::<Vec<_>>());
+        assert_eq!(bits, (&sink).into_iter().collect::<Vec<_>>());
+        assert_eq!(bits, sink.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_word_sizes() {
+        test_word_size::<u8>();
+        test_word_size::<u16>();
+        test_word_size::<u32>();
+        test_word_size::<u64>();
+        test_word_size::<usize>();
+    }
+
+    #[test]
+    fn test_bit_order() {
+        let mut sink = WordVecBitSink::<u64>::new();
+        sink.write(&[true, false, false, true]).unwrap();
+        sink.write(&[false; 60]).unwrap();
+        sink.write(&[true]).unwrap();
+        assert_eq!(&vec![0b1001, 1], sink.get_words());
+    }
+
+    #[test]
+    fn test_reuse() {
+        let mut sink = WordVecBitSink::<u32>::with_capacity(10);
+        sink.write(&[true; 100]).unwrap();
+        let capacity = sink.get_words().capacity();
+
+        sink.clear();
+        assert_eq!(0, sink.get_num_bools());
+        assert_eq!(capacity, sink.get_words().capacity());
+        sink.write(&[false, true]).unwrap();
+        assert_eq!(vec![false, true], sink.get_bools());
+
+        sink.reserve_bits(1000);
+        // 1 word is already in use, and 1000 bits need 32 more words
+        assert!(sink.get_words().capacity() >= 33);
+    }
+
+    #[test]
+    fn test_truncate_bits() {
+        let mut sink = WordVecBitSink::<u16>::new();
+        sink.write(&[true; 40]).unwrap();
+
+        sink.truncate_bits(50);
+        assert_eq!(40, sink.get_num_bools());
+
+        sink.truncate_bits(32);
+        assert_eq!(vec![true; 32], sink.get_bools());
+        assert_eq!(2, sink.get_words().len());
+
+        sink.truncate_bits(19);
+        assert_eq!(vec![true; 19], sink.get_bools());
+        sink.write(&[false; 5]).unwrap();
+        assert_eq!(&vec![u16::MAX, 0b111], sink.get_words());
+
+        sink.truncate_bits(0);
+        assert!(sink.get_words().is_empty());
+    }
+
+    #[test]
+    fn test_words_round_trip() {
+        let mut sink = WordVecBitSink::<u8>::new();
+        assert_eq!((Vec::new(), 0), sink.into_words());
+
+        sink = WordVecBitSink::from_words(Vec::new(), 0);
+        sink.write(&[true; 16]).unwrap();
+        assert_eq!((vec![255, 255], 8), sink.into_words());
+
+        sink = WordVecBitSink::from_words(vec![255, 255], 3);
+        assert_eq!(11, sink.get_num_bools());
+        sink.write(&[false, true]).unwrap();
+        assert_eq!((vec![255, 0b10111], 5), sink.into_words());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_words_without_used_bits() {
+        WordVecBitSink::<u32>::from_words(vec![1], 0);
+    }
+
+    #[test]
+    fn test_empty() {
+        let sink = WordVecBitSink::<u32>::new();
+        assert_eq!(0, sink.get_num_bools());
+        assert!(sink.get_bools().is_empty());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::*;
+
+use std::collections::BTreeMap;
+
+/// The state of an entity that can be replicated with *write_snapshot_delta* and
+/// *apply_snapshot_delta*. The state consists of *NUM_FIELDS* fields that can be
+/// compared, encoded and decoded separately, so that only the fields that
+/// changed need to be sent.
+///
+/// Newly created entities start with their *Default* state, after which all
+/// their fields are decoded.
+pub trait EntityState: Default {
+    /// The number of fields of this state. The fields are identified by their
+    /// index, which is smaller than *NUM_FIELDS*.
+    const NUM_FIELDS: usize;
+
+    /// Checks whether the field with the given index is equal in *self* and
+    /// *other*
+    fn field_equals(&self, other: &Self, field: usize) -> bool;
+
+    /// Encodes the field with the given index and writes it to *sink*
+    fn write_field(
+        &self,
+        field: usize,
+        encoder: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError>;
+
+    /// Reads the field with the given index from *source*, and stores it in
+    /// *self*
+    fn read_field(
+        &mut self,
+        field: usize,
+        decoder: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+    ) -> Result<(), DecodeError>;
+}
+
+/// A snapshot of a set of entities: a map from entity id to entity state
+pub type Snapshot<S> = BTreeMap<u64, S>;
+
+fn write_ids<'a>(
+    encoder: &impl IntEncodingProtocol,
+    sink: &mut impl BitSink,
+    ids: impl Iterator<Item = &'a u64>,
+) -> Result<(), WriteError> {
+    let ids: Vec<u64> = ids.copied().collect();
+    write_sorted_set(encoder, sink, &ids)
+}
+
+/// Writes the differences between *previous* and *current* to *sink*, so that
+/// the receiving side (which has *previous*) can turn it into *current* using
+/// *apply_snapshot_delta*. The delta consists of:
+///
+/// 1. the ids of the removed entities
+/// 2. the ids of the created entities, followed by all fields of their states
+/// 3. the ids of the updated entities, followed by (for each of them) a mask
+///    of *NUM_FIELDS* bits telling which fields changed, and those fields
+///
+/// The id sets are encoded with *write_sorted_set*, so nearby ids are cheap.
+/// Entities that didn't change at all cost nothing.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// #[derive(Default, Clone, PartialEq, Debug)]
+/// struct Player {
+///     x: i32,
+///     y: i32,
+///     health: u8,
+/// }
+///
+/// impl EntityState for Player {
+///     const NUM_FIELDS: usize = 3;
+///
+///     fn field_equals(&self, other: &Self, field: usize) -> bool {
+///         match field {
+///             0 => self.x == other.x,
+///             1 => self.y == other.y,
+///             _ => self.health == other.health,
+///         }
+///     }
+///
+///     fn write_field(
+///         &self, field: usize, encoder: &impl IntEncodingProtocol, sink: &mut impl BitSink
+///     ) -> Result<(), WriteError> {
+///         match field {
+///             0 => encoder.write_i32(sink, self.x),
+///             1 => encoder.write_i32(sink, self.y),
+///             _ => encoder.write_u8(sink, self.health),
+///         }
+///     }
+///
+///     fn read_field(
+///         &mut self, field: usize, decoder: &impl IntDecodingProtocol, source: &mut impl BitSource
+///     ) -> Result<(), DecodeError> {
+///         match field {
+///             0 => self.x = decoder.read_i32(source)?,
+///             1 => self.y = decoder.read_i32(source)?,
+///             _ => self.health = decoder.read_u8(source)?,
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let previous = Snapshot::new();
+/// let mut current = Snapshot::new();
+/// current.insert(7, Player { x: 10, y: -3, health: 100 });
+///
+/// let mut sink = BoolVecBitSink::new();
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// write_snapshot_delta(&encoder, &mut sink, &previous, &current).unwrap();
+///
+/// let mut receiver = previous.clone();
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// apply_snapshot_delta(&decoder, &mut source, &mut context, &mut receiver).unwrap();
+/// assert_eq!(current, receiver);
+/// ```
+pub fn write_snapshot_delta<S: EntityState>(
+    encoder: &impl IntEncodingProtocol,
+    sink: &mut impl BitSink,
+    previous: &Snapshot<S>,
+    current: &Snapshot<S>,
+) -> Result<(), WriteError> {
+    write_delta(encoder, encoder, sink, previous, current)
+}
+
+/// Like *write_snapshot_delta*, but encodes the id sets with the ids protocol
+/// of *protocols*, and the fields with its payload protocol. The delta must be
+/// applied with *apply_snapshot_delta_with_protocols*.
+pub fn write_snapshot_delta_with_protocols<
+    S: EntityState,
+    L,
+    I: IntEncodingProtocol,
+    T,
+    P: IntEncodingProtocol,
+>(
+    protocols: &ProtocolSet<L, I, T, P>,
+    sink: &mut impl BitSink,
+    previous: &Snapshot<S>,
+    current: &Snapshot<S>,
+) -> Result<(), WriteError> {
+    write_delta(
+        protocols.get_ids(),
+        protocols.get_payload(),
+        sink,
+        previous,
+        current,
+    )
+}
+
+fn write_delta<S: EntityState>(
+    id_encoder: &impl IntEncodingProtocol,
+    encoder: &impl IntEncodingProtocol,
+    sink: &mut impl BitSink,
+    previous: &Snapshot<S>,
+    current: &Snapshot<S>,
+) -> Result<(), WriteError> {
+    let removed = previous.keys().filter(|id| !current.contains_key(id));
+    write_ids(id_encoder, sink, removed)?;
+
+    let created: Vec<(&u64, &S)> = current
+        .iter()
+        .filter(|(id, _)| !previous.contains_key(id))
+        .collect();
+    write_ids(id_encoder, sink, created.iter().map(|(id, _)| *id))?;
+    for (_, state) in &created {
+        for field in 0..S::NUM_FIELDS {
+            state.write_field(field, encoder, sink)?;
+        }
+    }
+
+    let mut updated = Vec::new();
+    for (id, state) in current {
+        if let Some(old_state) = previous.get(id) {
+            let mask: Vec<bool> = (0..S::NUM_FIELDS)
+                .map(|field| !state.field_equals(old_state, field))
+                .collect();
+            if mask.contains(&true) {
+                updated.push((id, state, mask));
+            }
+        }
+    }
+    write_ids(id_encoder, sink, updated.iter().map(|(id, _, _)| *id))?;
+    for (_, state, mask) in &updated {
+        sink.write(mask)?;
+        for (field, changed) in mask.iter().enumerate() {
+            if *changed {
+                state.write_field(field, encoder, sink)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn delta_error(message: String) -> DecodeError {
+    DecodeError::Other(message.into())
+}
+
+/// Reads a delta that was written by *write_snapshot_delta* from *source*, and
+/// applies it to *snapshot*. Returns a *DecodeError::Other* if the delta
+/// doesn't match *snapshot* (for instance when it updates an entity that
+/// doesn't exist), which means that both sides are out of sync. In that case,
+/// *snapshot* may have been partially updated.
+pub fn apply_snapshot_delta<S: EntityState>(
+    decoder: &impl IntDecodingProtocol,
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+    snapshot: &mut Snapshot<S>,
+) -> Result<(), DecodeError> {
+    apply_delta(decoder, decoder, source, context, snapshot)
+}
+
+/// Reads a delta that was written by *write_snapshot_delta_with_protocols*
+/// from *source*, and applies it to *snapshot*. See *apply_snapshot_delta*
+/// for the possible errors.
+pub fn apply_snapshot_delta_with_protocols<
+    S: EntityState,
+    L,
+    I: IntDecodingProtocol,
+    T,
+    P: IntDecodingProtocol,
+>(
+    protocols: &ProtocolSet<L, I, T, P>,
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+    snapshot: &mut Snapshot<S>,
+) -> Result<(), DecodeError> {
+    apply_delta(
+        protocols.get_ids(),
+        protocols.get_payload(),
+        source,
+        context,
+        snapshot,
+    )
+}
+
+fn apply_delta<S: EntityState>(
+    id_decoder: &impl IntDecodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+    snapshot: &mut Snapshot<S>,
+) -> Result<(), DecodeError> {
+    for id in read_sorted_set(id_decoder, source, context)? {
+        if snapshot.remove(&id).is_none() {
+            return Err(delta_error(format!("Can't remove missing entity {}", id)));
+        }
+    }
+
+    for id in read_sorted_set(id_decoder, source, context)? {
+        if snapshot.contains_key(&id) {
+            return Err(delta_error(format!("Entity {} already exists", id)));
+        }
+        let mut state = S::default();
+        for field in 0..S::NUM_FIELDS {
+            state.read_field(field, decoder, source)?;
+        }
+        snapshot.insert(id, state);
+    }
+
+    let mut mask = vec![false; S::NUM_FIELDS];
+    for id in read_sorted_set(id_decoder, source, context)? {
+        let state = match snapshot.get_mut(&id) {
+            Some(state) => state,
+            None => return Err(delta_error(format!("Can't update missing entity {}", id))),
+        };
+        source.read(&mut mask)?;
+        for (field, changed) in mask.iter().enumerate() {
+            if *changed {
+                state.read_field(field, decoder, source)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[derive(Default, Clone, PartialEq, Debug)]
+    struct TestEntity {
+        fields: [u32; 4],
+    }
+
+    impl EntityState for TestEntity {
+        const NUM_FIELDS: usize = 4;
+
+        fn field_equals(&self, other: &Self, field: usize) -> bool {
+            self.fields[field] == other.fields[field]
+        }
+
+        fn write_field(
+            &self,
+            field: usize,
+            encoder: &impl IntEncodingProtocol,
+            sink: &mut impl BitSink,
+        ) -> Result<(), WriteError> {
+            encoder.write_u32(sink, self.fields[field])
+        }
+
+        fn read_field(
+            &mut self,
+            field: usize,
+            decoder: &impl IntDecodingProtocol,
+            source: &mut impl BitSource,
+        ) -> Result<(), DecodeError> {
+            self.fields[field] = decoder.read_u32(source)?;
+            Ok(())
+        }
+    }
+
+    fn entity(fields: [u32; 4]) -> TestEntity {
+        TestEntity { fields }
+    }
+
+    fn transmit(previous: &Snapshot<TestEntity>, current: &Snapshot<TestEntity>) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        write_snapshot_delta(
+            &DigitIntEncodingProtocol::v1(),
+            &mut sink,
+            previous,
+            current,
+        )
+        .unwrap();
+
+        let mut receiver = previous.clone();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        apply_snapshot_delta(
+            &DigitIntDecodingProtocol::v1(),
+            &mut source,
+            &mut context,
+            &mut receiver,
+        )
+        .unwrap();
+        assert_eq!(current, &receiver);
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_deltas() {
+        let empty = Snapshot::new();
+        let mut first = Snapshot::new();
+        for id in 0..20 {
+            first.insert(id * 3, entity([id as u32, 1000, 0, 5]));
+        }
+        transmit(&empty, &first);
+
+        let mut second = first.clone();
+        second.remove(&6);
+        second.remove(&57);
+        second.insert(100, entity([1, 2, 3, 4]));
+        second.get_mut(&9).unwrap().fields[1] = 1001;
+        second.get_mut(&12).unwrap().fields = [7; 4];
+        transmit(&first, &second);
+        transmit(&second, &empty);
+
+        // An unchanged snapshot should only need 3 empty id sets
+        let unchanged_size = transmit(&second, &second);
+        let mut sink = BoolVecBitSink::new();
+        write_sorted_set(&DigitIntEncodingProtocol::v1(), &mut sink, &[]).unwrap();
+        assert_eq!(3 * sink.get_num_bools(), unchanged_size);
+    }
+
+    #[test]
+    fn test_protocol_set() {
+        let mut previous = Snapshot::new();
+        for id in 0..10 {
+            previous.insert(1000 + id * 2, entity([id as u32, 1, 2, 3]));
+        }
+        let mut current = previous.clone();
+        current.remove(&1004);
+        current.insert(2000, entity([u32::MAX; 4]));
+        current.get_mut(&1010).unwrap().fields[3] = 12345;
+
+        let encoders = ProtocolSet::uniform(SimpleIntEncodingProtocol::new())
+            .with_ids(DigitIntEncodingProtocol::v1());
+        let mut sink = BoolVecBitSink::new();
+        write_snapshot_delta_with_protocols(&encoders, &mut sink, &previous, &current).unwrap();
+
+        let decoders = ProtocolSet::uniform(SimpleIntDecodingProtocol::new())
+            .with_ids(DigitIntDecodingProtocol::v1());
+        let mut receiver = previous.clone();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        apply_snapshot_delta_with_protocols(&decoders, &mut source, &mut context, &mut receiver)
+            .unwrap();
+        assert_eq!(current, receiver);
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_only_changed_fields() {
+        let mut previous = Snapshot::new();
+        previous.insert(1, entity([1_000_000; 4]));
+        let mut current = previous.clone();
+        current.get_mut(&1).unwrap().fields[2] = 5;
+
+        let full_size = transmit(&Snapshot::new(), &current);
+        let delta_size = transmit(&previous, &current);
+        assert!(delta_size * 2 < full_size);
+    }
+
+    #[test]
+    fn test_out_of_sync() {
+        let mut previous = Snapshot::new();
+        previous.insert(1, entity([1; 4]));
+        let mut current = previous.clone();
+        current.get_mut(&1).unwrap().fields[0] = 2;
+
+        let mut sink = BoolVecBitSink::new();
+        write_snapshot_delta(
+            &DigitIntEncodingProtocol::v1(),
+            &mut sink,
+            &previous,
+            &current,
+        )
+        .unwrap();
+
+        let mut receiver: Snapshot<TestEntity> = Snapshot::new();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert!(apply_snapshot_delta(
+            &DigitIntDecodingProtocol::v1(),
+            &mut source,
+            &mut context,
+            &mut receiver
+        )
+        .is_err());
+    }
+}
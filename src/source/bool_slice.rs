@@ -26,6 +26,7 @@ use crate::*;
 /// entire byte to store 1 *bool*.
 pub struct BoolSliceBitSource<'a> {
     slice: &'a [bool],
+    position: usize,
 }
 
 impl<'a> BoolSliceBitSource<'a> {
@@ -33,31 +34,71 @@ impl<'a> BoolSliceBitSource<'a> {
     /// values from the given slice of *bool*s. See the documentation of this
     /// struct for an example.
     pub fn new(slice: &'a [bool]) -> Self {
-        Self { slice }
+        Self { slice, position: 0 }
     }
 }
 
 impl<'a> BitSource for BoolSliceBitSource<'a> {
     fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
-        if dest.len() > self.slice.len() {
-            for index in 0..self.slice.len() {
-                dest[index] = self.slice[index];
-            }
-
-            let old_length = self.slice.len();
-            self.slice = &[];
+        let remaining = &self.slice[self.position..];
+        if dest.len() > remaining.len() {
+            dest[0..remaining.len()].copy_from_slice(remaining);
+            self.position = self.slice.len();
 
             return Err(ReadError::ReachedEnd {
-                read_bools: old_length,
+                read_bools: remaining.len(),
             });
         }
 
-        for index in 0..dest.len() {
-            dest[index] = self.slice[index];
+        dest.copy_from_slice(&remaining[0..dest.len()]);
+        self.position += dest.len();
+
+        Ok(())
+    }
+}
+
+impl<'a> SeekableBitSource for BoolSliceBitSource<'a> {
+    fn seek_bits(&mut self, position: u64) -> Result<(), ReadError> {
+        if position > self.slice.len() as u64 {
+            return Err(ReadError::ReachedEnd { read_bools: 0 });
         }
+        self.position = position as usize;
+        Ok(())
+    }
 
-        self.slice = &self.slice[dest.len()..self.slice.len()];
+    fn position(&self) -> u64 {
+        self.position as u64
+    }
+}
 
+/// Reads the bits from the start of the slice, and advances the slice past the
+/// bits that were read (like *std::io::Read* does for *&[u8]*). This allows
+/// quick scripts to decode from a plain slice without constructing a
+/// *BoolSliceBitSource*:
+///
+/// ```
+/// use bit_encoding::*;
+///
+/// let bits = vec![true, false, true, true, false];
+/// let mut source = &bits[..];
+/// assert_eq!(1, DigitIntDecodingProtocol::new(2, false).read_u8(&mut source).unwrap());
+/// assert_eq!(&[false], source);
+/// ```
+///
+/// Note that there is no such implementation for *&[u8]*, since a byte slice
+/// can't keep track of a position in the middle of a byte. Use
+/// *U8SliceBitSource* for that.
+impl BitSource for &[bool] {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        if dest.len() > self.len() {
+            let read_bools = self.len();
+            dest[0..read_bools].copy_from_slice(self);
+            *self = &[];
+            return Err(ReadError::ReachedEnd { read_bools });
+        }
+
+        dest.copy_from_slice(&self[0..dest.len()]);
+        *self = &self[dest.len()..];
         Ok(())
     }
 }
@@ -80,6 +121,18 @@ mod tests {
         });
     }
 
+    #[test]
+    fn seek_tests() {
+        super::test_helper::seek_tests(&|slice| {
+            let mut as_vec = vec![false; slice.len()];
+            as_vec.copy_from_slice(slice);
+
+            // Not so neat, but it's only a unit test anyway
+            let leaked_vec = Box::leak(Box::new(as_vec));
+            BoolSliceBitSource::new(leaked_vec)
+        });
+    }
+
     #[test]
     fn random_tests() {
         super::test_helper::random_tests(&|slice| {
@@ -92,4 +145,16 @@ mod tests {
             source
         });
     }
+
+    #[test]
+    fn slice_tests() {
+        super::test_helper::basic_tests(&|slice| {
+            let leaked_vec: &'static [bool] = Box::leak(Box::new(slice.to_vec()));
+            leaked_vec
+        });
+        super::test_helper::random_tests(&|slice| {
+            let leaked_vec: &'static [bool] = Box::leak(Box::new(slice.to_vec()));
+            leaked_vec
+        });
+    }
 }
\ No newline at end of file
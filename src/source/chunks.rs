@@ -0,0 +1,153 @@
+use crate::*;
+
+/// An iterator that reads chunks of a fixed number of bits from a *BitSource*,
+/// and returns each chunk as a *u64*. This is created by
+/// *BitSource::read_chunks*.
+///
+/// The first bit of each chunk is stored in the least significant bit of the
+/// *u64*, which is the same order that *SimpleIntEncodingProtocol* uses. The
+/// iteration stops when the source reaches its end. If the source ends in the
+/// middle of a chunk, the bits of that incomplete chunk can be obtained with
+/// *get_remainder*. When the source returns any other error, that error is
+/// returned once, after which the iteration stops.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let bytes = [0x5A, 0xC3];
+/// let mut source = U8SliceBitSource::new(&bytes, 2);
+/// let mut nibbles = source.read_chunks(4);
+/// let values: Vec<u64> = nibbles.by_ref().map(|nibble| nibble.unwrap()).collect();
+/// assert_eq!(vec![0xA, 0x5, 0x3], values);
+///
+/// // The last 2 bits don't form a complete nibble
+/// assert_eq!(Some((0b00, 2)), nibbles.get_remainder());
+/// ```
+pub struct BitChunks<'a, S: BitSource + ?Sized> {
+    source: &'a mut S,
+    buffer: Vec<bool>,
+    remainder: Option<(u64, u8)>,
+    is_done: bool,
+}
+
+impl<'a, S: BitSource + ?Sized> BitChunks<'a, S> {
+    /// Constructs a new *BitChunks* that reads chunks of *num_bits* bits from
+    /// *source*.
+    ///
+    /// Panics if *num_bits* is 0 or larger than 64.
+    pub fn new(source: &'a mut S, num_bits: u8) -> Self {
+        assert!(
+            num_bits > 0 && num_bits <= 64,
+            "The number of bits per chunk must be between 1 and 64, but got {}",
+            num_bits
+        );
+        Self {
+            source,
+            buffer: vec![false; num_bits as usize],
+            remainder: None,
+            is_done: false,
+        }
+    }
+
+    /// Gets the number of bits per chunk
+    pub fn get_num_bits(&self) -> u8 {
+        self.buffer.len() as u8
+    }
+
+    /// Gets the bits of the incomplete chunk at the end of the source, and the
+    /// number of bits in it. This returns *None* until the iteration has
+    /// reached the end of the source, and it also returns *None* if the source
+    /// ended right after a complete chunk.
+    pub fn get_remainder(&self) -> Option<(u64, u8)> {
+        self.remainder
+    }
+
+    fn pack(bits: &[bool]) -> u64 {
+        let mut chunk = 0;
+        for (index, bit) in bits.iter().enumerate() {
+            if *bit {
+                chunk |= 1 << index;
+            }
+        }
+        chunk
+    }
+}
+
+impl<'a, S: BitSource + ?Sized> Iterator for BitChunks<'a, S> {
+    type Item = Result<u64, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        match self.source.read(&mut self.buffer) {
+            Ok(()) => Some(Ok(Self::pack(&self.buffer))),
+            Err(ReadError::ReachedEnd { read_bools }) => {
+                self.is_done = true;
+                if read_bools > 0 {
+                    let partial = &self.buffer[0..read_bools];
+                    self.remainder = Some((Self::pack(partial), read_bools as u8));
+                }
+                None
+            }
+            Err(error) => {
+                self.is_done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::faults::*;
+    use crate::*;
+
+    #[test]
+    fn test_same_as_simple_protocol() {
+        let encoder = SimpleIntEncodingProtocol::new();
+        let mut sink = BoolVecBitSink::new();
+        let values = [0u64, 1, 12345, u64::MAX, 1 << 63, 987_654_321_012];
+        for value in &values {
+            encoder.write_u64(&mut sink, *value).unwrap();
+        }
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut chunks = source.read_chunks(64);
+        assert_eq!(64, chunks.get_num_bits());
+        let chunks: Vec<u64> = chunks.by_ref().map(|chunk| chunk.unwrap()).collect();
+        assert_eq!(&values[..], &chunks[..]);
+    }
+
+    #[test]
+    fn test_remainder() {
+        let bits = [true, false, true, false, true, true, true];
+        let mut source = BoolSliceBitSource::new(&bits);
+        let mut chunks = source.read_chunks(3);
+        assert_eq!(Some(0b101), chunks.next().map(|chunk| chunk.unwrap()));
+        assert_eq!(None, chunks.get_remainder());
+        assert_eq!(Some(0b110), chunks.next().map(|chunk| chunk.unwrap()));
+        assert!(chunks.next().is_none());
+        assert_eq!(Some((0b1, 1)), chunks.get_remainder());
+        assert!(chunks.next().is_none());
+
+        let mut source = BoolSliceBitSource::new(&bits[0..6]);
+        let mut chunks = source.read_chunks(3);
+        assert_eq!(2, chunks.by_ref().count());
+        assert_eq!(None, chunks.get_remainder());
+    }
+
+    #[test]
+    fn test_source_error() {
+        let bits = [true; 20];
+        let source = BoolSliceBitSource::new(&bits);
+        let mut failing = FailAfterBitSource::new(source, 10);
+        let results: Vec<_> = failing.read_chunks(4).collect();
+        assert_eq!(3, results.len());
+        assert_eq!(0b1111, *results[0].as_ref().unwrap());
+        assert_eq!(0b1111, *results[1].as_ref().unwrap());
+        assert!(results[2].is_err());
+    }
+}
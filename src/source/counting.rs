@@ -0,0 +1,68 @@
+use crate::*;
+
+/// A *BitSource* that borrows another *BitSource* and counts the number of bits
+/// that are read from it. Unlike *BitSink*s, *BitSource*s don't keep track of
+/// the number of bits that have been read, so this wrapper can be used when
+/// that number is needed (for instance by *IntDecodingProtocol::read_counted*).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let bits = [true, false, true, true];
+/// let mut source = BoolSliceBitSource::new(&bits);
+/// let mut counting_source = CountingBitSource::new(&mut source);
+/// counting_source.read(&mut [false; 3]).unwrap();
+/// assert_eq!(3, counting_source.get_num_bits());
+/// ```
+pub struct CountingBitSource<'a, S: BitSource> {
+    source: &'a mut S,
+    num_bits: u64,
+}
+
+impl<'a, S: BitSource> CountingBitSource<'a, S> {
+    /// Constructs a new *CountingBitSource* that reads from *source*
+    pub fn new(source: &'a mut S) -> Self {
+        Self {
+            source,
+            num_bits: 0,
+        }
+    }
+
+    /// Gets the number of bits that have been read through this source. When
+    /// a read fails with *ReadError::ReachedEnd*, the bits that were read
+    /// before the end was reached are counted as well.
+    pub fn get_num_bits(&self) -> u64 {
+        self.num_bits
+    }
+}
+
+impl<'a, S: BitSource> BitSource for CountingBitSource<'a, S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let result = self.source.read(dest);
+        self.num_bits += match &result {
+            Ok(()) => dest.len(),
+            Err(ReadError::ReachedEnd { read_bools }) => *read_bools,
+            Err(ReadError::Other(_)) => 0,
+        } as u64;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_count() {
+        let bits = [true; 10];
+        let mut source = BoolSliceBitSource::new(&bits);
+        let mut counting_source = CountingBitSource::new(&mut source);
+        counting_source.read(&mut [false; 4]).unwrap();
+        counting_source.read(&mut [false; 5]).unwrap();
+        assert_eq!(9, counting_source.get_num_bits());
+        assert!(counting_source.read(&mut [false; 5]).is_err());
+        assert_eq!(10, counting_source.get_num_bits());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::*;
+
+/// Uses a *BitIndex* to read records from a slice of bytes without decoding all
+/// records in front of it. See the documentation of *IndexedBitSink* for an
+/// example.
+pub struct IndexedBitReader<'a> {
+    bytes: &'a [u8],
+    num_padding_bits: u8,
+    index: &'a BitIndex,
+}
+
+impl<'a> IndexedBitReader<'a> {
+    /// Constructs a new *IndexedBitReader* that reads the records from *bytes*
+    /// (that has *num_padding_bits* padding bits, just like *U8SliceBitSource*)
+    /// and uses *index* to find the start of each record.
+    pub fn new(bytes: &'a [u8], num_padding_bits: u8, index: &'a BitIndex) -> Self {
+        Self {
+            bytes,
+            num_padding_bits,
+            index,
+        }
+    }
+
+    /// Gets the number of records in the index
+    pub fn num_records(&self) -> usize {
+        self.index.num_records()
+    }
+
+    /// Creates a *BitSource* that starts reading at the start of the record
+    /// with the given index. It can continue reading the next records after
+    /// that record. Returns *None* if the index doesn't have the record or if
+    /// its offset is outside the bytes.
+    pub fn record_source(&self, record: usize) -> Option<U8SliceBitSource<'a>> {
+        let mut source = U8SliceBitSource::new(self.bytes, self.num_padding_bits);
+        self.index.seek_to_record(&mut source, record).ok()?;
+        Some(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_record_sources() {
+        let encoder = DigitIntEncodingProtocol::new(4, false);
+        let decoder = DigitIntDecodingProtocol::new(4, false);
+        let mut sink = IndexedBitSink::new(U8VecBitSink::new());
+        for record in 0..500 {
+            sink.mark_record();
+            encoder.write_u16(&mut sink, record).unwrap();
+            encoder.write_i32(&mut sink, -(record as i32)).unwrap();
+        }
+
+        let (inner, index) = sink.into_parts();
+        let num_padding_bits = (8 - inner.get_num_bools() % 8) % 8;
+        let reader = IndexedBitReader::new(inner.get_bytes(), num_padding_bits as u8, &index);
+        assert_eq!(500, reader.num_records());
+
+        for record in (0..500).rev() {
+            let mut source = reader.record_source(record as usize).unwrap();
+            assert_eq!(record, decoder.read_u16(&mut source).unwrap());
+            assert_eq!(-(record as i32), decoder.read_i32(&mut source).unwrap());
+        }
+        assert!(reader.record_source(500).is_none());
+    }
+}
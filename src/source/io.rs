@@ -0,0 +1,135 @@
+use crate::*;
+
+use std::io::{ErrorKind, Read, Stdin};
+
+/// A *BitSource* that reads bytes from a *std::io::Read* (for instance a file or
+/// a *TcpStream*), and reads the bits of these bytes in the same order as
+/// *U8SliceBitSource*. Bytes are read from the reader when they are needed.
+///
+/// Since the reader only provides whole bytes, this source can't know how many
+/// padding bits are in the last byte, so it will simply return them as well.
+/// When the reader reaches its end, *read* will return *ReadError::ReachedEnd*.
+///
+/// This source reads in chunks of at most *IO_SOURCE_BUFFER_SIZE* bytes, so it
+/// may read more bytes from the reader than it needs.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let bytes: &[u8] = &[0b101];
+/// let mut source = IoBitSource::new(bytes);
+/// let mut dest = [false; 3];
+/// source.read(&mut dest).unwrap();
+/// assert_eq!([true, false, true], dest);
+/// ```
+pub struct IoBitSource<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    buffer_length: usize,
+    bit_position: usize,
+}
+
+/// The maximum number of bytes that *IoBitSource* reads from its reader at once
+pub const IO_SOURCE_BUFFER_SIZE: usize = 4096;
+
+impl<R: Read> IoBitSource<R> {
+    /// Constructs a new *IoBitSource* that reads its bytes from *reader*
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; IO_SOURCE_BUFFER_SIZE],
+            buffer_length: 0,
+            bit_position: 0,
+        }
+    }
+
+    /// Consumes this source and returns its reader. Bytes that were read from
+    /// the reader, but not (completely) read from this source, will be lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next chunk of bytes into the buffer. Returns false if the
+    /// reader reached its end.
+    fn fill_buffer(&mut self) -> Result<bool, ReadError> {
+        loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => return Ok(false),
+                Ok(length) => {
+                    self.buffer_length = length;
+                    self.bit_position = 0;
+                    return Ok(true);
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(ReadError::Other(Box::new(error))),
+            }
+        }
+    }
+}
+
+impl IoBitSource<Stdin> {
+    /// Constructs a new *IoBitSource* that reads its bytes from the standard
+    /// input, for instance to decode data that is piped from another process.
+    /// Like any *IoBitSource*, it reads the bytes in chunks, so it may read
+    /// more bytes from the standard input than it needs.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use bit_encoding::*;
+    ///
+    /// let mut source = IoBitSource::from_stdin();
+    /// let value = DigitIntDecodingProtocol::v1().read_u32(&mut source).unwrap();
+    /// println!("Received {}", value);
+    /// ```
+    pub fn from_stdin() -> Self {
+        Self::new(std::io::stdin())
+    }
+}
+
+impl<R: Read> BitSource for IoBitSource<R> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        for (index, dest_bit) in dest.iter_mut().enumerate() {
+            if self.bit_position == self.buffer_length * 8 && !self.fill_buffer()? {
+                return Err(ReadError::ReachedEnd { read_bools: index });
+            }
+            let byte = self.buffer[self.bit_position / 8];
+            *dest_bit = byte & (1 << (self.bit_position % 8)) != 0;
+            self.bit_position += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_read() {
+        let bytes: Vec<u8> = (0..10_000).map(|index| (index * 7) as u8).collect();
+        let expected = bytes_to_bools(&bytes);
+
+        let mut source = IoBitSource::new(&bytes[..]);
+        let mut actual = vec![false; expected.len()];
+        for chunk in actual.chunks_mut(77) {
+            source.read(chunk).unwrap();
+        }
+        assert_eq!(expected, actual);
+
+        match source.read(&mut [false; 5]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(0, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+
+    #[test]
+    fn test_partial_read() {
+        let mut source = IoBitSource::new(&[255u8][..]);
+        match source.read(&mut [false; 10]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(8, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+}
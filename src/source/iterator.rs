@@ -0,0 +1,157 @@
+use crate::*;
+
+/// A *BitSource* that reads its bits from an *Iterator* of *bool*s. This allows
+/// procedurally generated or lazily transformed bit streams to be decoded
+/// without collecting them into a *Vec* first. When the iterator returns
+/// *None*, *read* returns *ReadError::ReachedEnd*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // An endless stream of alternating bits
+/// let mut source = IteratorBitSource::new((0..).map(|index| index % 2 == 0));
+/// assert_eq!(0b0101_0101, SimpleIntDecodingProtocol::new().read_u8(&mut source).unwrap());
+/// assert_eq!(8, source.get_num_read_bits());
+/// ```
+#[derive(Debug, Clone)]
+pub struct IteratorBitSource<I: Iterator<Item = bool>> {
+    iterator: I,
+    num_read_bits: u64,
+}
+
+impl<I: Iterator<Item = bool>> IteratorBitSource<I> {
+    /// Constructs a new *IteratorBitSource* that reads its bits from *iterator*
+    pub fn new(iterator: I) -> Self {
+        Self {
+            iterator,
+            num_read_bits: 0,
+        }
+    }
+
+    /// Gets the number of bits that have been read from this source
+    pub fn get_num_read_bits(&self) -> u64 {
+        self.num_read_bits
+    }
+
+    /// Consumes this source and returns its iterator
+    pub fn into_inner(self) -> I {
+        self.iterator
+    }
+}
+
+impl<I: Iterator<Item = bool>> BitSource for IteratorBitSource<I> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        for (index, dest_bit) in dest.iter_mut().enumerate() {
+            match self.iterator.next() {
+                Some(bit) => *dest_bit = bit,
+                None => return Err(ReadError::ReachedEnd { read_bools: index }),
+            }
+            self.num_read_bits += 1;
+        }
+        Ok(())
+    }
+}
+
+/// A *BitSource* that reads its bits from an *Iterator* of bytes, in the same
+/// order as *U8SliceBitSource* (starting at the least significant bit of each
+/// byte). This is useful to decode bytes that are produced on the fly, for
+/// instance by a decryption closure. Like *IoBitSource*, this source can't
+/// know how many padding bits are in the last byte, so it will simply return
+/// them as well.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let mut sink = U8VecBitSink::new();
+/// encoder.write_u32(&mut sink, 12345).unwrap();
+/// let key = 0b1010_0110;
+/// let encrypted: Vec<u8> = sink.get_bytes().iter().map(|byte| byte ^ key).collect();
+///
+/// // Decrypt the bytes while decoding them
+/// let mut source = ByteIteratorBitSource::new(encrypted.iter().map(|byte| byte ^ key));
+/// assert_eq!(12345, DigitIntDecodingProtocol::v1().read_u32(&mut source).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ByteIteratorBitSource<I: Iterator<Item = u8>> {
+    iterator: I,
+    current_byte: u8,
+    bit_index: u8,
+}
+
+impl<I: Iterator<Item = u8>> ByteIteratorBitSource<I> {
+    /// Constructs a new *ByteIteratorBitSource* that reads its bytes from
+    /// *iterator*
+    pub fn new(iterator: I) -> Self {
+        Self {
+            iterator,
+            current_byte: 0,
+            bit_index: 8,
+        }
+    }
+
+    /// Consumes this source and returns its iterator. The remaining bits of
+    /// the current byte will be lost.
+    pub fn into_inner(self) -> I {
+        self.iterator
+    }
+}
+
+impl<I: Iterator<Item = u8>> BitSource for ByteIteratorBitSource<I> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        for (index, dest_bit) in dest.iter_mut().enumerate() {
+            if self.bit_index == 8 {
+                match self.iterator.next() {
+                    Some(byte) => self.current_byte = byte,
+                    None => return Err(ReadError::ReachedEnd { read_bools: index }),
+                }
+                self.bit_index = 0;
+            }
+            *dest_bit = self.current_byte & (1 << self.bit_index) != 0;
+            self.bit_index += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn basic_tests() {
+        super::test_helper::basic_tests(&|slice| {
+            let bits = slice.to_vec();
+            IteratorBitSource::new(bits.into_iter())
+        });
+    }
+
+    #[test]
+    fn random_tests() {
+        super::test_helper::random_tests(&|slice| {
+            let bits = slice.to_vec();
+            IteratorBitSource::new(bits.into_iter())
+        });
+    }
+
+    #[test]
+    fn test_bytes() {
+        let bytes: Vec<u8> = (0..100).map(|index| (index * 13) as u8).collect();
+        let expected = bytes_to_bools(&bytes);
+        let mut source = ByteIteratorBitSource::new(bytes.into_iter());
+        let mut actual = vec![false; expected.len()];
+        for chunk in actual.chunks_mut(11) {
+            source.read(chunk).unwrap();
+        }
+        assert_eq!(expected, actual);
+
+        let mut source = ByteIteratorBitSource::new(vec![255].into_iter());
+        match source.read(&mut [false; 10]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(8, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+}
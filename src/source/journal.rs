@@ -0,0 +1,347 @@
+use crate::*;
+
+use std::convert::TryFrom;
+
+/// The result of a single *read* call that was recorded in a *BitJournal*.
+/// Since implementation-specific errors can't be cloned, only their message is
+/// recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalOutcome {
+    /// The read call returned *Ok*
+    Ok,
+    /// The read call returned *ReadError::ReachedEnd*
+    ReachedEnd { read_bools: usize },
+    /// The read call returned *ReadError::Other* with the given message
+    Other(String),
+}
+
+/// A single *read* call that was recorded in a *BitJournal*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// The number of bits that were read from the source before this call
+    pub offset: u64,
+    /// The content of the destination slice after the call. Its length is the
+    /// number of bits that was requested.
+    pub bits: Vec<bool>,
+    /// The result of the call
+    pub outcome: JournalOutcome,
+}
+
+/// A log of all *read* calls on a *BitSource*, recorded by a
+/// *RecordingBitSource*. It can be stored with *write_to* and served back with
+/// a *ReplayBitSource*, which makes it possible to reproduce decoding problems
+/// that were observed on a stream that can't be read again.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// let mut sink = BoolVecBitSink::new();
+/// encoder.write_u32(&mut sink, 1234).unwrap();
+///
+/// // Record the reads while decoding
+/// let mut source = RecordingBitSource::new(BoolSliceBitSource::new(sink.get_bits()));
+/// assert_eq!(1234, decoder.read_u32(&mut source).unwrap());
+/// let journal = source.into_journal();
+///
+/// // And replay them later
+/// let mut replay = ReplayBitSource::new(&journal);
+/// assert_eq!(1234, decoder.read_u32(&mut replay).unwrap());
+/// assert!(replay.is_finished());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl BitJournal {
+    /// Constructs an empty *BitJournal*
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Gets all recorded entries, in the order in which they were recorded
+    pub fn get_entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Writes this journal to *sink*. All integers are encoded with *encoder*.
+    pub fn write_to(
+        &self,
+        sink: &mut impl BitSink,
+        encoder: &impl IntEncodingProtocol,
+    ) -> Result<(), WriteError> {
+        encoder.write_u64(sink, self.entries.len() as u64)?;
+        for entry in &self.entries {
+            encoder.write_u64(sink, entry.offset)?;
+            encoder.write_u64(sink, entry.bits.len() as u64)?;
+            sink.write(&entry.bits)?;
+            match &entry.outcome {
+                JournalOutcome::Ok => encoder.write_u8(sink, 0)?,
+                JournalOutcome::ReachedEnd { read_bools } => {
+                    encoder.write_u8(sink, 1)?;
+                    encoder.write_u64(sink, *read_bools as u64)?;
+                }
+                JournalOutcome::Other(message) => {
+                    encoder.write_u8(sink, 2)?;
+                    encoder.write_string(sink, message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a journal that was written by *write_to* from *source*. All
+    /// lengths are checked against the limits of *context*.
+    pub fn read_from(
+        source: &mut impl BitSource,
+        decoder: &impl IntDecodingProtocol,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError> {
+        let num_entries = context.check_vec_len::<JournalEntry>(decoder.read_u64(source)?)?;
+        let mut entries = Vec::with_capacity(num_entries);
+        for _counter in 0..num_entries {
+            let offset = decoder.read_u64(source)?;
+            let num_bits = context.check_vec_len::<bool>(decoder.read_u64(source)?)?;
+            let mut bits = vec![false; num_bits];
+            source.read(&mut bits)?;
+
+            let outcome = match decoder.read_u8(source)? {
+                0 => JournalOutcome::Ok,
+                1 => {
+                    let read_bools = decoder.read_u64(source)?;
+                    let read_bools =
+                        usize::try_from(read_bools).map_err(|_| DecodeError::IntOverflow)?;
+                    JournalOutcome::ReachedEnd { read_bools }
+                }
+                2 => JournalOutcome::Other(decoder.read_string(source, context)?),
+                invalid => {
+                    return Err(DecodeError::Other(
+                        format!("Invalid journal outcome {}", invalid).into(),
+                    ))
+                }
+            };
+            entries.push(JournalEntry {
+                offset,
+                bits,
+                outcome,
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+impl Default for BitJournal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A *BitSource* that wraps another *BitSource* and records every *read* call
+/// in a *BitJournal*. See the documentation of *BitJournal* for an example.
+pub struct RecordingBitSource<S: BitSource> {
+    source: S,
+    journal: BitJournal,
+    offset: u64,
+}
+
+impl<S: BitSource> RecordingBitSource<S> {
+    /// Constructs a new *RecordingBitSource* that reads from *source*
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            journal: BitJournal::new(),
+            offset: 0,
+        }
+    }
+
+    /// Gets the journal of all reads so far
+    pub fn get_journal(&self) -> &BitJournal {
+        &self.journal
+    }
+
+    /// Consumes this *RecordingBitSource* and returns its journal
+    pub fn into_journal(self) -> BitJournal {
+        self.journal
+    }
+
+    /// Splits this *RecordingBitSource* into the wrapped *BitSource* and the
+    /// journal
+    pub fn into_parts(self) -> (S, BitJournal) {
+        (self.source, self.journal)
+    }
+}
+
+impl<S: BitSource> BitSource for RecordingBitSource<S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let result = self.source.read(dest);
+        let offset = self.offset;
+        let outcome = match &result {
+            Ok(()) => {
+                self.offset += dest.len() as u64;
+                JournalOutcome::Ok
+            }
+            Err(ReadError::ReachedEnd { read_bools }) => {
+                self.offset += *read_bools as u64;
+                JournalOutcome::ReachedEnd {
+                    read_bools: *read_bools,
+                }
+            }
+            Err(ReadError::Other(error)) => JournalOutcome::Other(error.to_string()),
+        };
+        self.journal.entries.push(JournalEntry {
+            offset,
+            bits: dest.to_vec(),
+            outcome,
+        });
+        result
+    }
+}
+
+/// A *BitSource* that serves the reads of a *BitJournal* back, in the same
+/// order as they were recorded. Every *read* call must request exactly as many
+/// bits as the corresponding recorded call; otherwise it will return a
+/// *ReadError::Other* because the replay diverged from the recording.
+///
+/// When all entries have been replayed, all further reads will return
+/// *ReadError::ReachedEnd*.
+pub struct ReplayBitSource<'a> {
+    journal: &'a BitJournal,
+    next_entry: usize,
+}
+
+impl<'a> ReplayBitSource<'a> {
+    /// Constructs a new *ReplayBitSource* that starts at the first entry of
+    /// *journal*
+    pub fn new(journal: &'a BitJournal) -> Self {
+        Self {
+            journal,
+            next_entry: 0,
+        }
+    }
+
+    /// Gets the index of the journal entry that will be replayed next
+    pub fn get_next_entry(&self) -> usize {
+        self.next_entry
+    }
+
+    /// Checks whether all entries of the journal have been replayed
+    pub fn is_finished(&self) -> bool {
+        self.next_entry >= self.journal.entries.len()
+    }
+}
+
+impl<'a> BitSource for ReplayBitSource<'a> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let entry = match self.journal.entries.get(self.next_entry) {
+            Some(entry) => entry,
+            None => return Err(ReadError::ReachedEnd { read_bools: 0 }),
+        };
+        if entry.bits.len() != dest.len() {
+            return Err(ReadError::Other(
+                format!(
+                    "Replay diverged at entry {}: {} bits were recorded, but {} bits were requested",
+                    self.next_entry,
+                    entry.bits.len(),
+                    dest.len()
+                )
+                .into(),
+            ));
+        }
+
+        self.next_entry += 1;
+        dest.copy_from_slice(&entry.bits);
+        match &entry.outcome {
+            JournalOutcome::Ok => Ok(()),
+            JournalOutcome::ReachedEnd { read_bools } => Err(ReadError::ReachedEnd {
+                read_bools: *read_bools,
+            }),
+            JournalOutcome::Other(message) => Err(ReadError::Other(message.clone().into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let bits = [true, false, false, true, true];
+        let mut source = RecordingBitSource::new(BoolSliceBitSource::new(&bits));
+        let mut dest = [false; 2];
+        source.read(&mut dest).unwrap();
+        source.read(&mut dest).unwrap();
+        assert!(source.read(&mut dest).is_err());
+
+        let journal = source.into_journal();
+        let entries = journal.get_entries();
+        assert_eq!(3, entries.len());
+        assert_eq!(
+            JournalEntry {
+                offset: 2,
+                bits: vec![false, true],
+                outcome: JournalOutcome::Ok
+            },
+            entries[1]
+        );
+        assert_eq!(4, entries[2].offset);
+        assert_eq!(
+            JournalOutcome::ReachedEnd { read_bools: 1 },
+            entries[2].outcome
+        );
+
+        let mut replay = ReplayBitSource::new(&journal);
+        replay.read(&mut dest).unwrap();
+        assert_eq!([true, false], dest);
+        assert!(matches!(
+            replay.read(&mut [false; 3]),
+            Err(ReadError::Other(_))
+        ));
+        replay.read(&mut dest).unwrap();
+        assert_eq!([false, true], dest);
+        assert!(matches!(
+            replay.read(&mut dest),
+            Err(ReadError::ReachedEnd { read_bools: 1 })
+        ));
+        assert!(replay.is_finished());
+        assert!(replay.read(&mut dest).is_err());
+    }
+
+    #[test]
+    fn test_journal_symmetry() {
+        let mut journal = BitJournal::new();
+        journal.entries.push(JournalEntry {
+            offset: 0,
+            bits: vec![true, true, false],
+            outcome: JournalOutcome::Ok,
+        });
+        journal.entries.push(JournalEntry {
+            offset: 3,
+            bits: vec![false; 10],
+            outcome: JournalOutcome::ReachedEnd { read_bools: 7 },
+        });
+        journal.entries.push(JournalEntry {
+            offset: 10,
+            bits: vec![],
+            outcome: JournalOutcome::Other("Connection reset".to_string()),
+        });
+
+        let mut sink = BoolVecBitSink::new();
+        journal
+            .write_to(&mut sink, &DigitIntEncodingProtocol::v1())
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded =
+            BitJournal::read_from(&mut source, &DigitIntDecodingProtocol::v1(), &mut context)
+                .unwrap();
+        assert_eq!(journal, decoded);
+    }
+}
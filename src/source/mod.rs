@@ -1,8 +1,24 @@
 mod bool_slice;
+mod chunks;
+mod counting;
+mod indexed;
+mod io;
+mod iterator;
+mod journal;
+mod progress;
+mod sub;
 mod u8_slice;
 mod errors;
 
 pub use bool_slice::*;
+pub use chunks::*;
+pub use counting::*;
+pub use indexed::*;
+pub use io::*;
+pub use iterator::*;
+pub use journal::*;
+pub use progress::*;
+pub use sub::*;
 pub use u8_slice::*;
 pub use errors::*;
 
@@ -26,6 +42,34 @@ pub trait BitSource {
     /// if another error occurred while reading, a `ReadError` will be
     /// returned.
     fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError>;
+
+    /// Returns an iterator that reads chunks of *num_bits* bits from this
+    /// source, and returns each chunk as *u64* (see *BitChunks*). This is
+    /// convenient for custom codecs that would otherwise need to read bool
+    /// buffers and reassemble the integers manually.
+    ///
+    /// Panics if *num_bits* is 0 or larger than 64.
+    fn read_chunks(&mut self, num_bits: u8) -> BitChunks<'_, Self> {
+        BitChunks::new(self, num_bits)
+    }
+}
+
+/// A *BitSource* that can jump to any bit position, for instance because it
+/// reads from a slice that is completely in memory. This makes it possible to
+/// read records in random order (see *BitIndex*) or to read the same bits
+/// multiple times.
+pub trait SeekableBitSource: BitSource {
+    /// Moves the read position of this source to *position*: the next call to
+    /// *read* will start with the bit at index *position* (counted from the
+    /// very first bit of this source).
+    ///
+    /// Seeking to the end of this source is allowed, but seeking beyond that
+    /// will return *ReadError::ReachedEnd* (and leave the position unchanged).
+    fn seek_bits(&mut self, position: u64) -> Result<(), ReadError>;
+
+    /// Gets the current read position: the number of bits before the bit that
+    /// will be read next.
+    fn position(&self) -> u64;
 }
 
 #[cfg(test)]
@@ -73,6 +117,31 @@ pub(crate) mod test_helper {
         test_slice(&[true, true, false, false, true, false, false, true, true], source_generator);
     }
 
+    pub fn seek_tests<S: SeekableBitSource>(source_generator: &impl Fn(&[bool]) -> S) {
+        let slice = [true, true, false, false, true, false, false, true, true, false, true];
+        let mut source = source_generator(&slice);
+        assert_eq!(0, source.position());
+
+        let mut target = [false; 3];
+        source.read(&mut target).unwrap();
+        assert_eq!(3, source.position());
+
+        source.seek_bits(8).unwrap();
+        assert_eq!(8, source.position());
+        source.read(&mut target).unwrap();
+        assert_eq!(&slice[8..11], &target);
+        source.read(&mut [false]).unwrap_err();
+
+        source.seek_bits(1).unwrap();
+        source.read(&mut target).unwrap();
+        assert_eq!(&slice[1..4], &target);
+
+        source.seek_bits(slice.len() as u64).unwrap();
+        source.read(&mut [false]).unwrap_err();
+        source.seek_bits(slice.len() as u64 + 1).unwrap_err();
+        assert_eq!(slice.len() as u64, source.position());
+    }
+
     pub fn random_tests<S: BitSource>(source_generator: &impl Fn(&[bool]) -> S) {
         let mut rng = rand::thread_rng();
         for _counter in 0 .. 100 {
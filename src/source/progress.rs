@@ -0,0 +1,101 @@
+use crate::*;
+
+/// A *BitSource* that wraps another *BitSource* and calls an observer whenever
+/// another *report_interval* bits have been read from it. This can be used to
+/// show a progress bar while decoding large files. See *ProgressBitSink* for
+/// the encoding counterpart.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let bits = [false; 1000];
+/// let mut last_fraction = 0.0;
+/// let mut source = ProgressBitSource::new(
+///     BoolSliceBitSource::new(&bits), 100, Some(bits.len() as u64),
+///     |progress| last_fraction = progress.fraction().unwrap()
+/// );
+/// source.read(&mut [false; 500]).unwrap();
+/// drop(source);
+/// assert_eq!(0.5, last_fraction);
+/// ```
+pub struct ProgressBitSource<S: BitSource, F: FnMut(Progress)> {
+    source: S,
+    report_interval: u64,
+    total_bits: Option<u64>,
+    observer: F,
+
+    processed_bits: u64,
+    next_report: u64,
+}
+
+impl<S: BitSource, F: FnMut(Progress)> ProgressBitSource<S, F> {
+    /// Constructs a new *ProgressBitSource* that reads from *source* and calls
+    /// *observer* every *report_interval* bits. The *total_bits* (if known)
+    /// will be passed to the observer as well.
+    ///
+    /// Panics if *report_interval* is 0.
+    pub fn new(source: S, report_interval: u64, total_bits: Option<u64>, observer: F) -> Self {
+        assert!(report_interval > 0, "The report interval must be positive");
+        Self {
+            source,
+            report_interval,
+            total_bits,
+            observer,
+            processed_bits: 0,
+            next_report: report_interval,
+        }
+    }
+
+    /// Gets the number of bits that have been read so far
+    pub fn get_processed_bits(&self) -> u64 {
+        self.processed_bits
+    }
+
+    /// Consumes this *ProgressBitSource* and returns the wrapped *BitSource*
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: BitSource, F: FnMut(Progress)> BitSource for ProgressBitSource<S, F> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        self.source.read(dest)?;
+        self.processed_bits += dest.len() as u64;
+        if self.processed_bits >= self.next_report {
+            (self.observer)(Progress {
+                processed_bits: self.processed_bits,
+                total_bits: self.total_bits,
+            });
+            self.next_report =
+                (self.processed_bits / self.report_interval + 1) * self.report_interval;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_reports() {
+        let bits = [true; 100];
+        let mut reports = Vec::new();
+        {
+            let mut source =
+                ProgressBitSource::new(BoolSliceBitSource::new(&bits), 30, Some(100), |progress| {
+                    reports.push(progress.processed_bits)
+                });
+            for _counter in 0..10 {
+                source.read(&mut [false; 7]).unwrap();
+            }
+            source.read(&mut [false; 30]).unwrap();
+            assert!(source.read(&mut [false]).is_err());
+            assert_eq!(100, source.get_processed_bits());
+        }
+
+        assert_eq!(vec![35, 63, 100], reports);
+    }
+}
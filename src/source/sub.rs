@@ -0,0 +1,137 @@
+use crate::*;
+
+/// A *BitSource* that borrows another *BitSource*, and only exposes the next
+/// *num_bits* bits of it. Reading past the end of this window returns
+/// *ReadError::ReachedEnd*, even when the parent source has more bits.
+///
+/// This is useful to decode nested length-prefixed structures: when the inner
+/// data is decoded from a *SubSource* whose size is the length prefix, a
+/// corrupt or malicious inner structure can never read bits that belong to the
+/// data after it. Use *skip_remaining* to move the parent to the end of the
+/// window when the inner data didn't use all its bits.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let encoder = SimpleIntEncodingProtocol::new();
+/// let decoder = SimpleIntDecodingProtocol::new();
+/// let mut sink = BoolVecBitSink::new();
+/// // A section of 16 bits that contains 1 byte, followed by another byte
+/// encoder.write_u8(&mut sink, 16).unwrap();
+/// encoder.write_u8(&mut sink, 12).unwrap();
+/// encoder.write_u8(&mut sink, 0).unwrap();
+/// encoder.write_u8(&mut sink, 34).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let section_size = decoder.read_u8(&mut source).unwrap();
+/// let mut section = SubSource::new(&mut source, section_size as u64);
+/// assert_eq!(12, decoder.read_u8(&mut section).unwrap());
+/// // The section doesn't contain a second u16
+/// assert!(decoder.read_u16(&mut section).is_err());
+/// section.skip_remaining().unwrap();
+///
+/// assert_eq!(34, decoder.read_u8(&mut source).unwrap());
+/// ```
+pub struct SubSource<'a, S: BitSource> {
+    source: &'a mut S,
+    num_remaining_bits: u64,
+}
+
+impl<'a, S: BitSource> SubSource<'a, S> {
+    /// Constructs a new *SubSource* that exposes the next *num_bits* bits of
+    /// *source*
+    pub fn new(source: &'a mut S, num_bits: u64) -> Self {
+        Self {
+            source,
+            num_remaining_bits: num_bits,
+        }
+    }
+
+    /// Gets the number of bits of the window that haven't been read yet
+    pub fn get_num_remaining_bits(&self) -> u64 {
+        self.num_remaining_bits
+    }
+
+    /// Reads (and discards) the remaining bits of the window from the parent
+    /// source, so that the parent source will continue after the window
+    pub fn skip_remaining(&mut self) -> Result<(), ReadError> {
+        let mut buffer = [false; 256];
+        while self.num_remaining_bits > 0 {
+            let chunk_size = u64::min(self.num_remaining_bits, buffer.len() as u64) as usize;
+            self.read(&mut buffer[0..chunk_size])?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, S: BitSource> BitSource for SubSource<'a, S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        if dest.len() as u64 > self.num_remaining_bits {
+            let read_bools = self.num_remaining_bits as usize;
+            self.read(&mut dest[0..read_bools])?;
+            return Err(ReadError::ReachedEnd { read_bools });
+        }
+
+        let result = self.source.read(dest);
+        self.num_remaining_bits -= match &result {
+            Ok(()) => dest.len(),
+            Err(ReadError::ReachedEnd { read_bools }) => *read_bools,
+            Err(ReadError::Other(_)) => 0,
+        } as u64;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_window() {
+        let bits: Vec<bool> = (0..20).map(|index| index % 3 == 0).collect();
+        let mut source = BoolSliceBitSource::new(&bits);
+        source.read(&mut [false; 2]).unwrap();
+
+        let mut sub_source = SubSource::new(&mut source, 10);
+        let mut dest = [false; 4];
+        sub_source.read(&mut dest).unwrap();
+        assert_eq!(&bits[2..6], &dest);
+        assert_eq!(6, sub_source.get_num_remaining_bits());
+
+        let mut dest = [false; 8];
+        match sub_source.read(&mut dest) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(6, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+        assert_eq!(&bits[6..12], &dest[0..6]);
+        assert_eq!(0, sub_source.get_num_remaining_bits());
+        match sub_source.read(&mut [false]) {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(0, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+
+        let mut rest = [false; 8];
+        source.read(&mut rest).unwrap();
+        assert_eq!(&bits[12..20], &rest);
+    }
+
+    #[test]
+    fn test_skip_remaining() {
+        let bits: Vec<bool> = (0..1000).map(|index| index % 7 == 0).collect();
+        let mut source = BoolSliceBitSource::new(&bits);
+        let mut sub_source = SubSource::new(&mut source, 900);
+        sub_source.read(&mut [false; 5]).unwrap();
+        sub_source.skip_remaining().unwrap();
+        assert_eq!(0, sub_source.get_num_remaining_bits());
+        assert_eq!(900, source.position());
+
+        // The parent source ends before the window does
+        let mut sub_source = SubSource::new(&mut source, 200);
+        match sub_source.skip_remaining() {
+            Err(ReadError::ReachedEnd { read_bools }) => assert_eq!(100, read_bools),
+            _ => panic!("Expected ReachedEnd"),
+        }
+    }
+}
@@ -33,7 +33,7 @@ use crate::*;
 /// uses 8 bits to store 1 bool.
 pub struct U8SliceBitSource<'a> {
     slice: &'a [u8],
-    bit_offset: u8,
+    bit_position: u64,
     num_padding_bits: u8
 }
 
@@ -48,24 +48,34 @@ impl<'a> U8SliceBitSource<'a> {
     /// multiple of 8 number of bools, so there are usually a couple of fake values
     /// at the end, that were never written, and thus should not be read.
     pub fn new(slice: &'a [u8], num_padding_bits: u8) -> Self {
-        Self { slice, bit_offset: 0, num_padding_bits }
+        Self { slice, bit_position: 0, num_padding_bits }
+    }
+
+    fn get_num_bits(&self) -> u64 {
+        // Saturate to avoid panicking when num_padding_bits exceeds the slice
+        (self.slice.len() as u64 * 8).saturating_sub(self.num_padding_bits as u64)
+    }
+
+    /// Checks whether all padding bits (the last *num_padding_bits* bits of the
+    /// slice) are 0. Sinks like *U8VecBitSink* always write 0 padding bits, so
+    /// other padding bits indicate that the bytes were modified. Applications
+    /// that hash or sign encoded bytes can use this to reject such bytes, since
+    /// they would otherwise decode to the same value as the original bytes.
+    pub fn has_zero_padding(&self) -> bool {
+        let num_bits = self.get_num_bits();
+        (num_bits..self.slice.len() as u64 * 8)
+            .all(|bit| self.slice[(bit / 8) as usize] & (1 << (bit % 8)) == 0)
     }
 }
 
 impl<'a> BitSource for U8SliceBitSource<'a> {
     fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
-        let remaining_bits = self.slice.len() * 8 - self.bit_offset as usize - self.num_padding_bits as usize;
-        let num_bits_to_write = usize::min(dest.len(), remaining_bits);
-        for dest_index in 0..num_bits_to_write {
-            let own_byte = self.slice[0];
-            let own_bit = own_byte & (1 << self.bit_offset) != 0;
-            dest[dest_index] = own_bit;
-            if self.bit_offset == 7 {
-                self.bit_offset = 0;
-                self.slice = &self.slice[1..self.slice.len()];
-            } else {
-                self.bit_offset += 1;
-            }
+        let remaining_bits = self.get_num_bits() - self.bit_position;
+        let num_bits_to_write = u64::min(dest.len() as u64, remaining_bits) as usize;
+        for dest_bit in dest[0..num_bits_to_write].iter_mut() {
+            let own_byte = self.slice[(self.bit_position / 8) as usize];
+            *dest_bit = own_byte & (1 << (self.bit_position % 8)) != 0;
+            self.bit_position += 1;
         }
 
         if num_bits_to_write < dest.len() {
@@ -76,6 +86,20 @@ impl<'a> BitSource for U8SliceBitSource<'a> {
     }
 }
 
+impl<'a> SeekableBitSource for U8SliceBitSource<'a> {
+    fn seek_bits(&mut self, position: u64) -> Result<(), ReadError> {
+        if position > self.get_num_bits() {
+            return Err(ReadError::ReachedEnd { read_bools: 0 });
+        }
+        self.bit_position = position;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.bit_position
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -93,6 +117,17 @@ mod tests {
         });
     }
 
+    #[test]
+    fn seek_tests() {
+        super::test_helper::seek_tests(&|slice| {
+            let bytes = bools_to_bytes(slice);
+
+            // Not such a nice solution, but this is just for unit testing anyway
+            let byte_slice = Box::leak(Box::new(bytes));
+            U8SliceBitSource::new(byte_slice, (8 * byte_slice.len() - slice.len()) as u8)
+        });
+    }
+
     #[test]
     fn random_tests() {
         super::test_helper::random_tests(&|slice| {
@@ -104,4 +139,23 @@ mod tests {
             source
         });
     }
+
+    #[test]
+    fn zero_padding() {
+        assert!(U8SliceBitSource::new(&[], 0).has_zero_padding());
+        assert!(U8SliceBitSource::new(&[0b0001_1111], 3).has_zero_padding());
+        assert!(!U8SliceBitSource::new(&[0b0010_1111], 3).has_zero_padding());
+        assert!(U8SliceBitSource::new(&[255, 0], 8).has_zero_padding());
+        assert!(!U8SliceBitSource::new(&[255, 0], 9).has_zero_padding());
+        assert!(U8SliceBitSource::new(&[0, 0], 30).has_zero_padding());
+    }
+
+    #[test]
+    fn too_many_padding_bits() {
+        let mut source = U8SliceBitSource::new(&[], 3);
+        source.read(&mut [false]).unwrap_err();
+
+        let mut source = U8SliceBitSource::new(&[255], 12);
+        source.read(&mut [false]).unwrap_err();
+    }
 }
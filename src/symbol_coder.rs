@@ -0,0 +1,218 @@
+use crate::*;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FromIterator;
+
+/// Encodes symbols of an arbitrary type (like enum variants or small structs)
+/// by mapping them to integer codes. Every symbol in the alphabet must be
+/// registered first, after which its code is the number of symbols that were
+/// registered before it. The codes are encoded with an int protocol, so the
+/// symbols that were registered first are usually the cheapest.
+///
+/// The encoder and decoder must register the same symbols in the same order.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// enum Weather {
+///     Sunny,
+///     Cloudy,
+///     Rain { heavy: bool },
+/// }
+///
+/// let coder: SymbolCoder<Weather> = vec![
+///     Weather::Sunny,
+///     Weather::Cloudy,
+///     Weather::Rain { heavy: false },
+///     Weather::Rain { heavy: true },
+/// ].into_iter().collect();
+/// assert_eq!(Some(3), coder.get_code(&Weather::Rain { heavy: true }));
+///
+/// let mut sink = BoolVecBitSink::new();
+/// let encoder = DigitIntEncodingProtocol::v1();
+/// coder.write(&encoder, &mut sink, &Weather::Rain { heavy: false }).unwrap();
+/// coder.write(&encoder, &mut sink, &Weather::Sunny).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let decoder = DigitIntDecodingProtocol::v1();
+/// assert_eq!(&Weather::Rain { heavy: false }, coder.read(&decoder, &mut source).unwrap());
+/// assert_eq!(&Weather::Sunny, coder.read(&decoder, &mut source).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolCoder<T: Eq + Hash> {
+    symbols: Vec<T>,
+    codes: HashMap<T, u32>,
+}
+
+impl<T: Eq + Hash + Clone> SymbolCoder<T> {
+    /// Constructs a *SymbolCoder* without any registered symbols
+    pub fn new() -> Self {
+        Self {
+            symbols: Vec::new(),
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Adds *symbol* to the alphabet (if it wasn't registered already) and
+    /// returns its code.
+    ///
+    /// Panics if the alphabet already has *u32::MAX* symbols.
+    pub fn register(&mut self, symbol: T) -> u32 {
+        if let Some(code) = self.codes.get(&symbol) {
+            return *code;
+        }
+        if self.symbols.len() >= u32::MAX as usize {
+            panic!("Too many symbols");
+        }
+        let code = self.symbols.len() as u32;
+        self.codes.insert(symbol.clone(), code);
+        self.symbols.push(symbol);
+        code
+    }
+
+    /// Gets the code of *symbol*, or *None* if it wasn't registered
+    pub fn get_code(&self, symbol: &T) -> Option<u32> {
+        self.codes.get(symbol).copied()
+    }
+
+    /// Gets the symbol with the given code, or *None* if there is no such
+    /// symbol
+    pub fn get_symbol(&self, code: u32) -> Option<&T> {
+        self.symbols.get(code as usize)
+    }
+
+    /// Gets the registered symbols, ordered by their code
+    pub fn get_symbols(&self) -> &[T] {
+        &self.symbols
+    }
+
+    /// Gets the number of registered symbols
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Checks whether no symbols have been registered
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Encodes the code of *symbol* with *protocol* and writes it to *sink*.
+    /// Returns an error if *symbol* wasn't registered.
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        symbol: &T,
+    ) -> Result<(), WriteError> {
+        match self.get_code(symbol) {
+            Some(code) => protocol.write_u32(sink, code),
+            None => Err("This symbol wasn't registered".into()),
+        }
+    }
+
+    /// Reads a code with *protocol* from *source*, and returns its symbol.
+    /// Returns *DecodeError::Other* if no symbol has that code.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+    ) -> Result<&T, DecodeError> {
+        let code = protocol.read_u32(source)?;
+        match self.get_symbol(code) {
+            Some(symbol) => Ok(symbol),
+            None => Err(DecodeError::Other(
+                format!(
+                    "Code {} is too large for an alphabet of {} symbols",
+                    code,
+                    self.len()
+                )
+                .into(),
+            )),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> Default for SymbolCoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash + Clone> FromIterator<T> for SymbolCoder<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(symbols: I) -> Self {
+        let mut coder = Self::new();
+        for symbol in symbols {
+            coder.register(symbol);
+        }
+        coder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_register() {
+        let mut coder = SymbolCoder::new();
+        assert!(coder.is_empty());
+        assert_eq!(0, coder.register("north"));
+        assert_eq!(1, coder.register("east"));
+        assert_eq!(0, coder.register("north"));
+        assert_eq!(2, coder.register("south"));
+        assert_eq!(3, coder.len());
+
+        assert_eq!(Some(1), coder.get_code(&"east"));
+        assert_eq!(None, coder.get_code(&"west"));
+        assert_eq!(Some(&"south"), coder.get_symbol(2));
+        assert_eq!(None, coder.get_symbol(3));
+        assert_eq!(&["north", "east", "south"], coder.get_symbols());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let coder: SymbolCoder<(u8, char)> = (0..50)
+            .map(|index| (index, (b'a' + index % 26) as char))
+            .collect();
+
+        for protocol_pair in &[
+            (
+                DigitIntEncodingProtocol::v1(),
+                DigitIntDecodingProtocol::v1(),
+            ),
+            (
+                DigitIntEncodingProtocol::new(2, false),
+                DigitIntDecodingProtocol::new(2, false),
+            ),
+        ] {
+            let mut sink = BoolVecBitSink::new();
+            for symbol in coder.get_symbols().iter().rev() {
+                coder.write(&protocol_pair.0, &mut sink, symbol).unwrap();
+            }
+            assert!(coder.write(&protocol_pair.0, &mut sink, &(0, 'b')).is_err());
+
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            for symbol in coder.get_symbols().iter().rev() {
+                assert_eq!(symbol, coder.read(&protocol_pair.1, &mut source).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        let coder: SymbolCoder<bool> = vec![true, false].into_iter().collect();
+        let mut sink = BoolVecBitSink::new();
+        DigitIntEncodingProtocol::v1()
+            .write_u32(&mut sink, 2)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        assert!(coder
+            .read(&DigitIntDecodingProtocol::v1(), &mut source)
+            .is_err());
+    }
+}
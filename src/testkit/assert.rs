@@ -0,0 +1,177 @@
+use crate::testkit::parse_bit_string;
+use crate::*;
+
+/// Types whose bits can be compared by *assert_bits_eq!*. This is implemented for
+/// the sinks of this crate that remember their bits and for bool slices.
+pub trait AssertBits {
+    /// Gets the bits that should be compared
+    fn bits_for_assert(&self) -> Vec<bool>;
+}
+
+impl AssertBits for BoolVecBitSink {
+    fn bits_for_assert(&self) -> Vec<bool> {
+        self.get_bits().to_vec()
+    }
+}
+
+impl<W: UnsignedWord> AssertBits for WordVecBitSink<W> {
+    fn bits_for_assert(&self) -> Vec<bool> {
+        self.get_bools()
+    }
+}
+
+impl AssertBits for [bool] {
+    fn bits_for_assert(&self) -> Vec<bool> {
+        self.to_vec()
+    }
+}
+
+impl AssertBits for Vec<bool> {
+    fn bits_for_assert(&self) -> Vec<bool> {
+        self.clone()
+    }
+}
+
+impl<T: AssertBits + ?Sized> AssertBits for &T {
+    fn bits_for_assert(&self) -> Vec<bool> {
+        (*self).bits_for_assert()
+    }
+}
+
+/// Checks that *actual* contains exactly the bits of *expected* (in the notation
+/// of *parse_bit_string*), and panics with a readable diff if it doesn't. The
+/// actual bits will be grouped in the same way as the expected bits, and every
+/// difference will be marked with a '^'. This is the function behind the
+/// *assert_bits_eq!* macro.
+pub fn assert_bits_eq_impl(expected: &str, actual: &[bool]) {
+    let expected_bits = parse_bit_string(expected);
+    if expected_bits == actual {
+        return;
+    }
+
+    let mut expected_line = String::new();
+    let mut actual_line = String::new();
+    let mut marker_line = String::new();
+    let mut bit_index = 0;
+    for character in expected.chars() {
+        if character == ' ' {
+            expected_line.push(' ');
+            actual_line.push(' ');
+            marker_line.push(' ');
+            continue;
+        }
+
+        expected_line.push(character);
+        match actual.get(bit_index) {
+            Some(bit) => {
+                actual_line.push(if *bit { '1' } else { '0' });
+                marker_line.push(if *bit == expected_bits[bit_index] {
+                    ' '
+                } else {
+                    '^'
+                });
+            }
+            None => {
+                actual_line.push('-');
+                marker_line.push('^');
+            }
+        }
+        bit_index += 1;
+    }
+
+    if actual.len() > bit_index {
+        actual_line.push(' ');
+        marker_line.push(' ');
+        for (index, bit) in actual[bit_index..].iter().enumerate() {
+            if index > 0 && index % 4 == 0 {
+                actual_line.push(' ');
+                marker_line.push(' ');
+            }
+            actual_line.push(if *bit { '1' } else { '0' });
+            marker_line.push('^');
+        }
+    }
+
+    let first_difference = (0..expected_bits.len().max(actual.len()))
+        .find(|index| expected_bits.get(*index) != actual.get(*index))
+        .unwrap_or(0);
+    panic!(
+        "The bits are not equal: expected {} bits, but got {} bits, and the first difference is at bit {}\n\
+        expected: {}\n\
+        actual:   {}\n          \
+        {}\n",
+        expected_bits.len(),
+        actual.len(),
+        first_difference,
+        expected_line,
+        actual_line,
+        marker_line.trim_end()
+    );
+}
+
+/// Asserts that the bits of a sink (or a bool slice) are equal to the given
+/// string in the notation of *parse_bit_string* (for instance "0101 0011"). When
+/// they are not equal, it will panic with an aligned diff, for instance:
+///
+/// ```text
+/// The bits are not equal: expected 9 bits, but got 10 bits, and the first difference is at bit 3
+/// expected: 0 1000 1111
+/// actual:   0 1010 1111 1
+///               ^       ^
+/// ```
+///
+/// This macro is only available when the *testkit* feature is enabled.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut sink = BoolVecBitSink::new();
+/// DigitIntEncodingProtocol::new(4, true).write_u8(&mut sink, 3).unwrap();
+/// assert_bits_eq!("0 1000 1111", sink);
+/// ```
+#[macro_export]
+macro_rules! assert_bits_eq {
+    ($expected:expr, $actual:expr) => {
+        $crate::testkit::assert_bits_eq_impl(
+            $expected,
+            &$crate::testkit::AssertBits::bits_for_assert(&$actual),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_equal() {
+        let mut sink = U8VecBitSink::new();
+        sink.write(&[true, false, true]).unwrap();
+        crate::assert_bits_eq!("1 01", sink);
+        crate::assert_bits_eq!("", Vec::new());
+        crate::assert_bits_eq!("10", &[true, false][..]);
+    }
+
+    fn panic_message(expected: &str, actual: &[bool]) -> String {
+        let result = std::panic::catch_unwind(|| assert_bits_eq_impl(expected, actual));
+        let error = result.unwrap_err();
+        error.downcast_ref::<String>().unwrap().clone()
+    }
+
+    #[test]
+    fn test_diff() {
+        let message = panic_message("0 1000 1111", &parse_bit_string("0101011111"));
+        assert!(message.contains("expected 9 bits, but got 10 bits"));
+        assert!(message.contains("first difference is at bit 3"));
+        assert!(message.contains("expected: 0 1000 1111\n"));
+        assert!(message.contains("actual:   0 1010 1111 1\n"));
+        assert!(message.contains("\n              ^       ^\n"));
+
+        let message = panic_message("1111 1111", &parse_bit_string("111111"));
+        assert!(message.contains("actual:   1111 11--\n"));
+        assert!(message.contains("\n                 ^^\n"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Wrappers around *BitSink*s and *BitSource*s that inject faults: they fail
+//! after a given number of bits, flip random bits, or return short reads. These
+//! are useful to test that protocols and user code handle errors and corrupted
+//! input gracefully (without panicking or allocating huge amounts of memory).
+//!
+//! All randomness is generated from a seed, so every test is deterministic.
+//!
+//! # Example
+//! ```
+//! use bit_encoding::*;
+//! use bit_encoding::testkit::faults::*;
+//!
+//! let encoder = DigitIntEncodingProtocol::v1();
+//! let decoder = DigitIntDecodingProtocol::v1();
+//! let mut sink = BoolVecBitSink::new();
+//! encoder.write_u64(&mut sink, 123_456_789).unwrap();
+//!
+//! let mut source = FailAfterBitSource::new(BoolSliceBitSource::new(sink.get_bits()), 10);
+//! match decoder.read_u64(&mut source) {
+//!     Err(DecodeError::Reading(ReadError::Other(error))) => assert!(error.is::<InjectedFault>()),
+//!     _ => panic!("The injected fault should have been returned"),
+//! }
+//! ```
+
+use crate::*;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+/// The error that is returned by the wrappers in this module when they inject
+/// a failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InjectedFault {
+    /// The number of bits that were read or written successfully before the
+    /// fault was injected
+    pub position: u64,
+}
+
+impl std::fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "Injected fault at bit {}", self.position)
+    }
+}
+
+impl std::error::Error for InjectedFault {}
+
+/// A *BitSink* that passes the first *num_bits* bits to the wrapped sink, and
+/// returns an *InjectedFault* for every write after that. The write that
+/// crosses the limit will pass the bits before the limit to the wrapped sink
+/// and then return the error.
+pub struct FailAfterBitSink<S: BitSink> {
+    sink: S,
+    num_bits: u64,
+}
+
+impl<S: BitSink> FailAfterBitSink<S> {
+    /// Constructs a new *FailAfterBitSink* that fails after *num_bits* bits
+    pub fn new(sink: S, num_bits: u64) -> Self {
+        Self { sink, num_bits }
+    }
+
+    /// Gets a reference to the wrapped *BitSink*
+    pub fn get_inner(&self) -> &S {
+        &self.sink
+    }
+}
+
+impl<S: BitSink> BitSink for FailAfterBitSink<S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let remaining = self.num_bits.saturating_sub(self.sink.get_num_bools());
+        if (bits.len() as u64) <= remaining {
+            return self.sink.write(bits);
+        }
+        self.sink.write(&bits[..remaining as usize])?;
+        Err(Box::new(InjectedFault {
+            position: self.sink.get_num_bools(),
+        }))
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.sink.finish()
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.sink.get_num_bools()
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+/// A *BitSource* that reads the first *num_bits* bits from the wrapped source,
+/// and returns a *ReadError::Other* with an *InjectedFault* for every read after
+/// that.
+pub struct FailAfterBitSource<S: BitSource> {
+    source: S,
+    num_bits: u64,
+    position: u64,
+}
+
+impl<S: BitSource> FailAfterBitSource<S> {
+    /// Constructs a new *FailAfterBitSource* that fails after *num_bits* bits
+    pub fn new(source: S, num_bits: u64) -> Self {
+        Self {
+            source,
+            num_bits,
+            position: 0,
+        }
+    }
+}
+
+impl<S: BitSource> BitSource for FailAfterBitSource<S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        if self.position + dest.len() as u64 > self.num_bits {
+            return Err(ReadError::Other(Box::new(InjectedFault {
+                position: self.position,
+            })));
+        }
+        self.source.read(dest)?;
+        self.position += dest.len() as u64;
+        Ok(())
+    }
+}
+
+/// A *BitSink* that flips every bit written to it with probability
+/// *flip_probability* before passing it to the wrapped sink
+pub struct BitFlipSink<S: BitSink> {
+    sink: S,
+    flip_probability: f64,
+    rng: StdRng,
+    buffer: Vec<bool>,
+}
+
+impl<S: BitSink> BitFlipSink<S> {
+    /// Constructs a new *BitFlipSink* that flips bits with probability
+    /// *flip_probability* (which must be between 0 and 1), using a random
+    /// generator seeded with *seed*
+    pub fn new(sink: S, flip_probability: f64, seed: u64) -> Self {
+        Self {
+            sink,
+            flip_probability,
+            rng: StdRng::seed_from_u64(seed),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Gets a reference to the wrapped *BitSink*
+    pub fn get_inner(&self) -> &S {
+        &self.sink
+    }
+}
+
+impl<S: BitSink> BitSink for BitFlipSink<S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        self.buffer.clear();
+        for bit in bits {
+            self.buffer
+                .push(*bit ^ self.rng.gen_bool(self.flip_probability));
+        }
+        self.sink.write(&self.buffer)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.sink.finish()
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.sink.get_num_bools()
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+/// A *BitSource* that flips every bit read from the wrapped source with
+/// probability *flip_probability*
+pub struct BitFlipSource<S: BitSource> {
+    source: S,
+    flip_probability: f64,
+    rng: StdRng,
+}
+
+impl<S: BitSource> BitFlipSource<S> {
+    /// Constructs a new *BitFlipSource* that flips bits with probability
+    /// *flip_probability* (which must be between 0 and 1), using a random
+    /// generator seeded with *seed*
+    pub fn new(source: S, flip_probability: f64, seed: u64) -> Self {
+        Self {
+            source,
+            flip_probability,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<S: BitSource> BitSource for BitFlipSource<S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let result = self.source.read(dest);
+        for bit in dest.iter_mut() {
+            *bit ^= self.rng.gen_bool(self.flip_probability);
+        }
+        result
+    }
+}
+
+/// A *BitSource* that simulates short reads: when more than *max_bits_per_read*
+/// bits are requested, it only reads *max_bits_per_read* bits from the wrapped
+/// source and returns *ReadError::ReachedEnd*, like a source whose data was
+/// truncated.
+pub struct ShortReadBitSource<S: BitSource> {
+    source: S,
+    max_bits_per_read: usize,
+}
+
+impl<S: BitSource> ShortReadBitSource<S> {
+    /// Constructs a new *ShortReadBitSource* that reads at most
+    /// *max_bits_per_read* bits per call
+    pub fn new(source: S, max_bits_per_read: usize) -> Self {
+        Self {
+            source,
+            max_bits_per_read,
+        }
+    }
+}
+
+impl<S: BitSource> BitSource for ShortReadBitSource<S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        if dest.len() <= self.max_bits_per_read {
+            return self.source.read(dest);
+        }
+        self.source.read(&mut dest[..self.max_bits_per_read])?;
+        Err(ReadError::ReachedEnd {
+            read_bools: self.max_bits_per_read,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_fail_after_sink() {
+        let mut sink = FailAfterBitSink::new(BoolVecBitSink::new(), 5);
+        sink.write(&[true; 3]).unwrap();
+        let error = sink.write(&[false; 3]).unwrap_err();
+        assert_eq!(
+            Some(&InjectedFault { position: 5 }),
+            error.downcast_ref::<InjectedFault>()
+        );
+        assert_eq!(
+            &[true, true, true, false, false],
+            sink.get_inner().get_bits()
+        );
+        assert!(sink.write(&[true]).is_err());
+    }
+
+    #[test]
+    fn test_fail_after_source() {
+        let bits = [true; 10];
+        let mut source = FailAfterBitSource::new(BoolSliceBitSource::new(&bits), 4);
+        source.read(&mut [false; 4]).unwrap();
+        assert!(matches!(
+            source.read(&mut [false]),
+            Err(ReadError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn test_bit_flips_are_deterministic() {
+        let bits = [false; 1000];
+        let mut sink1 = BitFlipSink::new(BoolVecBitSink::new(), 0.1, 42);
+        sink1.write(&bits).unwrap();
+        let mut sink2 = BitFlipSink::new(BoolVecBitSink::new(), 0.1, 42);
+        sink2.write(&bits).unwrap();
+        assert_eq!(sink1.get_inner().get_bits(), sink2.get_inner().get_bits());
+
+        let num_flips = sink1
+            .get_inner()
+            .get_bits()
+            .iter()
+            .filter(|bit| **bit)
+            .count();
+        assert!(num_flips > 50 && num_flips < 150);
+
+        let mut source = BitFlipSource::new(BoolSliceBitSource::new(&bits), 0.1, 42);
+        let mut dest = [false; 1000];
+        source.read(&mut dest).unwrap();
+        assert_eq!(sink1.get_inner().get_bits(), &dest[..]);
+    }
+
+    #[test]
+    fn test_short_reads() {
+        let bits = [true; 10];
+        let mut source = ShortReadBitSource::new(BoolSliceBitSource::new(&bits), 3);
+        source.read(&mut [false; 3]).unwrap();
+        let mut dest = [false; 5];
+        assert!(matches!(
+            source.read(&mut dest),
+            Err(ReadError::ReachedEnd { read_bools: 3 })
+        ));
+        assert_eq!([true, true, true, false, false], dest);
+    }
+
+    #[test]
+    fn test_protocols_survive_faults() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        for value in 0..200u64 {
+            encoder.write_u64(&mut sink, value * value * value).unwrap();
+        }
+
+        for seed in 0..20 {
+            let mut source =
+                BitFlipSource::new(BoolSliceBitSource::new(sink.get_bits()), 0.05, seed);
+            while decoder.read_u64(&mut source).is_ok() {}
+
+            let source = BoolSliceBitSource::new(sink.get_bits());
+            let mut source = ShortReadBitSource::new(source, 1 + seed as usize);
+            while decoder.read_u64(&mut source).is_ok() {}
+        }
+    }
+}
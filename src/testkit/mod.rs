@@ -0,0 +1,709 @@
+//! Test helpers for checking implementations of *IntEncodingProtocol* and
+//! *IntDecodingProtocol*. This crate uses these functions to test its own
+//! protocols, but they are also available to other crates (when the *testkit*
+//! feature is enabled) so that they can test their own protocol pairs in the
+//! same exhaustive way.
+//!
+//! All functions in this module will panic when the test fails, so they are
+//! meant to be called from within unit tests.
+//!
+//! # Example
+//! ```
+//! use bit_encoding::*;
+//! use bit_encoding::testkit::*;
+//!
+//! let encoder = DigitIntEncodingProtocol::new(4, true);
+//! let decoder = DigitIntDecodingProtocol::new(4, true);
+//!
+//! test_encoding_pair(&encoder, &decoder);
+//! test_u8_result(&encoder, &decoder, 3, "0 1000 1111");
+//! ```
+
+mod assert;
+pub mod faults;
+
+#[cfg(feature = "proptest")]
+pub mod strategies;
+mod vectors;
+
+pub use assert::*;
+pub use vectors::*;
+
+use crate::*;
+
+use rand::distributions::Standard;
+use rand::prelude::*;
+
+/// Tests that *decoder* can decode everything that was encoded by *encoder*.
+/// All possible *u8*, *i8*, *u16* and *i16* values will be tested, as well as
+/// some edge cases and random values for the bigger integer types. Finally, it
+/// will test a combination of different integer types and raw bits.
+pub fn test_encoding_pair(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_u8(encoder, decoder);
+    test_i8(encoder, decoder);
+    test_u16(encoder, decoder);
+    test_i16(encoder, decoder);
+    test_u32(encoder, decoder);
+    test_i32(encoder, decoder);
+    test_u64(encoder, decoder);
+    test_i64(encoder, decoder);
+    test_u128(encoder, decoder);
+    test_i128(encoder, decoder);
+
+    // Combining them...
+    let mut sink = BoolVecBitSink::new();
+    write_combined(&mut sink, encoder).unwrap();
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    read_combined(&mut source, decoder).unwrap();
+
+    test_skip(encoder, decoder);
+    test_encoded_sizes(encoder);
+    test_slices(encoder, decoder);
+    test_bools(encoder, decoder);
+}
+
+/// Tests that *decoder* skips exactly the bits of the values that were written
+/// by *encoder*, for every *IntType*.
+pub fn test_skip(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut sink = BoolVecBitSink::new();
+    for value in &[0, 1, 2, 100, -1, -100, i8::MIN as i128, u8::MAX as i128] {
+        for int_type in &IntType::ALL {
+            match int_type {
+                IntType::U8 => encoder.write_u8(&mut sink, *value as u8),
+                IntType::I8 => encoder.write_i8(&mut sink, *value as i8),
+                IntType::U16 => encoder.write_u16(&mut sink, *value as u16),
+                IntType::I16 => encoder.write_i16(&mut sink, *value as i16),
+                IntType::U32 => encoder.write_u32(&mut sink, *value as u32),
+                IntType::I32 => encoder.write_i32(&mut sink, *value as i32),
+                IntType::U64 => encoder.write_u64(&mut sink, *value as u64),
+                IntType::I64 => encoder.write_i64(&mut sink, *value as i64),
+                IntType::U128 => encoder.write_u128(&mut sink, *value as u128),
+                IntType::I128 => encoder.write_i128(&mut sink, *value),
+            }
+            .unwrap();
+            encoder.write_u8(&mut sink, 123).unwrap();
+        }
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for _value_counter in 0..8 {
+        for int_type in &IntType::ALL {
+            decoder.skip(&mut source, *int_type).unwrap();
+            assert_eq!(123, decoder.read_u8(&mut source).unwrap());
+        }
+    }
+}
+
+/// Tests that the *encoded_size* methods of *encoder* return exactly the number
+/// of bits that the corresponding write methods write. All possible *u8*, *i8*,
+/// *u16* and *i16* values will be tested, as well as some edge cases and random
+/// values for the bigger integer types.
+pub fn test_encoded_sizes(encoder: &impl IntEncodingProtocol) {
+    fn check<T: Copy + std::fmt::Debug>(
+        values: impl IntoIterator<Item = T>,
+        write: impl Fn(&mut BoolVecBitSink, T) -> Result<(), WriteError>,
+        size: impl Fn(T) -> u32,
+    ) {
+        for value in values {
+            let mut sink = BoolVecBitSink::new();
+            write(&mut sink, value).unwrap();
+            assert_eq!(
+                sink.get_num_bools(),
+                size(value) as u64,
+                "Wrong encoded size for {:?}",
+                value
+            );
+        }
+    }
+
+    fn with_random<T>(edge_cases: &[T]) -> Vec<T>
+    where
+        T: Copy,
+        Standard: Distribution<T>,
+    {
+        let mut rng = rand::thread_rng();
+        let mut values = edge_cases.to_vec();
+        for _counter in 0..RANDOM_AMOUNT {
+            values.push(rng.gen());
+        }
+        values
+    }
+
+    check(
+        0..=u8::MAX,
+        |s, v| encoder.write_u8(s, v),
+        |v| encoder.encoded_size_u8(v),
+    );
+    check(
+        i8::MIN..=i8::MAX,
+        |s, v| encoder.write_i8(s, v),
+        |v| encoder.encoded_size_i8(v),
+    );
+    check(
+        0..=u16::MAX,
+        |s, v| encoder.write_u16(s, v),
+        |v| encoder.encoded_size_u16(v),
+    );
+    check(
+        i16::MIN..=i16::MAX,
+        |s, v| encoder.write_i16(s, v),
+        |v| encoder.encoded_size_i16(v),
+    );
+    check(
+        with_random(&[0, 1, 2, u32::MAX]),
+        |s, v| encoder.write_u32(s, v),
+        |v| encoder.encoded_size_u32(v),
+    );
+    check(
+        with_random(&[0, 1, 2, -1, -2, i32::MAX, i32::MIN]),
+        |s, v| encoder.write_i32(s, v),
+        |v| encoder.encoded_size_i32(v),
+    );
+    check(
+        with_random(&[0, 1, 2, u64::MAX]),
+        |s, v| encoder.write_u64(s, v),
+        |v| encoder.encoded_size_u64(v),
+    );
+    check(
+        with_random(&[0, 1, 2, -1, -2, i64::MAX, i64::MIN]),
+        |s, v| encoder.write_i64(s, v),
+        |v| encoder.encoded_size_i64(v),
+    );
+    check(
+        with_random(&[0, 1, 2, u128::MAX]),
+        |s, v| encoder.write_u128(s, v),
+        |v| encoder.encoded_size_u128(v),
+    );
+    check(
+        with_random(&[0, 1, 2, -1, -2, i128::MAX, i128::MIN]),
+        |s, v| encoder.write_i128(s, v),
+        |v| encoder.encoded_size_i128(v),
+    );
+}
+
+/// Tests that *decoder* can decode the slices that were encoded by *encoder*,
+/// for every integer type. Both empty slices and slices with random values will
+/// be tested.
+pub fn test_slices(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    fn check<T: Copy + Eq + std::fmt::Debug>(
+        write: impl Fn(&mut BoolVecBitSink, &[T]) -> Result<(), WriteError>,
+        read: impl Fn(&mut BoolSliceBitSource, &mut DecodeContext) -> Result<Vec<T>, DecodeError>,
+    ) where
+        Standard: Distribution<T>,
+    {
+        let mut rng = rand::thread_rng();
+        let slices: Vec<Vec<T>> = vec![
+            Vec::new(),
+            vec![rng.gen()],
+            (0..RANDOM_AMOUNT).map(|_| rng.gen()).collect(),
+        ];
+
+        let mut sink = BoolVecBitSink::new();
+        for slice in &slices {
+            write(&mut sink, slice).unwrap();
+        }
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        for slice in &slices {
+            assert_eq!(slice, &read(&mut source, &mut context).unwrap());
+        }
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    check(
+        |sink, values| encoder.write_u8_slice(sink, values),
+        |source, context| decoder.read_u8_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_i8_slice(sink, values),
+        |source, context| decoder.read_i8_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_u16_slice(sink, values),
+        |source, context| decoder.read_u16_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_i16_slice(sink, values),
+        |source, context| decoder.read_i16_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_u32_slice(sink, values),
+        |source, context| decoder.read_u32_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_i32_slice(sink, values),
+        |source, context| decoder.read_i32_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_u64_slice(sink, values),
+        |source, context| decoder.read_u64_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_i64_slice(sink, values),
+        |source, context| decoder.read_i64_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_u128_slice(sink, values),
+        |source, context| decoder.read_u128_vec(source, context),
+    );
+    check(
+        |sink, values| encoder.write_i128_slice(sink, values),
+        |source, context| decoder.read_i128_vec(source, context),
+    );
+}
+
+/// Tests that *decoder* can decode the bools that were encoded by *encoder*
+/// with *write_bools*, both for random bools and for bools with long runs (which
+/// should be run-length encoded). It also checks that invalid run lengths are
+/// rejected.
+pub fn test_bools(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut rng = rand::thread_rng();
+    let random_bools: Vec<bool> = (0..RANDOM_AMOUNT).map(|_| rng.gen()).collect();
+    let mut runs = vec![true; 10_000];
+    runs[1234] = false;
+    runs.extend_from_slice(&[false; 5000]);
+    let test_cases = vec![Vec::new(), vec![true], random_bools, runs];
+
+    let mut sink = BoolVecBitSink::new();
+    for bools in &test_cases {
+        encoder.write_bools(&mut sink, bools).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    let mut context = DecodeContext::new(DecodeLimits::new());
+    for bools in &test_cases {
+        assert_eq!(
+            bools,
+            &decoder.read_bools(&mut source, &mut context).unwrap()
+        );
+    }
+    assert!(source.read(&mut [false]).is_err());
+
+    let mut sink = BoolVecBitSink::new();
+    encoder.write_bools(&mut sink, &test_cases[3]).unwrap();
+    assert!(sink.get_num_bools() < 1000);
+
+    // A run length that exceeds the total length must be rejected
+    let mut sink = BoolVecBitSink::new();
+    encoder.write_u64(&mut sink, 5).unwrap();
+    sink.write(&[true, false]).unwrap();
+    encoder.write_u64(&mut sink, 2).unwrap();
+    encoder.write_u64(&mut sink, 2).unwrap();
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    assert!(decoder.read_bools(&mut source, &mut context).is_err());
+}
+
+fn write_combined(
+    sink: &mut impl BitSink,
+    encoder: &impl IntEncodingProtocol,
+) -> Result<(), WriteError> {
+    encoder.write_i128(sink, -123456)?;
+    sink.write(&[true, false])?;
+    encoder.write_i64(sink, 987654321)?;
+    encoder.write_i32(sink, -13579)?;
+    encoder.write_i16(sink, 24680)?;
+    encoder.write_i8(sink, 123)?;
+    sink.write(&[false])?;
+    encoder.write_u8(sink, 200)?;
+    encoder.write_u16(sink, 23456)?;
+    encoder.write_u32(sink, 123456789)?;
+    sink.write(&[true])?;
+    encoder.write_u64(sink, 0)?;
+    encoder.write_u128(sink, 345678901234567)?;
+    Ok(())
+}
+
+fn read_combined(
+    source: &mut impl BitSource,
+    decoder: &impl IntDecodingProtocol,
+) -> Result<(), DecodeError> {
+    assert_eq!(-123456, decoder.read_i128(source)?);
+    let mut dest = [false; 2];
+    source.read(&mut dest).unwrap();
+    assert_eq!(&[true, false], &dest);
+    assert_eq!(987654321, decoder.read_i64(source)?);
+    assert_eq!(-13579, decoder.read_i32(source)?);
+    assert_eq!(24680, decoder.read_i16(source)?);
+    assert_eq!(123, decoder.read_i8(source)?);
+    let mut dest = [true];
+    source.read(&mut dest).unwrap();
+    assert!(!dest[0]);
+    assert_eq!(200, decoder.read_u8(source)?);
+    assert_eq!(23456, decoder.read_u16(source)?);
+    assert_eq!(123456789, decoder.read_u32(source)?);
+    source.read(&mut dest).unwrap();
+    assert!(dest[0]);
+    assert_eq!(0, decoder.read_u64(source)?);
+    assert_eq!(345678901234567, decoder.read_u128(source)?);
+    Ok(())
+}
+
+fn test_u8(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut sink = BoolVecBitSink::with_capacity(256 * 8);
+    for value in 0..=255 {
+        encoder.write_u8(&mut sink, value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in 0..=255 {
+        assert_eq!(value, decoder.read_u8(&mut source).unwrap());
+    }
+}
+
+fn test_i8(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut sink = BoolVecBitSink::with_capacity(256 * 8);
+    for value in -128..=127 {
+        encoder.write_i8(&mut sink, value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in -128..=127 {
+        assert_eq!(value, decoder.read_i8(&mut source).unwrap());
+    }
+}
+
+fn test_u16(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut sink = BoolVecBitSink::with_capacity(65536 * 16);
+    for value in 0..=65535 {
+        encoder.write_u16(&mut sink, value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in 0..=65535 {
+        assert_eq!(value, decoder.read_u16(&mut source).unwrap());
+    }
+}
+
+fn test_i16(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    let mut sink = BoolVecBitSink::with_capacity(656536 * 16);
+    for value in -32768..=32767 {
+        encoder.write_i16(&mut sink, value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in -32768..=32767 {
+        assert_eq!(value, decoder.read_i16(&mut source).unwrap());
+    }
+}
+
+const RANDOM_AMOUNT: usize = 1_000;
+
+fn test_random_symmetry<T: Copy + Eq + std::fmt::Debug>(
+    write_method: impl Fn(&mut BoolVecBitSink, T) -> Result<(), WriteError>,
+    read_method: impl Fn(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+) where
+    Standard: Distribution<T>,
+{
+    let mut rng = rand::thread_rng();
+    let mut values = Vec::with_capacity(RANDOM_AMOUNT);
+    for _counter in 0..RANDOM_AMOUNT {
+        values.push(rng.gen());
+    }
+
+    let mut sink = BoolVecBitSink::new();
+    for value in &values {
+        write_method(&mut sink, *value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in &values {
+        assert_eq!(*value, read_method(&mut source).unwrap());
+    }
+}
+
+fn test_given_symmetry<T: Copy + Eq + std::fmt::Debug>(
+    values: &[T],
+    write_method: impl Fn(&mut BoolVecBitSink, T) -> Result<(), WriteError>,
+    read_method: impl Fn(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+) {
+    let mut sink = BoolVecBitSink::new();
+    for value in values {
+        write_method(&mut sink, *value).unwrap();
+    }
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    for value in values {
+        assert_eq!(*value, read_method(&mut source).unwrap());
+    }
+}
+
+fn test_u32(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, u32::MAX],
+        |sink, value| encoder.write_u32(sink, value),
+        |source| decoder.read_u32(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_u32(sink, value),
+        |source| decoder.read_u32(source),
+    );
+}
+
+fn test_i32(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, -1, i32::MAX, i32::MIN],
+        |sink, value| encoder.write_i32(sink, value),
+        |source| decoder.read_i32(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_i32(sink, value),
+        |source| decoder.read_i32(source),
+    );
+}
+
+fn test_u64(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, u64::MAX],
+        |sink, value| encoder.write_u64(sink, value),
+        |source| decoder.read_u64(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_u64(sink, value),
+        |source| decoder.read_u64(source),
+    );
+}
+
+fn test_i64(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, -1, i64::MAX, i64::MIN],
+        |sink, value| encoder.write_i64(sink, value),
+        |source| decoder.read_i64(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_i64(sink, value),
+        |source| decoder.read_i64(source),
+    );
+}
+
+fn test_u128(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, u128::MAX],
+        |sink, value| encoder.write_u128(sink, value),
+        |source| decoder.read_u128(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_u128(sink, value),
+        |source| decoder.read_u128(source),
+    );
+}
+
+fn test_i128(encoder: &impl IntEncodingProtocol, decoder: &impl IntDecodingProtocol) {
+    test_given_symmetry(
+        &[0, 1, -1, i128::MAX, i128::MIN],
+        |sink, value| encoder.write_i128(sink, value),
+        |source| decoder.read_i128(source),
+    );
+
+    test_random_symmetry(
+        |sink, value| encoder.write_i128(sink, value),
+        |source| decoder.read_i128(source),
+    );
+}
+
+/// Converts a string like "0101 0011" to the corresponding sequence of bools.
+/// Every '0' will become *false* and every '1' will become *true*. Spaces are
+/// ignored, so they can be used to make the string easier to read. This function
+/// will panic if the string contains any other characters.
+pub fn parse_bit_string(encoded: &str) -> Vec<bool> {
+    for character in encoded.chars() {
+        assert!(character == '0' || character == '1' || character == ' ');
+    }
+
+    encoded
+        .chars()
+        .filter(|c| *c != ' ')
+        .map(|c| c == '1')
+        .collect()
+}
+
+fn test_encoding_result(
+    mut action: impl FnMut(&mut BoolVecBitSink) -> Result<(), WriteError>,
+    encoded: &str,
+) {
+    let mut sink = BoolVecBitSink::new();
+
+    action(&mut sink).unwrap();
+
+    assert_bits_eq_impl(encoded, sink.get_bits());
+}
+
+fn test_decoding_result<R: Eq + std::fmt::Debug>(
+    mut action: impl FnMut(&mut BoolSliceBitSource) -> Result<R, DecodeError>,
+    value: R,
+    encoded: &str,
+) {
+    let as_bools = parse_bit_string(encoded);
+    let mut source = BoolSliceBitSource::new(&as_bools);
+
+    assert_eq!(value, action(&mut source).unwrap());
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. The *encoded* string
+/// uses the notation of *parse_bit_string*, for instance "0 1000 1111".
+pub fn test_u8_decoding(decoder: &impl IntDecodingProtocol, value: u8, encoded: &str) {
+    test_decoding_result(|source| decoder.read_u8(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_i8_decoding(decoder: &impl IntDecodingProtocol, value: i8, encoded: &str) {
+    test_decoding_result(|source| decoder.read_i8(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_u16_decoding(decoder: &impl IntDecodingProtocol, value: u16, encoded: &str) {
+    test_decoding_result(|source| decoder.read_u16(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_i16_decoding(decoder: &impl IntDecodingProtocol, value: i16, encoded: &str) {
+    test_decoding_result(|source| decoder.read_i16(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_u32_decoding(decoder: &impl IntDecodingProtocol, value: u32, encoded: &str) {
+    test_decoding_result(|source| decoder.read_u32(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_i32_decoding(decoder: &impl IntDecodingProtocol, value: i32, encoded: &str) {
+    test_decoding_result(|source| decoder.read_i32(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_u64_decoding(decoder: &impl IntDecodingProtocol, value: u64, encoded: &str) {
+    test_decoding_result(|source| decoder.read_u64(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_i64_decoding(decoder: &impl IntDecodingProtocol, value: i64, encoded: &str) {
+    test_decoding_result(|source| decoder.read_i64(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_u128_decoding(decoder: &impl IntDecodingProtocol, value: u128, encoded: &str) {
+    test_decoding_result(|source| decoder.read_u128(source), value, encoded);
+}
+
+/// Tests that *decoder* decodes *encoded* to *value*. See *test_u8_decoding*.
+pub fn test_i128_decoding(decoder: &impl IntDecodingProtocol, value: i128, encoded: &str) {
+    test_decoding_result(|source| decoder.read_i128(source), value, encoded);
+}
+
+/// Tests that *encoder* encodes *value* as *encoded* and that *decoder* decodes
+/// *encoded* back to *value*. The *encoded* string uses the notation of
+/// *parse_bit_string*, for instance "0 1000 1111".
+pub fn test_u8_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: u8,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_u8(sink, value), encoded);
+    test_u8_decoding(decoder, value, encoded);
+}
+
+/// The *i8* version of *test_u8_result*
+pub fn test_i8_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: i8,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_i8(sink, value), encoded);
+    test_i8_decoding(decoder, value, encoded);
+}
+
+/// The *u16* version of *test_u8_result*
+pub fn test_u16_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: u16,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_u16(sink, value), encoded);
+    test_u16_decoding(decoder, value, encoded);
+}
+
+/// The *i16* version of *test_u8_result*
+pub fn test_i16_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: i16,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_i16(sink, value), encoded);
+    test_i16_decoding(decoder, value, encoded);
+}
+
+/// The *u32* version of *test_u8_result*
+pub fn test_u32_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: u32,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_u32(sink, value), encoded);
+    test_u32_decoding(decoder, value, encoded);
+}
+
+/// The *i32* version of *test_u8_result*
+pub fn test_i32_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: i32,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_i32(sink, value), encoded);
+    test_i32_decoding(decoder, value, encoded);
+}
+
+/// The *u64* version of *test_u8_result*
+pub fn test_u64_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: u64,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_u64(sink, value), encoded);
+    test_u64_decoding(decoder, value, encoded);
+}
+
+/// The *i64* version of *test_u8_result*
+pub fn test_i64_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: i64,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_i64(sink, value), encoded);
+    test_i64_decoding(decoder, value, encoded);
+}
+
+/// The *u128* version of *test_u8_result*
+pub fn test_u128_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: u128,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_u128(sink, value), encoded);
+    test_u128_decoding(decoder, value, encoded);
+}
+
+/// The *i128* version of *test_u8_result*
+pub fn test_i128_result(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    value: i128,
+    encoded: &str,
+) {
+    test_encoding_result(|sink| encoder.write_i128(sink, value), encoded);
+    test_i128_decoding(decoder, value, encoded);
+}
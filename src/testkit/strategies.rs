@@ -0,0 +1,124 @@
+//! *proptest* strategies for generating protocol configurations and values, and
+//! the *roundtrip_property* helper to check that decoding is the inverse of
+//! encoding. This module requires the *proptest* feature.
+//!
+//! # Example
+//! ```
+//! use bit_encoding::*;
+//! use bit_encoding::testkit::strategies::*;
+//! use proptest::prelude::*;
+//!
+//! proptest!(|((encoder, decoder) in digit_protocol_pair(), values in value_sequence::<u32>(50))| {
+//!     roundtrip_property(
+//!         &values,
+//!         |sink, values| {
+//!             for value in values {
+//!                 encoder.write_u32(sink, *value)?;
+//!             }
+//!             Ok(())
+//!         },
+//!         |source| {
+//!             let mut result = Vec::with_capacity(values.len());
+//!             for _counter in 0..values.len() {
+//!                 result.push(decoder.read_u32(source)?);
+//!             }
+//!             Ok(result)
+//!         },
+//!     )?;
+//! });
+//! ```
+
+use crate::*;
+
+use proptest::prelude::*;
+use std::fmt::Debug;
+
+/// A strategy that generates all valid *digit_size*s of *DigitIntEncodingProtocol*
+/// and *DigitIntDecodingProtocol*.
+pub fn digit_size() -> impl Strategy<Value = u8> {
+    2u8..=127
+}
+
+/// A strategy that generates random (but matching) pairs of
+/// *DigitIntEncodingProtocol* and *DigitIntDecodingProtocol*. Small digit sizes
+/// are generated more often than big ones because they are more interesting.
+pub fn digit_protocol_pair(
+) -> impl Strategy<Value = (DigitIntEncodingProtocol, DigitIntDecodingProtocol)> {
+    (prop_oneof![4 => 2u8..=8, 1 => digit_size()], any::<bool>()).prop_map(
+        |(digit_size, short_zero_and_one)| {
+            (
+                DigitIntEncodingProtocol::new(digit_size, short_zero_and_one),
+                DigitIntDecodingProtocol::new(digit_size, short_zero_and_one),
+            )
+        },
+    )
+}
+
+/// A strategy that generates *Vec*s of random values with a length of at most
+/// *max_length*.
+pub fn value_sequence<T: Arbitrary>(max_length: usize) -> impl Strategy<Value = Vec<T>> {
+    proptest::collection::vec(any::<T>(), 0..=max_length)
+}
+
+/// Checks that *read* decodes the bits written by *write* back to *value*, and
+/// that it consumes exactly the bits that were written. This is meant to be used
+/// inside *proptest!* blocks: it returns a *TestCaseError* when the property is
+/// violated.
+pub fn roundtrip_property<T: PartialEq + Debug>(
+    value: &T,
+    write: impl FnOnce(&mut BoolVecBitSink, &T) -> Result<(), WriteError>,
+    read: impl FnOnce(&mut BoolSliceBitSource) -> Result<T, DecodeError>,
+) -> Result<(), TestCaseError> {
+    let mut sink = BoolVecBitSink::new();
+    write(&mut sink, value).map_err(|error| TestCaseError::fail(error.to_string()))?;
+
+    let mut source = BoolSliceBitSource::new(sink.get_bits());
+    let decoded = read(&mut source).map_err(|error| TestCaseError::fail(error.to_string()))?;
+    prop_assert_eq!(value, &decoded);
+    prop_assert!(
+        source.read(&mut [false]).is_err(),
+        "The decoder didn't read all bits that were written by the encoder"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_digit_roundtrip(
+            (encoder, decoder) in digit_protocol_pair(),
+            values in value_sequence::<i64>(20)
+        ) {
+            roundtrip_property(
+                &values,
+                |sink, values| {
+                    for value in values {
+                        encoder.write_i64(sink, *value)?;
+                    }
+                    Ok(())
+                },
+                |source| {
+                    let mut result = Vec::with_capacity(values.len());
+                    for _counter in 0..values.len() {
+                        result.push(decoder.read_i64(source)?);
+                    }
+                    Ok(result)
+                },
+            )?;
+        }
+    }
+
+    #[test]
+    fn test_detects_leftover_bits() {
+        let result = roundtrip_property(
+            &5u8,
+            |sink, value| SimpleIntEncodingProtocol::new().write_u16(sink, *value as u16),
+            |source| SimpleIntDecodingProtocol::new().read_u8(source),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::testkit::parse_bit_string;
+use crate::*;
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// An integer value together with its encoding by some *IntEncodingProtocol*.
+/// Implementations of the protocol in other languages can use these to check
+/// that they are compatible with this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector {
+    /// The type of the value
+    pub int_type: IntType,
+    /// The value in decimal notation. This is a string because not every
+    /// language can represent all *u128* and *i128* values as numbers.
+    pub value: String,
+    /// The encoded bits, in the order in which they were written
+    pub bits: Vec<bool>,
+}
+
+fn type_name(int_type: IntType) -> &'static str {
+    match int_type {
+        IntType::U8 => "u8",
+        IntType::I8 => "i8",
+        IntType::U16 => "u16",
+        IntType::I16 => "i16",
+        IntType::U32 => "u32",
+        IntType::I32 => "i32",
+        IntType::U64 => "u64",
+        IntType::I64 => "i64",
+        IntType::U128 => "u128",
+        IntType::I128 => "i128",
+    }
+}
+
+fn bits_to_string(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|bit| if *bit { '1' } else { '0' })
+        .collect()
+}
+
+/// Gets the unsigned values that are tested: small values, the values around
+/// every power of 2, and some pseudo-random values (which are the same every
+/// time)
+fn unsigned_candidates() -> Vec<u128> {
+    let mut values: Vec<u128> = (0..=3).collect();
+    for shift in 2..128 {
+        let power = 1u128 << shift;
+        values.extend_from_slice(&[power - 1, power, power + 1]);
+    }
+    values.push(u128::MAX);
+
+    let mut state = 0x2545_f491_4f6c_dd1du128;
+    for num_bits in 1..=128 {
+        state = state
+            .wrapping_mul(0x5851_f42d_4c95_7f2d_1405_7b7e_f767_814f)
+            .wrapping_add(1);
+        values.push(state >> (128 - num_bits));
+    }
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+fn signed_candidates() -> Vec<i128> {
+    let mut values = Vec::new();
+    for value in unsigned_candidates() {
+        if let Ok(value) = i128::try_from(value) {
+            values.push(value);
+            values.push(-value);
+        }
+    }
+    values.push(i128::MIN);
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+macro_rules! generate_vectors {
+    ($vectors:ident, $encoder:ident, $candidates:expr, $int_type:expr, $t:ty, $write:ident) => {
+        let values = $candidates
+            .iter()
+            .filter_map(|candidate| <$t>::try_from(*candidate).ok());
+        for value in values {
+            let mut sink = BoolVecBitSink::new();
+            $encoder.$write(&mut sink, value).unwrap();
+            $vectors.push(TestVector {
+                int_type: $int_type,
+                value: value.to_string(),
+                bits: sink.get_bits().to_vec(),
+            });
+        }
+    };
+}
+
+/// Encodes a fixed corpus of values with *encoder*, and returns the results as
+/// test vectors. For every integer type, the corpus contains small values, the
+/// minimum and maximum value, the values around every power of 2, and some
+/// pseudo-random values. The corpus is the same every time this is called.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+/// use bit_encoding::testkit::*;
+///
+/// let vectors = generate_test_vectors(&DigitIntEncodingProtocol::v1());
+/// let json = test_vectors_to_json("digit v1", &vectors);
+/// assert!(json.starts_with("{\"protocol\":\"digit v1\",\"vectors\":[{\"type\":\"u8\""));
+///
+/// let text = test_vectors_to_text("digit v1", &vectors);
+/// assert_eq!(vectors, parse_test_vectors_text(&text));
+/// check_test_vectors(&DigitIntEncodingProtocol::v1(), &DigitIntDecodingProtocol::v1(), &vectors);
+/// ```
+pub fn generate_test_vectors(encoder: &impl IntEncodingProtocol) -> Vec<TestVector> {
+    let unsigned = unsigned_candidates();
+    let signed = signed_candidates();
+    let mut vectors = Vec::new();
+    generate_vectors!(vectors, encoder, unsigned, IntType::U8, u8, write_u8);
+    generate_vectors!(vectors, encoder, signed, IntType::I8, i8, write_i8);
+    generate_vectors!(vectors, encoder, unsigned, IntType::U16, u16, write_u16);
+    generate_vectors!(vectors, encoder, signed, IntType::I16, i16, write_i16);
+    generate_vectors!(vectors, encoder, unsigned, IntType::U32, u32, write_u32);
+    generate_vectors!(vectors, encoder, signed, IntType::I32, i32, write_i32);
+    generate_vectors!(vectors, encoder, unsigned, IntType::U64, u64, write_u64);
+    generate_vectors!(vectors, encoder, signed, IntType::I64, i64, write_i64);
+    generate_vectors!(vectors, encoder, unsigned, IntType::U128, u128, write_u128);
+    generate_vectors!(vectors, encoder, signed, IntType::I128, i128, write_i128);
+    vectors
+}
+
+/// Converts test vectors to a simple line-based text format. The first line is
+/// a comment (starting with '#') with the name of the protocol configuration,
+/// and every other line contains the type, the value and the bits of 1 test
+/// vector, separated by spaces. For instance "u16 300 0001110111".
+pub fn test_vectors_to_text(protocol_name: &str, vectors: &[TestVector]) -> String {
+    let mut text = format!("# {}\n", protocol_name);
+    for vector in vectors {
+        writeln!(
+            text,
+            "{} {} {}",
+            type_name(vector.int_type),
+            vector.value,
+            bits_to_string(&vector.bits)
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Converts test vectors to JSON, in the form
+/// {"protocol":"name","vectors":[{"type":"u16","value":"300","bits":"0001110111"}]}.
+/// The values are strings because JSON numbers can't represent all 64-bit and
+/// 128-bit integers in every language.
+pub fn test_vectors_to_json(protocol_name: &str, vectors: &[TestVector]) -> String {
+    let mut json = String::from("{\"protocol\":\"");
+    for character in protocol_name.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            character if (character as u32) < 0x20 => {
+                write!(json, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => json.push(character),
+        }
+    }
+    json.push_str("\",\"vectors\":[");
+    for (index, vector) in vectors.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write!(
+            json,
+            "{{\"type\":\"{}\",\"value\":\"{}\",\"bits\":\"{}\"}}",
+            type_name(vector.int_type),
+            vector.value,
+            bits_to_string(&vector.bits)
+        )
+        .unwrap();
+    }
+    json.push_str("]}");
+    json
+}
+
+/// Parses test vectors in the text format of *test_vectors_to_text*. Empty
+/// lines and lines starting with '#' are ignored.
+///
+/// Panics if the text is not valid.
+pub fn parse_test_vectors_text(text: &str) -> Vec<TestVector> {
+    let mut vectors = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split(' ').collect();
+        assert_eq!(3, parts.len(), "Invalid test vector line: {}", line);
+        let int_type = *IntType::ALL
+            .iter()
+            .find(|int_type| type_name(**int_type) == parts[0])
+            .unwrap_or_else(|| panic!("Unknown integer type {}", parts[0]));
+        vectors.push(TestVector {
+            int_type,
+            value: parts[1].to_string(),
+            bits: parse_bit_string(parts[2]),
+        });
+    }
+    vectors
+}
+
+macro_rules! check_vector {
+    ($encoder:ident, $decoder:ident, $vector:ident, $t:ty, $write:ident, $read:ident) => {{
+        let value: $t = $vector.value.parse().expect("Invalid test vector value");
+        let mut sink = BoolVecBitSink::new();
+        $encoder.$write(&mut sink, value).unwrap();
+        assert_eq!(
+            $vector.bits,
+            sink.get_bits(),
+            "Encoding of {} {} changed",
+            type_name($vector.int_type),
+            value
+        );
+
+        let mut source = BoolSliceBitSource::new(&$vector.bits);
+        assert_eq!(value, $decoder.$read(&mut source).unwrap());
+        assert!(
+            source.read(&mut [false]).is_err(),
+            "Decoding {} {} didn't consume all bits",
+            type_name($vector.int_type),
+            value
+        );
+    }};
+}
+
+/// Checks that *encoder* produces exactly the bits of every test vector, and
+/// that *decoder* decodes those bits to the value of the test vector (without
+/// leaving bits unused). This can be used to check that a protocol is still
+/// compatible with a corpus that was generated earlier.
+///
+/// Panics if any test vector doesn't match.
+pub fn check_test_vectors(
+    encoder: &impl IntEncodingProtocol,
+    decoder: &impl IntDecodingProtocol,
+    vectors: &[TestVector],
+) {
+    for vector in vectors {
+        match vector.int_type {
+            IntType::U8 => check_vector!(encoder, decoder, vector, u8, write_u8, read_u8),
+            IntType::I8 => check_vector!(encoder, decoder, vector, i8, write_i8, read_i8),
+            IntType::U16 => check_vector!(encoder, decoder, vector, u16, write_u16, read_u16),
+            IntType::I16 => check_vector!(encoder, decoder, vector, i16, write_i16, read_i16),
+            IntType::U32 => check_vector!(encoder, decoder, vector, u32, write_u32, read_u32),
+            IntType::I32 => check_vector!(encoder, decoder, vector, i32, write_i32, read_i32),
+            IntType::U64 => check_vector!(encoder, decoder, vector, u64, write_u64, read_u64),
+            IntType::I64 => check_vector!(encoder, decoder, vector, i64, write_i64, read_i64),
+            IntType::U128 => check_vector!(encoder, decoder, vector, u128, write_u128, read_u128),
+            IntType::I128 => check_vector!(encoder, decoder, vector, i128, write_i128, read_i128),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::*;
+    use crate::*;
+
+    #[test]
+    fn test_corpus() {
+        let vectors = generate_test_vectors(&SimpleIntEncodingProtocol::new());
+        let count = |int_type| {
+            vectors
+                .iter()
+                .filter(|vector| vector.int_type == int_type)
+                .count()
+        };
+        // 0, 1, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17... 127, 128, 129, 255
+        assert!(count(IntType::U8) >= 20);
+        assert!(count(IntType::U128) > count(IntType::U64));
+        assert!(vectors
+            .iter()
+            .any(|vector| vector.value == i128::MIN.to_string()));
+        assert!(vectors
+            .iter()
+            .any(|vector| vector.value == u128::MAX.to_string()));
+
+        // The corpus must be the same every time
+        assert_eq!(
+            vectors,
+            generate_test_vectors(&SimpleIntEncodingProtocol::new())
+        );
+        check_test_vectors(
+            &SimpleIntEncodingProtocol::new(),
+            &SimpleIntDecodingProtocol::new(),
+            &vectors,
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let vectors = vec![
+            TestVector {
+                int_type: IntType::U16,
+                value: "300".to_string(),
+                bits: parse_bit_string("0001110111"),
+            },
+            TestVector {
+                int_type: IntType::I8,
+                value: "-3".to_string(),
+                bits: parse_bit_string("101"),
+            },
+        ];
+        let text = test_vectors_to_text("my \"protocol\"", &vectors);
+        assert_eq!("# my \"protocol\"\nu16 300 0001110111\ni8 -3 101\n", text);
+        assert_eq!(vectors, parse_test_vectors_text(&text));
+
+        assert_eq!(
+            "{\"protocol\":\"my \\\"protocol\\\"\",\"vectors\":[\
+            {\"type\":\"u16\",\"value\":\"300\",\"bits\":\"0001110111\"},\
+            {\"type\":\"i8\",\"value\":\"-3\",\"bits\":\"101\"}]}",
+            test_vectors_to_json("my \"protocol\"", &vectors)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_changed_encoding() {
+        let vectors = generate_test_vectors(&DigitIntEncodingProtocol::new(4, true));
+        check_test_vectors(
+            &DigitIntEncodingProtocol::new(4, false),
+            &DigitIntDecodingProtocol::new(4, false),
+            &vectors,
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! Wrappers that emit *tracing* spans and events while encoding and decoding.
+//! This module is only available when the *tracing* feature is enabled.
+//!
+//! * *TracedProtocol* wraps an *IntEncodingProtocol* and/or *IntDecodingProtocol*
+//!   and opens a span for every call. When a call fails, it emits an event with
+//!   the error and the number of bits that the call consumed.
+//! * *TracedBitSource* wraps a *BitSource*, keeps track of the bit offset, and
+//!   emits an event (with the offset) whenever a read fails.
+//! * *TracedBitSink* wraps a *BitSink* and emits events when it is finished or
+//!   when a write fails.
+//!
+//! Combining a *TracedProtocol* with a *TracedBitSource* gives events that
+//! contain both the protocol call that failed and the absolute bit offset at
+//! which it failed.
+//!
+//! # Example
+//! ```
+//! use bit_encoding::*;
+//!
+//! let encoder = TracedProtocol::new(DigitIntEncodingProtocol::v1());
+//! let decoder = TracedProtocol::new(DigitIntDecodingProtocol::v1());
+//!
+//! let mut sink = TracedBitSink::new(BoolVecBitSink::new());
+//! encoder.write_u32(&mut sink, 1234).unwrap();
+//! sink.finish().unwrap();
+//!
+//! let bits = sink.into_inner().get_bits().to_vec();
+//! let mut source = TracedBitSource::new(BoolSliceBitSource::new(&bits));
+//! assert_eq!(1234, decoder.read_u32(&mut source).unwrap());
+//!
+//! // This will emit an event with offset = source.get_offset()
+//! assert!(decoder.read_u32(&mut source).is_err());
+//! ```
+
+use crate::*;
+
+use std::fmt::Debug;
+
+/// Wraps an *IntEncodingProtocol* and/or an *IntDecodingProtocol* and emits a
+/// *tracing* span for every call to it. See the module documentation for more
+/// information.
+#[derive(Debug, Clone, Copy)]
+pub struct TracedProtocol<P> {
+    protocol: P,
+}
+
+impl<P> TracedProtocol<P> {
+    /// Wraps *protocol*
+    pub fn new(protocol: P) -> Self {
+        Self { protocol }
+    }
+
+    /// Gets a reference to the wrapped protocol
+    pub fn get_inner(&self) -> &P {
+        &self.protocol
+    }
+}
+
+fn traced_write<S: BitSink, T: Debug>(
+    name: &'static str,
+    sink: &mut S,
+    value: T,
+    write: impl FnOnce(&mut S) -> Result<(), WriteError>,
+) -> Result<(), WriteError> {
+    let span = tracing::trace_span!("write", int_type = name, value = ?value);
+    let _entered = span.enter();
+
+    let start_bits = sink.get_num_bools();
+    let result = write(sink);
+    let num_bits = sink.get_num_bools().saturating_sub(start_bits);
+    match &result {
+        Ok(()) => tracing::trace!(num_bits, "Encoded {}", name),
+        Err(error) => tracing::warn!(num_bits, error = %error, "Failed to encode {}", name),
+    }
+    result
+}
+
+fn traced_read<S: BitSource, T: Debug>(
+    name: &'static str,
+    source: &mut S,
+    read: impl FnOnce(&mut CountingBitSource<S>) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    let span = tracing::trace_span!("read", int_type = name);
+    let _entered = span.enter();
+
+    let mut counting_source = CountingBitSource::new(source);
+    let result = read(&mut counting_source);
+    let num_bits = counting_source.get_num_bits();
+    match &result {
+        Ok(value) => tracing::trace!(num_bits, value = ?value, "Decoded {}", name),
+        Err(error) => tracing::warn!(num_bits, error = %error, "Failed to decode {}", name),
+    }
+    result
+}
+
+impl<P: IntEncodingProtocol> IntEncodingProtocol for TracedProtocol<P> {
+    fn write_u8(&self, sink: &mut impl BitSink, value: u8) -> Result<(), WriteError> {
+        traced_write("u8", sink, value, |sink| {
+            self.protocol.write_u8(sink, value)
+        })
+    }
+
+    fn write_i8(&self, sink: &mut impl BitSink, value: i8) -> Result<(), WriteError> {
+        traced_write("i8", sink, value, |sink| {
+            self.protocol.write_i8(sink, value)
+        })
+    }
+
+    fn write_u16(&self, sink: &mut impl BitSink, value: u16) -> Result<(), WriteError> {
+        traced_write("u16", sink, value, |sink| {
+            self.protocol.write_u16(sink, value)
+        })
+    }
+
+    fn write_i16(&self, sink: &mut impl BitSink, value: i16) -> Result<(), WriteError> {
+        traced_write("i16", sink, value, |sink| {
+            self.protocol.write_i16(sink, value)
+        })
+    }
+
+    fn write_u32(&self, sink: &mut impl BitSink, value: u32) -> Result<(), WriteError> {
+        traced_write("u32", sink, value, |sink| {
+            self.protocol.write_u32(sink, value)
+        })
+    }
+
+    fn write_i32(&self, sink: &mut impl BitSink, value: i32) -> Result<(), WriteError> {
+        traced_write("i32", sink, value, |sink| {
+            self.protocol.write_i32(sink, value)
+        })
+    }
+
+    fn write_u64(&self, sink: &mut impl BitSink, value: u64) -> Result<(), WriteError> {
+        traced_write("u64", sink, value, |sink| {
+            self.protocol.write_u64(sink, value)
+        })
+    }
+
+    fn write_i64(&self, sink: &mut impl BitSink, value: i64) -> Result<(), WriteError> {
+        traced_write("i64", sink, value, |sink| {
+            self.protocol.write_i64(sink, value)
+        })
+    }
+
+    fn write_u128(&self, sink: &mut impl BitSink, value: u128) -> Result<(), WriteError> {
+        traced_write("u128", sink, value, |sink| {
+            self.protocol.write_u128(sink, value)
+        })
+    }
+
+    fn write_i128(&self, sink: &mut impl BitSink, value: i128) -> Result<(), WriteError> {
+        traced_write("i128", sink, value, |sink| {
+            self.protocol.write_i128(sink, value)
+        })
+    }
+
+    fn encoded_size_u8(&self, value: u8) -> u32 {
+        self.protocol.encoded_size_u8(value)
+    }
+
+    fn encoded_size_i8(&self, value: i8) -> u32 {
+        self.protocol.encoded_size_i8(value)
+    }
+
+    fn encoded_size_u16(&self, value: u16) -> u32 {
+        self.protocol.encoded_size_u16(value)
+    }
+
+    fn encoded_size_i16(&self, value: i16) -> u32 {
+        self.protocol.encoded_size_i16(value)
+    }
+
+    fn encoded_size_u32(&self, value: u32) -> u32 {
+        self.protocol.encoded_size_u32(value)
+    }
+
+    fn encoded_size_i32(&self, value: i32) -> u32 {
+        self.protocol.encoded_size_i32(value)
+    }
+
+    fn encoded_size_u64(&self, value: u64) -> u32 {
+        self.protocol.encoded_size_u64(value)
+    }
+
+    fn encoded_size_i64(&self, value: i64) -> u32 {
+        self.protocol.encoded_size_i64(value)
+    }
+
+    fn encoded_size_u128(&self, value: u128) -> u32 {
+        self.protocol.encoded_size_u128(value)
+    }
+
+    fn encoded_size_i128(&self, value: i128) -> u32 {
+        self.protocol.encoded_size_i128(value)
+    }
+}
+
+impl<P: IntDecodingProtocol> IntDecodingProtocol for TracedProtocol<P> {
+    fn read_u8(&self, source: &mut impl BitSource) -> Result<u8, DecodeError> {
+        traced_read("u8", source, |source| self.protocol.read_u8(source))
+    }
+
+    fn read_i8(&self, source: &mut impl BitSource) -> Result<i8, DecodeError> {
+        traced_read("i8", source, |source| self.protocol.read_i8(source))
+    }
+
+    fn read_u16(&self, source: &mut impl BitSource) -> Result<u16, DecodeError> {
+        traced_read("u16", source, |source| self.protocol.read_u16(source))
+    }
+
+    fn read_i16(&self, source: &mut impl BitSource) -> Result<i16, DecodeError> {
+        traced_read("i16", source, |source| self.protocol.read_i16(source))
+    }
+
+    fn read_u32(&self, source: &mut impl BitSource) -> Result<u32, DecodeError> {
+        traced_read("u32", source, |source| self.protocol.read_u32(source))
+    }
+
+    fn read_i32(&self, source: &mut impl BitSource) -> Result<i32, DecodeError> {
+        traced_read("i32", source, |source| self.protocol.read_i32(source))
+    }
+
+    fn read_u64(&self, source: &mut impl BitSource) -> Result<u64, DecodeError> {
+        traced_read("u64", source, |source| self.protocol.read_u64(source))
+    }
+
+    fn read_i64(&self, source: &mut impl BitSource) -> Result<i64, DecodeError> {
+        traced_read("i64", source, |source| self.protocol.read_i64(source))
+    }
+
+    fn read_u128(&self, source: &mut impl BitSource) -> Result<u128, DecodeError> {
+        traced_read("u128", source, |source| self.protocol.read_u128(source))
+    }
+
+    fn read_i128(&self, source: &mut impl BitSource) -> Result<i128, DecodeError> {
+        traced_read("i128", source, |source| self.protocol.read_i128(source))
+    }
+
+    fn skip(&self, source: &mut impl BitSource, int_type: IntType) -> Result<(), DecodeError> {
+        traced_read("skip", source, |source| {
+            self.protocol.skip(source, int_type)
+        })
+    }
+}
+
+/// A *BitSource* that wraps another *BitSource*, keeps track of the number of
+/// bits that have been read, and emits a *tracing* event whenever a read fails.
+pub struct TracedBitSource<S: BitSource> {
+    source: S,
+    offset: u64,
+}
+
+impl<S: BitSource> TracedBitSource<S> {
+    /// Wraps *source*
+    pub fn new(source: S) -> Self {
+        Self { source, offset: 0 }
+    }
+
+    /// Gets the number of bits that have been read successfully from this source
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Consumes this *TracedBitSource* and returns the wrapped *BitSource*
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: BitSource> BitSource for TracedBitSource<S> {
+    fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+        let result = self.source.read(dest);
+        match &result {
+            Ok(()) => self.offset += dest.len() as u64,
+            Err(error) => {
+                tracing::warn!(
+                    offset = self.offset,
+                    requested_bits = dest.len(),
+                    error = %error,
+                    "Failed to read from BitSource"
+                );
+                if let ReadError::ReachedEnd { read_bools } = error {
+                    self.offset += *read_bools as u64;
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A *BitSink* that wraps another *BitSink* and emits *tracing* events when it
+/// is finished or when a write fails.
+pub struct TracedBitSink<S: BitSink> {
+    sink: S,
+}
+
+impl<S: BitSink> TracedBitSink<S> {
+    /// Wraps *sink*
+    pub fn new(sink: S) -> Self {
+        Self { sink }
+    }
+
+    /// Consumes this *TracedBitSink* and returns the wrapped *BitSink*
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S: BitSink> BitSink for TracedBitSink<S> {
+    fn write(&mut self, bits: &[bool]) -> Result<(), WriteError> {
+        let result = self.sink.write(bits);
+        if let Err(error) = &result {
+            tracing::warn!(
+                offset = self.sink.get_num_bools(),
+                num_bits = bits.len(),
+                error = %error,
+                "Failed to write to BitSink"
+            );
+        }
+        result
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        let result = self.sink.finish();
+        match &result {
+            Ok(()) => tracing::debug!(num_bits = self.sink.get_num_bools(), "Finished BitSink"),
+            Err(error) => tracing::warn!(
+                num_bits = self.sink.get_num_bools(),
+                error = %error,
+                "Failed to finish BitSink"
+            ),
+        }
+        result
+    }
+
+    fn get_num_bools(&self) -> u64 {
+        self.sink.get_num_bools()
+    }
+
+    fn capacity_bits(&self) -> Option<u64> {
+        self.sink.capacity_bits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// A minimal subscriber that stores all fields of all events as strings
+    struct CollectingSubscriber {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct FieldCollector(String);
+
+    impl Visit for FieldCollector {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0 += &format!("{}={:?} ", field.name(), value);
+        }
+    }
+
+    impl Subscriber for CollectingSubscriber {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut collector = FieldCollector(String::new());
+            event.record(&mut collector);
+            self.events.lock().unwrap().push(collector.0);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn collect_events(action: impl FnOnce()) -> Vec<String> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = CollectingSubscriber {
+            events: Arc::clone(&events),
+        };
+        tracing::subscriber::with_default(subscriber, action);
+        let events = events.lock().unwrap();
+        events.clone()
+    }
+
+    #[test]
+    fn test_traced_pair() {
+        let encoder = TracedProtocol::new(DigitIntEncodingProtocol::v1());
+        let decoder = TracedProtocol::new(DigitIntDecodingProtocol::v1());
+        testkit::test_encoding_pair(&encoder, &decoder);
+    }
+
+    #[test]
+    fn test_decode_error_events() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let mut sink = BoolVecBitSink::new();
+        encoder.write_u32(&mut sink, 12345).unwrap();
+        let num_bits = sink.get_num_bools();
+
+        let events = collect_events(|| {
+            let decoder = TracedProtocol::new(DigitIntDecodingProtocol::v1());
+            let mut source = TracedBitSource::new(BoolSliceBitSource::new(sink.get_bits()));
+            decoder.read_u32(&mut source).unwrap();
+            assert!(decoder.read_u32(&mut source).is_err());
+        });
+
+        assert_eq!(3, events.len());
+        assert!(events[0].contains("Decoded u32"));
+        assert!(events[1].contains(&format!("offset={}", num_bits)));
+        assert!(events[2].contains("Failed to decode u32"));
+    }
+
+    #[test]
+    fn test_sink_events() {
+        let events = collect_events(|| {
+            let mut sink = TracedBitSink::new(ChunkedBitSink::new(1, |_, _| Ok(())));
+            sink.write(&[true; 12]).unwrap();
+            sink.finish().unwrap();
+        });
+
+        assert_eq!(3, events.len());
+        assert!(events[0].contains("chunk_index=0"));
+        assert!(events[1].contains("chunk_index=1"));
+        assert!(events[2].contains("num_bits=12"));
+    }
+}
@@ -0,0 +1,327 @@
+use crate::*;
+
+/// A reversible transformation of a stream of symbols, which can be inserted
+/// between the source of the symbols and an entropy coder to make the symbols
+/// easier to compress. Transforms can be composed with *then*.
+pub trait SymbolTransform {
+    /// Gets the number of distinct symbols that *forward* can output when its
+    /// input symbols are all smaller than *input_alphabet_size*
+    fn get_output_alphabet_size(&self, input_alphabet_size: u32) -> u32;
+
+    /// Transforms *symbols*. Returns an error if any of the symbols is invalid
+    /// for this transform.
+    fn forward(&self, symbols: &[u32]) -> Result<Vec<u32>, WriteError>;
+
+    /// Reverses *forward*. The memory for the output is claimed from *context*.
+    /// Returns an error if *symbols* could not have been produced by *forward*.
+    fn inverse(
+        &self,
+        symbols: &[u32],
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError>;
+
+    /// Creates a transform that applies *self* first, and *next* after that
+    fn then<T: SymbolTransform>(self, next: T) -> ChainedTransform<Self, T>
+    where
+        Self: Sized,
+    {
+        ChainedTransform {
+            first: self,
+            second: next,
+        }
+    }
+}
+
+/// Applies 2 transforms after each other. This is created by
+/// *SymbolTransform::then*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainedTransform<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: SymbolTransform, B: SymbolTransform> SymbolTransform for ChainedTransform<A, B> {
+    fn get_output_alphabet_size(&self, input_alphabet_size: u32) -> u32 {
+        self.second
+            .get_output_alphabet_size(self.first.get_output_alphabet_size(input_alphabet_size))
+    }
+
+    fn forward(&self, symbols: &[u32]) -> Result<Vec<u32>, WriteError> {
+        self.second.forward(&self.first.forward(symbols)?)
+    }
+
+    fn inverse(
+        &self,
+        symbols: &[u32],
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        let intermediate = self.second.inverse(symbols, context)?;
+        self.first.inverse(&intermediate, context)
+    }
+}
+
+fn invalid_symbol(symbol: u32, alphabet_size: u32) -> String {
+    format!(
+        "Symbol {} is not part of the alphabet of size {}",
+        symbol, alphabet_size
+    )
+}
+
+/// The move-to-front transform: every symbol is replaced by its position in a
+/// list of all symbols, after which the symbol is moved to the front of that
+/// list. Recently used symbols will thus be replaced by small numbers, and
+/// repeated symbols will be replaced by 0.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mtf = MoveToFront::new(4);
+/// assert_eq!(vec![2, 0, 0, 1, 3, 0], mtf.forward(&[2, 2, 2, 0, 3, 3]).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveToFront {
+    alphabet_size: u32,
+}
+
+impl MoveToFront {
+    /// Constructs a move-to-front transform for symbols smaller than
+    /// *alphabet_size*
+    pub fn new(alphabet_size: u32) -> Self {
+        Self { alphabet_size }
+    }
+
+    /// Gets the number of symbols in the alphabet
+    pub fn get_alphabet_size(&self) -> u32 {
+        self.alphabet_size
+    }
+
+    fn initial_list(&self) -> Vec<u32> {
+        (0..self.alphabet_size).collect()
+    }
+}
+
+impl SymbolTransform for MoveToFront {
+    fn get_output_alphabet_size(&self, _input_alphabet_size: u32) -> u32 {
+        self.alphabet_size
+    }
+
+    fn forward(&self, symbols: &[u32]) -> Result<Vec<u32>, WriteError> {
+        let mut list = self.initial_list();
+        let mut output = Vec::with_capacity(symbols.len());
+        for &symbol in symbols {
+            let position = match list.iter().position(|candidate| *candidate == symbol) {
+                Some(position) => position,
+                None => return Err(invalid_symbol(symbol, self.alphabet_size).into()),
+            };
+            list[..=position].rotate_right(1);
+            output.push(position as u32);
+        }
+        Ok(output)
+    }
+
+    fn inverse(
+        &self,
+        symbols: &[u32],
+        _context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        let mut list = self.initial_list();
+        let mut output = Vec::with_capacity(symbols.len());
+        for &position in symbols {
+            if position >= self.alphabet_size {
+                return Err(DecodeError::Other(
+                    invalid_symbol(position, self.alphabet_size).into(),
+                ));
+            }
+            let position = position as usize;
+            let symbol = list[position];
+            list[..=position].rotate_right(1);
+            output.push(symbol);
+        }
+        Ok(output)
+    }
+}
+
+/// The zero run-length transform (as used by bzip2): every run of zeros is
+/// replaced by its length, written in bijective base 2 with the digits *0*
+/// (RUNA) and *1* (RUNB), least significant digit first. All other symbols are
+/// incremented by 1. A run of length *n* thus takes about *log2(n)* symbols.
+///
+/// This transform works best after *MoveToFront*, which turns repeated symbols
+/// into runs of zeros.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let transform = MoveToFront::new(4).then(ZeroRunLength);
+/// let symbols = [3, 3, 3, 3, 3, 3, 3, 1, 1];
+/// let transformed = transform.forward(&symbols).unwrap();
+/// assert_eq!(vec![4, 1, 1, 3, 0], transformed);
+/// assert_eq!(5, transform.get_output_alphabet_size(4));
+///
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// assert_eq!(symbols.to_vec(), transform.inverse(&transformed, &mut context).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ZeroRunLength;
+
+const RUN_A: u32 = 0;
+const RUN_B: u32 = 1;
+
+fn write_zero_run(output: &mut Vec<u32>, mut length: u64) {
+    while length > 0 {
+        if length % 2 == 1 {
+            output.push(RUN_A);
+            length = (length - 1) / 2;
+        } else {
+            output.push(RUN_B);
+            length = (length - 2) / 2;
+        }
+    }
+}
+
+fn run_overflow() -> DecodeError {
+    DecodeError::Other("The length of a run of zeros is too large".into())
+}
+
+impl SymbolTransform for ZeroRunLength {
+    fn get_output_alphabet_size(&self, input_alphabet_size: u32) -> u32 {
+        input_alphabet_size + 1
+    }
+
+    fn forward(&self, symbols: &[u32]) -> Result<Vec<u32>, WriteError> {
+        let mut output = Vec::new();
+        let mut run_length = 0;
+        for &symbol in symbols {
+            if symbol == 0 {
+                run_length += 1;
+            } else {
+                if symbol == u32::MAX {
+                    return Err(invalid_symbol(symbol, u32::MAX).into());
+                }
+                write_zero_run(&mut output, run_length);
+                run_length = 0;
+                output.push(symbol + 1);
+            }
+        }
+        write_zero_run(&mut output, run_length);
+        Ok(output)
+    }
+
+    fn inverse(
+        &self,
+        symbols: &[u32],
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        // Compute the output length first, so that it can be checked before
+        // anything is allocated
+        let mut output_length: u64 = 0;
+        let mut digit_weight: u64 = 1;
+        for &symbol in symbols {
+            if symbol == RUN_A || symbol == RUN_B {
+                let digit_value = (symbol as u64 + 1)
+                    .checked_mul(digit_weight)
+                    .ok_or_else(run_overflow)?;
+                output_length = output_length
+                    .checked_add(digit_value)
+                    .ok_or_else(run_overflow)?;
+                digit_weight = digit_weight.checked_mul(2).ok_or_else(run_overflow)?;
+            } else {
+                output_length = output_length.checked_add(1).ok_or_else(run_overflow)?;
+                digit_weight = 1;
+            }
+        }
+
+        let mut output = Vec::with_capacity(context.check_vec_len::<u32>(output_length)?);
+        let mut run_length = 0;
+        digit_weight = 1;
+        for &symbol in symbols {
+            if symbol == RUN_A || symbol == RUN_B {
+                run_length += (symbol as u64 + 1) * digit_weight;
+                digit_weight *= 2;
+            } else {
+                output.resize(output.len() + run_length as usize, 0);
+                run_length = 0;
+                digit_weight = 1;
+                output.push(symbol - 1);
+            }
+        }
+        output.resize(output.len() + run_length as usize, 0);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn new_context() -> DecodeContext {
+        DecodeContext::new(DecodeLimits::new())
+    }
+
+    fn check_round_trip(transform: &impl SymbolTransform, symbols: &[u32]) -> Vec<u32> {
+        let transformed = transform.forward(symbols).unwrap();
+        assert_eq!(
+            symbols.to_vec(),
+            transform.inverse(&transformed, &mut new_context()).unwrap()
+        );
+        transformed
+    }
+
+    #[test]
+    fn test_move_to_front() {
+        let mtf = MoveToFront::new(5);
+        assert_eq!(
+            vec![1, 1, 0, 4, 1, 2],
+            check_round_trip(&mtf, &[1, 0, 0, 4, 0, 1])
+        );
+        assert_eq!(Vec::<u32>::new(), check_round_trip(&mtf, &[]));
+        assert!(mtf.forward(&[1, 5]).is_err());
+        assert!(mtf.inverse(&[5], &mut new_context()).is_err());
+    }
+
+    #[test]
+    fn test_zero_run_length() {
+        let rle = ZeroRunLength;
+        assert_eq!(vec![0], check_round_trip(&rle, &[0]));
+        assert_eq!(vec![1], check_round_trip(&rle, &[0, 0]));
+        assert_eq!(vec![0, 0], check_round_trip(&rle, &[0, 0, 0]));
+        assert_eq!(vec![1, 0], check_round_trip(&rle, &[0; 4]));
+        assert_eq!(vec![0, 1, 0], check_round_trip(&rle, &[0; 9]));
+        assert_eq!(vec![3, 1, 6, 0], check_round_trip(&rle, &[2, 0, 0, 5, 0]));
+        for length in 0..300 {
+            let symbols = vec![0; length];
+            let transformed = check_round_trip(&rle, &symbols);
+            assert!(transformed.len() <= 9);
+        }
+        assert!(rle.forward(&[u32::MAX]).is_err());
+    }
+
+    #[test]
+    fn test_zero_run_length_limits() {
+        // 40 RUNB symbols would decode to more than 2^40 zeros
+        let bomb = vec![1; 40];
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_vec_len(1000));
+        assert!(ZeroRunLength.inverse(&bomb, &mut context).is_err());
+
+        let overflow = vec![0; 70];
+        assert!(ZeroRunLength
+            .inverse(&overflow, &mut new_context())
+            .is_err());
+    }
+
+    #[test]
+    fn test_chain() {
+        let transform = MoveToFront::new(256).then(ZeroRunLength);
+        assert_eq!(257, transform.get_output_alphabet_size(256));
+
+        let mut symbols = Vec::new();
+        for index in 0..1000u32 {
+            symbols.push((index / 50) * 7 % 256);
+        }
+        let transformed = check_round_trip(&transform, &symbols);
+        assert!(transformed.len() < 200);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A node in the parse tree of a *TunstallCode*. Internal nodes have 1 child
+/// per symbol of the alphabet, and every leaf corresponds to a codeword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrieNode {
+    children: Vec<usize>,
+    codeword: u32,
+}
+
+/// A leaf that could be expanded while building the code, ordered by its
+/// probability
+struct Candidate {
+    probability: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Prefer the most probable leaf, and the oldest one in case of a tie
+        self.probability
+            .total_cmp(&other.probability)
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+/// A Tunstall code: a variable-to-fixed code that splits a sequence of symbols
+/// into words of variable length, and encodes every word as a codeword of
+/// exactly *codeword_bits* bits. Since every codeword has the same size, the
+/// decoder only needs a table lookup per codeword, which makes this code
+/// suitable for receivers that need a fixed decoding rate.
+///
+/// The codebook is built from the frequencies of the symbols: starting with 1
+/// word per symbol, the most probable word is repeatedly replaced by all its
+/// extensions with 1 more symbol, until there is no room left for more
+/// codewords. Both sides must build the code from the same frequencies.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // Symbol 0 is much more common than symbols 1 and 2
+/// let code = TunstallCode::new(&[90, 7, 3], 4);
+/// assert!(code.get_num_codewords() <= 16);
+///
+/// let symbols = [0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 2, 0, 0, 0, 0];
+/// let mut sink = BoolVecBitSink::new();
+/// code.write(&DigitIntEncodingProtocol::v1(), &mut sink, &symbols).unwrap();
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = code.read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(symbols.to_vec(), decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunstallCode {
+    codeword_bits: u8,
+    nodes: Vec<TrieNode>,
+    words: Vec<Vec<u32>>,
+}
+
+impl TunstallCode {
+    /// Builds the Tunstall code for an alphabet with *frequencies.len()*
+    /// symbols, where symbol *i* occurs (approximately) *frequencies[i]* times,
+    /// and every codeword takes *codeword_bits* bits. When all frequencies are
+    /// 0, all symbols are considered equally likely.
+    ///
+    /// Panics if the alphabet has less than 2 symbols, if *codeword_bits* is
+    /// larger than 20, or if *2^codeword_bits* is smaller than the size of the
+    /// alphabet.
+    pub fn new(frequencies: &[u64], codeword_bits: u8) -> Self {
+        let alphabet_size = frequencies.len();
+        if alphabet_size < 2 {
+            panic!("The alphabet must have at least 2 symbols");
+        }
+        if codeword_bits > 20 {
+            panic!("The number of codeword bits must be at most 20");
+        }
+        let max_codewords = 1usize << codeword_bits;
+        if max_codewords < alphabet_size {
+            panic!(
+                "{} codeword bits are not enough for {} symbols",
+                codeword_bits, alphabet_size
+            );
+        }
+
+        let total: u64 = frequencies.iter().sum();
+        let probabilities: Vec<f64> = frequencies
+            .iter()
+            .map(|frequency| {
+                if total == 0 {
+                    1.0 / alphabet_size as f64
+                } else {
+                    *frequency as f64 / total as f64
+                }
+            })
+            .collect();
+
+        let mut nodes = vec![TrieNode {
+            children: Vec::new(),
+            codeword: 0,
+        }];
+        let mut candidates = BinaryHeap::new();
+        candidates.push(Candidate {
+            probability: 1.0,
+            node: 0,
+        });
+        let mut num_words = 1;
+        while num_words + alphabet_size - 1 <= max_codewords {
+            let expanded = candidates.pop().unwrap();
+            for probability in &probabilities {
+                let child = nodes.len();
+                candidates.push(Candidate {
+                    probability: expanded.probability * probability,
+                    node: child,
+                });
+                nodes[expanded.node].children.push(child);
+                nodes.push(TrieNode {
+                    children: Vec::new(),
+                    codeword: 0,
+                });
+            }
+            num_words += alphabet_size - 1;
+        }
+
+        let mut code = Self {
+            codeword_bits,
+            nodes,
+            words: Vec::with_capacity(num_words),
+        };
+        code.assign_codewords(0, &mut Vec::new());
+        code
+    }
+
+    fn assign_codewords(&mut self, node: usize, path: &mut Vec<u32>) {
+        if self.nodes[node].children.is_empty() {
+            self.nodes[node].codeword = self.words.len() as u32;
+            self.words.push(path.clone());
+        } else {
+            for symbol in 0..self.nodes[node].children.len() {
+                path.push(symbol as u32);
+                self.assign_codewords(self.nodes[node].children[symbol], path);
+                path.pop();
+            }
+        }
+    }
+
+    /// Gets the number of symbols in the alphabet
+    pub fn get_alphabet_size(&self) -> u32 {
+        self.nodes[0].children.len() as u32
+    }
+
+    /// Gets the number of bits of every codeword
+    pub fn get_codeword_bits(&self) -> u8 {
+        self.codeword_bits
+    }
+
+    /// Gets the number of codewords in the codebook, which is at most
+    /// *2^codeword_bits*
+    pub fn get_num_codewords(&self) -> u32 {
+        self.words.len() as u32
+    }
+
+    /// Gets the sequence of symbols that is represented by *codeword*, or
+    /// *None* if *codeword* is not part of the codebook
+    pub fn get_word(&self, codeword: u32) -> Option<&[u32]> {
+        self.words.get(codeword as usize).map(|word| &word[..])
+    }
+
+    fn write_codeword(&self, sink: &mut impl BitSink, node: usize) -> Result<(), WriteError> {
+        SimpleIntEncodingProtocol::new().write_unsigned(
+            sink,
+            self.codeword_bits as usize,
+            self.nodes[node].codeword as u128,
+        )
+    }
+
+    /// Encodes *symbols* and writes the result to *sink*. The number of symbols
+    /// is encoded with *protocol*, after which the codewords are written. If the
+    /// last word is incomplete, it is padded with arbitrary symbols, which the
+    /// decoder will discard. Returns an error if any of the symbols is not part
+    /// of the alphabet.
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        symbols: &[u32],
+    ) -> Result<(), WriteError> {
+        if let Some(symbol) = symbols
+            .iter()
+            .find(|symbol| **symbol >= self.get_alphabet_size())
+        {
+            return Err(format!(
+                "Symbol {} is not part of the alphabet of size {}",
+                symbol,
+                self.get_alphabet_size()
+            )
+            .into());
+        }
+
+        protocol.write_u64(sink, symbols.len() as u64)?;
+        let mut node = 0;
+        for symbol in symbols {
+            node = self.nodes[node].children[*symbol as usize];
+            if self.nodes[node].children.is_empty() {
+                self.write_codeword(sink, node)?;
+                node = 0;
+            }
+        }
+        if node != 0 {
+            while !self.nodes[node].children.is_empty() {
+                node = self.nodes[node].children[0];
+            }
+            self.write_codeword(sink, node)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes symbols that were encoded by *write*. The number of symbols is
+    /// checked against the limits of *context* before anything is allocated.
+    /// Returns *DecodeError::Other* when a codeword is not part of the codebook.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u32>, DecodeError> {
+        let length = context.check_vec_len::<u32>(protocol.read_u64(source)?)?;
+        let mut symbols = Vec::with_capacity(length);
+        let reader = SimpleIntDecodingProtocol::new();
+        while symbols.len() < length {
+            let codeword = reader.read_unsigned(source, self.codeword_bits as usize)? as u32;
+            let word = match self.get_word(codeword) {
+                Some(word) => word,
+                None => {
+                    return Err(DecodeError::Other(
+                        format!("Unknown codeword {}", codeword).into(),
+                    ))
+                }
+            };
+            let remaining = length - symbols.len();
+            symbols.extend_from_slice(&word[..word.len().min(remaining)]);
+        }
+        Ok(symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(code: &TunstallCode, symbols: &[u32]) -> u64 {
+        let mut sink = BoolVecBitSink::new();
+        code.write(&DigitIntEncodingProtocol::v1(), &mut sink, symbols)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = code
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .unwrap();
+        assert_eq!(symbols, &decoded[..]);
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_codebook() {
+        let code = TunstallCode::new(&[7, 3], 3);
+        assert_eq!(2, code.get_alphabet_size());
+        assert_eq!(3, code.get_codeword_bits());
+        assert_eq!(8, code.get_num_codewords());
+
+        // The words must form a complete prefix-free set
+        let mut total_probability = 0.0;
+        for codeword in 0..code.get_num_codewords() {
+            let word = code.get_word(codeword).unwrap();
+            total_probability += word
+                .iter()
+                .map(|symbol| if *symbol == 0 { 0.7 } else { 0.3 })
+                .product::<f64>();
+        }
+        assert!((total_probability - 1.0f64).abs() < 1e-9);
+        assert_eq!(None, code.get_word(8));
+
+        // The most probable word is expanded first
+        assert_eq!(Some(&[0, 0, 0, 0, 0][..]), code.get_word(0));
+
+        // 3 symbols with 3 bits allow only 7 codewords
+        assert_eq!(7, TunstallCode::new(&[1, 1, 1], 3).get_num_codewords());
+        assert_eq!(
+            5,
+            TunstallCode::new(&[0, 0, 0, 0, 0], 3).get_num_codewords()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let code = TunstallCode::new(&[80, 10, 5, 5], 6);
+        round_trip(&code, &[]);
+        round_trip(&code, &[3]);
+        round_trip(&code, &[0, 0, 0]);
+
+        let mut symbols = Vec::new();
+        let mut state = 98765u32;
+        for _ in 0..10_000 {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            let random = (state >> 16) % 100;
+            symbols.push(match random {
+                0..=79 => 0,
+                80..=89 => 1,
+                90..=94 => 2,
+                _ => 3,
+            });
+        }
+        let num_bits = round_trip(&code, &symbols);
+
+        // A fixed-length code would need 2 bits per symbol
+        assert!(num_bits < symbols.len() as u64 * 3 / 2);
+
+        for end in 0..20 {
+            round_trip(&code, &symbols[..end]);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let code = TunstallCode::new(&[5, 1, 1], 2);
+        assert_eq!(3, code.get_num_codewords());
+
+        let mut sink = BoolVecBitSink::new();
+        assert!(code
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, &[0, 3])
+            .is_err());
+        assert_eq!(0, sink.get_num_bools());
+
+        DigitIntEncodingProtocol::v1()
+            .write_u64(&mut sink, 1)
+            .unwrap();
+        sink.write(&[true, true]).unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert!(code
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_few_bits() {
+        TunstallCode::new(&[1, 1, 1, 1, 1], 2);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::*;
+
+/// A value that can be encoded in several ways, depending on some parameters
+/// (like digit sizes, bit widths or dictionaries). *write_two_pass* uses this
+/// to find the parameters that give the smallest encoding, so that they don't
+/// need to be tuned manually.
+pub trait TwoPassEncode {
+    /// The parameters that determine how the value is encoded
+    type Parameters;
+
+    /// Gets the parameters that *write_two_pass* should try. This is allowed to
+    /// depend on the value (for instance, to build a dictionary from it).
+    fn get_candidate_parameters(&self) -> Vec<Self::Parameters>;
+
+    /// Writes *parameters* to *sink*, so that the decoder knows how to decode
+    /// the value
+    fn write_parameters(
+        parameters: &Self::Parameters,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError>;
+
+    /// Encodes this value using *parameters*, and writes it to *sink*
+    fn write_with(
+        &self,
+        parameters: &Self::Parameters,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError>;
+}
+
+/// The counterpart of *TwoPassEncode*, which reads the values that were written
+/// by *write_two_pass*
+pub trait TwoPassDecode: Sized {
+    /// The parameters that determine how the value was encoded
+    type Parameters;
+
+    /// Reads the parameters that were written by *TwoPassEncode::write_parameters*
+    fn read_parameters(source: &mut impl BitSource) -> Result<Self::Parameters, DecodeError>;
+
+    /// Reads a value that was encoded using *parameters*
+    fn read_with(
+        parameters: &Self::Parameters,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError>;
+}
+
+/// Encodes *value* in 2 passes. During the first pass, *value* is encoded with
+/// every candidate parameter set into a *CountingBitSink*, to measure which one
+/// gives the smallest encoding (including the parameters themselves). During
+/// the second pass, the best parameters and the value are written to *sink*.
+/// When multiple candidates are equally good, the first one is chosen.
+///
+/// Returns the chosen parameters, or an error if there are no candidates or if
+/// encoding fails. The result can be decoded with *read_two_pass*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// struct Samples(Vec<u64>);
+///
+/// impl TwoPassEncode for Samples {
+///     type Parameters = DigitIntParameters;
+///
+///     fn get_candidate_parameters(&self) -> Vec<DigitIntParameters> {
+///         DigitIntParameters::all_candidates()
+///     }
+///
+///     fn write_parameters(parameters: &DigitIntParameters, sink: &mut impl BitSink) -> Result<(), WriteError> {
+///         parameters.write(sink)
+///     }
+///
+///     fn write_with(&self, parameters: &DigitIntParameters, sink: &mut impl BitSink) -> Result<(), WriteError> {
+///         parameters.get_encoder().write_u64_slice(sink, &self.0)
+///     }
+/// }
+///
+/// impl TwoPassDecode for Samples {
+///     type Parameters = DigitIntParameters;
+///
+///     fn read_parameters(source: &mut impl BitSource) -> Result<DigitIntParameters, DecodeError> {
+///         DigitIntParameters::read(source)
+///     }
+///
+///     fn read_with(
+///         parameters: &DigitIntParameters, source: &mut impl BitSource, context: &mut DecodeContext
+///     ) -> Result<Self, DecodeError> {
+///         Ok(Samples(parameters.get_decoder().read_u64_vec(source, context)?))
+///     }
+/// }
+///
+/// // Most samples are 0 or 1, so short_zero_and_one is worth it
+/// let samples = Samples((0..100).map(|index| index % 2).collect());
+/// let mut sink = BoolVecBitSink::new();
+/// let parameters = write_two_pass(&samples, &mut sink).unwrap();
+/// assert!(parameters.is_short_zero_and_one());
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded: Samples = read_two_pass(&mut source, &mut context).unwrap();
+/// assert_eq!(samples.0, decoded.0);
+/// ```
+pub fn write_two_pass<T: TwoPassEncode>(
+    value: &T,
+    sink: &mut impl BitSink,
+) -> Result<T::Parameters, WriteError> {
+    let mut best: Option<(T::Parameters, u64)> = None;
+    for parameters in value.get_candidate_parameters() {
+        let mut counter = CountingBitSink::new();
+        T::write_parameters(&parameters, &mut counter)?;
+        value.write_with(&parameters, &mut counter)?;
+        let size = counter.get_num_bools();
+        if !matches!(&best, Some((_, best_size)) if *best_size <= size) {
+            best = Some((parameters, size));
+        }
+    }
+
+    let parameters = match best {
+        Some((parameters, _)) => parameters,
+        None => return Err("There are no candidate parameters".into()),
+    };
+    T::write_parameters(&parameters, sink)?;
+    value.write_with(&parameters, sink)?;
+    Ok(parameters)
+}
+
+/// Reads a value that was written by *write_two_pass*: first its parameters,
+/// and then the value itself
+pub fn read_two_pass<T: TwoPassDecode>(
+    source: &mut impl BitSource,
+    context: &mut DecodeContext,
+) -> Result<T, DecodeError> {
+    let parameters = T::read_parameters(source)?;
+    T::read_with(&parameters, source, context)
+}
+
+/// The parameters of a *DigitIntEncodingProtocol*, which are a common choice of
+/// parameters for *TwoPassEncode*. They are written using 6 bits for the digit
+/// size and 1 bit for *short_zero_and_one*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DigitIntParameters {
+    digit_size: u8,
+    short_zero_and_one: bool,
+}
+
+impl DigitIntParameters {
+    /// The largest digit size that can be stored in *DigitIntParameters*
+    pub const MAX_DIGIT_SIZE: u8 = 63;
+
+    /// Constructs new *DigitIntParameters*.
+    ///
+    /// Panics if *digit_size* is smaller than 2 or larger than *MAX_DIGIT_SIZE*.
+    pub fn new(digit_size: u8, short_zero_and_one: bool) -> Self {
+        if !(2..=Self::MAX_DIGIT_SIZE).contains(&digit_size) {
+            panic!("Invalid digit size {}", digit_size);
+        }
+        Self {
+            digit_size,
+            short_zero_and_one,
+        }
+    }
+
+    /// Gets all combinations of digit sizes from 2 to 16 and both values of
+    /// *short_zero_and_one*, which are good candidates for most data
+    pub fn all_candidates() -> Vec<Self> {
+        let mut candidates = Vec::new();
+        for digit_size in 2..=16 {
+            for &short_zero_and_one in &[false, true] {
+                candidates.push(Self::new(digit_size, short_zero_and_one));
+            }
+        }
+        candidates
+    }
+
+    /// Gets the digit size
+    pub fn get_digit_size(&self) -> u8 {
+        self.digit_size
+    }
+
+    /// Checks whether 0 and 1 get a shorter encoding
+    pub fn is_short_zero_and_one(&self) -> bool {
+        self.short_zero_and_one
+    }
+
+    /// Constructs the encoder with these parameters
+    pub fn get_encoder(&self) -> DigitIntEncodingProtocol {
+        DigitIntEncodingProtocol::new(self.digit_size, self.short_zero_and_one)
+    }
+
+    /// Constructs the decoder with these parameters
+    pub fn get_decoder(&self) -> DigitIntDecodingProtocol {
+        DigitIntDecodingProtocol::new(self.digit_size, self.short_zero_and_one)
+    }
+
+    /// Writes these parameters to *sink*
+    pub fn write(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        SimpleIntEncodingProtocol::new().write_unsigned(sink, 6, self.digit_size as u128)?;
+        sink.write(&[self.short_zero_and_one])
+    }
+
+    /// Reads parameters that were written by *write*. Returns
+    /// *DecodeError::Other* if the digit size is invalid.
+    pub fn read(source: &mut impl BitSource) -> Result<Self, DecodeError> {
+        let digit_size = SimpleIntDecodingProtocol::new().read_unsigned(source, 6)? as u8;
+        let mut short_zero_and_one = [false];
+        source.read(&mut short_zero_and_one)?;
+        if digit_size < 2 {
+            return Err(DecodeError::Other(
+                format!("Invalid digit size {}", digit_size).into(),
+            ));
+        }
+        Ok(Self::new(digit_size, short_zero_and_one[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    /// Encodes a list of bytes either raw or with a dictionary of at most 4
+    /// distinct bytes (2 bits per byte)
+    #[derive(Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl TwoPassEncode for Bytes {
+        type Parameters = Option<Vec<u8>>;
+
+        fn get_candidate_parameters(&self) -> Vec<Option<Vec<u8>>> {
+            let mut dictionary = self.0.clone();
+            dictionary.sort_unstable();
+            dictionary.dedup();
+            if dictionary.len() <= 4 {
+                vec![None, Some(dictionary)]
+            } else {
+                vec![None]
+            }
+        }
+
+        fn write_parameters(
+            parameters: &Option<Vec<u8>>,
+            sink: &mut impl BitSink,
+        ) -> Result<(), WriteError> {
+            let encoder = DigitIntEncodingProtocol::v1();
+            match parameters {
+                None => sink.write(&[false]),
+                Some(dictionary) => {
+                    sink.write(&[true])?;
+                    encoder.write_u8(sink, dictionary.len() as u8)?;
+                    let simple = SimpleIntEncodingProtocol::new();
+                    dictionary
+                        .iter()
+                        .try_for_each(|byte| simple.write_u8(sink, *byte))
+                }
+            }
+        }
+
+        fn write_with(
+            &self,
+            parameters: &Option<Vec<u8>>,
+            sink: &mut impl BitSink,
+        ) -> Result<(), WriteError> {
+            let simple = SimpleIntEncodingProtocol::new();
+            DigitIntEncodingProtocol::v1().write_u64(sink, self.0.len() as u64)?;
+            for byte in &self.0 {
+                match parameters {
+                    None => simple.write_u8(sink, *byte)?,
+                    Some(dictionary) => {
+                        let index = dictionary.iter().position(|entry| entry == byte).unwrap();
+                        simple.write_unsigned(sink, 2, index as u128)?
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl TwoPassDecode for Bytes {
+        type Parameters = Option<Vec<u8>>;
+
+        fn read_parameters(source: &mut impl BitSource) -> Result<Option<Vec<u8>>, DecodeError> {
+            let mut has_dictionary = [false];
+            source.read(&mut has_dictionary)?;
+            if has_dictionary[0] {
+                let length = DigitIntDecodingProtocol::v1().read_u8(source)?;
+                let simple = SimpleIntDecodingProtocol::new();
+                let mut dictionary = Vec::new();
+                for _ in 0..length {
+                    dictionary.push(simple.read_u8(source)?);
+                }
+                Ok(Some(dictionary))
+            } else {
+                Ok(None)
+            }
+        }
+
+        fn read_with(
+            parameters: &Option<Vec<u8>>,
+            source: &mut impl BitSource,
+            context: &mut DecodeContext,
+        ) -> Result<Self, DecodeError> {
+            let simple = SimpleIntDecodingProtocol::new();
+            let length =
+                context.check_vec_len::<u8>(DigitIntDecodingProtocol::v1().read_u64(source)?)?;
+            let mut bytes = Vec::with_capacity(length);
+            for _ in 0..length {
+                bytes.push(match parameters {
+                    None => simple.read_u8(source)?,
+                    Some(dictionary) => {
+                        let index = simple.read_unsigned(source, 2)? as usize;
+                        *dictionary
+                            .get(index)
+                            .ok_or_else(|| DecodeError::Other("Invalid index".into()))?
+                    }
+                });
+            }
+            Ok(Bytes(bytes))
+        }
+    }
+
+    fn round_trip(bytes: Bytes) -> (Option<Vec<u8>>, u64) {
+        let mut sink = BoolVecBitSink::new();
+        let parameters = write_two_pass(&bytes, &mut sink).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert_eq!(bytes, read_two_pass(&mut source, &mut context).unwrap());
+        (parameters, sink.get_num_bools())
+    }
+
+    #[test]
+    fn test_dictionary_choice() {
+        let (parameters, _) = round_trip(Bytes([b'a', b'c', b'g', b't'].repeat(20)));
+        assert_eq!(Some(vec![b'a', b'c', b'g', b't']), parameters);
+
+        // The dictionary isn't worth it for a single byte
+        let (parameters, _) = round_trip(Bytes(vec![12]));
+        assert_eq!(None, parameters);
+
+        let (parameters, _) = round_trip(Bytes((0..100).collect()));
+        assert_eq!(None, parameters);
+    }
+
+    #[test]
+    fn test_no_candidates() {
+        struct Nothing;
+
+        impl TwoPassEncode for Nothing {
+            type Parameters = ();
+
+            fn get_candidate_parameters(&self) -> Vec<()> {
+                Vec::new()
+            }
+
+            fn write_parameters(_: &(), _: &mut impl BitSink) -> Result<(), WriteError> {
+                Ok(())
+            }
+
+            fn write_with(&self, _: &(), _: &mut impl BitSink) -> Result<(), WriteError> {
+                Ok(())
+            }
+        }
+
+        assert!(write_two_pass(&Nothing, &mut BoolVecBitSink::new()).is_err());
+    }
+
+    #[test]
+    fn test_digit_int_parameters() {
+        let mut sink = BoolVecBitSink::new();
+        for parameters in DigitIntParameters::all_candidates() {
+            parameters.write(&mut sink).unwrap();
+        }
+        DigitIntParameters::new(DigitIntParameters::MAX_DIGIT_SIZE, true)
+            .write(&mut sink)
+            .unwrap();
+        sink.write(&[false; 7]).unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        for parameters in DigitIntParameters::all_candidates() {
+            assert_eq!(parameters, DigitIntParameters::read(&mut source).unwrap());
+        }
+        let max = DigitIntParameters::read(&mut source).unwrap();
+        assert_eq!(63, max.get_digit_size());
+        assert!(max.is_short_zero_and_one());
+        assert!(DigitIntParameters::read(&mut source).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::*;
+
+/// The error that *UntaggedDecoder::decode* returns (wrapped in a
+/// *DecodeError::Other*) when none of its alternatives matched the data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoMatchingAlternative {
+    /// For every alternative (in the same order as the alternatives), the
+    /// reason why it didn't match
+    pub reasons: Vec<String>,
+}
+
+impl std::fmt::Display for NoMatchingAlternative {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "None of the {} alternatives matched", self.reasons.len())?;
+        for (index, reason) in self.reasons.iter().enumerate() {
+            write!(f, "; alternative {}: {}", index, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NoMatchingAlternative {}
+
+/// The alternative that was chosen by *UntaggedDecoder::decode*
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntaggedMatch<T> {
+    /// The index of the alternative that matched
+    pub index: usize,
+    /// The value that was decoded by that alternative
+    pub value: T,
+    /// The number of bits that the alternative read from the source
+    pub num_bits: u64,
+}
+
+/// The signature of the alternatives of an *UntaggedDecoder*
+type Alternative<'a, S, T> = Box<dyn Fn(&mut S, &mut DecodeContext) -> Result<T, DecodeError> + 'a>;
+
+/// Decodes data that can have one of several layouts, without a discriminant
+/// that tells which layout was used. This is sometimes needed to read legacy
+/// formats, where the layout can only be determined by trying to decode it.
+///
+/// Every alternative is a function that tries to decode a value of type *T*.
+/// The alternatives are tried in the order in which they were added, each
+/// starting at the same position of the source. An alternative matches when it
+/// decodes a value without error and the validation callback accepts that
+/// value. The first alternative that matches wins, and the source is left
+/// right after the bits that it read. When an alternative doesn't match, the
+/// source is rewound before the next alternative is tried.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // Old records contain a u8 and a bool, new records contain a u16 and a u8
+/// let old_layout = Message::new()
+///     .int("id", IntType::U8, FieldEncoding::Simple)
+///     .bool("active");
+/// let new_layout = Message::new()
+///     .int("id", IntType::U16, FieldEncoding::Simple)
+///     .int("level", IntType::U8, FieldEncoding::Simple);
+///
+/// let mut sink = BoolVecBitSink::new();
+/// let values = [Value::Unsigned(1000), Value::Unsigned(5)];
+/// new_layout.write(&mut sink, &values).unwrap();
+///
+/// let decoder = UntaggedDecoder::new()
+///     .with_alternative(|source, context| old_layout.read(source, context))
+///     .with_alternative(|source, context| new_layout.read(source, context));
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// // The old layout can be decoded as well, but the validation callback knows
+/// // that it must be followed by the end of the data
+/// let total_bits = sink.get_bits().len() as u64;
+/// let result = decoder
+///     .decode(&mut source, &mut context, |_index, _values, num_bits| {
+///         num_bits == total_bits
+///     })
+///     .unwrap();
+/// assert_eq!(1, result.index);
+/// assert_eq!(values.to_vec(), result.value);
+/// ```
+pub struct UntaggedDecoder<'a, S: SeekableBitSource, T> {
+    alternatives: Vec<Alternative<'a, S, T>>,
+}
+
+impl<'a, S: SeekableBitSource, T> UntaggedDecoder<'a, S, T> {
+    /// Constructs a new *UntaggedDecoder* without any alternatives
+    pub fn new() -> Self {
+        Self {
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Adds an alternative that will be tried after all alternatives that
+    /// were added before it
+    pub fn with_alternative(
+        mut self,
+        alternative: impl Fn(&mut S, &mut DecodeContext) -> Result<T, DecodeError> + 'a,
+    ) -> Self {
+        self.alternatives.push(Box::new(alternative));
+        self
+    }
+
+    /// Gets the number of alternatives
+    pub fn get_num_alternatives(&self) -> usize {
+        self.alternatives.len()
+    }
+
+    /// Tries the alternatives in order, and returns the first one that
+    /// decodes a value that is accepted by *validate*. The validation callback
+    /// gets the index of the alternative, the decoded value, and the number of
+    /// bits that the alternative read.
+    ///
+    /// Alternatives that don't match don't consume any budget of *context*.
+    /// When no alternative matches, this returns a *NoMatchingAlternative*
+    /// (wrapped in a *DecodeError::Other*) and the source is rewound to where
+    /// it was. Errors of the source itself (other than reaching the end) and
+    /// errors while seeking are returned immediately.
+    pub fn decode(
+        &self,
+        source: &mut S,
+        context: &mut DecodeContext,
+        mut validate: impl FnMut(usize, &T, u64) -> bool,
+    ) -> Result<UntaggedMatch<T>, DecodeError> {
+        let start_position = source.position();
+        let mut reasons = Vec::with_capacity(self.alternatives.len());
+        for (index, alternative) in self.alternatives.iter().enumerate() {
+            let mut attempt_context = context.clone();
+            let result = alternative(source, &mut attempt_context);
+            let num_bits = source.position() - start_position;
+            match result {
+                Ok(value) => {
+                    if validate(index, &value, num_bits) {
+                        *context = attempt_context;
+                        return Ok(UntaggedMatch {
+                            index,
+                            value,
+                            num_bits,
+                        });
+                    }
+                    reasons.push("rejected by the validation callback".to_string());
+                }
+                Err(DecodeError::Reading(ReadError::Other(error))) => {
+                    return Err(DecodeError::Reading(ReadError::Other(error)));
+                }
+                Err(error) => reasons.push(error.to_string()),
+            }
+            source.seek_bits(start_position)?;
+        }
+        Err(DecodeError::Other(Box::new(NoMatchingAlternative {
+            reasons,
+        })))
+    }
+}
+
+impl<'a, S: SeekableBitSource, T> Default for UntaggedDecoder<'a, S, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::testkit::faults::*;
+    use crate::*;
+
+    fn create_data() -> Vec<bool> {
+        let mut sink = BoolVecBitSink::new();
+        let encoder = SimpleIntEncodingProtocol::new();
+        encoder.write_u16(&mut sink, 1000).unwrap();
+        encoder.write_u8(&mut sink, 200).unwrap();
+        sink.get_bits().to_vec()
+    }
+
+    fn create_decoder<'a>() -> UntaggedDecoder<'a, BoolSliceBitSource<'a>, u32> {
+        let decoder = SimpleIntDecodingProtocol::new();
+        UntaggedDecoder::new()
+            .with_alternative(move |source, _| decoder.read_u32(source))
+            .with_alternative(move |source, _| Ok(decoder.read_u16(source)? as u32))
+            .with_alternative(move |source, _| Ok(decoder.read_u8(source)? as u32))
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let bits = create_data();
+        let decoder = create_decoder();
+        assert_eq!(3, decoder.get_num_alternatives());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+
+        // The data is too short for a u32, so the u16 is the first match
+        let mut source = BoolSliceBitSource::new(&bits);
+        let result = decoder
+            .decode(&mut source, &mut context, |_, _, _| true)
+            .unwrap();
+        assert_eq!(
+            UntaggedMatch {
+                index: 1,
+                value: 1000,
+                num_bits: 16
+            },
+            result
+        );
+        assert_eq!(16, source.position());
+
+        // Let the callback reject the u16
+        let mut source = BoolSliceBitSource::new(&bits);
+        let mut tried = Vec::new();
+        let result = decoder
+            .decode(&mut source, &mut context, |index, value, _| {
+                tried.push(index);
+                *value < 256
+            })
+            .unwrap();
+        assert_eq!((2, 1000 % 256), (result.index, result.value));
+        assert_eq!(vec![1, 2], tried);
+        assert_eq!(8, source.position());
+    }
+
+    #[test]
+    fn test_no_match() {
+        let bits = create_data();
+        let decoder = create_decoder();
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let mut source = BoolSliceBitSource::new(&bits);
+        source.seek_bits(3).unwrap();
+
+        match decoder.decode(&mut source, &mut context, |_, _, _| false) {
+            Err(DecodeError::Other(error)) => {
+                let error = error.downcast_ref::<NoMatchingAlternative>().unwrap();
+                assert_eq!(3, error.reasons.len());
+                assert_ne!(error.reasons[0], error.reasons[1]);
+                assert_eq!(error.reasons[1], error.reasons[2]);
+            }
+            other => panic!("Expected NoMatchingAlternative, but got {:?}", other),
+        }
+        assert_eq!(3, source.position());
+    }
+
+    #[test]
+    fn test_budget_of_failed_attempts() {
+        let mut sink = BoolVecBitSink::new();
+        let encoder = DigitIntEncodingProtocol::v1();
+        encoder.write_string(&mut sink, "hello").unwrap();
+        let bits = sink.get_bits().to_vec();
+
+        let protocol = DigitIntDecodingProtocol::v1();
+        let decoder = UntaggedDecoder::new()
+            .with_alternative(move |source: &mut BoolSliceBitSource, context| {
+                protocol.read_string(source, context)
+            })
+            .with_alternative(move |source, context| protocol.read_string(source, context));
+
+        // The budget only allows 1 string of 5 bytes
+        let mut context = DecodeContext::new(DecodeLimits::new().with_max_total_bytes(5));
+        let mut source = BoolSliceBitSource::new(&bits);
+        let result = decoder
+            .decode(&mut source, &mut context, |index, _, _| index == 1)
+            .unwrap();
+        assert_eq!("hello", result.value);
+        assert_eq!(0, context.get_budget().get_remaining_bytes());
+    }
+
+    #[test]
+    fn test_source_error() {
+        struct SeekableFailing<'a> {
+            source: FailAfterBitSource<BoolSliceBitSource<'a>>,
+        }
+
+        impl<'a> BitSource for SeekableFailing<'a> {
+            fn read(&mut self, dest: &mut [bool]) -> Result<(), ReadError> {
+                self.source.read(dest)
+            }
+        }
+
+        impl<'a> SeekableBitSource for SeekableFailing<'a> {
+            fn seek_bits(&mut self, _position: u64) -> Result<(), ReadError> {
+                panic!("The decoder shouldn't seek after a source error")
+            }
+
+            fn position(&self) -> u64 {
+                0
+            }
+        }
+
+        let bits = create_data();
+        let mut source = SeekableFailing {
+            source: FailAfterBitSource::new(BoolSliceBitSource::new(&bits), 10),
+        };
+        let decoder = SimpleIntDecodingProtocol::new();
+        let untagged = UntaggedDecoder::new()
+            .with_alternative(move |source: &mut SeekableFailing, _| decoder.read_u16(source));
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        assert!(matches!(
+            untagged.decode(&mut source, &mut context, |_, _, _| true),
+            Err(DecodeError::Reading(ReadError::Other(_)))
+        ));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WaveletNode {
+    /// A node for a single symbol
+    Leaf,
+    /// A node for the symbols in a range of at least 2 symbols. For every
+    /// symbol in the sequence of this node, *bits* contains a 1 if it is in the
+    /// upper half of the range (which goes to the *right* child), or a 0 if it
+    /// is in the lower half (which goes to the *left* child).
+    Inner {
+        bits: RankSelectBitVec,
+        left: Box<WaveletNode>,
+        right: Box<WaveletNode>,
+    },
+}
+
+impl WaveletNode {
+    fn build(symbols: &[u32], start: u32, end: u32) -> Self {
+        if end - start == 1 {
+            return WaveletNode::Leaf;
+        }
+        let middle = start + (end - start) / 2;
+        let bits: Vec<bool> = symbols.iter().map(|symbol| *symbol >= middle).collect();
+        let (left, right): (Vec<u32>, Vec<u32>) =
+            symbols.iter().partition(|symbol| **symbol < middle);
+        WaveletNode::Inner {
+            bits: RankSelectBitVec::from_bools(&bits),
+            left: Box::new(Self::build(&left, start, middle)),
+            right: Box::new(Self::build(&right, middle, end)),
+        }
+    }
+
+    fn write(&self, sink: &mut impl BitSink) -> Result<(), WriteError> {
+        if let WaveletNode::Inner { bits, left, right } = self {
+            bits.write_bits(sink)?;
+            left.write(sink)?;
+            right.write(sink)?;
+        }
+        Ok(())
+    }
+
+    fn read(
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+        length: u64,
+        start: u32,
+        end: u32,
+    ) -> Result<Self, DecodeError> {
+        if end - start == 1 {
+            return Ok(WaveletNode::Leaf);
+        }
+        let middle = start + (end - start) / 2;
+        let bits = RankSelectBitVec::read_bits(source, context, length)?;
+        let num_ones = bits.count_ones();
+        let left = Self::read(source, context, length - num_ones, start, middle)?;
+        let right = Self::read(source, context, num_ones, middle, end)?;
+        Ok(WaveletNode::Inner {
+            bits,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+}
+
+/// A sequence of symbols from a small alphabet (the symbols 0 up to
+/// *alphabet_size*) that is stored compactly, but can still be queried
+/// efficiently. It uses about *log2(alphabet_size)* bits per symbol, and
+/// supports the following queries in *O(log(alphabet_size))* time:
+/// - *access*: getting the symbol at a given position
+/// - *rank*: counting the occurrences of a symbol before a given position
+/// - *select*: finding the position of the n-th occurrence of a symbol
+///
+/// Internally, this is a wavelet tree: a balanced binary tree over the
+/// alphabet whose nodes store a *RankSelectBitVec* with 1 bit per symbol that
+/// indicates whether the symbol is in the upper or lower half of the alphabet
+/// range of the node. Only the bits of the nodes are encoded by *write*; their
+/// rank and select samples are rebuilt by *read*.
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// // The weather of each day: 0 = sunny, 1 = cloudy, 2 = rainy
+/// let weather = [0, 0, 1, 2, 2, 2, 1, 0, 0, 2];
+/// let tree = WaveletTree::new(&weather, 3);
+/// assert_eq!(2, tree.access(4));
+/// // It rained 2 times in the first 5 days
+/// assert_eq!(2, tree.rank(2, 5));
+/// // The 3rd sunny day was day 7
+/// assert_eq!(Some(7), tree.select(0, 2));
+///
+/// let mut sink = BoolVecBitSink::new();
+/// tree.write(&DigitIntEncodingProtocol::v1(), &mut sink).unwrap();
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = WaveletTree::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(tree, decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveletTree {
+    root: WaveletNode,
+    alphabet_size: u32,
+    length: u64,
+}
+
+impl WaveletTree {
+    /// Constructs a new *WaveletTree* that contains *symbols*.
+    ///
+    /// Panics if *alphabet_size* is 0, or if any of the symbols is not smaller
+    /// than *alphabet_size*.
+    pub fn new(symbols: &[u32], alphabet_size: u32) -> Self {
+        assert!(alphabet_size > 0, "The alphabet size must be positive");
+        if let Some(symbol) = symbols.iter().find(|symbol| **symbol >= alphabet_size) {
+            panic!(
+                "Symbol {} is not smaller than the alphabet size {}",
+                symbol, alphabet_size
+            );
+        }
+        Self {
+            root: WaveletNode::build(symbols, 0, alphabet_size),
+            alphabet_size,
+            length: symbols.len() as u64,
+        }
+    }
+
+    /// Gets the number of possible symbols
+    pub fn get_alphabet_size(&self) -> u32 {
+        self.alphabet_size
+    }
+
+    /// Gets the number of symbols in the sequence
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Checks whether the sequence is empty
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Gets the symbol at *position*.
+    ///
+    /// Panics if *position* is not smaller than the length of the sequence.
+    pub fn access(&self, mut position: u64) -> u32 {
+        assert!(
+            position < self.length,
+            "Position {} is out of bounds",
+            position
+        );
+        let mut node = &self.root;
+        let (mut start, mut end) = (0, self.alphabet_size);
+        while let WaveletNode::Inner { bits, left, right } = node {
+            let middle = start + (end - start) / 2;
+            if bits.get(position) {
+                position = bits.rank1(position);
+                node = right;
+                start = middle;
+            } else {
+                position = bits.rank0(position);
+                node = left;
+                end = middle;
+            }
+        }
+        start
+    }
+
+    /// Counts the number of occurrences of *symbol* before *position*
+    /// (exclusive).
+    ///
+    /// Panics if *position* is larger than the length of the sequence.
+    pub fn rank(&self, symbol: u32, mut position: u64) -> u64 {
+        assert!(
+            position <= self.length,
+            "Position {} is out of bounds",
+            position
+        );
+        if symbol >= self.alphabet_size {
+            return 0;
+        }
+        let mut node = &self.root;
+        let (mut start, mut end) = (0, self.alphabet_size);
+        while let WaveletNode::Inner { bits, left, right } = node {
+            let middle = start + (end - start) / 2;
+            if symbol >= middle {
+                position = bits.rank1(position);
+                node = right;
+                start = middle;
+            } else {
+                position = bits.rank0(position);
+                node = left;
+                end = middle;
+            }
+        }
+        position
+    }
+
+    /// Counts the number of occurrences of *symbol* in the sequence
+    pub fn count(&self, symbol: u32) -> u64 {
+        self.rank(symbol, self.length)
+    }
+
+    /// Finds the position of the occurrence of *symbol* with index *rank*
+    /// (starting at 0), or returns *None* if *symbol* doesn't occur more than
+    /// *rank* times.
+    pub fn select(&self, symbol: u32, rank: u64) -> Option<u64> {
+        if symbol >= self.alphabet_size {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut node = &self.root;
+        let (mut start, mut end) = (0, self.alphabet_size);
+        while let WaveletNode::Inner { bits, left, right } = node {
+            let middle = start + (end - start) / 2;
+            let is_right = symbol >= middle;
+            path.push((bits, is_right));
+            if is_right {
+                node = right;
+                start = middle;
+            } else {
+                node = left;
+                end = middle;
+            }
+        }
+
+        let mut position = rank;
+        if path.is_empty() && position >= self.length {
+            return None;
+        }
+        for (bits, is_right) in path.into_iter().rev() {
+            position = match is_right {
+                true => bits.select1(position)?,
+                false => bits.select0(position)?,
+            };
+        }
+        Some(position)
+    }
+
+    /// Gets an iterator over the symbols of the sequence
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.length).map(move |position| self.access(position))
+    }
+
+    /// Writes the alphabet size and length with *protocol*, followed by the
+    /// bits of all nodes of the tree
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+    ) -> Result<(), WriteError> {
+        protocol.write_u32(sink, self.alphabet_size - 1)?;
+        protocol.write_u64(sink, self.length)?;
+        self.root.write(sink)
+    }
+
+    /// Reads a tree that was written by *write*. The number of nodes and the
+    /// sizes of their bit vectors are checked against the limits of *context*.
+    pub fn read(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Self, DecodeError> {
+        let alphabet_size = protocol
+            .read_u32(source)?
+            .checked_add(1)
+            .ok_or(DecodeError::IntOverflow)?;
+        context.check_vec_len::<WaveletNode>(alphabet_size as u64)?;
+        let length = protocol.read_u64(source)?;
+        let root = WaveletNode::read(source, context, length, 0, alphabet_size)?;
+        Ok(Self {
+            root,
+            alphabet_size,
+            length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn check_queries(symbols: &[u32], alphabet_size: u32) {
+        let tree = WaveletTree::new(symbols, alphabet_size);
+        assert_eq!(symbols.len() as u64, tree.len());
+        let mut counts = vec![0; alphabet_size as usize + 1];
+        for (position, symbol) in symbols.iter().enumerate() {
+            assert_eq!(*symbol, tree.access(position as u64));
+            for other in 0..=alphabet_size {
+                assert_eq!(counts[other as usize], tree.rank(other, position as u64));
+            }
+            assert_eq!(
+                Some(position as u64),
+                tree.select(*symbol, counts[*symbol as usize])
+            );
+            counts[*symbol as usize] += 1;
+        }
+        for symbol in 0..=alphabet_size {
+            assert_eq!(counts[symbol as usize], tree.count(symbol));
+            assert_eq!(None, tree.select(symbol, counts[symbol as usize]));
+        }
+        assert!(tree.iter().eq(symbols.iter().copied()));
+
+        let mut sink = BoolVecBitSink::new();
+        tree.write(&DigitIntEncodingProtocol::v1(), &mut sink)
+            .unwrap();
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = WaveletTree::read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+        assert_eq!(tree, decoded.unwrap());
+        assert!(source.read(&mut [false]).is_err());
+    }
+
+    #[test]
+    fn test_queries() {
+        check_queries(&[], 1);
+        check_queries(&[0, 0, 0], 1);
+        check_queries(&[], 5);
+        check_queries(&[1, 0, 1, 1], 2);
+        let symbols: Vec<u32> = (0..2000u32)
+            .map(|index| index.wrapping_mul(2_654_435_761) % 13)
+            .collect();
+        check_queries(&symbols, 13);
+        // Some symbols of the alphabet don't occur
+        let symbols: Vec<u32> = (0..1000).map(|index| index % 7 * 3).collect();
+        check_queries(&symbols, 20);
+    }
+
+    #[test]
+    fn test_size() {
+        let symbols: Vec<u32> = (0..10_000).map(|index| index % 16).collect();
+        let tree = WaveletTree::new(&symbols, 16);
+        let mut sink = BoolVecBitSink::new();
+        tree.write(&SimpleIntEncodingProtocol::new(), &mut sink)
+            .unwrap();
+        assert_eq!(32 + 64 + 4 * 10_000, sink.get_num_bools());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_symbol() {
+        WaveletTree::new(&[0, 1, 2], 2);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::*;
+
+/// Determines how a *ZeroRunCodec* encodes the positions of the zeros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroRunMode {
+    /// Writes 1 bit per value that indicates whether the value is non-zero.
+    /// This works best when the zeros are scattered.
+    Bitmap,
+    /// Writes the number of non-zero values, followed by the number of zeros in
+    /// front of every non-zero value. This works best when the zeros occur in
+    /// long runs.
+    RunLengths,
+    /// Encodes the values in both modes, and uses whichever is smaller. This
+    /// costs 1 extra bit (to store the chosen mode) and encodes the values
+    /// twice.
+    Auto,
+}
+
+/// Encodes slices of *u64*s that mostly consist of zeros, like sparse vectors
+/// of counters. Only the non-zero values are encoded with the int protocol
+/// (minus 1, since they are at least 1), and the positions of the zeros are
+/// encoded using a presence bitmap or zero run lengths (see *ZeroRunMode*).
+///
+/// # Example
+/// ```
+/// use bit_encoding::*;
+///
+/// let mut counters = vec![0u64; 1000];
+/// counters[10] = 5;
+/// counters[500] = 123;
+///
+/// let codec = ZeroRunCodec::new(ZeroRunMode::RunLengths);
+/// let mut sink = BoolVecBitSink::new();
+/// codec.write(&DigitIntEncodingProtocol::v1(), &mut sink, &counters).unwrap();
+/// assert!(sink.get_num_bools() < 100);
+///
+/// let mut source = BoolSliceBitSource::new(sink.get_bits());
+/// let mut context = DecodeContext::new(DecodeLimits::new());
+/// let decoded = codec.read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context);
+/// assert_eq!(counters, decoded.unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZeroRunCodec {
+    mode: ZeroRunMode,
+}
+
+impl ZeroRunCodec {
+    /// Constructs a new *ZeroRunCodec* that uses the given *mode*
+    pub const fn new(mode: ZeroRunMode) -> Self {
+        Self { mode }
+    }
+
+    /// Gets the mode of this codec
+    pub fn get_mode(&self) -> ZeroRunMode {
+        self.mode
+    }
+
+    fn write_bitmap(
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[u64],
+    ) -> Result<(), WriteError> {
+        let bitmap: Vec<bool> = values.iter().map(|value| *value != 0).collect();
+        sink.write(&bitmap)?;
+        for value in values.iter().filter(|value| **value != 0) {
+            protocol.write_u64(sink, value - 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_run_lengths(
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[u64],
+    ) -> Result<(), WriteError> {
+        let num_non_zero_values = values.iter().filter(|value| **value != 0).count();
+        protocol.write_u64(sink, num_non_zero_values as u64)?;
+        let mut num_zeros = 0;
+        for value in values {
+            if *value == 0 {
+                num_zeros += 1;
+            } else {
+                protocol.write_u64(sink, num_zeros)?;
+                protocol.write_u64(sink, value - 1)?;
+                num_zeros = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes *values* with *protocol*, and writes them to *sink*
+    pub fn write(
+        &self,
+        protocol: &impl IntEncodingProtocol,
+        sink: &mut impl BitSink,
+        values: &[u64],
+    ) -> Result<(), WriteError> {
+        protocol.write_u64(sink, values.len() as u64)?;
+        match self.mode {
+            ZeroRunMode::Bitmap => Self::write_bitmap(protocol, sink, values),
+            ZeroRunMode::RunLengths => Self::write_run_lengths(protocol, sink, values),
+            ZeroRunMode::Auto => {
+                let mut bitmap_size = CountingBitSink::new();
+                Self::write_bitmap(protocol, &mut bitmap_size, values)?;
+                let mut run_lengths_size = CountingBitSink::new();
+                Self::write_run_lengths(protocol, &mut run_lengths_size, values)?;
+                if bitmap_size.get_num_bools() <= run_lengths_size.get_num_bools() {
+                    sink.write(&[false])?;
+                    Self::write_bitmap(protocol, sink, values)
+                } else {
+                    sink.write(&[true])?;
+                    Self::write_run_lengths(protocol, sink, values)
+                }
+            }
+        }
+    }
+
+    fn read_non_zero(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+    ) -> Result<u64, DecodeError> {
+        protocol
+            .read_u64(source)?
+            .checked_add(1)
+            .ok_or(DecodeError::IntOverflow)
+    }
+
+    fn read_bitmap(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        length: usize,
+    ) -> Result<Vec<u64>, DecodeError> {
+        let mut bitmap = vec![false; length];
+        source.read(&mut bitmap)?;
+        let mut values = Vec::with_capacity(length);
+        for is_non_zero in bitmap {
+            values.push(if is_non_zero {
+                Self::read_non_zero(protocol, source)?
+            } else {
+                0
+            });
+        }
+        Ok(values)
+    }
+
+    fn read_run_lengths(
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        length: usize,
+    ) -> Result<Vec<u64>, DecodeError> {
+        let num_non_zero_values = protocol.read_u64(source)?;
+        if num_non_zero_values > length as u64 {
+            return Err(DecodeError::Other(
+                format!(
+                    "{} non-zero values don't fit in {} values",
+                    num_non_zero_values, length
+                )
+                .into(),
+            ));
+        }
+        let mut values = Vec::with_capacity(length);
+        for _ in 0..num_non_zero_values {
+            let num_zeros = protocol.read_u64(source)?;
+            if num_zeros >= (length - values.len()) as u64 {
+                return Err(DecodeError::Other(
+                    format!(
+                        "A run of {} zeros doesn't fit in the remaining {} values",
+                        num_zeros,
+                        length - values.len()
+                    )
+                    .into(),
+                ));
+            }
+            values.resize(values.len() + num_zeros as usize, 0);
+            values.push(Self::read_non_zero(protocol, source)?);
+        }
+        values.resize(length, 0);
+        Ok(values)
+    }
+
+    /// Decodes values that were encoded by *write* with the same mode. The
+    /// number of values is checked against the limits of *context* before the
+    /// *Vec* is allocated.
+    pub fn read(
+        &self,
+        protocol: &impl IntDecodingProtocol,
+        source: &mut impl BitSource,
+        context: &mut DecodeContext,
+    ) -> Result<Vec<u64>, DecodeError> {
+        let length = context.check_vec_len::<u64>(protocol.read_u64(source)?)?;
+        match self.mode {
+            ZeroRunMode::Bitmap => Self::read_bitmap(protocol, source, length),
+            ZeroRunMode::RunLengths => Self::read_run_lengths(protocol, source, length),
+            ZeroRunMode::Auto => {
+                let mut mode = [false];
+                source.read(&mut mode)?;
+                if mode[0] {
+                    Self::read_run_lengths(protocol, source, length)
+                } else {
+                    Self::read_bitmap(protocol, source, length)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    fn round_trip(mode: ZeroRunMode, values: &[u64]) -> u64 {
+        let codec = ZeroRunCodec::new(mode);
+        let mut sink = BoolVecBitSink::new();
+        codec
+            .write(&DigitIntEncodingProtocol::v1(), &mut sink, values)
+            .unwrap();
+
+        let mut source = BoolSliceBitSource::new(sink.get_bits());
+        let mut context = DecodeContext::new(DecodeLimits::new());
+        let decoded = codec
+            .read(&DigitIntDecodingProtocol::v1(), &mut source, &mut context)
+            .unwrap();
+        assert_eq!(values, &decoded[..]);
+        assert!(source.read(&mut [false]).is_err());
+        sink.get_num_bools()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let scattered: Vec<u64> = (0..200).map(|index| index % 3).collect();
+        let mut runs = vec![0; 500];
+        runs[0] = 1;
+        runs[250] = u64::MAX;
+        runs[251] = 7;
+        for values in &[
+            vec![],
+            vec![0; 10],
+            vec![5, 0, 0],
+            vec![0, 0, 9],
+            scattered,
+            runs,
+        ] {
+            let bitmap = round_trip(ZeroRunMode::Bitmap, values);
+            let run_lengths = round_trip(ZeroRunMode::RunLengths, values);
+            let auto = round_trip(ZeroRunMode::Auto, values);
+            assert_eq!(u64::min(bitmap, run_lengths) + 1, auto);
+        }
+    }
+
+    #[test]
+    fn test_invalid_run_lengths() {
+        let encoder = DigitIntEncodingProtocol::v1();
+        let decoder = DigitIntDecodingProtocol::v1();
+        let codec = ZeroRunCodec::new(ZeroRunMode::RunLengths);
+        for invalid in &[[3u64, 4, 0, 0, 0, 0, 0], [3, 1, 3, 0, 0, 0, 0]] {
+            let mut sink = BoolVecBitSink::new();
+            for value in invalid {
+                encoder.write_u64(&mut sink, *value).unwrap();
+            }
+            let mut source = BoolSliceBitSource::new(sink.get_bits());
+            let mut context = DecodeContext::new(DecodeLimits::new());
+            assert!(codec.read(&decoder, &mut source, &mut context).is_err());
+        }
+    }
+}